@@ -0,0 +1,109 @@
+//! Measures archetype matching with many registered components, where the
+//! component sets of archetypes and handlers exceed the inline capacity of a
+//! `BitSet` and spill to the heap.
+//!
+//! Run with and without the `wide-bit-set` feature to compare tiers.
+
+use std::alloc::Layout;
+
+use divan::{black_box, Bencher};
+use evenio::component::ComponentDescriptor;
+use evenio::prelude::*;
+
+fn main() {
+    divan::main()
+}
+
+/// Number of components registered before the components being matched.
+const ARGS: [usize; 3] = [100, 1000, 5000];
+
+#[derive(Component)]
+struct A(#[allow(dead_code)] u64);
+
+#[derive(Component)]
+struct B(#[allow(dead_code)] u64);
+
+#[derive(Component)]
+struct M<const N: usize>;
+
+#[derive(Event)]
+struct E;
+
+/// Registers `count` dynamic components followed by 256 archetypes made of
+/// `A`, `B` and every combination of the markers `M<0>` to `M<5>`.
+fn populate(world: &mut World, count: usize) {
+    for _ in 0..count {
+        let desc = ComponentDescriptor {
+            name: "dynamic".into(),
+            type_id: None,
+            layout: Layout::new::<u64>(),
+            drop: None,
+            is_immutable: false,
+            is_double_buffered: false,
+            skip_identical_writes: None,
+            fields: vec![],
+        };
+
+        // SAFETY: `u64` has no drop function and the layout is correct.
+        unsafe { world.add_component_with_descriptor(desc) };
+    }
+
+    macro_rules! insert_markers {
+        ($world:ident, $e:ident, $mask:ident, $($n:literal)*) => {
+            $(
+                if $mask & (1 << $n) != 0 {
+                    $world.insert($e, M::<$n>);
+                }
+            )*
+        }
+    }
+
+    for mask in 0..256_usize {
+        let e = world.spawn();
+
+        if mask & 1 != 0 {
+            world.insert(e, A(0));
+        }
+
+        if mask & 2 != 0 {
+            world.insert(e, B(0));
+        }
+
+        let mask = mask >> 2;
+        insert_markers!(world, e, mask, 0 1 2 3 4 5);
+    }
+}
+
+#[divan::bench(args = ARGS, sample_size = 10)]
+fn add_handler(bencher: Bencher, count: usize) {
+    let mut world = World::new();
+    populate(&mut world, count);
+
+    bencher.bench_local(|| {
+        let id = world.add_handler(|_: Receiver<E>, f: Fetcher<(&A, &B, Not<&M<0>>)>| {
+            black_box(f);
+        });
+
+        world.remove_handler(id);
+    });
+}
+
+#[divan::bench(args = ARGS, sample_size = 10)]
+fn move_between_archetypes(bencher: Bencher, count: usize) {
+    let mut world = World::new();
+    populate(&mut world, count);
+
+    world.add_handler(|_: Receiver<E>, f: Fetcher<(&A, &B)>| {
+        black_box(f);
+    });
+
+    let e = world.spawn();
+    world.insert(e, A(0));
+
+    bencher.bench_local(|| {
+        world.insert(e, M::<0>);
+        world.insert(e, B(0));
+        world.remove::<M<0>>(e);
+        world.remove::<B>(e);
+    });
+}
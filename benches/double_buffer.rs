@@ -0,0 +1,57 @@
+//! Measures copying current component values into previous values with
+//! `World::swap_buffers` versus a handler copying into a second component.
+
+use divan::Bencher;
+use evenio::prelude::*;
+
+fn main() {
+    divan::main()
+}
+
+const ARGS: [usize; 5] = [1, 100, 10_000, 100_000, 1_000_000];
+
+#[derive(Component, Clone, Copy)]
+#[component(double_buffer)]
+struct Transform(#[allow(dead_code)] [f32; 4]);
+
+#[derive(Component, Clone, Copy)]
+struct Plain([f32; 4]);
+
+#[derive(Component, Clone, Copy)]
+struct PrevPlain([f32; 4]);
+
+#[derive(Event)]
+struct CopyPrev;
+
+#[divan::bench(args = ARGS)]
+fn swap_buffers(bencher: Bencher, len: usize) {
+    let mut world = World::new();
+
+    for _ in 0..len {
+        let e = world.spawn();
+        world.insert(e, Transform([1.0; 4]));
+    }
+
+    bencher.bench_local(|| world.swap_buffers::<Transform>());
+}
+
+#[divan::bench(args = ARGS)]
+fn handler_copy(bencher: Bencher, len: usize) {
+    let mut world = World::new();
+
+    for _ in 0..len {
+        let e = world.spawn();
+        world.insert(e, Plain([1.0; 4]));
+        world.insert(e, PrevPlain([1.0; 4]));
+    }
+
+    world.add_handler(
+        |_: Receiver<CopyPrev>, f: Fetcher<(&Plain, &mut PrevPlain)>| {
+            for (cur, prev) in f {
+                prev.0 = cur.0;
+            }
+        },
+    );
+
+    bencher.bench_local(|| world.send(CopyPrev));
+}
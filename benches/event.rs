@@ -56,3 +56,79 @@ fn send_many_events(bencher: Bencher) {
         black_box(&mut world);
     });
 }
+
+/// Twenty receivers of a targeted event, of which only two match the target.
+#[divan::bench(sample_count = 1000)]
+fn send_targeted_filtered(bencher: Bencher) {
+    let mut world = World::new();
+
+    #[derive(Event)]
+    struct Damage(#[event(target)] EntityId);
+
+    #[derive(Component)]
+    struct Player;
+
+    #[derive(Component)]
+    struct Health(u64);
+
+    // Handlers are deduplicated by type, so every closure needs to be distinct.
+    macro_rules! add_handlers {
+        ($filter:ty; $($n:literal)*) => {$(
+            world.add_handler(|r: Receiver<Damage, ($filter, &mut Health)>| {
+                r.query.1 .0 += $n;
+            });
+        )*};
+    }
+
+    add_handlers!(With<&Player>; 0 1);
+    add_handlers!(Not<&Player>; 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19);
+
+    let e = world.spawn();
+    world.insert(e, Player);
+    world.insert(e, Health(0));
+
+    bencher.bench_local(|| {
+        world.send(Damage(e));
+        black_box(&mut world);
+    });
+}
+
+/// Like `send_targeted_filtered`, but every receiver runs and returns early
+/// when the target isn't a player.
+#[divan::bench(sample_count = 1000)]
+fn send_targeted_early_return(bencher: Bencher) {
+    let mut world = World::new();
+
+    #[derive(Event)]
+    struct Damage(#[event(target)] EntityId);
+
+    #[derive(Component)]
+    struct Player;
+
+    #[derive(Component)]
+    struct Health(u64);
+
+    macro_rules! add_handlers {
+        ($player:literal; $($n:literal)*) => {$(
+            world.add_handler(|r: Receiver<Damage, (Has<&Player>, &mut Health)>| {
+                if r.query.0 != $player {
+                    return;
+                }
+
+                r.query.1 .0 += $n;
+            });
+        )*};
+    }
+
+    add_handlers!(true; 0 1);
+    add_handlers!(false; 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19);
+
+    let e = world.spawn();
+    world.insert(e, Player);
+    world.insert(e, Health(0));
+
+    bencher.bench_local(|| {
+        world.send(Damage(e));
+        black_box(&mut world);
+    });
+}
@@ -18,9 +18,34 @@ struct C2(#[allow(dead_code)] f64);
 #[derive(evenio::component::Component, bevy_ecs::component::Component)]
 struct C3(#[allow(dead_code)] f64);
 
+#[derive(evenio::component::Component)]
+struct Pos([f32; 3]);
+
+#[derive(evenio::component::Component)]
+struct Vel([f32; 3]);
+
 #[derive(evenio::event::Event)]
 struct E;
 
+/// Spawns `len` entities with a position and velocity, split across two
+/// archetypes.
+fn pos_vel_world(len: usize) -> evenio::world::World {
+    let mut world = evenio::world::World::new();
+
+    for i in 0..len {
+        let e = world.spawn();
+
+        world.insert(e, Pos([0.0; 3]));
+        world.insert(e, Vel([1.0, 2.0, 3.0]));
+
+        if i % 2 == 0 {
+            world.insert(e, C1(DATA));
+        }
+    }
+
+    world
+}
+
 #[divan::bench(args = ARGS)]
 fn iter_evenio(bencher: Bencher, len: usize) {
     use evenio::prelude::*;
@@ -143,4 +168,77 @@ fn par_iter_bevy(bencher: Bencher, len: usize) {
     });
 }
 
+#[divan::bench(args = ARGS)]
+fn pos_vel_iter_mut(bencher: Bencher, len: usize) {
+    use evenio::prelude::*;
+
+    let mut world = pos_vel_world(len);
+
+    world.add_handler(|_: Receiver<E>, f: Fetcher<(&mut Pos, &Vel)>| {
+        for (pos, vel) in f {
+            for i in 0..3 {
+                pos.0[i] += vel.0[i];
+            }
+        }
+    });
+
+    bencher.bench_local(|| world.send(E));
+}
+
+#[divan::bench(args = ARGS)]
+fn sort_by_key_collect(bencher: Bencher, len: usize) {
+    use evenio::prelude::*;
+
+    let mut world = pos_vel_world(len);
+
+    world.add_handler(|_: Receiver<E>, f: Fetcher<(&Pos, &Vel)>| {
+        let mut items: Vec<_> = f.iter().collect();
+        items.sort_unstable_by_key(|(pos, _)| pos.0[0].to_bits());
+
+        for item in items {
+            divan::black_box(item);
+        }
+    });
+
+    bencher.bench_local(|| world.send(E));
+}
+
+#[divan::bench(args = ARGS)]
+fn sort_by_key_cached(bencher: Bencher, len: usize) {
+    use evenio::entity::EntityLocation;
+    use evenio::fetch::QueryBuffer;
+    use evenio::prelude::*;
+
+    let mut world = pos_vel_world(len);
+
+    world.add_handler(
+        |_: Receiver<E>, f: Fetcher<(&Pos, &Vel)>, mut buf: QueryBuffer<(u32, EntityLocation)>| {
+            for item in f.iter_sorted_by_key_cached(|(pos, _)| pos.0[0].to_bits(), &mut buf) {
+                divan::black_box(item);
+            }
+        },
+    );
+
+    bencher.bench_local(|| world.send(E));
+}
+
+#[cfg(feature = "rayon")]
+#[divan::bench(args = ARGS)]
+fn pos_vel_par_iter_mut(bencher: Bencher, len: usize) {
+    use evenio::prelude::*;
+    use rayon::prelude::*;
+
+    let mut world = pos_vel_world(len);
+
+    world.add_handler(|_: Receiver<E>, mut f: Fetcher<(&mut Pos, &Vel)>| {
+        f.par_iter_mut().for_each(|(pos, vel)| {
+            for i in 0..3 {
+                pos.0[i] += vel.0[i];
+            }
+        });
+    });
+
+    bencher.bench_local(|| world.send(E));
+}
+
 // TODO: parallel iteration with varying amounts of work per iteration.
@@ -0,0 +1,98 @@
+//! Measures a naive broadphase which tests every pair of entities within a
+//! grid cell, before and after sorting rows along a Morton curve.
+
+use std::collections::BTreeMap;
+
+use divan::{black_box, Bencher};
+use evenio::prelude::*;
+
+fn main() {
+    divan::main()
+}
+
+const ARGS: [usize; 3] = [10_000, 100_000, 1_000_000];
+
+const WORLD_SIZE: f32 = 1000.0;
+const CELL_SIZE: f32 = 10.0;
+
+#[derive(Component)]
+struct Position([f32; 2]);
+
+#[derive(Component)]
+struct Velocity(#[allow(dead_code)] [f32; 2]);
+
+#[derive(Event)]
+struct Broadphase;
+
+#[derive(Component)]
+struct Collisions(usize);
+
+fn populate(world: &mut World, len: usize) {
+    // xorshift, so that the benchmark doesn't depend on a random number crate.
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state >> 40) as f32 / (1 << 24) as f32 * WORLD_SIZE
+    };
+
+    for _ in 0..len {
+        let e = world.spawn();
+        world.insert(e, Position([next(), next()]));
+        world.insert(e, Velocity([0.0; 2]));
+    }
+
+    let e = world.spawn();
+    world.insert(e, Collisions(0));
+
+    world.add_handler(
+        |_: Receiver<Broadphase>,
+         positions: Fetcher<(EntityId, &Position)>,
+         Single(collisions): Single<&mut Collisions>| {
+            let mut cells: BTreeMap<(u32, u32), Vec<EntityId>> = BTreeMap::new();
+
+            for (id, pos) in &positions {
+                let cell = ((pos.0[0] / CELL_SIZE) as u32, (pos.0[1] / CELL_SIZE) as u32);
+                cells.entry(cell).or_default().push(id);
+            }
+
+            collisions.0 = 0;
+
+            for ids in cells.values() {
+                for (i, &a) in ids.iter().enumerate() {
+                    let (_, a) = positions.get(a).unwrap();
+
+                    for &b in &ids[i + 1..] {
+                        let (_, b) = positions.get(b).unwrap();
+
+                        let dx = a.0[0] - b.0[0];
+                        let dy = a.0[1] - b.0[1];
+
+                        if dx * dx + dy * dy < 1.0 {
+                            collisions.0 += 1;
+                        }
+                    }
+                }
+            }
+        },
+    );
+}
+
+#[divan::bench(args = ARGS)]
+fn unsorted(bencher: Bencher, len: usize) {
+    let mut world = World::new();
+    populate(&mut world, len);
+
+    bencher.bench_local(|| black_box(&mut world).send(Broadphase));
+}
+
+#[divan::bench(args = ARGS)]
+fn morton_sorted(bencher: Bencher, len: usize) {
+    let mut world = World::new();
+    populate(&mut world, len);
+
+    world.sort_rows_morton(|p: &Position| p.0);
+
+    bencher.bench_local(|| black_box(&mut world).send(Broadphase));
+}
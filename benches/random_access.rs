@@ -126,3 +126,73 @@ fn random_access_bevy(bencher: Bencher) {
 
     bencher.bench_local(|| sched.run(&mut world));
 }
+
+/// Random access with queries of one to four components, to compare the
+/// per-lookup cost as the query grows.
+mod query_size {
+    use divan::{black_box, Bencher};
+    use evenio::prelude::*;
+
+    use super::{FETCHED_COUNT, TOTAL_ENTITIES};
+
+    #[derive(Component)]
+    struct A(#[allow(dead_code)] u64);
+
+    #[derive(Component)]
+    struct B(#[allow(dead_code)] u64);
+
+    #[derive(Component)]
+    struct C(#[allow(dead_code)] u64);
+
+    #[derive(Component)]
+    struct D(#[allow(dead_code)] u64);
+
+    #[derive(Event)]
+    struct E;
+
+    fn world() -> (World, Vec<EntityId>) {
+        let mut entities = vec![];
+        let mut world = World::new();
+
+        for i in 0..TOTAL_ENTITIES {
+            let e = world.spawn();
+
+            if i % (TOTAL_ENTITIES / FETCHED_COUNT) == 0 {
+                entities.push(e);
+            }
+
+            world.insert(e, A(i as u64));
+            world.insert(e, B(i as u64));
+            world.insert(e, C(i as u64));
+            world.insert(e, D(i as u64));
+        }
+
+        (world, entities)
+    }
+
+    macro_rules! bench_get {
+        ($($name:ident: $query:ty),*) => {
+            $(
+                #[divan::bench]
+                fn $name(bencher: Bencher) {
+                    let (mut world, entities) = world();
+
+                    world.add_handler(move |_: Receiver<E>, f: Fetcher<$query>| {
+                        for &e in &entities {
+                            let _ = black_box(f.get(e));
+                        }
+                    });
+
+                    bencher.bench_local(|| world.send(E));
+                }
+            )*
+        }
+    }
+
+    bench_get!(
+        get_1: &A,
+        get_2: (&A, &B),
+        get_3: (&A, &B, &C),
+        get_4: (&A, &B, &C, &D)
+    );
+}
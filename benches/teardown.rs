@@ -0,0 +1,57 @@
+//! Measures dropping a world with many entities, with and without an arena
+//! for its columns.
+
+use divan::Bencher;
+use evenio::prelude::*;
+
+fn main() {
+    divan::main()
+}
+
+const ARGS: [usize; 3] = [1_000, 50_000, 500_000];
+
+#[derive(Component)]
+struct Position(#[allow(dead_code)] [f32; 3]);
+
+#[derive(Component)]
+struct Velocity(#[allow(dead_code)] [f32; 3]);
+
+#[derive(Component)]
+struct Name(#[allow(dead_code)] String);
+
+fn populate(world: &mut World, len: usize) {
+    for i in 0..len {
+        let e = world.spawn();
+        world.insert(e, Position([0.0; 3]));
+
+        if i % 2 == 0 {
+            world.insert(e, Velocity([1.0; 3]));
+        }
+
+        if i % 16 == 0 {
+            world.insert(e, Name(format!("entity {i}")));
+        }
+    }
+}
+
+#[divan::bench(args = ARGS)]
+fn default(bencher: Bencher, len: usize) {
+    bencher
+        .with_inputs(|| {
+            let mut world = World::new();
+            populate(&mut world, len);
+            world
+        })
+        .bench_local_values(drop);
+}
+
+#[divan::bench(args = ARGS)]
+fn arena(bencher: Bencher, len: usize) {
+    bencher
+        .with_inputs(|| {
+            let mut world = World::new_in_arena(len * 128);
+            populate(&mut world, len);
+            world
+        })
+        .bench_local_values(drop);
+}
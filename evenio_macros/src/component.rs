@@ -2,7 +2,7 @@ use proc_macro2::TokenStream;
 use quote::quote;
 use syn::{parse2, parse_quote, DeriveInput, Result};
 
-use crate::util::parse_attr_immutable;
+use crate::util::reflect_fields_fn;
 
 pub(crate) fn derive_component(input: TokenStream) -> Result<TokenStream> {
     let mut input = parse2::<DeriveInput>(input)?;
@@ -13,7 +13,71 @@ pub(crate) fn derive_component(input: TokenStream) -> Result<TokenStream> {
         .predicates
         .push(parse_quote!(Self: Send + Sync + 'static));
 
-    let is_immutable = parse_attr_immutable("component", &input.attrs)?;
+    let mut is_immutable = false;
+    let mut is_double_buffered = false;
+    let mut skip_identical_writes = false;
+    let mut reflect_fields = false;
+
+    for attr in &input.attrs {
+        if attr.path().is_ident("component") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("immutable") {
+                    is_immutable = true;
+                    Ok(())
+                } else if meta.path.is_ident("double_buffer") {
+                    is_double_buffered = true;
+                    Ok(())
+                } else if meta.path.is_ident("skip_identical_writes") {
+                    skip_identical_writes = true;
+                    Ok(())
+                } else if meta.path.is_ident("reflect_fields") {
+                    reflect_fields = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unrecognized argument"))
+                }
+            })?;
+        }
+    }
+
+    if is_double_buffered {
+        // Double-buffered components are copied bitwise.
+        input
+            .generics
+            .make_where_clause()
+            .predicates
+            .push(parse_quote!(Self: Copy));
+    }
+
+    let double_buffered = if is_double_buffered {
+        quote!(::core::option::Option::Some(
+            ::evenio::component::DoubleBuffered::new()
+        ))
+    } else {
+        quote!(::core::option::Option::None)
+    };
+
+    let eq_fn = if skip_identical_writes {
+        input
+            .generics
+            .make_where_clause()
+            .predicates
+            .push(parse_quote!(Self: PartialEq));
+
+        quote! {
+            ::core::option::Option::Some(|a, b| unsafe {
+                *a.cast::<Self>().as_ref() == *b.cast::<Self>().as_ref()
+            })
+        }
+    } else {
+        quote!(::core::option::Option::None)
+    };
+
+    let reflect_fields_fn = if reflect_fields {
+        reflect_fields_fn("component", &mut input)?
+    } else {
+        quote!()
+    };
 
     let name = &input.ident;
     let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
@@ -22,6 +86,10 @@ pub(crate) fn derive_component(input: TokenStream) -> Result<TokenStream> {
         #[automatically_derived]
         impl #impl_generics ::evenio::component::Component for #name #ty_generics #where_clause {
             const IS_IMMUTABLE: bool = #is_immutable;
+            const DOUBLE_BUFFERED: ::core::option::Option<::evenio::component::DoubleBuffered<Self>> = #double_buffered;
+            const SKIP_IDENTICAL_WRITES: ::core::option::Option<::evenio::component::EqFn> = #eq_fn;
+
+            #reflect_fields_fn
         }
     })
 }
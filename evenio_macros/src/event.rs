@@ -2,7 +2,7 @@ use proc_macro2::{Span, TokenStream};
 use quote::{quote, ToTokens};
 use syn::{parse2, parse_quote, Data, DeriveInput, LitInt, Result};
 
-use crate::util::parse_attr_immutable;
+use crate::util::reflect_fields_fn;
 
 pub(crate) fn derive_event(input: TokenStream) -> Result<TokenStream> {
     let mut input = parse2::<DeriveInput>(input)?;
@@ -72,7 +72,30 @@ pub(crate) fn derive_event(input: TokenStream) -> Result<TokenStream> {
 
     let is_targeted = target_field.is_some();
 
-    let is_immutable = parse_attr_immutable("event", &input.attrs)?;
+    let mut is_immutable = false;
+    let mut reflect_fields = false;
+
+    for attr in &input.attrs {
+        if attr.path().is_ident("event") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("immutable") {
+                    is_immutable = true;
+                    Ok(())
+                } else if meta.path.is_ident("reflect_fields") {
+                    reflect_fields = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unrecognized argument"))
+                }
+            })?;
+        }
+    }
+
+    let reflect_fields_fn = if reflect_fields {
+        reflect_fields_fn("event", &mut input)?
+    } else {
+        quote!()
+    };
 
     let target_fn_body = if let Some((idx, field)) = target_field {
         let f = match field.ident {
@@ -99,6 +122,8 @@ pub(crate) fn derive_event(input: TokenStream) -> Result<TokenStream> {
             fn target(&self) -> ::evenio::entity::EntityId {
                 #target_fn_body
             }
+
+            #reflect_fields_fn
         }
     })
 }
@@ -157,6 +157,18 @@ pub(crate) fn derive_handler_param(input: TokenStream) -> Result<TokenStream> {
                     arch
                 )
             }
+
+            fn teardown(state: &mut Self::State, world: &mut ::evenio::world::World) {
+                <#tuple_ty as ::evenio::handler::HandlerParam>::teardown(state, world)
+            }
+
+            fn take_locals(state: &mut Self::State, locals: &mut ::evenio::handler::Locals) {
+                <#tuple_ty as ::evenio::handler::HandlerParam>::take_locals(state, locals)
+            }
+
+            fn restore_locals(state: &mut Self::State, locals: &mut ::evenio::handler::Locals) {
+                <#tuple_ty as ::evenio::handler::HandlerParam>::restore_locals(state, locals)
+            }
         }
     })
 }
@@ -42,13 +42,22 @@ pub(crate) fn derive_query(input: TokenStream) -> Result<TokenStream> {
                     replace_lifetime(&mut replaced_ty, &life.lifetime.ident, &parse_quote!(__a));
                 }
 
-                where_clause.predicates.push(
-                    parse_quote!(#ty: for<'__a> ::evenio::query::Query<Item<'__a> = #replaced_ty>),
+                // Field values are converted from the query items with `From`, so
+                // that queries like `Has<Q>` with a different item type can be
+                // used as fields.
+                let from_item: syn::WherePredicate = parse_quote!(
+                    for<'__a> #replaced_ty: ::core::convert::From<<#ty as ::evenio::query::Query>::Item<'__a>>
                 );
 
+                where_clause
+                    .predicates
+                    .push(parse_quote!(#ty: ::evenio::query::Query));
+                where_clause.predicates.push(from_item.clone());
+
                 ro_where_clause
                     .predicates
-                    .push(parse_quote!(#ty: for<'__a> ::evenio::query::ReadOnlyQuery<Item<'__a> = #replaced_ty>));
+                    .push(parse_quote!(#ty: ::evenio::query::ReadOnlyQuery));
+                ro_where_clause.predicates.push(from_item);
             }
 
             get_body = match &struct_.fields {
@@ -68,7 +77,7 @@ pub(crate) fn derive_query(input: TokenStream) -> Result<TokenStream> {
                         let (#(#underscored_idents,)*) = <#tuple_ty as ::evenio::query::Query>::get(state, row);
 
                         #name {
-                            #(#idents: #underscored_idents),*
+                            #(#idents: ::core::convert::From::from(#underscored_idents)),*
                         }
                     }
                 }
@@ -82,7 +91,7 @@ pub(crate) fn derive_query(input: TokenStream) -> Result<TokenStream> {
                     quote! {
                         let __tuple = <#tuple_ty as ::evenio::query::Query>::get(state, row);
 
-                        #name(#(__tuple.#indices),*)
+                        #name(#(::core::convert::From::from(__tuple.#indices)),*)
                     }
                 }
                 syn::Fields::Unit => quote!(#name),
@@ -139,6 +148,13 @@ pub(crate) fn derive_query(input: TokenStream) -> Result<TokenStream> {
             unsafe fn get<'__a>(state: &Self::ArchState, row: ::evenio::archetype::ArchetypeRow) -> Self::Item<'__a> {
                 #get_body
             }
+
+            const FILTERS_ROWS: bool = <#tuple_ty as ::evenio::query::Query>::FILTERS_ROWS;
+
+            #[inline]
+            unsafe fn filter_row(state: &Self::ArchState, row: ::evenio::archetype::ArchetypeRow, last_run_tick: u64) -> bool {
+                <#tuple_ty as ::evenio::query::Query>::filter_row(state, row, last_run_tick)
+            }
         }
 
         #[automatically_derived]
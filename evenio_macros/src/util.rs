@@ -1,28 +1,60 @@
-use proc_macro2::Ident;
-use quote::ToTokens;
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::ext::IdentExt;
 use syn::{
-    parse_quote, Attribute, GenericArgument, Path, Result, ReturnType, Type, TypeParamBound,
-    TypeTuple,
+    parse_quote, Data, DeriveInput, Error, GenericArgument, LitInt, Path, Result, ReturnType, Type,
+    TypeParamBound, TypeTuple,
 };
 
-/// Parse a `#[foo(immutable)]` attribute where `outer` is `foo`.
-pub(crate) fn parse_attr_immutable(outer: &str, attrs: &[Attribute]) -> Result<bool> {
-    let mut res = false;
-
-    for attr in attrs {
-        if attr.path().is_ident(outer) {
-            attr.parse_nested_meta(|meta| {
-                if meta.path.is_ident("immutable") {
-                    res = true;
-                    Ok(())
-                } else {
-                    Err(meta.error("unrecognized argument"))
-                }
-            })?;
-        }
+/// Generate a `reflect_fields` method describing every field of the struct
+/// `input`, for a `#[foo(reflect_fields)]` attribute where `outer` is `foo`.
+/// The field types are required to be `'static`.
+pub(crate) fn reflect_fields_fn(outer: &str, input: &mut DeriveInput) -> Result<TokenStream> {
+    let Data::Struct(struct_) = &input.data else {
+        return Err(Error::new(
+            Span::call_site(),
+            format!("`#[{outer}(reflect_fields)]` is only supported on structs"),
+        ));
+    };
+
+    let mut types = vec![];
+    let mut entries = vec![];
+
+    for (idx, field) in struct_.fields.iter().enumerate() {
+        let (member, name) = match &field.ident {
+            Some(ident) => (ident.to_token_stream(), ident.unraw().to_string()),
+            None => (
+                LitInt::new(&idx.to_string(), Span::call_site()).to_token_stream(),
+                idx.to_string(),
+            ),
+        };
+
+        let ty = &field.ty;
+
+        entries.push(quote! {
+            f(unsafe {
+                ::evenio::reflect::FieldInfo::new::<#ty>(
+                    #name.into(),
+                    ::evenio::__private::offset_of!(Self, #member),
+                )
+            });
+        });
+
+        types.push(ty.clone());
+    }
+
+    let where_clause = input.generics.make_where_clause();
+
+    for ty in types {
+        where_clause.predicates.push(parse_quote!(#ty: 'static));
     }
 
-    Ok(res)
+    Ok(quote! {
+        #[allow(unused_variables)]
+        fn reflect_fields(f: &mut dyn ::core::ops::FnMut(::evenio::reflect::FieldInfo)) {
+            #(#entries)*
+        }
+    })
 }
 
 /// Make a tuple from a list of the tuple's element types.
@@ -108,10 +140,8 @@ pub(crate) fn replace_lifetime(ty: &mut Type, old: &Ident, new: &Ident) {
     ) {
         for arg in args {
             match arg {
-                syn::GenericArgument::Lifetime(l) => {
-                    if l.ident == *old {
-                        l.ident = new.clone();
-                    }
+                syn::GenericArgument::Lifetime(l) if l.ident == *old => {
+                    l.ident = new.clone();
                 }
                 syn::GenericArgument::Type(t) => replace_lifetime(t, old, new),
                 syn::GenericArgument::Const(_) => {
@@ -147,10 +177,8 @@ pub(crate) fn replace_lifetime(ty: &mut Type, old: &Ident, new: &Ident) {
         for bound in bounds {
             match bound {
                 syn::TypeParamBound::Trait(t) => handle_path(&mut t.path, old, new),
-                syn::TypeParamBound::Lifetime(l) => {
-                    if l.ident == *old {
-                        l.ident = new.clone();
-                    }
+                syn::TypeParamBound::Lifetime(l) if l.ident == *old => {
+                    l.ident = new.clone();
                 }
                 syn::TypeParamBound::Verbatim(_) => {}
                 _ => {}
@@ -134,6 +134,20 @@ impl<T> AccessMap<T> {
         self.read.is_disjoint(&other.write) && self.write.is_disjoint(&other.read)
     }
 
+    /// Returns an iterator over the keys whose access in `self` is not
+    /// [compatible] with their access in `other`.
+    ///
+    /// [compatible]: Access::is_compatible
+    pub fn conflicts<'a>(&'a self, other: &'a Self) -> impl Iterator<Item = T> + 'a
+    where
+        T: SparseIndex,
+    {
+        // Read-write access implies read access, so every conflicting key is in `read`.
+        self.read
+            .iter()
+            .filter(move |&key| !self.get(key).is_compatible(other.get(key)))
+    }
+
     /// Computes the union between `self` and `other` and assigns the result to
     /// `self`.
     pub fn union_assign(&mut self, other: &Self) {
@@ -252,6 +266,9 @@ impl ComponentAccessExpr {
 
     /// ANDs two access exprs together. Returns `Err` if the two exprs are
     /// incompatible.
+    // The expr is large when `wide-bit-set` is enabled, but is returned by
+    // value so that it isn't lost on error.
+    #[allow(clippy::result_large_err)]
     pub fn and(mut self, other: &Self) -> Result<Self, Self> {
         if !self.is_compatible(other) {
             return Err(self);
@@ -264,6 +281,7 @@ impl ComponentAccessExpr {
 
     /// ORs two access exprs together. Returns `Err` if the two exprs are
     /// incompatible.
+    #[allow(clippy::result_large_err)]
     pub fn or(mut self, other: &Self) -> Result<Self, Self> {
         if !self.is_compatible(other) {
             return Err(self);
@@ -299,6 +317,22 @@ mod tests {
     const B: ComponentIdx = ComponentIdx(1);
     const C: ComponentIdx = ComponentIdx(2);
 
+    #[test]
+    fn conflicts() {
+        let mut map = AccessMap::new();
+        map.set(A, Access::Read);
+        map.set(B, Access::ReadWrite);
+        map.set(C, Access::Read);
+
+        let mut other = AccessMap::new();
+        other.set(A, Access::ReadWrite);
+        other.set(B, Access::Read);
+        other.set(C, Access::Read);
+
+        assert_eq!(map.conflicts(&other).collect::<Vec<_>>(), [A, B]);
+        assert_eq!(map.conflicts(&map.clone()).collect::<Vec<_>>(), [B]);
+    }
+
     #[test]
     fn t0() {
         let expr = Cae::with(A, Access::ReadWrite);
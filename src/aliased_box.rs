@@ -66,6 +66,13 @@ impl<T: ?Sized> AliasedBox<T> {
     pub(crate) fn as_non_null(this: &Self) -> NonNull<T> {
         this.0
     }
+
+    #[must_use]
+    pub(crate) fn into_box(this: Self) -> Box<T> {
+        let ptr = this.0.as_ptr();
+        mem::forget(this);
+        unsafe { Box::from_raw(ptr) }
+    }
 }
 
 impl<T: ?Sized> Deref for AliasedBox<T> {
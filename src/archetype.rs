@@ -2,25 +2,30 @@
 
 use alloc::collections::btree_map::Entry as BTreeEntry;
 use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::sync::Arc;
 #[cfg(not(feature = "std"))]
 use alloc::{boxed::Box, vec, vec::Vec};
+use core::alloc::Layout;
 use core::cmp::Ordering;
 use core::ptr::NonNull;
-use core::{mem, ptr, slice};
+use core::{fmt, mem, ptr, slice};
 
 use ahash::RandomState;
 use slab::Slab;
 
 use crate::aliased_box::AliasedBox;
+use crate::arena::Arena;
 use crate::assert::{assume_debug_checked, GetDebugChecked, UnwrapDebugChecked};
+use crate::bit_set::BitSet;
 use crate::blob_vec::BlobVec;
-use crate::component::{ComponentIdx, ComponentInfo, Components};
+use crate::bool_expr::BoolExpr;
+use crate::component::{ComponentId, ComponentIdx, ComponentInfo, Components, EqFn};
 use crate::entity::{Entities, EntityId, EntityLocation};
 use crate::event::{EventIdx, EventPtr, TargetedEventIdx};
 use crate::handler::{
     Config, HandlerInfo, HandlerInfoPtr, HandlerList, HandlerParam, Handlers, InitError,
 };
-use crate::map::{Entry, HashMap};
+use crate::map::{Entry, HashMap, IndexSet};
 use crate::prelude::World;
 use crate::sparse::SparseIndex;
 use crate::sparse_map::SparseMap;
@@ -41,9 +46,55 @@ use crate::world::UnsafeWorldCell;
 /// world.add_handler(|_: Receiver<E>, archetypes: &Archetypes| {});
 /// ```
 #[derive(Debug)]
+#[allow(clippy::struct_field_names)]
 pub struct Archetypes {
     archetypes: Slab<Archetype>,
     by_components: HashMap<AliasedBox<[ComponentIdx]>, ArchetypeIdx>,
+    /// Components with a drop hook that were removed from an entity, waiting
+    /// to be handed to the hook.
+    removed: Vec<RemovedComponent>,
+    /// The spawn sequence number given to the next spawned entity.
+    next_spawn_seq: u64,
+    /// The change tick given to components as they are written. See
+    /// [`World::change_tick`].
+    change_tick: ChangeTick,
+    /// Distinct handler match expressions in normalized form. Every archetype
+    /// caches its result for each expression.
+    match_exprs: IndexSet<BoolExpr<ComponentIdx>>,
+    /// Arena the columns of new archetypes are allocated from. See
+    /// [`World::new_in_arena`].
+    ///
+    /// [`World::new_in_arena`]: crate::world::World::new_in_arena
+    arena: Option<Arc<Arena>>,
+    /// Handlers which are running and flushing their events with [`Flush`].
+    /// Their state is borrowed, so they are not notified of archetype changes.
+    ///
+    /// [`Flush`]: crate::flush::Flush
+    suspended: Vec<HandlerInfoPtr>,
+    /// Handlers which missed archetype changes while suspended and must be
+    /// brought up to date once they return.
+    stale: Vec<HandlerInfoPtr>,
+    /// Handlers added with [`World::add_handler_incremental`] which are not
+    /// matched against every archetype yet.
+    ///
+    /// [`World::add_handler_incremental`]: crate::world::World::add_handler_incremental
+    pending: Vec<PendingMatch>,
+}
+
+/// The remaining matching work of a handler added with
+/// [`Archetypes::register_handler_incremental`].
+#[derive(Debug)]
+struct PendingMatch {
+    handler: HandlerInfoPtr,
+    /// Archetypes which existed when the handler was added and are not
+    /// matched against it yet. Archetypes created afterwards are matched
+    /// eagerly.
+    remaining: Vec<ArchetypeIdx>,
+    /// Match expressions of the handler which the archetypes in `remaining`
+    /// have not cached yet.
+    uncached: Vec<MatchExprIdx>,
+    /// Maximum number of archetypes matched per cascade.
+    budget: usize,
 }
 
 impl Archetypes {
@@ -51,12 +102,91 @@ impl Archetypes {
         let mut map = HashMap::with_hasher(RandomState::new());
         map.insert(vec![].into_boxed_slice().into(), ArchetypeIdx::EMPTY);
 
+        let change_tick = ChangeTick::new();
+
         Self {
-            archetypes: Slab::from_iter([(0, Archetype::empty())]),
+            archetypes: Slab::from_iter([(0, Archetype::empty(change_tick.0))]),
             by_components: map,
+            removed: vec![],
+            next_spawn_seq: 0,
+            change_tick,
+            match_exprs: IndexSet::with_hasher(RandomState::new()),
+            arena: None,
+            suspended: vec![],
+            stale: vec![],
+            pending: vec![],
+        }
+    }
+
+    /// Allocates the columns of archetypes created from now on from `arena`.
+    pub(crate) fn set_arena(&mut self, arena: Arena) {
+        self.arena = Some(Arc::new(arena));
+    }
+
+    pub(crate) fn arena(&self) -> Option<&Arena> {
+        self.arena.as_deref()
+    }
+
+    /// Stops notifying `handler` of archetype changes until
+    /// [`Self::resume_handler`] is called.
+    pub(crate) fn suspend_handler(&mut self, handler: HandlerInfoPtr) {
+        self.suspended.push(handler);
+    }
+
+    /// Undoes the most recent [`Self::suspend_handler`]. The handler is
+    /// brought up to date by [`Self::refresh_stale_handlers`] once it is no
+    /// longer suspended.
+    pub(crate) fn resume_handler(&mut self) {
+        if let Some(handler) = self.suspended.pop() {
+            if !self.stale.contains(&handler) {
+                self.stale.push(handler);
+            }
         }
     }
 
+    /// Returns whether `handler` is suspended.
+    pub(crate) fn is_suspended(&self, handler: HandlerInfoPtr) -> bool {
+        self.suspended.contains(&handler)
+    }
+
+    /// Returns whether there are handlers waiting for
+    /// [`Self::refresh_stale_handlers`].
+    pub(crate) fn has_stale_handlers(&self) -> bool {
+        !self.stale.is_empty()
+    }
+
+    /// Refreshes or removes every archetype in the state of the handlers which
+    /// missed changes while suspended, unless they are still suspended.
+    ///
+    /// # Safety
+    ///
+    /// Stale handlers which are not suspended must not be borrowed.
+    pub(crate) unsafe fn refresh_stale_handlers(&mut self) {
+        let suspended = &self.suspended;
+        let archetypes = &self.archetypes;
+
+        self.stale.retain(|&listener| {
+            if suspended.contains(&listener) {
+                return true;
+            }
+
+            let mut ptr = listener;
+            let handler = ptr.as_info_mut().handler_mut();
+
+            for (_, arch) in archetypes {
+                if arch.refresh_listeners.contains(&listener) {
+                    if arch.entity_count() > 0 {
+                        handler.refresh_archetype(arch);
+                    } else {
+                        handler.remove_archetype(arch);
+                    }
+                }
+            }
+
+            false
+        });
+    }
+
     /// Returns a reference to the empty archetype (The archetype with no
     /// components).
     ///
@@ -66,12 +196,6 @@ impl Archetypes {
         unsafe { self.archetypes.get_debug_checked(0) }
     }
 
-    /// Returns a mutable reference to the empty archetype.
-    pub(crate) fn empty_mut(&mut self) -> &mut Archetype {
-        // SAFETY: The empty archetype is always at index 0.
-        unsafe { self.archetypes.get_debug_checked_mut(0) }
-    }
-
     /// Gets a reference to the archetype identified by the given
     /// [`ArchetypeIdx`]. Returns `None` if the index is invalid.
     pub fn get(&self, idx: ArchetypeIdx) -> Option<&Archetype> {
@@ -88,20 +212,67 @@ impl Archetypes {
         Some(unsafe { self.get(idx).unwrap_debug_checked() })
     }
 
+    /// Reserves room for `additional` more entities in the empty archetype.
+    pub(crate) fn reserve_spawns(&mut self, additional: usize) {
+        // SAFETY: The empty archetype is always at index 0.
+        let empty = unsafe { self.archetypes.get_debug_checked_mut(0) };
+
+        let ids_before = empty.entity_ids.as_ptr();
+        let seqs_before = empty.spawn_seqs.as_ptr();
+
+        empty.entity_ids.reserve(additional);
+        empty.spawn_seqs.reserve(additional);
+
+        // Handlers only hold on to the buffers of nonempty archetypes.
+        if empty.entity_count() > 0
+            && (ids_before != empty.entity_ids.as_ptr() || seqs_before != empty.spawn_seqs.as_ptr())
+        {
+            unsafe { empty.notify_refresh(&self.suspended) };
+        }
+    }
+
+    /// Reserves room for `additional` more entities in the archetype at `idx`,
+    /// including its columns.
+    pub(crate) fn reserve(&mut self, idx: ArchetypeIdx, additional: usize) {
+        let Some(arch) = self.archetypes.get_mut(idx.0 as usize) else {
+            return;
+        };
+
+        let capacity_before = arch.buffer_capacity();
+
+        arch.entity_ids.reserve(additional);
+        arch.spawn_seqs.reserve(additional);
+
+        for col in arch.columns_mut() {
+            col.reserve(additional);
+        }
+
+        // Handlers only hold on to the buffers of nonempty archetypes.
+        if arch.entity_count() > 0 && arch.buffer_capacity() != capacity_before {
+            unsafe { arch.notify_refresh(&self.suspended) };
+        }
+    }
+
     /// Spawns a new entity into the empty archetype with the given ID and
     /// returns its location.
     pub(crate) fn spawn(&mut self, id: EntityId) -> EntityLocation {
-        let empty = self.empty_mut();
+        let spawn_seq = self.next_spawn_seq;
+        self.next_spawn_seq += 1;
+
+        // SAFETY: The empty archetype is always at index 0.
+        let empty = unsafe { self.archetypes.get_debug_checked_mut(0) };
 
         let rellocated = empty.push_would_reallocate();
 
         let row = ArchetypeRow(empty.entity_count());
         empty.entity_ids.push(id);
+        empty.spawn_seqs.push(spawn_seq);
+        empty.row_churn = empty.row_churn.saturating_add(1);
+
+        empty.debug_assert_consistent();
 
         if empty.entity_count() == 1 || rellocated {
-            for mut ptr in empty.refresh_listeners.iter().copied() {
-                unsafe { ptr.as_info_mut().handler_mut().refresh_archetype(empty) };
-            }
+            unsafe { empty.notify_refresh(&self.suspended) };
         }
 
         EntityLocation {
@@ -110,6 +281,17 @@ impl Archetypes {
         }
     }
 
+    /// Returns the change tick given to components as they are written.
+    pub(crate) fn change_tick(&self) -> u64 {
+        self.change_tick.get()
+    }
+
+    /// Advances the change tick. Called before each event is dispatched and
+    /// after each handler runs.
+    pub(crate) fn advance_change_tick(&mut self) {
+        self.change_tick.set(self.change_tick.get() + 1);
+    }
+
     /// Returns an iterator over all archetypes in an arbitrary order.
     pub fn iter(&self) -> impl Iterator<Item = &Archetype> {
         self.archetypes.iter().map(|(_, v)| v)
@@ -120,14 +302,310 @@ impl Archetypes {
         self.archetypes.len()
     }
 
+    /// Copies the current values of a double-buffered component into the
+    /// previous buffer in every archetype containing the component.
+    pub(crate) fn copy_to_previous(&mut self, info: &ComponentInfo) {
+        for &arch_idx in &info.member_of {
+            let arch = unsafe { self.archetypes.get_debug_checked_mut(arch_idx.0 as usize) };
+            let col = unsafe { arch.column_of_mut(info.id().index()).unwrap_debug_checked() };
+
+            col.copy_to_previous();
+        }
+    }
+
+    /// Updates every archetype containing the component to match whether the
+    /// component has a drop hook.
+    pub(crate) fn refresh_drop_hook(&mut self, info: &ComponentInfo) {
+        for &arch_idx in &info.member_of {
+            let arch = unsafe { self.archetypes.get_debug_checked_mut(arch_idx.0 as usize) };
+            let col = unsafe { arch.column_of_mut(info.id().index()).unwrap_debug_checked() };
+
+            col.has_drop_hook = info.drop_hook.is_some();
+        }
+    }
+
+    /// Resets the row churn of an archetype whose rows are known to be sorted.
+    pub(crate) fn clear_row_churn(&mut self, arch_idx: ArchetypeIdx) {
+        if let Some(arch) = self.archetypes.get_mut(arch_idx.0 as usize) {
+            arch.row_churn = 0;
+        }
+    }
+
+    /// Reorders the rows of an archetype so that the row previously at
+    /// `perm[i]` is moved to row `i`, updating the locations of the affected
+    /// entities.
+    ///
+    /// # Safety
+    ///
+    /// - `arch_idx` must be a valid archetype index.
+    /// - `perm` must be a permutation of the archetype's rows.
+    pub(crate) unsafe fn permute_rows(
+        &mut self,
+        arch_idx: ArchetypeIdx,
+        perm: &[u32],
+        entities: &mut Entities,
+    ) {
+        let arch = self.archetypes.get_debug_checked_mut(arch_idx.0 as usize);
+
+        for col in arch.columns_mut() {
+            col.data.permute(perm);
+
+            if let Some(previous) = &mut col.previous {
+                previous.permute(perm);
+            }
+
+            col.ticks = perm
+                .iter()
+                .map(|&row| *col.ticks.get_debug_checked(row as usize))
+                .collect();
+
+            col.added = perm
+                .iter()
+                .map(|&row| *col.added.get_debug_checked(row as usize))
+                .collect();
+        }
+
+        arch.entity_ids = perm
+            .iter()
+            .map(|&row| *arch.entity_ids.get_debug_checked(row as usize))
+            .collect();
+
+        arch.spawn_seqs = perm
+            .iter()
+            .map(|&row| *arch.spawn_seqs.get_debug_checked(row as usize))
+            .collect();
+
+        for (row, &id) in arch.entity_ids.iter().enumerate() {
+            entities.get_mut(id).unwrap_debug_checked().row = ArchetypeRow(row as u32);
+        }
+
+        arch.row_churn = 0;
+
+        arch.debug_assert_consistent();
+
+        // Column buffers were reallocated.
+        arch.notify_refresh(&self.suspended);
+    }
+
+    /// Takes the components with a drop hook which were removed since the last
+    /// call.
+    pub(crate) fn take_removed(&mut self) -> Vec<RemovedComponent> {
+        mem::take(&mut self.removed)
+    }
+
     pub(crate) fn register_handler(&mut self, info: &mut HandlerInfo) {
+        self.intern_handler_match_exprs(info);
+
         // TODO: use a `Component -> Vec<Archetype>` index to make this faster?
         for (_, arch) in &mut self.archetypes {
             arch.register_handler(info);
         }
     }
 
+    /// Registers `info` with the archetypes in batches of `budget` archetypes
+    /// per call to [`Self::continue_matching`]. Until then, the handler only
+    /// sees the archetypes it was matched against, and archetypes created
+    /// in the meantime.
+    pub(crate) fn register_handler_incremental(&mut self, info: &mut HandlerInfo, budget: usize) {
+        debug_assert!(budget > 0);
+
+        let mut uncached = vec![];
+
+        let access = self.intern_match_expr_lazy(&info.component_access().expr, &mut uncached);
+        let targeted = match info.targeted_event_expr() {
+            Some(expr) => self.intern_match_expr_lazy(expr, &mut uncached),
+            None => MatchExprIdx::NULL,
+        };
+
+        info.set_match_exprs(access, targeted);
+
+        self.pending.push(PendingMatch {
+            handler: info.ptr(),
+            remaining: self.archetypes.iter().map(|(_, arch)| arch.index).collect(),
+            uncached,
+            budget,
+        });
+    }
+
+    /// Matches every handler added with [`Self::register_handler_incremental`]
+    /// against its budget of archetypes. Handlers which are matched against
+    /// every archetype afterwards are marked as fully matched.
+    ///
+    /// Suspended handlers are skipped.
+    ///
+    /// # Safety
+    ///
+    /// Pending handlers which are not suspended must not be borrowed.
+    pub(crate) unsafe fn continue_matching(&mut self) {
+        let archetypes = &mut self.archetypes;
+        let match_exprs = &self.match_exprs;
+        let suspended = &self.suspended;
+
+        self.pending.retain_mut(|pending| {
+            if suspended.contains(&pending.handler) {
+                return true;
+            }
+
+            let mut ptr = pending.handler;
+            let info = ptr.as_info_mut();
+
+            let start = pending.remaining.len().saturating_sub(pending.budget);
+
+            for arch_idx in pending.remaining.drain(start..) {
+                let arch = archetypes.get_debug_checked_mut(arch_idx.0 as usize);
+
+                for &expr in &pending.uncached {
+                    arch.cache_match_expr(expr, &match_exprs[expr.index()]);
+                }
+
+                arch.register_handler(info);
+            }
+
+            if pending.remaining.is_empty() {
+                info.set_fully_matched();
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    /// Stops matching `handler` incrementally, if it was added with
+    /// [`Self::register_handler_incremental`].
+    fn cancel_pending(&mut self, handler: HandlerInfoPtr) {
+        let Some(i) = self.pending.iter().position(|p| p.handler == handler) else {
+            return;
+        };
+
+        // The expressions are shared with other handlers from now on, so they
+        // must be cached by every archetype.
+        for idx in self.pending.swap_remove(i).uncached {
+            self.cache_match_expr_everywhere(idx);
+        }
+    }
+
+    /// Sorts the lists of handlers for the targeted event `idx` by the rank of
+    /// the handlers, after the list in [`Handlers`] was reordered.
+    pub(crate) fn sort_handler_lists(&mut self, idx: TargetedEventIdx) {
+        for (_, arch) in &mut self.archetypes {
+            if let Some(list) = arch.event_listeners.get_mut(idx) {
+                list.sort_by_rank();
+            }
+        }
+    }
+
+    /// Registers `new` in place of `old`. `new` takes the position of `old`
+    /// in every event listener list both of them belong to.
+    pub(crate) fn replace_handler(&mut self, old: &HandlerInfo, new: &mut HandlerInfo) {
+        self.cancel_pending(old.ptr());
+        self.intern_handler_match_exprs(new);
+
+        for (_, arch) in &mut self.archetypes {
+            arch.replace_handler(old, new);
+        }
+    }
+
+    fn intern_handler_match_exprs(&mut self, info: &mut HandlerInfo) {
+        let access = self.intern_match_expr(&info.component_access().expr);
+        let targeted = match info.targeted_event_expr() {
+            Some(expr) => self.intern_match_expr(expr),
+            None => MatchExprIdx::NULL,
+        };
+
+        info.set_match_exprs(access, targeted);
+    }
+
+    /// Collects every cached edge of the archetype graph in sorted order.
+    pub(crate) fn graph(&self, components: &Components) -> Vec<ArchetypeEdge> {
+        let mut edges = vec![];
+
+        let id_of = |idx| unsafe { components.get_by_index(idx).unwrap_debug_checked().id() };
+
+        for (_, arch) in &self.archetypes {
+            for (&component, &to) in &arch.insert_components {
+                edges.push(ArchetypeEdge {
+                    from: arch.index,
+                    component: id_of(component),
+                    to,
+                    kind: ArchetypeEdgeKind::Insert,
+                });
+            }
+
+            for (&component, &to) in &arch.remove_components {
+                edges.push(ArchetypeEdge {
+                    from: arch.index,
+                    component: id_of(component),
+                    to,
+                    kind: ArchetypeEdgeKind::Remove,
+                });
+            }
+        }
+
+        edges.sort_unstable();
+
+        edges
+    }
+
+    /// Returns the index of the cached match expression equal to `expr` after
+    /// normalization. If the expression is new, it is evaluated against every
+    /// archetype.
+    fn intern_match_expr(&mut self, expr: &BoolExpr<ComponentIdx>) -> MatchExprIdx {
+        let (idx, is_new) = self.insert_match_expr(expr);
+
+        if is_new || self.pending.iter().any(|p| p.uncached.contains(&idx)) {
+            self.cache_match_expr_everywhere(idx);
+        }
+
+        idx
+    }
+
+    /// Like [`Self::intern_match_expr`], but pushes the expression to
+    /// `uncached` instead of evaluating it if it is not cached by every
+    /// archetype.
+    fn intern_match_expr_lazy(
+        &mut self,
+        expr: &BoolExpr<ComponentIdx>,
+        uncached: &mut Vec<MatchExprIdx>,
+    ) -> MatchExprIdx {
+        let (idx, is_new) = self.insert_match_expr(expr);
+
+        if (is_new || self.pending.iter().any(|p| p.uncached.contains(&idx)))
+            && !uncached.contains(&idx)
+        {
+            uncached.push(idx);
+        }
+
+        idx
+    }
+
+    fn insert_match_expr(&mut self, expr: &BoolExpr<ComponentIdx>) -> (MatchExprIdx, bool) {
+        let mut expr = expr.clone();
+        expr.normalize();
+
+        let (idx, is_new) = self.match_exprs.insert_full(expr);
+
+        assert!(idx < u32::MAX as usize, "too many match expressions");
+
+        (MatchExprIdx(idx as u32), is_new)
+    }
+
+    /// Evaluates the match expression `idx` against every archetype. It is no
+    /// longer left to pending handlers.
+    fn cache_match_expr_everywhere(&mut self, idx: MatchExprIdx) {
+        let expr = &self.match_exprs[idx.index()];
+
+        for (_, arch) in &mut self.archetypes {
+            arch.cache_match_expr(idx, expr);
+        }
+
+        for pending in &mut self.pending {
+            pending.uncached.retain(|&e| e != idx);
+        }
+    }
+
     pub(crate) fn remove_handler(&mut self, info: &HandlerInfo) {
+        self.cancel_pending(info.ptr());
+
         // TODO: use a `Component -> Vec<Archetype>` index to make this faster?
         for (_, arch) in &mut self.archetypes {
             arch.refresh_listeners.remove(&info.ptr());
@@ -153,10 +631,12 @@ impl Archetypes {
         for arch_idx in info.member_of.drain(..) {
             let mut arch = self.archetypes.remove(arch_idx.0 as usize);
 
-            for mut ptr in arch.refresh_listeners.iter().copied() {
-                unsafe { ptr.as_info_mut().handler_mut().remove_archetype(&arch) };
+            for pending in &mut self.pending {
+                pending.remaining.retain(|&idx| idx != arch_idx);
             }
 
+            unsafe { arch.notify_remove(&self.suspended) };
+
             for &comp_idx in arch.component_indices() {
                 if comp_idx != removed_component_id.index() {
                     let info =
@@ -237,12 +717,18 @@ impl Archetypes {
                             arch_id,
                             vacant_by_components.key().as_ref().into(),
                             components,
+                            self.arena.as_ref(),
+                            self.change_tick.0,
                         );
 
                         new_arch
                             .remove_components
                             .insert(component_idx, src_arch_idx);
 
+                        for (i, expr) in self.match_exprs.iter().enumerate() {
+                            new_arch.cache_match_expr(MatchExprIdx(i as u32), expr);
+                        }
+
                         for info in handlers.iter_mut() {
                             new_arch.register_handler(info);
                         }
@@ -311,12 +797,18 @@ impl Archetypes {
                             arch_id,
                             vacant_by_components.key().as_ref().into(),
                             components,
+                            self.arena.as_ref(),
+                            self.change_tick.0,
                         );
 
                         new_arch
                             .insert_components
                             .insert(component_idx, src_arch_idx);
 
+                        for (i, expr) in self.match_exprs.iter().enumerate() {
+                            new_arch.cache_match_expr(MatchExprIdx(i as u32), expr);
+                        }
+
                         for info in handlers.iter_mut() {
                             new_arch.register_handler(info);
                         }
@@ -356,7 +848,17 @@ impl Archetypes {
             for (comp_idx, comp_ptr) in new_components {
                 let col = arch.column_of_mut(comp_idx).unwrap_debug_checked();
 
-                col.data.assign(src.row.0 as usize, comp_ptr);
+                let written = if let Some(eq) = col.skip_identical_writes {
+                    col.data.assign_unless_eq(src.row.0 as usize, comp_ptr, eq)
+                } else {
+                    col.data.assign(src.row.0 as usize, comp_ptr);
+                    true
+                };
+
+                // Identical writes that were skipped keep their old tick.
+                if written {
+                    *col.ticks.get_debug_checked_mut(src.row.0 as usize) = self.change_tick.get();
+                }
             }
 
             return src.row;
@@ -393,16 +895,21 @@ impl Archetypes {
                     match src_comp_idx.cmp(&dst_comp_idx) {
                         Ordering::Less => {
                             let src_col = &mut *src_arch.columns.as_ptr().add(src_idx);
-                            src_col.data.swap_remove(src.row.0 as usize);
+
+                            if let Some(data) = src_col.swap_remove(src.row.0 as usize) {
+                                self.removed.push(RemovedComponent {
+                                    idx: src_comp_idx,
+                                    data,
+                                });
+                            }
+
                             src_idx += 1;
                         }
                         Ordering::Equal => {
                             let src_col = &mut *src_arch.columns.as_ptr().add(src_idx);
                             let dst_col = &mut *dst_arch.columns.as_ptr().add(dst_idx);
 
-                            src_col
-                                .data
-                                .transfer_elem(&mut dst_col.data, src.row.0 as usize);
+                            src_col.transfer_elem(dst_col, src.row.0 as usize);
 
                             src_idx += 1;
                             dst_idx += 1;
@@ -420,11 +927,7 @@ impl Archetypes {
 
                             debug_assert_eq!(component_idx, dst_comp_idx);
 
-                            ptr::copy_nonoverlapping(
-                                component_ptr,
-                                dst_col.data.push().as_ptr(),
-                                dst_col.data.elem_layout().size(),
-                            );
+                            dst_col.push_copy(component_ptr, self.change_tick.get());
 
                             dst_idx += 1;
                         }
@@ -432,7 +935,17 @@ impl Archetypes {
                 }
                 (true, false) => {
                     let src_col = &mut *src_arch.columns.as_ptr().add(src_idx);
-                    src_col.data.swap_remove(src.row.0 as usize);
+
+                    if let Some(data) = src_col.swap_remove(src.row.0 as usize) {
+                        self.removed.push(RemovedComponent {
+                            idx: *src_arch
+                                .component_indices
+                                .as_ref()
+                                .get_debug_checked(src_idx),
+                            data,
+                        });
+                    }
+
                     src_idx += 1;
                 }
                 (false, true) => {
@@ -448,11 +961,7 @@ impl Archetypes {
 
                     debug_assert_eq!(component_idx, dst_comp_idx);
 
-                    ptr::copy_nonoverlapping(
-                        component_ptr,
-                        dst_col.data.push().as_ptr(),
-                        dst_col.data.elem_layout().size(),
-                    );
+                    dst_col.push_copy(component_ptr, self.change_tick.get());
 
                     dst_idx += 1;
                 }
@@ -465,6 +974,14 @@ impl Archetypes {
         let entity_id = src_arch.entity_ids.swap_remove(src.row.0 as usize);
         dst_arch.entity_ids.push(entity_id);
 
+        let spawn_seq = src_arch.spawn_seqs.swap_remove(src.row.0 as usize);
+        dst_arch.spawn_seqs.push(spawn_seq);
+
+        dst_arch.row_churn = dst_arch.row_churn.saturating_add(1);
+        if (src.row.0 as usize) < src_arch.entity_ids.len() {
+            src_arch.row_churn = src_arch.row_churn.saturating_add(1);
+        }
+
         *unsafe { entities.get_mut(entity_id).unwrap_debug_checked() } = EntityLocation {
             archetype: dst,
             row: dst_row,
@@ -474,24 +991,60 @@ impl Archetypes {
             unsafe { entities.get_mut(swapped_entity_id).unwrap_debug_checked() }.row = src.row;
         }
 
+        src_arch.debug_assert_consistent();
+        dst_arch.debug_assert_consistent();
+
         if src_arch.entity_ids.is_empty() {
-            for mut ptr in src_arch.refresh_listeners.iter().copied() {
-                unsafe { ptr.as_info_mut().handler_mut().remove_archetype(src_arch) };
-            }
+            unsafe { src_arch.notify_remove(&self.suspended) };
         }
 
         if dst_arch_reallocated || dst_arch.entity_count() == 1 {
-            for mut ptr in dst_arch.refresh_listeners.iter().copied() {
-                unsafe { ptr.as_info_mut().handler_mut().refresh_archetype(dst_arch) };
-            }
+            unsafe { dst_arch.notify_refresh(&self.suspended) };
         }
 
         dst_row
     }
 
     pub(crate) fn remove_entity(&mut self, entity: EntityId, entities: &mut Entities) {
+        let mut removed = mem::take(&mut self.removed);
+
+        self.remove_entity_with(entity, entities, |idx, col, row| {
+            if let Some(data) = unsafe { col.swap_remove(row) } {
+                removed.push(RemovedComponent { idx, data });
+            }
+        });
+
+        self.removed = removed;
+    }
+
+    /// Removes `entity` and moves each of its components into a separate
+    /// buffer without dropping them. Returns `None` if the entity doesn't
+    /// exist.
+    pub(crate) fn take_entity(
+        &mut self,
+        entity: EntityId,
+        entities: &mut Entities,
+    ) -> Option<Vec<(ComponentIdx, BlobVec)>> {
+        let mut taken = vec![];
+
+        let exists = self.remove_entity_with(entity, entities, |idx, col, row| {
+            taken.push((idx, unsafe { col.take(row) }));
+        });
+
+        exists.then_some(taken)
+    }
+
+    /// Removes `entity` from its archetype, calling `remove` with the row of
+    /// every column. `remove` must swap remove the row from the column.
+    /// Returns `false` if the entity doesn't exist.
+    fn remove_entity_with(
+        &mut self,
+        entity: EntityId,
+        entities: &mut Entities,
+        mut remove: impl FnMut(ComponentIdx, &mut Column, usize),
+    ) -> bool {
         let Some(loc) = entities.remove(entity) else {
-            return;
+            return false;
         };
 
         let arch = unsafe {
@@ -499,8 +1052,10 @@ impl Archetypes {
                 .get_debug_checked_mut(loc.archetype.0 as usize)
         };
 
-        for col in arch.columns_mut() {
-            unsafe { col.data.swap_remove(loc.row.0 as usize) };
+        let component_indices = unsafe { arch.component_indices.as_ref() };
+
+        for (&idx, col) in component_indices.iter().zip(arch.columns_mut()) {
+            remove(idx, col, loc.row.0 as usize);
         }
 
         unsafe {
@@ -508,12 +1063,24 @@ impl Archetypes {
         };
 
         arch.entity_ids.swap_remove(loc.row.0 as usize);
+        arch.spawn_seqs.swap_remove(loc.row.0 as usize);
+
+        if (loc.row.0 as usize) < arch.entity_ids.len() {
+            arch.row_churn = arch.row_churn.saturating_add(1);
+        }
+
+        // Update the location of the entity that was swapped into the removed row.
+        if let Some(&swapped_entity_id) = arch.entity_ids.get(loc.row.0 as usize) {
+            unsafe { entities.get_mut(swapped_entity_id).unwrap_debug_checked() }.row = loc.row;
+        }
+
+        arch.debug_assert_consistent();
 
         if arch.entity_count() == 0 {
-            for mut ptr in arch.refresh_listeners.iter().copied() {
-                unsafe { ptr.as_info_mut().handler_mut().remove_archetype(arch) };
-            }
+            unsafe { arch.notify_remove(&self.suspended) };
         }
+
+        true
     }
 }
 
@@ -522,8 +1089,8 @@ unsafe impl HandlerParam for &'_ Archetypes {
 
     type Item<'a> = &'a Archetypes;
 
-    fn init(_world: &mut World, _config: &mut Config) -> Result<Self::State, InitError> {
-        Ok(())
+    fn init(_world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+        config.read_world::<Self>()
     }
 
     unsafe fn get<'a>(
@@ -566,6 +1133,51 @@ unsafe impl SparseIndex for ArchetypeIdx {
     }
 }
 
+/// An edge of the archetype graph, as returned by [`World::archetype_graph`].
+///
+/// Edges are created the first time an entity moves between two archetypes.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ArchetypeEdge {
+    /// The source archetype.
+    pub from: ArchetypeIdx,
+    /// The component inserted into or removed from `from`.
+    pub component: ComponentId,
+    /// The destination archetype.
+    pub to: ArchetypeIdx,
+    /// Whether `component` is inserted or removed.
+    pub kind: ArchetypeEdgeKind,
+}
+
+/// The kind of an [`ArchetypeEdge`].
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum ArchetypeEdgeKind {
+    /// The component is inserted.
+    Insert,
+    /// The component is removed.
+    Remove,
+}
+
+/// Index of a distinct handler match expression cached by every archetype.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub(crate) struct MatchExprIdx(pub(crate) u32);
+
+impl MatchExprIdx {
+    /// The match expression index that is always invalid.
+    pub(crate) const NULL: Self = Self(u32::MAX);
+}
+
+unsafe impl SparseIndex for MatchExprIdx {
+    const MAX: Self = MatchExprIdx::NULL;
+
+    fn index(self) -> usize {
+        self.0.index()
+    }
+
+    fn from_index(idx: usize) -> Self {
+        Self(u32::from_index(idx))
+    }
+}
+
 /// Offset from the beginning of a component column. Combined with an
 /// [`ArchetypeIdx`], this can identify the location of an entity.
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
@@ -602,25 +1214,40 @@ pub struct Archetype {
     /// A special column containing the [`EntityId`] for all entities in the
     /// archetype.
     entity_ids: Vec<EntityId>,
+    /// The spawn sequence number of every entity in the archetype, parallel
+    /// to `entity_ids`.
+    spawn_seqs: Vec<u64>,
+    /// Number of rows added, or moved to fill a removed row, since the rows
+    /// were last sorted.
+    row_churn: u32,
     insert_components: BTreeMap<ComponentIdx, ArchetypeIdx>,
     remove_components: BTreeMap<ComponentIdx, ArchetypeIdx>,
     /// Handlers that need to be notified about column changes.
     refresh_listeners: BTreeSet<HandlerInfoPtr>,
     /// Targeted event listeners for this archetype.
     event_listeners: SparseMap<TargetedEventIdx, HandlerList>,
+    /// The set of match expressions in [`Archetypes`] which are true for this
+    /// archetype.
+    matched_exprs: BitSet<MatchExprIdx>,
+    /// Points to the change tick of [`Archetypes`].
+    change_tick: NonNull<u64>,
 }
 
 impl Archetype {
-    fn empty() -> Self {
+    fn empty(change_tick: NonNull<u64>) -> Self {
         Self {
             index: ArchetypeIdx::EMPTY,
             component_indices: NonNull::from(<&[_]>::default()),
             columns: NonNull::dangling(),
             entity_ids: vec![],
+            spawn_seqs: vec![],
+            row_churn: 0,
             insert_components: BTreeMap::new(),
             remove_components: BTreeMap::new(),
             refresh_listeners: BTreeSet::new(),
             event_listeners: SparseMap::new(),
+            matched_exprs: BitSet::new(),
+            change_tick,
         }
     }
 
@@ -629,10 +1256,13 @@ impl Archetype {
     /// - Component indices slice must be in sorted order.
     /// - Component indices slice must outlive the archetype.
     /// - All component indices must be valid.
+    /// - `change_tick` must point to the change tick of [`Archetypes`].
     unsafe fn new(
         arch_idx: ArchetypeIdx,
         component_indices: NonNull<[ComponentIdx]>,
         components: &mut Components,
+        arena: Option<&Arc<Arena>>,
+        change_tick: NonNull<u64>,
     ) -> Self {
         let columns: Box<[Column]> = component_indices
             .as_ref()
@@ -646,13 +1276,32 @@ impl Archetype {
 
                 info.member_of.insert(arch_idx);
 
-                Column {
-                    data: unsafe { BlobVec::new(info.layout(), info.drop()) },
-                }
-            })
-            .collect();
+                let new_blob_vec = |drop| {
+                    let mut vec = unsafe { BlobVec::new(info.unpadded_layout(), drop) };
+                    vec.track_allocations(info.bytes_allocated.clone());
 
-        // SAFETY: `Box::into_raw` guarantees non-null.
+                    if let Some(arena) = arena {
+                        vec.use_arena(arena.clone());
+                    }
+
+                    vec
+                };
+
+                let data = new_blob_vec(info.drop());
+                let previous = info.is_double_buffered().then(|| new_blob_vec(None));
+
+                Column {
+                    data,
+                    previous,
+                    ticks: vec![],
+                    added: vec![],
+                    has_drop_hook: info.drop_hook.is_some(),
+                    skip_identical_writes: info.skip_identical_writes(),
+                }
+            })
+            .collect();
+
+        // SAFETY: `Box::into_raw` guarantees non-null.
         let columns_ptr = unsafe { NonNull::new_unchecked(Box::into_raw(columns) as *mut Column) };
 
         Self {
@@ -660,19 +1309,67 @@ impl Archetype {
             component_indices,
             columns: columns_ptr,
             entity_ids: vec![],
+            spawn_seqs: vec![],
+            row_churn: 0,
             insert_components: BTreeMap::new(),
             remove_components: BTreeMap::new(),
             refresh_listeners: BTreeSet::new(),
             event_listeners: SparseMap::new(),
+            matched_exprs: BitSet::new(),
+            change_tick,
+        }
+    }
+
+    fn cache_match_expr(&mut self, idx: MatchExprIdx, expr: &BoolExpr<ComponentIdx>) {
+        if expr.eval(|idx| self.column_of(idx).is_some()) {
+            self.matched_exprs.insert(idx);
+        }
+    }
+
+    /// Returns the cached result of a match expression for this archetype.
+    pub(crate) fn cached_match(&self, idx: MatchExprIdx) -> bool {
+        self.matched_exprs.contains(idx)
+    }
+
+    /// Calls [`Handler::refresh_archetype`] on every handler listening to this
+    /// archetype, except for `suspended` handlers.
+    ///
+    /// # Safety
+    ///
+    /// Listeners which are not suspended must not be borrowed.
+    ///
+    /// [`Handler::refresh_archetype`]: crate::handler::Handler::refresh_archetype
+    unsafe fn notify_refresh(&self, suspended: &[HandlerInfoPtr]) {
+        for mut ptr in self.refresh_listeners.iter().copied() {
+            if !suspended.contains(&ptr) {
+                ptr.as_info_mut().handler_mut().refresh_archetype(self);
+            }
+        }
+    }
+
+    /// Calls [`Handler::remove_archetype`] on every handler listening to this
+    /// archetype, except for `suspended` handlers.
+    ///
+    /// # Safety
+    ///
+    /// Listeners which are not suspended must not be borrowed.
+    ///
+    /// [`Handler::remove_archetype`]: crate::handler::Handler::remove_archetype
+    unsafe fn notify_remove(&self, suspended: &[HandlerInfoPtr]) {
+        for mut ptr in self.refresh_listeners.iter().copied() {
+            if !suspended.contains(&ptr) {
+                ptr.as_info_mut().handler_mut().remove_archetype(self);
+            }
         }
     }
 
+    /// Returns whether `handler` is notified of changes to this archetype.
+    pub(crate) fn has_refresh_listener(&self, handler: HandlerInfoPtr) -> bool {
+        self.refresh_listeners.contains(&handler)
+    }
+
     fn register_handler(&mut self, info: &mut HandlerInfo) {
-        if info
-            .component_access()
-            .expr
-            .eval(|idx| self.column_of(idx).is_some())
-        {
+        if self.cached_match(info.access_match_expr()) {
             if self.entity_count() > 0 {
                 info.handler_mut().refresh_archetype(self);
             }
@@ -680,15 +1377,13 @@ impl Archetype {
             self.refresh_listeners.insert(info.ptr());
         }
 
-        if let (Some(expr), EventIdx::Targeted(targeted_event_idx)) =
-            (info.targeted_event_expr(), info.received_event().index())
-        {
-            if expr.eval(|idx| self.column_of(idx).is_some()) {
+        if let EventIdx::Targeted(targeted_event_idx) = info.received_event().index() {
+            if self.cached_match(info.targeted_match_expr()) {
                 if let Some(list) = self.event_listeners.get_mut(targeted_event_idx) {
-                    list.insert(info.ptr(), info.priority());
+                    list.insert_ranked(info.ptr());
                 } else {
                     let mut list = HandlerList::new();
-                    list.insert(info.ptr(), info.priority());
+                    list.insert_ranked(info.ptr());
 
                     self.event_listeners.insert(targeted_event_idx, list);
                 }
@@ -696,6 +1391,40 @@ impl Archetype {
         }
     }
 
+    fn replace_handler(&mut self, old: &HandlerInfo, new: &mut HandlerInfo) {
+        self.refresh_listeners.remove(&old.ptr());
+
+        if self.cached_match(new.access_match_expr()) {
+            if self.entity_count() > 0 {
+                new.handler_mut().refresh_archetype(self);
+            }
+
+            self.refresh_listeners.insert(new.ptr());
+        }
+
+        if let EventIdx::Targeted(targeted_event_idx) = new.received_event().index() {
+            let matches = self.cached_match(new.targeted_match_expr());
+
+            if let Some(list) = self.event_listeners.get_mut(targeted_event_idx) {
+                if !matches {
+                    list.remove(old.ptr());
+                    return;
+                }
+
+                if list.replace(old.ptr(), new.ptr()) {
+                    return;
+                }
+
+                list.insert_ranked(new.ptr());
+            } else if matches {
+                let mut list = HandlerList::new();
+                list.insert_ranked(new.ptr());
+
+                self.event_listeners.insert(targeted_event_idx, list);
+            }
+        }
+    }
+
     pub(crate) fn handler_list_for(&self, idx: TargetedEventIdx) -> Option<&HandlerList> {
         self.event_listeners.get(idx)
     }
@@ -705,6 +1434,12 @@ impl Archetype {
         self.index
     }
 
+    /// Returns the number of rows which were added to the archetype, or moved
+    /// to fill the row of a removed entity, since the rows were last sorted.
+    pub(crate) fn row_churn(&self) -> u32 {
+        self.row_churn
+    }
+
     /// Returns the total number of entities in this archetype.
     pub fn entity_count(&self) -> u32 {
         debug_assert!(u32::try_from(self.entity_ids.len()).is_ok());
@@ -717,6 +1452,20 @@ impl Archetype {
         &self.entity_ids
     }
 
+    /// Returns a pointer to the current change tick, which stays valid for as
+    /// long as the archetype exists.
+    pub(crate) fn change_tick_ptr(&self) -> NonNull<u64> {
+        self.change_tick
+    }
+
+    /// Returns the spawn sequence numbers of all the entities in this
+    /// archetype, in the same order as [`entity_ids`](Archetype::entity_ids).
+    ///
+    /// See [`SpawnSeq`](crate::query::SpawnSeq) for more information.
+    pub fn spawn_seqs(&self) -> &[u64] {
+        &self.spawn_seqs
+    }
+
     /// Returns a sorted slice of component types for every column in this
     /// archetype.
     ///
@@ -752,16 +1501,57 @@ impl Archetype {
         Some(unsafe { &mut *self.columns.as_ptr().add(idx) })
     }
 
+    /// Returns the total capacity of the entity ID and column buffers, used to
+    /// detect reallocation. Columns of zero-sized components are excluded.
+    pub(crate) fn buffer_capacity(&self) -> usize {
+        self.columns()
+            .iter()
+            .flat_map(|col| [Some(&col.data), col.previous.as_ref()])
+            .flatten()
+            .map(BlobVec::capacity)
+            .filter(|&cap| cap != usize::MAX)
+            .sum::<usize>()
+            + self
+                .columns()
+                .iter()
+                .map(|col| col.ticks.capacity() + col.added.capacity())
+                .sum::<usize>()
+            + self.entity_ids.capacity()
+            + self.spawn_seqs.capacity()
+    }
+
     /// Would the columns of this archetype reallocate if an entity were added
     /// to it?
     fn push_would_reallocate(&self) -> bool {
         // All columns should have the same capacity and length, so we only need to look
-        // at one of them. The `Vec` holding the Entity IDs might have a different
-        // reallocation strategy, so check that too.
-        self.columns()
-            .first()
-            .map_or(false, |col| col.data.len() == col.data.capacity())
-            || self.entity_ids.capacity() == self.entity_ids.len()
+        // at one of them. The `Vec`s holding the Entity IDs and spawn sequence
+        // numbers might have a different reallocation strategy, so check those too.
+        self.columns().first().is_some_and(|col| {
+            col.data.len() == col.data.capacity() || col.ticks.len() == col.ticks.capacity()
+        }) || self.entity_ids.capacity() == self.entity_ids.len()
+            || self.spawn_seqs.capacity() == self.spawn_seqs.len()
+    }
+
+    /// Checks that every column has exactly one element per entity. Called
+    /// after each structural change. Does nothing in release builds.
+    ///
+    /// See the [`safety`](crate::safety#archetype-rows-and-columns) module.
+    fn debug_assert_consistent(&self) {
+        if cfg!(debug_assertions) {
+            let len = self.entity_ids.len();
+
+            assert_eq!(self.spawn_seqs.len(), len, "spawn sequence length");
+
+            for (col, idx) in self.columns().iter().zip(self.component_indices()) {
+                assert_eq!(col.data.len(), len, "data length of column {idx:?}");
+                assert_eq!(col.ticks.len(), len, "tick length of column {idx:?}");
+                assert_eq!(col.added.len(), len, "added tick length of column {idx:?}");
+
+                if let Some(previous) = &col.previous {
+                    assert_eq!(previous.len(), len, "previous length of column {idx:?}");
+                }
+            }
+        }
     }
 }
 
@@ -773,7 +1563,7 @@ impl Drop for Archetype {
         // SAFETY: The columns pointer originated from a
         // `Box<[Column]>` with the length of `component_indices`.
         let _ = unsafe {
-            Box::from_raw(slice::from_raw_parts_mut(
+            Box::from_raw(ptr::slice_from_raw_parts_mut(
                 self.columns.as_ptr(),
                 self.component_indices.len(),
             ))
@@ -781,6 +1571,44 @@ impl Drop for Archetype {
     }
 }
 
+unsafe impl Send for Archetypes {}
+unsafe impl Sync for Archetypes {}
+
+/// A change tick with an address that doesn't change when [`Archetypes`]
+/// moves, so that [`Mut`] can read the current tick through a pointer cached
+/// in its per-archetype state.
+///
+/// [`Mut`]: crate::query::Mut
+struct ChangeTick(NonNull<u64>);
+
+impl ChangeTick {
+    fn new() -> Self {
+        // SAFETY: `Box::into_raw` guarantees non-null.
+        Self(unsafe { NonNull::new_unchecked(Box::into_raw(Box::new(0))) })
+    }
+
+    fn get(&self) -> u64 {
+        unsafe { *self.0.as_ptr() }
+    }
+
+    fn set(&mut self, tick: u64) {
+        unsafe { *self.0.as_ptr() = tick };
+    }
+}
+
+impl Drop for ChangeTick {
+    fn drop(&mut self) {
+        // SAFETY: The pointer originated from a `Box<u64>`.
+        drop(unsafe { Box::from_raw(self.0.as_ptr()) });
+    }
+}
+
+impl fmt::Debug for ChangeTick {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.get().fmt(f)
+    }
+}
+
 unsafe impl Send for Archetype {}
 unsafe impl Sync for Archetype {}
 
@@ -789,6 +1617,21 @@ unsafe impl Sync for Archetype {}
 pub struct Column {
     /// Component data in this column.
     data: BlobVec,
+    /// Copy of the component data as of the last call to
+    /// [`World::swap_buffers`]. Only present for [double-buffered] components.
+    ///
+    /// [double-buffered]: crate::component::Component::DOUBLE_BUFFERED
+    previous: Option<BlobVec>,
+    /// The change tick of each component in this column.
+    ticks: Vec<u64>,
+    /// The change tick at which each component in this column was added to
+    /// its entity.
+    added: Vec<u64>,
+    /// Whether removed components are kept for a drop hook instead of being
+    /// dropped.
+    has_drop_hook: bool,
+    /// Copied from [`ComponentInfo::skip_identical_writes`].
+    skip_identical_writes: Option<EqFn>,
 }
 
 impl Column {
@@ -797,12 +1640,159 @@ impl Column {
     pub fn data(&self) -> NonNull<u8> {
         self.data.as_ptr()
     }
+
+    /// Returns a pointer to the beginning of the buffer holding the previous
+    /// component data, or `None` if the component is not
+    /// [double-buffered].
+    ///
+    /// [double-buffered]: crate::component::Component::DOUBLE_BUFFERED
+    pub fn previous_data(&self) -> Option<NonNull<u8>> {
+        self.previous.as_ref().map(BlobVec::as_ptr)
+    }
+
+    /// Returns the change tick of each component in this column, indexed by
+    /// row. See [`World::change_tick`] for more information.
+    pub fn change_ticks(&self) -> &[u64] {
+        &self.ticks
+    }
+
+    /// Returns the change tick at which each component in this column was
+    /// added to its entity, indexed by row. Overwriting a component with
+    /// [`Insert`] doesn't change it, but moving the entity to another
+    /// archetype keeps it.
+    ///
+    /// [`Insert`]: crate::event::Insert
+    pub fn added_ticks(&self) -> &[u64] {
+        &self.added
+    }
+
+    /// Returns a pointer to the beginning of the buffer holding the change
+    /// ticks.
+    pub(crate) fn ticks_ptr(&self) -> NonNull<u64> {
+        // SAFETY: `Vec` pointers are never null.
+        unsafe { NonNull::new_unchecked(self.ticks.as_ptr().cast_mut()) }
+    }
+
+    /// Returns a pointer to the beginning of the buffer holding the added
+    /// ticks.
+    pub(crate) fn added_ptr(&self) -> NonNull<u64> {
+        // SAFETY: `Vec` pointers are never null.
+        unsafe { NonNull::new_unchecked(self.added.as_ptr().cast_mut()) }
+    }
+
+    /// Copies the current component data into the previous buffer. Does
+    /// nothing if the component is not double-buffered.
+    fn copy_to_previous(&mut self) {
+        if let Some(previous) = &mut self.previous {
+            debug_assert_eq!(self.data.len(), previous.len());
+
+            // SAFETY: Both buffers have the same length and element layout, and
+            // double-buffered components can be copied bitwise.
+            unsafe {
+                ptr::copy_nonoverlapping(
+                    self.data.as_ptr().as_ptr(),
+                    previous.as_ptr().as_ptr(),
+                    self.data.len() * self.data.elem_layout().size(),
+                )
+            };
+        }
+    }
+
+    /// Pushes a copy of the component at `src` onto the end of the column with
+    /// the change tick `tick`. The previous value is initialized to the same
+    /// component.
+    unsafe fn push_copy(&mut self, src: *const u8, tick: u64) {
+        let size = self.data.elem_size();
+
+        ptr::copy_nonoverlapping(src, self.data.push().as_ptr(), size);
+        self.ticks.push(tick);
+        self.added.push(tick);
+
+        if let Some(previous) = &mut self.previous {
+            ptr::copy_nonoverlapping(src, previous.push().as_ptr(), size);
+        }
+    }
+
+    /// Reserves room for `additional` more components.
+    fn reserve(&mut self, additional: usize) {
+        self.data.reserve(additional);
+        self.ticks.reserve(additional);
+        self.added.reserve(additional);
+
+        if let Some(previous) = &mut self.previous {
+            previous.reserve(additional);
+        }
+    }
+
+    /// Swap removes the component at `idx`. If the component has a drop
+    /// hook, it is moved into a new buffer and returned instead of being
+    /// dropped.
+    unsafe fn swap_remove(&mut self, idx: usize) -> Option<BlobVec> {
+        if self.has_drop_hook {
+            return Some(self.take(idx));
+        }
+
+        self.data.swap_remove(idx);
+        self.ticks.swap_remove(idx);
+        self.added.swap_remove(idx);
+
+        if let Some(previous) = &mut self.previous {
+            previous.swap_remove(idx);
+        }
+
+        None
+    }
+
+    /// Swap removes the component at `idx` and moves it into a new buffer
+    /// without dropping it.
+    unsafe fn take(&mut self, idx: usize) -> BlobVec {
+        let layout = Layout::from_size_align_unchecked(
+            self.data.elem_size(),
+            self.data.elem_layout().align(),
+        );
+
+        let mut taken = BlobVec::new(layout, self.data.drop_fn());
+        self.data.transfer_elem(&mut taken, idx);
+        self.ticks.swap_remove(idx);
+        self.added.swap_remove(idx);
+
+        if let Some(previous) = &mut self.previous {
+            previous.swap_remove(idx);
+        }
+
+        taken
+    }
+
+    /// Moves the component at `src_idx` to the end of `other`, along with its
+    /// previous value.
+    unsafe fn transfer_elem(&mut self, other: &mut Self, src_idx: usize) {
+        self.data.transfer_elem(&mut other.data, src_idx);
+        other.ticks.push(self.ticks.swap_remove(src_idx));
+        other.added.push(self.added.swap_remove(src_idx));
+
+        if let (Some(src), Some(dst)) = (&mut self.previous, &mut other.previous) {
+            src.transfer_elem(dst, src_idx);
+        }
+    }
 }
 
 // SAFETY: Components are guaranteed `Send` and `Sync`.
 unsafe impl Send for Column {}
 unsafe impl Sync for Column {}
 
+/// A component removed from an entity which is waiting to be passed to its
+/// drop hook.
+#[derive(Debug)]
+pub(crate) struct RemovedComponent {
+    pub(crate) idx: ComponentIdx,
+    /// Buffer holding the single removed component.
+    pub(crate) data: BlobVec,
+}
+
+// SAFETY: Components are guaranteed `Send` and `Sync`.
+unsafe impl Send for RemovedComponent {}
+unsafe impl Sync for RemovedComponent {}
+
 #[cfg(test)]
 mod tests {
     use crate::prelude::*;
@@ -810,12 +1800,6 @@ mod tests {
     #[derive(Component)]
     struct C(String);
 
-    #[derive(Event)]
-    struct E1;
-
-    #[derive(Event)]
-    struct E2;
-
     #[test]
     fn insert_overwrites() {
         let mut world = World::new();
@@ -830,4 +1814,473 @@ mod tests {
 
         assert_eq!(world.get::<C>(e).unwrap().0, "goodbye");
     }
+
+    #[test]
+    fn archetype_graph() {
+        use crate::archetype::{ArchetypeEdge, ArchetypeEdgeKind, ArchetypeIdx};
+
+        #[derive(Component)]
+        struct A;
+
+        #[derive(Component)]
+        struct B;
+
+        let mut world = World::new();
+
+        let a = world.add_component::<A>();
+        let b = world.add_component::<B>();
+
+        let e = world.spawn();
+        world.insert(e, A);
+        world.insert(e, B);
+        world.remove::<A>(e);
+
+        let arch = |components: &[_]| {
+            world
+                .archetypes()
+                .get_by_components(components)
+                .unwrap()
+                .index()
+        };
+
+        let empty = ArchetypeIdx::EMPTY;
+        let arch_a = arch(&[a.index()]);
+        let arch_b = arch(&[b.index()]);
+        let arch_ab = arch(&[a.index(), b.index()]);
+
+        let edge = |from, component, to, kind| ArchetypeEdge {
+            from,
+            component,
+            to,
+            kind,
+        };
+
+        let graph = world.archetype_graph();
+
+        let expected = [
+            edge(empty, a, arch_a, ArchetypeEdgeKind::Insert),
+            edge(arch_a, b, arch_ab, ArchetypeEdgeKind::Insert),
+            edge(arch_a, a, empty, ArchetypeEdgeKind::Remove),
+            edge(arch_ab, a, arch_b, ArchetypeEdgeKind::Remove),
+            edge(arch_ab, b, arch_a, ArchetypeEdgeKind::Remove),
+            edge(arch_b, a, arch_ab, ArchetypeEdgeKind::Insert),
+        ];
+
+        assert_eq!(graph.len(), expected.len());
+
+        for edge in &expected {
+            assert!(graph.contains(edge), "missing edge {edge:?}");
+        }
+    }
+
+    #[test]
+    fn match_expr_cache() {
+        #[derive(Component)]
+        struct A;
+
+        #[derive(Component)]
+        struct B;
+
+        #[derive(Component)]
+        struct D;
+
+        #[derive(Component)]
+        struct X;
+
+        #[derive(Event)]
+        struct E;
+
+        let mut world = World::new();
+
+        let e1 = world.spawn();
+        world.insert(e1, A);
+        let e2 = world.spawn();
+        world.insert(e2, A);
+        world.insert(e2, B);
+        let e3 = world.spawn();
+        world.insert(e3, D);
+        let e4 = world.spawn();
+        world.insert(e4, B);
+
+        // (A ∧ ¬B) ∨ D, written two different ways.
+        type Filter1 = Or<(With<&'static A>, Not<&'static B>), With<&'static D>>;
+        type Filter2 = Or<With<&'static D>, (Not<&'static B>, With<&'static A>)>;
+
+        let h1 = world.add_handler(|_: Receiver<E>, _: Fetcher<Filter1>| {});
+        let h2 = world.add_handler(|_: Receiver<E>, _: Fetcher<Filter2>| {});
+
+        let info = world.handlers().get(h1).unwrap();
+        let idx = info.access_match_expr();
+        let expr = info.component_access().expr.clone();
+
+        assert_eq!(world.handlers().get(h2).unwrap().access_match_expr(), idx);
+
+        let check = |world: &World| {
+            for arch in world.archetypes().iter() {
+                assert_eq!(
+                    arch.cached_match(idx),
+                    expr.eval(|c| arch.column_of(c).is_some())
+                );
+            }
+        };
+
+        check(&world);
+
+        let matches = |world: &World, e| {
+            let loc = world.entities().get(e).unwrap();
+            world
+                .archetypes()
+                .get(loc.archetype)
+                .unwrap()
+                .cached_match(idx)
+        };
+
+        assert!(matches(&world, e1));
+        assert!(!matches(&world, e2));
+        assert!(matches(&world, e3));
+        assert!(!matches(&world, e4));
+
+        // Creates the archetypes `{A, X}` and `{A, B, X}`.
+        world.insert(e1, X);
+        world.insert(e2, X);
+
+        check(&world);
+        assert!(matches(&world, e1));
+        assert!(!matches(&world, e2));
+    }
+
+    #[test]
+    fn incremental_matching_equals_eager() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        use ::alloc::sync::Arc;
+
+        use crate::archetype::Archetype;
+        use crate::event::EventIdx;
+
+        #[derive(Component)]
+        struct A;
+
+        #[derive(Component)]
+        struct B;
+
+        #[derive(Component)]
+        struct M<const N: usize>;
+
+        #[derive(Component)]
+        struct X;
+
+        #[derive(Event)]
+        struct E;
+
+        #[derive(Event)]
+        struct T(#[event(target)] EntityId);
+
+        fn populate(world: &mut World) -> EntityId {
+            let mut last = EntityId::NULL;
+
+            for mask in 0..32 {
+                let e = world.spawn();
+
+                if mask & 1 != 0 {
+                    world.insert(e, A);
+                }
+                if mask & 2 != 0 {
+                    world.insert(e, B);
+                }
+                if mask & 4 != 0 {
+                    world.insert(e, M::<0>);
+                }
+                if mask & 8 != 0 {
+                    world.insert(e, M::<1>);
+                }
+                if mask & 16 != 0 {
+                    world.insert(e, M::<2>);
+                }
+
+                last = e;
+            }
+
+            // Registered up front so component indices agree between worlds.
+            world.add_component::<X>();
+
+            last
+        }
+
+        fn add(world: &mut World, budget: Option<usize>) -> ([HandlerId; 2], Arc<AtomicUsize>) {
+            let seen = Arc::new(AtomicUsize::new(0));
+            let s = seen.clone();
+
+            let fetch = move |_: Receiver<E>, f: Fetcher<(&A, Not<&B>)>| {
+                s.fetch_add(f.iter().count(), Ordering::Relaxed);
+            };
+            let targeted = |_: Receiver<T, (&A, Not<&M<0>>)>| {};
+
+            let ids = match budget {
+                Some(budget) => [
+                    world.add_handler_incremental(fetch, budget),
+                    world.add_handler_incremental(targeted, budget),
+                ],
+                None => [world.add_handler(fetch), world.add_handler(targeted)],
+            };
+
+            (ids, seen)
+        }
+
+        let mut control = World::new();
+        let e = populate(&mut control);
+        let (control_ids, control_seen) = add(&mut control, None);
+
+        let mut world = World::new();
+        populate(&mut world);
+        let (ids, seen) = add(&mut world, Some(3));
+
+        assert!(control.handlers()[control_ids[0]].fully_matched());
+        assert!(!world.handlers()[ids[0]].fully_matched());
+        assert!(!world.handlers()[ids[1]].fully_matched());
+
+        world.send(E);
+        control.send(E);
+
+        // Only some of the archetypes are matched so far.
+        assert!(seen.load(Ordering::Relaxed) < control_seen.load(Ordering::Relaxed));
+
+        // Creates new archetypes while matching is in progress.
+        world.insert(e, X);
+        control.insert(e, X);
+
+        let mut cascades = 0;
+
+        while !world.handlers()[ids[0]].fully_matched() || !world.handlers()[ids[1]].fully_matched()
+        {
+            world.send(E);
+            cascades += 1;
+        }
+
+        assert!(cascades > 1);
+
+        seen.store(0, Ordering::Relaxed);
+        control_seen.store(0, Ordering::Relaxed);
+        world.send(E);
+        control.send(E);
+
+        assert_eq!(
+            seen.load(Ordering::Relaxed),
+            control_seen.load(Ordering::Relaxed)
+        );
+
+        assert_eq!(world.archetypes().len(), control.archetypes().len());
+
+        let handler = |world: &World, id: HandlerId| world.handlers()[id].ptr();
+        let EventIdx::Targeted(t) = world.handlers()[ids[1]].received_event().index() else {
+            unreachable!()
+        };
+
+        for arch in world.archetypes().iter() {
+            let control_arch = control
+                .archetypes()
+                .get_by_components(arch.component_indices())
+                .unwrap();
+
+            assert_eq!(
+                arch.has_refresh_listener(handler(&world, ids[0])),
+                control_arch.has_refresh_listener(handler(&control, control_ids[0]))
+            );
+
+            let listens = |arch: &Archetype, ptr| {
+                arch.handler_list_for(t)
+                    .is_some_and(|list| list.handlers().contains(&ptr))
+            };
+
+            assert_eq!(
+                listens(arch, handler(&world, ids[1])),
+                listens(control_arch, handler(&control, control_ids[1]))
+            );
+        }
+    }
+
+    /// Archetype transfers between every combination of the component kinds
+    /// below, in both the insert and remove directions.
+    mod transfer_matrix {
+        use core::mem::align_of;
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        use ::alloc::string::{String, ToString};
+        use ::alloc::sync::Arc;
+        #[cfg(not(feature = "std"))]
+        use ::alloc::vec::Vec;
+
+        use crate::prelude::*;
+
+        #[derive(Component, PartialEq, Debug)]
+        struct Zst;
+
+        #[derive(Component, PartialEq, Debug)]
+        #[repr(align(32))]
+        struct AlignedZst;
+
+        #[derive(Component, PartialEq, Debug)]
+        struct Byte(u8);
+
+        #[derive(Component, PartialEq, Debug)]
+        struct Word(u64);
+
+        #[derive(Component, PartialEq, Debug)]
+        #[repr(align(32))]
+        struct Simd([f32; 8]);
+
+        #[derive(Component, PartialEq, Debug)]
+        struct Str(String);
+
+        #[derive(Component)]
+        struct Counted(usize, Arc<AtomicUsize>);
+
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.1.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        const KINDS: u32 = 7;
+
+        fn insert(world: &mut World, e: EntityId, kind: u32, i: usize, drops: &Arc<AtomicUsize>) {
+            match kind {
+                0 => world.insert(e, Zst),
+                1 => world.insert(e, Byte(i as u8)),
+                2 => world.insert(e, Word(i as u64 * 0x0101_0101_0101)),
+                3 => world.insert(e, Simd([i as f32; 8])),
+                4 => world.insert(e, Str(i.to_string())),
+                5 => world.insert(e, Counted(i, drops.clone())),
+                6 => world.insert(e, AlignedZst),
+                _ => unreachable!(),
+            }
+        }
+
+        fn remove(world: &mut World, e: EntityId, kind: u32) {
+            match kind {
+                0 => world.remove::<Zst>(e),
+                1 => world.remove::<Byte>(e),
+                2 => world.remove::<Word>(e),
+                3 => world.remove::<Simd>(e),
+                4 => world.remove::<Str>(e),
+                5 => world.remove::<Counted>(e),
+                6 => world.remove::<AlignedZst>(e),
+                _ => unreachable!(),
+            }
+        }
+
+        fn check_ptr<C>(c: Option<&C>, present: bool) -> Option<&C> {
+            assert_eq!(c.is_some(), present);
+
+            if let Some(c) = c {
+                assert_eq!((c as *const C as usize) % align_of::<C>(), 0);
+            }
+
+            c
+        }
+
+        fn check(world: &World, e: EntityId, mask: u32, i: usize) {
+            let has = |kind: u32| mask & (1 << kind) != 0;
+
+            if let Some(c) = check_ptr(world.get::<Zst>(e), has(0)) {
+                assert_eq!(*c, Zst);
+            }
+            if let Some(c) = check_ptr(world.get::<Byte>(e), has(1)) {
+                assert_eq!(*c, Byte(i as u8));
+            }
+            if let Some(c) = check_ptr(world.get::<Word>(e), has(2)) {
+                assert_eq!(*c, Word(i as u64 * 0x0101_0101_0101));
+            }
+            if let Some(c) = check_ptr(world.get::<Simd>(e), has(3)) {
+                assert_eq!(*c, Simd([i as f32; 8]));
+            }
+            if let Some(c) = check_ptr(world.get::<Str>(e), has(4)) {
+                assert_eq!(c.0, i.to_string());
+            }
+            if let Some(c) = check_ptr(world.get::<Counted>(e), has(5)) {
+                assert_eq!(c.0, i);
+            }
+            if let Some(c) = check_ptr(world.get::<AlignedZst>(e), has(6)) {
+                assert_eq!(*c, AlignedZst);
+            }
+        }
+
+        /// Spawns `count` entities with the components in `mask`, toggles
+        /// `kind` on each of them, and checks every component afterwards.
+        fn run(mask: u32, kind: u32, count: usize) {
+            let mut world = World::new();
+            let drops = Arc::new(AtomicUsize::new(0));
+            let mut created = 0;
+
+            let entities: Vec<EntityId> = (0..count)
+                .map(|i| {
+                    let e = world.spawn();
+
+                    for k in (0..KINDS).filter(|k| mask & (1 << k) != 0) {
+                        insert(&mut world, e, k, i, &drops);
+                        created += usize::from(k == 5);
+                    }
+
+                    e
+                })
+                .collect();
+
+            let toggled = mask ^ (1 << kind);
+
+            for (i, &e) in entities.iter().enumerate() {
+                if toggled & (1 << kind) != 0 {
+                    insert(&mut world, e, kind, i, &drops);
+                    created += usize::from(kind == 5);
+                } else {
+                    remove(&mut world, e, kind);
+                }
+            }
+
+            let live = if toggled & (1 << 5) != 0 { count } else { 0 };
+            assert_eq!(drops.load(Ordering::Relaxed), created - live);
+
+            for (i, &e) in entities.iter().enumerate() {
+                check(&world, e, toggled, i);
+            }
+
+            // Move everything back to check the reverse direction.
+            for (i, &e) in entities.iter().enumerate() {
+                if mask & (1 << kind) != 0 {
+                    insert(&mut world, e, kind, i, &drops);
+                    created += usize::from(kind == 5);
+                } else {
+                    remove(&mut world, e, kind);
+                }
+            }
+
+            for (i, &e) in entities.iter().enumerate() {
+                check(&world, e, mask, i);
+            }
+
+            drop(world);
+            assert_eq!(drops.load(Ordering::Relaxed), created);
+        }
+
+        macro_rules! matrix {
+            ($($name:ident: $count:expr),* $(,)?) => {
+                $(
+                    #[test]
+                    fn $name() {
+                        for mask in 0..1 << KINDS {
+                            for kind in 0..KINDS {
+                                run(mask, kind, $count);
+                            }
+                        }
+                    }
+                )*
+            };
+        }
+
+        matrix! {
+            zero_entities: 0,
+            one_entity: 1,
+            two_entities: 2,
+            past_growth_boundary: 33,
+        }
+    }
 }
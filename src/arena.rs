@@ -0,0 +1,143 @@
+//! A bump allocator for the columns of a world created with
+//! [`World::new_in_arena`].
+//!
+//! [`World::new_in_arena`]: crate::world::World::new_in_arena
+
+use alloc::alloc;
+use core::alloc::Layout;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Alignment of the arena's buffer. Allocations with a larger alignment are
+/// still placed correctly, but may waste more space.
+const ARENA_ALIGN: usize = 64;
+
+/// A fixed-size region of memory which is handed out in increasing order.
+/// Memory is never reused. Freed and reallocated blocks are abandoned in place
+/// until the arena itself is dropped.
+///
+/// Every column using the arena holds an `Arc` to it, so the buffer outlives
+/// all columns regardless of the order in which they are dropped.
+#[derive(Debug)]
+pub(crate) struct Arena {
+    base: NonNull<u8>,
+    capacity: usize,
+    /// Offset of the first unused byte.
+    offset: AtomicUsize,
+    /// Bytes of blocks which were handed out and later freed.
+    abandoned: AtomicUsize,
+    /// Bytes currently allocated from the global allocator because the arena
+    /// was full.
+    fallback_bytes: AtomicUsize,
+    /// Number of allocations which did not fit in the arena.
+    fallback_count: AtomicUsize,
+}
+
+// SAFETY: The buffer is owned by the arena, and all bookkeeping is atomic.
+unsafe impl Send for Arena {}
+unsafe impl Sync for Arena {}
+
+impl Arena {
+    pub(crate) fn new(capacity: usize) -> Self {
+        let base = if capacity == 0 {
+            NonNull::<u8>::dangling()
+        } else {
+            let layout = Layout::from_size_align(capacity, ARENA_ALIGN).expect("arena too large");
+
+            // SAFETY: The size of the layout is nonzero.
+            match NonNull::new(unsafe { alloc::alloc(layout) }) {
+                Some(ptr) => ptr,
+                None => alloc::handle_alloc_error(layout),
+            }
+        };
+
+        Self {
+            base,
+            capacity,
+            offset: AtomicUsize::new(0),
+            abandoned: AtomicUsize::new(0),
+            fallback_bytes: AtomicUsize::new(0),
+            fallback_count: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns a block of memory fitting `layout` from the arena, or `None` if
+    /// the arena is full.
+    pub(crate) fn alloc(&self, layout: Layout) -> Option<NonNull<u8>> {
+        let base = self.base.as_ptr() as usize;
+        let mut start = 0;
+
+        self.offset
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |offset| {
+                let addr = base.checked_add(offset)?;
+                let aligned = addr.checked_add(layout.align() - 1)? & !(layout.align() - 1);
+                start = aligned - base;
+
+                let end = start.checked_add(layout.size())?;
+                (end <= self.capacity).then_some(end)
+            })
+            .ok()?;
+
+        // SAFETY: `start` is within the arena's buffer.
+        Some(unsafe { NonNull::new_unchecked(self.base.as_ptr().add(start)) })
+    }
+
+    /// Returns `true` if `ptr` points into the arena's buffer.
+    pub(crate) fn contains(&self, ptr: NonNull<u8>) -> bool {
+        let base = self.base.as_ptr() as usize;
+        let addr = ptr.as_ptr() as usize;
+
+        addr >= base && addr < base + self.capacity
+    }
+
+    /// Records that a block of `size` bytes from the arena is no longer used.
+    pub(crate) fn abandon(&self, size: usize) {
+        self.abandoned.fetch_add(size, Ordering::Relaxed);
+    }
+
+    /// Records an allocation of `size` bytes from the global allocator made
+    /// because the arena was full.
+    pub(crate) fn add_fallback(&self, size: usize) {
+        self.fallback_bytes.fetch_add(size, Ordering::Relaxed);
+        self.fallback_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a fallback allocation of `size` bytes was freed.
+    pub(crate) fn remove_fallback(&self, size: usize) {
+        self.fallback_bytes.fetch_sub(size, Ordering::Relaxed);
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub(crate) fn used(&self) -> usize {
+        self.offset.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn abandoned(&self) -> usize {
+        self.abandoned.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn fallback_bytes(&self) -> usize {
+        self.fallback_bytes.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn fallback_count(&self) -> usize {
+        self.fallback_count.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        if self.capacity > 0 {
+            // SAFETY: The buffer was allocated in `new` with this layout.
+            unsafe {
+                alloc::dealloc(
+                    self.base.as_ptr(),
+                    Layout::from_size_align_unchecked(self.capacity, ARENA_ALIGN),
+                )
+            };
+        }
+    }
+}
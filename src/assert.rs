@@ -189,6 +189,15 @@ impl<E: Event> AssertMutable<E> {
     );
 }
 
+pub(crate) struct AssertDoubleBuffered<C>(PhantomData<C>);
+
+impl<C: Component> AssertDoubleBuffered<C> {
+    pub(crate) const COMPONENT: () = assert!(
+        C::DOUBLE_BUFFERED.is_some(),
+        "component is not double-buffered (see `Component::DOUBLE_BUFFERED`)."
+    );
+}
+
 pub(crate) struct AssertUntargetedEvent<E>(PhantomData<E>);
 
 impl<E: Event> AssertUntargetedEvent<E> {
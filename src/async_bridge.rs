@@ -0,0 +1,247 @@
+//! Delivering the results of [`Future`]s to a [`World`] as events.
+//!
+//! See [`World::spawn_future`] for more information.
+
+use alloc::boxed::Box;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::future::Future;
+use core::panic::{RefUnwindSafe, UnwindSafe};
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::{fmt, mem};
+use std::sync::{Mutex, PoisonError};
+
+use crate::event::Event;
+use crate::world::World;
+
+/// A type-erased future passed to the spawner registered with
+/// [`World::set_future_spawner`].
+pub type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// Handle to a future started with [`World::spawn_future`].
+///
+/// Dropping the handle cancels delivery of the event, even if the future has
+/// already completed. The future itself continues to run on the executor
+/// unless the executor drops it. Use [`TaskHandle::detach`] to deliver the
+/// event without keeping the handle around.
+#[must_use = "dropping the handle cancels the event"]
+pub struct TaskHandle {
+    cancelled: Arc<AtomicBool>,
+    detached: bool,
+}
+
+impl TaskHandle {
+    /// Lets the event be delivered without holding on to the handle.
+    pub fn detach(mut self) {
+        self.detached = true;
+    }
+
+    /// Cancels delivery of the event. This is equivalent to dropping the
+    /// handle.
+    pub fn cancel(self) {}
+}
+
+impl Drop for TaskHandle {
+    fn drop(&mut self) {
+        if !self.detached {
+            self.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+impl fmt::Debug for TaskHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskHandle")
+            .field("cancelled", &self.cancelled.load(Ordering::Relaxed))
+            .field("detached", &self.detached)
+            .finish()
+    }
+}
+
+/// Sends an event to the world.
+pub(crate) type SendFn = Box<dyn FnOnce(&mut World) + Send>;
+
+/// An event produced by a completed future, waiting to be sent.
+struct Staged {
+    cancelled: Arc<AtomicBool>,
+    send: SendFn,
+}
+
+type Ingress = Mutex<Vec<Staged>>;
+
+/// Per-world state for [`World::spawn_future`].
+#[derive(Default)]
+pub(crate) struct AsyncBridge {
+    spawner: Option<Box<dyn Fn(BoxFuture) + Send + Sync>>,
+    /// Events from completed futures. Futures only hold a weak reference so
+    /// that nothing is staged after the world is dropped.
+    ingress: Arc<Ingress>,
+}
+
+impl AsyncBridge {
+    pub(crate) fn set_spawner(&mut self, spawner: Box<dyn Fn(BoxFuture) + Send + Sync>) {
+        self.spawner = Some(spawner);
+    }
+
+    pub(crate) fn spawn<F, M, E>(&self, future: F, map: M) -> TaskHandle
+    where
+        F: Future + Send + 'static,
+        M: FnOnce(F::Output) -> E + Send + 'static,
+        E: Event,
+    {
+        let Some(spawner) = &self.spawner else {
+            panic!("no future spawner was registered with `World::set_future_spawner`")
+        };
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let ingress: Weak<Ingress> = Arc::downgrade(&self.ingress);
+
+        let task_cancelled = cancelled.clone();
+
+        spawner(Box::pin(async move {
+            let output = future.await;
+
+            if task_cancelled.load(Ordering::Relaxed) {
+                return;
+            }
+
+            let event = map(output);
+
+            if let Some(ingress) = ingress.upgrade() {
+                ingress
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push(Staged {
+                        cancelled: task_cancelled,
+                        send: Box::new(move |world| world.send(event)),
+                    });
+            }
+        }));
+
+        TaskHandle {
+            cancelled,
+            detached: false,
+        }
+    }
+
+    /// Returns the number of staged events which have not been cancelled.
+    pub(crate) fn completed_count(&self) -> usize {
+        self.ingress
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .filter(|s| !s.cancelled.load(Ordering::Relaxed))
+            .count()
+    }
+
+    /// Removes all staged events from the ingress queue.
+    pub(crate) fn take_completed(&self) -> Vec<SendFn> {
+        let staged = mem::take(&mut *self.ingress.lock().unwrap_or_else(PoisonError::into_inner));
+
+        staged
+            .into_iter()
+            .filter(|s| !s.cancelled.load(Ordering::Relaxed))
+            .map(|s| s.send)
+            .collect()
+    }
+}
+
+impl fmt::Debug for AsyncBridge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncBridge")
+            .field("has_spawner", &self.spawner.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl UnwindSafe for AsyncBridge {}
+impl RefUnwindSafe for AsyncBridge {}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+    use std::sync::Mutex;
+
+    use futures_executor::block_on;
+
+    use super::BoxFuture;
+    use crate::prelude::*;
+
+    #[derive(Event)]
+    struct Loaded(u32);
+
+    #[derive(Component)]
+    struct Log(Vec<u32>);
+
+    /// Futures handed to the spawner, run on demand with [`run_all`].
+    type Tasks = Arc<Mutex<Vec<BoxFuture>>>;
+
+    fn run_all(tasks: &Tasks) {
+        for task in tasks.lock().unwrap().drain(..) {
+            block_on(task);
+        }
+    }
+
+    fn setup() -> (World, EntityId, Tasks) {
+        let tasks = Tasks::default();
+
+        let mut world = World::new();
+
+        let spawned = tasks.clone();
+        world.set_future_spawner(move |fut| spawned.lock().unwrap().push(fut));
+
+        world.add_handler(|r: Receiver<Loaded>, Single(log): Single<&mut Log>| {
+            log.0.push(r.event.0);
+        });
+
+        let log = world.spawn();
+        world.insert(log, Log(vec![]));
+
+        (world, log, tasks)
+    }
+
+    #[test]
+    fn completion() {
+        let (mut world, log, tasks) = setup();
+
+        world.spawn_future(async { 20 }, |n| Loaded(n + 1)).detach();
+        let _handle = world.spawn_future(async { 30 }, Loaded);
+
+        assert_eq!(world.poll_completed(), 0);
+
+        run_all(&tasks);
+        assert!(world.get::<Log>(log).unwrap().0.is_empty());
+
+        assert_eq!(world.poll_completed(), 2);
+        assert_eq!(world.get::<Log>(log).unwrap().0, [21, 30]);
+    }
+
+    #[test]
+    fn cancellation() {
+        let (mut world, log, tasks) = setup();
+
+        // Cancelled before completion.
+        world.spawn_future(async { 1 }, Loaded).cancel();
+
+        // Cancelled after completion, but before the event is delivered.
+        let handle = world.spawn_future(async { 2 }, Loaded);
+        run_all(&tasks);
+        drop(handle);
+
+        assert_eq!(world.poll_completed(), 0);
+        assert!(world.get::<Log>(log).unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn world_dropped_first() {
+        let (mut world, _, tasks) = setup();
+
+        let handle = world.spawn_future(async { 1 }, Loaded);
+        drop(world);
+
+        // The future completes without a world to deliver to.
+        run_all(&tasks);
+        handle.detach();
+    }
+}
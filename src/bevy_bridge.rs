@@ -0,0 +1,408 @@
+//! Copying entities and components from a [`bevy_ecs`] world.
+//!
+//! This is intended for projects migrating from `bevy_ecs` which need both
+//! libraries to run side by side for a while. Register conversions for the
+//! component types to carry over with [`BevyBridge::map`], copy everything
+//! once with [`BevyBridge::import`], and keep selected components up to date
+//! with [`BevyBridge::sync`].
+//!
+//! ```
+//! use bevy_ecs::component::Component as BevyComponent;
+//! use evenio::bevy_bridge::BevyBridge;
+//! use evenio::prelude::*;
+//!
+//! #[derive(BevyComponent)]
+//! struct OldHealth(u32);
+//!
+//! #[derive(Component)]
+//! struct Health(u32);
+//!
+//! let mut bevy_world = bevy_ecs::world::World::new();
+//! let old = bevy_world.spawn(OldHealth(10)).id();
+//!
+//! let mut world = World::new();
+//!
+//! let mut bridge = BevyBridge::new();
+//! bridge.sync_map::<OldHealth, Health, _>(|h| Health(h.0));
+//!
+//! let map = bridge.import(&mut bevy_world, &mut world);
+//! let new = map.get(old).unwrap();
+//! assert_eq!(world.get::<Health>(new).unwrap().0, 10);
+//!
+//! bevy_world.get_mut::<OldHealth>(old).unwrap().0 = 5;
+//!
+//! bridge.sync(&mut bevy_world, &mut world);
+//! assert_eq!(world.get::<Health>(new).unwrap().0, 5);
+//! ```
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+
+use bevy_ecs::change_detection::DetectChanges;
+use bevy_ecs::component::{Component as BevyComponent, Tick};
+use bevy_ecs::entity::Entity;
+use bevy_ecs::world::{Ref, World as BevyWorld};
+
+use crate::component::Component;
+use crate::entity::EntityId;
+use crate::map::HashMap;
+use crate::world::World;
+
+/// Copies a component from every entity of a bevy world. When a range of
+/// ticks is given, only components changed in that range are copied.
+type CopyFn = dyn FnMut(&mut BevyWorld, &mut World, &EntityMap, Option<(Tick, Tick)>);
+
+struct Mapping {
+    copy: Box<CopyFn>,
+    /// Whether the mapping is kept up to date by [`BevyBridge::sync`].
+    sync: bool,
+}
+
+/// Registry of component conversions from a bevy world into an evenio
+/// [`World`]. See the [module documentation](self) for an example.
+pub struct BevyBridge {
+    mappings: Vec<Mapping>,
+    entities: EntityMap,
+    /// Change tick of the bevy world as of the last import or sync.
+    last_run: Option<Tick>,
+}
+
+impl BevyBridge {
+    /// Creates a bridge without any component conversions.
+    pub fn new() -> Self {
+        Self {
+            mappings: vec![],
+            entities: EntityMap::default(),
+            last_run: None,
+        }
+    }
+
+    /// Registers a conversion from the bevy component `B` to the component
+    /// `C`. Mapped components are copied by [`import`](Self::import).
+    pub fn map<B, C, F>(&mut self, f: F) -> &mut Self
+    where
+        B: BevyComponent,
+        C: Component,
+        F: Fn(&B) -> C + 'static,
+    {
+        self.add_mapping(f, false)
+    }
+
+    /// Like [`map`](Self::map), but the component is also copied again by
+    /// [`sync`](Self::sync) whenever it changes on the bevy side.
+    pub fn sync_map<B, C, F>(&mut self, f: F) -> &mut Self
+    where
+        B: BevyComponent,
+        C: Component,
+        F: Fn(&B) -> C + 'static,
+    {
+        self.add_mapping(f, true)
+    }
+
+    fn add_mapping<B, C, F>(&mut self, f: F, sync: bool) -> &mut Self
+    where
+        B: BevyComponent,
+        C: Component,
+        F: Fn(&B) -> C + 'static,
+    {
+        self.mappings.push(Mapping {
+            copy: Box::new(move |bevy_world, world, map, ticks| {
+                let mut query = bevy_world.query::<(Entity, Ref<B>)>();
+
+                world.send_many(|mut sender| {
+                    for (entity, component) in query.iter(bevy_world) {
+                        if let Some((last_run, this_run)) = ticks {
+                            if !component.last_changed().is_newer_than(last_run, this_run) {
+                                continue;
+                            }
+                        }
+
+                        if let Some(id) = map.get(entity) {
+                            sender.insert(id, f(&component));
+                        }
+                    }
+                });
+            }),
+            sync,
+        });
+
+        self
+    }
+
+    /// Spawns an entity in `world` for every entity in `bevy_world` and copies
+    /// all mapped components. Returns the correspondence between the entities
+    /// of the two worlds.
+    ///
+    /// Entities which were already imported by this bridge are not spawned
+    /// again, but their mapped components are copied.
+    pub fn import(&mut self, bevy_world: &mut BevyWorld, world: &mut World) -> &EntityMap {
+        self.spawn_missing(bevy_world, world);
+
+        let this_run = bevy_world.increment_change_tick();
+
+        for mapping in &mut self.mappings {
+            (mapping.copy)(bevy_world, world, &self.entities, None);
+        }
+
+        self.last_run = Some(this_run);
+
+        &self.entities
+    }
+
+    /// Copies the components registered with [`sync_map`](Self::sync_map)
+    /// which changed in `bevy_world` since the last import or sync. Entities
+    /// spawned in `bevy_world` since then are spawned in `world` as well.
+    ///
+    /// This is meant to be called once per frame, after the bevy schedule
+    /// has run.
+    pub fn sync(&mut self, bevy_world: &mut BevyWorld, world: &mut World) -> &EntityMap {
+        let Some(last_run) = self.last_run else {
+            return self.import(bevy_world, world);
+        };
+
+        let spawned = self.spawn_missing(bevy_world, world);
+
+        let this_run = bevy_world.increment_change_tick();
+
+        for mapping in &mut self.mappings {
+            if mapping.sync {
+                (mapping.copy)(
+                    bevy_world,
+                    world,
+                    &self.entities,
+                    Some((last_run, this_run)),
+                );
+            } else if !spawned.is_empty() {
+                // Entities spawned since the last run still need all of their
+                // mapped components.
+                (mapping.copy)(bevy_world, world, &spawned, None);
+            }
+        }
+
+        self.last_run = Some(this_run);
+
+        &self.entities
+    }
+
+    /// Returns the correspondence between the entities of the two worlds.
+    pub fn entities(&self) -> &EntityMap {
+        &self.entities
+    }
+
+    /// Spawns an entity for every bevy entity which doesn't have one yet and
+    /// returns the newly spawned entities.
+    fn spawn_missing(&mut self, bevy_world: &mut BevyWorld, world: &mut World) -> EntityMap {
+        let missing: Vec<Entity> = bevy_world
+            .iter_entities()
+            .map(|entity| entity.id())
+            .filter(|&entity| self.entities.get(entity).is_none())
+            .collect();
+
+        let mut spawned = EntityMap::default();
+
+        world.send_many(|mut sender| {
+            for entity in missing {
+                let id = sender.spawn();
+                self.entities.insert(entity, id);
+                spawned.insert(entity, id);
+            }
+        });
+
+        spawned
+    }
+}
+
+impl Default for BevyBridge {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for BevyBridge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BevyBridge")
+            .field("mappings", &self.mappings.len())
+            .field("entities", &self.entities)
+            .field("last_run", &self.last_run)
+            .finish()
+    }
+}
+
+/// Correspondence between bevy entities and the [`EntityId`]s they were
+/// imported as.
+#[derive(Clone, Default, Debug)]
+pub struct EntityMap {
+    to_evenio: HashMap<Entity, EntityId>,
+}
+
+impl EntityMap {
+    /// Returns the entity which `entity` was imported as, if any.
+    pub fn get(&self, entity: Entity) -> Option<EntityId> {
+        self.to_evenio.get(&entity).copied()
+    }
+
+    /// Returns the bevy entity which `id` was imported from, if any.
+    ///
+    /// This is a linear search.
+    pub fn get_bevy(&self, id: EntityId) -> Option<Entity> {
+        self.to_evenio
+            .iter()
+            .find_map(|(&entity, &other)| (other == id).then_some(entity))
+    }
+
+    /// Returns the number of imported entities.
+    pub fn len(&self) -> usize {
+        self.to_evenio.len()
+    }
+
+    /// Returns `true` if no entities were imported.
+    pub fn is_empty(&self) -> bool {
+        self.to_evenio.is_empty()
+    }
+
+    /// Returns an iterator over all pairs of bevy entities and the entities
+    /// they were imported as, in an arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, EntityId)> + '_ {
+        self.to_evenio.iter().map(|(&entity, &id)| (entity, id))
+    }
+
+    fn insert(&mut self, entity: Entity, id: EntityId) {
+        self.to_evenio.insert(entity, id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_ecs::component::Component as BevyComponent;
+
+    use super::*;
+
+    mod old {
+        use super::*;
+
+        #[derive(BevyComponent)]
+        pub(super) struct Position(pub(super) f32, pub(super) f32);
+
+        #[derive(BevyComponent)]
+        pub(super) struct Health(pub(super) u32);
+
+        #[derive(BevyComponent)]
+        pub(super) struct Name(pub(super) String);
+
+        #[derive(BevyComponent)]
+        pub(super) struct Unmapped;
+    }
+
+    #[derive(crate::component::Component, PartialEq, Debug)]
+    struct Position(f32, f32);
+
+    #[derive(crate::component::Component, PartialEq, Debug)]
+    struct Health(u32);
+
+    #[derive(crate::component::Component, PartialEq, Debug)]
+    struct Name(String);
+
+    fn bridge() -> BevyBridge {
+        let mut bridge = BevyBridge::new();
+
+        bridge
+            .map::<old::Position, _, _>(|p| Position(p.0, p.1))
+            .map::<old::Name, _, _>(|n| Name(n.0.clone()))
+            .sync_map::<old::Health, _, _>(|h| Health(h.0));
+
+        bridge
+    }
+
+    #[test]
+    fn round_trip() {
+        const COUNT: u32 = 10_000;
+
+        let mut bevy_world = BevyWorld::new();
+
+        let old_entities: Vec<Entity> = (0..COUNT)
+            .map(|i| {
+                let mut e = bevy_world.spawn(old::Position(i as f32, -(i as f32)));
+
+                if i.is_multiple_of(2) {
+                    e.insert(old::Health(i));
+                }
+                if i.is_multiple_of(3) {
+                    e.insert((old::Name(format!("e{i}")), old::Unmapped));
+                }
+
+                e.id()
+            })
+            .collect();
+
+        let mut world = World::new();
+        let mut bridge = bridge();
+
+        let map = bridge.import(&mut bevy_world, &mut world).clone();
+        assert_eq!(map.len(), COUNT as usize);
+        assert_eq!(world.entities().len(), COUNT);
+
+        for (i, &old) in old_entities.iter().enumerate() {
+            let i = i as u32;
+            let id = map.get(old).unwrap();
+
+            assert_eq!(map.get_bevy(id), Some(old));
+            assert_eq!(
+                world.get::<Position>(id),
+                Some(&Position(i as f32, -(i as f32)))
+            );
+            assert_eq!(
+                world.get::<Health>(id),
+                i.is_multiple_of(2).then_some(&Health(i))
+            );
+            assert_eq!(
+                world.get::<Name>(id).map(|n| n.0.as_str()),
+                i.is_multiple_of(3).then(|| format!("e{i}")).as_deref()
+            );
+        }
+
+        // Only synced components are copied again.
+        for &old in &old_entities[..10] {
+            bevy_world.get_mut::<old::Position>(old).unwrap().0 = 1000.0;
+
+            if let Some(mut health) = bevy_world.get_mut::<old::Health>(old) {
+                health.0 += 1;
+            }
+        }
+
+        let added = bevy_world.spawn(old::Position(1.0, 2.0)).id();
+
+        let map = bridge.sync(&mut bevy_world, &mut world);
+        assert_eq!(map.len(), COUNT as usize + 1);
+
+        let first = map.get(old_entities[0]).unwrap();
+        assert_eq!(world.get::<Position>(first), Some(&Position(0.0, 0.0)));
+        assert_eq!(world.get::<Health>(first), Some(&Health(1)));
+
+        let untouched = map.get(old_entities[10]).unwrap();
+        assert_eq!(world.get::<Health>(untouched), Some(&Health(10)));
+
+        let added = map.get(added).unwrap();
+        assert_eq!(world.get::<Position>(added), Some(&Position(1.0, 2.0)));
+    }
+
+    #[test]
+    fn sync_skips_unchanged() {
+        let mut bevy_world = BevyWorld::new();
+        let old = bevy_world.spawn(old::Health(1)).id();
+
+        let mut world = World::new();
+        let mut bridge = bridge();
+
+        let id = bridge.import(&mut bevy_world, &mut world).get(old).unwrap();
+
+        // Changes on the evenio side survive a sync when bevy didn't change.
+        world.insert(id, Health(50));
+        bridge.sync(&mut bevy_world, &mut world);
+        assert_eq!(world.get::<Health>(id), Some(&Health(50)));
+
+        bevy_world.get_mut::<old::Health>(old).unwrap().0 = 2;
+        bridge.sync(&mut bevy_world, &mut world);
+        assert_eq!(world.get::<Health>(id), Some(&Health(2)));
+    }
+}
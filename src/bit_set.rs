@@ -1,19 +1,37 @@
 //! The [`BitSet`], a set backed by a vector of bits.
+//!
+//! # Performance
+//!
+//! Bit sets are used for the component sets of archetypes and for the access
+//! expressions of handlers, so archetype matching and conflict checks are
+//! linear in the largest component index involved.
+//!
+//! Sets whose elements are all below [`INLINE_BITS`] store their bits inline
+//! and never allocate. Sets with larger elements spill to a heap allocation,
+//! which is slower to create and clone but otherwise behaves identically.
+//! Inline and spilled sets can be freely combined. A world which registers
+//! more than [`INLINE_BITS`] components will have spilled sets on its hot
+//! paths. Enabling the `wide-bit-set` feature raises [`INLINE_BITS`] from 256
+//! to 1024 at the cost of a larger [`BitSet`].
 
 #[cfg(not(feature = "std"))]
-use alloc::{vec, vec::Vec};
+use alloc::vec::Vec;
 use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
 use core::iter::FusedIterator;
 use core::marker::PhantomData;
-use core::ops::{BitOr, BitOrAssign};
+use core::ops::{BitOr, BitOrAssign, Deref, DerefMut};
 use core::{any, fmt};
 
 use crate::assert::GetDebugChecked;
 use crate::sparse::SparseIndex;
 
 /// A set data structure backed by a vector of bits.
+///
+/// Elements below [`INLINE_BITS`] are stored inline. See the [module
+/// documentation](self) for details.
 pub struct BitSet<T = usize> {
-    blocks: Vec<Block>,
+    blocks: Blocks,
     _marker: PhantomData<T>,
 }
 
@@ -26,19 +44,134 @@ impl<T> Clone for BitSet<T> {
     }
 }
 
-type Block = usize;
+/// The block type is fixed rather than `usize` so that iteration order and
+/// [`Ord`] are the same on every target.
+type Block = u64;
 /// Number of bits in a block.
 const BITS: usize = Block::BITS as usize;
 
+/// Number of blocks stored inline before a [`BitSet`] spills to the heap.
+#[cfg(not(feature = "wide-bit-set"))]
+const INLINE_BLOCKS: usize = 4;
+#[cfg(feature = "wide-bit-set")]
+const INLINE_BLOCKS: usize = 16;
+
+/// The number of elements a [`BitSet`] can hold without allocating. Elements
+/// at or above this value cause the set to spill to the heap.
+///
+/// This is 256 by default and 1024 with the `wide-bit-set` feature.
+pub const INLINE_BITS: usize = INLINE_BLOCKS * BITS;
+
+/// Block storage of a [`BitSet`]. Blocks past the length of the inline array
+/// are always zero.
+#[derive(Clone)]
+enum Blocks {
+    Inline {
+        len: usize,
+        array: [Block; INLINE_BLOCKS],
+    },
+    Heap(Vec<Block>),
+}
+
+impl Blocks {
+    const fn new() -> Self {
+        Self::Inline {
+            len: 0,
+            array: [0; INLINE_BLOCKS],
+        }
+    }
+
+    /// Resizes to `new_len` blocks, filling new blocks with zeros.
+    fn resize(&mut self, new_len: usize) {
+        match self {
+            Self::Inline { len, array } => {
+                if new_len <= INLINE_BLOCKS {
+                    if new_len < *len {
+                        array[new_len..*len].fill(0);
+                    }
+                    *len = new_len;
+                } else {
+                    let mut vec = Vec::with_capacity(new_len);
+                    vec.extend_from_slice(&array[..*len]);
+                    vec.resize(new_len, 0);
+                    *self = Self::Heap(vec);
+                }
+            }
+            Self::Heap(vec) => vec.resize(new_len, 0),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.resize(0);
+    }
+
+    /// Removes trailing zero blocks and moves spilled blocks back inline if
+    /// they fit.
+    fn shrink_to_fit(&mut self) {
+        let len = self.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+
+        match self {
+            Self::Inline { .. } => self.resize(len),
+            Self::Heap(vec) => {
+                if len <= INLINE_BLOCKS {
+                    let mut array = [0; INLINE_BLOCKS];
+                    array[..len].copy_from_slice(&vec[..len]);
+                    *self = Self::Inline { len, array };
+                } else {
+                    vec.truncate(len);
+                    vec.shrink_to_fit();
+                }
+            }
+        }
+    }
+}
+
+impl Deref for Blocks {
+    type Target = [Block];
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        match self {
+            // SAFETY: `len` never exceeds the length of the array.
+            Self::Inline { len, array } => unsafe { array.get_debug_checked(..*len) },
+            Self::Heap(vec) => vec,
+        }
+    }
+}
+
+impl DerefMut for Blocks {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            // SAFETY: `len` never exceeds the length of the array.
+            Self::Inline { len, array } => unsafe { array.get_debug_checked_mut(..*len) },
+            Self::Heap(vec) => vec,
+        }
+    }
+}
+
+impl fmt::Debug for Blocks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
 impl<T> BitSet<T> {
     /// Create a new, empty bit set.
     pub const fn new() -> Self {
         Self {
-            blocks: vec![],
+            blocks: Blocks::new(),
             _marker: PhantomData,
         }
     }
 
+    /// Returns `true` if the set has spilled to the heap, which happens once
+    /// an element at or above [`INLINE_BITS`] is inserted.
+    #[must_use]
+    pub fn spilled(&self) -> bool {
+        matches!(self.blocks, Blocks::Heap(_))
+    }
+
     /// Clears the set, removing all elements.
     pub fn clear(&mut self) {
         self.blocks.clear();
@@ -55,7 +188,7 @@ impl<T> BitSet<T> {
                 "reached maximum block count in {}",
                 any::type_name::<Self>()
             );
-            self.blocks.resize(block_idx + 1, 0);
+            self.blocks.resize(block_idx + 1);
         }
 
         // SAFETY: Block index is in bounds due to check above.
@@ -71,6 +204,38 @@ impl<T> BitSet<T> {
             .all(|(a, b)| a & b == 0)
     }
 
+    /// Returns `true` if every element of `self` is also in `other`.
+    #[must_use]
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.blocks.iter().enumerate().all(|(i, &a)| {
+            let b = other.blocks.get(i).copied().unwrap_or(0);
+            a & !b == 0
+        })
+    }
+
+    /// Returns the number of elements in both `self` and `other`, without
+    /// allocating.
+    #[must_use]
+    pub fn intersection_len(&self, other: &Self) -> usize {
+        self.blocks
+            .iter()
+            .zip(other.blocks.iter())
+            .map(|(a, b)| (a & b).count_ones() as usize)
+            .sum()
+    }
+
+    /// Modifies `self` to contain the elements which are in either `self` or
+    /// `other`, but not both.
+    pub fn symmetric_difference_with(&mut self, other: &Self) {
+        if self.blocks.len() < other.blocks.len() {
+            self.blocks.resize(other.blocks.len());
+        }
+
+        for (a, b) in self.blocks.iter_mut().zip(other.blocks.iter()) {
+            *a ^= *b;
+        }
+    }
+
     /// Returns the number of elements in the set.
     #[must_use]
     pub fn len(&self) -> usize {
@@ -133,11 +298,31 @@ impl<T: SparseIndex> BitSet<T> {
 
         self.blocks
             .get(block)
-            .map_or(false, |&block| (block >> bit) & 1 == 1)
+            .is_some_and(|&block| (block >> bit) & 1 == 1)
+    }
+
+    /// Retains only the elements for which `f` returns `true`. Elements are
+    /// visited in ascending order.
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(T) -> bool,
+    {
+        for (block_idx, block) in self.blocks.iter_mut().enumerate() {
+            let mut bits = *block;
+
+            while bits != 0 {
+                let zeros = bits.trailing_zeros() as usize;
+                bits ^= 1 << zeros;
+
+                if !f(T::from_index(block_idx * BITS + zeros)) {
+                    *block &= !(1 << zeros);
+                }
+            }
+        }
     }
 
     /// Returns an iterator over the element in the set in ascending order.
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
         Iter {
             bits: self.blocks.first().copied().unwrap_or(0),
             block_idx: 0,
@@ -146,17 +331,9 @@ impl<T: SparseIndex> BitSet<T> {
         }
     }
 
-    /// Shrinks the capacity of the set as much as possible.
+    /// Shrinks the capacity of the set as much as possible. A spilled set is
+    /// moved back inline if its elements are all below [`INLINE_BITS`].
     pub fn shrink_to_fit(&mut self) {
-        while let Some(&last) = self.blocks.last() {
-            if last != 0 {
-                // There are bits in this block.
-                break;
-            }
-
-            self.blocks.pop();
-        }
-
         self.blocks.shrink_to_fit();
     }
 }
@@ -164,7 +341,7 @@ impl<T: SparseIndex> BitSet<T> {
 impl<T> BitOrAssign<&Self> for BitSet<T> {
     fn bitor_assign(&mut self, other: &Self) {
         if self.blocks.len() < other.blocks.len() {
-            self.blocks.resize(other.blocks.len(), 0);
+            self.blocks.resize(other.blocks.len());
         }
 
         for (a, b) in self.blocks.iter_mut().zip(other.blocks.iter()) {
@@ -184,10 +361,7 @@ impl<T> BitOr<&Self> for BitSet<T> {
 
 impl<T> Default for BitSet<T> {
     fn default() -> Self {
-        Self {
-            blocks: vec![],
-            _marker: PhantomData,
-        }
+        Self::new()
     }
 }
 
@@ -316,6 +490,20 @@ impl<T: SparseIndex> PartialEq for BitSet<T> {
 
 impl<T: SparseIndex> Eq for BitSet<T> {}
 
+impl<T: SparseIndex> Hash for BitSet<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // Trailing zero blocks are ignored by `Eq`, so they must be ignored
+        // here too.
+        let len = self
+            .blocks
+            .iter()
+            .rposition(|&b| b != 0)
+            .map_or(0, |i| i + 1);
+
+        self.blocks[..len].hash(state);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -383,4 +571,160 @@ mod tests {
         assert!(iter.next().is_none());
         assert!(iter.next().is_none());
     }
+
+    #[test]
+    fn platform_independent_blocks() {
+        assert_eq!(BITS, 64);
+
+        // Indices around the boundaries of both 32 and 64-bit blocks.
+        let indices = [0_usize, 31, 32, 33, 63, 64, 65, 95, 96, 127, 128];
+
+        let set = BitSet::from_iter(indices);
+        assert_eq!(set.iter().collect::<Vec<_>>(), indices);
+
+        for idx in indices {
+            assert!(set.contains(idx));
+            assert!(!set.contains(idx + 200));
+        }
+
+        // Bit 32 shares a block with bit 0, so this would compare the other
+        // way with 32-bit blocks.
+        assert!(BitSet::<u32>::from_iter([32]) > BitSet::from_iter([0]));
+        assert!(BitSet::<u32>::from_iter([64]) < BitSet::from_iter([0]));
+    }
+
+    #[test]
+    fn spill_and_shrink() {
+        let mut set = BitSet::<usize>::new();
+        set.insert(3);
+        set.insert(INLINE_BITS - 1);
+        assert!(!set.spilled());
+
+        set.insert(INLINE_BITS);
+        assert!(set.spilled());
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            [3, INLINE_BITS - 1, INLINE_BITS]
+        );
+
+        set.remove(INLINE_BITS);
+        set.shrink_to_fit();
+        assert!(!set.spilled());
+        assert_eq!(set.iter().collect::<Vec<_>>(), [3, INLINE_BITS - 1]);
+
+        // Bits beyond the length of an inline set must read as zero after it
+        // grows again.
+        set.remove(INLINE_BITS - 1);
+        set.shrink_to_fit();
+        set.insert(INLINE_BITS - 2);
+        assert_eq!(set.iter().collect::<Vec<_>>(), [3, INLINE_BITS - 2]);
+
+        set.clear();
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn mixed_representations() {
+        let small = BitSet::<usize>::from_iter([1, 5, 70]);
+        let large = BitSet::<usize>::from_iter([1, 70, INLINE_BITS * 3]);
+        let disjoint = BitSet::<usize>::from_iter([2, INLINE_BITS + 1]);
+
+        assert!(!small.spilled());
+        assert!(large.spilled());
+
+        assert!(!small.is_disjoint(&large));
+        assert!(!large.is_disjoint(&small));
+        assert!(small.is_disjoint(&disjoint));
+        assert!(disjoint.is_disjoint(&small));
+
+        let sub = BitSet::<usize>::from_iter([1, 70]);
+        assert!(sub.is_subset(&large));
+        assert!(!large.is_subset(&sub));
+
+        let union = small.clone() | &large;
+        assert!(union.spilled());
+        assert_eq!(
+            union.iter().collect::<Vec<_>>(),
+            [1, 5, 70, INLINE_BITS * 3]
+        );
+
+        // Inline and spilled sets with the same elements are equal and hash
+        // the same.
+        let mut spilled = BitSet::<usize>::from_iter([1, 5, 70, INLINE_BITS]);
+        spilled.remove(INLINE_BITS);
+        assert!(spilled.spilled());
+        assert_eq!(spilled, small);
+        assert_eq!(hash(&spilled), hash(&small));
+        assert_ne!(large, small);
+    }
+
+    fn hash<T: SparseIndex>(set: &BitSet<T>) -> u64 {
+        ahash::RandomState::with_seeds(1, 2, 3, 4).hash_one(set)
+    }
+
+    #[test]
+    fn bulk_ops_match_hash_set() {
+        use std::collections::HashSet;
+
+        let mut state = 0x9e37_79b9_u32;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+
+        for _ in 0..200 {
+            // Mix inline and spilled sets.
+            let max = if next() % 2 == 0 { 200 } else { 2000 };
+
+            let mut a = BitSet::<u32>::new();
+            let mut b = BitSet::<u32>::new();
+            let mut a_oracle = HashSet::new();
+            let mut b_oracle = HashSet::new();
+
+            for _ in 0..next() % 300 {
+                let value = next() % max;
+                let (set, oracle) = if next() % 2 == 0 {
+                    (&mut a, &mut a_oracle)
+                } else {
+                    (&mut b, &mut b_oracle)
+                };
+
+                if next() % 4 == 0 {
+                    assert_eq!(set.remove(value), oracle.remove(&value));
+                } else {
+                    assert_eq!(set.insert(value), oracle.insert(value));
+                }
+            }
+
+            assert_eq!(
+                a.intersection_len(&b),
+                a_oracle.intersection(&b_oracle).count()
+            );
+            assert_eq!(a.intersection_len(&b), b.intersection_len(&a));
+
+            let mut sym = a.clone();
+            sym.symmetric_difference_with(&b);
+            let mut sym_oracle: Vec<_> =
+                a_oracle.symmetric_difference(&b_oracle).copied().collect();
+            sym_oracle.sort_unstable();
+            assert_eq!(sym.iter().collect::<Vec<_>>(), sym_oracle);
+
+            let modulus = next() % 5 + 1;
+            let mut visited = vec![];
+            a.retain(|value| {
+                visited.push(value);
+                value % modulus == 0
+            });
+            a_oracle.retain(|value| value % modulus == 0);
+
+            // Every element was visited once, in ascending order.
+            assert!(visited.windows(2).all(|w| w[0] < w[1]));
+
+            let mut retained_oracle: Vec<_> = a_oracle.into_iter().collect();
+            retained_oracle.sort_unstable();
+            assert_eq!(a.iter().collect::<Vec<_>>(), retained_oracle);
+        }
+    }
 }
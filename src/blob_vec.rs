@@ -1,17 +1,26 @@
 use alloc::alloc;
 use core::alloc::Layout;
-use core::ptr;
 use core::ptr::NonNull;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::{mem, ptr};
 
+use ::alloc::sync::Arc;
+
+use crate::arena::Arena;
 use crate::assert::UnwrapDebugChecked;
+use crate::component::EqFn;
 use crate::drop::DropFn;
 use crate::layout_util::pad_to_align;
 
 /// Like `Vec<T>`, but `T` is erased.
 #[derive(Debug)]
 pub(crate) struct BlobVec {
-    /// Layout of a single element.
+    /// Layout of a single element, padded so that the size is the stride
+    /// between consecutive elements.
     elem_layout: Layout,
+    /// Size of a single element without trailing padding. This is the number
+    /// of bytes copied in from outside the buffer.
+    elem_size: usize,
     /// Number of elements.
     len: usize,
     /// Capacity of allocated buffer.
@@ -20,22 +29,49 @@ pub(crate) struct BlobVec {
     data: NonNull<u8>,
     /// The erased element type's drop function, if any.
     drop: DropFn,
+    /// Running total of allocated bytes to update when the buffer is
+    /// reallocated or freed.
+    allocated: Option<Arc<AtomicUsize>>,
+    /// Arena to allocate the buffer from instead of the global allocator.
+    arena: Option<Arc<Arena>>,
 }
 
 impl BlobVec {
+    /// Creates a new `BlobVec`. The size of `layout` does not need to be a
+    /// multiple of its alignment.
+    ///
     /// # Safety
     /// - `drop` must be safe to call with elements of this `BlobVec` as
     ///   described by [`DropFn`]'s documentation.
     pub(crate) unsafe fn new(layout: Layout, drop: DropFn) -> Self {
         Self {
             elem_layout: pad_to_align(&layout),
+            elem_size: layout.size(),
             len: 0,
             cap: if layout.size() == 0 { usize::MAX } else { 0 },
-            data: NonNull::dangling(),
+            // Dangling, but aligned for the element type. Pointers to zero-sized
+            // elements are never reallocated, so this must respect over-alignment.
+            data: NonNull::new_unchecked(layout.align() as *mut u8),
             drop,
+            allocated: None,
+            arena: None,
         }
     }
 
+    /// Adds the size of the allocated buffer to `counter`, now and whenever
+    /// the buffer is reallocated or freed.
+    pub(crate) fn track_allocations(&mut self, counter: Arc<AtomicUsize>) {
+        counter.fetch_add(self.capacity_layout().size(), Ordering::Relaxed);
+        self.allocated = Some(counter);
+    }
+
+    /// Allocates the buffer from `arena` from now on. Must be called before
+    /// anything is allocated.
+    pub(crate) fn use_arena(&mut self, arena: Arc<Arena>) {
+        debug_assert_eq!(self.capacity_layout().size(), 0);
+        self.arena = Some(arena);
+    }
+
     pub(crate) unsafe fn push(&mut self) -> NonNull<u8> {
         self.reserve(1);
 
@@ -93,7 +129,36 @@ impl BlobVec {
             drop(NonNull::new_unchecked(ptr));
         }
 
-        ptr::copy_nonoverlapping(elem, ptr, self.elem_layout.size());
+        ptr::copy_nonoverlapping(elem, ptr, self.elem_size);
+    }
+
+    /// Like [`assign`](Self::assign), but drops `elem` instead if it is equal
+    /// to the element at `idx` according to `eq`. Returns `true` if `elem` was
+    /// assigned.
+    pub(crate) unsafe fn assign_unless_eq(
+        &mut self,
+        idx: usize,
+        elem: *const u8,
+        eq: EqFn,
+    ) -> bool {
+        debug_assert!(idx < self.len, "index out of bounds");
+
+        let ptr = self.data.as_ptr().add(idx * self.elem_layout.size());
+
+        if eq(
+            NonNull::new_unchecked(ptr),
+            NonNull::new_unchecked(elem.cast_mut()),
+        ) {
+            if let Some(drop) = self.drop {
+                drop(NonNull::new_unchecked(elem.cast_mut()));
+            }
+
+            false
+        } else {
+            self.assign(idx, elem);
+
+            true
+        }
     }
 
     #[cfg(test)]
@@ -162,7 +227,19 @@ impl BlobVec {
             // The current layout of the capacity.
             let old_cap_layout = self.capacity_layout();
 
-            let ptr = if old_cap_layout.size() == 0 {
+            let ptr = if let Some(arena) = &self.arena {
+                // SAFETY: `data` is the buffer described by `old_cap_layout`, and the first
+                // `len` elements are initialized.
+                unsafe {
+                    grow_in_arena(
+                        arena,
+                        self.data,
+                        self.len * self.elem_layout.size(),
+                        old_cap_layout,
+                        new_cap_layout,
+                    )
+                }
+            } else if old_cap_layout.size() == 0 {
                 // SAFETY: `new_cap_layout` is nonzero due to previous ZST check.
                 unsafe { alloc::alloc(new_cap_layout) }
             } else {
@@ -182,6 +259,13 @@ impl BlobVec {
                 None => alloc::handle_alloc_error(new_cap_layout),
             }
 
+            if let Some(counter) = &self.allocated {
+                counter.fetch_add(
+                    new_cap_layout.size() - old_cap_layout.size(),
+                    Ordering::Relaxed,
+                );
+            }
+
             self.cap = new_cap;
         }
     }
@@ -223,6 +307,58 @@ impl BlobVec {
         self.elem_layout
     }
 
+    /// Returns the size of a single element without trailing padding. Only
+    /// this many bytes should be read when copying elements in from outside
+    /// the buffer.
+    pub(crate) fn elem_size(&self) -> usize {
+        self.elem_size
+    }
+
+    /// Reorders the elements so that the element previously at index
+    /// `perm[i]` is moved to index `i`. Elements are moved, not cloned.
+    ///
+    /// # Safety
+    /// - `perm` must be a permutation of `0..self.len()`.
+    pub(crate) unsafe fn permute(&mut self, perm: &[u32]) {
+        debug_assert_eq!(perm.len(), self.len, "permutation length mismatch");
+
+        let size = self.elem_layout.size();
+
+        if size == 0 {
+            return;
+        }
+
+        let mut permuted = Self::new(
+            Layout::from_size_align_unchecked(self.elem_size, self.elem_layout.align()),
+            self.drop,
+        );
+        permuted.allocated.clone_from(&self.allocated);
+        permuted.arena.clone_from(&self.arena);
+        permuted.reserve(self.len);
+
+        for &idx in perm {
+            let src = self.data.as_ptr().add(idx as usize * size);
+            ptr::copy_nonoverlapping(src, permuted.push().as_ptr(), size);
+        }
+
+        // Ownership of the elements was moved to `permuted`.
+        self.len = 0;
+        mem::swap(self, &mut permuted);
+    }
+
+    /// Sets the length to zero without dropping any elements.
+    ///
+    /// # Safety
+    /// - Ownership of the elements must have been transferred elsewhere.
+    pub(crate) unsafe fn forget_elements(&mut self) {
+        self.len = 0;
+    }
+
+    /// Returns the function used to drop elements.
+    pub(crate) fn drop_fn(&self) -> DropFn {
+        self.drop
+    }
+
     pub(crate) fn as_ptr(&self) -> NonNull<u8> {
         self.data
     }
@@ -242,16 +378,77 @@ impl Drop for BlobVec {
 
         let cap_layout = self.capacity_layout();
 
+        if let Some(counter) = &self.allocated {
+            counter.fetch_sub(cap_layout.size(), Ordering::Relaxed);
+        }
+
         if cap_layout.size() > 0 {
             // SAFETY: Ptr is currently allocated because size is nonzero, and `cap_layout`
             // was the layout used for the allocation.
             unsafe {
-                alloc::dealloc(self.data.as_ptr(), cap_layout);
+                match &self.arena {
+                    // Blocks in the arena are freed along with the arena once
+                    // the last column holding it is dropped.
+                    Some(arena) => free_in_arena(arena, self.data, cap_layout),
+                    None => alloc::dealloc(self.data.as_ptr(), cap_layout),
+                }
             }
         }
     }
 }
 
+/// Allocates a buffer for `new_layout` from `arena`, or from the global
+/// allocator if the arena is full, and moves the first `used` bytes of `data`
+/// into it. Returns null if the allocation failed, in which case `data` is
+/// left untouched.
+///
+/// # Safety
+/// - `data` must be a buffer described by `old_layout` which was allocated with
+///   `arena`, or dangling if the size of `old_layout` is zero.
+/// - `used` must not exceed the size of either layout.
+unsafe fn grow_in_arena(
+    arena: &Arena,
+    data: NonNull<u8>,
+    used: usize,
+    old_layout: Layout,
+    new_layout: Layout,
+) -> *mut u8 {
+    let ptr = match arena.alloc(new_layout) {
+        Some(ptr) => ptr.as_ptr(),
+        None => {
+            let ptr = alloc::alloc(new_layout);
+
+            if !ptr.is_null() {
+                arena.add_fallback(new_layout.size());
+            }
+
+            ptr
+        }
+    };
+
+    if !ptr.is_null() && old_layout.size() > 0 {
+        ptr::copy_nonoverlapping(data.as_ptr(), ptr, used);
+        free_in_arena(arena, data, old_layout);
+    }
+
+    ptr
+}
+
+/// Frees a buffer allocated by [`grow_in_arena`]. Buffers in the arena are
+/// abandoned until the arena is dropped.
+///
+/// # Safety
+/// - `data` must be a buffer described by `layout` which was allocated with
+///   `arena`.
+unsafe fn free_in_arena(arena: &Arena, data: NonNull<u8>, layout: Layout) {
+    if arena.contains(data) {
+        arena.abandon(layout.size());
+    } else {
+        arena.remove_fallback(layout.size());
+        alloc::dealloc(data.as_ptr(), layout);
+    }
+}
+
 #[cold]
 fn capacity_overflow() -> ! {
     panic!("capacity overflow")
@@ -327,7 +524,7 @@ mod tests {
 
             #[track_caller]
             fn check(ptr: NonNull<u8>) {
-                assert!(ptr.as_ptr() as usize % 128 == 0);
+                assert!((ptr.as_ptr() as usize).is_multiple_of(128));
             }
 
             check(vec.push());
@@ -338,4 +535,66 @@ mod tests {
             check(vec.get_mut(1).unwrap());
         }
     }
+
+    #[test]
+    fn size_not_multiple_of_align() {
+        unsafe {
+            let mut vec = BlobVec::new(Layout::from_size_align(12, 8).unwrap(), None);
+            let mut other = BlobVec::new(Layout::from_size_align(12, 8).unwrap(), None);
+
+            assert_eq!(vec.elem_layout().size(), 16);
+            assert_eq!(vec.elem_size(), 12);
+
+            for i in 0..3_u8 {
+                vec.push().as_ptr().write_bytes(i, 12);
+            }
+
+            // Only the unpadded size is read from the source.
+            let elem = [9_u8; 12];
+            vec.assign(0, elem.as_ptr());
+            vec.transfer_elem(&mut other, 0);
+
+            assert_eq!(vec.len(), 2);
+            assert_eq!(
+                *other.get_mut(0).unwrap().cast::<[u8; 12]>().as_ptr(),
+                [9; 12]
+            );
+            assert_eq!(
+                *vec.get_mut(0).unwrap().cast::<[u8; 12]>().as_ptr(),
+                [2; 12]
+            );
+            assert_eq!(vec.capacity_layout().size(), vec.capacity() * 16);
+        }
+    }
+
+    #[test]
+    fn arena_columns_outlive_owner() {
+        type T = Rc<()>;
+
+        let arena = Arc::new(Arena::new(64));
+        let rc = Rc::new(());
+
+        let mut vec = new_blob_vec::<T>();
+        vec.use_arena(arena.clone());
+
+        // Overflow the arena so the column ends up in a fallback allocation.
+        for _ in 0..64 {
+            unsafe { vec.push().cast::<T>().as_ptr().write(rc.clone()) };
+        }
+
+        let mut plain = new_blob_vec::<u64>();
+        plain.use_arena(arena.clone());
+        unsafe { plain.push() };
+
+        assert!(arena.fallback_count() > 0);
+
+        // Drop the arena's owner before any of its columns.
+        let weak = Arc::downgrade(&arena);
+        drop(arena);
+        drop(plain);
+        drop(vec);
+
+        assert_eq!(Rc::strong_count(&rc), 1);
+        assert_eq!(weak.strong_count(), 0);
+    }
 }
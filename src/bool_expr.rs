@@ -2,6 +2,7 @@
 
 #[cfg(not(feature = "std"))]
 use alloc::{vec, vec::Vec};
+use core::hash::{Hash, Hasher};
 use core::{fmt, mem};
 
 use crate::bit_set::BitSet;
@@ -185,11 +186,157 @@ impl<T> BoolExpr<T> {
             }
 
             res = res.and(&ors);
+
+            // Repeated ANDs multiply the number of terms, so prune as we go to
+            // keep negations of large expressions manageable.
+            res.remove_subsumed();
         }
 
         res
     }
 
+    /// Removes redundant terms from the expression without changing its
+    /// value for any assignment of the variables. A term is redundant if it
+    /// duplicates another term, contradicts itself like `A ∧ ¬A`, or
+    /// requires everything another term requires and more, like `A ∧ B` in
+    /// `A ∨ (A ∧ B)`.
+    ///
+    /// Expressions built up with [`or`](Self::or) can accumulate many such
+    /// terms, which makes later operations like
+    /// [`is_disjoint`](Self::is_disjoint) slower.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evenio::bool_expr::BoolExpr;
+    ///
+    /// const A: u32 = 0;
+    /// const B: u32 = 1;
+    ///
+    /// let mut expr = BoolExpr::var(A).or(&BoolExpr::var(A).and(&BoolExpr::var(B)));
+    /// expr.simplify();
+    ///
+    /// assert_eq!(expr, BoolExpr::var(A));
+    /// ```
+    pub fn simplify(&mut self)
+    where
+        T: SparseIndex,
+    {
+        self.ands
+            .retain(|ands| ands.vars.is_disjoint(&ands.negated_vars));
+        self.remove_subsumed();
+    }
+
+    /// Removes every term which is implied by another term, such as `A ∧ B`
+    /// in `A ∨ (A ∧ B)`, along with duplicate terms. The result is logically
+    /// equivalent to `self`.
+    fn remove_subsumed(&mut self) {
+        self.ands
+            .sort_by_key(|ands| ands.vars.len() + ands.negated_vars.len());
+
+        let mut kept: Vec<Ands<T>> = Vec::with_capacity(self.ands.len());
+
+        for ands in mem::take(&mut self.ands) {
+            let subsumed = kept.iter().any(|k| {
+                k.vars.is_subset(&ands.vars) && k.negated_vars.is_subset(&ands.negated_vars)
+            });
+
+            if !subsumed {
+                kept.push(ands);
+            }
+        }
+
+        self.ands = kept;
+    }
+
+    /// Returns `true` if there is some combination of values the variables
+    /// could have to make the expression true.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evenio::bool_expr::BoolExpr;
+    ///
+    /// const A: u32 = 0;
+    /// const B: u32 = 1;
+    ///
+    /// assert!(BoolExpr::var(A).and(&BoolExpr::not_var(B)).is_satisfiable());
+    ///
+    /// // `A ∧ ¬A` can never be true.
+    /// assert!(!BoolExpr::var(A).and(&BoolExpr::not_var(A)).is_satisfiable());
+    /// ```
+    pub fn is_satisfiable(&self) -> bool {
+        // A term of the DNF is satisfiable unless it contains both a variable
+        // and its negation.
+        self.ands
+            .iter()
+            .any(|ands| ands.vars.is_disjoint(&ands.negated_vars))
+    }
+
+    /// Converts the expression to conjunctive normal form, e.g.
+    /// (A ∨ B ∨ ¬C) ∧ (D ∨ ¬E). The expression is true exactly when every
+    /// returned [`Clause`] is true. An empty list of clauses is always true,
+    /// and an empty clause is always false.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evenio::bool_expr::BoolExpr;
+    ///
+    /// const A: u32 = 0;
+    /// const B: u32 = 1;
+    /// const C: u32 = 2;
+    ///
+    /// // (A ∧ B) ∨ C ≡ (A ∨ C) ∧ (B ∨ C)
+    /// let expr = BoolExpr::var(A).and(&BoolExpr::var(B)).or(&BoolExpr::var(C));
+    /// let cnf = expr.to_cnf();
+    ///
+    /// assert_eq!(cnf.len(), 2);
+    ///
+    /// for clause in &cnf {
+    ///     assert!(clause.vars().contains(C));
+    ///     assert!(clause.negated_vars().is_empty());
+    /// }
+    /// ```
+    pub fn to_cnf(&self) -> Vec<Clause<T>>
+    where
+        T: SparseIndex,
+    {
+        // E ≡ ¬¬E, and ¬E is in DNF. Negating each term of ¬E with De Morgan's
+        // laws turns the "OR of ANDs" into an "AND of ORs".
+        let mut negated = self.clone().not();
+        negated.simplify();
+
+        negated
+            .ands
+            .into_iter()
+            .map(|ands| Clause {
+                vars: ands.negated_vars,
+                negated_vars: ands.vars,
+            })
+            .collect()
+    }
+
+    /// Puts the expression into a canonical form by sorting and deduplicating
+    /// its terms.
+    ///
+    /// Two normalized expressions compare equal if they are made of the same
+    /// terms. Expressions which are logically equivalent but written
+    /// differently, such as `A ∨ (A ∧ B)` and `A`, may still compare unequal.
+    pub fn normalize(&mut self)
+    where
+        T: SparseIndex,
+    {
+        self.ands
+            .retain(|ands| ands.vars.is_disjoint(&ands.negated_vars));
+        self.ands.sort_unstable_by(|a, b| {
+            a.vars
+                .cmp(&b.vars)
+                .then_with(|| a.negated_vars.cmp(&b.negated_vars))
+        });
+        self.ands.dedup_by(|a, b| a == b);
+    }
+
     /// XOR two expressions together.
     pub fn xor(self, other: &Self) -> Self
     where
@@ -238,6 +385,77 @@ impl<T> BoolExpr<T> {
     }
 }
 
+/// A disjunction of variables and negated variables, such as A ∨ B ∨ ¬C.
+/// Returned by [`BoolExpr::to_cnf`].
+pub struct Clause<T> {
+    vars: BitSet<T>,
+    negated_vars: BitSet<T>,
+}
+
+impl<T> Clause<T> {
+    /// Returns the variables which appear in the clause without negation.
+    pub fn vars(&self) -> &BitSet<T> {
+        &self.vars
+    }
+
+    /// Returns the variables which appear in the clause negated.
+    pub fn negated_vars(&self) -> &BitSet<T> {
+        &self.negated_vars
+    }
+
+    /// Evaluate the clause. `get_var` provides the values of the variables in
+    /// the clause.
+    pub fn eval<F>(&self, mut get_var: F) -> bool
+    where
+        T: SparseIndex,
+        F: FnMut(T) -> bool,
+    {
+        self.vars.iter().any(&mut get_var) || self.negated_vars.iter().any(|var| !get_var(var))
+    }
+}
+
+impl<T> Clone for Clause<T> {
+    fn clone(&self) -> Self {
+        Self {
+            vars: self.vars.clone(),
+            negated_vars: self.negated_vars.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for Clause<T>
+where
+    T: SparseIndex + fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.vars.is_empty() && self.negated_vars.is_empty() {
+            return write!(f, "⊥");
+        }
+
+        let mut first = true;
+
+        for var in &self.vars {
+            if !first {
+                write!(f, " ∨ ")?;
+            }
+            first = false;
+
+            write!(f, "{var:?}")?;
+        }
+
+        for var in &self.negated_vars {
+            if !first {
+                write!(f, " ∨ ")?;
+            }
+            first = false;
+
+            write!(f, "¬{var:?}")?;
+        }
+
+        Ok(())
+    }
+}
+
 impl<T> Clone for BoolExpr<T> {
     fn clone(&self) -> Self {
         Self {
@@ -255,6 +473,35 @@ impl<T> Clone for Ands<T> {
     }
 }
 
+impl<T: SparseIndex> PartialEq for BoolExpr<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ands == other.ands
+    }
+}
+
+impl<T: SparseIndex> Eq for BoolExpr<T> {}
+
+impl<T: SparseIndex> Hash for BoolExpr<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.ands.hash(state);
+    }
+}
+
+impl<T: SparseIndex> PartialEq for Ands<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.vars == other.vars && self.negated_vars == other.negated_vars
+    }
+}
+
+impl<T: SparseIndex> Eq for Ands<T> {}
+
+impl<T: SparseIndex> Hash for Ands<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.vars.hash(state);
+        self.negated_vars.hash(state);
+    }
+}
+
 impl<T> fmt::Debug for BoolExpr<T>
 where
     T: SparseIndex + fmt::Debug,
@@ -300,3 +547,172 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VARS: u32 = 6;
+
+    /// Builds a random expression over `VARS` variables from the bits of
+    /// `next`.
+    fn random_expr(next: &mut impl FnMut() -> u32, depth: u32) -> BoolExpr<u32> {
+        if depth == 0 {
+            let var = next() % VARS;
+
+            return match next() % 4 {
+                0 => BoolExpr::not_var(var),
+                1 if next() % 8 == 7 => BoolExpr::new(next() & 1 == 0),
+                _ => BoolExpr::var(var),
+            };
+        }
+
+        let a = random_expr(next, depth - 1);
+        let b = random_expr(next, depth - 1);
+
+        match next() % 5 {
+            0 | 1 => a.or(&b),
+            2 | 3 => a.and(&b),
+            _ => a.not(),
+        }
+    }
+
+    #[test]
+    fn simplify_preserves_eval() {
+        let mut state = 0x9e37_79b9_u32;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+
+        for _ in 0..500 {
+            let depth = next() % 5;
+            let expr = random_expr(&mut next, depth);
+
+            // Redundant copies of the terms, which simplifying should remove.
+            let expr = expr
+                .clone()
+                .or(&expr.clone().and(&random_expr(&mut next, 1)));
+
+            let mut simplified = expr.clone();
+            simplified.simplify();
+
+            assert!(simplified.ands.len() <= expr.ands.len());
+
+            for assignment in 0..1_u32 << VARS {
+                let get_var = |var: u32| assignment >> var & 1 == 1;
+
+                assert_eq!(
+                    expr.eval(get_var),
+                    simplified.eval(get_var),
+                    "{expr:?} and {simplified:?} differ"
+                );
+            }
+
+            // Simplifying again has no effect.
+            let mut again = simplified.clone();
+            again.simplify();
+            assert_eq!(again.ands.len(), simplified.ands.len());
+        }
+    }
+
+    #[test]
+    fn simplify_absorption() {
+        const A: u32 = 0;
+        const B: u32 = 1;
+        const C: u32 = 2;
+
+        let a = BoolExpr::var(A);
+        let b = BoolExpr::var(B);
+        let not_c = BoolExpr::not_var(C);
+
+        // (A ∧ B) ∨ A ∨ (A ∧ ¬C) ∨ A ∨ (B ∧ ¬B)
+        let mut expr = a.clone().and(&b).or(&a).or(&a.clone().and(&not_c)).or(&a);
+        expr.ands.push(Ands {
+            vars: b.ands[0].vars.clone(),
+            negated_vars: b.ands[0].vars.clone(),
+        });
+
+        expr.simplify();
+        assert_eq!(expr, a);
+
+        // Neither term implies the other.
+        let mut expr = a.clone().and(&not_c).or(&b.clone().and(&not_c));
+        expr.simplify();
+        assert_eq!(expr.ands.len(), 2);
+
+        let mut expr = BoolExpr::<u32>::new(true).or(&a);
+        expr.simplify();
+        assert_eq!(expr, BoolExpr::new(true));
+    }
+
+    #[test]
+    fn contradiction_is_unsatisfiable() {
+        const A: u32 = 0;
+        const B: u32 = 1;
+
+        let a = BoolExpr::var(A);
+        let not_a = BoolExpr::not_var(A);
+
+        assert!(!a.clone().and(&not_a).is_satisfiable());
+        assert!(!a
+            .clone()
+            .and(&BoolExpr::var(B))
+            .and(&not_a)
+            .is_satisfiable());
+        assert!(!BoolExpr::<u32>::new(false).is_satisfiable());
+
+        // A contradiction pushed in directly rather than pruned by `and`.
+        let mut expr = BoolExpr::<u32>::new(false);
+        expr.ands.push(Ands {
+            vars: a.ands[0].vars.clone(),
+            negated_vars: not_a.ands[0].negated_vars.clone(),
+        });
+        assert!(!expr.is_satisfiable());
+
+        assert!(BoolExpr::<u32>::new(true).is_satisfiable());
+        assert!(a.clone().or(&not_a).is_satisfiable());
+
+        // The CNF of a contradiction contains an empty clause.
+        let cnf = a.and(&not_a).to_cnf();
+        assert!(cnf
+            .iter()
+            .any(|c| c.vars().is_empty() && c.negated_vars().is_empty()));
+    }
+
+    #[test]
+    fn cnf_preserves_eval() {
+        let mut state = 0x2545_f491_u32;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+
+        for _ in 0..500 {
+            let depth = next() % 5;
+            let expr = random_expr(&mut next, depth);
+            let cnf = expr.to_cnf();
+
+            let mut satisfiable = false;
+
+            for assignment in 0..1_u32 << VARS {
+                let get_var = |var: u32| assignment >> var & 1 == 1;
+
+                let value = expr.eval(get_var);
+                satisfiable |= value;
+
+                assert_eq!(
+                    value,
+                    cnf.iter().all(|clause| clause.eval(get_var)),
+                    "{expr:?} and {cnf:?} differ"
+                );
+            }
+
+            assert_eq!(expr.is_satisfiable(), satisfiable, "{expr:?}");
+        }
+    }
+}
@@ -0,0 +1,292 @@
+//! Deferred structural changes.
+//!
+//! See [`Commands`] for more information.
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::vec::Vec;
+use core::panic::{RefUnwindSafe, UnwindSafe};
+use core::{any, fmt, mem};
+
+use crate::access::Access;
+use crate::archetype::Archetype;
+use crate::component::Component;
+use crate::entity::{EntityId, EntityLocation};
+use crate::event::{Despawn, Event, EventPtr, Insert, Remove, Spawn};
+use crate::handler::{Config, HandlerInfo, HandlerParam, InitError};
+use crate::world::{Sender, UnsafeWorldCell, World};
+
+/// A [`HandlerParam`] which records structural changes to apply once the
+/// handler returns.
+///
+/// Spawning and despawning entities and inserting and removing components
+/// are done by sending the [`Spawn`], [`Despawn`], [`Insert`] and [`Remove`]
+/// events, so handlers of those events run just as if the changes were made
+/// through a [`Sender`](crate::event::Sender). Unlike a `Sender`, `Commands`
+/// doesn't need the types of the events up front, so any component can be
+/// inserted or removed.
+///
+/// The recorded operations are applied in order right after the handler
+/// returns, and the events they send are handled before the next handler of
+/// the current event runs. Since nothing changes while the handler runs, it
+/// is safe to despawn the entities the handler is iterating over.
+///
+/// If the current event is targeted and the operations move its target to
+/// another archetype, the remaining handlers don't receive the event.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::commands::Commands;
+/// use evenio::prelude::*;
+///
+/// # #[derive(Event)] struct Tick;
+/// #[derive(Component)]
+/// struct Health(u32);
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(
+///     |_: Receiver<Tick>, fetcher: Fetcher<(EntityId, &Health)>, mut commands: Commands| {
+///         for (id, health) in &fetcher {
+///             if health.0 == 0 {
+///                 commands.despawn(id);
+///             }
+///         }
+///     },
+/// );
+///
+/// let e = world.spawn();
+/// world.insert(e, Health(0));
+///
+/// world.send(Tick);
+///
+/// assert!(!world.entities().contains(e));
+/// ```
+///
+/// # Panics
+///
+/// Panics after the handler returns if the handler receives one of the events
+/// sent by its own operations.
+pub struct Commands<'a> {
+    world: UnsafeWorldCell<'a>,
+}
+
+impl Commands<'_> {
+    /// Reserves an entity to be spawned, returns its [`EntityId`], and
+    /// records sending the [`Spawn`] event. The returned `EntityId` is not
+    /// used by any previous entities in the [`World`].
+    pub fn spawn(&mut self) -> EntityId {
+        // SAFETY: Access to the event queue was claimed in `init`.
+        let id = unsafe { self.world.queue_spawn() };
+        self.push::<Spawn>(move |s| s.send(Spawn(id)));
+        id
+    }
+
+    /// Records sending an [`Insert`] event.
+    pub fn insert<C: Component>(&mut self, entity: EntityId, component: C) {
+        self.push::<Insert<C>>(move |s| s.insert(entity, component));
+    }
+
+    /// Records sending a [`Remove`] event.
+    pub fn remove<C: Component>(&mut self, entity: EntityId) {
+        self.push::<Remove<C>>(move |s| s.remove::<C>(entity));
+    }
+
+    /// Records sending a [`Despawn`] event.
+    pub fn despawn(&mut self, entity: EntityId) {
+        self.push::<Despawn>(move |s| s.despawn(entity));
+    }
+
+    /// Records `send`, which sends an event of type `E`.
+    fn push<E: Event>(&mut self, send: impl FnOnce(&mut Sender) + Send + Sync + 'static) {
+        let command = Command {
+            register: |world| {
+                world.add_event::<E>();
+            },
+            send: Box::new(send),
+        };
+
+        // SAFETY: Access to the event queue was claimed in `init`.
+        unsafe { self.world.push_command(command) }
+    }
+}
+
+unsafe impl HandlerParam for Commands<'_> {
+    type State = ();
+
+    type Item<'a> = Commands<'a>;
+
+    fn init(_world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+        if !config
+            .event_queue_access
+            .set_if_compatible(Access::ReadWrite)
+        {
+            return Err(InitError(
+                format!(
+                    "`{}` has conflicting access with a previous handler parameter. Only one \
+                     handler parameter can send events",
+                    any::type_name::<Self>()
+                )
+                .into(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    unsafe fn get<'a>(
+        _state: &'a mut Self::State,
+        _info: &'a HandlerInfo,
+        _event_ptr: EventPtr<'a>,
+        _target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::Item<'a> {
+        Commands { world }
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
+impl fmt::Debug for Commands<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Commands")
+            .field("world", &self.world)
+            .finish()
+    }
+}
+
+/// An operation recorded by [`Commands`].
+pub(crate) struct Command {
+    /// Adds the type of the event sent by the operation to the world.
+    /// Registering an event sends events of its own, so this is done for
+    /// every operation before any of them are sent.
+    pub(crate) register: fn(&mut World),
+    pub(crate) send: Box<dyn FnOnce(&mut Sender) + Send + Sync>,
+}
+
+/// The operations recorded by the running handler's [`Commands`].
+#[derive(Default)]
+pub(crate) struct CommandQueue(Vec<Command>);
+
+impl CommandQueue {
+    pub(crate) fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub(crate) fn push(&mut self, command: Command) {
+        self.0.push(command);
+    }
+
+    pub(crate) fn take(&mut self) -> Vec<Command> {
+        mem::take(&mut self.0)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Debug for CommandQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CommandQueue")
+            .field("len", &self.0.len())
+            .finish()
+    }
+}
+
+impl UnwindSafe for CommandQueue {}
+impl RefUnwindSafe for CommandQueue {}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use super::Commands;
+    use crate::prelude::*;
+
+    #[derive(Event)]
+    struct E;
+
+    #[derive(Component)]
+    struct C(u32);
+
+    #[derive(Component)]
+    struct Tag;
+
+    #[derive(Component, Default)]
+    struct Log(Vec<&'static str>);
+
+    #[test]
+    fn despawn_while_iterating() {
+        let mut world = World::new();
+
+        let log = world.spawn();
+        world.insert(log, Log::default());
+
+        world.add_handler(|r: Receiver<Despawn, ()>, Single(log): Single<&mut Log>| {
+            let _ = r;
+            log.0.push("despawned");
+        });
+
+        world.add_handler(
+            |_: Receiver<E>, fetcher: Fetcher<(EntityId, &C)>, mut commands: Commands| {
+                for (id, c) in &fetcher {
+                    // Despawn the entity being visited, and change the next one.
+                    if c.0 % 2 == 0 {
+                        commands.despawn(id);
+                    } else {
+                        commands.insert(id, Tag);
+                        commands.remove::<C>(id);
+                    }
+                }
+
+                let e = commands.spawn();
+                commands.insert(e, C(100));
+            },
+        );
+
+        // Runs after the operations of the previous handler are applied.
+        world.add_handler(
+            |_: Receiver<E>,
+             fetcher: Fetcher<&C>,
+             tags: Fetcher<&Tag>,
+             Single(log): Single<&mut Log>| {
+                assert_eq!(fetcher.iter().map(|c| c.0).collect::<Vec<_>>(), [100]);
+                assert_eq!(tags.iter().count(), 5);
+                log.0.push("next handler");
+            },
+        );
+
+        let entities: Vec<_> = (0..10)
+            .map(|i| {
+                let e = world.spawn();
+                world.insert(e, C(i));
+                e
+            })
+            .collect();
+
+        world.send(E);
+
+        for (i, &e) in entities.iter().enumerate() {
+            assert_eq!(world.entities().contains(e), i % 2 == 1);
+        }
+
+        let mut expected = vec!["despawned"; 5];
+        expected.push("next handler");
+        assert_eq!(world.get::<Log>(log).unwrap().0, expected);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only one handler parameter can send events")]
+    fn conflicts_with_sender() {
+        World::new().add_handler(|_: Receiver<E>, _: Sender<Spawn>, _: Commands| {});
+    }
+}
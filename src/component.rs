@@ -2,22 +2,37 @@
 
 use alloc::borrow::Cow;
 use alloc::collections::BTreeSet;
+use alloc::sync::Arc;
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, vec, vec::Vec};
 use core::alloc::Layout;
-use core::any::TypeId;
+use core::any::{self, Any, TypeId};
+use core::fmt;
+use core::marker::PhantomData;
 use core::ops::Index;
+use core::panic::{RefUnwindSafe, UnwindSafe};
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 use ahash::RandomState;
+use evenio_macros::all_tuples;
 pub use evenio_macros::Component;
 
-use crate::archetype::{Archetype, ArchetypeIdx};
+use crate::archetype::{Archetype, ArchetypeIdx, Archetypes};
 use crate::assert::UnwrapDebugChecked;
+use crate::blob_vec::BlobVec;
 use crate::drop::DropFn;
-use crate::entity::EntityLocation;
+use crate::entity::{EntityId, EntityLocation};
 use crate::event::{Event, EventId, EventPtr};
 use crate::handler::{Config, HandlerInfo, HandlerParam, InitError};
+use crate::layout_util::pad_to_align;
 use crate::map::{Entry, IndexSet, TypeIdMap};
 use crate::prelude::World;
+use crate::quota::Quota;
+use crate::reflect::FieldInfo;
 use crate::slot_map::{Key, SlotMap};
+#[cfg(feature = "serde")]
+use crate::snapshot::ComponentSerde;
 use crate::sparse::SparseIndex;
 use crate::world::UnsafeWorldCell;
 
@@ -57,12 +72,24 @@ impl Components {
                         name: desc.name,
                         id: ComponentId(k),
                         type_id: desc.type_id,
-                        layout: desc.layout,
+                        layout: pad_to_align(&desc.layout),
+                        size: desc.layout.size(),
                         drop: desc.drop,
                         is_immutable: desc.is_immutable,
+                        is_double_buffered: desc.is_double_buffered,
+                        skip_identical_writes: desc.skip_identical_writes,
+                        fields: desc.fields.into_boxed_slice(),
                         insert_events: BTreeSet::new(),
                         remove_events: BTreeSet::new(),
                         member_of: IndexSet::with_hasher(RandomState::new()),
+                        query_default: None,
+                        drop_hook: None,
+                        invariants: vec![],
+                        quota: None,
+                        bytes_allocated: Arc::new(AtomicUsize::new(0)),
+                        size_warned: false,
+                        #[cfg(feature = "serde")]
+                        serde: None,
                     }) else {
                         panic!("too many components")
                     };
@@ -77,12 +104,24 @@ impl Components {
             name: desc.name,
             id: ComponentId(k),
             type_id: desc.type_id,
-            layout: desc.layout,
+            layout: pad_to_align(&desc.layout),
+            size: desc.layout.size(),
             drop: desc.drop,
             is_immutable: desc.is_immutable,
+            is_double_buffered: desc.is_double_buffered,
+            skip_identical_writes: desc.skip_identical_writes,
+            fields: desc.fields.into_boxed_slice(),
             insert_events: BTreeSet::new(),
             remove_events: BTreeSet::new(),
             member_of: IndexSet::with_hasher(RandomState::new()),
+            query_default: None,
+            drop_hook: None,
+            invariants: vec![],
+            quota: None,
+            bytes_allocated: Arc::new(AtomicUsize::new(0)),
+            size_warned: false,
+            #[cfg(feature = "serde")]
+            serde: None,
         }) else {
             panic!("too many components")
         };
@@ -123,6 +162,11 @@ impl Components {
         Some(unsafe { self.get(id).unwrap_debug_checked() })
     }
 
+    /// Returns the number of components in the world.
+    pub fn len(&self) -> u32 {
+        self.infos.len()
+    }
+
     /// Does the given component exist in the world?
     pub fn contains(&self, id: ComponentId) -> bool {
         self.get(id).is_some()
@@ -132,6 +176,10 @@ impl Components {
     pub fn iter(&self) -> impl Iterator<Item = &ComponentInfo> {
         self.infos.iter().map(|(_, v)| v)
     }
+
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = &mut ComponentInfo> {
+        self.infos.iter_mut().map(|(_, v)| v)
+    }
 }
 
 impl Index<ComponentId> for Components {
@@ -175,8 +223,8 @@ unsafe impl HandlerParam for &'_ Components {
 
     type Item<'a> = &'a Components;
 
-    fn init(_world: &mut World, _config: &mut Config) -> Result<Self::State, InitError> {
-        Ok(())
+    fn init(_world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+        config.read_world::<Self>()
     }
 
     unsafe fn get<'a>(
@@ -197,19 +245,138 @@ unsafe impl HandlerParam for &'_ Components {
 /// Metadata for a component.
 #[derive(Debug)]
 pub struct ComponentInfo {
-    name: Cow<'static, str>,
+    pub(crate) name: Cow<'static, str>,
     id: ComponentId,
     type_id: Option<TypeId>,
+    /// Layout of the component with the size rounded up to the alignment.
     layout: Layout,
+    /// Size of the component as given in the [`ComponentDescriptor`].
+    size: usize,
     drop: DropFn,
     is_immutable: bool,
+    is_double_buffered: bool,
+    skip_identical_writes: Option<EqFn>,
+    fields: Box<[FieldInfo]>,
     pub(crate) insert_events: BTreeSet<EventId>,
     pub(crate) remove_events: BTreeSet<EventId>,
     /// The set of archetypes that have this component as one of its columns.
     pub(crate) member_of: IndexSet<ArchetypeIdx>,
+    /// Value used by [`WithDefaultRef`] when the component is absent.
+    ///
+    /// [`WithDefaultRef`]: crate::query::WithDefaultRef
+    pub(crate) query_default: Option<QueryDefault>,
+    /// Callback registered with [`World::set_component_drop_hook`].
+    pub(crate) drop_hook: Option<DropHook>,
+    /// Checks registered with [`World::add_invariant`].
+    pub(crate) invariants: Vec<Invariant>,
+    /// Set by [`World::set_component_quota`].
+    pub(crate) quota: Option<Quota>,
+    /// Running total of bytes allocated by this component's columns, shared
+    /// with every column.
+    pub(crate) bytes_allocated: Arc<AtomicUsize>,
+    /// Whether the hook registered with [`World::set_component_size_warning`]
+    /// was called for this component.
+    pub(crate) size_warned: bool,
+    /// Set by [`World::add_serde_component`].
+    #[cfg(feature = "serde")]
+    pub(crate) serde: Option<ComponentSerde>,
+}
+
+/// Memory statistics of a component, returned by
+/// [`World::component_memory`].
+///
+/// [`World::component_memory`]: crate::world::World::component_memory
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct ComponentMemory {
+    /// Total capacity of the component's columns in bytes, including the
+    /// previous values of double-buffered components.
+    pub bytes_allocated: usize,
+    /// Number of bytes occupied by component values.
+    pub bytes_used: usize,
+    /// Number of archetypes with a column for the component.
+    pub archetype_count: usize,
+}
+
+/// Callback registered with [`World::set_component_size_warning`].
+pub(crate) struct SizeWarning {
+    pub(crate) threshold: usize,
+    pub(crate) hook: Box<dyn FnMut(&ComponentInfo) + Send + Sync>,
+}
+
+impl SizeWarning {
+    /// Calls the hook if the component is larger than the threshold and the
+    /// hook hasn't been called for it yet.
+    pub(crate) fn check(&mut self, info: &mut ComponentInfo) {
+        if !info.size_warned && info.layout.size() > self.threshold {
+            info.size_warned = true;
+            (self.hook)(info);
+        }
+    }
+}
+
+impl fmt::Debug for SizeWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SizeWarning")
+            .field("threshold", &self.threshold)
+            .finish_non_exhaustive()
+    }
+}
+
+impl UnwindSafe for SizeWarning {}
+impl RefUnwindSafe for SizeWarning {}
+
+/// Type-erased default value of a component. Registered with
+/// [`World::set_query_default`].
+///
+/// The value is boxed so that its address remains stable for the lifetime of
+/// the component.
+#[derive(Debug)]
+pub(crate) struct QueryDefault(pub(crate) Box<dyn Any + Send + Sync>);
+
+impl UnwindSafe for QueryDefault {}
+impl RefUnwindSafe for QueryDefault {}
+
+/// Type-erased callback registered with [`World::set_component_drop_hook`].
+/// Takes ownership of the removed component stored in the buffer.
+#[derive(Clone)]
+pub(crate) struct DropHook(pub(crate) Arc<DropHookFn>);
+
+type DropHookFn = dyn Fn(&mut World, EntityId, BlobVec) + Send + Sync;
+
+impl fmt::Debug for DropHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DropHook").finish_non_exhaustive()
+    }
+}
+
+impl UnwindSafe for DropHook {}
+impl RefUnwindSafe for DropHook {}
+
+/// Type-erased check registered with [`World::add_invariant`]. Takes a
+/// pointer to a column and the number of values in it, and returns the row of
+/// the first value which violates the invariant.
+pub(crate) struct Invariant(pub(crate) Box<InvariantFn>);
+
+type InvariantFn = dyn Fn(NonNull<u8>, usize) -> Option<usize> + Send + Sync;
+
+impl fmt::Debug for Invariant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Invariant").finish_non_exhaustive()
+    }
 }
 
+impl UnwindSafe for Invariant {}
+impl RefUnwindSafe for Invariant {}
+
 impl ComponentInfo {
+    /// Returns a pointer to the value registered with
+    /// [`World::set_query_default`], if any.
+    pub(crate) fn query_default_ptr(&self) -> Option<NonNull<u8>> {
+        self.query_default
+            .as_ref()
+            .map(|QueryDefault(v)| NonNull::from(&**v).cast())
+    }
+
     /// Gets the name of the component.
     ///
     /// This name is intended for debugging purposes and should not be relied
@@ -230,10 +397,31 @@ impl ComponentInfo {
     }
 
     /// Gets the [`Layout`] of the component.
+    ///
+    /// The size of the layout is always a multiple of its alignment, even if
+    /// the layout given in the [`ComponentDescriptor`] was not. This is the
+    /// stride between consecutive components in an archetype column. For types
+    /// added with [`World::add_component`], this is the same as
+    /// [`Layout::new`].
+    ///
+    /// [`World::add_component`]: crate::world::World::add_component
     pub fn layout(&self) -> Layout {
         self.layout
     }
 
+    /// Gets the size of the component without trailing padding, as given in
+    /// the [`ComponentDescriptor`]. This is the number of bytes read when a
+    /// component value is copied into the world.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Gets the component's layout without trailing padding.
+    pub(crate) fn unpadded_layout(&self) -> Layout {
+        // SAFETY: The size was originally paired with this alignment.
+        unsafe { Layout::from_size_align_unchecked(self.size, self.layout.align()) }
+    }
+
     /// Gets the [`DropFn`] of the component.
     pub fn drop(&self) -> DropFn {
         self.drop
@@ -246,6 +434,38 @@ impl ComponentInfo {
         self.is_immutable
     }
 
+    /// Gets whether the component is [double-buffered].
+    ///
+    /// [double-buffered]: Component::DOUBLE_BUFFERED
+    pub fn is_double_buffered(&self) -> bool {
+        self.is_double_buffered
+    }
+
+    /// Gets the [`EqFn`] used to [skip identical writes], if any.
+    ///
+    /// [skip identical writes]: Component::SKIP_IDENTICAL_WRITES
+    pub fn skip_identical_writes(&self) -> Option<EqFn> {
+        self.skip_identical_writes
+    }
+
+    /// Gets the descriptions of the component's fields. Empty unless the
+    /// component was [reflected](Component::reflect_fields).
+    pub fn fields(&self) -> &[FieldInfo] {
+        &self.fields
+    }
+
+    /// Gets the description of the field named `name`, if any.
+    pub fn field(&self, name: &str) -> Option<&FieldInfo> {
+        self.fields.iter().find(|field| field.name() == name)
+    }
+
+    /// Gets the key the component is serialized under, if it was added with
+    /// [`World::add_serde_component`].
+    #[cfg(feature = "serde")]
+    pub fn serde_key(&self) -> Option<&str> {
+        self.serde.as_ref().map(ComponentSerde::key)
+    }
+
     /// Gets the set of [`Insert`] events for this component.
     ///
     /// [`Insert`]: crate::event::Insert
@@ -259,6 +479,28 @@ impl ComponentInfo {
     pub fn remove_events(&self) -> &BTreeSet<EventId> {
         &self.remove_events
     }
+
+    /// Returns the number of entities with this component.
+    pub(crate) fn entity_count(&self, archetypes: &Archetypes) -> usize {
+        self.member_of
+            .iter()
+            .filter_map(|&idx| archetypes.get(idx))
+            .map(|arch| arch.entity_count() as usize)
+            .sum()
+    }
+
+    /// Returns the memory used by this component across all archetypes.
+    pub(crate) fn memory(&self, archetypes: &Archetypes) -> ComponentMemory {
+        let buffers = if self.is_double_buffered { 2 } else { 1 };
+
+        let rows = self.entity_count(archetypes);
+
+        ComponentMemory {
+            bytes_allocated: self.bytes_allocated.load(Ordering::Relaxed),
+            bytes_used: rows * self.layout.size() * buffers,
+            archetype_count: self.member_of.len(),
+        }
+    }
 }
 
 /// Types which store data on [entities].
@@ -311,6 +553,27 @@ impl ComponentInfo {
 /// #[derive(Component)]
 /// #[component(immutable)] // Override the default mutability.
 /// struct FooCounter(i32);
+///
+/// // Double-buffered components keep a copy of their previous value, which can
+/// // be read with the `Previous` query. The type must be `Copy`.
+/// #[derive(Component, Clone, Copy)]
+/// #[component(double_buffer)]
+/// struct Velocity(f32, f32);
+///
+/// // Inserting a value equal to the current one leaves the component
+/// // untouched. The type must be `PartialEq`.
+/// #[derive(Component, PartialEq)]
+/// #[component(skip_identical_writes)]
+/// struct Health(u32);
+///
+/// // Records the name, offset and type of each field for runtime inspection.
+/// // Only available on structs.
+/// #[derive(Component)]
+/// #[component(reflect_fields)]
+/// struct Stats {
+///     strength: u32,
+///     speed: f32,
+/// }
 /// ```
 pub trait Component: Send + Sync + 'static {
     /// Whether or not this component is immutable.
@@ -318,8 +581,191 @@ pub trait Component: Send + Sync + 'static {
     /// Immutable components disallow mutable references, which can be used to
     /// ensure components are used in particular ways.
     const IS_IMMUTABLE: bool = false;
+
+    /// Whether or not this component is double-buffered.
+    ///
+    /// Double-buffered components store a second copy of every value, which is
+    /// updated from the current value by [`World::swap_buffers`] and read with
+    /// the [`Previous`] query. The copy is made bitwise, so a
+    /// [`DoubleBuffered`] can only be created for [`Copy`] types. Deriving with
+    /// `#[component(double_buffer)]` sets this to
+    /// `Some(DoubleBuffered::new())`.
+    ///
+    /// [`Previous`]: crate::query::Previous
+    const DOUBLE_BUFFERED: Option<DoubleBuffered<Self>> = None;
+
+    /// Function used to compare a newly inserted value against the
+    /// component's current value, if any.
+    ///
+    /// When this is `Some` and the two values are equal, [`Insert`] drops the
+    /// new value instead of overwriting the old one, so the component's memory
+    /// is not written to. [`Insert`] handlers still run as usual. Deriving with
+    /// `#[component(skip_identical_writes)]` uses the type's [`PartialEq`]
+    /// implementation.
+    ///
+    /// [`Mut::set`] also skips identical writes, leaving the component's
+    /// change tick untouched. Other writes made through mutable references
+    /// obtained from queries are not affected.
+    ///
+    /// [`Insert`]: crate::event::Insert
+    /// [`Mut::set`]: crate::query::Mut::set
+    const SKIP_IDENTICAL_WRITES: Option<EqFn> = None;
+
+    /// Calls `f` with the [`FieldInfo`] of each top-level field of this
+    /// component, in declaration order. The fields are stored in
+    /// [`ComponentInfo::fields`].
+    ///
+    /// The default implementation describes no fields. Deriving with
+    /// `#[component(reflect_fields)]` describes every field.
+    fn reflect_fields(f: &mut dyn FnMut(FieldInfo)) {
+        let _ = f;
+    }
+}
+
+/// Equality function for some data. The data may not necessarily have a type
+/// in Rust's type system.
+///
+/// The function pointer takes pointers to two values of the same type and
+/// returns whether they are equal. In order to be safe to call, both pointers
+/// must be correctly aligned and must point to initialized values of the
+/// correct type.
+pub type EqFn = unsafe fn(NonNull<u8>, NonNull<u8>) -> bool;
+
+/// Proof that values of the component `C` can be copied bitwise, which is
+/// required by [`Component::DOUBLE_BUFFERED`].
+///
+/// # Examples
+///
+/// ```
+/// use evenio::component::DoubleBuffered;
+/// use evenio::prelude::*;
+///
+/// #[derive(Clone, Copy)]
+/// struct Position(f32, f32);
+///
+/// impl Component for Position {
+///     const DOUBLE_BUFFERED: Option<DoubleBuffered<Self>> = Some(DoubleBuffered::new());
+/// }
+/// ```
+///
+/// Types which aren't [`Copy`] can't be double-buffered.
+///
+/// ```compile_fail
+/// use evenio::component::DoubleBuffered;
+/// use evenio::prelude::*;
+///
+/// struct Name(String);
+///
+/// impl Component for Name {
+///     const DOUBLE_BUFFERED: Option<DoubleBuffered<Self>> = Some(DoubleBuffered::new());
+/// }
+/// ```
+pub struct DoubleBuffered<C: ?Sized>(PhantomData<fn() -> C>);
+
+impl<C: Copy> DoubleBuffered<C> {
+    /// Creates a new `DoubleBuffered` for the [`Copy`] type `C`.
+    pub const fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<C: ?Sized> DoubleBuffered<C> {
+    /// Creates a new `DoubleBuffered` for `C` without checking that `C` is
+    /// [`Copy`].
+    ///
+    /// # Safety
+    ///
+    /// Copying a value of `C` bitwise must be sound, and `C` must not have any
+    /// drop glue.
+    pub const unsafe fn new_unchecked() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<C: Copy> Default for DoubleBuffered<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: ?Sized> Clone for DoubleBuffered<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C: ?Sized> Copy for DoubleBuffered<C> {}
+
+impl<C: ?Sized> fmt::Debug for DoubleBuffered<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DoubleBuffered<{}>", any::type_name::<C>())
+    }
+}
+
+/// A set of [`Component`]s which are added to an entity together, moving the
+/// entity into the archetype with all of them at once.
+///
+/// This is implemented for tuples of up to 15 components. A bundle must not
+/// contain the same component type more than once.
+///
+/// See [`World::spawn_bundles`] and [`InsertBundle`].
+///
+/// # Safety
+///
+/// [`add_components`](Bundle::add_components),
+/// [`component_ids`](Bundle::component_ids) and
+/// [`for_each_ptr`](Bundle::for_each_ptr) must visit the same components in the
+/// same order, and each pointer must point to the value of the component whose
+/// ID was visited.
+///
+/// [`InsertBundle`]: crate::event::InsertBundle
+pub unsafe trait Bundle: Send + Sync + 'static {
+    /// Adds each component type of the bundle to `world`, if it isn't already,
+    /// and pushes its [`ComponentId`] to `ids`.
+    fn add_components(world: &mut World, ids: &mut Vec<ComponentId>);
+
+    /// Pushes the [`ComponentId`] of each component type of the bundle to
+    /// `ids`. Returns `false` if one of them is not in `components`.
+    fn component_ids(components: &Components, ids: &mut Vec<ComponentId>) -> bool;
+
+    /// Calls `f` with a pointer to each component of the bundle.
+    fn for_each_ptr(&mut self, f: impl FnMut(NonNull<u8>));
+}
+
+macro_rules! impl_bundle_tuple {
+    ($(($C:ident, $c:ident)),*) => {
+        unsafe impl<$($C: Component),*> Bundle for ($($C,)*) {
+            fn add_components(world: &mut World, ids: &mut Vec<ComponentId>) {
+                $(
+                    ids.push(world.add_component::<$C>());
+                )*
+            }
+
+            fn component_ids(components: &Components, ids: &mut Vec<ComponentId>) -> bool {
+                $(
+                    let Some(info) = components.get_by_type_id(TypeId::of::<$C>()) else {
+                        return false;
+                    };
+
+                    ids.push(info.id());
+                )*
+
+                true
+            }
+
+            fn for_each_ptr(&mut self, mut f: impl FnMut(NonNull<u8>)) {
+                let ($($c,)*) = self;
+
+                $(
+                    f(NonNull::from($c).cast());
+                )*
+            }
+        }
+    };
 }
 
+all_tuples!(impl_bundle_tuple, 1, 15, C, c);
+
 /// Data needed to create a new component.
 #[derive(Clone, Debug)]
 pub struct ComponentDescriptor {
@@ -331,12 +777,24 @@ pub struct ComponentDescriptor {
     /// The [`TypeId`] of this component, if any.
     pub type_id: Option<TypeId>,
     /// The [`Layout`] of the component.
+    ///
+    /// The size does not need to be a multiple of the alignment, which is
+    /// common for types described at runtime. The stored layout is padded
+    /// (see [`ComponentInfo::layout`]), but only `layout.size()` bytes are read
+    /// when a component value is copied into the world.
     pub layout: Layout,
     /// The [`DropFn`] of the component. This is passed a pointer to the
     /// component in order to drop it.
     pub drop: DropFn,
     /// If this component is [immutable](Component::IS_IMMUTABLE).
     pub is_immutable: bool,
+    /// If this component is [double-buffered](Component::DOUBLE_BUFFERED).
+    pub is_double_buffered: bool,
+    /// The [`EqFn`] used to [skip identical
+    /// writes](Component::SKIP_IDENTICAL_WRITES), if any.
+    pub skip_identical_writes: Option<EqFn>,
+    /// The [fields](Component::reflect_fields) of the component.
+    pub fields: Vec<FieldInfo>,
 }
 
 /// Lightweight identifier for a component type.
@@ -394,6 +852,30 @@ unsafe impl SparseIndex for ComponentIdx {
     }
 }
 
+/// An error returned by untyped component functions such as
+/// [`World::insert_dynamic`] when given a [`ComponentId`] which does not
+/// identify a live component, usually because the component was removed.
+///
+/// The index of a removed component may be reused by a new component with a
+/// different generation count, so a stale ID is never mistaken for the new
+/// component.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct StaleComponentId(pub ComponentId);
+
+impl fmt::Display for StaleComponentId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "component {:?} does not exist (it may have been removed)",
+            self.0
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl core::error::Error for StaleComponentId {}
+
 /// An event sent immediately after a new component is added to the world.
 /// Contains the ID of the added component.
 #[derive(Event, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
@@ -406,6 +888,8 @@ pub struct RemoveComponent(pub ComponentId);
 
 #[cfg(test)]
 mod tests {
+    use alloc::sync::Arc;
+
     use crate::prelude::*;
 
     #[derive(Event)]
@@ -454,6 +938,70 @@ mod tests {
         assert_eq!(world.archetypes().len(), 1);
     }
 
+    #[test]
+    fn skip_identical_writes() {
+        /// Equality ignores `writes` so that skipped writes are observable.
+        #[derive(Component, Debug)]
+        #[component(skip_identical_writes)]
+        struct Hp {
+            value: u32,
+            writes: u32,
+        }
+
+        impl PartialEq for Hp {
+            fn eq(&self, other: &Self) -> bool {
+                self.value == other.value
+            }
+        }
+
+        let mut world = World::new();
+        let e = world.spawn();
+
+        world.insert(
+            e,
+            Hp {
+                value: 10,
+                writes: 1,
+            },
+        );
+        world.insert(
+            e,
+            Hp {
+                value: 10,
+                writes: 2,
+            },
+        );
+        assert_eq!(world.get::<Hp>(e).unwrap().writes, 1);
+
+        world.insert(
+            e,
+            Hp {
+                value: 5,
+                writes: 3,
+            },
+        );
+        assert_eq!(world.get::<Hp>(e).unwrap().writes, 3);
+    }
+
+    #[test]
+    fn skip_identical_writes_drops_new_value() {
+        #[derive(Component, PartialEq)]
+        #[component(skip_identical_writes)]
+        struct Shared(Arc<u32>);
+
+        let value = Arc::new(1);
+
+        let mut world = World::new();
+        let e = world.spawn();
+
+        world.insert(e, Shared(value.clone()));
+        world.insert(e, Shared(value.clone()));
+        assert_eq!(Arc::strong_count(&value), 2);
+
+        world.despawn(e);
+        assert_eq!(Arc::strong_count(&value), 1);
+    }
+
     #[test]
     fn component_member_of() {
         let mut world = World::new();
@@ -497,4 +1045,102 @@ mod tests {
 
         assert_eq!(world.components()[c1].member_of.len(), 1);
     }
+
+    #[test]
+    fn component_memory() {
+        #[derive(Component)]
+        struct A(#[allow(dead_code)] u64);
+
+        #[derive(Component)]
+        struct B;
+
+        let mut world = World::new();
+
+        let a = world.add_component::<A>();
+
+        let entities: Vec<_> = (0..100)
+            .map(|i| {
+                let e = world.spawn();
+                world.insert(e, A(i));
+                e
+            })
+            .collect();
+
+        // Columns grow by doubling.
+        let memory = world.component_memory(a).unwrap();
+        assert_eq!(memory.bytes_allocated, 128 * 8);
+        assert_eq!(memory.bytes_used, 100 * 8);
+        assert_eq!(memory.archetype_count, 1);
+
+        // Moving to another archetype allocates a second column. The first
+        // column keeps its capacity.
+        for &e in &entities {
+            world.insert(e, B);
+        }
+
+        let memory = world.component_memory(a).unwrap();
+        assert_eq!(memory.bytes_allocated, 2 * 128 * 8);
+        assert_eq!(memory.bytes_used, 100 * 8);
+        assert_eq!(memory.archetype_count, 2);
+
+        for &e in &entities {
+            world.despawn(e);
+        }
+
+        let memory = world.component_memory(a).unwrap();
+        assert_eq!(memory.bytes_allocated, 2 * 128 * 8);
+        assert_eq!(memory.bytes_used, 0);
+
+        // Removing `B` removes the archetype `{A, B}` and frees its columns.
+        let b = world.add_component::<B>();
+        world.remove_component(b);
+
+        let memory = world.component_memory(a).unwrap();
+        assert_eq!(memory.bytes_allocated, 128 * 8);
+        assert_eq!(memory.archetype_count, 1);
+
+        world.remove_component(a);
+        assert!(world.component_memory(a).is_none());
+    }
+
+    #[test]
+    fn component_size_warning() {
+        use std::sync::Mutex;
+
+        #[derive(Component)]
+        struct Small(#[allow(dead_code)] u64);
+
+        #[derive(Component)]
+        struct Big(#[allow(dead_code)] [u8; 4096]);
+
+        #[derive(Component)]
+        struct Huge(#[allow(dead_code)] [u8; 8192]);
+
+        let mut world = World::new();
+
+        let big = world.add_component::<Big>();
+
+        let warned = Arc::new(Mutex::new(vec![]));
+
+        let w = warned.clone();
+        world.set_component_size_warning(1024, move |info| w.lock().unwrap().push(info.id()));
+
+        // Components added earlier are checked immediately.
+        assert_eq!(*warned.lock().unwrap(), [big]);
+
+        world.add_component::<Small>();
+        let huge = world.add_component::<Huge>();
+        world.add_component::<Huge>();
+
+        let e = world.spawn();
+        world.insert(e, Huge([0; 8192]));
+
+        assert_eq!(*warned.lock().unwrap(), [big, huge]);
+
+        // Components are only reported once.
+        let w = warned.clone();
+        world.set_component_size_warning(16, move |info| w.lock().unwrap().push(info.id()));
+
+        assert_eq!(*warned.lock().unwrap(), [big, huge]);
+    }
 }
@@ -0,0 +1,349 @@
+//! Suppression of duplicate events.
+//!
+//! See [`World::dedup_window`] for more information.
+//!
+//! [`World::dedup_window`]: crate::world::World::dedup_window
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::Hash;
+use core::ptr::NonNull;
+
+use ahash::RandomState;
+use hashbrown::HashTable;
+
+use crate::blob_vec::BlobVec;
+use crate::component::EqFn;
+use crate::drop::drop_fn_of;
+use crate::entity::EntityId;
+use crate::event::Event;
+
+/// How long a sent event suppresses equal events. See
+/// [`World::dedup_window`].
+///
+/// [`World::dedup_window`]: crate::world::World::dedup_window
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Window {
+    /// Until the end of the cascade of events the event was sent in.
+    Cascade,
+    /// Until the given number of cascades have started, counting from the
+    /// cascade of the first event seen in the window. Must be nonzero.
+    Cascades(u32),
+    /// Until [`World::reset_dedup`] is called.
+    ///
+    /// [`World::reset_dedup`]: crate::world::World::reset_dedup
+    UntilExplicitReset,
+}
+
+/// Statistics of the deduplication of an event, returned by
+/// [`World::dedup_stats`].
+///
+/// [`World::dedup_stats`]: crate::world::World::dedup_stats
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DedupStats {
+    /// The window of the deduplication.
+    pub window: Window,
+    /// Number of events which were delivered because no equal event was sent
+    /// in the current window.
+    pub delivered: u64,
+    /// Number of events which were dropped because an equal event was sent
+    /// in the current window.
+    pub suppressed: u64,
+    /// Number of distinct events seen in the current window.
+    pub distinct: usize,
+}
+
+/// Type-erased equality, hashing, and cloning of an event.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct PayloadFns {
+    eq: EqFn,
+    hash: unsafe fn(NonNull<u8>, &RandomState) -> u64,
+    /// Pushes a clone of the event onto a [`BlobVec`] of events.
+    clone_into: unsafe fn(NonNull<u8>, &mut BlobVec),
+}
+
+impl PayloadFns {
+    pub(crate) fn of<E: Event + Eq + Hash + Clone>() -> Self {
+        Self {
+            eq: |a, b| unsafe { *a.cast::<E>().as_ref() == *b.cast::<E>().as_ref() },
+            hash: |ptr, state| state.hash_one(unsafe { ptr.cast::<E>().as_ref() }),
+            clone_into: |ptr, values| unsafe {
+                // Clone before pushing in case `clone` unwinds.
+                let value = ptr.cast::<E>().as_ref().clone();
+                values.push().cast::<E>().as_ptr().write(value);
+            },
+        }
+    }
+}
+
+/// The set of events seen in the current window, stored in the
+/// [`EventInfo`] of the event.
+///
+/// [`EventInfo`]: crate::event::EventInfo
+pub(crate) struct Dedup {
+    window: Window,
+    fns: PayloadFns,
+    hasher: RandomState,
+    /// Rows of `values` by hash.
+    table: HashTable<usize>,
+    /// Clones of the distinct events seen in the current window.
+    values: BlobVec,
+    /// Targets of the events in `values`, or `None` for untargeted events.
+    targets: Vec<Option<EntityId>>,
+    /// The cascade of the first event seen in the current window.
+    opened: u64,
+    delivered: u64,
+    suppressed: u64,
+}
+
+impl Dedup {
+    pub(crate) fn new<E: Event + Eq + Hash + Clone>(window: Window) -> Self {
+        Self {
+            window,
+            fns: PayloadFns::of::<E>(),
+            hasher: RandomState::new(),
+            table: HashTable::new(),
+            // SAFETY: The drop fn is for `E`.
+            values: unsafe { BlobVec::new(core::alloc::Layout::new::<E>(), drop_fn_of::<E>()) },
+            targets: vec![],
+            opened: 0,
+            delivered: 0,
+            suppressed: 0,
+        }
+    }
+
+    pub(crate) fn stats(&self) -> DedupStats {
+        DedupStats {
+            window: self.window,
+            delivered: self.delivered,
+            suppressed: self.suppressed,
+            distinct: self.values.len(),
+        }
+    }
+
+    /// Forgets the events seen in the current window. Allocations are kept.
+    pub(crate) fn reset(&mut self) {
+        self.table.clear();
+        self.values.clear();
+        self.targets.clear();
+    }
+
+    /// Returns `true` if an event equal to `event` with the same target was
+    /// already seen in the current window, which is first closed if it expired
+    /// by `cascade`. Otherwise, the event is remembered.
+    ///
+    /// # Safety
+    ///
+    /// `event` must point to an event of the type this was created with.
+    pub(crate) unsafe fn is_duplicate(
+        &mut self,
+        event: NonNull<u8>,
+        target: Option<EntityId>,
+        cascade: u64,
+    ) -> bool {
+        let expired = match self.window {
+            Window::Cascade => cascade != self.opened,
+            Window::Cascades(n) => cascade.wrapping_sub(self.opened) >= u64::from(n),
+            Window::UntilExplicitReset => false,
+        };
+
+        if expired {
+            self.reset();
+        }
+
+        if self.values.len() == 0 {
+            self.opened = cascade;
+        }
+
+        let Self {
+            fns,
+            hasher,
+            table,
+            values,
+            targets,
+            ..
+        } = self;
+
+        let elem_size = values.elem_layout().size();
+        let row_ptr = |values: &BlobVec, row: usize| {
+            NonNull::new_unchecked(values.as_ptr().as_ptr().add(row * elem_size))
+        };
+
+        let hash_of =
+            |event, target: Option<EntityId>| hasher.hash_one((target, (fns.hash)(event, hasher)));
+
+        let hash = hash_of(event, target);
+
+        if table
+            .find(hash, |&row| {
+                targets[row] == target && (fns.eq)(row_ptr(values, row), event)
+            })
+            .is_some()
+        {
+            self.suppressed += 1;
+            return true;
+        }
+
+        (fns.clone_into)(event, values);
+        targets.push(target);
+
+        table.insert_unique(hash, values.len() - 1, |&row| {
+            hash_of(row_ptr(values, row), targets[row])
+        });
+
+        self.delivered += 1;
+
+        false
+    }
+}
+
+// SAFETY: Events are guaranteed `Send` and `Sync`.
+unsafe impl Send for Dedup {}
+unsafe impl Sync for Dedup {}
+
+impl fmt::Debug for Dedup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dedup")
+            .field("window", &self.window)
+            .field("opened", &self.opened)
+            .field("delivered", &self.delivered)
+            .field("suppressed", &self.suppressed)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Event, Clone, PartialEq, Eq, Hash)]
+    struct Dirty(u32);
+
+    /// Ignores the target when compared.
+    #[derive(Event, Clone)]
+    struct Poke(#[event(target)] EntityId);
+
+    impl PartialEq for Poke {
+        fn eq(&self, _: &Self) -> bool {
+            true
+        }
+    }
+
+    impl Eq for Poke {}
+
+    impl Hash for Poke {
+        fn hash<H: core::hash::Hasher>(&self, _: &mut H) {}
+    }
+
+    #[derive(Event)]
+    struct SendTwice(u32);
+
+    #[derive(Component, Default)]
+    struct Log(Vec<u32>);
+
+    fn world_with_log() -> (World, EntityId) {
+        let mut world = World::new();
+
+        let log = world.spawn();
+        world.insert(log, Log::default());
+
+        world
+            .add_handler(|r: Receiver<Dirty>, Single(log): Single<&mut Log>| log.0.push(r.event.0));
+
+        world.add_handler(|r: Receiver<SendTwice>, mut s: Sender<Dirty>| {
+            s.send(Dirty(r.event.0));
+            s.send(Dirty(r.event.0));
+        });
+
+        (world, log)
+    }
+
+    #[test]
+    fn until_explicit_reset() {
+        let (mut world, log) = world_with_log();
+
+        world.dedup_window::<Dirty>(Window::UntilExplicitReset);
+
+        world.send(Dirty(1));
+        world.send(Dirty(2));
+        world.send(Dirty(1));
+        world.send(SendTwice(2));
+
+        assert_eq!(world.get::<Log>(log).unwrap().0, [1, 2]);
+
+        world.reset_dedup::<Dirty>();
+        world.send(Dirty(1));
+        world.send(Dirty(1));
+
+        assert_eq!(world.get::<Log>(log).unwrap().0, [1, 2, 1]);
+        assert_eq!(
+            world.dedup_stats::<Dirty>(),
+            Some(DedupStats {
+                window: Window::UntilExplicitReset,
+                delivered: 3,
+                suppressed: 4,
+                distinct: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn cascade_windows() {
+        let (mut world, log) = world_with_log();
+
+        world.dedup_window::<Dirty>(Window::Cascade);
+
+        world.send(SendTwice(1));
+        world.send(SendTwice(1));
+
+        assert_eq!(world.get::<Log>(log).unwrap().0, [1, 1]);
+        assert_eq!(world.dedup_stats::<Dirty>().unwrap().suppressed, 2);
+
+        world.dedup_window::<Dirty>(Window::Cascades(2));
+        world.get_mut::<Log>(log).unwrap().0.clear();
+
+        world.send(Dirty(1));
+        world.send(Dirty(1));
+        world.send(Dirty(1));
+        world.send(Dirty(1));
+
+        assert_eq!(world.get::<Log>(log).unwrap().0, [1, 1]);
+        assert_eq!(world.dedup_stats::<Dirty>().unwrap().suppressed, 2);
+    }
+
+    #[test]
+    fn targets_are_compared() {
+        let mut world = World::new();
+
+        let log = world.spawn();
+        world.insert(log, Log::default());
+
+        world.add_handler(
+            |r: Receiver<Poke, EntityId>, Single(log): Single<&mut Log>| {
+                log.0.push(r.query.index().0)
+            },
+        );
+
+        world.dedup_window::<Poke>(Window::UntilExplicitReset);
+
+        let a = world.spawn();
+        let b = world.spawn();
+
+        world.send(Poke(a));
+        world.send(Poke(b));
+        world.send(Poke(a));
+
+        assert_eq!(world.get::<Log>(log).unwrap().0, [a.index().0, b.index().0]);
+        assert_eq!(world.dedup_stats::<Poke>().unwrap().distinct, 2);
+    }
+
+    #[test]
+    #[should_panic = "must be nonzero"]
+    fn empty_window() {
+        World::new().dedup_window::<Dirty>(Window::Cascades(0));
+    }
+}
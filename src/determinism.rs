@@ -0,0 +1,117 @@
+//! Support for deterministic worlds, such as those used in lockstep
+//! networking.
+//!
+//! See [`World::new_deterministic`] for more information.
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::component::Component;
+use crate::event::{Event, Insert, Remove};
+use crate::world::World;
+
+/// An ordered list of the components and events used by a deterministic
+/// [`World`].
+///
+/// Types are registered in the order they appear in the manifest, so two worlds
+/// created from the same manifest assign identical [`ComponentId`]s and
+/// [`EventId`]s regardless of the order in which types are later touched.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::determinism::Manifest;
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct Position;
+///
+/// #[derive(Event)]
+/// struct Tick;
+///
+/// let manifest = Manifest::new().component::<Position>().event::<Tick>();
+///
+/// let mut world = World::new_deterministic(&manifest);
+/// ```
+///
+/// [`ComponentId`]: crate::component::ComponentId
+/// [`EventId`]: crate::event::EventId
+#[derive(Clone, Default)]
+pub struct Manifest {
+    entries: Vec<fn(&mut World)>,
+}
+
+impl Manifest {
+    /// Creates an empty manifest.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends component `C` to the manifest. The [`Insert<C>`] and
+    /// [`Remove<C>`] events are included as well.
+    pub fn component<C: Component>(mut self) -> Self {
+        self.entries.push(|world| {
+            world.add_component::<C>();
+            world.add_event::<Insert<C>>();
+            world.add_event::<Remove<C>>();
+        });
+        self
+    }
+
+    /// Appends event `E` to the manifest.
+    pub fn event<E: Event>(mut self) -> Self {
+        self.entries.push(|world| {
+            world.add_event::<E>();
+        });
+        self
+    }
+
+    /// Returns the number of entries in the manifest.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the manifest is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Registers the built-in events followed by every entry in the manifest.
+    pub(crate) fn apply(&self, world: &mut World) {
+        world.add_builtin_events();
+
+        for f in &self.entries {
+            f(world);
+        }
+    }
+}
+
+impl fmt::Debug for Manifest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Manifest")
+            .field("len", &self.entries.len())
+            .finish()
+    }
+}
+
+/// 64-bit FNV-1a hasher. Unlike the hashers used elsewhere in the library, the
+/// output is identical on every platform.
+pub(crate) struct StableHasher(u64);
+
+impl StableHasher {
+    pub(crate) const fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub(crate) fn write_u32(&mut self, n: u32) {
+        for byte in n.to_le_bytes() {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub(crate) const fn finish(&self) -> u64 {
+        self.0
+    }
+}
@@ -0,0 +1,263 @@
+//! Coalesced notifications of changed entities.
+//!
+//! See [`World::enable_dirty_tracking`] for more information.
+//!
+//! [`World::enable_dirty_tracking`]: crate::world::World::enable_dirty_tracking
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use ahash::RandomState;
+
+use crate::archetype::{Archetype, Archetypes};
+use crate::bit_set::BitSet;
+use crate::component::ComponentIdx;
+use crate::entity::EntityId;
+use crate::event::Event;
+use crate::map::IndexSet;
+
+/// Which changes mark an entity as dirty. See
+/// [`World::enable_dirty_tracking`].
+///
+/// [`World::enable_dirty_tracking`]: crate::world::World::enable_dirty_tracking
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum DirtyScope {
+    /// Spawning or despawning the entity, or adding or removing any of its
+    /// components.
+    Structural,
+    /// Structural changes, as well as writes to component values which are
+    /// recorded by change ticks (see [`World::change_tick`]).
+    ///
+    /// Finding changed values requires checking the change tick of every
+    /// tracked component at the end of each cascade.
+    ///
+    /// [`World::change_tick`]: crate::world::World::change_tick
+    StructuralAndValues,
+}
+
+/// How dirty entities are reported. See [`World::enable_dirty_tracking`].
+///
+/// [`World::enable_dirty_tracking`]: crate::world::World::enable_dirty_tracking
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum DirtyDelivery {
+    /// An [`EntitiesDirty`] event is sent at the end of every cascade which
+    /// changed entities.
+    Event,
+    /// Dirty entities accumulate until they are taken with
+    /// [`World::take_dirty_entities`].
+    ///
+    /// [`World::take_dirty_entities`]: crate::world::World::take_dirty_entities
+    Pull,
+}
+
+/// Sent at the end of a cascade of events with every entity which changed
+/// during the cascade, if [`DirtyDelivery::Event`] is used. See
+/// [`World::enable_dirty_tracking`].
+///
+/// Changes made by handlers of this event are reported at the end of the next
+/// cascade.
+///
+/// [`World::enable_dirty_tracking`]: crate::world::World::enable_dirty_tracking
+#[derive(Event, Clone, PartialEq, Eq, Debug)]
+pub struct EntitiesDirty {
+    /// The entities which changed, in the order they first changed. Each
+    /// entity appears once. Entities which were despawned are included.
+    pub entities: Vec<EntityId>,
+}
+
+#[derive(Debug)]
+pub(crate) struct DirtyTracker {
+    pub(crate) scope: DirtyScope,
+    pub(crate) delivery: DirtyDelivery,
+    /// Components whose changes don't mark an entity as dirty.
+    excluded: BitSet<ComponentIdx>,
+    entities: IndexSet<EntityId>,
+    /// Component values with a change tick above this were not checked yet.
+    scanned_tick: u64,
+    /// Whether an [`EntitiesDirty`] event is being sent.
+    pub(crate) delivering: bool,
+}
+
+impl DirtyTracker {
+    pub(crate) fn new(scope: DirtyScope, delivery: DirtyDelivery, change_tick: u64) -> Self {
+        Self {
+            scope,
+            delivery,
+            excluded: BitSet::new(),
+            entities: IndexSet::with_hasher(RandomState::new()),
+            scanned_tick: change_tick,
+            delivering: false,
+        }
+    }
+
+    pub(crate) fn exclude(&mut self, component: ComponentIdx) {
+        self.excluded.insert(component);
+    }
+
+    /// Records `entity` moving from archetype `src` to `dst`. The entity is
+    /// only dirty if a component which isn't excluded was added or removed.
+    pub(crate) fn on_move(
+        &mut self,
+        entity: EntityId,
+        src: Option<&Archetype>,
+        dst: Option<&Archetype>,
+    ) {
+        if let (Some(src), Some(dst)) = (src, dst) {
+            let src = src.component_indices();
+            let dst = dst.component_indices();
+
+            // Both lists are sorted, so components in one but not the other
+            // can be found by merging them.
+            let (mut i, mut j) = (0, 0);
+            let mut changed = false;
+
+            while i < src.len() || j < dst.len() {
+                let idx = match (src.get(i), dst.get(j)) {
+                    (Some(a), Some(b)) if a == b => {
+                        i += 1;
+                        j += 1;
+                        continue;
+                    }
+                    (Some(&a), Some(&b)) if a < b => {
+                        i += 1;
+                        a
+                    }
+                    (Some(&a), None) => {
+                        i += 1;
+                        a
+                    }
+                    (_, Some(&b)) => {
+                        j += 1;
+                        b
+                    }
+                    (None, None) => break,
+                };
+
+                if !self.excluded.contains(idx) {
+                    changed = true;
+                    break;
+                }
+            }
+
+            if !changed {
+                return;
+            }
+        }
+
+        self.entities.insert(entity);
+    }
+
+    /// Marks every entity with a component value which changed since the last
+    /// scan, if values are tracked.
+    pub(crate) fn scan_values(&mut self, archetypes: &Archetypes) {
+        if self.scope != DirtyScope::StructuralAndValues {
+            return;
+        }
+
+        let since = self.scanned_tick;
+
+        for arch in archetypes.iter() {
+            for (&idx, col) in arch.component_indices().iter().zip(arch.columns()) {
+                if self.excluded.contains(idx) {
+                    continue;
+                }
+
+                for (&tick, &entity) in col.change_ticks().iter().zip(arch.entity_ids()) {
+                    if tick > since {
+                        self.entities.insert(entity);
+                    }
+                }
+            }
+        }
+
+        self.scanned_tick = archetypes.change_tick();
+    }
+
+    pub(crate) fn take(&mut self) -> Vec<EntityId> {
+        self.entities.drain(..).collect()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use crate::query::Mut;
+
+    #[derive(Component)]
+    struct Health(u32);
+
+    #[derive(Component)]
+    struct Sprite;
+
+    #[derive(Event)]
+    struct Touch(EntityId);
+
+    #[derive(Component)]
+    struct Reports(Vec<Vec<EntityId>>);
+
+    #[test]
+    fn coalesce_multiple_touches() {
+        let mut world = World::new();
+        world.enable_dirty_tracking(DirtyScope::Structural, DirtyDelivery::Event);
+
+        let log = world.spawn();
+        world.insert(log, Reports(vec![]));
+
+        world.add_handler(
+            |r: Receiver<EntitiesDirty>, Single(reports): Single<&mut Reports>| {
+                reports.0.push(r.event.entities.clone());
+            },
+        );
+
+        world.add_handler(
+            |r: Receiver<Touch>, mut s: Sender<(Insert<Health>, Remove<Health>)>| {
+                for _ in 0..50 {
+                    s.insert(r.event.0, Health(1));
+                    s.remove::<Health>(r.event.0);
+                }
+            },
+        );
+
+        let e = world.spawn();
+        let reports = |world: &World| world.get::<Reports>(log).unwrap().0.clone();
+
+        // Reports the spawn of `e`.
+        assert_eq!(reports(&world), [vec![e]]);
+
+        world.send(Touch(e));
+        assert_eq!(reports(&world), [vec![e], vec![e]]);
+    }
+
+    #[test]
+    fn exclusion_list() {
+        let mut world = World::new();
+        world.enable_dirty_tracking(DirtyScope::StructuralAndValues, DirtyDelivery::Pull);
+        world.exclude_from_dirty_tracking::<Sprite>();
+
+        let a = world.spawn();
+        let b = world.spawn();
+        world.insert(b, Health(10));
+        assert_eq!(world.take_dirty_entities(), [a, b]);
+
+        world.insert(a, Sprite);
+        world.remove::<Sprite>(a);
+        assert_eq!(world.take_dirty_entities(), []);
+
+        world.add_handler(|r: Receiver<Touch>, mut f: Fetcher<Mut<Health>>| {
+            f.get_mut(r.event.0).unwrap().0 -= 1;
+        });
+
+        world.send(Touch(b));
+        world.send(Touch(b));
+        assert_eq!(world.take_dirty_entities(), [b]);
+        assert_eq!(world.take_dirty_entities(), []);
+
+        world.despawn(a);
+        assert_eq!(world.take_dirty_entities(), [a]);
+    }
+}
@@ -0,0 +1,475 @@
+//! Components holding trait objects.
+//!
+//! See [`DynComponent`] for more information.
+
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+use core::any::{self, TypeId};
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::{fmt, mem, ptr};
+
+use crate::access::ComponentAccessExpr;
+use crate::archetype::{Archetype, ArchetypeRow};
+use crate::component::{Component, ComponentIdx};
+use crate::handler::{Config, InitError};
+use crate::query::{ColumnPtr, Query, ReadOnlyQuery};
+use crate::world::World;
+
+/// A component holding a boxed trait object `T` along with the [`TypeId`] of
+/// the concrete type in the box.
+///
+/// Register the component with [`World::init_dyn_component`], or just insert
+/// it. [`AsDyn`] and [`AsDynMut`] fetch the trait object directly, and
+/// [`IsConcrete`] selects entities by the concrete type.
+///
+/// Because Rust cannot coerce a `Box<U>` into a `Box<T>` for a generic `T`,
+/// the constructor takes a function which performs the coercion. A closure
+/// returning its argument is enough, as long as `T` is known, for instance
+/// from `DynComponent::<dyn Behavior>::new`.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::dyn_component::{AsDyn, DynComponent};
+/// use evenio::prelude::*;
+///
+/// trait Behavior: Send + Sync {
+///     fn name(&self) -> &str;
+/// }
+///
+/// struct Patrol;
+///
+/// impl Behavior for Patrol {
+///     fn name(&self) -> &str {
+///         "patrol"
+///     }
+/// }
+///
+/// #[derive(Event)]
+/// struct Tick;
+///
+/// let mut world = World::new();
+///
+/// let e = world.spawn();
+/// world.insert(
+///     e,
+///     DynComponent::<dyn Behavior>::new(Box::new(Patrol), |b| b),
+/// );
+///
+/// world.add_handler(|_: Receiver<Tick>, f: Fetcher<AsDyn<dyn Behavior>>| {
+///     for behavior in f {
+///         println!("{}", behavior.name());
+///     }
+/// });
+///
+/// world.send(Tick);
+/// ```
+///
+/// [`World::init_dyn_component`]: crate::world::World::init_dyn_component
+pub struct DynComponent<T: ?Sized> {
+    value: Box<T>,
+    /// The `TypeId` of the value in the box. Stored in the row alongside the
+    /// box so it stays correct as the component is moved or replaced.
+    concrete: TypeId,
+    concrete_name: &'static str,
+}
+
+impl<T: ?Sized> DynComponent<T> {
+    /// Creates a new component from a boxed value of concrete type `U`.
+    /// `coerce` must convert the box into a `Box<T>`, which is usually done
+    /// with `|b| b`.
+    pub fn new<U: 'static, F>(value: Box<U>, coerce: F) -> Self
+    where
+        F: FnOnce(Box<U>) -> Box<T>,
+    {
+        Self {
+            value: coerce(value),
+            concrete: TypeId::of::<U>(),
+            concrete_name: any::type_name::<U>(),
+        }
+    }
+
+    /// Replaces the value with a boxed value of a possibly different
+    /// concrete type `U`. Returns the previous value.
+    pub fn set<U: 'static, F>(&mut self, value: Box<U>, coerce: F) -> Box<T>
+    where
+        F: FnOnce(Box<U>) -> Box<T>,
+    {
+        let new = Self::new(value, coerce);
+        self.concrete = new.concrete;
+        self.concrete_name = new.concrete_name;
+        mem::replace(&mut self.value, new.value)
+    }
+
+    /// Returns the [`TypeId`] of the concrete type of the value.
+    pub fn concrete_type_id(&self) -> TypeId {
+        self.concrete
+    }
+
+    /// Returns the name of the concrete type of the value.
+    ///
+    /// This name is intended for debugging purposes and should not be relied
+    /// upon for correctness.
+    pub fn concrete_type_name(&self) -> &'static str {
+        self.concrete_name
+    }
+
+    /// Returns `true` if the concrete type of the value is `U`.
+    pub fn is<U: 'static>(&self) -> bool {
+        self.concrete == TypeId::of::<U>()
+    }
+
+    /// Returns `true` if the concrete type of the value at `ptr` is `U`,
+    /// without creating a reference to the value.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to an initialized `DynComponent<T>` whose `concrete`
+    /// field is not being written to.
+    pub(crate) unsafe fn is_at<U: 'static>(ptr: *const Self) -> bool {
+        ptr::addr_of!((*ptr).concrete).read() == TypeId::of::<U>()
+    }
+
+    /// Returns a reference to the value if its concrete type is `U`.
+    pub fn downcast_ref<U: 'static>(&self) -> Option<&U> {
+        // SAFETY: The box was created from a `Box<U>`, so the data pointer of
+        // the trait object points to a `U`.
+        self.is::<U>()
+            .then(|| unsafe { &*(&*self.value as *const T).cast::<U>() })
+    }
+
+    /// Returns a mutable reference to the value if its concrete type is `U`.
+    pub fn downcast_mut<U: 'static>(&mut self) -> Option<&mut U> {
+        // SAFETY: See `downcast_ref`.
+        self.is::<U>()
+            .then(|| unsafe { &mut *(&mut *self.value as *mut T).cast::<U>() })
+    }
+
+    /// Consumes the component and returns the boxed value.
+    pub fn into_inner(self) -> Box<T> {
+        self.value
+    }
+}
+
+impl<T: ?Sized + Send + Sync + 'static> Component for DynComponent<T> {}
+
+impl<T: ?Sized> Deref for DynComponent<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T: ?Sized> DerefMut for DynComponent<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for DynComponent<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynComponent")
+            .field("concrete", &self.concrete_name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A [`Query`] for the trait object of the [`DynComponent<T>`] component,
+/// returning `&T`.
+pub struct AsDyn<T: ?Sized>(PhantomData<fn() -> Box<T>>);
+
+unsafe impl<T: ?Sized + Send + Sync + 'static> Query for AsDyn<T> {
+    type Item<'a> = &'a T;
+
+    type ArchState = ColumnPtr<DynComponent<T>>;
+
+    type State = ComponentIdx;
+
+    fn init(
+        world: &mut World,
+        config: &mut Config,
+    ) -> Result<(ComponentAccessExpr, Self::State), InitError> {
+        <&DynComponent<T>>::init(world, config)
+    }
+
+    fn new_state(world: &mut World) -> Self::State {
+        <&DynComponent<T>>::new_state(world)
+    }
+
+    fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
+        <&DynComponent<T>>::new_arch_state(arch, state)
+    }
+
+    unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::Item<'a> {
+        &<&DynComponent<T>>::get(state, row).value
+    }
+}
+
+unsafe impl<T: ?Sized + Send + Sync + 'static> ReadOnlyQuery for AsDyn<T> {}
+
+impl<T: ?Sized> fmt::Debug for AsDyn<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AsDyn<{}>", any::type_name::<T>())
+    }
+}
+
+/// A [`Query`] for the trait object of the [`DynComponent<T>`] component,
+/// returning `&mut T`.
+///
+/// The concrete type of the value cannot be changed through the reference,
+/// so it remains accurate.
+pub struct AsDynMut<T: ?Sized>(PhantomData<fn() -> Box<T>>);
+
+unsafe impl<T: ?Sized + Send + Sync + 'static> Query for AsDynMut<T> {
+    type Item<'a> = &'a mut T;
+
+    type ArchState = (ColumnPtr<DynComponent<T>>, ColumnPtr<u64>, ColumnPtr<u64>);
+
+    type State = ComponentIdx;
+
+    fn init(
+        world: &mut World,
+        config: &mut Config,
+    ) -> Result<(ComponentAccessExpr, Self::State), InitError> {
+        <&mut DynComponent<T>>::init(world, config)
+    }
+
+    fn new_state(world: &mut World) -> Self::State {
+        <&mut DynComponent<T>>::new_state(world)
+    }
+
+    fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
+        <&mut DynComponent<T>>::new_arch_state(arch, state)
+    }
+
+    unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::Item<'a> {
+        &mut <&mut DynComponent<T>>::get(state, row).value
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for AsDynMut<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AsDynMut<{}>", any::type_name::<T>())
+    }
+}
+
+/// A filter matching entities whose [`DynComponent<T>`] holds a value of
+/// concrete type `U`.
+///
+/// The concrete type is not part of the archetype, so `IsConcrete` is not a
+/// [`Query`]. Pass it to [`Fetcher::iter_concrete`] instead. Filtering
+/// compares the [`TypeId`] stored in every row of every archetype matching the
+/// fetcher, so it costs as much as iterating over all of those entities.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::dyn_component::{DynComponent, IsConcrete};
+/// use evenio::prelude::*;
+///
+/// trait Behavior: Send + Sync {}
+///
+/// struct Patrol;
+/// impl Behavior for Patrol {}
+///
+/// struct Guard;
+/// impl Behavior for Guard {}
+///
+/// #[derive(Event)]
+/// struct Tick;
+///
+/// let mut world = World::new();
+///
+/// let patrol = world.spawn();
+/// world.insert(
+///     patrol,
+///     DynComponent::<dyn Behavior>::new(Box::new(Patrol), |b| b),
+/// );
+///
+/// let guard = world.spawn();
+/// world.insert(
+///     guard,
+///     DynComponent::<dyn Behavior>::new(Box::new(Guard), |b| b),
+/// );
+///
+/// world.add_handler(move |_: Receiver<Tick>, f: Fetcher<EntityId>| {
+///     let patrols: Vec<_> = f
+///         .iter_concrete(IsConcrete::<dyn Behavior, Patrol>::new())
+///         .collect();
+///
+///     assert_eq!(patrols, [patrol]);
+/// });
+///
+/// world.send(Tick);
+/// ```
+///
+/// [`Fetcher::iter_concrete`]: crate::fetch::Fetcher::iter_concrete
+pub struct IsConcrete<T: ?Sized, U>(PhantomData<fn() -> Box<T>>, PhantomData<fn() -> U>);
+
+impl<T: ?Sized, U> IsConcrete<T, U> {
+    /// Creates a new filter.
+    pub const fn new() -> Self {
+        Self(PhantomData, PhantomData)
+    }
+}
+
+impl<T: ?Sized, U> Clone for IsConcrete<T, U> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized, U> Copy for IsConcrete<T, U> {}
+
+impl<T: ?Sized, U> Default for IsConcrete<T, U> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ?Sized, U> fmt::Debug for IsConcrete<T, U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "IsConcrete<{}, {}>",
+            any::type_name::<T>(),
+            any::type_name::<U>()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::prelude::*;
+
+    trait Behavior: Send + Sync {
+        fn name(&self) -> String;
+        fn bump(&mut self);
+    }
+
+    struct Patrol(u32);
+
+    impl Behavior for Patrol {
+        fn name(&self) -> String {
+            alloc::format!("patrol {}", self.0)
+        }
+
+        fn bump(&mut self) {
+            self.0 += 1;
+        }
+    }
+
+    struct Guard;
+
+    impl Behavior for Guard {
+        fn name(&self) -> String {
+            "guard".into()
+        }
+
+        fn bump(&mut self) {}
+    }
+
+    #[derive(Event)]
+    struct Tick;
+
+    #[derive(Component, Default)]
+    struct Names(Vec<String>);
+
+    #[derive(Component, Default)]
+    struct Found(Vec<EntityId>);
+
+    fn behavior<U: Behavior + 'static>(value: U) -> DynComponent<dyn Behavior> {
+        DynComponent::<dyn Behavior>::new(Box::new(value), |b| b)
+    }
+
+    #[test]
+    fn dyn_access() {
+        let mut world = World::new();
+
+        world.init_dyn_component::<dyn Behavior>();
+
+        let log = world.spawn();
+        world.insert(log, Names::default());
+
+        let e = world.spawn();
+        world.insert(e, behavior(Patrol(1)));
+
+        world.add_handler(|_: Receiver<Tick>, f: Fetcher<AsDynMut<dyn Behavior>>| {
+            for b in f {
+                b.bump();
+            }
+        });
+
+        world.add_handler(
+            |_: Receiver<Tick>,
+             f: Fetcher<AsDyn<dyn Behavior>>,
+             Single(names): Single<&mut Names>| {
+                names.0.extend(f.iter().map(|b| b.name()))
+            },
+        );
+
+        world.send(Tick);
+
+        assert_eq!(world.get::<Names>(log).unwrap().0, ["patrol 2"]);
+        assert_eq!(
+            world
+                .get::<DynComponent<dyn Behavior>>(e)
+                .unwrap()
+                .downcast_ref::<Patrol>()
+                .unwrap()
+                .0,
+            2
+        );
+    }
+
+    #[test]
+    fn concrete_filter() {
+        let mut world = World::new();
+
+        let log = world.spawn();
+        world.insert(log, Found::default());
+
+        let patrol = world.spawn();
+        world.insert(patrol, behavior(Patrol(0)));
+
+        let guard = world.spawn();
+        world.insert(guard, behavior(Guard));
+
+        world.add_handler(
+            |_: Receiver<Tick>,
+             mut f: Fetcher<(EntityId, AsDynMut<dyn Behavior>)>,
+             Single(found): Single<&mut Found>| {
+                found.0.clear();
+
+                for (e, b) in f.iter_concrete_mut(IsConcrete::<dyn Behavior, Patrol>::new()) {
+                    b.bump();
+                    found.0.push(e);
+                }
+            },
+        );
+
+        world.send(Tick);
+        assert_eq!(world.get::<Found>(log).unwrap().0, [patrol]);
+
+        // Replace the values with different concrete types.
+        world.insert(patrol, behavior(Guard));
+        world
+            .get_mut::<DynComponent<dyn Behavior>>(guard)
+            .unwrap()
+            .set(Box::new(Patrol(5)), |b| b);
+
+        world.send(Tick);
+        assert_eq!(world.get::<Found>(log).unwrap().0, [guard]);
+
+        let get = |e| world.get::<DynComponent<dyn Behavior>>(e).unwrap();
+        assert_eq!(get(guard).name(), "patrol 6");
+        assert!(get(patrol).is::<Guard>());
+        assert!(get(patrol).downcast_ref::<Patrol>().is_none());
+    }
+}
@@ -1,8 +1,16 @@
 //! Entity related items.
 
+use alloc::borrow::Cow;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::any::TypeId;
+use core::mem;
 use core::ops::Index;
+use core::ptr::NonNull;
 
 use crate::archetype::{ArchetypeIdx, ArchetypeRow};
+use crate::blob_vec::BlobVec;
+use crate::component::{Component, ComponentId};
 use crate::event::EventPtr;
 use crate::handler::{Config, HandlerInfo, HandlerParam, InitError};
 use crate::prelude::World;
@@ -35,6 +43,18 @@ impl Entities {
         }
     }
 
+    /// Creates an empty `Entities` which reuses entity slots in the order they
+    /// were freed.
+    pub(crate) fn new_fifo() -> Self {
+        Self {
+            locs: SlotMap::new_fifo(),
+        }
+    }
+
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        self.locs.reserve(additional);
+    }
+
     /// Gets the [`EntityLocation`] of the given entity. Returns `None` if the
     /// ID is invalid.
     pub fn get(&self, id: EntityId) -> Option<EntityLocation> {
@@ -68,6 +88,12 @@ impl Entities {
         self.locs.remove(id.0)
     }
 
+    /// Returns the number of entity slots which can be held without
+    /// reallocating.
+    pub(crate) fn capacity(&self) -> usize {
+        self.locs.capacity()
+    }
+
     /// Returns the total number of entities.
     pub fn len(&self) -> u32 {
         self.locs.len()
@@ -77,6 +103,21 @@ impl Entities {
     pub fn iter(&self) -> impl Iterator<Item = EntityLocation> + '_ {
         self.locs.iter().map(|(_, v)| *v)
     }
+
+    /// Returns the generation count of every entity slot. Odd generations
+    /// belong to live entities.
+    #[cfg(feature = "serde")]
+    pub(crate) fn generations(&self) -> impl ExactSizeIterator<Item = u32> + '_ {
+        self.locs.generations()
+    }
+
+    /// Restores the slot generations returned by [`Self::generations`]. Live
+    /// entities are given a null location until they are placed in an
+    /// archetype.
+    #[cfg(feature = "serde")]
+    pub(crate) fn restore(&mut self, generations: &[u32]) {
+        self.locs.restore(generations, |_| EntityLocation::NULL);
+    }
 }
 
 impl Index<EntityId> for Entities {
@@ -110,8 +151,8 @@ unsafe impl HandlerParam for &'_ Entities {
 
     type Item<'a> = &'a Entities;
 
-    fn init(_world: &mut World, _config: &mut Config) -> Result<Self::State, InitError> {
-        Ok(())
+    fn init(_world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+        config.read_world::<Self>()
     }
 
     unsafe fn get<'a>(
@@ -183,12 +224,155 @@ impl EntityId {
     pub const fn generation(self) -> u32 {
         self.0.generation().get()
     }
+
+    /// Returns `true` if this is [`EntityId::NULL`].
+    pub const fn is_null(self) -> bool {
+        self.0.to_bits() == Key::NULL.to_bits()
+    }
+
+    /// Converts this ID into a `u64`. The index is stored in the least
+    /// significant half and the generation in the most significant half.
+    ///
+    /// The result is never zero, and can be converted back with
+    /// [`EntityId::from_bits`].
+    pub const fn to_bits(self) -> u64 {
+        self.0.to_bits()
+    }
+
+    /// Converts bits returned by [`EntityId::to_bits`] back into an ID.
+    /// Returns `None` if `bits` is not a valid ID.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::entity::EntityId;
+    ///
+    /// let id = EntityId::new(5, 3).unwrap();
+    ///
+    /// assert_eq!(EntityId::try_from_bits(id.to_bits()), Some(id));
+    /// // Generations are always odd.
+    /// assert_eq!(EntityId::try_from_bits(2 << 32 | 5), None);
+    /// ```
+    pub const fn try_from_bits(bits: u64) -> Option<Self> {
+        Self::new(bits as u32, (bits >> 32) as u32)
+    }
+
+    /// Converts bits returned by [`EntityId::to_bits`] back into an ID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bits` is not a valid ID. See [`EntityId::try_from_bits`]
+    /// for a non-panicking version.
+    #[track_caller]
+    pub const fn from_bits(bits: u64) -> Self {
+        match Self::try_from_bits(bits) {
+            Some(id) => id,
+            None => panic!("invalid entity ID bits"),
+        }
+    }
+}
+
+// `Option<EntityId>` uses the niche of the never-zero bits.
+const _: () = assert!(mem::size_of::<EntityId>() == mem::size_of::<u64>());
+const _: () = assert!(mem::size_of::<Option<EntityId>>() == mem::size_of::<EntityId>());
+
+/// Serialized as an `(index, generation)` pair.
+#[cfg(feature = "serde")]
+impl serde::Serialize for EntityId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        (self.index().0, self.generation()).serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for EntityId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (index, generation) = <(u32, u32)>::deserialize(deserializer)?;
+
+        Self::new(index, generation)
+            .ok_or_else(|| serde::de::Error::custom("entity generation must be odd"))
+    }
 }
 
 /// An [`EntityId`] with the generation count stripped out.
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Hash, Debug)]
 pub struct EntityIdx(pub u32);
 
+/// An entity removed from a world along with all of its components. Returned
+/// by [`World::take_entity`] and consumed by [`World::put_entity`].
+///
+/// Component values are stored type-erased by [`ComponentId`]. Dropping an
+/// `OwnedEntity` drops the components it holds.
+#[derive(Debug)]
+pub struct OwnedEntity {
+    /// Sorted by component ID.
+    components: Vec<OwnedComponent>,
+}
+
+/// A component value held by an [`OwnedEntity`], along with the metadata
+/// needed to check it against the component it is put back into.
+#[derive(Debug)]
+pub(crate) struct OwnedComponent {
+    pub(crate) id: ComponentId,
+    pub(crate) type_id: Option<TypeId>,
+    pub(crate) name: Cow<'static, str>,
+    /// Buffer holding the single component value.
+    pub(crate) data: BlobVec,
+}
+
+impl OwnedEntity {
+    pub(crate) fn new(mut components: Vec<OwnedComponent>) -> Self {
+        components.sort_unstable_by_key(|c| c.id.index());
+
+        Self { components }
+    }
+
+    /// Returns the number of components held.
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    /// Returns `true` if the entity has no components.
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    /// Returns an iterator over the IDs of the components held, in order of
+    /// [`ComponentIdx`](crate::component::ComponentIdx).
+    pub fn component_ids(&self) -> impl Iterator<Item = ComponentId> + '_ {
+        self.components.iter().map(|c| c.id)
+    }
+
+    /// Returns a pointer to the value of the component with the given ID, or
+    /// `None` if the entity doesn't have it.
+    pub fn get_ptr(&self, component: ComponentId) -> Option<NonNull<u8>> {
+        self.components
+            .iter()
+            .find(|c| c.id == component)
+            .map(|c| c.data.as_ptr())
+    }
+
+    /// Returns a reference to the component `C`, or `None` if the entity
+    /// doesn't have it.
+    pub fn get<C: Component>(&self) -> Option<&C> {
+        self.components
+            .iter()
+            .find(|c| c.type_id == Some(TypeId::of::<C>()))
+            // SAFETY: The type ID matches, so the buffer holds a `C`.
+            .map(|c| unsafe { c.data.as_ptr().cast::<C>().as_ref() })
+    }
+
+    /// Returns the components sorted by component ID. The caller takes
+    /// ownership of the values.
+    pub(crate) fn into_components(self) -> Vec<OwnedComponent> {
+        self.components
+    }
+}
+
+// SAFETY: Components are guaranteed `Send` and `Sync`.
+unsafe impl Send for OwnedEntity {}
+unsafe impl Sync for OwnedEntity {}
+
 /// A queue of entities to be spawned into the world.
 #[derive(Debug)]
 pub(crate) struct ReservedEntities {
@@ -226,6 +410,12 @@ impl ReservedEntities {
         }
     }
 
+    /// Returns the number of reserved entities which have not been spawned
+    /// yet.
+    pub(crate) fn count(&self) -> u32 {
+        self.count
+    }
+
     pub(crate) fn refresh(&mut self, entities: &Entities) {
         debug_assert_eq!(self.count, 0);
         self.iter = entities.locs.next_key_iter();
@@ -240,6 +430,35 @@ mod tests {
     use crate::entity::Entities;
     use crate::prelude::*;
 
+    #[test]
+    fn entity_id_bits() {
+        let id = EntityId::new(7, 41).unwrap();
+
+        assert_eq!(id.to_bits(), 41 << 32 | 7);
+        assert_eq!(EntityId::from_bits(id.to_bits()), id);
+        assert!(!id.is_null());
+
+        assert!(EntityId::NULL.is_null());
+        assert!(EntityId::default().is_null());
+        assert_eq!(
+            EntityId::from_bits(EntityId::NULL.to_bits()),
+            EntityId::NULL
+        );
+        assert!(EntityId::try_from_bits(EntityId::NULL.to_bits())
+            .unwrap()
+            .is_null());
+
+        assert_eq!(EntityId::try_from_bits(0), None);
+        assert_eq!(EntityId::try_from_bits(7), None);
+        assert_eq!(EntityId::try_from_bits(42 << 32 | 7), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid entity ID bits")]
+    fn entity_id_from_invalid_bits() {
+        EntityId::from_bits(0);
+    }
+
     #[test]
     fn spawn_despawn_entity() {
         let mut world = World::new();
@@ -293,9 +512,6 @@ mod tests {
     fn spawn_event_entity_exists() {
         let mut world = World::new();
 
-        #[derive(Event)]
-        struct E;
-
         world.add_handler(|r: Receiver<Spawn, ()>, entities: &Entities| {
             assert!(entities.contains(r.event.0));
         });
@@ -2,15 +2,15 @@
 
 use alloc::borrow::Cow;
 #[cfg(not(feature = "std"))]
-use alloc::{format, vec, vec::Vec};
+use alloc::{boxed::Box, format, vec, vec::Vec};
 use core::alloc::Layout;
-use core::any::TypeId;
+use core::any::{Any, TypeId};
 use core::marker::PhantomData;
 use core::num::NonZeroU32;
 use core::ops::{Deref, DerefMut, Index};
 use core::panic::{RefUnwindSafe, UnwindSafe};
 use core::ptr::NonNull;
-use core::{any, fmt};
+use core::{any, fmt, mem};
 
 use bumpalo::Bump;
 use evenio_macros::all_tuples;
@@ -18,20 +18,23 @@ pub use evenio_macros::Event;
 use memoffset::offset_of;
 
 use crate::access::Access;
-use crate::archetype::Archetype;
+use crate::archetype::{Archetype, ArchetypeIdx};
 use crate::assert::{
     AssertMutable, AssertTargetedEvent, AssertUntargetedEvent, GetDebugChecked, UnwrapDebugChecked,
 };
-use crate::component::ComponentIdx;
+use crate::component::{Bundle, ComponentIdx, Components};
+use crate::dedup::Dedup;
 use crate::drop::DropFn;
 use crate::entity::{EntityId, EntityLocation};
 use crate::fetch::FetcherState;
-use crate::handler::{Config, HandlerInfo, HandlerParam, InitError};
+use crate::handler::{Config, HandlerId, HandlerInfo, HandlerParam, InitError};
 use crate::map::{Entry, TypeIdMap};
 use crate::prelude::Component;
 use crate::query::Query;
+use crate::reflect::{FieldInfo, FieldMut, FieldRef};
 use crate::slot_map::{Key, SlotMap};
 use crate::sparse::SparseIndex;
+use crate::validate::Validator;
 use crate::world::{UnsafeWorldCell, World};
 
 /// Stores metadata for all [`Event`]s in the world.
@@ -49,16 +52,16 @@ use crate::world::{UnsafeWorldCell, World};
 /// world.add_handler(|_: Receiver<E>, events: &Events| {});
 #[derive(Debug)]
 pub struct Events {
-    untargeted_events: SlotMap<EventInfo>,
-    targeted_events: SlotMap<EventInfo>,
+    untargeted: SlotMap<EventInfo>,
+    targeted: SlotMap<EventInfo>,
     by_type_id: TypeIdMap<EventId>,
 }
 
 impl Events {
     pub(crate) fn new() -> Self {
         let mut this = Self {
-            untargeted_events: SlotMap::new(),
-            targeted_events: SlotMap::new(),
+            untargeted: SlotMap::new(),
+            targeted: SlotMap::new(),
             by_type_id: TypeIdMap::default(),
         };
 
@@ -70,6 +73,7 @@ impl Events {
             layout: Layout::new::<SpawnQueued>(),
             drop: None,
             is_immutable: true,
+            fields: vec![],
         });
 
         this
@@ -84,13 +88,17 @@ impl Events {
             layout: desc.layout,
             drop: desc.drop,
             is_immutable: desc.is_immutable,
+            fields: desc.fields.into_boxed_slice(),
+            dedup: None,
+            validator: None,
+            exclusive_receiver: None,
         };
 
         let insert = || {
             let map = if desc.is_targeted {
-                &mut self.targeted_events
+                &mut self.targeted
             } else {
-                &mut self.untargeted_events
+                &mut self.untargeted
             };
 
             let Some(k) = map.insert(info) else {
@@ -117,8 +125,8 @@ impl Events {
     pub fn get(&self, id: EventId) -> Option<&EventInfo> {
         let k = id.as_key();
         match id.index() {
-            EventIdx::Targeted(_) => self.targeted_events.get(k),
-            EventIdx::Untargeted(_) => self.untargeted_events.get(k),
+            EventIdx::Targeted(_) => self.targeted.get(k),
+            EventIdx::Untargeted(_) => self.untargeted.get(k),
         }
     }
 
@@ -127,8 +135,23 @@ impl Events {
     #[inline]
     pub fn get_by_index(&self, idx: EventIdx) -> Option<&EventInfo> {
         match idx {
-            EventIdx::Untargeted(idx) => Some(self.untargeted_events.get_by_index(idx.0)?.1),
-            EventIdx::Targeted(idx) => Some(self.targeted_events.get_by_index(idx.0)?.1),
+            EventIdx::Untargeted(idx) => Some(self.untargeted.get_by_index(idx.0)?.1),
+            EventIdx::Targeted(idx) => Some(self.targeted.get_by_index(idx.0)?.1),
+        }
+    }
+
+    pub(crate) fn get_mut(&mut self, id: EventId) -> Option<&mut EventInfo> {
+        let k = id.as_key();
+        match id.index() {
+            EventIdx::Targeted(_) => self.targeted.get_mut(k),
+            EventIdx::Untargeted(_) => self.untargeted.get_mut(k),
+        }
+    }
+
+    pub(crate) fn get_by_index_mut(&mut self, idx: EventIdx) -> Option<&mut EventInfo> {
+        match idx {
+            EventIdx::Untargeted(idx) => Some(self.untargeted.get_by_index_mut(idx.0)?.1),
+            EventIdx::Targeted(idx) => Some(self.targeted.get_by_index_mut(idx.0)?.1),
         }
     }
 
@@ -152,9 +175,9 @@ impl Events {
         let k = id.as_key();
 
         let info = if id.is_targeted() {
-            self.targeted_events.remove(k)
+            self.targeted.remove(k)
         } else {
-            self.untargeted_events.remove(k)
+            self.untargeted.remove(k)
         }?;
 
         if let Some(type_id) = info.type_id {
@@ -166,9 +189,9 @@ impl Events {
 
     /// Returns an iterator over all event infos.
     pub fn iter(&self) -> impl Iterator<Item = &EventInfo> {
-        self.targeted_events
+        self.targeted
             .iter()
-            .chain(self.untargeted_events.iter())
+            .chain(self.untargeted.iter())
             .map(|(_, v)| v)
     }
 }
@@ -214,8 +237,8 @@ unsafe impl HandlerParam for &'_ Events {
 
     type Item<'a> = &'a Events;
 
-    fn init(_world: &mut World, _config: &mut Config) -> Result<Self::State, InitError> {
-        Ok(())
+    fn init(_world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+        config.read_world::<Self>()
     }
 
     unsafe fn get<'a>(
@@ -270,6 +293,14 @@ unsafe impl HandlerParam for &'_ Events {
 /// #[derive(Event)]
 /// struct TupleStruct(i32, #[event(target)] EntityId);
 ///
+/// // Records the name, offset and type of each field for runtime inspection.
+/// // Only available on structs.
+/// #[derive(Event)]
+/// #[event(reflect_fields)]
+/// struct Reflected {
+///     amount: u64,
+/// }
+///
 /// #[derive(Event)]
 /// enum Enum {
 ///     Foo(i32),
@@ -285,6 +316,36 @@ unsafe impl HandlerParam for &'_ Events {
 /// #[derive(Event)]
 /// struct EmptyEvent;
 /// ```
+///
+/// Generic types are supported. Each instantiation of a generic event is a
+/// distinct event with its own [`EventId`].
+///
+/// ```
+/// use std::marker::PhantomData;
+///
+/// use evenio::prelude::*;
+///
+/// struct Fire;
+/// struct Poison;
+///
+/// #[derive(Event)]
+/// struct Damage<K> {
+///     #[event(target)]
+///     entity: EntityId,
+///     amount: u32,
+///     _kind: PhantomData<fn() -> K>,
+/// }
+///
+/// let mut world = World::new();
+///
+/// // Only receives `Damage<Fire>`.
+/// world.add_handler(|r: Receiver<Damage<Fire>, ()>| println!("{}", r.event.amount));
+///
+/// assert_ne!(
+///     world.add_event::<Damage<Fire>>(),
+///     world.add_event::<Damage<Poison>>()
+/// );
+/// ```
 pub trait Event: Send + Sync + 'static {
     /// If this event is considered "targeted" or "untargeted".
     ///
@@ -328,6 +389,15 @@ pub trait Event: Send + Sync + 'static {
         let _ = world;
         EventKind::Normal
     }
+
+    /// Calls `f` with the [`FieldInfo`] of each top-level field of this event,
+    /// in declaration order. The fields are stored in [`EventInfo::fields`].
+    ///
+    /// The default implementation describes no fields. Deriving with
+    /// `#[event(reflect_fields)]` describes every field.
+    fn reflect_fields(f: &mut dyn FnMut(FieldInfo)) {
+        let _ = f;
+    }
 }
 
 /// Additional behaviors for an event. This is used to distinguish normal
@@ -347,6 +417,12 @@ pub enum EventKind {
         /// [`Insert::component`] field.
         component_offset: u32,
     },
+    /// The [`InsertBundle`] event.
+    InsertBundle {
+        /// Index of the bundle type in the world, used to find its components
+        /// in the event.
+        bundle_idx: u32,
+    },
     /// The [`Remove`] event.
     Remove {
         /// The [`ComponentIdx`] of the component to remove.
@@ -537,6 +613,13 @@ pub struct EventInfo {
     layout: Layout,
     drop: DropFn,
     is_immutable: bool,
+    fields: Box<[FieldInfo]>,
+    /// Set by [`World::dedup_window`](crate::world::World::dedup_window).
+    pub(crate) dedup: Option<Dedup>,
+    /// Set by [`World::set_event_validator`](crate::world::World::set_event_validator).
+    pub(crate) validator: Option<Validator>,
+    /// The handler receiving the event with a [`TakeReceiver`], if any.
+    pub(crate) exclusive_receiver: Option<HandlerId>,
 }
 
 impl EventInfo {
@@ -579,6 +662,24 @@ impl EventInfo {
     pub fn is_immutable(&self) -> bool {
         self.is_immutable
     }
+
+    /// Gets the descriptions of the event's fields. Empty unless the event
+    /// was [reflected](Event::reflect_fields).
+    pub fn fields(&self) -> &[FieldInfo] {
+        &self.fields
+    }
+
+    /// Gets the description of the field named `name`, if any.
+    pub fn field(&self, name: &str) -> Option<&FieldInfo> {
+        self.fields.iter().find(|field| field.name() == name)
+    }
+
+    /// Gets the handler which takes ownership of the event with a
+    /// [`TakeReceiver`], if any. No other handler can receive the event while
+    /// this is `Some`.
+    pub fn exclusive_receiver(&self) -> Option<HandlerId> {
+        self.exclusive_receiver
+    }
 }
 
 /// Data needed to create a new event.
@@ -602,24 +703,46 @@ pub struct EventDescriptor {
     pub drop: DropFn,
     /// If this event is [immutable](Event::IS_IMMUTABLE).
     pub is_immutable: bool,
+    /// The [fields](Event::reflect_fields) of the event.
+    pub fields: Vec<FieldInfo>,
 }
 
 #[derive(Debug)]
 pub(crate) struct EventQueue {
     items: Vec<EventQueueItem>,
+    /// Events pushed behind every other event with `push_back`, in reverse
+    /// order. Kept apart from `items` so indices into `items` stay valid.
+    back: Vec<EventQueueItem>,
     bump: Bump,
+    /// Sequence number assigned to the next pushed event.
+    next_sequence: u64,
 }
 
 impl EventQueue {
     pub(crate) fn new() -> Self {
         Self {
             items: vec![],
+            back: vec![],
             bump: Bump::new(),
+            next_sequence: 0,
         }
     }
 
     pub(crate) fn pop_front(&mut self) -> Option<EventQueueItem> {
-        self.items.pop()
+        self.items.pop().or_else(|| self.back.pop())
+    }
+
+    /// Like [`Self::pop_front`], but only pops events at index `floor` and
+    /// above. Events pushed with [`Self::push_back`] are only popped when
+    /// `floor` is zero.
+    pub(crate) fn pop_front_above(&mut self, floor: usize) -> Option<EventQueueItem> {
+        if floor == 0 {
+            self.pop_front()
+        } else if self.items.len() > floor {
+            self.items.pop()
+        } else {
+            None
+        }
     }
 
     #[inline]
@@ -636,7 +759,63 @@ impl EventQueue {
         };
 
         let event = NonNull::from(self.bump.alloc(event)).cast::<u8>();
-        self.items.push(EventQueueItem { meta, event });
+        debug_assert_eq!(event.as_ptr() as usize % mem::align_of::<E>(), 0);
+
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.items.push(EventQueueItem {
+            meta,
+            event,
+            sequence,
+            #[cfg(feature = "entity-history")]
+            sender: None,
+            #[cfg(feature = "tracing")]
+            span: tracing::Span::none(),
+        });
+    }
+
+    /// Pushes a [`Remove`] event for `target`, where the type of the removed
+    /// component is only known through the event index.
+    ///
+    /// # Safety
+    ///
+    /// `idx` must be the index of a `Remove<C>` event.
+    pub(crate) unsafe fn push_front_remove(&mut self, target: EntityId, idx: TargetedEventIdx) {
+        // `Remove<C>` is a transparent wrapper around its target.
+        let event = NonNull::from(self.bump.alloc(target)).cast::<u8>();
+
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.items.push(EventQueueItem {
+            meta: EventMeta::Targeted { idx, target },
+            event,
+            sequence,
+            #[cfg(feature = "entity-history")]
+            sender: None,
+            #[cfg(feature = "tracing")]
+            span: tracing::Span::none(),
+        });
+    }
+
+    /// Pushes an event behind every event currently in the queue.
+    pub(crate) fn push_back(&mut self, item: EventQueueItem) {
+        self.back.insert(0, item);
+    }
+
+    /// Marks the events in the range `from..` as sent by `sender`.
+    #[cfg(feature = "entity-history")]
+    pub(crate) fn set_sender_from(&mut self, from: usize, sender: crate::handler::HandlerId) {
+        for item in &mut self.items[from..] {
+            item.sender = Some(sender);
+        }
+    }
+
+    /// Sets the parent span of the events in the range `from..` to `span`.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn set_span_from(&mut self, from: usize, span: &tracing::Span) {
+        for item in &mut self.items[from..] {
+            item.span = span.clone();
+        }
     }
 
     /// Reverses elements in the range `from..`.
@@ -645,11 +824,26 @@ impl EventQueue {
     ///
     /// `from` must be in bounds.
     pub(crate) unsafe fn reverse_from(&mut self, from: usize) {
-        self.items.get_debug_checked_mut(from..).reverse();
+        let items = self.items.get_debug_checked_mut(from..);
+        items.reverse();
+
+        // Events above `from` were pushed by the same handler in order, so
+        // they must now be popped in increasing sequence order.
+        debug_assert!(
+            items.windows(2).all(|w| w[0].sequence > w[1].sequence)
+                && items.iter().all(|item| item.sequence < self.next_sequence),
+            "event queue sequence numbers out of order"
+        );
     }
 
     pub(crate) fn iter(&self) -> impl Iterator<Item = &EventQueueItem> {
-        self.items.iter()
+        self.back.iter().chain(&self.items)
+    }
+
+    /// Returns the sequence number which will be assigned to the next pushed
+    /// event.
+    pub(crate) fn next_sequence(&self) -> u64 {
+        self.next_sequence
     }
 
     /// Clears the event queue and resets the internal bump allocator.
@@ -657,15 +851,45 @@ impl EventQueue {
     /// Any remaining event pointers are invalidated.
     pub(crate) fn clear(&mut self) {
         self.items.clear();
+        self.back.clear();
         self.bump.reset();
     }
 
+    /// Returns the number of events in the queue, not counting events pushed
+    /// with [`Self::push_back`].
     pub(crate) fn len(&self) -> usize {
         self.items.len()
     }
 
     pub(crate) fn is_empty(&self) -> bool {
-        self.len() == 0
+        self.items.is_empty() && self.back.is_empty()
+    }
+
+    /// Reserves space for at least `events` queued events using `bytes` of
+    /// event storage in total. The queue must be empty.
+    pub(crate) fn reserve(&mut self, events: usize, bytes: usize) {
+        debug_assert!(self.is_empty());
+
+        self.items.reserve(events);
+
+        if bytes > self.bump.chunk_capacity() {
+            // The most recent chunk is retained by `reset`, so allocate it at the
+            // full size up front.
+            self.bump
+                .alloc_layout(Layout::from_size_align(bytes, 16).unwrap());
+            self.bump.reset();
+        }
+    }
+
+    /// Returns the number of events that can be queued without reallocating.
+    pub(crate) fn capacity(&self) -> usize {
+        self.items.capacity()
+    }
+
+    /// Returns the number of bytes of event storage which have been
+    /// allocated.
+    pub(crate) fn allocated_bytes(&self) -> usize {
+        self.bump.allocated_bytes()
     }
 }
 
@@ -676,12 +900,22 @@ unsafe impl Sync for EventQueue {}
 impl UnwindSafe for EventQueue {}
 impl RefUnwindSafe for EventQueue {}
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Debug)]
 pub(crate) struct EventQueueItem {
     pub(crate) meta: EventMeta,
     /// Type-erased pointer to this event. When null, ownership of the event
     /// has been transferred and no destructor needs to run.
     pub(crate) event: NonNull<u8>,
+    /// See [`EventSequence`].
+    pub(crate) sequence: u64,
+    /// The handler which sent this event, or `None` if it was sent from
+    /// outside of a handler.
+    #[cfg(feature = "entity-history")]
+    pub(crate) sender: Option<crate::handler::HandlerId>,
+    /// The span of the handler which sent this event. Spans of nested events
+    /// are created as children of it.
+    #[cfg(feature = "tracing")]
+    pub(crate) span: tracing::Span,
 }
 
 // SAFETY: Events are always Send + Sync.
@@ -710,22 +944,95 @@ impl EventMeta {
     }
 }
 
+/// A [`HandlerParam`] which provides the sequence number of the event being
+/// handled.
+///
+/// Every event sent to a [`World`] is assigned a sequence number when it is
+/// enqueued, so the numbers reflect the order in which events were _sent_
+/// rather than the order in which they are handled. Sequence numbers increase
+/// monotonically for the lifetime of the world, across event types and
+/// cascades.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::event::EventSequence;
+/// use evenio::prelude::*;
+///
+/// #[derive(Event)]
+/// struct A;
+///
+/// #[derive(Event)]
+/// struct B;
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(
+///     |_: Receiver<A>, EventSequence(seq): EventSequence, mut s: Sender<B>| {
+///         println!("A has sequence number {seq}");
+///         s.send(B);
+///     },
+/// );
+///
+/// world.add_handler(|_: Receiver<B>, EventSequence(seq): EventSequence| {
+///     println!("B has sequence number {seq}");
+/// });
+///
+/// world.send(A);
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct EventSequence(pub u64);
+
+unsafe impl HandlerParam for EventSequence {
+    type State = ();
+
+    type Item<'a> = EventSequence;
+
+    fn init(_world: &mut World, _config: &mut Config) -> Result<Self::State, InitError> {
+        Ok(())
+    }
+
+    unsafe fn get<'a>(
+        _state: &'a mut Self::State,
+        _info: &'a HandlerInfo,
+        _event_ptr: EventPtr<'a>,
+        _target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::Item<'a> {
+        EventSequence(world.event_sequence())
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
 /// Type-erased pointer to an event. Passed to handlers in [`Handler::run`].
 ///
 /// [`Handler::run`]: crate::handler::Handler::run
 #[derive(Clone, Copy, Debug)]
 pub struct EventPtr<'a> {
     event: NonNull<u8>,
-    // `false` when borrowed, `true` when taken.
-    ownership_flag: NonNull<bool>,
+    ownership: NonNull<EventOwnership>,
     _marker: PhantomData<&'a mut u8>,
 }
 
+/// What a handler did with the event behind an [`EventPtr`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum EventOwnership {
+    /// The event is borrowed by the handler.
+    Borrowed,
+    /// The handler took ownership of the event.
+    Owned,
+    /// The handler deferred the event with [`EventMut::defer`].
+    Deferred,
+}
+
 impl<'a> EventPtr<'a> {
-    pub(crate) fn new(event: NonNull<u8>, ownership_flag: NonNull<bool>) -> Self {
+    pub(crate) fn new(event: NonNull<u8>, ownership: NonNull<EventOwnership>) -> Self {
         Self {
             event,
-            ownership_flag,
+            ownership,
             _marker: PhantomData,
         }
     }
@@ -733,15 +1040,25 @@ impl<'a> EventPtr<'a> {
     /// Returns the underlying pointer to the type-erased event.
     #[track_caller]
     pub fn as_ptr(self) -> NonNull<u8> {
-        let is_owned = unsafe { *self.ownership_flag.as_ptr() };
+        let ownership = unsafe { *self.ownership.as_ptr() };
         debug_assert!(
-            !is_owned,
-            "`as_ptr` cannot be called after the event has been marked as owned"
+            ownership == EventOwnership::Borrowed,
+            "`as_ptr` cannot be called after the event has been marked as owned or deferred"
         );
 
         self.event
     }
 
+    /// Returns `true` if a handler has taken ownership of the event with
+    /// [`set_owned`](Self::set_owned), such as through [`EventMut::take`].
+    /// Handlers of a consumed event which have not run yet will not receive
+    /// it, and the event is not dropped by the world.
+    ///
+    /// Unlike [`as_ptr`](Self::as_ptr), this can be called at any time.
+    pub fn is_consumed(self) -> bool {
+        unsafe { *self.ownership.as_ptr() == EventOwnership::Owned }
+    }
+
     /// Marks the event as owned. It is then the handler's responsibility to
     /// drop the event.
     ///
@@ -752,10 +1069,122 @@ impl<'a> EventPtr<'a> {
     ///
     /// [`as_ptr`]: Self::as_ptr
     pub unsafe fn set_owned(self) {
-        *self.ownership_flag.as_ptr() = true;
+        *self.ownership.as_ptr() = EventOwnership::Owned;
+    }
+
+    /// Marks the event as deferred. See [`EventMut::defer`].
+    ///
+    /// # Safety
+    ///
+    /// - Must have permission to access the event mutably.
+    /// - Once the event is set as deferred, [`as_ptr`] cannot be called.
+    ///
+    /// [`as_ptr`]: Self::as_ptr
+    unsafe fn set_deferred(self) {
+        *self.ownership.as_ptr() = EventOwnership::Deferred;
+    }
+}
+
+/// Type-erased reference to an event along with its [`EventInfo`], giving
+/// access to the event's [fields](EventInfo::fields) by name.
+///
+/// # Examples
+///
+/// ```
+/// use core::any::TypeId;
+/// use core::ptr::NonNull;
+///
+/// use evenio::event::{AnyEvent, Events};
+/// use evenio::prelude::*;
+///
+/// #[derive(Event)]
+/// #[event(reflect_fields)]
+/// struct Deposit {
+///     amount: u64,
+/// }
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|mut r: ReceiverMut<Deposit>, events: &Events| {
+///     let info = events.get_by_type_id(TypeId::of::<Deposit>()).unwrap();
+///     let ptr = NonNull::from(&mut *r.event).cast();
+///
+///     let mut event = unsafe { AnyEvent::new(ptr, info) };
+///     let mut amount = event.field_mut("amount").unwrap();
+///
+///     assert!(amount.downcast_mut::<u32>().is_none());
+///     *amount.downcast_mut::<u64>().unwrap() *= 2;
+/// });
+///
+/// world.add_handler(|r: Receiver<Deposit>| assert_eq!(r.event.amount, 20));
+///
+/// world.send(Deposit { amount: 10 });
+/// ```
+#[derive(Debug)]
+pub struct AnyEvent<'a> {
+    event: NonNull<u8>,
+    info: &'a EventInfo,
+    _marker: PhantomData<&'a mut u8>,
+}
+
+impl<'a> AnyEvent<'a> {
+    /// Creates a new `AnyEvent` from a pointer to an event and the event's
+    /// info.
+    ///
+    /// # Safety
+    ///
+    /// - `event` must point to an initialized event of the type described by
+    ///   `info`, valid for reads for `'a`.
+    /// - If [`field_mut`](Self::field_mut) is called, `event` must also be
+    ///   valid for writes and must not be aliased for `'a`.
+    pub unsafe fn new(event: NonNull<u8>, info: &'a EventInfo) -> Self {
+        Self {
+            event,
+            info,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Gets the [`EventInfo`] of the event.
+    pub fn info(&self) -> &'a EventInfo {
+        self.info
+    }
+
+    /// Returns a pointer to the event.
+    pub fn as_ptr(&self) -> NonNull<u8> {
+        self.event
+    }
+
+    /// Returns a reference to the field named `name`, or `None` if the event
+    /// has no such [field](EventInfo::fields).
+    pub fn field(&self, name: &str) -> Option<FieldRef<'_>> {
+        let field = self.info.field(name)?;
+        Some(unsafe { field.get(self.event) })
+    }
+
+    /// Returns a mutable reference to the field named `name`, or `None` if the
+    /// event has no such [field](EventInfo::fields).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the event is [immutable](EventInfo::is_immutable).
+    #[track_caller]
+    pub fn field_mut(&mut self, name: &str) -> Option<FieldMut<'_>> {
+        assert!(
+            !self.info.is_immutable(),
+            "cannot mutate field of immutable event `{}`",
+            self.info.name()
+        );
+
+        let field = self.info.field(name)?;
+        Some(unsafe { field.get_mut(self.event) })
     }
 }
 
+/// The number of times a single event can be deferred with
+/// [`EventMut::defer`] before a panic occurs.
+pub const MAX_EVENT_DEFERRALS: u32 = 16;
+
 /// Mutable reference to an instance of event `E`.
 ///
 /// To get at `E`, use the [`Deref`] and [`DerefMut`] implementations or
@@ -801,6 +1230,112 @@ impl<'a, E> EventMut<'a, E> {
         unsafe { this.ptr.set_owned() };
         res
     }
+
+    /// Consumes the event, so that handlers of the event which have not run
+    /// yet don't receive it. The event is dropped.
+    ///
+    /// This is [`take`](Self::take) for handlers which have no use for the
+    /// event itself. Consuming the event does not undo anything done by the
+    /// handlers which already received it, including the caller, and events
+    /// they sent are still handled.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// #[derive(Event)]
+    /// struct KeyPress(char);
+    ///
+    /// #[derive(Component)]
+    /// struct Text(String);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// // The console gets the first look at input and swallows the keys it
+    /// // handles.
+    /// world.add_handler(
+    ///     (|r: ReceiverMut<KeyPress>| {
+    ///         if r.event.0 == '`' {
+    ///             EventMut::consume(r.event);
+    ///         }
+    ///     })
+    ///     .high(),
+    /// );
+    ///
+    /// world.add_handler(|r: Receiver<KeyPress>, text: Single<&mut Text>| {
+    ///     text.0 .0.push(r.event.0);
+    /// });
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, Text(String::new()));
+    ///
+    /// world.send(KeyPress('a'));
+    /// world.send(KeyPress('`'));
+    /// world.send(KeyPress('b'));
+    ///
+    /// assert_eq!(world.get::<Text>(e).unwrap().0, "ab");
+    /// ```
+    pub fn consume(this: Self) {
+        drop(Self::take(this));
+    }
+
+    /// Moves the event to the back of the event queue without copying it.
+    /// Handlers of the event which have not run yet will receive it after
+    /// every event which is currently queued has been handled. This includes
+    /// events sent by handlers of this event.
+    ///
+    /// Handlers which already received the event, including the caller, do
+    /// not receive it again. The remaining handlers run in their usual order.
+    /// For targeted events, the target is looked up again when the event is
+    /// resumed, so the handlers which run are those matching the target at
+    /// that point. The [`EventSequence`] of the event is unchanged.
+    ///
+    /// Deferring an event received by a [`Throttle`] from its coalescing
+    /// buffer has no effect.
+    ///
+    /// # Panics
+    ///
+    /// Panics when the event is resumed if it has been deferred more than
+    /// [`MAX_EVENT_DEFERRALS`] times.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// #[derive(Event)]
+    /// struct A;
+    ///
+    /// #[derive(Event)]
+    /// struct B;
+    ///
+    /// #[derive(Component)]
+    /// struct Log(Vec<&'static str>);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(
+    ///     (|r: ReceiverMut<A>, mut s: Sender<B>| {
+    ///         s.send(B);
+    ///         EventMut::defer(r.event);
+    ///     })
+    ///     .high(),
+    /// );
+    ///
+    /// world.add_handler(|_: Receiver<A>, log: Single<&mut Log>| log.0 .0.push("A"));
+    /// world.add_handler(|_: Receiver<B>, log: Single<&mut Log>| log.0 .0.push("B"));
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, Log(vec![]));
+    ///
+    /// world.send(A);
+    ///
+    /// assert_eq!(world.get::<Log>(e).unwrap().0, ["B", "A"]);
+    /// ```
+    ///
+    /// [`Throttle`]: crate::handler::Throttle
+    pub fn defer(this: Self) {
+        unsafe { this.ptr.set_deferred() };
+    }
 }
 
 unsafe impl<E: Send> Send for EventMut<'_, E> {}
@@ -845,6 +1380,42 @@ impl<E: fmt::Debug> fmt::Debug for EventMut<'_, E> {
 ///     println!("got event of type E!");
 /// });
 /// ```
+///
+/// # Targeted events
+///
+/// For targeted events, `Q` is a [`Query`] the target entity must match for
+/// the handler to run. The filter is applied by the dispatcher: the target's
+/// archetype is resolved once per event, and only the handlers whose query
+/// matches that archetype are invoked. Handlers which don't match are skipped
+/// without fetching any of their parameters.
+///
+/// Structural changes made by a handler, such as inserting a component on the
+/// target, are queued and take effect after every handler of the current event
+/// has run. All handlers of an event therefore see the archetype of the target
+/// from when the event was dispatched. If the target doesn't exist at that
+/// point, no handlers are run.
+///
+/// ```
+/// use evenio::prelude::*;
+///
+/// #[derive(Event)]
+/// struct Damage(#[event(target)] EntityId);
+///
+/// #[derive(Component)]
+/// struct Player;
+///
+/// #[derive(Component)]
+/// struct Health(u32);
+///
+/// let mut world = World::new();
+///
+/// // Only invoked when the target is a player with health.
+/// world.add_handler(|r: Receiver<Damage, (With<&Player>, &mut Health)>| {
+///     r.query.1 .0 -= 1;
+/// });
+/// ```
+///
+/// [`Query`]: crate::query::Query
 #[derive(Clone, Copy)]
 pub struct Receiver<'a, E: Event, Q: ReceiverQuery + 'static = NullReceiverQuery> {
     /// A reference to the received event.
@@ -898,6 +1469,9 @@ unsafe impl<E: Event, Q: Query + 'static> HandlerParam for Receiver<'_, E, Q> {
 
         set_received_event::<E>(world, config, Access::Read)?;
 
+        config.read_world::<Q>()?;
+        reject_row_filters::<Q>()?;
+
         let (expr, state) = Q::init(world, config)?;
 
         let res = FetcherState::new(state);
@@ -1015,6 +1589,9 @@ unsafe impl<E: Event, Q: Query + 'static> HandlerParam for ReceiverMut<'_, E, Q>
 
         set_received_event::<E>(world, config, Access::ReadWrite)?;
 
+        config.read_world::<Q>()?;
+        reject_row_filters::<Q>()?;
+
         let (expr, state) = Q::init(world, config)?;
 
         let res = FetcherState::new(state);
@@ -1077,25 +1654,166 @@ where
     }
 }
 
-fn set_received_event<E: Event>(
-    world: &mut World,
-    config: &mut Config,
-    access: Access,
-) -> Result<EventId, InitError> {
-    let id = world.add_event::<E>();
-
-    if let Some(received_event) = config.received_event {
-        if received_event != id {
-            let other = world
-                .events()
-                .get(received_event)
-                .map_or("<unknown>", |info| info.name());
-
-            return Err(InitError(
-                format!(
-                    "tried to set `{}` as the received event for this handler, but the handler \
-                     was already configured to receive `{other}`. Handlers must listen for \
-                     exactly one event type",
+/// Like [`ReceiverMut`], but moves the event into the handler instead of
+/// lending it.
+///
+/// A handler with a `TakeReceiver<E>` must be the only handler of `E`, so
+/// that the event is never needed after the handler takes it. Adding the
+/// handler panics if `E` already has a handler, and adding any other handler
+/// of `E` panics while the `TakeReceiver` handler exists. The event is not
+/// dropped by the world once it has been moved into the handler.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::event::TakeReceiver;
+/// use evenio::prelude::*;
+///
+/// #[derive(Event)]
+/// struct LoadChunk {
+///     data: Vec<u8>,
+/// }
+///
+/// #[derive(Component)]
+/// struct Chunk(Vec<u8>);
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|r: TakeReceiver<LoadChunk>, mut s: Sender<(Spawn, Insert<Chunk>)>| {
+///     // The buffer is moved, not cloned.
+///     let e = s.spawn();
+///     s.insert(e, Chunk(r.event.data));
+/// });
+///
+/// world.send(LoadChunk { data: vec![1, 2, 3] });
+/// ```
+pub struct TakeReceiver<'a, E: Event, Q: ReceiverQuery + 'static = NullReceiverQuery> {
+    /// The received event.
+    pub event: E,
+    /// The result of the query. This field is meaningless if `E` is not a
+    /// targeted event.
+    pub query: Q::Item<'a>,
+}
+
+unsafe impl<E: Event> HandlerParam for TakeReceiver<'_, E> {
+    type State = ();
+
+    type Item<'a> = TakeReceiver<'a, E>;
+
+    fn init(world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+        let () = AssertMutable::<E>::EVENT;
+        let () = AssertUntargetedEvent::<E>::ASSERTION;
+
+        set_received_event::<E>(world, config, Access::ReadWrite)?;
+        config.takes_event = true;
+
+        Ok(())
+    }
+
+    unsafe fn get<'a>(
+        _state: &'a mut Self::State,
+        _info: &'a HandlerInfo,
+        event_ptr: EventPtr<'a>,
+        _target_location: EntityLocation,
+        _world: UnsafeWorldCell<'a>,
+    ) -> Self::Item<'a> {
+        TakeReceiver {
+            event: EventMut::take(EventMut::new(event_ptr)),
+            query: (),
+        }
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
+unsafe impl<E: Event, Q: Query + 'static> HandlerParam for TakeReceiver<'_, E, Q> {
+    type State = FetcherState<Q>;
+
+    type Item<'a> = TakeReceiver<'a, E, Q>;
+
+    fn init(world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+        let state = ReceiverMut::<E, Q>::init(world, config)?;
+        config.takes_event = true;
+
+        Ok(state)
+    }
+
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        info: &'a HandlerInfo,
+        event_ptr: EventPtr<'a>,
+        target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::Item<'a> {
+        let ReceiverMut { event, query } =
+            ReceiverMut::<E, Q>::get(state, info, event_ptr, target_location, world);
+
+        TakeReceiver {
+            event: EventMut::take(event),
+            query,
+        }
+    }
+
+    fn refresh_archetype(state: &mut Self::State, arch: &Archetype) {
+        state.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(state: &mut Self::State, arch: &Archetype) {
+        state.remove_archetype(arch)
+    }
+}
+
+impl<'a, E, Q> fmt::Debug for TakeReceiver<'a, E, Q>
+where
+    E: Event + fmt::Debug,
+    Q: ReceiverQuery,
+    Q::Item<'a>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TakeReceiver")
+            .field("event", &self.event)
+            .field("query", &self.query)
+            .finish()
+    }
+}
+
+/// Fails if the query of a receiver filters individual entities, since the
+/// receiver can only skip events based on the archetype of the target.
+fn reject_row_filters<Q: Query>() -> Result<(), InitError> {
+    if Q::FILTERS_ROWS {
+        return Err(InitError(
+            format!(
+                "receiver query `{}` can't contain `Added` or `Changed` filters",
+                any::type_name::<Q>()
+            )
+            .into(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn set_received_event<E: Event>(
+    world: &mut World,
+    config: &mut Config,
+    access: Access,
+) -> Result<EventId, InitError> {
+    let id = world.add_event::<E>();
+
+    if let Some(received_event) = config.received_event {
+        if received_event != id {
+            let other = world
+                .events()
+                .get(received_event)
+                .map_or("<unknown>", |info| info.name());
+
+            return Err(InitError(
+                format!(
+                    "tried to set `{}` as the received event for this handler, but the handler \
+                     was already configured to receive `{other}`. Handlers must listen for \
+                     exactly one event type",
                     any::type_name::<E>(),
                 )
                 .into(),
@@ -1198,6 +1916,104 @@ impl<T: EventSet> Sender<'_, T> {
         id
     }
 
+    /// Like [`spawn`](Self::spawn), but the entity only lives until the end of
+    /// the current cascade. Once the events of the cascade have all been
+    /// handled, the entity is despawned with a [`Despawn`] event, unless it
+    /// was despawned already or passed to [`persist`](Self::persist).
+    ///
+    /// This is useful for temporary entities like probes and accumulators,
+    /// which would leak if a handler forgot to despawn them.
+    ///
+    /// The cascade is the one started by the outermost call to a method of
+    /// the [`World`] like [`World::send`]. Events handled through a
+    /// [`Flush`] or a [`WorldMut`] are part of the same cascade, so they
+    /// don't despawn scoped entities on their own.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `Spawn` is not in the [`EventSet`] of this sender.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// struct Explode;
+    ///
+    /// #[derive(Component)]
+    /// struct Probe;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|_: Receiver<Explode>, mut s: Sender<(Spawn, Insert<Probe>)>| {
+    ///     let probe = s.spawn_scoped();
+    ///     s.insert(probe, Probe);
+    /// });
+    ///
+    /// world.send(Explode);
+    ///
+    /// // The probe is gone once `send` returns.
+    /// assert_eq!(world.component_count::<Probe>(), 0);
+    /// ```
+    ///
+    /// [`Flush`]: crate::flush::Flush
+    /// [`WorldMut`]: crate::world::WorldMut
+    #[track_caller]
+    pub fn spawn_scoped(&mut self) -> EntityId {
+        let id = self.spawn();
+        unsafe { self.world.scope_entity(id) };
+        id
+    }
+
+    /// Keeps an entity spawned with [`spawn_scoped`](Self::spawn_scoped)
+    /// from being despawned at the end of the cascade, which makes it an
+    /// ordinary entity. Has no effect on other entities.
+    ///
+    /// Any handler can persist a scoped entity, not just the one which
+    /// spawned it.
+    pub fn persist(&mut self, entity: EntityId) {
+        unsafe { self.world.persist_entity(entity) };
+    }
+
+    /// Queues an entity to be spawned along with the [`Spawn`] and
+    /// [`InsertBundle`] events, and returns its [`EntityId`]. The entity is
+    /// moved straight into the archetype with all of the components of
+    /// `bundle` once `InsertBundle` finishes broadcasting.
+    ///
+    /// The entity does not have any components yet when `Spawn` is received.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `Spawn` or `InsertBundle<B>` is not in the [`EventSet`] of
+    /// this sender.
+    #[track_caller]
+    pub fn spawn_bundle<B: Bundle>(&mut self, bundle: B) -> EntityId {
+        let id = self.spawn();
+        self.send(InsertBundle::new(id, bundle));
+        id
+    }
+
+    /// Like [`spawn_bundle`](Self::spawn_bundle), but spawns an entity for
+    /// each bundle in `bundles`. Returns the IDs of the entities in spawn
+    /// order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `Spawn` or `InsertBundle<B>` is not in the [`EventSet`] of
+    /// this sender.
+    #[track_caller]
+    pub fn spawn_bundles<B, I>(&mut self, bundles: I) -> Vec<EntityId>
+    where
+        B: Bundle,
+        I: IntoIterator<Item = B>,
+    {
+        bundles
+            .into_iter()
+            .map(|bundle| self.spawn_bundle(bundle))
+            .collect()
+    }
+
     /// Queue an [`Insert`] event.
     ///
     /// This is equivalent to:
@@ -1453,6 +2269,90 @@ impl<C: Component> Event for Insert<C> {
     }
 }
 
+/// An [`Event`] which adds every component of the [`Bundle`] `B` to an entity
+/// when sent, moving the entity straight into the archetype with all of them.
+/// Components the entity already has are replaced.
+///
+/// Unlike sending an [`Insert`] per component, the entity moves between
+/// archetypes at most once. Handlers for [`Insert<C>`] do not run for the
+/// components of the bundle.
+///
+/// Any handler which listens for `InsertBundle<B>` will run before the
+/// components are inserted. `InsertBundle<B>` has no effect if the target
+/// entity does not exist, any of the components is rejected by its
+/// [quota](crate::quota), or the event is consumed before it finishes
+/// broadcasting.
+///
+/// # Panics
+///
+/// Adding the event panics if `B` contains the same component type more than
+/// once.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[repr(C)] // Field order is significant!
+pub struct InsertBundle<B> {
+    /// The entity to insert the components on.
+    pub entity: EntityId,
+    /// The components to insert.
+    pub bundle: B,
+}
+
+impl<B> InsertBundle<B> {
+    /// Create a new instance.
+    pub const fn new(entity: EntityId, bundle: B) -> Self {
+        Self { entity, bundle }
+    }
+}
+
+impl<B: Bundle> Event for InsertBundle<B> {
+    const IS_TARGETED: bool = true;
+
+    fn target(&self) -> EntityId {
+        self.entity
+    }
+
+    unsafe fn init(world: &mut World) -> EventKind {
+        EventKind::InsertBundle {
+            bundle_idx: world.add_bundle::<B>(),
+        }
+    }
+}
+
+/// Type-erased [`insert_bundle_components`].
+pub(crate) type InsertBundleFn =
+    unsafe fn(&Components, NonNull<u8>, &mut Vec<(ComponentIdx, *const u8)>) -> bool;
+
+/// Pushes the index of each component in the [`InsertBundle<B>`] event at
+/// `event` to `out`, along with a pointer to its value, sorted by index.
+/// Returns `false` if one of the components no longer exists.
+///
+/// # Safety
+///
+/// `event` must point to an initialized `InsertBundle<B>`.
+pub(crate) unsafe fn insert_bundle_components<B: Bundle>(
+    components: &Components,
+    event: NonNull<u8>,
+    out: &mut Vec<(ComponentIdx, *const u8)>,
+) -> bool {
+    let mut ids = vec![];
+
+    if !B::component_ids(components, &mut ids) {
+        return false;
+    }
+
+    let mut ids = ids.into_iter();
+
+    let event = &mut *event.cast::<InsertBundle<B>>().as_ptr();
+
+    event.bundle.for_each_ptr(|ptr| {
+        let idx = ids.next().unwrap_debug_checked().index();
+        out.push((idx, ptr.as_ptr().cast_const()));
+    });
+
+    out.sort_unstable_by_key(|&(idx, _)| idx);
+
+    true
+}
+
 /// An [`Event`] which removes component `C` from an entity when sent. The
 /// component is dropped and cannot be recovered.
 ///
@@ -1540,6 +2440,48 @@ impl Event for Despawn {
     }
 }
 
+/// An [`Event`] sent after an entity moves from one archetype to another due to
+/// a component being inserted or removed.
+///
+/// The event is not sent when an [`Insert`] overwrites an existing component,
+/// since the entity's archetype does not change. Spawning and despawning
+/// entities do not send this event either.
+///
+/// To avoid overhead in the common case, the event is only sent once it has
+/// been added to the world, such as by a handler which receives it.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::event::ArchetypeMoved;
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct C;
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|r: Receiver<ArchetypeMoved, ()>| {
+///     println!(
+///         "{:?} moved from {:?} to {:?}",
+///         r.event.entity, r.event.from, r.event.to
+///     );
+/// });
+///
+/// let e = world.spawn();
+/// world.insert(e, C);
+/// ```
+#[derive(Event, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct ArchetypeMoved {
+    /// The entity that moved.
+    #[event(target)]
+    pub entity: EntityId,
+    /// The archetype the entity was in before the move.
+    pub from: ArchetypeIdx,
+    /// The archetype the entity is in after the move.
+    pub to: ArchetypeIdx,
+}
+
 /// An [`Event`] sent immediately after a new event is added to the world.
 ///
 /// Contains the [`EventId`] of the added event.
@@ -1552,6 +2494,113 @@ pub struct AddEvent(pub EventId);
 #[derive(Event, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub struct RemoveEvent(pub EventId);
 
+/// A position in the world's log of recorded events.
+///
+/// Cursors are obtained from [`World::drain_events_since`]. The default cursor
+/// refers to the very beginning of the log.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct EventCursor(u64);
+
+/// A copy of a top-level event captured by the world's event log.
+///
+/// Only events registered with [`World::record_events`] are captured. See
+/// [`World::drain_events_since`] for more information.
+#[derive(Debug)]
+pub struct EventRecord {
+    id: EventId,
+    sequence: u64,
+    event: Box<dyn Any + Send + Sync>,
+}
+
+impl EventRecord {
+    /// Returns the [`EventId`] of the recorded event.
+    pub fn id(&self) -> EventId {
+        self.id
+    }
+
+    /// Returns the cursor pointing at this record. Draining from this cursor
+    /// will yield this record and all records after it.
+    pub fn cursor(&self) -> EventCursor {
+        EventCursor(self.sequence)
+    }
+
+    /// Returns a reference to the recorded event if it is of type `E`.
+    pub fn downcast_ref<E: Event>(&self) -> Option<&E> {
+        self.event.downcast_ref()
+    }
+
+    /// Returns the recorded event if it is of type `E`. Otherwise, `self` is
+    /// returned unchanged.
+    pub fn downcast<E: Event>(self) -> Result<E, Self> {
+        if self.event.is::<E>() {
+            // SAFETY: We just checked the type.
+            Ok(*unsafe { self.event.downcast::<E>().unwrap_debug_checked() })
+        } else {
+            Err(self)
+        }
+    }
+}
+
+/// Function which clones a type-erased event for the event log.
+type RecordFn = fn(&dyn Any) -> Box<dyn Any + Send + Sync>;
+
+/// Log of top-level events sent to the world.
+#[derive(Debug)]
+pub(crate) struct EventLog {
+    /// Event types which should be recorded.
+    recorders: TypeIdMap<RecordFn>,
+    records: Vec<EventRecord>,
+    /// Sequence number of the next record.
+    next_sequence: u64,
+}
+
+impl EventLog {
+    pub(crate) fn new() -> Self {
+        Self {
+            recorders: TypeIdMap::default(),
+            records: vec![],
+            next_sequence: 0,
+        }
+    }
+
+    pub(crate) fn add_recorder<E: Event + Clone>(&mut self) {
+        fn record<E: Event + Clone>(event: &dyn Any) -> Box<dyn Any + Send + Sync> {
+            // SAFETY: Recorders are keyed by the `TypeId` of `E`.
+            Box::new(unsafe { event.downcast_ref::<E>().unwrap_debug_checked() }.clone())
+        }
+
+        self.recorders.insert(TypeId::of::<E>(), record::<E>);
+    }
+
+    /// Returns whether events of type `E` are recorded.
+    pub(crate) fn is_recorded<E: Event>(&self) -> bool {
+        self.recorders.contains_key(&TypeId::of::<E>())
+    }
+
+    /// Records the event if its type was registered with
+    /// [`add_recorder`](Self::add_recorder).
+    pub(crate) fn record<E: Event>(&mut self, id: EventId, event: &E) {
+        if let Some(f) = self.recorders.get(&TypeId::of::<E>()) {
+            self.records.push(EventRecord {
+                id,
+                sequence: self.next_sequence,
+                event: f(event),
+            });
+            self.next_sequence += 1;
+        }
+    }
+
+    pub(crate) fn drain_since(&mut self, cursor: EventCursor) -> (Vec<EventRecord>, EventCursor) {
+        let mut records = mem::take(&mut self.records);
+        records.retain(|r| r.sequence >= cursor.0);
+
+        (records, EventCursor(self.next_sequence))
+    }
+}
+
+impl UnwindSafe for EventLog {}
+impl RefUnwindSafe for EventLog {}
+
 #[cfg(test)]
 mod tests {
     use crate::prelude::*;
@@ -1564,6 +2613,57 @@ mod tests {
         assert!(world.remove_event(EventId::SPAWN_QUEUED).is_none());
     }
 
+    #[test]
+    fn insert_bundle() {
+        use alloc::sync::Arc;
+
+        use super::InsertBundle;
+
+        #[derive(Event)]
+        struct E;
+
+        #[derive(Component, PartialEq, Debug)]
+        struct A(u32);
+
+        #[derive(Component)]
+        struct B(#[allow(dead_code)] Arc<()>);
+
+        #[derive(Component)]
+        struct C;
+
+        let mut world = World::new();
+
+        let count = Arc::new(());
+        let c = count.clone();
+
+        world.add_handler(
+            move |_: Receiver<E>, mut s: Sender<(Spawn, InsertBundle<(A, B)>)>| {
+                s.spawn_bundles([(A(1), B(c.clone())), (A(2), B(c.clone()))]);
+            },
+        );
+
+        world.send(E);
+
+        assert_eq!(world.component_count::<A>(), 2);
+        assert_eq!(Arc::strong_count(&count), 4);
+
+        // Existing components are replaced, and others are kept.
+        let e = world.spawn();
+        world.insert(e, A(0));
+        world.insert(e, C);
+        world.send(InsertBundle::new(e, (A(3), B(count.clone()))));
+
+        assert_eq!(world.get::<A>(e), Some(&A(3)));
+        assert!(world.get::<C>(e).is_some());
+        assert_eq!(Arc::strong_count(&count), 5);
+
+        // The bundle is dropped if the entity doesn't exist.
+        world.despawn(e);
+        world.send(InsertBundle::new(e, (A(4), B(count.clone()))));
+
+        assert_eq!(Arc::strong_count(&count), 4);
+    }
+
     #[test]
     fn change_entity_during_broadcast() {
         let mut world = World::new();
@@ -1680,4 +2780,626 @@ mod tests {
 
         world.send(E(e));
     }
+
+    #[test]
+    fn targeted_receivers_filtered_at_dispatch() {
+        use alloc::sync::Arc;
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Event)]
+        struct Damage(#[event(target)] EntityId);
+
+        #[derive(Component)]
+        struct Player;
+
+        #[derive(Component)]
+        struct Monster;
+
+        let mut world = World::new();
+
+        let player_runs = Arc::new(AtomicUsize::new(0));
+        let monster_runs = Arc::new(AtomicUsize::new(0));
+
+        // Runs first and turns the monster into a player.
+        world.add_handler(
+            (|r: Receiver<Damage, With<&Monster>>, mut s: Sender<Insert<Player>>| {
+                s.insert(r.event.0, Player);
+            })
+            .high(),
+        );
+
+        // Handlers are deduplicated by type, so every closure needs to be
+        // distinct.
+        macro_rules! add_counters {
+            ($($n:literal)*) => {$(
+                let _ = $n;
+
+                let runs = player_runs.clone();
+                world.add_handler(move |_: Receiver<Damage, With<&Player>>| {
+                    runs.fetch_add(1, Ordering::Relaxed);
+                });
+
+                let runs = monster_runs.clone();
+                world.add_handler(move |_: Receiver<Damage, With<&Monster>>| {
+                    runs.fetch_add(1, Ordering::Relaxed);
+                });
+            )*};
+        }
+
+        add_counters!(0 1 2 3 4 5 6 7 8 9);
+
+        let player = world.spawn();
+        world.insert(player, Player);
+
+        world.send(Damage(player));
+        assert_eq!(player_runs.load(Ordering::Relaxed), 10);
+        assert_eq!(monster_runs.load(Ordering::Relaxed), 0);
+
+        // The archetype of the target is resolved once, so `Player` receivers
+        // don't see the component inserted by the first handler.
+        let monster = world.spawn();
+        world.insert(monster, Monster);
+
+        world.send(Damage(monster));
+        assert_eq!(player_runs.load(Ordering::Relaxed), 10);
+        assert_eq!(monster_runs.load(Ordering::Relaxed), 10);
+        assert!(world.get::<Player>(monster).is_some());
+
+        world.send(Damage(monster));
+        assert_eq!(player_runs.load(Ordering::Relaxed), 20);
+        assert_eq!(monster_runs.load(Ordering::Relaxed), 20);
+
+        // Dead targets skip every handler.
+        world.despawn(player);
+        world.send(Damage(player));
+        assert_eq!(player_runs.load(Ordering::Relaxed), 20);
+    }
+
+    #[test]
+    fn targeted_receivers_skip_despawned_and_unmatched() {
+        #[derive(Event)]
+        struct Damage(#[event(target)] EntityId);
+
+        #[derive(Event)]
+        struct Explode(EntityId);
+
+        #[derive(Component)]
+        struct Health(u32);
+
+        #[derive(Component)]
+        struct Invulnerable;
+
+        #[derive(Component)]
+        struct Hits(u32);
+
+        let mut world = World::new();
+
+        world.add_handler(
+            |r: Receiver<Damage, (&mut Health, Not<&Invulnerable>)>, hits: Single<&mut Hits>| {
+                let (health, _) = r.query;
+                health.0 -= 1;
+                hits.0 .0 += 1;
+            },
+        );
+
+        // The target is despawned after the damage event is sent but before it
+        // is received.
+        world.add_handler(|r: Receiver<Explode>, mut s: Sender<(Damage, Despawn)>| {
+            s.despawn(r.event.0);
+            s.send(Damage(r.event.0));
+        });
+
+        let counter = world.spawn();
+        world.insert(counter, Hits(0));
+
+        let e = world.spawn();
+        world.insert(e, Health(10));
+
+        world.send(Damage(e));
+        assert_eq!(world.get::<Health>(e).unwrap().0, 9);
+        assert_eq!(world.get::<Hits>(counter).unwrap().0, 1);
+
+        // Doesn't match `Not<&Invulnerable>`.
+        world.insert(e, Invulnerable);
+        world.send(Damage(e));
+        assert_eq!(world.get::<Health>(e).unwrap().0, 9);
+
+        // Doesn't match `&mut Health`.
+        let no_health = world.spawn();
+        world.send(Damage(no_health));
+        assert_eq!(world.get::<Hits>(counter).unwrap().0, 1);
+
+        world.remove::<Invulnerable>(e);
+        world.send(Explode(e));
+        assert!(!world.entities().contains(e));
+        assert_eq!(world.get::<Hits>(counter).unwrap().0, 1);
+    }
+
+    #[test]
+    fn take_receiver_moves_event() {
+        use alloc::sync::Arc;
+        use core::any::TypeId;
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        use super::TakeReceiver;
+
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        // Not `Clone`.
+        struct Payload(Vec<u8>);
+
+        impl Drop for Payload {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        #[derive(Event)]
+        struct Request(Payload);
+
+        let mut world = World::new();
+
+        let received_ptr = Arc::new(AtomicUsize::new(0));
+        let received = received_ptr.clone();
+
+        let id = world.add_handler(move |r: TakeReceiver<Request>| {
+            let Request(payload) = r.event;
+            received.store(payload.0.as_ptr() as usize, Ordering::Relaxed);
+        });
+
+        let data = vec![1, 2, 3];
+        let ptr = data.as_ptr() as usize;
+
+        assert_eq!(world.send_traced(Request(Payload(data))), [id]);
+
+        // The heap buffer was moved into the handler and dropped exactly once.
+        assert_eq!(received_ptr.load(Ordering::Relaxed), ptr);
+        assert_eq!(DROPS.load(Ordering::Relaxed), 1);
+
+        let event = world
+            .events()
+            .get_by_type_id(TypeId::of::<Request>())
+            .unwrap();
+        assert_eq!(event.exclusive_receiver(), Some(id));
+
+        // Removing the handler allows other handlers to receive the event.
+        world.remove_handler(id);
+        world.add_handler(|_: Receiver<Request>| {});
+        world.send(Request(Payload(vec![])));
+        assert_eq!(DROPS.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn take_receiver_is_exclusive() {
+        use core::panic::AssertUnwindSafe;
+        use std::panic;
+
+        use super::TakeReceiver;
+
+        #[derive(Event)]
+        struct Request;
+
+        fn add_fails<H: IntoHandler<M>, M>(world: &mut World, handler: H) -> String {
+            let err = panic::catch_unwind(AssertUnwindSafe(|| {
+                world.add_handler(handler);
+            }))
+            .unwrap_err();
+
+            err.downcast_ref::<String>().unwrap().clone()
+        }
+
+        fn take(_: TakeReceiver<Request>) {}
+
+        fn take_again(_: TakeReceiver<Request>) {}
+
+        fn borrow(_: Receiver<Request>) {}
+
+        // Taking receiver added first.
+        let mut world = World::new();
+        world.add_handler(take);
+
+        let msg = add_fails(&mut world, borrow);
+        assert!(msg.contains("borrow"), "{msg}");
+        assert!(msg.contains("take"), "{msg}");
+
+        let msg = add_fails(&mut world, take_again);
+        assert!(msg.contains("take_again"), "{msg}");
+
+        // Taking receiver added second.
+        let mut world = World::new();
+        world.add_handler(borrow);
+
+        let msg = add_fails(&mut world, take);
+        assert!(msg.contains("borrow"), "{msg}");
+        assert!(msg.contains("take"), "{msg}");
+
+        // Replacing the only handler is allowed.
+        let id = world.handlers().iter().last().unwrap().id();
+        world.replace_handler(id, take).unwrap();
+        assert!(world.replace_handler(id, borrow).is_ok());
+    }
+
+    #[test]
+    fn take_stops_delivery() {
+        use alloc::sync::Arc;
+        use core::ptr::NonNull;
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        use super::{EventOwnership, EventPtr};
+
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Counted;
+
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        #[derive(Event)]
+        struct Collision(Vec<Counted>);
+
+        let mut world = World::new();
+
+        let runs = Arc::new([(); 3].map(|()| AtomicUsize::new(0)));
+        let taken = Arc::new(AtomicUsize::new(0));
+
+        let (r, t) = (runs.clone(), taken.clone());
+        world.add_handler(move |receiver: ReceiverMut<Collision>| {
+            r[0].fetch_add(1, Ordering::Relaxed);
+            let Collision(contacts) = EventMut::take(receiver.event);
+            t.store(contacts.len(), Ordering::Relaxed);
+        });
+
+        let r = runs.clone();
+        world.add_handler(move |_: Receiver<Collision>| {
+            r[1].fetch_add(1, Ordering::Relaxed);
+        });
+
+        let r = runs.clone();
+        world.add_handler(move |_: Receiver<Collision>| {
+            r[2].fetch_add(1, Ordering::Relaxed);
+        });
+
+        world.send(Collision(vec![Counted, Counted]));
+
+        let runs = runs.each_ref().map(|n| n.load(Ordering::Relaxed));
+        assert_eq!(runs, [1, 0, 0]);
+        assert_eq!(taken.load(Ordering::Relaxed), 2);
+        // Dropped once by the handler, not again by the queue.
+        assert_eq!(DROPS.load(Ordering::Relaxed), 2);
+
+        let mut event = 0_u8;
+        let mut ownership = EventOwnership::Borrowed;
+        let ptr = EventPtr::new(NonNull::from(&mut event), NonNull::from(&mut ownership));
+
+        assert!(!ptr.is_consumed());
+        unsafe { ptr.set_owned() };
+        assert!(ptr.is_consumed());
+    }
+
+    #[test]
+    fn consume_stops_delivery() {
+        use alloc::sync::Arc;
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Event)]
+        struct Click(#[allow(dead_code)] Arc<()>);
+
+        impl Drop for Click {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        #[derive(Event)]
+        struct Clicked;
+
+        #[derive(Component)]
+        struct Log(Vec<&'static str>);
+
+        let mut world = World::new();
+
+        world.add_handler(
+            (|r: ReceiverMut<Click>, log: Single<&mut Log>, mut s: Sender<Clicked>| {
+                log.0 .0.push("first");
+                s.send(Clicked);
+                EventMut::consume(r.event);
+            })
+            .high(),
+        );
+        world.add_handler(|_: Receiver<Click>, log: Single<&mut Log>| log.0 .0.push("second"));
+        world.add_handler(
+            (|_: Receiver<Click>, log: Single<&mut Log>| log.0 .0.push("third")).low(),
+        );
+        world.add_handler(|_: Receiver<Clicked>, log: Single<&mut Log>| log.0 .0.push("clicked"));
+
+        let e = world.spawn();
+        world.insert(e, Log(vec![]));
+
+        let payload = Arc::new(());
+        world.send(Click(payload.clone()));
+
+        // The effects of the first handler, including the event it sent, are
+        // kept.
+        assert_eq!(world.get::<Log>(e).unwrap().0, ["first", "clicked"]);
+        assert_eq!(DROPS.load(Ordering::Relaxed), 1);
+        assert_eq!(Arc::strong_count(&payload), 1);
+    }
+
+    #[test]
+    fn scoped_entities() {
+        use alloc::sync::Arc;
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Event)]
+        struct Probe(u32);
+
+        #[derive(Component)]
+        struct Keep;
+
+        #[derive(Component)]
+        struct Early;
+
+        #[derive(Component)]
+        struct Marker;
+
+        let mut world = World::new();
+
+        let despawns = Arc::new(AtomicUsize::new(0));
+        let d = despawns.clone();
+
+        world.add_handler(move |_: Receiver<Despawn, ()>| {
+            d.fetch_add(1, Ordering::Relaxed);
+        });
+
+        type ProbeEvents = (Spawn, Insert<Marker>, Insert<Keep>, Insert<Early>);
+
+        world.add_handler(|r: Receiver<Probe>, mut s: Sender<ProbeEvents>| {
+            // Spawn everything first so no despawn races a pending spawn.
+            let entities: Vec<_> = (0..r.event.0).map(|_| s.spawn_scoped()).collect();
+
+            for (i, e) in entities.into_iter().enumerate() {
+                s.insert(e, Marker);
+
+                match i % 4 {
+                    1 => s.insert(e, Keep),
+                    2 => s.insert(e, Early),
+                    _ => {}
+                }
+            }
+        });
+
+        // Promoted by a handler other than the one which spawned the entity.
+        world.add_handler(|r: Receiver<Insert<Keep>, EntityId>, mut s: Sender<()>| {
+            s.persist(r.query);
+        });
+
+        world.add_handler(
+            |r: Receiver<Insert<Early>, EntityId>, mut s: Sender<Despawn>| {
+                s.despawn(r.query);
+            },
+        );
+
+        world.send(Probe(4));
+
+        assert_eq!(world.component_count::<Marker>(), 1);
+        assert_eq!(world.component_count::<Keep>(), 1);
+        // Every entity except the promoted one was despawned exactly once.
+        assert_eq!(despawns.load(Ordering::Relaxed), 3);
+
+        despawns.store(0, Ordering::Relaxed);
+        world.send(Probe(10_000));
+
+        assert_eq!(world.component_count::<Marker>(), 1 + 2500);
+        assert_eq!(despawns.load(Ordering::Relaxed), 7500);
+
+        // Scoped entities spawned through `send_many` only live until the
+        // events are handled.
+        let (scoped, kept) = world.send_many(|mut s| {
+            let scoped = s.spawn_scoped();
+            let kept = s.spawn_scoped();
+            s.persist(kept);
+            (scoped, kept)
+        });
+
+        assert!(!world.entities().contains(scoped));
+        assert!(world.entities().contains(kept));
+    }
+
+    #[test]
+    fn generic_event() {
+        #[derive(Event)]
+        struct Msg<T>(T);
+
+        #[derive(Component)]
+        struct Log(Vec<String>);
+
+        let mut world = World::new();
+
+        assert_ne!(
+            world.add_event::<Msg<u32>>(),
+            world.add_event::<Msg<String>>()
+        );
+
+        world.add_handler(|r: Receiver<Msg<u32>>, log: Single<&mut Log>| {
+            log.0 .0.push(format!("u32 {}", r.event.0));
+        });
+
+        world.add_handler(|r: Receiver<Msg<String>>, log: Single<&mut Log>| {
+            log.0 .0.push(format!("String {}", r.event.0));
+        });
+
+        let e = world.spawn();
+        world.insert(e, Log(vec![]));
+
+        world.send(Msg(1_u32));
+        world.send(Msg("a".to_owned()));
+        world.send(Msg(2_u32));
+
+        assert_eq!(
+            world.get::<Log>(e).unwrap().0,
+            ["u32 1", "String a", "u32 2"]
+        );
+    }
+
+    #[test]
+    fn defer_event() {
+        use crate::event::EventSequence;
+
+        #[derive(Event)]
+        struct A;
+
+        #[derive(Event)]
+        struct B(u32);
+
+        #[derive(Component)]
+        struct Log(Vec<String>);
+
+        let mut world = World::new();
+
+        let e = world.spawn();
+        world.insert(e, Log(vec![]));
+
+        world.add_handler(
+            (|r: ReceiverMut<A>,
+              EventSequence(seq): EventSequence,
+              log: Single<&mut Log>,
+              mut s: Sender<B>| {
+                log.0 .0.push(format!("first {seq}"));
+                s.send(B(1));
+                s.send(B(2));
+                EventMut::defer(r.event);
+            })
+            .high(),
+        );
+
+        world.add_handler(
+            |_: Receiver<A>, EventSequence(seq): EventSequence, log: Single<&mut Log>| {
+                log.0 .0.push(format!("second {seq}"));
+            },
+        );
+
+        world.add_handler(|_: Receiver<A>, log: Single<&mut Log>| {
+            log.0 .0.push("third".into());
+        });
+
+        world.add_handler(|r: Receiver<B>, log: Single<&mut Log>| {
+            log.0 .0.push(format!("B {}", r.event.0));
+        });
+
+        world.send_many(|mut s| {
+            s.send(A);
+            s.send(B(3));
+        });
+
+        let log = &world.get::<Log>(e).unwrap().0;
+        // The event keeps its sequence number.
+        let seq = log[0].strip_prefix("first ").unwrap();
+
+        assert_eq!(
+            log,
+            &[
+                format!("first {seq}"),
+                "B 1".into(),
+                "B 2".into(),
+                "B 3".into(),
+                format!("second {seq}"),
+                "third".into(),
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "deferred more than")]
+    fn defer_event_limit() {
+        #[derive(Event)]
+        struct E;
+
+        let mut world = World::new();
+
+        // A handler doesn't receive an event it deferred, so every deferral
+        // needs a distinct handler.
+        macro_rules! add_deferring_handlers {
+            ($($n:literal)*) => {$(
+                world.add_handler(|r: ReceiverMut<E>| {
+                    let _ = $n;
+                    EventMut::defer(r.event);
+                });
+            )*};
+        }
+
+        add_deferring_handlers!(0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16);
+
+        world.send(E);
+    }
+
+    #[test]
+    fn event_sequence_is_send_order() {
+        use super::EventSequence;
+
+        #[derive(Event)]
+        struct A;
+
+        #[derive(Event)]
+        struct B;
+
+        #[derive(Event)]
+        struct C;
+
+        #[derive(Component)]
+        struct Log(Vec<(&'static str, u64)>);
+
+        let mut world = World::new();
+
+        world.add_handler(
+            |_: Receiver<A>,
+             EventSequence(seq): EventSequence,
+             mut s: Sender<B>,
+             Single(log): Single<&mut Log>| {
+                log.0.push(("A", seq));
+                s.send(B);
+                s.send(B);
+            },
+        );
+
+        world.add_handler(
+            |_: Receiver<B>,
+             EventSequence(seq): EventSequence,
+             mut s: Sender<C>,
+             Single(log): Single<&mut Log>| {
+                log.0.push(("B", seq));
+                s.send(C);
+            },
+        );
+
+        world.add_handler(
+            |_: Receiver<C>, EventSequence(seq): EventSequence, Single(log): Single<&mut Log>| {
+                log.0.push(("C", seq));
+            },
+        );
+
+        let e = world.spawn();
+        world.insert(e, Log(vec![]));
+
+        world.send(A);
+
+        // Handled depth-first, but numbered in the order the events were sent.
+        let log = &world.get::<Log>(e).unwrap().0;
+        let names: Vec<_> = log.iter().map(|&(name, _)| name).collect();
+        assert_eq!(names, ["A", "B", "C", "B", "C"]);
+
+        let a = log[0].1;
+        let seqs: Vec<_> = log.iter().map(|&(_, seq)| seq - a).collect();
+        assert_eq!(seqs, [0, 1, 3, 2, 4]);
+
+        let last = a + 4;
+        world.send(A);
+
+        let log = &world.get::<Log>(e).unwrap().0;
+        assert!(log[5..].iter().all(|&(_, seq)| seq > last));
+    }
 }
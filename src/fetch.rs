@@ -1,16 +1,25 @@
 //! Accessing components on entities.
 
 use alloc::format;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::alloc::Layout;
+use core::cmp::Ordering;
 use core::iter::FusedIterator;
-use core::ptr::NonNull;
-use core::{any, fmt};
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::ptr::{self, NonNull};
+use core::{any, fmt, mem, slice};
 
 use crate::archetype::{Archetype, ArchetypeIdx, ArchetypeRow, Archetypes};
 use crate::assert::{assume_debug_checked, UnwrapDebugChecked};
+use crate::component::{Component, ComponentId};
+use crate::dyn_component::{DynComponent, IsConcrete};
 use crate::entity::{Entities, EntityId, EntityLocation};
 use crate::event::EventPtr;
+use crate::exclusive::Exclusive;
 use crate::handler::{Config, HandlerInfo, HandlerParam, InitError};
-use crate::query::{Query, ReadOnlyQuery};
+use crate::query::{ChangedBetween, ColumnQuery, Query, ReadOnlyQuery};
 use crate::sparse_map::SparseMap;
 use crate::world::{UnsafeWorldCell, World};
 
@@ -19,6 +28,11 @@ use crate::world::{UnsafeWorldCell, World};
 pub struct FetcherState<Q: Query> {
     map: SparseMap<ArchetypeIdx, Q::ArchState>,
     state: Q::State,
+    /// The change tick of the previous run of the handler, which
+    /// [`Query::filter_row`] compares against.
+    last_run_tick: u64,
+    /// The change tick of the current run of the handler.
+    run_tick: u64,
 }
 
 impl<Q: Query> FetcherState<Q> {
@@ -26,10 +40,19 @@ impl<Q: Query> FetcherState<Q> {
         Self {
             map: SparseMap::new(),
             state,
+            last_run_tick: 0,
+            run_tick: 0,
         }
     }
 
+    /// Starts a new run of the handler at the change tick `now`.
+    pub(crate) fn start_run(&mut self, now: u64) {
+        self.last_run_tick = mem::replace(&mut self.run_tick, now);
+    }
+
     pub(crate) fn init(world: &mut World, config: &mut Config) -> Result<Self, InitError> {
+        config.read_world::<Q>()?;
+
         let (expr, state) = Q::init(world, config)?;
 
         let res = FetcherState::new(state);
@@ -71,9 +94,36 @@ impl<Q: Query> FetcherState<Q> {
             return Err(GetError::QueryDoesNotMatch);
         };
 
+        if !Q::filter_row(state, loc.row, self.last_run_tick) {
+            return Err(GetError::QueryDoesNotMatch);
+        }
+
         Ok(Q::get(state, loc.row))
     }
 
+    #[inline]
+    #[track_caller]
+    pub(crate) unsafe fn get_unchecked(&self, entities: &Entities, entity: EntityId) -> Q::Item<'_>
+    where
+        Q: ReadOnlyQuery,
+    {
+        let loc = entities
+            .get(entity)
+            .expect_debug_checked("entity does not exist");
+
+        let state = self
+            .map
+            .get(loc.archetype)
+            .expect_debug_checked("entity does not match the query");
+
+        debug_assert!(
+            Q::filter_row(state, loc.row, self.last_run_tick),
+            "entity does not match the query"
+        );
+
+        Q::get(state, loc.row)
+    }
+
     #[inline]
     pub(crate) unsafe fn get_mut(
         &mut self,
@@ -94,6 +144,10 @@ impl<Q: Query> FetcherState<Q> {
             return Err(GetError::QueryDoesNotMatch);
         };
 
+        if !Q::filter_row(state, loc.row, self.last_run_tick) {
+            return Err(GetError::QueryDoesNotMatch);
+        }
+
         Ok(Q::get(state, loc.row))
     }
 
@@ -106,7 +160,88 @@ impl<Q: Query> FetcherState<Q> {
         Q::get(state, loc.row)
     }
 
-    // TODO: get_many_mut
+    #[inline]
+    pub(crate) unsafe fn get_many_mut<const N: usize>(
+        &mut self,
+        entities: &Entities,
+        ids: [EntityId; N],
+    ) -> Result<[Q::Item<'_>; N], GetError> {
+        for (i, id) in ids.iter().enumerate() {
+            if ids[..i].contains(id) {
+                return Err(GetError::AliasedMutability);
+            }
+        }
+
+        let mut states = [None; N];
+
+        for (state, id) in states.iter_mut().zip(ids) {
+            let Some(loc) = entities.get(id) else {
+                return Err(GetError::NoSuchEntity);
+            };
+
+            // Eliminate a panicking branch.
+            assume_debug_checked(loc.archetype != ArchetypeIdx::NULL);
+
+            let Some(arch_state) = self.map.get(loc.archetype) else {
+                return Err(GetError::QueryDoesNotMatch);
+            };
+
+            if !Q::filter_row(arch_state, loc.row, self.last_run_tick) {
+                return Err(GetError::QueryDoesNotMatch);
+            }
+
+            *state = Some((arch_state, loc.row));
+        }
+
+        // SAFETY: The entities are distinct, so the items don't alias.
+        Ok(states.map(|state| {
+            let (arch_state, row) = state.unwrap_debug_checked();
+            Q::get(arch_state, row)
+        }))
+    }
+
+    /// # Safety
+    ///
+    /// Must have permission to access the items of the query, mutably if the
+    /// query is not read-only.
+    pub(crate) unsafe fn iter_concrete<'a, T, U>(
+        &'a self,
+        world: UnsafeWorldCell<'a>,
+        _filter: IsConcrete<T, U>,
+    ) -> impl Iterator<Item = Q::Item<'a>> + 'a
+    where
+        T: ?Sized + Send + Sync + 'static,
+        U: 'static,
+    {
+        let archetypes = world.archetypes();
+
+        let component = world
+            .components()
+            .get_by_type_id(any::TypeId::of::<DynComponent<T>>())
+            .map(|info| info.id().index());
+
+        self.map
+            .keys()
+            .iter()
+            .zip(self.map.values())
+            .filter_map(move |(&idx, state)| {
+                let arch = archetypes.get(idx).unwrap_debug_checked();
+                let col = arch.column_of(component?)?;
+
+                let values = col.data().cast::<DynComponent<T>>().as_ptr().cast_const();
+
+                Some(
+                    (0..arch.entity_count())
+                        // Read through a raw pointer, since the query may hold
+                        // mutable references to the values of other rows.
+                        .filter(move |&row| DynComponent::is_at::<U>(values.add(row as usize)))
+                        .map(ArchetypeRow)
+                        .filter(move |&row| Q::filter_row(state, row, self.last_run_tick))
+                        .map(|row| Q::get(state, row)),
+                )
+            })
+            .flatten()
+    }
 
     #[inline]
     pub(crate) unsafe fn iter<'a>(&'a self, archetypes: &'a Archetypes) -> Iter<'a, Q>
@@ -134,6 +269,7 @@ impl<Q: Query> FetcherState<Q> {
                 index: NonNull::dangling(),
                 row: ArchetypeRow(0),
                 len: 0,
+                last_run_tick: self.last_run_tick,
                 archetypes,
             }
         } else {
@@ -148,11 +284,23 @@ impl<Q: Query> FetcherState<Q> {
                     .get(indices[0])
                     .unwrap_debug_checked()
                     .entity_count(),
+                last_run_tick: self.last_run_tick,
                 archetypes,
             }
         }
     }
 
+    unsafe fn iter_columns_unchecked<'a>(&'a self, archetypes: &'a Archetypes) -> ColumnIter<'a, Q>
+    where
+        Q: ColumnQuery,
+    {
+        ColumnIter {
+            states: self.map.values().iter(),
+            indices: self.map.keys().iter(),
+            archetypes,
+        }
+    }
+
     #[cfg(feature = "rayon")]
     pub(crate) unsafe fn par_iter<'a>(&'a self, archetypes: &'a Archetypes) -> ParIter<'a, Q>
     where
@@ -161,6 +309,7 @@ impl<Q: Query> FetcherState<Q> {
         ParIter {
             arch_states: self.map.values(),
             arch_indices: self.map.keys(),
+            last_run_tick: self.last_run_tick,
             archetypes,
         }
     }
@@ -173,6 +322,7 @@ impl<Q: Query> FetcherState<Q> {
         ParIter {
             arch_states: self.map.values(),
             arch_indices: self.map.keys(),
+            last_run_tick: self.last_run_tick,
             archetypes,
         }
     }
@@ -198,6 +348,8 @@ impl<Q: Query> fmt::Debug for FetcherState<Q> {
         f.debug_struct("FetcherState")
             .field("map", &self.map)
             .field("state", &self.state)
+            .field("last_run_tick", &self.last_run_tick)
+            .field("run_tick", &self.run_tick)
             .finish()
     }
 }
@@ -217,6 +369,10 @@ impl<'a, Q: Query> Fetcher<'a, Q> {
     ///
     /// If the entity doesn't exist or doesn't match the query, then a
     /// [`GetError`] is returned.
+    ///
+    /// This takes constant time regardless of the query. The entity's location
+    /// is read from the entity table, and the query's per-archetype state is
+    /// read from a sparse table indexed by archetype.
     #[inline]
     pub fn get(&self, entity: EntityId) -> Result<Q::Item<'_>, GetError>
     where
@@ -225,6 +381,27 @@ impl<'a, Q: Query> Fetcher<'a, Q> {
         unsafe { self.state.get(self.world.entities(), entity) }
     }
 
+    /// Returns the read-only query item for the given entity without checking
+    /// that the entity exists and matches the query.
+    ///
+    /// The checks are still performed in debug mode, where a violation results
+    /// in a panic.
+    ///
+    /// # Safety
+    ///
+    /// `entity` must exist and match the query. See [`get`] for a checked
+    /// version of this method.
+    ///
+    /// [`get`]: Self::get
+    #[inline]
+    #[track_caller]
+    pub unsafe fn get_unchecked(&self, entity: EntityId) -> Q::Item<'_>
+    where
+        Q: ReadOnlyQuery,
+    {
+        self.state.get_unchecked(self.world.entities(), entity)
+    }
+
     /// Returns the query item for the given entity.
     ///
     /// If the entity doesn't exist or doesn't match the query, then a
@@ -234,8 +411,45 @@ impl<'a, Q: Query> Fetcher<'a, Q> {
         unsafe { self.state.get_mut(self.world.entities(), entity) }
     }
 
+    /// Returns the query items for several distinct entities at once.
+    ///
+    /// If any two IDs are equal, [`GetError::AliasedMutability`] is returned.
+    /// Otherwise, if an entity doesn't exist or doesn't match the query, then
+    /// the corresponding [`GetError`] is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// #[derive(Event)]
+    /// struct Attack {
+    ///     attacker: EntityId,
+    ///     victim: EntityId,
+    /// }
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|r: Receiver<Attack>, mut f: Fetcher<&mut Health>| {
+    ///     if let Ok([attacker, victim]) = f.get_many_mut([r.event.attacker, r.event.victim]) {
+    ///         let damage = attacker.0 / 10;
+    ///         victim.0 = victim.0.saturating_sub(damage);
+    ///     }
+    /// });
+    /// ```
+    #[inline]
+    pub fn get_many_mut<const N: usize>(
+        &mut self,
+        ids: [EntityId; N],
+    ) -> Result<[Q::Item<'_>; N], GetError> {
+        unsafe { self.state.get_many_mut(self.world.entities(), ids) }
+    }
+
     /// Returns an iterator over all entities matching the read-only query.
-    pub fn iter(&self) -> Iter<Q>
+    pub fn iter(&self) -> Iter<'_, Q>
     where
         Q: ReadOnlyQuery,
     {
@@ -243,9 +457,447 @@ impl<'a, Q: Query> Fetcher<'a, Q> {
     }
 
     /// Returns an iterator over all entities matching the query.
-    pub fn iter_mut(&mut self) -> Iter<Q> {
+    pub fn iter_mut(&mut self) -> Iter<'_, Q> {
         unsafe { self.state.iter_mut(self.world.archetypes()) }
     }
+
+    /// Returns an iterator over the columns of every archetype matching the
+    /// read-only query. See [`ColumnQuery`] for more information.
+    pub fn iter_columns(&self) -> ColumnIter<'_, Q>
+    where
+        Q: ColumnQuery + ReadOnlyQuery,
+    {
+        unsafe { self.state.iter_columns_unchecked(self.world.archetypes()) }
+    }
+
+    /// Returns an iterator over the columns of every archetype matching the
+    /// query. See [`ColumnQuery`] for more information.
+    pub fn iter_columns_mut(&mut self) -> ColumnIter<'_, Q>
+    where
+        Q: ColumnQuery,
+    {
+        unsafe { self.state.iter_columns_unchecked(self.world.archetypes()) }
+    }
+
+    /// Returns an iterator over the component slices of every archetype
+    /// matching the query, such as `(&[A], &mut [B])` for the query
+    /// `(&A, &mut B)`. Slices are borrowed from the archetype's columns
+    /// directly.
+    ///
+    /// This is the same as [`iter_columns_mut`]. See [`ColumnQuery`] for more
+    /// information.
+    ///
+    /// [`iter_columns_mut`]: Fetcher::iter_columns_mut
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// #
+    /// # #[derive(Event)] struct E;
+    /// #
+    /// #[derive(Component)]
+    /// struct Pos(f32);
+    ///
+    /// #[derive(Component)]
+    /// struct Vel(f32);
+    ///
+    /// # let mut world = World::new();
+    /// world.add_handler(|_: Receiver<E>, mut f: Fetcher<(&mut Pos, &Vel)>| {
+    ///     for (pos, vel) in f.iter_archetype_slices() {
+    ///         for (p, v) in pos.iter_mut().zip(vel) {
+    ///             p.0 += v.0;
+    ///         }
+    ///     }
+    /// });
+    /// ```
+    pub fn iter_archetype_slices(&mut self) -> ColumnIter<'_, Q>
+    where
+        Q: ColumnQuery,
+    {
+        self.iter_columns_mut()
+    }
+
+    /// Returns an iterator over the entities matching the read-only query
+    /// whose component `C` was last written within the change tick range of
+    /// `filter`. Entities without `C` are skipped.
+    ///
+    /// See [`ChangedBetween`] for more information.
+    pub fn iter_changed_between<C: Component>(
+        &self,
+        filter: ChangedBetween<C>,
+    ) -> impl Iterator<Item = Q::Item<'_>> + '_
+    where
+        Q: ReadOnlyQuery,
+    {
+        let archetypes = self.world.archetypes();
+
+        let component = self
+            .world
+            .components()
+            .get_by_type_id(any::TypeId::of::<C>())
+            .map(|info| info.id().index());
+
+        self.state
+            .map
+            .keys()
+            .iter()
+            .zip(self.state.map.values())
+            .filter_map(move |(&idx, state)| {
+                let arch = unsafe { archetypes.get(idx).unwrap_debug_checked() };
+                let ticks = arch.column_of(component?)?.change_ticks();
+
+                Some(
+                    ticks
+                        .iter()
+                        .enumerate()
+                        .filter(move |&(_, &tick)| filter.contains(tick))
+                        .map(|(row, _)| ArchetypeRow(row as u32))
+                        .filter(move |&row| unsafe {
+                            Q::filter_row(state, row, self.state.last_run_tick)
+                        })
+                        .map(|row| unsafe { Q::get(state, row) }),
+                )
+            })
+            .flatten()
+    }
+
+    /// Returns the change tick of the previous run of the handler, or zero if
+    /// this is the first run. See [`World::change_tick`] for more
+    /// information.
+    pub fn last_run_tick(&self) -> u64 {
+        self.state.last_run_tick
+    }
+
+    /// Returns an iterator over the entities matching the read-only query
+    /// whose [`DynComponent<T>`] holds a value of concrete type `U`. Entities
+    /// without the component are skipped.
+    ///
+    /// See [`IsConcrete`] for more information.
+    pub fn iter_concrete<T, U>(
+        &self,
+        filter: IsConcrete<T, U>,
+    ) -> impl Iterator<Item = Q::Item<'_>> + '_
+    where
+        Q: ReadOnlyQuery,
+        T: ?Sized + Send + Sync + 'static,
+        U: 'static,
+    {
+        unsafe { self.state.iter_concrete(self.world, filter) }
+    }
+
+    /// Like [`iter_concrete`](Self::iter_concrete), but for queries which are
+    /// not read-only.
+    pub fn iter_concrete_mut<T, U>(
+        &mut self,
+        filter: IsConcrete<T, U>,
+    ) -> impl Iterator<Item = Q::Item<'_>> + '_
+    where
+        T: ?Sized + Send + Sync + 'static,
+        U: 'static,
+    {
+        unsafe { self.state.iter_concrete(self.world, filter) }
+    }
+
+    /// Returns the entity matching the read-only query whose item has the
+    /// largest key, along with its item. Returns `None` if no entities match.
+    ///
+    /// Every matching entity is visited once. If several items have the
+    /// largest key, the first one in iteration order is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// #[derive(Event)]
+    /// struct E;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|_: Receiver<E>, f: Fetcher<&Health>| {
+    ///     if let Some((entity, health)) = f.max_by_key(|h| h.0) {
+    ///         println!("{entity:?} is the healthiest with {}", health.0);
+    ///     }
+    /// });
+    /// ```
+    pub fn max_by_key<K, F>(&self, f: F) -> Option<(EntityId, Q::Item<'_>)>
+    where
+        Q: ReadOnlyQuery,
+        K: Ord,
+        F: FnMut(&Q::Item<'_>) -> K,
+    {
+        self.best_by_key(f, Ordering::Greater)
+    }
+
+    /// Returns the entity matching the read-only query whose item has the
+    /// smallest key, along with its item. Returns `None` if no entities match.
+    ///
+    /// Every matching entity is visited once. If several items have the
+    /// smallest key, the first one in iteration order is returned.
+    pub fn min_by_key<K, F>(&self, f: F) -> Option<(EntityId, Q::Item<'_>)>
+    where
+        Q: ReadOnlyQuery,
+        K: Ord,
+        F: FnMut(&Q::Item<'_>) -> K,
+    {
+        self.best_by_key(f, Ordering::Less)
+    }
+
+    /// Returns the first item whose key compares as `wanted` to the keys of
+    /// every item before it.
+    fn best_by_key<K, F>(&self, mut f: F, wanted: Ordering) -> Option<(EntityId, Q::Item<'_>)>
+    where
+        Q: ReadOnlyQuery,
+        K: Ord,
+        F: FnMut(&Q::Item<'_>) -> K,
+    {
+        let archetypes = self.world.archetypes();
+
+        let mut best: Option<(K, EntityId, Q::Item<'_>)> = None;
+
+        for (&idx, state) in self.state.map.keys().iter().zip(self.state.map.values()) {
+            let arch = unsafe { archetypes.get(idx).unwrap_debug_checked() };
+
+            for (row, &entity) in arch.entity_ids().iter().enumerate() {
+                let row = ArchetypeRow(row as u32);
+
+                if !unsafe { Q::filter_row(state, row, self.state.last_run_tick) } {
+                    continue;
+                }
+
+                let item = unsafe { Q::get(state, row) };
+                let key = f(&item);
+
+                if best.as_ref().is_none_or(|(k, _, _)| key.cmp(k) == wanted) {
+                    best = Some((key, entity, item));
+                }
+            }
+        }
+
+        best.map(|(_, entity, item)| (entity, item))
+    }
+
+    /// Returns every entity matching the query along with a bitmask of the
+    /// given components it has. Bit `i` of the mask is set if the entity has
+    /// `components[i]`.
+    ///
+    /// The mask is computed once per archetype, so this is cheap even for
+    /// large numbers of entities. Entities are returned in iteration order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than 64 components are given.
+    pub fn presence_mask(&self, components: &[ComponentId]) -> Vec<(EntityId, u64)> {
+        assert!(
+            components.len() <= 64,
+            "presence mask can hold at most 64 components, but {} were given",
+            components.len()
+        );
+
+        let archetypes = self.world.archetypes();
+
+        let mut res = Vec::new();
+
+        for (&idx, state) in self.state.map.keys().iter().zip(self.state.map.values()) {
+            let arch = unsafe { archetypes.get(idx).unwrap_debug_checked() };
+
+            let mask = components
+                .iter()
+                .enumerate()
+                .filter(|(_, id)| arch.column_of(id.index()).is_some())
+                .fold(0_u64, |mask, (i, _)| mask | 1 << i);
+
+            res.extend(self.filter_ids(state, arch.entity_ids()).map(|e| (e, mask)));
+        }
+
+        res
+    }
+
+    /// Collects the IDs of every entity matching the query into `buffer`,
+    /// replacing its contents. See [`QueryBuffer`].
+    pub fn collect_ids_into(&self, buffer: &mut QueryBuffer<EntityId>) {
+        let archetypes = self.world.archetypes();
+
+        buffer.clear();
+
+        for (&idx, state) in self.state.map.keys().iter().zip(self.state.map.values()) {
+            let arch = unsafe { archetypes.get(idx).unwrap_debug_checked() };
+
+            if Q::FILTERS_ROWS {
+                buffer.extend(self.filter_ids(state, arch.entity_ids()));
+            } else {
+                buffer.extend_from_slice(arch.entity_ids());
+            }
+        }
+    }
+
+    /// Returns the IDs of the entities of an archetype which pass the row
+    /// filters of the query.
+    fn filter_ids<'s>(
+        &'s self,
+        state: &'s Q::ArchState,
+        ids: &'s [EntityId],
+    ) -> impl Iterator<Item = EntityId> + 's {
+        ids.iter().enumerate().filter_map(move |(row, &id)| {
+            let row = ArchetypeRow(row as u32);
+
+            // SAFETY: `row` is in bounds and the row filters only read change
+            // ticks, which the handler has permission to read.
+            unsafe { Q::filter_row(state, row, self.state.last_run_tick) }.then_some(id)
+        })
+    }
+
+    /// Collects the items of the read-only query into the memory of
+    /// `buffer`, replacing its contents. The items can be sorted or visited
+    /// several times through the returned [`Collected`].
+    ///
+    /// Unlike collecting into a new `Vec`, this doesn't allocate once the
+    /// buffer is large enough.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::fetch::QueryBuffer;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Depth(u32);
+    ///
+    /// #[derive(Event)]
+    /// struct Draw;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(
+    ///     |_: Receiver<Draw>, f: Fetcher<(EntityId, &Depth)>, mut buf: QueryBuffer<(EntityId, &Depth)>| {
+    ///         let mut items = f.collect_into(&mut buf);
+    ///         items.sort_unstable_by_key(|(_, depth)| depth.0);
+    ///
+    ///         for (id, depth) in items.iter() {
+    ///             println!("drawing {id:?} at depth {}", depth.0);
+    ///         }
+    ///     },
+    /// );
+    /// ```
+    pub fn collect_into<'f>(
+        &'f self,
+        buffer: &'f mut QueryBuffer<Q::Item<'_>>,
+    ) -> Collected<'f, Q::Item<'f>>
+    where
+        Q: ReadOnlyQuery,
+    {
+        // SAFETY: The types only differ in lifetimes.
+        unsafe { Collected::new(buffer.vec, self.iter()) }
+    }
+
+    /// Like [`collect_into`](Self::collect_into), but for queries which are
+    /// not read-only.
+    pub fn collect_into_mut<'f>(
+        &'f mut self,
+        buffer: &'f mut QueryBuffer<Q::Item<'_>>,
+    ) -> Collected<'f, Q::Item<'f>> {
+        // SAFETY: The types only differ in lifetimes.
+        unsafe { Collected::new(buffer.vec, self.iter_mut()) }
+    }
+
+    /// Returns an iterator over the items of the read-only query in
+    /// ascending order of the keys returned by `f`.
+    ///
+    /// `buffer` holds the key and location of every entity while they are
+    /// sorted, so this doesn't allocate once the buffer is large enough.
+    /// Items with equal keys are returned in iteration order. Only the keys
+    /// are moved around while sorting, so large items are no more expensive
+    /// to sort than small ones.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::entity::EntityLocation;
+    /// use evenio::fetch::QueryBuffer;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Score(u32);
+    ///
+    /// #[derive(Event)]
+    /// struct Leaderboard;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(
+    ///     |_: Receiver<Leaderboard>,
+    ///      f: Fetcher<&Score>,
+    ///      mut buf: QueryBuffer<(u32, EntityLocation)>| {
+    ///         let scores: Vec<u32> = f
+    ///             .iter_sorted_by_key_cached(|score| score.0, &mut buf)
+    ///             .map(|score| score.0)
+    ///             .collect();
+    ///
+    ///         assert_eq!(scores, [10, 20, 30]);
+    ///     },
+    /// );
+    ///
+    /// for score in [30, 10, 20] {
+    ///     let e = world.spawn();
+    ///     world.insert(e, Score(score));
+    /// }
+    ///
+    /// world.send(Leaderboard);
+    /// ```
+    pub fn iter_sorted_by_key_cached<'f, K, F>(
+        &'f self,
+        mut f: F,
+        buffer: &'f mut QueryBuffer<(K, EntityLocation)>,
+    ) -> impl Iterator<Item = Q::Item<'f>> + 'f
+    where
+        Q: ReadOnlyQuery,
+        K: Ord + Send + 'static,
+        F: FnMut(&Q::Item<'_>) -> K,
+    {
+        let archetypes = self.world.archetypes();
+
+        buffer.clear();
+
+        for (&idx, state) in self.state.map.keys().iter().zip(self.state.map.values()) {
+            let arch = unsafe { archetypes.get(idx).unwrap_debug_checked() };
+
+            for row in 0..arch.entity_count() {
+                let row = ArchetypeRow(row);
+
+                if !unsafe { Q::filter_row(state, row, self.state.last_run_tick) } {
+                    continue;
+                }
+
+                let item = unsafe { Q::get(state, row) };
+
+                buffer.push((
+                    f(&item),
+                    EntityLocation {
+                        archetype: idx,
+                        row,
+                    },
+                ));
+            }
+        }
+
+        // Stable sorting would allocate. Ties are broken by location instead,
+        // which is the iteration order.
+        buffer.sort_unstable_by(|(k1, loc1), (k2, loc2)| {
+            k1.cmp(k2)
+                .then(loc1.archetype.cmp(&loc2.archetype))
+                .then(loc1.row.cmp(&loc2.row))
+        });
+
+        let map = &self.state.map;
+
+        buffer.iter().map(move |(_, loc)| unsafe {
+            Q::get(map.get(loc.archetype).unwrap_debug_checked(), loc.row)
+        })
+    }
 }
 
 impl<'a, Q: Query> IntoIterator for Fetcher<'a, Q> {
@@ -310,7 +962,7 @@ impl fmt::Display for GetError {
 
 #[cfg(feature = "std")]
 #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
-impl std::error::Error for GetError {}
+impl core::error::Error for GetError {}
 
 unsafe impl<Q> HandlerParam for Fetcher<'_, Q>
 where
@@ -331,6 +983,8 @@ where
         _target_location: EntityLocation,
         world: UnsafeWorldCell<'a>,
     ) -> Self::Item<'a> {
+        state.start_run(world.archetypes().change_tick());
+
         Fetcher { state, world }
     }
 
@@ -434,6 +1088,8 @@ unsafe impl<Q: Query + 'static> HandlerParam for TrySingle<'_, Q> {
         _target_location: EntityLocation,
         world: UnsafeWorldCell<'a>,
     ) -> Self::Item<'a> {
+        state.start_run(world.archetypes().change_tick());
+
         let mut it = state.iter_mut(world.archetypes());
 
         let Some(item) = it.next() else {
@@ -478,58 +1134,225 @@ impl fmt::Display for SingleError {
 
 #[cfg(feature = "std")]
 #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
-impl std::error::Error for SingleError {}
+impl core::error::Error for SingleError {}
 
-/// Iterator over entities matching the query `Q`.
+/// A [`HandlerParam`] holding a `Vec` which keeps its capacity between runs
+/// of the handler. The `Vec` is empty every time the handler runs.
 ///
-/// Entities are visited in a deterministic but otherwise unspecified order.
-#[must_use = "iterators are lazy and do nothing unless consumed"]
-pub struct Iter<'a, Q: Query> {
-    /// Pointer into the array of archetype states. This pointer moves forward
-    /// until it reaches `state_last`.
-    state: NonNull<Q::ArchState>,
-    /// Pointer to the last arch state, or dangling if there are no arch states.
-    /// This is _not_ a one-past-the-end pointer.
-    state_last: NonNull<Q::ArchState>,
-    /// Pointer into the array of archetype indices. This pointer moves forward
-    /// in lockstep with `state`.
-    index: NonNull<ArchetypeIdx>,
-    /// Current row of the current archetype.
-    row: ArchetypeRow,
-    /// Number of entities in the current archetype.
-    len: u32,
-    archetypes: &'a Archetypes,
+/// This avoids allocating a new `Vec` on every run of handlers which collect
+/// query results, for instance to sort them or to visit them twice. See
+/// [`Fetcher::collect_into`], [`Fetcher::collect_ids_into`] and
+/// [`Fetcher::iter_sorted_by_key_cached`].
+///
+/// Every handler has its own buffer, which is dropped along with the
+/// handler.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::fetch::QueryBuffer;
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct Enemy;
+///
+/// #[derive(Event)]
+/// struct Explode;
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(
+///     |_: Receiver<Explode>,
+///      f: Fetcher<(EntityId, With<&Enemy>)>,
+///      mut ids: QueryBuffer<EntityId>,
+///      mut s: Sender<Despawn>| {
+///         f.collect_ids_into(&mut ids);
+///
+///         for &id in ids.iter() {
+///             s.despawn(id);
+///         }
+///     },
+/// );
+/// ```
+#[derive(Debug)]
+pub struct QueryBuffer<'a, T> {
+    vec: &'a mut Vec<T>,
 }
 
-impl<'a, Q: Query> Iterator for Iter<'a, Q> {
-    type Item = Q::Item<'a>;
+unsafe impl<T: Send + 'static> HandlerParam for QueryBuffer<'_, T> {
+    type State = Exclusive<Vec<T>>;
 
-    #[inline]
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.row.0 == self.len {
-            if self.state == self.state_last {
-                return None;
-            }
+    type Item<'a> = QueryBuffer<'a, T>;
+
+    fn init(_world: &mut World, _config: &mut Config) -> Result<Self::State, InitError> {
+        Ok(Exclusive::new(Vec::new()))
+    }
+
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        _info: &'a HandlerInfo,
+        _event_ptr: EventPtr<'a>,
+        _target_location: EntityLocation,
+        _world: UnsafeWorldCell<'a>,
+    ) -> Self::Item<'a> {
+        let vec = state.get_mut();
+        vec.clear();
+
+        QueryBuffer { vec }
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
+impl<T> Deref for QueryBuffer<'_, T> {
+    type Target = Vec<T>;
 
-            self.state = unsafe { NonNull::new_unchecked(self.state.as_ptr().add(1)) };
-            self.index = unsafe { NonNull::new_unchecked(self.index.as_ptr().add(1)) };
+    fn deref(&self) -> &Self::Target {
+        self.vec
+    }
+}
+
+impl<T> DerefMut for QueryBuffer<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.vec
+    }
+}
+
+/// Query items collected into the memory of a [`QueryBuffer`] by
+/// [`Fetcher::collect_into`]. Dereferences to a slice of the items.
+///
+/// The items borrow from the [`Fetcher`], so they are kept out of the
+/// buffer's `Vec`, which is empty until this is dropped.
+pub struct Collected<'a, T> {
+    items: NonNull<T>,
+    len: usize,
+    _marker: PhantomData<&'a mut T>,
+}
 
-            let idx = unsafe { *self.index.as_ptr() };
-            let arch = unsafe { self.archetypes.get(idx).unwrap_debug_checked() };
+impl<'a, T> Collected<'a, T> {
+    /// Clears `buffer` and moves the items of `iter` into its memory.
+    ///
+    /// # Safety
+    ///
+    /// `T` and `U` must only differ in lifetimes.
+    unsafe fn new<U, I>(buffer: &'a mut Vec<U>, iter: I) -> Self
+    where
+        I: ExactSizeIterator<Item = T>,
+    {
+        debug_assert_eq!(Layout::new::<T>(), Layout::new::<U>());
 
-            self.row = ArchetypeRow(0);
-            self.len = arch.entity_count();
+        buffer.clear();
+        buffer.reserve(iter.len());
 
-            // SAFETY: Fetcher state only contains nonempty archetypes.
-            unsafe { assume_debug_checked(self.len > 0) };
+        let items = NonNull::new_unchecked(buffer.as_mut_ptr()).cast::<T>();
+        let mut len = 0;
+
+        for item in iter.take(buffer.capacity()) {
+            items.as_ptr().add(len).write(item);
+            len += 1;
+        }
+
+        Self {
+            items,
+            len,
+            _marker: PhantomData,
         }
+    }
+}
+
+impl<T> Deref for Collected<'_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        unsafe { slice::from_raw_parts(self.items.as_ptr(), self.len) }
+    }
+}
+
+impl<T> DerefMut for Collected<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        unsafe { slice::from_raw_parts_mut(self.items.as_ptr(), self.len) }
+    }
+}
+
+impl<T> Drop for Collected<'_, T> {
+    fn drop(&mut self) {
+        unsafe { ptr::drop_in_place(&mut **self) };
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Collected<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+unsafe impl<T: Send> Send for Collected<'_, T> {}
+unsafe impl<T: Sync> Sync for Collected<'_, T> {}
+
+/// Iterator over entities matching the query `Q`.
+///
+/// Entities are visited in a deterministic but otherwise unspecified order.
+/// If `Q` contains an [`Added`] or [`Changed`] filter, [`len`] visits
+/// every remaining entity to count the matches.
+///
+/// [`Added`]: crate::query::Added
+/// [`Changed`]: crate::query::Changed
+/// [`len`]: ExactSizeIterator::len
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct Iter<'a, Q: Query> {
+    /// Pointer into the array of archetype states. This pointer moves forward
+    /// until it reaches `state_last`.
+    state: NonNull<Q::ArchState>,
+    /// Pointer to the last arch state, or dangling if there are no arch states.
+    /// This is _not_ a one-past-the-end pointer.
+    state_last: NonNull<Q::ArchState>,
+    /// Pointer into the array of archetype indices. This pointer moves forward
+    /// in lockstep with `state`.
+    index: NonNull<ArchetypeIdx>,
+    /// Current row of the current archetype.
+    row: ArchetypeRow,
+    /// Number of entities in the current archetype.
+    len: u32,
+    /// Change tick passed to [`Query::filter_row`].
+    last_run_tick: u64,
+    archetypes: &'a Archetypes,
+}
+
+impl<'a, Q: Query> Iterator for Iter<'a, Q> {
+    type Item = Q::Item<'a>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.row.0 == self.len {
+                if self.state == self.state_last {
+                    return None;
+                }
+
+                self.state = unsafe { NonNull::new_unchecked(self.state.as_ptr().add(1)) };
+                self.index = unsafe { NonNull::new_unchecked(self.index.as_ptr().add(1)) };
+
+                let idx = unsafe { *self.index.as_ptr() };
+                let arch = unsafe { self.archetypes.get(idx).unwrap_debug_checked() };
+
+                self.row = ArchetypeRow(0);
+                self.len = arch.entity_count();
 
-        let state = unsafe { &*self.state.as_ptr().cast_const() };
-        let item = unsafe { Q::get(state, self.row) };
+                // SAFETY: Fetcher state only contains nonempty archetypes.
+                unsafe { assume_debug_checked(self.len > 0) };
+            }
+
+            let state = unsafe { &*self.state.as_ptr().cast_const() };
+            let row = self.row;
 
-        self.row.0 += 1;
+            self.row.0 += 1;
 
-        Some(item)
+            if unsafe { Q::filter_row(state, row, self.last_run_tick) } {
+                return Some(unsafe { Q::get(state, row) });
+            }
+        }
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
@@ -540,6 +1363,10 @@ impl<'a, Q: Query> Iterator for Iter<'a, Q> {
 
 impl<Q: Query> ExactSizeIterator for Iter<'_, Q> {
     fn len(&self) -> usize {
+        if Q::FILTERS_ROWS {
+            return self.count_filtered();
+        }
+
         let mut remaining = self.len - self.row.0;
 
         let mut index = self.index.as_ptr();
@@ -560,6 +1387,39 @@ impl<Q: Query> ExactSizeIterator for Iter<'_, Q> {
     }
 }
 
+impl<Q: Query> Iter<'_, Q> {
+    /// Counts the remaining entities which pass the row filters of the query.
+    fn count_filtered(&self) -> usize {
+        if self.row.0 == self.len && self.state == self.state_last {
+            return 0;
+        }
+
+        let mut state = self.state.as_ptr().cast_const();
+        let mut index = self.index.as_ptr().cast_const();
+        let mut rows = self.row.0..self.len;
+        let mut count = 0;
+
+        loop {
+            let arch_state = unsafe { &*state };
+
+            count += rows
+                .filter(|&row| unsafe {
+                    Q::filter_row(arch_state, ArchetypeRow(row), self.last_run_tick)
+                })
+                .count();
+
+            if state == self.state_last.as_ptr().cast_const() {
+                return count;
+            }
+
+            state = unsafe { state.add(1) };
+            index = unsafe { index.add(1) };
+
+            rows = 0..unsafe { self.archetypes.get(*index).unwrap_debug_checked() }.entity_count();
+        }
+    }
+}
+
 impl<Q: Query> FusedIterator for Iter<'_, Q> {}
 
 // SAFETY: Iter is only cloneable when the query is read-only.
@@ -571,6 +1431,7 @@ impl<'a, Q: ReadOnlyQuery> Clone for Iter<'a, Q> {
             index: self.index,
             row: self.row,
             len: self.len,
+            last_run_tick: self.last_run_tick,
             archetypes: self.archetypes,
         }
     }
@@ -584,6 +1445,7 @@ impl<Q: Query> fmt::Debug for Iter<'_, Q> {
             .field("index", &self.index)
             .field("row", &self.row)
             .field("len", &self.len)
+            .field("last_run_tick", &self.last_run_tick)
             .field("archetypes", &self.archetypes)
             .finish()
     }
@@ -594,6 +1456,53 @@ impl<Q: Query> fmt::Debug for Iter<'_, Q> {
 unsafe impl<Q: Query> Send for Iter<'_, Q> {}
 unsafe impl<Q: Query> Sync for Iter<'_, Q> {}
 
+/// Iterator over the archetypes matching the [`ColumnQuery`] `Q`, returned by
+/// [`Fetcher::iter_columns`], [`Fetcher::iter_columns_mut`] and
+/// [`Fetcher::iter_archetype_slices`].
+///
+/// Archetypes are visited in the same order as [`Iter`].
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct ColumnIter<'a, Q: Query> {
+    states: slice::Iter<'a, Q::ArchState>,
+    indices: slice::Iter<'a, ArchetypeIdx>,
+    archetypes: &'a Archetypes,
+}
+
+impl<'a, Q: ColumnQuery> Iterator for ColumnIter<'a, Q> {
+    type Item = Q::Column<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let state = self.states.next()?;
+        let idx = *unsafe { self.indices.next().unwrap_debug_checked() };
+
+        let len = unsafe { self.archetypes.get(idx).unwrap_debug_checked() }.entity_count();
+
+        Some(unsafe { Q::get_column(state, len) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.states.size_hint()
+    }
+}
+
+impl<Q: ColumnQuery> ExactSizeIterator for ColumnIter<'_, Q> {}
+
+impl<Q: ColumnQuery> FusedIterator for ColumnIter<'_, Q> {}
+
+impl<Q: Query> fmt::Debug for ColumnIter<'_, Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ColumnIter")
+            .field("indices", &self.indices)
+            .field("archetypes", &self.archetypes)
+            .finish_non_exhaustive()
+    }
+}
+
+// SAFETY: `ColumnIter` iterates over component data only, which is always
+// `Send` and `Sync`.
+unsafe impl<Q: Query> Send for ColumnIter<'_, Q> {}
+unsafe impl<Q: Query> Sync for ColumnIter<'_, Q> {}
+
 #[cfg(feature = "rayon")]
 #[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
 pub use rayon_impl::*;
@@ -612,6 +1521,7 @@ mod rayon_impl {
     pub struct ParIter<'a, Q: Query> {
         pub(super) arch_states: &'a [Q::ArchState],
         pub(super) arch_indices: &'a [ArchetypeIdx],
+        pub(super) last_run_tick: u64,
         pub(super) archetypes: &'a Archetypes,
     }
 
@@ -620,6 +1530,7 @@ mod rayon_impl {
             Self {
                 arch_states: self.arch_states,
                 arch_indices: self.arch_indices,
+                last_run_tick: self.last_run_tick,
                 archetypes: self.archetypes,
             }
         }
@@ -630,6 +1541,7 @@ mod rayon_impl {
             f.debug_struct("ParIter")
                 .field("arch_states", &self.arch_states)
                 .field("arch_indices", &self.arch_indices)
+                .field("last_run_tick", &self.last_run_tick)
                 .field("archetypes", &self.archetypes)
                 .finish()
         }
@@ -655,10 +1567,14 @@ mod rayon_impl {
                     let entity_count =
                         unsafe { self.archetypes.get(index).unwrap_debug_checked() }.entity_count();
 
-                    (0..entity_count).into_par_iter().map(|row| {
-                        let item: Q::Item<'a> = unsafe { Q::get(state, ArchetypeRow(row)) };
-                        item
-                    })
+                    (0..entity_count)
+                        .into_par_iter()
+                        .map(ArchetypeRow)
+                        .filter(|&row| unsafe { Q::filter_row(state, row, self.last_run_tick) })
+                        .map(|row| {
+                            let item: Q::Item<'a> = unsafe { Q::get(state, row) };
+                            item
+                        })
                 })
                 .drive_unindexed(consumer)
         }
@@ -715,6 +1631,7 @@ mod tests {
     use alloc::collections::BTreeSet;
 
     use crate::prelude::*;
+    use crate::query::{ColumnMut, ColumnRef};
 
     #[derive(Event)]
     struct E1;
@@ -770,6 +1687,102 @@ mod tests {
         world.send(E3);
     }
 
+    #[test]
+    fn random_access_mut() {
+        let mut world = World::new();
+
+        let e = world.spawn();
+        let e2 = world.spawn();
+
+        world.insert(e, C1(1));
+        world.insert(e2, C2(2));
+
+        world.add_handler(move |_: Receiver<E1>, mut f: Fetcher<&mut C1>| {
+            f.get_mut(e).unwrap().0 += 10;
+
+            assert_eq!(
+                f.get_mut(EntityId::NULL).err(),
+                Some(GetError::NoSuchEntity)
+            );
+            assert_eq!(f.get_mut(e2).err(), Some(GetError::QueryDoesNotMatch));
+        });
+
+        world.send(E1);
+
+        assert_eq!(world.get::<C1>(e), Some(&C1(11)));
+    }
+
+    #[test]
+    fn get_many_mut() {
+        let mut world = World::new();
+
+        let e = world.spawn();
+        let e2 = world.spawn();
+        let e3 = world.spawn();
+
+        world.insert(e, C1(1));
+        world.insert(e2, C1(2));
+        world.insert(e3, C2(3));
+
+        world.add_handler(move |_: Receiver<E1>, mut f: Fetcher<&mut C1>| {
+            let [a, b] = f.get_many_mut([e, e2]).unwrap();
+            core::mem::swap(&mut a.0, &mut b.0);
+
+            assert_eq!(
+                f.get_many_mut([e2, e2]).err(),
+                Some(GetError::AliasedMutability)
+            );
+            assert_eq!(
+                f.get_many_mut([e, e2, e]).err(),
+                Some(GetError::AliasedMutability)
+            );
+            assert_eq!(
+                f.get_many_mut([e, e3]).err(),
+                Some(GetError::QueryDoesNotMatch)
+            );
+            assert_eq!(
+                f.get_many_mut([EntityId::NULL, e]).err(),
+                Some(GetError::NoSuchEntity)
+            );
+            assert!(f.get_many_mut([]).is_ok());
+        });
+
+        world.send(E1);
+
+        assert_eq!(world.get::<C1>(e), Some(&C1(2)));
+        assert_eq!(world.get::<C1>(e2), Some(&C1(1)));
+    }
+
+    #[test]
+    fn get_unchecked() {
+        let mut world = World::new();
+
+        let e = world.spawn();
+        world.insert(e, C1(123));
+
+        world.add_handler(move |_: Receiver<E1>, f: Fetcher<&C1>| {
+            assert_eq!(unsafe { f.get_unchecked(e) }, &C1(123));
+        });
+
+        world.send(E1);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "entity does not match the query")]
+    fn get_unchecked_no_match() {
+        let mut world = World::new();
+
+        let e = world.spawn();
+        world.insert(e, C2(123));
+
+        world.add_handler(move |_: Receiver<E1>, f: Fetcher<&C1>| {
+            let _ = unsafe { f.get_unchecked(e) };
+        });
+
+        world.send(E1);
+    }
+
     #[test]
     fn iter() {
         let mut world = World::new();
@@ -834,6 +1847,39 @@ mod tests {
         world.send(E1);
     }
 
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_iter_mut() {
+        use rayon::prelude::*;
+
+        let mut world = World::new();
+
+        const N: u32 = 20;
+
+        for i in 0..N {
+            let e = world.spawn();
+
+            world.insert(e, C1(i));
+
+            if i % 2 == 0 {
+                world.insert(e, C2(1));
+            }
+        }
+
+        world.add_handler(|_: Receiver<E1>, mut f: Fetcher<(&mut C1, Option<&C2>)>| {
+            f.par_iter_mut().for_each(|(c1, c2)| {
+                c1.0 += c2.map_or(0, |c2| c2.0);
+            });
+        });
+
+        world.add_handler(|_: Receiver<E1>, f: Fetcher<&C1>| {
+            let sum = f.iter().map(|c| c.0).sum::<u32>();
+            assert_eq!(sum, N * (N - 1) / 2 + N / 2);
+        });
+
+        world.send(E1);
+    }
+
     #[test]
     fn iter_empty() {
         let mut world = World::new();
@@ -893,6 +1939,682 @@ mod tests {
         world.send(E1);
     }
 
+    #[test]
+    fn iter_mut_with_filter() {
+        let mut world = World::new();
+
+        let mut entities = vec![];
+
+        for i in 0..12 {
+            let e = world.spawn();
+            world.insert(e, C1(i));
+
+            if i % 2 == 0 {
+                world.insert(e, C2(i));
+            }
+
+            if i % 3 == 0 {
+                world.insert(e, C3(i));
+            }
+
+            entities.push(e);
+        }
+
+        // Leave the `(C1, C3)` archetype empty.
+        for &e in entities.iter().skip(3).step_by(6) {
+            world.despawn(e);
+        }
+
+        world.add_handler(|_: Receiver<E1>, mut f: Fetcher<(&mut C1, With<&C2>)>| {
+            assert_eq!(f.iter_mut().len(), 6);
+
+            for (c, _) in &mut f {
+                c.0 += 100;
+            }
+        });
+
+        world.send(E1);
+
+        for (i, e) in entities.into_iter().enumerate() {
+            let expected = match (i % 2, i % 6) {
+                (_, 3) => None,
+                (0, _) => Some(C1(i as u32 + 100)),
+                _ => Some(C1(i as u32)),
+            };
+
+            assert_eq!(world.get::<C1>(e), expected.as_ref());
+        }
+    }
+
+    #[test]
+    fn iter_changed_between() {
+        use crate::query::ChangedBetween;
+
+        let mut world = World::new();
+
+        let e1 = world.spawn();
+        let e2 = world.spawn();
+        let e3 = world.spawn();
+        let e4 = world.spawn();
+
+        world.insert(e1, C1(1));
+        let from = world.change_tick() + 1;
+        world.insert(e2, C1(2));
+        world.insert(e3, C1(3));
+        world.insert(e3, C2(3));
+        let to = world.change_tick();
+        world.insert(e4, C1(4));
+
+        // Not a write to `C1`.
+        world.insert(e1, C2(1));
+
+        let window = ChangedBetween::<C1>::new(from, to);
+
+        world.add_handler(move |_: Receiver<E1>, f: Fetcher<EntityId>| {
+            let changed: BTreeSet<_> = f.iter_changed_between(window).collect();
+            assert_eq!(changed, BTreeSet::from([e2, e3]));
+        });
+
+        world.send(E1);
+
+        // Overwriting moves `e2` out of the window.
+        world.insert(e2, C1(20));
+
+        world.add_handler(move |_: Receiver<E2>, f: Fetcher<EntityId>| {
+            let changed: BTreeSet<_> = f.iter_changed_between(window).collect();
+            assert_eq!(changed, BTreeSet::from([e3]));
+        });
+
+        world.send(E2);
+    }
+
+    #[test]
+    fn added_and_changed_filters() {
+        use alloc::vec::Vec;
+
+        use crate::query::{Added, Changed};
+
+        #[derive(Event)]
+        struct Check {
+            added: Vec<EntityId>,
+            changed: Vec<EntityId>,
+        }
+
+        let mut world = World::new();
+
+        world.add_handler(
+            |r: Receiver<Check>,
+             added: Fetcher<(EntityId, Added<C1>)>,
+             changed: Fetcher<(EntityId, Changed<C1>)>| {
+                let added: Vec<_> = added.iter().map(|(e, _)| e).collect();
+                let changed: Vec<_> = changed.iter().map(|(e, _)| e).collect();
+
+                assert_eq!(added, r.event.added);
+                assert_eq!(changed, r.event.changed);
+            },
+        );
+
+        let e1 = world.spawn();
+        world.insert(e1, C1(1));
+
+        world.send(Check {
+            added: vec![e1],
+            changed: vec![e1],
+        });
+
+        // Nothing happened since the last run.
+        world.send(Check {
+            added: vec![],
+            changed: vec![],
+        });
+
+        // Overwriting is a change, but not an addition.
+        world.insert(e1, C1(10));
+
+        world.send(Check {
+            added: vec![],
+            changed: vec![e1],
+        });
+
+        let e2 = world.spawn();
+        world.insert(e2, C1(2));
+
+        world.send(Check {
+            added: vec![e2],
+            changed: vec![e2],
+        });
+    }
+
+    #[test]
+    fn filters_restart_after_handler_readded() {
+        use alloc::vec::Vec;
+
+        use crate::query::{Added, Changed};
+
+        #[derive(Event)]
+        struct Check {
+            added: Vec<EntityId>,
+            changed: Option<EntityId>,
+        }
+
+        fn check(
+            r: Receiver<Check>,
+            added: Fetcher<(EntityId, Added<C1>)>,
+            TrySingle(changed): TrySingle<(EntityId, Changed<C1>)>,
+        ) {
+            let added: Vec<_> = added.iter().map(|(e, _)| e).collect();
+
+            assert_eq!(added, r.event.added);
+            assert_eq!(changed.ok().map(|(e, _)| e), r.event.changed);
+        }
+
+        let mut world = World::new();
+
+        let e1 = world.spawn();
+        world.insert(e1, C1(1));
+
+        let id = world.add_handler(check);
+        world.send(Check {
+            added: vec![e1],
+            changed: Some(e1),
+        });
+        world.send(Check {
+            added: vec![],
+            changed: None,
+        });
+
+        world.remove_handler(id);
+
+        let e2 = world.spawn();
+        world.insert(e2, C1(2));
+        world.get_mut::<C1>(e1).unwrap().0 += 1;
+
+        // A handler added again starts from scratch, so everything counts as
+        // added and changed on its first run rather than what happened since
+        // the removed handler last ran.
+        world.add_handler(check);
+        world.send(Check {
+            added: vec![e1, e2],
+            changed: None,
+        });
+
+        world.get_mut::<C1>(e2).unwrap().0 += 1;
+        world.send(Check {
+            added: vec![],
+            changed: Some(e2),
+        });
+    }
+
+    #[test]
+    fn changed_tracks_mut() {
+        use alloc::vec::Vec;
+
+        use crate::query::{Changed, Mut};
+
+        #[derive(Event)]
+        struct Write(EntityId, EntityId);
+
+        #[derive(Event)]
+        struct Check(Vec<EntityId>);
+
+        #[derive(Event)]
+        struct Bump(usize);
+
+        let mut world = World::new();
+
+        world.add_handler(|r: Receiver<Write>, mut f: Fetcher<Mut<C1>>| {
+            f.get_mut(r.event.0).unwrap().0 += 1;
+
+            // Reading through `Mut` is not a write.
+            let read = f.get_mut(r.event.1).unwrap();
+            assert_eq!(read.0, 2);
+        });
+
+        world.add_handler(|r: Receiver<Check>, f: Fetcher<(EntityId, Changed<C1>)>| {
+            let changed: Vec<_> = f.iter().map(|(e, _)| e).collect();
+            assert_eq!(changed, r.event.0);
+        });
+
+        world.add_handler(|r: Receiver<Bump>, f: Fetcher<(Mut<C1>, Changed<C1>)>| {
+            let mut count = 0;
+
+            for (mut c, _) in f {
+                c.0 += 1;
+                count += 1;
+            }
+
+            assert_eq!(count, r.event.0);
+        });
+
+        let e1 = world.spawn();
+        let e2 = world.spawn();
+        world.insert(e1, C1(1));
+        world.insert(e2, C1(2));
+
+        world.send(Check(vec![e1, e2]));
+
+        world.send(Write(e1, e2));
+        world.send(Check(vec![e1]));
+        world.send(Check(vec![]));
+
+        // The handler doesn't see its own writes on its next run.
+        world.send(Bump(2));
+        world.send(Bump(0));
+        world.send(Check(vec![e1, e2]));
+    }
+
+    #[test]
+    fn changed_sees_every_mutable_access() {
+        use alloc::vec::Vec;
+
+        use crate::query::Changed;
+
+        #[derive(Event)]
+        struct Check(Vec<EntityId>);
+
+        #[derive(Event)]
+        struct WriteMany(EntityId, EntityId);
+
+        let mut world = World::new();
+
+        world.add_handler(|r: Receiver<Check>, f: Fetcher<(EntityId, Changed<C1>)>| {
+            let changed: BTreeSet<_> = f.iter().map(|(e, _)| e).collect();
+            assert_eq!(changed, r.event.0.iter().copied().collect());
+        });
+
+        world.add_handler(|r: Receiver<WriteMany>, mut f: Fetcher<&mut C1>| {
+            let [a, b] = f.get_many_mut([r.event.0, r.event.1]).unwrap();
+            core::mem::swap(a, b);
+        });
+
+        let e1 = world.spawn();
+        let e2 = world.spawn();
+        let e3 = world.spawn();
+
+        for e in [e1, e2, e3] {
+            world.insert(e, C1(0));
+        }
+
+        world.send(Check(vec![e1, e2, e3]));
+
+        world.get_mut::<C1>(e1).unwrap().0 += 1;
+        world.send(Check(vec![e1]));
+
+        world.entity_mut(e2).unwrap().get_mut::<C1>().unwrap().0 += 1;
+        world.send(Check(vec![e2]));
+
+        world.pairs_mut::<C1, _>(&[(e1, e3)], |_, _| {});
+        world.send(Check(vec![e1, e3]));
+
+        world.send(WriteMany(e2, e3));
+        world.send(Check(vec![e2, e3]));
+
+        world.send(Check(vec![]));
+    }
+
+    #[test]
+    fn changed_filter_on_random_access() {
+        use crate::query::Changed;
+
+        #[derive(Event)]
+        struct Check(EntityId, bool);
+
+        let mut world = World::new();
+
+        world.add_handler(|r: Receiver<Check>, f: Fetcher<(&C1, Changed<C1>)>| {
+            let Check(e, changed) = *r.event;
+
+            assert_eq!(f.get(e).is_ok(), changed);
+            assert_eq!(f.iter().len(), usize::from(changed));
+
+            if !changed {
+                assert_eq!(f.get(e).unwrap_err(), GetError::QueryDoesNotMatch);
+            }
+        });
+
+        let e = world.spawn();
+        world.insert(e, C1(1));
+        world.insert(e, C2(1));
+
+        world.send(Check(e, true));
+        world.send(Check(e, false));
+
+        world.get_mut::<C1>(e).unwrap().0 += 1;
+        world.send(Check(e, true));
+    }
+
+    #[test]
+    fn changed_in_derived_query() {
+        use crate::query::{Changed, Query};
+
+        #[derive(Query)]
+        struct Moved<'a> {
+            id: EntityId,
+            value: &'a C1,
+            _changed: Changed<C1>,
+        }
+
+        #[derive(Event)]
+        struct Check(Option<(EntityId, u32)>);
+
+        let mut world = World::new();
+
+        world.add_handler(|r: Receiver<Check>, f: Fetcher<Moved>| {
+            let moved = f.iter().map(|m| (m.id, m.value.0)).next();
+            assert_eq!(moved, r.event.0);
+        });
+
+        let e = world.spawn();
+        world.insert(e, C1(1));
+
+        world.send(Check(Some((e, 1))));
+        world.send(Check(None));
+
+        world.get_mut::<C1>(e).unwrap().0 = 2;
+        world.send(Check(Some((e, 2))));
+    }
+
+    #[test]
+    fn changed_conflicts_with_writers() {
+        use crate::query::{Changed, Mut};
+
+        let mut world = World::new();
+
+        let reader = world.add_handler(|_: Receiver<E1>, _: Fetcher<(EntityId, Changed<C1>)>| {});
+        let writer = world.add_handler(|_: Receiver<E1>, _: Fetcher<Mut<C1>>| {});
+        let other = world.add_handler(|_: Receiver<E1>, _: Fetcher<&mut C2>| {});
+
+        let info = |id| world.handlers().get(id).unwrap();
+
+        assert!(!info(reader).is_compatible(info(writer)));
+        assert!(!info(writer).is_compatible(info(reader)));
+        assert!(info(reader).is_compatible(info(other)));
+
+        // Mutable access to the filtered component in the same handler is fine.
+        world.add_handler(|_: Receiver<E1>, _: Fetcher<(&mut C1, Changed<C1>)>| {});
+    }
+
+    #[test]
+    #[should_panic(expected = "can't contain `Added` or `Changed` filters")]
+    fn receiver_query_rejects_changed() {
+        use crate::query::Changed;
+
+        #[derive(Event)]
+        struct Targeted(#[event(target)] EntityId);
+
+        let mut world = World::new();
+
+        world.add_handler(|_: Receiver<Targeted, Changed<C1>>| {});
+    }
+
+    #[test]
+    fn max_and_min_by_key() {
+        let mut world = World::new();
+
+        let e1 = world.spawn();
+        world.insert(e1, C1(3));
+
+        let e2 = world.spawn();
+        world.insert(e2, C1(7));
+        world.insert(e2, C2(0));
+
+        let e3 = world.spawn();
+        world.insert(e3, C1(1));
+        world.insert(e3, C3(0));
+
+        // Ties with `e2`, but comes later in iteration order.
+        let e4 = world.spawn();
+        world.insert(e4, C1(7));
+        world.insert(e4, C3(0));
+
+        world.add_handler(move |_: Receiver<E1>, f: Fetcher<&C1>| {
+            assert_eq!(f.max_by_key(|c| c.0), Some((e2, &C1(7))));
+            assert_eq!(f.min_by_key(|c| c.0), Some((e3, &C1(1))));
+        });
+
+        world.add_handler(|_: Receiver<E1>, f: Fetcher<(&C1, &C2, &C3)>| {
+            assert!(f.max_by_key(|(c, _, _)| c.0).is_none());
+            assert!(f.min_by_key(|(c, _, _)| c.0).is_none());
+        });
+
+        world.send(E1);
+    }
+
+    #[test]
+    fn presence_mask() {
+        let mut world = World::new();
+
+        let c1 = world.add_component::<C1>();
+        let c2 = world.add_component::<C2>();
+        let c3 = world.add_component::<C3>();
+
+        let e1 = world.spawn();
+        world.insert(e1, C1(0));
+
+        let e2 = world.spawn();
+        world.insert(e2, C1(0));
+        world.insert(e2, C3(0));
+
+        let e3 = world.spawn();
+        world.insert(e3, C1(0));
+        world.insert(e3, C2(0));
+        world.insert(e3, C3(0));
+
+        let e4 = world.spawn();
+        world.insert(e4, C2(0));
+
+        world.add_handler(move |_: Receiver<E1>, f: Fetcher<&C1>| {
+            let masks: BTreeSet<_> = f.presence_mask(&[c1, c2, c3]).into_iter().collect();
+
+            assert_eq!(
+                masks,
+                BTreeSet::from([(e1, 0b001), (e2, 0b101), (e3, 0b111)])
+            );
+        });
+
+        world.send(E1);
+    }
+
+    #[test]
+    fn query_buffer() {
+        use crate::entity::EntityLocation;
+        use crate::fetch::QueryBuffer;
+
+        let mut world = World::new();
+
+        let mut ids = vec![];
+
+        for i in 0..10 {
+            let e = world.spawn();
+            world.insert(e, C1(i % 5));
+
+            if i % 2 == 0 {
+                world.insert(e, C2(i));
+            }
+
+            ids.push(e);
+        }
+
+        world.add_handler(
+            |_: Receiver<E1>, mut f: Fetcher<&mut C1>, mut buf: QueryBuffer<&mut C1>| {
+                let capacity = buf.capacity();
+                assert!(buf.is_empty());
+
+                let mut items = f.collect_into_mut(&mut buf);
+                items.sort_unstable_by_key(|c| core::cmp::Reverse(c.0));
+
+                for (i, c) in items.iter_mut().enumerate() {
+                    c.0 = i as u32;
+                }
+
+                drop(items);
+
+                // The memory is kept for the next run.
+                assert!(buf.is_empty());
+                assert!(buf.capacity() >= 10);
+                assert!(capacity == 0 || capacity == buf.capacity());
+            },
+        );
+
+        world.send(E1);
+        world.send(E1);
+
+        let mut values: Vec<_> = ids.iter().map(|&e| world.get::<C1>(e).unwrap().0).collect();
+        values.sort_unstable();
+        assert_eq!(values, (0..10).collect::<Vec<_>>());
+
+        world.add_handler(
+            |_: Receiver<E2>,
+             f: Fetcher<(EntityId, &C1)>,
+             mut buf: QueryBuffer<(u32, EntityLocation)>| {
+                let sorted: Vec<_> = f
+                    .iter_sorted_by_key_cached(|(_, c)| c.0 / 2, &mut buf)
+                    .collect();
+
+                assert_eq!(sorted.len(), 10);
+
+                // Sorted by key, then in iteration order.
+                let order: Vec<_> = f.iter().map(|(id, _)| id).collect();
+                for pair in sorted.windows(2) {
+                    let [(a, ca), (b, cb)] = pair else {
+                        unreachable!()
+                    };
+                    let pos = |e| order.iter().position(|&x| x == e);
+
+                    assert!(ca.0 / 2 < cb.0 / 2 || (ca.0 / 2 == cb.0 / 2 && pos(*a) < pos(*b)));
+                }
+            },
+        );
+
+        world.send(E2);
+
+        let expected: BTreeSet<_> = ids.iter().copied().step_by(2).collect();
+
+        world.add_handler(
+            move |_: Receiver<E3>,
+                  f: Fetcher<(EntityId, With<&C2>)>,
+                  mut buf: QueryBuffer<EntityId>| {
+                buf.push(EntityId::NULL);
+                f.collect_ids_into(&mut buf);
+
+                assert_eq!(buf.iter().copied().collect::<BTreeSet<_>>(), expected);
+            },
+        );
+
+        world.send(E3);
+    }
+
+    #[test]
+    fn iter_columns() {
+        let mut world = World::new();
+
+        for i in 1..=20 {
+            let e = world.spawn();
+            world.insert(e, C1(i));
+
+            if i % 2 == 0 {
+                world.insert(e, C2(i));
+            }
+
+            if i % 3 == 0 {
+                world.insert(e, C3(i));
+            }
+        }
+
+        world.add_handler(|_: Receiver<E1>, mut f: Fetcher<ColumnMut<C1>>| {
+            for col in f.iter_columns_mut() {
+                for c in col {
+                    c.0 *= 10;
+                }
+            }
+        });
+
+        world.add_handler(
+            |_: Receiver<E2>,
+             cols: Fetcher<(EntityId, ColumnRef<C1>, Not<&C3>)>,
+             rows: Fetcher<(EntityId, &C1, Not<&C3>)>| {
+                let by_column: Vec<_> = cols
+                    .iter_columns()
+                    .flat_map(|(ids, c1, _)| {
+                        assert_eq!(ids.len(), c1.len());
+                        ids.iter().copied().zip(c1.iter().map(|c| c.0))
+                    })
+                    .collect();
+
+                let by_row: Vec<_> = rows.iter().map(|(id, c1, _)| (id, c1.0)).collect();
+
+                assert_eq!(by_column, by_row);
+                assert_eq!(by_row.len(), 14);
+                assert!(by_row.iter().all(|&(_, c)| c % 10 == 0));
+            },
+        );
+
+        world.send(E1);
+        world.send(E2);
+    }
+
+    #[test]
+    fn iter_archetype_slices() {
+        #[derive(Component)]
+        struct Zst;
+
+        let mut world = World::new();
+
+        for i in 1..=20 {
+            let e = world.spawn();
+            world.insert(e, C1(i));
+            world.insert(e, C2(i));
+            world.insert(e, Zst);
+
+            if i % 3 == 0 {
+                world.insert(e, C3(i));
+            }
+        }
+
+        // Matches `C1` without `C2`, so it's left alone.
+        let e = world.spawn();
+        world.insert(e, C1(0));
+
+        world.add_handler(|_: Receiver<E1>, mut f: Fetcher<(&mut C1, &C2, &Zst)>| {
+            let mut lens = vec![];
+
+            for (c1, c2, zst) in f.iter_archetype_slices() {
+                assert_eq!(c1.len(), c2.len());
+                assert_eq!(c1.len(), zst.len());
+
+                for (a, b) in c1.iter_mut().zip(c2) {
+                    a.0 += b.0;
+                }
+
+                lens.push(c1.len());
+            }
+
+            lens.sort();
+            assert_eq!(lens, [6, 14]);
+        });
+
+        world.send(E1);
+
+        world.add_handler(|_: Receiver<E2>, f: Fetcher<&C1>| {
+            let mut sum: u32 = f.iter().map(|c| c.0).sum();
+            sum -= (1..=20).sum::<u32>() * 2;
+            assert_eq!(sum, 0);
+        });
+
+        world.send(E2);
+    }
+
+    #[test]
+    fn column_mut_conflicts() {
+        let mut world = World::new();
+
+        let res = std::panic::catch_unwind(move || {
+            world.add_handler(|_: Receiver<E1>, _: Fetcher<(ColumnMut<C1>, &C1)>| {});
+        });
+
+        assert!(res.is_err());
+    }
+
     #[test]
     fn single_param() {
         let mut world = World::new();
@@ -0,0 +1,349 @@
+//! Handling events in the middle of a handler.
+//!
+//! See [`Flush`] for more information.
+
+use alloc::format;
+use core::{any, fmt};
+
+use crate::access::Access;
+use crate::archetype::Archetype;
+use crate::entity::EntityLocation;
+use crate::event::EventPtr;
+use crate::handler::{Config, HandlerInfo, HandlerParam, InitError, Locals};
+use crate::world::{UnsafeWorldCell, World};
+
+/// A [`HandlerParam`] which handles the events sent by its handler so far
+/// without waiting for the handler to return.
+///
+/// Structural changes like spawning entities and inserting components are
+/// events, so they normally only take effect once the handler returns. Calling
+/// [`Flush::flush`] sends every event the handler has queued with its
+/// [`Sender`] immediately, along with the events they cause, and then lets the
+/// handler continue.
+///
+/// Flushing moves entities and components around, so no part of the world may
+/// be borrowed while it happens. The handler parameter `P` holds everything
+/// the handler accesses in the world. It is only reachable through
+/// [`Flush::get`], which borrows the `Flush` so the borrow checker rejects
+/// holding on to anything from `P` across a call to [`Flush::flush`]. For the
+/// same reason, lifetimes in `P` are written as `'static` and replaced by the
+/// lifetime of that borrow.
+/// Parameters outside of the `Flush` which hold references into the world,
+/// such as a [`Fetcher`] or a [`Receiver`] with a query, are rejected during
+/// initialization if they come after the `Flush`, and the `Flush` is rejected
+/// if they come before it. `P` may not contain a [`Receiver`] with a query
+/// since the target of the event could move.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::flush::Flush;
+/// use evenio::prelude::*;
+///
+/// # #[derive(Event)] struct E;
+/// #[derive(Component)]
+/// struct Health(u32);
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(
+///     |_: Receiver<E>,
+///      mut sender: Sender<(Spawn, Insert<Health>)>,
+///      mut flush: Flush<Fetcher<'static, &'static Health>>| {
+///         let e = sender.spawn();
+///         sender.insert(e, Health(100));
+///
+///         flush.flush();
+///
+///         assert_eq!(flush.get().get(e).unwrap().0, 100);
+///     },
+/// );
+///
+/// world.send(E);
+/// ```
+///
+/// Items from `P` cannot be held across a flush:
+///
+/// ```compile_fail
+/// # use evenio::flush::Flush;
+/// # use evenio::prelude::*;
+/// # #[derive(Event)] struct E;
+/// # #[derive(Component)] struct Health(u32);
+/// # let mut world = World::new();
+/// world.add_handler(|_: Receiver<E>, mut flush: Flush<Fetcher<'static, &'static Health>>| {
+///     let fetcher = flush.get();
+///     flush.flush();
+///     let _ = fetcher.iter().count();
+/// });
+/// ```
+///
+/// # Panics
+///
+/// [`Flush::flush`] panics if one of the flushed events is received by the
+/// handler which is flushing.
+///
+/// [`Sender`]: crate::event::Sender
+/// [`Fetcher`]: crate::fetch::Fetcher
+/// [`Receiver`]: crate::event::Receiver
+pub struct Flush<'a, P: HandlerParam = ()> {
+    state: &'a mut P::State,
+    info: &'a HandlerInfo,
+    event_ptr: EventPtr<'a>,
+    world: UnsafeWorldCell<'a>,
+    /// Length of the event queue when the handler started.
+    from: usize,
+}
+
+impl<P: HandlerParam> Flush<'_, P> {
+    /// Handles every event sent by the handler so far, along with the events
+    /// they cause. Entities spawned by the handler exist afterwards, and the
+    /// state of `P` is brought up to date with the world.
+    pub fn flush(&mut self) {
+        // SAFETY:
+        // - `from` was read when the handler started.
+        // - `P` is the only parameter of the handler holding references into the world,
+        //   and nothing obtained from it outlives `&mut self`.
+        unsafe { self.world.flush_handler_events(self.info, self.from) };
+
+        let handler = self.info.ptr();
+
+        for arch in self.world.archetypes().iter() {
+            if arch.has_refresh_listener(handler) {
+                if arch.entity_count() > 0 {
+                    P::refresh_archetype(self.state, arch);
+                } else {
+                    P::remove_archetype(self.state, arch);
+                }
+            }
+        }
+    }
+
+    /// Returns the item of the handler parameter `P`. The item borrows the
+    /// `Flush`, so it cannot be held across a call to [`Flush::flush`].
+    pub fn get(&mut self) -> P::Item<'_> {
+        // SAFETY: `P` was initialized with the handler and its state is up to
+        // date. `P` never uses the target of the received event.
+        unsafe {
+            P::get(
+                self.state,
+                self.info,
+                self.event_ptr,
+                EntityLocation::NULL,
+                self.world,
+            )
+        }
+    }
+}
+
+unsafe impl<P: HandlerParam> HandlerParam for Flush<'_, P> {
+    type State = P::State;
+
+    type Item<'a> = Flush<'a, P>;
+
+    fn init(world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+        if config.world_access != Access::None {
+            return Err(InitError(
+                format!(
+                    "`{}` conflicts with a previous handler parameter which holds references into \
+                     the world. Move that parameter into the `Flush` instead",
+                    any::type_name::<Self>()
+                )
+                .into(),
+            ));
+        }
+
+        let targeted_event_expr = config.targeted_event_expr.clone();
+
+        let state = P::init(world, config)?;
+
+        if config.world_access == Access::ReadWrite {
+            return Err(InitError(
+                format!("`{}` contains another `Flush`", any::type_name::<Self>()).into(),
+            ));
+        }
+
+        if config.targeted_event_expr != targeted_event_expr {
+            return Err(InitError(
+                format!(
+                    "`{}` contains a `Receiver` with a query, whose target may move when flushed",
+                    any::type_name::<Self>()
+                )
+                .into(),
+            ));
+        }
+
+        config.world_access = Access::ReadWrite;
+
+        Ok(state)
+    }
+
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        info: &'a HandlerInfo,
+        event_ptr: EventPtr<'a>,
+        _target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::Item<'a> {
+        Flush {
+            state,
+            info,
+            event_ptr,
+            world,
+            from: world.handler_events_from(),
+        }
+    }
+
+    fn refresh_archetype(state: &mut Self::State, arch: &Archetype) {
+        P::refresh_archetype(state, arch)
+    }
+
+    fn remove_archetype(state: &mut Self::State, arch: &Archetype) {
+        P::remove_archetype(state, arch)
+    }
+
+    fn teardown(state: &mut Self::State, world: &mut World) {
+        P::teardown(state, world)
+    }
+
+    fn take_locals(state: &mut Self::State, locals: &mut Locals) {
+        P::take_locals(state, locals)
+    }
+
+    fn restore_locals(state: &mut Self::State, locals: &mut Locals) {
+        P::restore_locals(state, locals)
+    }
+}
+
+impl<P: HandlerParam> fmt::Debug for Flush<'_, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Flush")
+            .field("from", &self.from)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use std::panic;
+
+    use super::*;
+    use crate::entity::Entities;
+    use crate::prelude::*;
+
+    #[derive(Event)]
+    struct E;
+
+    #[derive(Component, PartialEq, Debug)]
+    struct C(u32);
+
+    #[derive(Component, Default)]
+    struct Log(Vec<&'static str>);
+
+    type FetcherAndLog = (
+        Fetcher<'static, (EntityId, &'static C)>,
+        Single<'static, &'static mut Log>,
+    );
+
+    #[test]
+    fn spawn_flush_fetch() {
+        let mut world = World::new();
+
+        let log = world.spawn();
+        world.insert(log, Log::default());
+
+        world.add_handler(
+            |r: Receiver<Insert<C>, ()>, Single(log): Single<&mut Log>| {
+                let _ = r;
+                log.0.push("inserted");
+            },
+        );
+
+        // An earlier handler leaves a spawn queued.
+        world.add_handler(|_: Receiver<E>, mut s: Sender<Spawn>| {
+            s.spawn();
+        });
+
+        world.add_handler(
+            |_: Receiver<E>,
+             mut s: Sender<(Spawn, Insert<C>, Despawn)>,
+             mut flush: Flush<FetcherAndLog>| {
+                let e = s.spawn();
+                s.insert(e, C(1));
+
+                flush.get().1 .0 .0.push("before flush");
+                flush.flush();
+
+                let (fetcher, Single(log)) = flush.get();
+                assert_eq!(fetcher.get(e), Ok((e, &C(1))));
+                log.0.push("after flush");
+
+                s.insert(e, C(2));
+                flush.flush();
+
+                assert_eq!(flush.get().0.get(e), Ok((e, &C(2))));
+            },
+        );
+
+        world.send(E);
+
+        assert_eq!(
+            world.get::<Log>(log).unwrap().0,
+            ["before flush", "inserted", "after flush", "inserted"]
+        );
+        assert_eq!(world.entities().len(), 3);
+
+        // The entity exists once the handler has returned, and the handler
+        // still works after being brought up to date.
+        world.send(E);
+
+        #[derive(Event)]
+        struct Check;
+
+        world.add_handler(|_: Receiver<Check>, f: Fetcher<&C>| {
+            assert_eq!(f.iter().collect::<Vec<_>>(), vec![&C(2), &C(2)]);
+        });
+
+        world.send(Check);
+    }
+
+    fn init_fails<H: IntoHandler<M>, M>(handler: H) -> bool {
+        panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            World::new().add_handler(handler);
+        }))
+        .is_err()
+    }
+
+    #[test]
+    fn conflicting_params() {
+        assert!(init_fails(|_: Receiver<E>, _: Fetcher<&C>, _: Flush| {}));
+        assert!(init_fails(|_: Receiver<E>, _: Flush, _: &Entities| {}));
+        assert!(init_fails(|_: Receiver<E>, _: Flush<Flush<'static>>| {}));
+        assert!(init_fails(
+            |_: Flush<Receiver<'static, Insert<C>, &'static C>>| {}
+        ));
+
+        assert!(!init_fails(
+            |_: Receiver<E>, _: Sender<Spawn>, _: Flush<&'static Entities>| {}
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "while flushing its own events")]
+    fn receive_own_event() {
+        #[derive(Event)]
+        struct Ping(u32);
+
+        let mut world = World::new();
+
+        world.add_handler(|r: Receiver<Ping>, mut s: Sender<Ping>, mut flush: Flush| {
+            if r.event.0 == 0 {
+                s.send(Ping(1));
+                flush.flush();
+            }
+        });
+
+        world.send(Ping(0));
+    }
+}
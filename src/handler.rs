@@ -2,31 +2,37 @@
 
 use alloc::borrow::Cow;
 use alloc::collections::BTreeMap;
+use alloc::format;
 #[cfg(not(feature = "std"))]
 use alloc::{boxed::Box, vec, vec::Vec};
 use core::any::TypeId;
 use core::cmp::Ordering;
 use core::hash::{Hash, Hasher};
 use core::marker::PhantomData;
+use core::mem::{self, ManuallyDrop};
 use core::ops::{Deref, DerefMut, Index};
 use core::panic::{RefUnwindSafe, UnwindSafe};
 use core::ptr::NonNull;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering as AtomicOrdering};
 use core::{any, fmt};
 
 use evenio_macros::all_tuples;
 pub use evenio_macros::HandlerParam;
 
-use crate::access::{Access, ComponentAccessExpr};
+use crate::access::{Access, AccessMap, ComponentAccessExpr};
 use crate::aliased_box::AliasedBox;
-use crate::archetype::Archetype;
+use crate::archetype::{Archetype, MatchExprIdx};
 use crate::assert::UnwrapDebugChecked;
 use crate::bit_set::BitSet;
 use crate::bool_expr::BoolExpr;
-use crate::component::ComponentIdx;
-use crate::entity::EntityLocation;
-use crate::event::{Event, EventId, EventIdx, EventPtr, TargetedEventIdx, UntargetedEventIdx};
+use crate::component::{ComponentId, ComponentIdx};
+use crate::entity::{EntityId, EntityLocation};
+use crate::event::{
+    Event, EventId, EventIdx, EventOwnership, EventPtr, TargetedEventIdx, UntargetedEventIdx,
+};
 use crate::exclusive::Exclusive;
 use crate::map::TypeIdMap;
+use crate::resource::ResourceIdx;
 use crate::slot_map::{Key, SlotMap};
 use crate::sparse::SparseIndex;
 use crate::world::{UnsafeWorldCell, World};
@@ -51,6 +57,10 @@ pub struct Handlers {
     /// Maps untargeted event indices to the ordered list of handlers that
     /// handle the event.
     by_untargeted_event: Vec<HandlerList>,
+    /// Maps targeted event indices to the ordered list of all handlers that
+    /// handle the event. Only used for [`Handlers::dispatch_groups`], since
+    /// targeted events are dispatched with the lists of archetypes.
+    by_targeted_event: Vec<HandlerList>,
     by_type_id: TypeIdMap<HandlerInfoPtr>,
     /// Counts up as new handlers are added.
     insert_counter: u64,
@@ -64,18 +74,19 @@ impl Handlers {
         Self {
             infos: SlotMap::new(),
             by_untargeted_event: vec![],
+            by_targeted_event: vec![],
             by_type_id: Default::default(),
             insert_counter: 0,
             by_insert_order: BTreeMap::new(),
         }
     }
 
+    /// Adds the handler and returns its ID. Panics if the run order
+    /// constraints of the handler can't be satisfied.
+    #[track_caller]
     pub(crate) fn add(&mut self, info: HandlerInfo) -> HandlerId {
         let ptr = info.ptr();
-
-        if let Some(type_id) = info.type_id() {
-            assert!(self.by_type_id.insert(type_id, ptr).is_none());
-        }
+        let type_id = info.type_id();
 
         let Some(k) = self.infos.insert_with(|k| {
             let id = HandlerId(k);
@@ -85,22 +96,30 @@ impl Handlers {
             inner.id = id;
             inner.order = self.insert_counter;
 
-            if let EventIdx::Untargeted(idx) = info.received_event().index() {
-                let idx = idx.0 as usize;
-
-                if idx >= self.by_untargeted_event.len() {
-                    self.by_untargeted_event
-                        .resize_with(idx + 1, HandlerList::default);
-                }
-
-                self.by_untargeted_event[idx].insert(ptr, info.priority())
-            }
-
             info
         }) else {
             panic!("too many handlers")
         };
 
+        let info = unsafe { ptr.as_info() };
+        let list = self.list_mut(info.received_event().index());
+
+        match list.insert_ordered(ptr) {
+            Ok(idx) => {
+                list.update_ranks();
+                list.regroup_from(idx);
+            }
+            Err(e) => {
+                let msg = format!("{e}");
+                self.infos.remove(k);
+                panic!("{msg}");
+            }
+        }
+
+        if let Some(type_id) = type_id {
+            assert!(self.by_type_id.insert(type_id, ptr).is_none());
+        }
+
         self.by_insert_order.insert(self.insert_counter, ptr);
         self.insert_counter += 1;
 
@@ -112,11 +131,10 @@ impl Handlers {
     pub(crate) fn remove(&mut self, id: HandlerId) -> Option<HandlerInfo> {
         let info = self.infos.remove(id.0)?;
 
-        let received_event = info.received_event();
-
-        if received_event.is_untargeted() {
-            let list = &mut self.by_untargeted_event[received_event.index().as_u32() as usize];
+        let list = self.list_mut(info.received_event().index());
+        if let Some(idx) = list.position(info.ptr()) {
             list.remove(info.ptr());
+            list.regroup_from(idx);
         }
 
         if let Some(type_id) = info.type_id() {
@@ -130,13 +148,83 @@ impl Handlers {
         Some(info)
     }
 
+    /// Puts `info` in place of the handler `id`, keeping its ID and insertion
+    /// order, and returns the info of the replaced handler. `info` must have
+    /// the same received event and priority as the replaced handler.
+    pub(crate) fn replace(&mut self, id: HandlerId, info: HandlerInfo) -> Option<HandlerInfo> {
+        let slot = self.infos.get(id.0)?;
+
+        let old_ptr = slot.ptr();
+        let new_ptr = info.ptr();
+
+        debug_assert_eq!(slot.received_event(), info.received_event());
+        debug_assert_eq!(slot.priority(), info.priority());
+
+        let order = slot.order();
+        let rank = slot.rank();
+
+        let inner = unsafe { &mut *new_ptr.0.as_ptr() };
+        inner.id = id;
+        inner.order = order;
+        inner.rank = rank;
+
+        if let Some(type_id) = slot.type_id() {
+            self.by_type_id.remove(&type_id);
+        }
+
+        if let Some(type_id) = info.type_id() {
+            assert!(self.by_type_id.insert(type_id, new_ptr).is_none());
+        }
+
+        let list = self.list_mut(info.received_event().index());
+        let replaced = list.replace(old_ptr, new_ptr);
+        debug_assert!(replaced);
+        list.regroup_at(new_ptr);
+
+        self.by_insert_order.insert(order, new_ptr);
+
+        Some(mem::replace(self.infos.get_mut(id.0)?, info))
+    }
+
     pub(crate) fn register_event(&mut self, event_idx: EventIdx) {
-        if let EventIdx::Untargeted(UntargetedEventIdx(idx)) = event_idx {
-            if idx as usize >= self.by_untargeted_event.len() {
-                self.by_untargeted_event
-                    .resize_with(idx as usize + 1, HandlerList::default);
-            }
+        self.list_mut(event_idx);
+    }
+
+    /// Gets the list of all handlers of an event, growing the lists as
+    /// needed.
+    fn list_mut(&mut self, event_idx: EventIdx) -> &mut HandlerList {
+        let (lists, idx) = match event_idx {
+            EventIdx::Untargeted(idx) => (&mut self.by_untargeted_event, idx.0 as usize),
+            EventIdx::Targeted(idx) => (&mut self.by_targeted_event, idx.0 as usize),
+        };
+
+        if idx >= lists.len() {
+            lists.resize_with(idx + 1, HandlerList::default);
         }
+
+        &mut lists[idx]
+    }
+
+    /// Returns the handlers of an event split into groups. See
+    /// [`World::dispatch_groups`].
+    pub(crate) fn dispatch_groups(&self, event_idx: EventIdx) -> Vec<Vec<HandlerId>> {
+        let list = match event_idx {
+            EventIdx::Untargeted(idx) => self.by_untargeted_event.get(idx.0 as usize),
+            EventIdx::Targeted(idx) => self.by_targeted_event.get(idx.0 as usize),
+        };
+
+        let Some(list) = list else {
+            return vec![];
+        };
+
+        list.groups()
+            .map(|group| {
+                group
+                    .iter()
+                    .map(|ptr| unsafe { ptr.as_info() }.id())
+                    .collect()
+            })
+            .collect()
     }
 
     pub(crate) fn get_untargeted_list(&self, idx: UntargetedEventIdx) -> Option<&HandlerList> {
@@ -229,8 +317,8 @@ unsafe impl HandlerParam for &'_ Handlers {
 
     type Item<'a> = &'a Handlers;
 
-    fn init(_world: &mut World, _config: &mut Config) -> Result<Self::State, InitError> {
-        Ok(())
+    fn init(_world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+        config.read_world::<Self>()
     }
 
     unsafe fn get<'a>(
@@ -317,9 +405,28 @@ pub(crate) struct HandlerInfoInner<H: ?Sized = dyn Handler> {
     pub(crate) sent_untargeted_events: BitSet<UntargetedEventIdx>,
     pub(crate) sent_targeted_events: BitSet<TargetedEventIdx>,
     pub(crate) event_queue_access: Access,
+    pub(crate) world_access: Access,
     pub(crate) component_access: ComponentAccessExpr,
     pub(crate) referenced_components: BitSet<ComponentIdx>,
+    pub(crate) change_tick_reads: BitSet<ComponentIdx>,
+    pub(crate) resource_access: AccessMap<ResourceIdx>,
     pub(crate) priority: Priority,
+    pub(crate) run_before: Vec<HandlerId>,
+    pub(crate) run_after: Vec<HandlerId>,
+    /// Position of the handler in the list of handlers for its received
+    /// event. Used to order the lists of archetypes the same way.
+    pub(crate) rank: u32,
+    pub(crate) throttle: Option<ThrottleCounters>,
+    /// Cached match expressions of `component_access` and
+    /// `targeted_event_expr`. Filled in when registered with [`Archetypes`].
+    ///
+    /// [`Archetypes`]: crate::archetype::Archetypes
+    pub(crate) match_exprs: [MatchExprIdx; 2],
+    /// Whether the handler is matched against every archetype. See
+    /// [`World::add_handler_incremental`].
+    ///
+    /// [`World::add_handler_incremental`]: crate::world::World::add_handler_incremental
+    pub(crate) fully_matched: bool,
     // SAFETY: There is intentionally no public accessor for this field as it would lead to mutable
     // aliasing.
     pub(crate) handler: H,
@@ -391,6 +498,12 @@ impl HandlerInfo {
         unsafe { (*AliasedBox::as_ptr(&self.0)).event_queue_access }
     }
 
+    /// Gets this handler's [`Access`] to the world as a whole. See
+    /// [`Config::world_access`].
+    pub fn world_access(&self) -> Access {
+        unsafe { (*AliasedBox::as_ptr(&self.0)).world_access }
+    }
+
     /// Gets the expression describing this handler's access
     pub fn component_access(&self) -> &ComponentAccessExpr {
         unsafe { &(*AliasedBox::as_ptr(&self.0)).component_access }
@@ -404,11 +517,120 @@ impl HandlerInfo {
         unsafe { &(*AliasedBox::as_ptr(&self.0)).referenced_components }
     }
 
+    /// Gets the set of components whose change ticks are read by this
+    /// handler. See [`Config::change_tick_reads`].
+    pub fn change_tick_reads(&self) -> &BitSet<ComponentIdx> {
+        unsafe { &(*AliasedBox::as_ptr(&self.0)).change_tick_reads }
+    }
+
+    /// Gets the handler's access to resources. See
+    /// [`Config::resource_access`].
+    pub fn resource_access(&self) -> &AccessMap<ResourceIdx> {
+        unsafe { &(*AliasedBox::as_ptr(&self.0)).resource_access }
+    }
+
     /// Gets the [`Priority`] of this handler.
     pub fn priority(&self) -> Priority {
         unsafe { (*AliasedBox::as_ptr(&self.0)).priority }
     }
 
+    /// Returns the handlers this handler must run before. See [`Before`].
+    pub fn run_before(&self) -> &[HandlerId] {
+        unsafe { &(*AliasedBox::as_ptr(&self.0)).run_before }
+    }
+
+    /// Returns the handlers this handler must run after. See [`After`].
+    pub fn run_after(&self) -> &[HandlerId] {
+        unsafe { &(*AliasedBox::as_ptr(&self.0)).run_after }
+    }
+
+    /// Returns `true` if this handler must run before `other` according to
+    /// the run order constraints of either of them.
+    pub(crate) fn must_run_before(&self, other: &HandlerInfo) -> bool {
+        self.run_before().contains(&other.id()) || other.run_after().contains(&self.id())
+    }
+
+    pub(crate) fn rank(&self) -> u32 {
+        unsafe { (*AliasedBox::as_ptr(&self.0)).rank }
+    }
+
+    /// Gets the throttling counters of this handler, or `None` if the handler
+    /// is not throttled. See [`Throttle`].
+    pub fn throttle_stats(&self) -> Option<ThrottleStats> {
+        self.throttle_counters().map(|c| ThrottleStats {
+            every: c.every,
+            runs: c.runs.load(AtomicOrdering::Relaxed),
+            skipped: c.skipped.load(AtomicOrdering::Relaxed),
+            pending: c.pending.load(AtomicOrdering::Relaxed),
+        })
+    }
+
+    /// Returns `true` if this handler and `other` can run in either order, or
+    /// concurrently, without observing each other's effects. This is the case
+    /// when none of their accesses to the received event, the event queue,
+    /// the world, components, change ticks, and resources conflict.
+    pub fn is_compatible(&self, other: &HandlerInfo) -> bool {
+        (self.received_event() != other.received_event()
+            || self
+                .received_event_access()
+                .is_compatible(other.received_event_access()))
+            && self
+                .event_queue_access()
+                .is_compatible(other.event_queue_access())
+            && self.world_access().is_compatible(other.world_access())
+            && self
+                .component_access()
+                .is_compatible(other.component_access())
+            && !self.writes_change_ticks_of(other.change_tick_reads())
+            && !other.writes_change_ticks_of(self.change_tick_reads())
+            && self
+                .resource_access()
+                .is_compatible(other.resource_access())
+    }
+
+    /// Returns `true` if this handler may write the change tick of any of the
+    /// given components.
+    fn writes_change_ticks_of(&self, components: &BitSet<ComponentIdx>) -> bool {
+        let access = &self.component_access().access;
+
+        components
+            .iter()
+            .any(|idx| access.get(idx) == Access::ReadWrite)
+    }
+
+    /// Returns `true` if this handler is matched against every archetype.
+    ///
+    /// This is always the case for handlers added with
+    /// [`World::add_handler`]. Handlers added with
+    /// [`World::add_handler_incremental`] become fully matched after a few
+    /// cascades.
+    ///
+    /// [`World::add_handler`]: crate::world::World::add_handler
+    /// [`World::add_handler_incremental`]: crate::world::World::add_handler_incremental
+    pub fn fully_matched(&self) -> bool {
+        unsafe { (*AliasedBox::as_ptr(&self.0)).fully_matched }
+    }
+
+    pub(crate) fn set_fully_matched(&mut self) {
+        unsafe { (*AliasedBox::as_mut_ptr(&mut self.0)).fully_matched = true };
+    }
+
+    pub(crate) fn throttle_counters(&self) -> Option<&ThrottleCounters> {
+        unsafe { (*AliasedBox::as_ptr(&self.0)).throttle.as_ref() }
+    }
+
+    pub(crate) fn access_match_expr(&self) -> MatchExprIdx {
+        unsafe { (*AliasedBox::as_ptr(&self.0)).match_exprs[0] }
+    }
+
+    pub(crate) fn targeted_match_expr(&self) -> MatchExprIdx {
+        unsafe { (*AliasedBox::as_ptr(&self.0)).match_exprs[1] }
+    }
+
+    pub(crate) fn set_match_exprs(&mut self, access: MatchExprIdx, targeted: MatchExprIdx) {
+        unsafe { (*AliasedBox::as_mut_ptr(&mut self.0)).match_exprs = [access, targeted] };
+    }
+
     pub(crate) fn ptr(&self) -> HandlerInfoPtr {
         HandlerInfoPtr(AliasedBox::as_non_null(&self.0))
     }
@@ -431,9 +653,15 @@ impl fmt::Debug for HandlerInfo {
             .field("sent_untargeted_events", &self.sent_untargeted_events())
             .field("sent_targeted_events", &self.sent_targeted_events())
             .field("event_queue_access", &self.event_queue_access())
+            .field("world_access", &self.world_access())
             .field("component_access", &self.component_access())
             .field("referenced_components", &self.referenced_components())
+            .field("change_tick_reads", &self.change_tick_reads())
+            .field("resource_access", &self.resource_access())
             .field("priority", &self.priority())
+            .field("run_before", &self.run_before())
+            .field("run_after", &self.run_after())
+            .field("fully_matched", &self.fully_matched())
             // Don't access the `handler` field.
             .finish_non_exhaustive()
     }
@@ -444,6 +672,9 @@ pub(crate) struct HandlerList {
     before: u32,
     after: u32,
     entries: Vec<HandlerInfoPtr>,
+    /// Index of the first entry of every dispatch group. Only maintained for
+    /// the lists in [`Handlers`] by calling [`HandlerList::regroup_from`].
+    group_starts: Vec<u32>,
 }
 
 unsafe impl Sync for HandlerList {}
@@ -454,6 +685,7 @@ impl HandlerList {
             before: 0,
             after: 0,
             entries: vec![],
+            group_starts: vec![],
         }
     }
 
@@ -476,6 +708,101 @@ impl HandlerList {
         }
     }
 
+    /// Inserts `ptr` and reorders the handlers with the same priority so that
+    /// every run order constraint between handlers in the list is satisfied.
+    /// Handlers which are not constrained keep their order. Returns the index
+    /// of the first entry which changed.
+    ///
+    /// On failure the list is unchanged.
+    pub(crate) fn insert_ordered(&mut self, ptr: HandlerInfoPtr) -> Result<usize, RunOrderError> {
+        let info = unsafe { ptr.as_info() };
+
+        if info.run_before().is_empty() && info.run_after().is_empty() {
+            self.insert(ptr, info.priority());
+            return Ok(self.position(ptr).unwrap());
+        }
+
+        let old = (self.entries.clone(), self.before, self.after);
+
+        self.insert(ptr, info.priority());
+
+        let band = match info.priority() {
+            Priority::High => 0..self.before as usize,
+            Priority::Medium => self.before as usize..self.after as usize,
+            Priority::Low => self.after as usize..self.entries.len(),
+        };
+
+        // Constraints between handlers of different priorities must agree with
+        // the priorities.
+        let conflict = self.entries[..band.start]
+            .iter()
+            .find(|p| info.must_run_before(unsafe { p.as_info() }))
+            .map(|&p| (ptr, p))
+            .or_else(|| {
+                self.entries[band.end..]
+                    .iter()
+                    .find(|p| unsafe { p.as_info() }.must_run_before(info))
+                    .map(|&p| (p, ptr))
+            });
+
+        let res = match conflict {
+            Some((first, then)) => Err(RunOrderError::Priority { first, then }),
+            None => {
+                sort_by_run_order(&mut self.entries[band.clone()]).map_err(RunOrderError::Cycle)
+            }
+        };
+
+        if let Err(e) = res {
+            (self.entries, self.before, self.after) = old;
+            return Err(e);
+        }
+
+        Ok(band.start
+            + self.entries[band.clone()]
+                .iter()
+                .zip(&old.0[band.start..])
+                .take_while(|(a, b)| a == b)
+                .count())
+    }
+
+    /// Inserts `ptr` at the position given by its [rank]. Used for lists which
+    /// mirror the order of the lists in [`Handlers`].
+    ///
+    /// [rank]: HandlerInfo::rank
+    pub(crate) fn insert_ranked(&mut self, ptr: HandlerInfoPtr) {
+        let info = unsafe { ptr.as_info() };
+        let idx = self
+            .entries
+            .partition_point(|p| unsafe { p.as_info() }.rank() < info.rank());
+
+        self.entries.insert(idx, ptr);
+
+        match info.priority() {
+            Priority::High => {
+                self.before += 1;
+                self.after += 1;
+            }
+            Priority::Medium => self.after += 1,
+            Priority::Low => {}
+        }
+    }
+
+    /// Sorts the list by the [rank] of its handlers.
+    ///
+    /// [rank]: HandlerInfo::rank
+    pub(crate) fn sort_by_rank(&mut self) {
+        self.entries.sort_by_key(|p| unsafe { p.as_info() }.rank());
+    }
+
+    /// Sets the [rank] of every handler in the list to its position.
+    ///
+    /// [rank]: HandlerInfo::rank
+    fn update_ranks(&mut self) {
+        for (i, p) in self.entries.iter().enumerate() {
+            unsafe { (*p.0.as_ptr()).rank = i as u32 };
+        }
+    }
+
     pub(crate) fn remove(&mut self, ptr: HandlerInfoPtr) -> bool {
         if let Some(idx) = self.entries.iter().position(|&p| p == ptr) {
             self.entries.remove(idx);
@@ -496,9 +823,203 @@ impl HandlerList {
         }
     }
 
+    /// Replaces `old` with `new` at the same position in the list. Returns
+    /// `false` if `old` is not in the list.
+    pub(crate) fn replace(&mut self, old: HandlerInfoPtr, new: HandlerInfoPtr) -> bool {
+        if let Some(p) = self.entries.iter_mut().find(|p| **p == old) {
+            *p = new;
+            true
+        } else {
+            false
+        }
+    }
+
     pub(crate) fn handlers(&self) -> &[HandlerInfoPtr] {
         &self.entries
     }
+
+    pub(crate) fn position(&self, ptr: HandlerInfoPtr) -> Option<usize> {
+        self.entries.iter().position(|&p| p == ptr)
+    }
+
+    /// Updates the dispatch groups after `ptr` was added to the list.
+    pub(crate) fn regroup_at(&mut self, ptr: HandlerInfoPtr) {
+        if let Some(idx) = self.position(ptr) {
+            self.regroup_from(idx);
+        }
+    }
+
+    /// Updates the dispatch groups after the entry at `idx` changed. Groups
+    /// ending before `idx - 1` are unaffected.
+    ///
+    /// A group never contains two handlers with a run order constraint
+    /// between them, even if their accesses are compatible.
+    pub(crate) fn regroup_from(&mut self, idx: usize) {
+        // The group containing the entry before `idx` may now extend further.
+        let kept = self
+            .group_starts
+            .partition_point(|&start| (start as usize) < idx)
+            .saturating_sub(1);
+
+        let mut start = self.group_starts.get(kept).map_or(0, |&s| s as usize);
+        self.group_starts.truncate(kept);
+
+        while start < self.entries.len() {
+            self.group_starts.push(start as u32);
+
+            let mut end = start + 1;
+
+            while let Some(next) = self.entries.get(end) {
+                let next = unsafe { next.as_info() };
+
+                if !self.entries[start..end].iter().all(|p| {
+                    let info = unsafe { p.as_info() };
+                    info.is_compatible(next) && !info.must_run_before(next)
+                }) {
+                    break;
+                }
+
+                end += 1;
+            }
+
+            start = end;
+        }
+    }
+
+    /// Returns the dispatch group starting at `idx` if it has more than one
+    /// handler and all of its handlers can run concurrently. Throttled
+    /// handlers and handlers with write access to the world as a whole, such
+    /// as those with a [`Flush`] parameter, can't. Handlers with a run order
+    /// constraint between them are never in the same group.
+    ///
+    /// [`Flush`]: crate::flush::Flush
+    #[cfg(feature = "rayon")]
+    pub(crate) fn parallel_group_at(&self, idx: usize) -> Option<&[HandlerInfoPtr]> {
+        let i = self.group_starts.binary_search(&(idx as u32)).ok()?;
+
+        let end = self
+            .group_starts
+            .get(i + 1)
+            .map_or(self.entries.len(), |&e| e as usize);
+
+        let group = &self.entries[idx..end];
+
+        debug_assert!(
+            group.iter().enumerate().all(|(i, a)| group[i + 1..]
+                .iter()
+                .all(|b| !unsafe { a.as_info() }.must_run_before(unsafe { b.as_info() }))),
+            "dispatch group contains handlers with a run order constraint"
+        );
+
+        (group.len() > 1
+            && group.iter().all(|p| {
+                let info = unsafe { p.as_info() };
+                info.throttle_stats().is_none() && info.world_access() != Access::ReadWrite
+            }))
+        .then_some(group)
+    }
+
+    /// Returns the dispatch groups of the list.
+    pub(crate) fn groups(&self) -> impl Iterator<Item = &[HandlerInfoPtr]> {
+        self.group_starts.iter().enumerate().map(|(i, &start)| {
+            let end = self
+                .group_starts
+                .get(i + 1)
+                .map_or(self.entries.len(), |&e| e as usize);
+
+            &self.entries[start as usize..end]
+        })
+    }
+}
+
+/// Sorts `handlers` so that every handler comes after the handlers it must
+/// run after, preferring the current order. Returns a cycle of handlers with
+/// contradictory constraints on failure.
+fn sort_by_run_order(handlers: &mut [HandlerInfoPtr]) -> Result<(), Vec<HandlerInfoPtr>> {
+    let precedes = |a: HandlerInfoPtr, b: HandlerInfoPtr| unsafe {
+        a != b && a.as_info().must_run_before(b.as_info())
+    };
+
+    // Number of unsorted handlers each handler must run after.
+    let mut waiting_on: Vec<usize> = handlers
+        .iter()
+        .map(|&b| handlers.iter().filter(|&&a| precedes(a, b)).count())
+        .collect();
+    let mut sorted = Vec::with_capacity(handlers.len());
+
+    while sorted.len() < handlers.len() {
+        let Some(i) = waiting_on.iter().position(|&n| n == 0) else {
+            // Every remaining handler waits on another, so walking backwards
+            // from any of them must come back around.
+            let mut path = vec![waiting_on.iter().position(|&n| n != usize::MAX).unwrap()];
+
+            loop {
+                let last = handlers[*path.last().unwrap()];
+                let prev = (0..handlers.len())
+                    .find(|&j| waiting_on[j] != usize::MAX && precedes(handlers[j], last))
+                    .unwrap();
+
+                if let Some(start) = path.iter().position(|&j| j == prev) {
+                    return Err(path[start..].iter().rev().map(|&j| handlers[j]).collect());
+                }
+
+                path.push(prev);
+            }
+        };
+
+        waiting_on[i] = usize::MAX;
+        sorted.push(handlers[i]);
+
+        for (j, &b) in handlers.iter().enumerate() {
+            if waiting_on[j] != usize::MAX && precedes(handlers[i], b) {
+                waiting_on[j] -= 1;
+            }
+        }
+    }
+
+    handlers.copy_from_slice(&sorted);
+
+    Ok(())
+}
+
+/// Run order constraints which can't be satisfied, found while adding a
+/// handler.
+#[derive(Debug)]
+pub(crate) enum RunOrderError {
+    /// The handlers must each run before the next, and the last before the
+    /// first.
+    Cycle(Vec<HandlerInfoPtr>),
+    /// `first` must run before `then`, but `then` has a higher priority.
+    Priority {
+        first: HandlerInfoPtr,
+        then: HandlerInfoPtr,
+    },
+}
+
+impl fmt::Display for RunOrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn name(p: &HandlerInfoPtr) -> &str {
+            unsafe { p.as_info() }.name()
+        }
+
+        match self {
+            RunOrderError::Cycle(cycle) => {
+                write!(f, "handler run order constraints form a cycle: ")?;
+
+                for p in cycle {
+                    write!(f, "`{}` -> ", name(p))?;
+                }
+
+                write!(f, "`{}`", name(&cycle[0]))
+            }
+            RunOrderError::Priority { first, then } => write!(
+                f,
+                "handler `{}` must run before `{}`, which has a higher priority",
+                name(first),
+                name(then)
+            ),
+        }
+    }
 }
 
 /// Lightweight identifier for a handler.
@@ -595,6 +1116,72 @@ pub trait IntoHandler<Marker>: Sized {
     fn low(self) -> Low<Self::Handler> {
         Low(self.into_handler())
     }
+
+    /// Returns a wrapper which makes this handler run before the handler `id`
+    /// when both receive the same event. See [`Before`] for more information.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// struct E;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let physics = world.add_handler(|_: Receiver<E>| println!("physics"));
+    /// world.add_handler((|_: Receiver<E>| println!("input")).before(physics));
+    ///
+    /// world.send(E); // Prints "input" then "physics".
+    /// ```
+    fn before(self, id: HandlerId) -> Before<Self::Handler> {
+        Before(self.into_handler(), id)
+    }
+
+    /// Returns a wrapper which makes this handler run after the handler `id`
+    /// when both receive the same event. See [`After`] for more information.
+    fn after(self, id: HandlerId) -> After<Self::Handler> {
+        After(self.into_handler(), id)
+    }
+
+    /// Returns a wrapper which gives the component `id` to a [`Dynamic`]
+    /// query of this handler. See [`WithDynamic`] for more information.
+    ///
+    /// [`Dynamic`]: crate::query::Dynamic
+    fn with_dynamic(self, id: ComponentId) -> WithDynamic<Self::Handler> {
+        WithDynamic(self.into_handler(), id)
+    }
+
+    /// Returns a wrapper which skips deliveries of the received event
+    /// according to `every`. See [`Throttle`] for more information.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::handler::Every;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// struct Tick;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// // Only runs on every 10th `Tick`.
+    /// world.add_handler((|_: Receiver<Tick>| println!("pathfinding")).throttle(Every::Nth(10)));
+    /// ```
+    fn throttle(self, every: Every) -> Throttle<Self::Handler> {
+        Throttle::new(self.into_handler(), every)
+    }
+
+    /// Returns a wrapper which skips deliveries of the received event until at
+    /// least `ticks` calls to [`World::advance_handler_cooldowns`] have
+    /// occurred since the handler last ran.
+    ///
+    /// This is shorthand for `self.throttle(Every::CooldownTicks(ticks))`.
+    fn cooldown_ticks(self, ticks: u64) -> Throttle<Self::Handler> {
+        self.throttle(Every::CooldownTicks(ticks))
+    }
 }
 
 #[doc(hidden)]
@@ -648,6 +1235,10 @@ impl<H: Handler> Handler for NoTypeId<H> {
         self.0.run(info, event_ptr, target_location, world)
     }
 
+    unsafe fn run_deferred(&mut self, info: &HandlerInfo, world: UnsafeWorldCell) -> bool {
+        self.0.run_deferred(info, world)
+    }
+
     fn refresh_archetype(&mut self, arch: &Archetype) {
         self.0.refresh_archetype(arch)
     }
@@ -655,6 +1246,18 @@ impl<H: Handler> Handler for NoTypeId<H> {
     fn remove_archetype(&mut self, arch: &Archetype) {
         self.0.remove_archetype(arch)
     }
+
+    fn teardown(&mut self, world: &mut World) {
+        self.0.teardown(world)
+    }
+
+    fn take_locals(&mut self, locals: &mut Locals) {
+        self.0.take_locals(locals)
+    }
+
+    fn restore_locals(&mut self, locals: &mut Locals) {
+        self.0.restore_locals(locals)
+    }
 }
 
 /// The wrapper handler returned by [`IntoHandler::high`].
@@ -686,6 +1289,10 @@ impl<H: Handler> Handler for High<H> {
         self.0.run(info, event_ptr, target_location, world)
     }
 
+    unsafe fn run_deferred(&mut self, info: &HandlerInfo, world: UnsafeWorldCell) -> bool {
+        self.0.run_deferred(info, world)
+    }
+
     fn refresh_archetype(&mut self, arch: &Archetype) {
         self.0.refresh_archetype(arch)
     }
@@ -693,6 +1300,18 @@ impl<H: Handler> Handler for High<H> {
     fn remove_archetype(&mut self, arch: &Archetype) {
         self.0.remove_archetype(arch)
     }
+
+    fn teardown(&mut self, world: &mut World) {
+        self.0.teardown(world)
+    }
+
+    fn take_locals(&mut self, locals: &mut Locals) {
+        self.0.take_locals(locals)
+    }
+
+    fn restore_locals(&mut self, locals: &mut Locals) {
+        self.0.restore_locals(locals)
+    }
 }
 
 /// The wrapper handler returned by [`IntoHandler::low`].
@@ -724,6 +1343,10 @@ impl<H: Handler> Handler for Low<H> {
         self.0.run(info, event_ptr, target_location, world)
     }
 
+    unsafe fn run_deferred(&mut self, info: &HandlerInfo, world: UnsafeWorldCell) -> bool {
+        self.0.run_deferred(info, world)
+    }
+
     fn refresh_archetype(&mut self, arch: &Archetype) {
         self.0.refresh_archetype(arch)
     }
@@ -731,39 +1354,683 @@ impl<H: Handler> Handler for Low<H> {
     fn remove_archetype(&mut self, arch: &Archetype) {
         self.0.remove_archetype(arch)
     }
+
+    fn teardown(&mut self, world: &mut World) {
+        self.0.teardown(world)
+    }
+
+    fn take_locals(&mut self, locals: &mut Locals) {
+        self.0.take_locals(locals)
+    }
+
+    fn restore_locals(&mut self, locals: &mut Locals) {
+        self.0.restore_locals(locals)
+    }
 }
 
-/// An [`Event`] handler function that can be added to a [`World`].
-///
-/// handlers are added to a world using the [`World::add_handler`] method.
+/// The wrapper handler returned by [`IntoHandler::before`]. The handler runs
+/// before the handler `.1` whenever both receive the same event.
 ///
-/// For more information about handlers, see the [tutorial](crate::tutorial).
-pub trait Handler: Send + Sync + 'static {
-    /// Returns the [`TypeId`] which uniquely identifies this handler, or `None`
-    /// if there is none.
-    ///
-    /// No two handlers with the same [`TypeId`] will exist in the [`World`] at
-    /// the same time.
-    fn type_id(&self) -> Option<TypeId>;
+/// Constraints referring to handlers which don't receive the same event, or
+/// which don't exist, are ignored. Constraints are applied within a
+/// [`Priority`], so a handler can't run before a handler with a higher
+/// priority. Adding a handler whose constraints contradict each other or its
+/// priority panics.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct Before<S>(pub S, pub HandlerId);
 
-    /// Returns the name of this handler for debugging purposes.
-    fn name(&self) -> Cow<'static, str>;
+impl<H: Handler> Handler for Before<H> {
+    fn type_id(&self) -> Option<TypeId> {
+        self.0.type_id()
+    }
 
-    /// Initializes the handler. Returns [`InitError`] on failure.
-    fn init(&mut self, world: &mut World, config: &mut Config) -> Result<(), InitError>;
+    fn name(&self) -> Cow<'static, str> {
+        self.0.name()
+    }
 
-    /// Execute the handler by passing in the handler's metadata, a pointer to
-    /// the received event of the configured type, the entity location of
-    /// the event's target, and an [`UnsafeWorldCell`] with permission to
-    /// access the data described in the configuration.
-    ///
-    /// # Safety
-    ///
-    /// - handler must be initialized via [`init`].
-    /// - `info` must be the correct information for this handler.
-    /// - `event_ptr` must point to the correct type of event configured by this
-    ///   handler in [`init`].
-    /// - `target_location` must be a valid location to an entity matching
+    fn init(&mut self, world: &mut World, config: &mut Config) -> Result<(), InitError> {
+        self.0.init(world, config)?;
+        config.run_before.push(self.1);
+        Ok(())
+    }
+
+    unsafe fn run(
+        &mut self,
+        info: &HandlerInfo,
+        event_ptr: EventPtr,
+        target_location: EntityLocation,
+        world: UnsafeWorldCell,
+    ) {
+        self.0.run(info, event_ptr, target_location, world)
+    }
+
+    unsafe fn run_deferred(&mut self, info: &HandlerInfo, world: UnsafeWorldCell) -> bool {
+        self.0.run_deferred(info, world)
+    }
+
+    fn refresh_archetype(&mut self, arch: &Archetype) {
+        self.0.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(&mut self, arch: &Archetype) {
+        self.0.remove_archetype(arch)
+    }
+
+    fn teardown(&mut self, world: &mut World) {
+        self.0.teardown(world)
+    }
+
+    fn take_locals(&mut self, locals: &mut Locals) {
+        self.0.take_locals(locals)
+    }
+
+    fn restore_locals(&mut self, locals: &mut Locals) {
+        self.0.restore_locals(locals)
+    }
+}
+
+/// The wrapper handler returned by [`IntoHandler::after`]. The handler runs
+/// after the handler `.1` whenever both receive the same event.
+///
+/// Constraints referring to handlers which don't receive the same event, or
+/// which don't exist, are ignored. Constraints are applied within a
+/// [`Priority`], so a handler can't run after a handler with a lower
+/// priority. Adding a handler whose constraints contradict each other or its
+/// priority panics.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct After<S>(pub S, pub HandlerId);
+
+impl<H: Handler> Handler for After<H> {
+    fn type_id(&self) -> Option<TypeId> {
+        self.0.type_id()
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.0.name()
+    }
+
+    fn init(&mut self, world: &mut World, config: &mut Config) -> Result<(), InitError> {
+        self.0.init(world, config)?;
+        config.run_after.push(self.1);
+        Ok(())
+    }
+
+    unsafe fn run(
+        &mut self,
+        info: &HandlerInfo,
+        event_ptr: EventPtr,
+        target_location: EntityLocation,
+        world: UnsafeWorldCell,
+    ) {
+        self.0.run(info, event_ptr, target_location, world)
+    }
+
+    unsafe fn run_deferred(&mut self, info: &HandlerInfo, world: UnsafeWorldCell) -> bool {
+        self.0.run_deferred(info, world)
+    }
+
+    fn refresh_archetype(&mut self, arch: &Archetype) {
+        self.0.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(&mut self, arch: &Archetype) {
+        self.0.remove_archetype(arch)
+    }
+
+    fn teardown(&mut self, world: &mut World) {
+        self.0.teardown(world)
+    }
+
+    fn take_locals(&mut self, locals: &mut Locals) {
+        self.0.take_locals(locals)
+    }
+
+    fn restore_locals(&mut self, locals: &mut Locals) {
+        self.0.restore_locals(locals)
+    }
+}
+
+/// The wrapper handler returned by [`IntoHandler::with_dynamic`]. Gives the
+/// component `.1` to a [`Dynamic`] query of the handler.
+///
+/// Components are given to the `Dynamic` queries in order, so in
+/// `handler.with_dynamic(a).with_dynamic(b)` the first query gets `a` and the
+/// second gets `b`. Adding the handler panics if the component doesn't exist,
+/// or if the number of components doesn't match the number of `Dynamic`
+/// queries.
+///
+/// [`Dynamic`]: crate::query::Dynamic
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct WithDynamic<S>(pub S, pub ComponentId);
+
+impl<H: Handler> Handler for WithDynamic<H> {
+    fn type_id(&self) -> Option<TypeId> {
+        self.0.type_id()
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.0.name()
+    }
+
+    fn init(&mut self, world: &mut World, config: &mut Config) -> Result<(), InitError> {
+        // Outer wrappers are initialized first, so this component goes before
+        // the ones given by them.
+        config.dynamic_components.insert(0, self.1);
+        self.0.init(world, config)?;
+
+        if config.dynamic_components.is_empty() {
+            Ok(())
+        } else {
+            Err(InitError(
+                format!(
+                    "handler `{}` was given more dynamic components than it has `Dynamic` queries",
+                    self.0.name()
+                )
+                .into(),
+            ))
+        }
+    }
+
+    unsafe fn run(
+        &mut self,
+        info: &HandlerInfo,
+        event_ptr: EventPtr,
+        target_location: EntityLocation,
+        world: UnsafeWorldCell,
+    ) {
+        self.0.run(info, event_ptr, target_location, world)
+    }
+
+    unsafe fn run_deferred(&mut self, info: &HandlerInfo, world: UnsafeWorldCell) -> bool {
+        self.0.run_deferred(info, world)
+    }
+
+    fn refresh_archetype(&mut self, arch: &Archetype) {
+        self.0.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(&mut self, arch: &Archetype) {
+        self.0.remove_archetype(arch)
+    }
+
+    fn teardown(&mut self, world: &mut World) {
+        self.0.teardown(world)
+    }
+
+    fn take_locals(&mut self, locals: &mut Locals) {
+        self.0.take_locals(locals)
+    }
+
+    fn restore_locals(&mut self, locals: &mut Locals) {
+        self.0.restore_locals(locals)
+    }
+}
+
+/// Describes how often a [`Throttle`]d handler runs. Used with
+/// [`IntoHandler::throttle`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Every {
+    /// Run on every `n`th delivery of the received event, counted per
+    /// handler. The first `n - 1` deliveries are skipped.
+    Nth(u32),
+    /// Run at most `n` times per cascade, where a cascade is a call to
+    /// [`World::send`] (or a similar method) along with every event sent as a
+    /// result.
+    AtMostPerCascade(u32),
+    /// Run at most once every `n` ticks, where ticks are advanced by
+    /// [`World::advance_handler_cooldowns`].
+    CooldownTicks(u64),
+}
+
+/// Counters describing a throttled handler. Returned by
+/// [`HandlerInfo::throttle_stats`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct ThrottleStats {
+    /// How often the handler runs.
+    pub every: Every,
+    /// Number of times the handler has run.
+    pub runs: u64,
+    /// Number of deliveries that were skipped.
+    pub skipped: u64,
+    /// Whether a coalesced event is waiting to be delivered.
+    pub pending: bool,
+}
+
+#[derive(Debug)]
+pub(crate) struct ThrottleCounters {
+    every: Every,
+    runs: AtomicU64,
+    skipped: AtomicU64,
+    pending: AtomicBool,
+}
+
+impl ThrottleCounters {
+    pub(crate) fn new(every: Every) -> Self {
+        Self {
+            every,
+            runs: AtomicU64::new(0),
+            skipped: AtomicU64::new(0),
+            pending: AtomicBool::new(false),
+        }
+    }
+}
+
+/// The wrapper handler returned by [`IntoHandler::throttle`] and
+/// [`IntoHandler::cooldown_ticks`].
+///
+/// Deliveries of the received event which are not permitted by the [`Every`]
+/// mode are skipped, and the wrapped handler does not observe them. The
+/// handler's counters are available from [`HandlerInfo::throttle_stats`].
+///
+/// # Coalescing
+///
+/// By default, skipped events are discarded. With [`Throttle::coalesce`], the
+/// most recent skipped event is instead buffered and delivered once the
+/// throttle permits it, even if no new event arrives:
+///
+/// - [`Every::AtMostPerCascade`] reserves the last run of each cascade for the
+///   latest skipped event, which is delivered when the cascade ends.
+/// - [`Every::CooldownTicks`] delivers the latest skipped event as soon as the
+///   cooldown elapses in [`World::advance_handler_cooldowns`].
+/// - [`Every::Nth`] always runs with the latest event, so coalescing has no
+///   effect.
+///
+/// A buffered event is discarded if the handler runs with a newer event
+/// first. Buffered targeted events are discarded if the target no longer
+/// matches the handler's query.
+pub struct Throttle<H> {
+    handler: H,
+    every: Every,
+    coalesce: Option<Coalescer>,
+    /// Number of deliveries seen since the last run for [`Every::Nth`], or the
+    /// number of runs in the current cascade for [`Every::AtMostPerCascade`].
+    count: u32,
+    cascade: u64,
+    last_run_tick: Option<u64>,
+    pending: Option<Box<dyn PendingEvent>>,
+}
+
+impl<H> Throttle<H> {
+    /// Creates a new throttled handler. Prefer using [`IntoHandler::throttle`].
+    pub fn new(handler: H, every: Every) -> Self {
+        Self {
+            handler,
+            every,
+            coalesce: None,
+            count: 0,
+            cascade: 0,
+            last_run_tick: None,
+            pending: None,
+        }
+    }
+
+    /// Buffer the most recent skipped event and deliver it once the throttle
+    /// permits. The handler must receive event `E`, which is cloned when it is
+    /// buffered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::handler::Every;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event, Clone)]
+    /// struct Autosave(u32);
+    ///
+    /// #[derive(Component)]
+    /// struct Saved(Vec<u32>);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(
+    ///     (|r: Receiver<Autosave>, Single(saved): Single<&mut Saved>| saved.0.push(r.event.0))
+    ///         .cooldown_ticks(1)
+    ///         .coalesce::<Autosave>(),
+    /// );
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, Saved(vec![]));
+    ///
+    /// world.send(Autosave(1));
+    /// world.send(Autosave(2));
+    /// world.send(Autosave(3));
+    /// assert_eq!(world.get::<Saved>(e).unwrap().0, [1]);
+    ///
+    /// world.advance_handler_cooldowns();
+    /// assert_eq!(world.get::<Saved>(e).unwrap().0, [1, 3]);
+    /// ```
+    pub fn coalesce<E: Event + Clone>(mut self) -> Self {
+        self.coalesce = Some(Coalescer {
+            type_id: TypeId::of::<E>(),
+            name: any::type_name::<E>(),
+            buffer: |ptr| {
+                Box::new(Pending {
+                    event: ManuallyDrop::new(unsafe { ptr.cast::<E>().as_ref() }.clone()),
+                    taken: false,
+                })
+            },
+        });
+        self
+    }
+
+    /// Returns whether the handler may run now, updating the counters for a
+    /// new delivery.
+    fn permit(&mut self, world: UnsafeWorldCell) -> bool {
+        match self.every {
+            Every::Nth(n) => {
+                self.count += 1;
+
+                if self.count >= n {
+                    self.count = 0;
+                    true
+                } else {
+                    false
+                }
+            }
+            Every::AtMostPerCascade(n) => {
+                self.sync_cascade(world);
+
+                // Reserve the last run for the coalesced event.
+                let limit = if self.coalesce.is_some() { n - 1 } else { n };
+
+                if self.count < limit {
+                    self.count += 1;
+                    true
+                } else {
+                    false
+                }
+            }
+            Every::CooldownTicks(ticks) => self.permit_cooldown(ticks, world),
+        }
+    }
+
+    /// Resets the run count if a new cascade has started.
+    fn sync_cascade(&mut self, world: UnsafeWorldCell) {
+        let cascade = world.cascade();
+
+        if cascade != self.cascade {
+            self.cascade = cascade;
+            self.count = 0;
+        }
+    }
+
+    fn permit_cooldown(&mut self, ticks: u64, world: UnsafeWorldCell) -> bool {
+        let now = world.handler_cooldown_tick();
+
+        match self.last_run_tick {
+            Some(last) if now - last < ticks => false,
+            _ => {
+                self.last_run_tick = Some(now);
+                true
+            }
+        }
+    }
+}
+
+impl<H: Handler> Handler for Throttle<H> {
+    fn type_id(&self) -> Option<TypeId> {
+        self.handler.type_id()
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.handler.name()
+    }
+
+    fn init(&mut self, world: &mut World, config: &mut Config) -> Result<(), InitError> {
+        self.handler.init(world, config)?;
+
+        if matches!(self.every, Every::Nth(0) | Every::AtMostPerCascade(0)) {
+            return Err(InitError(
+                format!(
+                    "throttle `{:?}` of handler `{}` must be nonzero",
+                    self.every,
+                    self.name()
+                )
+                .into(),
+            ));
+        }
+
+        if let Some(coalescer) = &self.coalesce {
+            let received_type_id = config
+                .received_event
+                .and_then(|id| world.events().get(id))
+                .and_then(|info| info.type_id());
+
+            if received_type_id != Some(coalescer.type_id) {
+                return Err(InitError(
+                    format!(
+                        "handler `{}` coalesces event `{}`, but does not receive it",
+                        self.name(),
+                        coalescer.name
+                    )
+                    .into(),
+                ));
+            }
+        }
+
+        config.throttle = Some(self.every);
+
+        Ok(())
+    }
+
+    unsafe fn run(
+        &mut self,
+        info: &HandlerInfo,
+        event_ptr: EventPtr,
+        target_location: EntityLocation,
+        world: UnsafeWorldCell,
+    ) {
+        let counters = info.throttle_counters().unwrap_debug_checked();
+
+        if self.permit(world) {
+            // The handler is seeing a newer event than the buffered one.
+            self.pending = None;
+            counters.pending.store(false, AtomicOrdering::Relaxed);
+            counters.runs.fetch_add(1, AtomicOrdering::Relaxed);
+
+            self.handler.run(info, event_ptr, target_location, world);
+
+            return;
+        }
+
+        counters.skipped.fetch_add(1, AtomicOrdering::Relaxed);
+
+        if let (Some(coalescer), false) = (&self.coalesce, matches!(self.every, Every::Nth(_))) {
+            let was_pending = self.pending.is_some();
+
+            self.pending = Some((coalescer.buffer)(event_ptr.as_ptr()));
+            counters.pending.store(true, AtomicOrdering::Relaxed);
+
+            if !was_pending {
+                world.defer_handler(info.id());
+            }
+        }
+    }
+
+    unsafe fn run_deferred(&mut self, info: &HandlerInfo, world: UnsafeWorldCell) -> bool {
+        if self.pending.is_none() {
+            return false;
+        }
+
+        match self.every {
+            Every::Nth(_) => {}
+            Every::AtMostPerCascade(n) => {
+                self.sync_cascade(world);
+
+                // The reserved run was already used in this cascade, so wait for the
+                // end of the next one.
+                if self.count >= n {
+                    return true;
+                }
+
+                self.count += 1;
+            }
+            Every::CooldownTicks(ticks) => {
+                if !self.permit_cooldown(ticks, world) {
+                    return true;
+                }
+            }
+        }
+
+        let counters = info.throttle_counters().unwrap_debug_checked();
+        counters.pending.store(false, AtomicOrdering::Relaxed);
+
+        let mut pending = self.pending.take().unwrap_debug_checked();
+
+        let target_location = match pending.target() {
+            Some(target) => {
+                let Some(loc) = world.entities().get(target) else {
+                    return false;
+                };
+
+                let arch = world.archetypes().get(loc.archetype).unwrap_debug_checked();
+
+                // Make sure the target still matches the handler's query.
+                let matches = match info.received_event().index() {
+                    EventIdx::Targeted(idx) => {
+                        arch.cached_match(info.targeted_match_expr())
+                            && (info.fully_matched()
+                                || arch
+                                    .handler_list_for(idx)
+                                    .is_some_and(|list| list.handlers().contains(&info.ptr())))
+                    }
+                    EventIdx::Untargeted(_) => true,
+                };
+
+                if !matches {
+                    return false;
+                }
+
+                loc
+            }
+            None => EntityLocation::NULL,
+        };
+
+        counters.runs.fetch_add(1, AtomicOrdering::Relaxed);
+
+        let mut ownership = EventOwnership::Borrowed;
+        let event_ptr = EventPtr::new(pending.as_ptr(), NonNull::from(&mut ownership));
+
+        self.handler.run(info, event_ptr, target_location, world);
+
+        // Deferring has no effect on the buffered event.
+        if ownership == EventOwnership::Owned {
+            // The handler moved the event out of the buffer.
+            pending.forget();
+        }
+
+        false
+    }
+
+    fn refresh_archetype(&mut self, arch: &Archetype) {
+        self.handler.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(&mut self, arch: &Archetype) {
+        self.handler.remove_archetype(arch)
+    }
+
+    fn teardown(&mut self, world: &mut World) {
+        self.handler.teardown(world)
+    }
+
+    fn take_locals(&mut self, locals: &mut Locals) {
+        self.handler.take_locals(locals)
+    }
+
+    fn restore_locals(&mut self, locals: &mut Locals) {
+        self.handler.restore_locals(locals)
+    }
+}
+
+impl<H: fmt::Debug> fmt::Debug for Throttle<H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Throttle")
+            .field("handler", &self.handler)
+            .field("every", &self.every)
+            .field("coalesce", &self.coalesce.as_ref().map(|c| c.name))
+            .field("pending", &self.pending.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Buffers clones of the event received by a coalescing [`Throttle`].
+struct Coalescer {
+    type_id: TypeId,
+    name: &'static str,
+    /// Clones the event behind the pointer into a new buffer.
+    buffer: unsafe fn(NonNull<u8>) -> Box<dyn PendingEvent>,
+}
+
+/// An event buffered by a coalescing [`Throttle`].
+trait PendingEvent: Send + Sync {
+    fn as_ptr(&mut self) -> NonNull<u8>;
+
+    /// Returns the target of the event if it is targeted.
+    fn target(&self) -> Option<EntityId>;
+
+    /// Prevents the event from being dropped after ownership of it was taken.
+    fn forget(&mut self);
+}
+
+struct Pending<E> {
+    event: ManuallyDrop<E>,
+    taken: bool,
+}
+
+impl<E: Event> PendingEvent for Pending<E> {
+    fn as_ptr(&mut self) -> NonNull<u8> {
+        NonNull::from(&mut *self.event).cast()
+    }
+
+    fn target(&self) -> Option<EntityId> {
+        E::IS_TARGETED.then(|| self.event.target())
+    }
+
+    fn forget(&mut self) {
+        self.taken = true;
+    }
+}
+
+impl<E> Drop for Pending<E> {
+    fn drop(&mut self) {
+        if !self.taken {
+            unsafe { ManuallyDrop::drop(&mut self.event) };
+        }
+    }
+}
+
+/// An [`Event`] handler function that can be added to a [`World`].
+///
+/// handlers are added to a world using the [`World::add_handler`] method.
+///
+/// For more information about handlers, see the [tutorial](crate::tutorial).
+pub trait Handler: Send + Sync + 'static {
+    /// Returns the [`TypeId`] which uniquely identifies this handler, or `None`
+    /// if there is none.
+    ///
+    /// No two handlers with the same [`TypeId`] will exist in the [`World`] at
+    /// the same time.
+    fn type_id(&self) -> Option<TypeId>;
+
+    /// Returns the name of this handler for debugging purposes.
+    fn name(&self) -> Cow<'static, str>;
+
+    /// Initializes the handler. Returns [`InitError`] on failure.
+    fn init(&mut self, world: &mut World, config: &mut Config) -> Result<(), InitError>;
+
+    /// Execute the handler by passing in the handler's metadata, a pointer to
+    /// the received event of the configured type, the entity location of
+    /// the event's target, and an [`UnsafeWorldCell`] with permission to
+    /// access the data described in the configuration.
+    ///
+    /// # Safety
+    ///
+    /// - handler must be initialized via [`init`].
+    /// - `info` must be the correct information for this handler.
+    /// - `event_ptr` must point to the correct type of event configured by this
+    ///   handler in [`init`].
+    /// - `target_location` must be a valid location to an entity matching
     ///   [`Config::targeted_event_expr`], unless the event is not targeted.
     /// - `world` must have permission to access all data configured by this
     ///   handler in [`init`].
@@ -777,6 +2044,29 @@ pub trait Handler: Send + Sync + 'static {
         world: UnsafeWorldCell,
     );
 
+    /// Runs an event delivery which the handler previously postponed, such as
+    /// the latest event buffered by a coalescing [`Throttle`]. Returns `true`
+    /// if the handler still has a postponed delivery afterwards.
+    ///
+    /// This is only called for handlers which requested it with
+    /// [`UnsafeWorldCell::defer_handler`]. The world calls it at the end of
+    /// the current cascade of events and after every call to
+    /// [`World::advance_handler_cooldowns`] until `false` is returned. The
+    /// default implementation does nothing and returns `false`.
+    ///
+    /// # Safety
+    ///
+    /// - handler must be initialized via [`init`].
+    /// - `info` must be the correct information for this handler.
+    /// - `world` must have permission to access all data configured by this
+    ///   handler in [`init`].
+    ///
+    /// [`init`]: Self::init
+    unsafe fn run_deferred(&mut self, info: &HandlerInfo, world: UnsafeWorldCell) -> bool {
+        let _ = (info, world);
+        false
+    }
+
     /// Notifies the handler that an archetype it might care about had its
     /// internal state updated.
     ///
@@ -800,6 +2090,30 @@ pub trait Handler: Send + Sync + 'static {
     /// available. Attempting to read the component data from a removed
     /// archetype is illegal.
     fn remove_archetype(&mut self, arch: &Archetype);
+
+    /// Called by [`World::remove_handler`] after the handler has been removed
+    /// from the world, so that it can free any world-side state it created in
+    /// [`init`]. The default implementation does nothing.
+    ///
+    /// [`init`]: Self::init
+    fn teardown(&mut self, world: &mut World) {
+        let _ = world;
+    }
+
+    /// Moves the state of every [`Local`] used by the handler into `locals`.
+    /// Called by [`World::replace_handler`] on the handler being replaced.
+    /// The default implementation does nothing.
+    fn take_locals(&mut self, locals: &mut Locals) {
+        let _ = locals;
+    }
+
+    /// Moves states out of `locals` and into the [`Local`]s used by the
+    /// handler. Called by [`World::replace_handler`] on the replacement
+    /// handler with the states taken from the old one. The default
+    /// implementation does nothing.
+    fn restore_locals(&mut self, locals: &mut Locals) {
+        let _ = locals;
+    }
 }
 
 /// An error returned when handler initialization fails. Contains an error
@@ -817,13 +2131,120 @@ impl fmt::Display for InitError {
 
 #[cfg(feature = "std")]
 #[cfg_attr(docsrs, doc(cfg(feature = "std")))]
-impl std::error::Error for InitError {}
+impl core::error::Error for InitError {}
+
+/// An error returned by [`World::replace_handler`]. The handler being replaced
+/// is left untouched.
+#[derive(Clone, Debug)]
+pub enum ReplaceHandlerError {
+    /// The handler to replace does not exist.
+    NoSuchHandler,
+    /// Another handler with the same [`TypeId`] as the replacement already
+    /// exists.
+    DuplicateTypeId(HandlerId),
+    /// The replacement failed to initialize. This includes conflicting access
+    /// within the replacement.
+    Init(InitError),
+    /// The replacement does not receive the same event as the handler it
+    /// replaces.
+    ReceivedEventMismatch {
+        /// The event received by the existing handler.
+        expected: EventId,
+        /// The event received by the replacement, if any.
+        found: Option<EventId>,
+    },
+    /// The replacement does not have the same [`Priority`] as the handler it
+    /// replaces.
+    PriorityMismatch {
+        /// The priority of the existing handler.
+        expected: Priority,
+        /// The priority of the replacement.
+        found: Priority,
+    },
+}
+
+impl fmt::Display for ReplaceHandlerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplaceHandlerError::NoSuchHandler => write!(f, "handler does not exist"),
+            ReplaceHandlerError::DuplicateTypeId(id) => {
+                write!(
+                    f,
+                    "a different handler with the same type already exists ({id:?})"
+                )
+            }
+            ReplaceHandlerError::Init(e) => write!(f, "replacement failed to initialize: {e}"),
+            ReplaceHandlerError::ReceivedEventMismatch { expected, found } => write!(
+                f,
+                "replacement must receive the same event as the handler it replaces (expected \
+                 {expected:?}, found {found:?})"
+            ),
+            ReplaceHandlerError::PriorityMismatch { expected, found } => write!(
+                f,
+                "replacement must have the same priority as the handler it replaces (expected \
+                 {expected:?}, found {found:?})"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl core::error::Error for ReplaceHandlerError {}
+
+/// The states of [`Local`] handler params, carried from a handler to its
+/// replacement by [`World::replace_handler`].
+///
+/// States are matched up by type in the order they were pushed, so the
+/// `n`th `Local<T>` of the replacement receives the state of the `n`th
+/// `Local<T>` of the old handler.
+#[derive(Default)]
+pub struct Locals {
+    states: Vec<(TypeId, Box<dyn any::Any + Send>)>,
+}
+
+impl Locals {
+    /// Adds a state to the end of the list.
+    pub fn push<T: Send + 'static>(&mut self, state: T) {
+        self.states.push((TypeId::of::<T>(), Box::new(state)));
+    }
+
+    /// Removes and returns the first state of type `T`, or `None` if there
+    /// is none.
+    pub fn take<T: 'static>(&mut self) -> Option<T> {
+        let idx = self
+            .states
+            .iter()
+            .position(|(id, _)| *id == TypeId::of::<T>())?;
+
+        self.states.remove(idx).1.downcast().ok().map(|b| *b)
+    }
+
+    /// Returns the number of states which have not been taken.
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Returns `true` if every state has been taken.
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+}
+
+impl fmt::Debug for Locals {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Locals")
+            .field("len", &self.states.len())
+            .finish()
+    }
+}
 
 /// The priority of a handler relative to other handlers that handle the same
 /// event.
 ///
-/// If multiple handlers have the same priority, then the order they were added
-/// to the [`World`] is used as a fallback.
+/// If multiple handlers have the same priority, then they are ordered by the
+/// constraints set with [`IntoHandler::before`] and [`IntoHandler::after`],
+/// and the order they were added to the [`World`] is used as a fallback.
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
 pub enum Priority {
     /// The handler runs before other handlers.
@@ -841,6 +2262,10 @@ pub enum Priority {
 pub struct Config {
     /// The priority of this handler.
     pub priority: Priority,
+    /// Handlers this handler must run before. Set by [`Before`].
+    pub run_before: Vec<HandlerId>,
+    /// Handlers this handler must run after. Set by [`After`].
+    pub run_after: Vec<HandlerId>,
     /// The event type to be received by the handler.
     ///
     /// Defaults to `None`, but must be assigned to `Some` before configuration
@@ -867,6 +2292,43 @@ pub struct Config {
     /// of `C`'s component index, so the whole handler must be removed when
     /// component `C` is removed.
     pub referenced_components: BitSet<ComponentIdx>,
+    /// The set of components whose change ticks are read by [`Added`] and
+    /// [`Changed`] filters. The filters don't access the components
+    /// themselves, but the handler can't run in parallel with handlers
+    /// writing them.
+    ///
+    /// [`Added`]: crate::query::Added
+    /// [`Changed`]: crate::query::Changed
+    pub change_tick_reads: BitSet<ComponentIdx>,
+    /// Access to the resources of the world. Set by [`Res`] and [`ResMut`].
+    ///
+    /// Resources aren't stored in archetypes, so unlike
+    /// [`Self::component_access`], accesses to the same resource always
+    /// conflict regardless of the entities matched.
+    ///
+    /// [`Res`]: crate::resource::Res
+    /// [`ResMut`]: crate::resource::ResMut
+    pub resource_access: AccessMap<ResourceIdx>,
+    /// How deliveries to the handler are throttled, if at all. Set by
+    /// [`Throttle`].
+    pub throttle: Option<Every>,
+    /// Access to the entities, archetypes, and components of the world as a
+    /// whole. Parameters which hold references into world data read it, and
+    /// [`Flush`] writes it since it can move that data while the handler is
+    /// running.
+    ///
+    /// [`Flush`]: crate::flush::Flush
+    pub world_access: Access,
+    /// Whether the handler takes ownership of the received event, making it
+    /// the only handler allowed to receive the event. Set by
+    /// [`TakeReceiver`](crate::event::TakeReceiver).
+    pub takes_event: bool,
+    /// Components of the [`Dynamic`] queries of the handler which have not
+    /// been initialized yet. Set by [`WithDynamic`], and each `Dynamic` query
+    /// takes the first one.
+    ///
+    /// [`Dynamic`]: crate::query::Dynamic
+    pub dynamic_components: Vec<ComponentId>,
 }
 
 impl Config {
@@ -874,6 +2336,8 @@ impl Config {
     pub fn new() -> Self {
         Self {
             priority: Default::default(),
+            run_before: vec![],
+            run_after: vec![],
             received_event: Default::default(),
             received_event_access: Default::default(),
             targeted_event_expr: BoolExpr::new(false),
@@ -882,6 +2346,32 @@ impl Config {
             event_queue_access: Default::default(),
             component_access: ComponentAccessExpr::new(false),
             referenced_components: Default::default(),
+            change_tick_reads: Default::default(),
+            resource_access: AccessMap::new(),
+            throttle: None,
+            world_access: Access::None,
+            takes_event: false,
+            dynamic_components: vec![],
+        }
+    }
+
+    /// Marks the handler parameter `P` as reading world data. Fails if a
+    /// [`Flush`] came before it.
+    ///
+    /// [`Flush`]: crate::flush::Flush
+    pub(crate) fn read_world<P: ?Sized>(&mut self) -> Result<(), InitError> {
+        if self.world_access.set_if_compatible(Access::Read) {
+            Ok(())
+        } else {
+            Err(InitError(
+                format!(
+                    "`{}` holds references into the world, which conflicts with a previous \
+                     `Flush` or `WorldMut` parameter. Move it into the `Flush` or use the \
+                     `WorldMut` instead",
+                    any::type_name::<P>()
+                )
+                .into(),
+            ))
         }
     }
 }
@@ -924,10 +2414,96 @@ impl Default for Config {
 /// });
 /// ```
 ///
+/// # Implementing
+///
+/// Handler params which can't be derived implement the trait by hand. A param
+/// goes through three phases:
+///
+/// 1. [`init`] is called once when the handler is added with
+///    [`World::add_handler`]. It has full access to the world, so it can add
+///    components and events or spawn entities. Every access the param makes
+///    later must be declared in the [`Config`] here.
+/// 2. [`get`] is called every time the handler runs. It only has access to the
+///    data declared in [`init`].
+/// 3. [`teardown`] is called once when the handler is removed with
+///    [`World::remove_handler`], so the param can free anything it created in
+///    [`init`].
+///
+/// The following param adds its event to the world when the handler is added
+/// and counts the number of times the handler has run:
+///
+/// ```
+/// use std::marker::PhantomData;
+///
+/// use evenio::archetype::Archetype;
+/// use evenio::entity::EntityLocation;
+/// use evenio::event::{EventId, EventPtr};
+/// use evenio::handler::{Config, HandlerInfo, HandlerParam, InitError};
+/// use evenio::prelude::*;
+/// use evenio::world::UnsafeWorldCell;
+///
+/// struct EventCounter<'a, E> {
+///     count: &'a mut u64,
+///     _marker: PhantomData<E>,
+/// }
+///
+/// unsafe impl<E: Event> HandlerParam for EventCounter<'_, E> {
+///     type State = (EventId, u64);
+///
+///     type Item<'a> = EventCounter<'a, E>;
+///
+///     fn init(world: &mut World, _config: &mut Config) -> Result<Self::State, InitError> {
+///         // The param only reads its own state, so no access is declared.
+///         Ok((world.add_event::<E>(), 0))
+///     }
+///
+///     unsafe fn get<'a>(
+///         (_, count): &'a mut Self::State,
+///         _info: &'a HandlerInfo,
+///         _event_ptr: EventPtr<'a>,
+///         _target_location: EntityLocation,
+///         _world: UnsafeWorldCell<'a>,
+///     ) -> Self::Item<'a> {
+///         *count += 1;
+///
+///         EventCounter {
+///             count,
+///             _marker: PhantomData,
+///         }
+///     }
+///
+///     fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+///
+///     fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+///
+///     fn teardown((event, count): &mut Self::State, world: &mut World) {
+///         let name = world.events().get(*event).unwrap().name();
+///         println!("received `{name}` {count} times");
+///     }
+/// }
+///
+/// #[derive(Event)]
+/// struct Ping;
+///
+/// let mut world = World::new();
+///
+/// let handler = world.add_handler(|_: Receiver<Ping>, counter: EventCounter<Ping>| {
+///     println!("ping #{}", counter.count);
+/// });
+///
+/// world.send(Ping);
+/// world.send(Ping);
+/// world.remove_handler(handler);
+/// ```
+///
 /// # Safety
 ///
 /// Implementors must ensure that [`HandlerParam::init`] correctly registers the
 /// data accessed by [`HandlerParam::get`].
+///
+/// [`init`]: HandlerParam::init
+/// [`get`]: HandlerParam::get
+/// [`teardown`]: HandlerParam::teardown
 pub unsafe trait HandlerParam {
     /// Persistent data stored in the handler.
     type State: Send + Sync + 'static;
@@ -967,6 +2543,28 @@ pub unsafe trait HandlerParam {
     /// Remove the given archetype for this handler param. Called whenever
     /// [`Handler::remove_archetype`] is called.
     fn remove_archetype(state: &mut Self::State, arch: &Archetype);
+
+    /// Frees any world-side state created by [`HandlerParam::init`]. Called
+    /// whenever [`Handler::teardown`] is called, which happens after the
+    /// handler is removed from the world. The default implementation does
+    /// nothing.
+    fn teardown(state: &mut Self::State, world: &mut World) {
+        let _ = (state, world);
+    }
+
+    /// Moves the state of every [`Local`] in this handler param into
+    /// `locals`. Called whenever [`Handler::take_locals`] is called. The
+    /// default implementation does nothing.
+    fn take_locals(state: &mut Self::State, locals: &mut Locals) {
+        let _ = (state, locals);
+    }
+
+    /// Moves states out of `locals` and into the [`Local`]s of this handler
+    /// param. Called whenever [`Handler::restore_locals`] is called. The
+    /// default implementation does nothing.
+    fn restore_locals(state: &mut Self::State, locals: &mut Locals) {
+        let _ = (state, locals);
+    }
 }
 
 unsafe impl<T> HandlerParam for PhantomData<T> {
@@ -1044,6 +2642,24 @@ macro_rules! impl_handler_param_tuple {
                     $P::remove_archetype($s, arch);
                 )*
             }
+
+            fn teardown(($($s,)*): &mut Self::State, world: &mut World) {
+                $(
+                    $P::teardown($s, world);
+                )*
+            }
+
+            fn take_locals(($($s,)*): &mut Self::State, locals: &mut Locals) {
+                $(
+                    $P::take_locals($s, locals);
+                )*
+            }
+
+            fn restore_locals(($($s,)*): &mut Self::State, locals: &mut Locals) {
+                $(
+                    $P::restore_locals($s, locals);
+                )*
+            }
         }
     }
 }
@@ -1137,6 +2753,24 @@ where
 
         F::Param::remove_archetype(state, arch)
     }
+
+    fn teardown(&mut self, world: &mut World) {
+        if let Some(state) = &mut self.state {
+            F::Param::teardown(state, world)
+        }
+    }
+
+    fn take_locals(&mut self, locals: &mut Locals) {
+        if let Some(state) = &mut self.state {
+            F::Param::take_locals(state, locals)
+        }
+    }
+
+    fn restore_locals(&mut self, locals: &mut Locals) {
+        if let Some(state) = &mut self.state {
+            F::Param::restore_locals(state, locals)
+        }
+    }
 }
 
 /// Trait for functions whose parameters are [`HandlerParam`]s.
@@ -1240,6 +2874,16 @@ unsafe impl<T: Default + Send + 'static> HandlerParam for Local<'_, T> {
     fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
 
     fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn take_locals(state: &mut Self::State, locals: &mut Locals) {
+        locals.push(mem::take(state.get_mut()));
+    }
+
+    fn restore_locals(state: &mut Self::State, locals: &mut Locals) {
+        if let Some(value) = locals.take::<T>() {
+            *state.get_mut() = value;
+        }
+    }
 }
 
 impl<T> Deref for Local<'_, T> {
@@ -1315,6 +2959,18 @@ unsafe impl<P: HandlerParam> HandlerParam for std::sync::Mutex<P> {
     fn remove_archetype(state: &mut Self::State, arch: &Archetype) {
         P::remove_archetype(state, arch)
     }
+
+    fn teardown(state: &mut Self::State, world: &mut World) {
+        P::teardown(state, world)
+    }
+
+    fn take_locals(state: &mut Self::State, locals: &mut Locals) {
+        P::take_locals(state, locals)
+    }
+
+    fn restore_locals(state: &mut Self::State, locals: &mut Locals) {
+        P::restore_locals(state, locals)
+    }
 }
 
 #[cfg(feature = "std")]
@@ -1345,6 +3001,18 @@ unsafe impl<P: HandlerParam> HandlerParam for std::sync::RwLock<P> {
     fn remove_archetype(state: &mut Self::State, arch: &Archetype) {
         P::remove_archetype(state, arch)
     }
+
+    fn teardown(state: &mut Self::State, world: &mut World) {
+        P::teardown(state, world)
+    }
+
+    fn take_locals(state: &mut Self::State, locals: &mut Locals) {
+        P::take_locals(state, locals)
+    }
+
+    fn restore_locals(state: &mut Self::State, locals: &mut Locals) {
+        P::restore_locals(state, locals)
+    }
 }
 
 /// An event sent immediately after a new handler is added to the world.
@@ -1432,6 +3100,123 @@ mod tests {
         world.send(E(e));
     }
 
+    #[test]
+    fn run_order_constraints() {
+        let mut world = World::new();
+
+        #[derive(Event)]
+        struct E(#[event(target)] EntityId);
+
+        #[derive(Event)]
+        struct Other;
+
+        #[derive(Component)]
+        struct Tracker(String);
+
+        #[derive(Component)]
+        struct Marker;
+
+        let a = world.add_handler(|r: Receiver<E, &mut Tracker>| r.query.0.push('a'));
+        let b = world.add_handler(|r: Receiver<E, &mut Tracker>| r.query.0.push('b'));
+        world.add_handler(
+            (|r: Receiver<E, &mut Tracker>| r.query.0.push('c'))
+                .after(b)
+                .before(a),
+        );
+        // Doesn't receive `E`, so it's ignored.
+        let other = world.add_handler(|_: Receiver<Other>| {});
+        let d =
+            world.add_handler((|r: Receiver<E, &mut Tracker>| r.query.0.push('d')).after(other));
+
+        let e = world.spawn();
+        world.insert(e, Tracker(String::new()));
+
+        world.send(E(e));
+        assert_eq!(world.get::<Tracker>(e).unwrap().0, "bcad");
+
+        // Only matches entities with `Marker`, but still orders `d` before
+        // `a` for entities without it.
+        world.add_handler(
+            (|r: Receiver<E, (&mut Tracker, With<&Marker>)>| r.query.0 .0.push('m'))
+                .after(d)
+                .before(a),
+        );
+
+        world.get_mut::<Tracker>(e).unwrap().0.clear();
+        world.send(E(e));
+        assert_eq!(world.get::<Tracker>(e).unwrap().0, "bcda");
+
+        world.insert(e, Marker);
+        world.get_mut::<Tracker>(e).unwrap().0.clear();
+        world.send(E(e));
+        assert_eq!(world.get::<Tracker>(e).unwrap().0, "bcdma");
+    }
+
+    #[test]
+    #[should_panic(expected = "form a cycle")]
+    fn run_order_cycle() {
+        let mut world = World::new();
+
+        let a = world.add_handler(|_: Receiver<Num>| {});
+        let b = world.add_handler((|_: Receiver<Num>| {}).after(a));
+        world.add_handler((|_: Receiver<Num>| {}).after(b).before(a));
+    }
+
+    #[test]
+    #[should_panic(expected = "which has a higher priority")]
+    fn run_order_priority_conflict() {
+        let mut world = World::new();
+
+        let a = world.add_handler((|_: Receiver<Num>| {}).high());
+        world.add_handler((|_: Receiver<Num>| {}).before(a));
+    }
+
+    #[test]
+    fn priority_order_after_removal() {
+        #[derive(Event)]
+        struct E;
+
+        #[derive(Component)]
+        struct Tracker(String);
+
+        let mut world = World::new();
+
+        let e = world.spawn();
+        world.insert(e, Tracker(String::new()));
+
+        // Every expansion is a distinct handler.
+        macro_rules! push {
+            ($c:literal) => {
+                (|_: Receiver<E>, Single(t): Single<&mut Tracker>| t.0.push($c))
+            };
+        }
+
+        let run = |world: &mut World| {
+            world.get_mut::<Tracker>(e).unwrap().0.clear();
+            world.send(E);
+            world.get::<Tracker>(e).unwrap().0.clone()
+        };
+
+        let m1 = world.add_handler(push!('1'));
+        world.add_handler(push!('a').low());
+        world.add_handler(push!('A').high());
+        world.add_handler(push!('2'));
+        let high = world.add_handler(push!('B').high());
+        world.add_handler(push!('b').low());
+
+        // Handlers with equal priorities run in the order they were added.
+        assert_eq!(run(&mut world), "AB12ab");
+
+        world.remove_handler(high);
+        world.remove_handler(m1);
+        assert_eq!(run(&mut world), "A2ab");
+
+        world.add_handler(push!('c').low());
+        world.add_handler(push!('3'));
+        world.add_handler(push!('C').high());
+        assert_eq!(run(&mut world), "AC23abc");
+    }
+
     #[test]
     fn handler_info_aliasing() {
         let mut world = World::new();
@@ -1448,4 +3233,293 @@ mod tests {
 
         world.send(E);
     }
+
+    #[derive(Event, Clone)]
+    struct Num(u32);
+
+    #[derive(Component)]
+    struct Log(Vec<u32>);
+
+    fn log_handler(r: Receiver<Num>, Single(log): Single<&mut Log>) {
+        log.0.push(r.event.0);
+    }
+
+    fn log_world() -> (World, EntityId) {
+        let mut world = World::new();
+        let e = world.spawn();
+        world.insert(e, Log(vec![]));
+        (world, e)
+    }
+
+    #[test]
+    fn throttle_nth() {
+        let (mut world, e) = log_world();
+
+        let id = world.add_handler(log_handler.throttle(Every::Nth(3)));
+
+        for i in 1..=7 {
+            world.send(Num(i));
+        }
+
+        assert_eq!(world.get::<Log>(e).unwrap().0, [3, 6]);
+
+        let stats = world.handlers()[id].throttle_stats().unwrap();
+        assert_eq!(
+            stats,
+            ThrottleStats {
+                every: Every::Nth(3),
+                runs: 2,
+                skipped: 5,
+                pending: false
+            }
+        );
+    }
+
+    #[test]
+    fn throttle_per_cascade() {
+        let (mut world, e) = log_world();
+
+        world.add_handler(log_handler.throttle(Every::AtMostPerCascade(2)));
+
+        world.send_many(|mut s| {
+            for i in 1..=4 {
+                s.send(Num(i));
+            }
+        });
+        world.send(Num(5));
+
+        assert_eq!(world.get::<Log>(e).unwrap().0, [1, 2, 5]);
+    }
+
+    #[test]
+    fn throttle_cooldown() {
+        let (mut world, e) = log_world();
+
+        world.add_handler(log_handler.cooldown_ticks(2));
+
+        world.send(Num(1));
+        world.send(Num(2));
+        world.advance_handler_cooldowns();
+        world.send(Num(3));
+        world.advance_handler_cooldowns();
+        world.send(Num(4));
+
+        assert_eq!(world.get::<Log>(e).unwrap().0, [1, 4]);
+    }
+
+    #[test]
+    fn coalesce_delivers_latest() {
+        let (mut world, e) = log_world();
+
+        world.add_handler(
+            log_handler
+                .throttle(Every::AtMostPerCascade(2))
+                .coalesce::<Num>(),
+        );
+
+        world.send_many(|mut s| {
+            for i in 1..=4 {
+                s.send(Num(i));
+            }
+        });
+
+        assert_eq!(world.get::<Log>(e).unwrap().0, [1, 4]);
+
+        let (mut world, e) = log_world();
+
+        let id = world.add_handler(log_handler.cooldown_ticks(2).coalesce::<Num>());
+
+        world.send(Num(1));
+        world.send(Num(2));
+        world.send(Num(3));
+
+        assert!(world.handlers()[id].throttle_stats().unwrap().pending);
+
+        world.advance_handler_cooldowns();
+        assert_eq!(world.get::<Log>(e).unwrap().0, [1]);
+
+        world.advance_handler_cooldowns();
+        assert_eq!(world.get::<Log>(e).unwrap().0, [1, 3]);
+
+        world.send(Num(4));
+        world.advance_handler_cooldowns();
+        world.advance_handler_cooldowns();
+        world.send(Num(5));
+        world.advance_handler_cooldowns();
+        world.advance_handler_cooldowns();
+        assert_eq!(world.get::<Log>(e).unwrap().0, [1, 3, 4, 5]);
+
+        let stats = world.handlers()[id].throttle_stats().unwrap();
+        assert_eq!((stats.runs, stats.skipped, stats.pending), (4, 4, false));
+    }
+
+    #[test]
+    fn coalesce_targeted_despawned() {
+        #[derive(Event, Clone)]
+        struct Hit(#[event(target)] EntityId);
+
+        #[derive(Component)]
+        struct Health(u32);
+
+        let mut world = World::new();
+
+        world.add_handler(
+            (|r: Receiver<Hit, &mut Health>| r.query.0 -= 1)
+                .cooldown_ticks(1)
+                .coalesce::<Hit>(),
+        );
+
+        let a = world.spawn();
+        world.insert(a, Health(10));
+        let b = world.spawn();
+        world.insert(b, Health(10));
+
+        world.send(Hit(a));
+        world.send(Hit(a));
+        world.send(Hit(b));
+        world.despawn(b);
+        world.advance_handler_cooldowns();
+
+        assert_eq!(world.get::<Health>(a).unwrap().0, 9);
+    }
+
+    #[test]
+    #[should_panic(expected = "must be nonzero")]
+    fn throttle_zero() {
+        World::new().add_handler(log_handler.throttle(Every::Nth(0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "does not receive it")]
+    fn coalesce_wrong_event() {
+        World::new().add_handler(log_handler.cooldown_ticks(1).coalesce::<Despawn>());
+    }
+
+    #[test]
+    fn param_teardown() {
+        /// Spawns an entity when the handler is added and despawns it when the
+        /// handler is removed.
+        struct Owned;
+
+        unsafe impl HandlerParam for Owned {
+            type State = EntityId;
+
+            type Item<'a> = Owned;
+
+            fn init(world: &mut World, _config: &mut Config) -> Result<Self::State, InitError> {
+                Ok(world.spawn())
+            }
+
+            unsafe fn get<'a>(
+                _state: &'a mut Self::State,
+                _info: &'a HandlerInfo,
+                _event_ptr: EventPtr<'a>,
+                _target_location: EntityLocation,
+                _world: UnsafeWorldCell<'a>,
+            ) -> Self::Item<'a> {
+                Owned
+            }
+
+            fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+            fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+            fn teardown(state: &mut Self::State, world: &mut World) {
+                world.despawn(*state);
+            }
+        }
+
+        #[derive(HandlerParam)]
+        struct Derived {
+            _owned: Owned,
+        }
+
+        #[derive(Event)]
+        struct E;
+
+        let mut world = World::new();
+
+        let h1 = world.add_handler(|_: Receiver<E>, _: Owned, _: Derived| {});
+        let h2 = world.add_handler((|_: Receiver<E>, _: Owned| {}).high());
+
+        assert_eq!(world.entities().len(), 3);
+
+        world.remove_handler(h1);
+        assert_eq!(world.entities().len(), 1);
+
+        world.remove_handler(h2);
+        assert_eq!(world.entities().len(), 0);
+    }
+
+    #[test]
+    fn dispatch_groups_random_edits() {
+        #[derive(Event)]
+        struct E;
+
+        #[derive(Event)]
+        struct F;
+
+        #[derive(Component)]
+        struct A;
+
+        #[derive(Component)]
+        struct B;
+
+        #[derive(Component)]
+        struct C;
+
+        let kinds: [fn(&mut World) -> HandlerId; 8] = [
+            |w| w.add_handler(|_: Receiver<E>, _: Fetcher<&mut A>| {}),
+            |w| w.add_handler(|_: Receiver<E>, _: Fetcher<&A>| {}),
+            |w| w.add_handler(|_: Receiver<E>, _: Fetcher<&mut B>| {}),
+            |w| w.add_handler((|_: Receiver<E>, _: Fetcher<&B>| {}).high()),
+            |w| w.add_handler(|_: Receiver<E>, _: Sender<F>| {}),
+            |w| w.add_handler(|_: ReceiverMut<E>| {}),
+            |w| w.add_handler((|_: Receiver<E>, _: Fetcher<(&A, &mut C)>| {}).low()),
+            |w| w.add_handler(|_: Receiver<E>, _: Fetcher<&C>| {}),
+        ];
+
+        let mut world = World::new();
+        let e = world.add_event::<E>();
+
+        let mut live: [Option<HandlerId>; 8] = [None; 8];
+        let mut rng = 0x2545_f491_u32;
+
+        for _ in 0..500 {
+            rng ^= rng << 13;
+            rng ^= rng >> 17;
+            rng ^= rng << 5;
+
+            let slot = &mut live[rng as usize % kinds.len()];
+
+            match slot.take() {
+                Some(id) => {
+                    world.remove_handler(id);
+                }
+                None => *slot = Some(kinds[rng as usize % kinds.len()](&mut world)),
+            }
+
+            let groups = world.dispatch_groups(e);
+            let info = |id| world.handlers().get(id).unwrap();
+
+            for group in &groups {
+                for (i, &a) in group.iter().enumerate() {
+                    for &b in &group[..i] {
+                        assert!(info(a).is_compatible(info(b)));
+                    }
+                }
+            }
+
+            for pair in groups.windows(2) {
+                let first = info(pair[1][0]);
+                assert!(pair[0].iter().any(|&h| !info(h).is_compatible(first)));
+            }
+
+            let mut grouped: Vec<_> = groups.into_iter().flatten().collect();
+            let mut expected: Vec<_> = live.iter().flatten().copied().collect();
+            grouped.sort();
+            expected.sort();
+            assert_eq!(grouped, expected);
+        }
+    }
 }
@@ -0,0 +1,273 @@
+//! Recording the structural transitions of entities for debugging.
+//!
+//! See [`World::entity_history`] for more information.
+//!
+//! [`World::entity_history`]: crate::world::World::entity_history
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use crate::component::ComponentId;
+use crate::entity::EntityId;
+use crate::handler::HandlerId;
+
+/// The maximum number of transitions kept for each entity. Older transitions
+/// are discarded.
+pub const ENTITY_HISTORY_LEN: usize = 8;
+
+/// The default number of despawned entities whose history is kept. See
+/// [`World::set_entity_history_retention`].
+///
+/// [`World::set_entity_history_retention`]: crate::world::World::set_entity_history_retention
+pub const DEFAULT_HISTORY_RETENTION: usize = 16;
+
+/// A structural change made to an entity.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TransitionRecord {
+    /// The [`EventSequence`] of the event which made the change.
+    ///
+    /// [`EventSequence`]: crate::event::EventSequence
+    pub sequence: u64,
+    /// What kind of change was made.
+    pub kind: TransitionKind,
+    /// The component which was inserted or removed, if any.
+    pub component: Option<ComponentId>,
+    /// Where the event which made the change came from.
+    pub cause: TransitionCause,
+}
+
+/// The kind of a [`TransitionRecord`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum TransitionKind {
+    /// The entity was spawned.
+    Spawn,
+    /// A component was inserted, either adding it or replacing the old value.
+    Insert,
+    /// A component was removed.
+    Remove,
+    /// The entity was despawned.
+    Despawn,
+}
+
+/// The sender of the event which caused a [`TransitionRecord`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum TransitionCause {
+    /// The event was sent by a handler.
+    Handler(HandlerId),
+    /// The event was sent from outside of a handler, such as with
+    /// [`World::send`].
+    ///
+    /// [`World::send`]: crate::world::World::send
+    External,
+}
+
+/// Fixed-capacity list of the most recent transitions, oldest first.
+#[derive(Clone, Copy, Debug)]
+struct History {
+    records: [TransitionRecord; ENTITY_HISTORY_LEN],
+    len: usize,
+}
+
+impl History {
+    const EMPTY: Self = Self {
+        records: [TransitionRecord {
+            sequence: 0,
+            kind: TransitionKind::Spawn,
+            component: None,
+            cause: TransitionCause::External,
+        }; ENTITY_HISTORY_LEN],
+        len: 0,
+    };
+
+    fn push(&mut self, record: TransitionRecord) {
+        if self.len == ENTITY_HISTORY_LEN {
+            self.records.copy_within(1.., 0);
+            self.records[ENTITY_HISTORY_LEN - 1] = record;
+        } else {
+            self.records[self.len] = record;
+            self.len += 1;
+        }
+    }
+
+    fn as_slice(&self) -> &[TransitionRecord] {
+        &self.records[..self.len]
+    }
+}
+
+/// Transition histories for every entity in a world.
+#[derive(Debug)]
+pub(crate) struct EntityHistories {
+    /// Histories of live entities, indexed by [`EntityIdx`].
+    ///
+    /// [`EntityIdx`]: crate::entity::EntityIdx
+    live: Vec<(EntityId, History)>,
+    /// Histories of recently despawned entities, oldest first.
+    dead: VecDeque<(EntityId, History)>,
+    retention: usize,
+}
+
+impl EntityHistories {
+    pub(crate) fn new() -> Self {
+        Self {
+            live: Vec::new(),
+            dead: VecDeque::new(),
+            retention: DEFAULT_HISTORY_RETENTION,
+        }
+    }
+
+    pub(crate) fn record(&mut self, entity: EntityId, record: TransitionRecord) {
+        let idx = entity.index().0 as usize;
+
+        if idx >= self.live.len() {
+            self.live.resize(idx + 1, (EntityId::NULL, History::EMPTY));
+        }
+
+        let (id, history) = &mut self.live[idx];
+
+        if *id != entity {
+            // The slot belonged to an entity which was since despawned.
+            *id = entity;
+            *history = History::EMPTY;
+        }
+
+        history.push(record);
+
+        if record.kind == TransitionKind::Despawn {
+            let dead = (*id, *history);
+            *id = EntityId::NULL;
+
+            if self.retention > 0 {
+                if self.dead.len() == self.retention {
+                    self.dead.pop_front();
+                }
+                self.dead.push_back(dead);
+            }
+        }
+    }
+
+    pub(crate) fn get(&self, entity: EntityId) -> &[TransitionRecord] {
+        if let Some((id, history)) = self.live.get(entity.index().0 as usize) {
+            if *id == entity {
+                return history.as_slice();
+            }
+        }
+
+        self.dead
+            .iter()
+            .rev()
+            .find(|(id, _)| *id == entity)
+            .map_or(&[], |(_, history)| history.as_slice())
+    }
+
+    pub(crate) fn set_retention(&mut self, retention: usize) {
+        self.retention = retention;
+
+        while self.dead.len() > retention {
+            self.dead.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Component)]
+    struct A;
+
+    #[derive(Component)]
+    struct B;
+
+    #[derive(Event)]
+    struct Setup(EntityId);
+
+    #[test]
+    fn records_transitions_with_cause() {
+        let mut world = World::new();
+
+        let handler = world.add_handler(
+            |r: Receiver<Setup>, mut s: Sender<(Insert<A>, Insert<B>, Remove<A>)>| {
+                s.insert(r.event.0, A);
+                s.insert(r.event.0, B);
+                s.remove::<A>(r.event.0);
+            },
+        );
+
+        let e = world.spawn();
+        world.send(Setup(e));
+        world.despawn(e);
+
+        let a = world.add_component::<A>();
+        let b = world.add_component::<B>();
+
+        let history = world.entity_history(e);
+
+        let summary: Vec<_> = history
+            .iter()
+            .map(|rec| (rec.kind, rec.component, rec.cause))
+            .collect();
+
+        assert_eq!(
+            summary,
+            [
+                (TransitionKind::Spawn, None, TransitionCause::External),
+                (
+                    TransitionKind::Insert,
+                    Some(a),
+                    TransitionCause::Handler(handler)
+                ),
+                (
+                    TransitionKind::Insert,
+                    Some(b),
+                    TransitionCause::Handler(handler)
+                ),
+                (
+                    TransitionKind::Remove,
+                    Some(a),
+                    TransitionCause::Handler(handler)
+                ),
+                (TransitionKind::Despawn, None, TransitionCause::External),
+            ]
+        );
+
+        assert!(history.windows(2).all(|w| w[0].sequence < w[1].sequence));
+    }
+
+    #[test]
+    fn keeps_most_recent() {
+        let mut world = World::new();
+
+        let e = world.spawn();
+
+        for _ in 0..ENTITY_HISTORY_LEN {
+            world.insert(e, A);
+        }
+
+        let history = world.entity_history(e);
+
+        assert_eq!(history.len(), ENTITY_HISTORY_LEN);
+        assert!(history.iter().all(|rec| rec.kind == TransitionKind::Insert));
+    }
+
+    #[test]
+    fn retention() {
+        let mut world = World::new();
+        world.set_entity_history_retention(1);
+
+        let e1 = world.spawn();
+        let e2 = world.spawn();
+
+        world.despawn(e1);
+        assert_eq!(world.entity_history(e1).len(), 2);
+
+        world.despawn(e2);
+        assert!(world.entity_history(e1).is_empty());
+        assert_eq!(world.entity_history(e2).len(), 2);
+
+        // The slot of `e2` is reused, but its history is kept.
+        let e3 = world.spawn();
+        assert_eq!(world.entity_history(e2).len(), 2);
+        assert_eq!(world.entity_history(e3).len(), 1);
+    }
+}
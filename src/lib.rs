@@ -13,25 +13,56 @@ extern crate self as evenio;
 pub mod access;
 mod aliased_box;
 pub mod archetype;
+mod arena;
 mod assert;
+#[cfg(feature = "async-bridge")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async-bridge")))]
+pub mod async_bridge;
+#[cfg(feature = "bevy-bridge")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bevy-bridge")))]
+pub mod bevy_bridge;
 pub mod bit_set;
 mod blob_vec;
 pub mod bool_expr;
+pub mod commands;
 pub mod component;
+pub mod dedup;
+pub mod determinism;
+pub mod dirty;
 pub mod drop;
+pub mod dyn_component;
 pub mod entity;
 pub mod event;
 pub mod exclusive;
 pub mod fetch;
+pub mod flush;
 pub mod handler;
+#[cfg(feature = "entity-history")]
+#[cfg_attr(docsrs, doc(cfg(feature = "entity-history")))]
+pub mod history;
 mod layout_util;
 mod map;
+pub mod morton;
+pub mod path;
 pub mod query;
+pub mod quota;
+pub mod reflect;
+pub mod resource;
+#[cfg(doc)]
+pub mod safety;
+pub mod schedule;
+pub mod shared;
 mod slot_map;
+pub mod slots;
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub mod snapshot;
 pub mod sparse;
 mod sparse_map;
+pub mod subscription;
 #[cfg(doc)]
 pub mod tutorial;
+pub mod validate;
 pub mod world;
 
 #[cfg(feature = "rayon")]
@@ -59,6 +90,8 @@ pub mod prelude {
     };
     pub use crate::fetch::{Fetcher, GetError, Single, SingleError, TrySingle};
     pub use crate::handler::{Handler, HandlerId, HandlerParam, IntoHandler};
-    pub use crate::query::{Has, Not, Or, Query, ReadOnlyQuery, With, Xor};
+    pub use crate::query::{
+        Has, Not, Or, Previous, Query, ReadOnlyQuery, With, WithDefault, WithDefaultRef, Xor,
+    };
     pub use crate::world::World;
 }
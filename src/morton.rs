@@ -0,0 +1,343 @@
+//! Spatial ordering of archetype rows along a Morton (Z-order) curve.
+//!
+//! See [`World::sort_rows_morton`] for more information.
+//!
+//! [`World::sort_rows_morton`]: crate::world::World::sort_rows_morton
+
+use crate::archetype::Archetype;
+
+/// A point which can be mapped onto a Morton curve. Implemented for 2D and 3D
+/// positions.
+pub trait MortonPoint: Copy + PartialEq + sealed::Sealed {
+    /// Returns the Morton code of this point, after quantizing every
+    /// coordinate to its position between `min` and `max`. Coordinates outside
+    /// the bounds are clamped to them, and NaN coordinates are treated as
+    /// `min`.
+    ///
+    /// Points which are close together tend to have similar codes.
+    fn morton_code(self, min: Self, max: Self) -> u64;
+
+    /// Returns the componentwise minimum of `self` and `other`.
+    fn min(self, other: Self) -> Self;
+
+    /// Returns the componentwise maximum of `self` and `other`.
+    fn max(self, other: Self) -> Self;
+}
+
+impl MortonPoint for [f32; 2] {
+    fn morton_code(self, min: Self, max: Self) -> u64 {
+        let [x, y] = [0, 1].map(|i| quantize(self[i], min[i], max[i], 32));
+
+        spread_by_1(x) | spread_by_1(y) << 1
+    }
+
+    fn min(self, other: Self) -> Self {
+        [self[0].min(other[0]), self[1].min(other[1])]
+    }
+
+    fn max(self, other: Self) -> Self {
+        [self[0].max(other[0]), self[1].max(other[1])]
+    }
+}
+
+impl MortonPoint for [f32; 3] {
+    fn morton_code(self, min: Self, max: Self) -> u64 {
+        let [x, y, z] = [0, 1, 2].map(|i| quantize(self[i], min[i], max[i], 21));
+
+        spread_by_2(x) | spread_by_2(y) << 1 | spread_by_2(z) << 2
+    }
+
+    fn min(self, other: Self) -> Self {
+        [0, 1, 2].map(|i| self[i].min(other[i]))
+    }
+
+    fn max(self, other: Self) -> Self {
+        [0, 1, 2].map(|i| self[i].max(other[i]))
+    }
+}
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for [f32; 2] {}
+    impl Sealed for [f32; 3] {}
+}
+
+/// Maps `v` from `min..=max` onto an integer with `bits` bits.
+fn quantize(v: f32, min: f32, max: f32, bits: u32) -> u32 {
+    let scale = ((1_u64 << bits) - 1) as f64;
+    let [v, min, max] = [v, min, max].map(f64::from);
+    let t = (v - min) / (max - min);
+
+    // NaN, including a zero-sized range, maps to zero.
+    if t.is_nan() {
+        0
+    } else {
+        (t.clamp(0.0, 1.0) * scale) as u32
+    }
+}
+
+/// Inserts a zero bit between each of the bits of `v`.
+fn spread_by_1(v: u32) -> u64 {
+    let mut v = u64::from(v);
+    v = (v | v << 16) & 0x0000_ffff_0000_ffff;
+    v = (v | v << 8) & 0x00ff_00ff_00ff_00ff;
+    v = (v | v << 4) & 0x0f0f_0f0f_0f0f_0f0f;
+    v = (v | v << 2) & 0x3333_3333_3333_3333;
+    (v | v << 1) & 0x5555_5555_5555_5555
+}
+
+/// Inserts two zero bits between each of the low 21 bits of `v`.
+fn spread_by_2(v: u32) -> u64 {
+    let mut v = u64::from(v) & 0x1f_ffff;
+    v = (v | v << 32) & 0x001f_0000_0000_ffff;
+    v = (v | v << 16) & 0x001f_0000_ff00_00ff;
+    v = (v | v << 8) & 0x100f_00f0_0f00_f00f;
+    v = (v | v << 4) & 0x10c3_0c30_c30c_30c3;
+    (v | v << 2) & 0x1249_2492_4924_9249
+}
+
+/// Keeps the rows of archetypes in approximate Morton order across calls to
+/// [`World::sort_rows_morton_with`].
+///
+/// Positions are quantized within bounds which are either given up front with
+/// [`MortonSort::with_bounds`], or tracked as the running minimum and maximum
+/// of every position seen so far. When tracked bounds grow, every archetype is
+/// sorted again since all of the codes change.
+///
+/// An archetype is only sorted when its estimated _unsortedness_ exceeds the
+/// [threshold]. The estimate is the larger of
+///
+/// - the fraction of the archetype's rows which were added or moved since it
+///   was last sorted, tracked by the world as entities come and go, and
+/// - the fraction of out-of-order pairs among a sample of adjacent rows, which
+///   accounts for entities that moved through space.
+///
+/// [`World::sort_rows_morton_with`]: crate::world::World::sort_rows_morton_with
+/// [threshold]: MortonSort::threshold
+#[derive(Clone, Debug)]
+pub struct MortonSort<P> {
+    bounds: Option<(P, P)>,
+    track_bounds: bool,
+    threshold: f32,
+    samples: u32,
+}
+
+impl<P: MortonPoint> MortonSort<P> {
+    /// Creates a new `MortonSort` which tracks the bounds of the positions and
+    /// sorts any archetype that isn't sorted.
+    pub fn new() -> Self {
+        Self {
+            bounds: None,
+            track_bounds: true,
+            threshold: 0.0,
+            samples: 64,
+        }
+    }
+
+    /// Creates a new `MortonSort` which quantizes positions between `min` and
+    /// `max`.
+    pub fn with_bounds(min: P, max: P) -> Self {
+        Self {
+            bounds: Some((min, max)),
+            track_bounds: false,
+            ..Self::new()
+        }
+    }
+
+    /// Sets the unsortedness, between 0 and 1, which an archetype must exceed
+    /// to be sorted again. The default of 0 sorts any archetype which doesn't
+    /// appear to be sorted.
+    pub fn threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Sets the number of adjacent rows sampled to estimate the unsortedness
+    /// of an archetype. Defaults to 64.
+    pub fn samples(mut self, samples: u32) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    /// Returns the bounds positions are quantized within, or `None` if no
+    /// positions have been seen yet.
+    pub fn bounds(&self) -> Option<(P, P)> {
+        self.bounds
+    }
+
+    /// Grows the tracked bounds to contain `point`. Returns `true` if they
+    /// changed.
+    pub(crate) fn include(&mut self, point: P) -> bool {
+        // Points with NaN coordinates aren't equal to themselves.
+        #[allow(clippy::eq_op)]
+        let has_nan = point != point;
+
+        if !self.track_bounds || has_nan {
+            return false;
+        }
+
+        let new = match self.bounds {
+            Some((min, max)) => (min.min(point), max.max(point)),
+            None => (point, point),
+        };
+
+        let changed = self.bounds != Some(new);
+        self.bounds = Some(new);
+        changed
+    }
+
+    /// Returns `true` if `arch`, whose codes are given by `code`, should be
+    /// sorted.
+    pub(crate) fn needs_sort(&self, arch: &Archetype, mut code: impl FnMut(u32) -> u64) -> bool {
+        let len = arch.entity_count();
+
+        if len < 2 {
+            return false;
+        }
+
+        let churn = arch.row_churn() as f32 / len as f32;
+
+        if churn > self.threshold {
+            return true;
+        }
+
+        let pairs = len - 1;
+        let samples = self.samples.clamp(1, pairs);
+        let step = pairs / samples;
+
+        let inversions = (0..samples)
+            .map(|i| i * step)
+            .filter(|&row| code(row) > code(row + 1))
+            .count();
+
+        inversions as f32 / samples as f32 > self.threshold
+    }
+}
+
+impl<P: MortonPoint> Default for MortonSort<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::any::TypeId;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Component, Clone, Copy)]
+    struct Position([f32; 2]);
+
+    fn interleave<const N: usize>(coords: [u32; N], bits: u32) -> u64 {
+        let mut code = 0;
+
+        for bit in 0..bits {
+            for (i, c) in coords.iter().enumerate() {
+                code |= u64::from(c >> bit & 1) << (bit as usize * N + i);
+            }
+        }
+
+        code
+    }
+
+    #[test]
+    fn morton_codes() {
+        for v in [0, 1, 0x1234_5678, 0x1f_ffff, u32::MAX] {
+            assert_eq!(
+                spread_by_1(v) | spread_by_1(!v) << 1,
+                interleave([v, !v], 32)
+            );
+            assert_eq!(
+                spread_by_2(v) | spread_by_2(v >> 3) << 1 | spread_by_2(v >> 7) << 2,
+                interleave([v, v >> 3, v >> 7].map(|c| c & 0x1f_ffff), 21)
+            );
+        }
+
+        let (min, max) = ([0.0; 2], [1.0; 2]);
+
+        assert_eq!([0.0, 0.0].morton_code(min, max), 0);
+        assert_eq!([1.0, 1.0].morton_code(min, max), u64::MAX);
+        assert_eq!(
+            [-5.0, 5.0].morton_code(min, max),
+            [0.0, 1.0].morton_code(min, max)
+        );
+        assert_eq!([f32::NAN, 0.0].morton_code(min, max), 0);
+    }
+
+    /// Returns the Morton codes of every `Position` in row order.
+    fn codes(world: &World, min: [f32; 2], max: [f32; 2]) -> Vec<u64> {
+        let idx = world
+            .components()
+            .get_by_type_id(TypeId::of::<Position>())
+            .unwrap()
+            .id()
+            .index();
+
+        world
+            .archetypes()
+            .iter()
+            .filter_map(|arch| Some((arch, arch.column_of(idx)?)))
+            .flat_map(|(arch, col)| {
+                let data = col.data().as_ptr().cast::<Position>();
+
+                (0..arch.entity_count() as usize)
+                    .map(move |row| unsafe { (*data.add(row)).0 }.morton_code(min, max))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn incremental_sort() {
+        let mut world = World::new();
+        let mut sort = MortonSort::new().threshold(0.25);
+
+        let mut entities = vec![];
+
+        for i in 0..64_u32 {
+            let e = world.spawn();
+            world.insert(e, Position([(i * 29 % 8) as f32, (i * 13 % 8) as f32]));
+            entities.push(e);
+        }
+
+        assert_eq!(
+            world.sort_rows_morton_with(&mut sort, |p: &Position| p.0),
+            1
+        );
+        assert_eq!(sort.bounds(), Some(([0.0; 2], [7.0; 2])));
+
+        let (min, max) = sort.bounds().unwrap();
+        assert!(codes(&world, min, max).windows(2).all(|w| w[0] <= w[1]));
+
+        // A single entity moving doesn't make the archetype unsorted enough.
+        world.get_mut::<Position>(entities[0]).unwrap().0 = [3.5, 3.5];
+        assert_eq!(
+            world.sort_rows_morton_with(&mut sort, |p: &Position| p.0),
+            0
+        );
+
+        // Adding many rows does.
+        for _ in 0..32 {
+            let e = world.spawn();
+            world.insert(e, Position([1.0, 6.0]));
+        }
+
+        assert_eq!(
+            world.sort_rows_morton_with(&mut sort, |p: &Position| p.0),
+            1
+        );
+        assert!(codes(&world, min, max).windows(2).all(|w| w[0] <= w[1]));
+
+        // Growing the bounds changes every code.
+        let e = world.spawn();
+        world.insert(e, Position([100.0, 100.0]));
+
+        world.sort_rows_morton_with(&mut sort, |p: &Position| p.0);
+
+        let (min, max) = sort.bounds().unwrap();
+        assert_eq!((min, max), ([0.0; 2], [100.0; 2]));
+        assert!(codes(&world, min, max).windows(2).all(|w| w[0] <= w[1]));
+    }
+}
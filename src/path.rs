@@ -0,0 +1,451 @@
+//! Following chains of links between entities.
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+use core::cell::RefCell;
+use core::fmt;
+
+use ahash::RandomState;
+use evenio_macros::all_tuples;
+
+use crate::archetype::Archetype;
+use crate::component::Component;
+use crate::entity::{Entities, EntityId, EntityLocation};
+use crate::event::EventPtr;
+use crate::fetch::FetcherState;
+use crate::handler::{Config, HandlerInfo, HandlerParam, InitError};
+use crate::map::HashMap;
+use crate::query::ReadOnlyQuery;
+use crate::world::{UnsafeWorldCell, World};
+
+/// A component which refers to another entity, such as the parent of an
+/// entity in a hierarchy.
+///
+/// Chains of links are followed with [`ViaPath`].
+pub trait Link: Component {
+    /// Returns the entity this component links to.
+    fn target(&self) -> EntityId;
+}
+
+/// A fixed sequence of [`Link`] components, one per hop of a [`ViaPath`].
+///
+/// This is implemented for tuples of up to twelve `Link`s. The first
+/// component in the tuple is read from the starting entity, the second from
+/// the entity it links to, and so on.
+///
+/// # Safety
+///
+/// Implementors must ensure that [`LinkPath::init`] correctly registers the
+/// data accessed in [`LinkPath::hop`].
+pub unsafe trait LinkPath: 'static {
+    /// The number of hops in the path.
+    const LEN: usize;
+    /// Cached data for following the path.
+    type State: Send + Sync + fmt::Debug + 'static;
+
+    /// Registers read access for every link in the path and returns a new
+    /// [`Self::State`].
+    fn init(world: &mut World, config: &mut Config) -> Result<Self::State, InitError>;
+
+    /// Reads the link at index `depth` of the path from `entity`. Returns
+    /// `None` if `entity` doesn't exist or doesn't have the link.
+    ///
+    /// # Safety
+    ///
+    /// - `depth` must be less than [`Self::LEN`].
+    /// - Must have the access registered by [`init`](Self::init).
+    unsafe fn hop(
+        state: &Self::State,
+        depth: usize,
+        entities: &Entities,
+        entity: EntityId,
+    ) -> Option<EntityId>;
+
+    /// Refresh an archetype for this path.
+    fn refresh_archetype(state: &mut Self::State, arch: &Archetype);
+
+    /// Remove the given archetype for this path.
+    fn remove_archetype(state: &mut Self::State, arch: &Archetype);
+}
+
+macro_rules! impl_link_path_tuple {
+    ($(($L:ident, $s:ident)),*) => {
+        unsafe impl<$($L: Link),*> LinkPath for ($($L,)*) {
+            const LEN: usize = [$(stringify!($L)),*].len();
+
+            type State = ($(FetcherState<&'static $L>,)*);
+
+            fn init(world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+                Ok((
+                    $(
+                        FetcherState::<&'static $L>::init(world, config)?,
+                    )*
+                ))
+            }
+
+            #[inline]
+            #[allow(unused_assignments)]
+            unsafe fn hop(
+                ($($s,)*): &Self::State,
+                depth: usize,
+                entities: &Entities,
+                entity: EntityId,
+            ) -> Option<EntityId> {
+                let mut i = 0;
+
+                $(
+                    if i == depth {
+                        return $s.get(entities, entity).ok().map(|link| link.target());
+                    }
+
+                    i += 1;
+                )*
+
+                None
+            }
+
+            fn refresh_archetype(($($s,)*): &mut Self::State, arch: &Archetype) {
+                $(
+                    $s.refresh_archetype(arch);
+                )*
+            }
+
+            fn remove_archetype(($($s,)*): &mut Self::State, arch: &Archetype) {
+                $(
+                    $s.remove_archetype(arch);
+                )*
+            }
+        }
+    }
+}
+
+all_tuples!(impl_link_path_tuple, 1, 12, L, s);
+
+/// A [`HandlerParam`] which follows the [`Link`]s in `P` out of an entity and
+/// fetches the read-only query `Q` from the entity at the end.
+///
+/// Every link component in the path and the data in `Q` are registered as
+/// read access, so `ViaPath` conflicts with handler params which access any of
+/// them mutably.
+///
+/// Within one run of the handler, the result of following the rest of the
+/// path out of each intermediate entity is cached. Many entities sharing a
+/// parent only look up the parent's links once.
+///
+/// The number of hops is fixed by `P`, so cycles in the links are harmless.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::path::{Link, ViaPath};
+/// use evenio::prelude::*;
+///
+/// #[derive(Component)]
+/// struct ChildOf(EntityId);
+///
+/// impl Link for ChildOf {
+///     fn target(&self) -> EntityId {
+///         self.0
+///     }
+/// }
+///
+/// #[derive(Component, PartialEq, Debug)]
+/// struct Transform(f32);
+///
+/// #[derive(Event)]
+/// struct Layout;
+///
+/// let mut world = World::new();
+///
+/// let root = world.spawn();
+/// world.insert(root, Transform(10.0));
+///
+/// let panel = world.spawn();
+/// world.insert(panel, ChildOf(root));
+///
+/// let button = world.spawn();
+/// world.insert(button, ChildOf(panel));
+///
+/// world.add_handler(
+///     move |_: Receiver<Layout>, via: ViaPath<(ChildOf, ChildOf), &Transform>| {
+///         assert_eq!(via.get(button), Some(&Transform(10.0)));
+///         assert_eq!(via.get(panel), None);
+///     },
+/// );
+///
+/// world.send(Layout);
+/// ```
+pub struct ViaPath<'a, P: LinkPath, Q: ReadOnlyQuery> {
+    links: &'a P::State,
+    terminal: &'a FetcherState<Q>,
+    cache: RefCell<&'a mut PathCache>,
+    world: UnsafeWorldCell<'a>,
+}
+
+impl<'a, P: LinkPath, Q: ReadOnlyQuery> ViaPath<'a, P, Q> {
+    /// Follows the path out of `entity` and returns the query item of the
+    /// entity at the end.
+    ///
+    /// Returns `None` if any entity along the way doesn't exist or is missing
+    /// its link, or if the entity at the end doesn't match the query.
+    pub fn get(&self, entity: EntityId) -> Option<Q::Item<'_>> {
+        let end = self.resolve(entity)?;
+
+        unsafe { self.terminal.get(self.world.entities(), end) }.ok()
+    }
+
+    /// Follows the path out of `entity` and returns the entity at the end,
+    /// without checking it against the query.
+    ///
+    /// Returns `None` if any entity along the way doesn't exist or is missing
+    /// its link.
+    pub fn resolve(&self, entity: EntityId) -> Option<EntityId> {
+        let entities = self.world.entities();
+        let cache = &mut **self.cache.borrow_mut();
+
+        cache.pending.clear();
+
+        let mut current = Some(entity);
+        let mut depth = 0;
+
+        let end = loop {
+            let Some(e) = current else {
+                break None;
+            };
+
+            if depth == P::LEN {
+                break Some(e);
+            }
+
+            if depth > 0 {
+                if let Some(&end) = cache.resolved.get(&(depth, e)) {
+                    break end;
+                }
+
+                cache.pending.push((depth, e));
+            }
+
+            // SAFETY: `depth` is less than the path length, and access to the
+            // links was registered in `init`.
+            current = unsafe { P::hop(self.links, depth, entities, e) };
+            depth += 1;
+
+            #[cfg(test)]
+            {
+                cache.hops += 1;
+            }
+        };
+
+        for key in cache.pending.drain(..) {
+            cache.resolved.insert(key, end);
+        }
+
+        end
+    }
+}
+
+unsafe impl<P, Q> HandlerParam for ViaPath<'_, P, Q>
+where
+    P: LinkPath,
+    Q: ReadOnlyQuery + 'static,
+{
+    type State = ViaPathState<P, Q>;
+
+    type Item<'a> = ViaPath<'a, P, Q>;
+
+    fn init(world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+        Ok(ViaPathState {
+            links: P::init(world, config)?,
+            terminal: FetcherState::init(world, config)?,
+            cache: PathCache::new(),
+        })
+    }
+
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        _info: &'a HandlerInfo,
+        _event_ptr: EventPtr<'a>,
+        _target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::Item<'a> {
+        // Links may have changed since the handler last ran.
+        state.cache.clear();
+        state.terminal.start_run(world.archetypes().change_tick());
+
+        ViaPath {
+            links: &state.links,
+            terminal: &state.terminal,
+            cache: RefCell::new(&mut state.cache),
+            world,
+        }
+    }
+
+    fn refresh_archetype(state: &mut Self::State, arch: &Archetype) {
+        P::refresh_archetype(&mut state.links, arch);
+        state.terminal.refresh_archetype(arch);
+    }
+
+    fn remove_archetype(state: &mut Self::State, arch: &Archetype) {
+        P::remove_archetype(&mut state.links, arch);
+        state.terminal.remove_archetype(arch);
+    }
+}
+
+impl<P: LinkPath, Q: ReadOnlyQuery> fmt::Debug for ViaPath<'_, P, Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ViaPath")
+            .field("links", &self.links)
+            .field("terminal", &self.terminal)
+            .field("cache", &self.cache)
+            .field("world", &self.world)
+            .finish()
+    }
+}
+
+/// Internal state for a [`ViaPath`].
+#[doc(hidden)]
+pub struct ViaPathState<P: LinkPath, Q: ReadOnlyQuery> {
+    links: P::State,
+    terminal: FetcherState<Q>,
+    cache: PathCache,
+}
+
+impl<P: LinkPath, Q: ReadOnlyQuery> fmt::Debug for ViaPathState<P, Q> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ViaPathState")
+            .field("links", &self.links)
+            .field("terminal", &self.terminal)
+            .field("cache", &self.cache)
+            .finish()
+    }
+}
+
+/// The end of the path out of intermediate entities, keyed by the number of
+/// hops taken to reach them.
+#[derive(Debug)]
+struct PathCache {
+    resolved: HashMap<(usize, EntityId), Option<EntityId>>,
+    /// Intermediate entities visited while resolving the current entity.
+    pending: Vec<(usize, EntityId)>,
+    /// Number of links read since the cache was cleared.
+    #[cfg(test)]
+    hops: usize,
+}
+
+impl PathCache {
+    fn new() -> Self {
+        Self {
+            resolved: HashMap::with_hasher(RandomState::new()),
+            pending: vec![],
+            #[cfg(test)]
+            hops: 0,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.resolved.clear();
+
+        #[cfg(test)]
+        {
+            self.hops = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Component)]
+    struct ChildOf(EntityId);
+
+    impl Link for ChildOf {
+        fn target(&self) -> EntityId {
+            self.0
+        }
+    }
+
+    #[derive(Component, PartialEq, Debug)]
+    struct Transform(u32);
+
+    #[derive(Event)]
+    struct E;
+
+    type Grandparent<'a> = ViaPath<'a, (ChildOf, ChildOf), &'static Transform>;
+
+    fn child_of(world: &mut World, parent: EntityId) -> EntityId {
+        let e = world.spawn();
+        world.insert(e, ChildOf(parent));
+        e
+    }
+
+    #[test]
+    fn three_levels() {
+        let mut world = World::new();
+
+        let root = world.spawn();
+        world.insert(root, Transform(1));
+        let mid = child_of(&mut world, root);
+        world.insert(mid, Transform(2));
+        let leaf = child_of(&mut world, mid);
+        world.insert(leaf, Transform(3));
+
+        world.add_handler(move |_: Receiver<E>, via: Grandparent| {
+            assert_eq!(via.get(leaf), Some(&Transform(1)));
+            assert_eq!(via.resolve(leaf), Some(root));
+            // `root` has no parent.
+            assert_eq!(via.get(mid), None);
+            assert_eq!(via.get(root), None);
+        });
+
+        world.send(E);
+    }
+
+    #[test]
+    fn broken_middle_link() {
+        let mut world = World::new();
+
+        let root = world.spawn();
+        world.insert(root, Transform(1));
+        let mid = child_of(&mut world, root);
+        let leaf = child_of(&mut world, mid);
+
+        let dead = world.spawn();
+        let orphan = child_of(&mut world, dead);
+        world.despawn(dead);
+
+        world.add_handler(move |_: Receiver<E>, via: Grandparent| {
+            assert_eq!(via.get(leaf), None);
+            assert_eq!(via.get(orphan), None);
+        });
+
+        world.remove::<ChildOf>(mid);
+        world.send(E);
+    }
+
+    #[test]
+    fn shared_parent_is_cached() {
+        let mut world = World::new();
+
+        let root = world.spawn();
+        world.insert(root, Transform(1));
+        let mid = child_of(&mut world, root);
+        let leaves: Vec<_> = (0..4).map(|_| child_of(&mut world, mid)).collect();
+
+        world.add_handler(
+            move |_: Receiver<E>, via: Grandparent, f: Fetcher<(EntityId, With<&ChildOf>)>| {
+                for (e, _) in f {
+                    if leaves.contains(&e) {
+                        assert_eq!(via.get(e), Some(&Transform(1)));
+                    }
+                }
+
+                // One hop out of each leaf, plus one out of `mid`.
+                assert_eq!(via.cache.borrow().hops, leaves.len() + 1);
+            },
+        );
+
+        world.send(E);
+    }
+}
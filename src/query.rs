@@ -1,19 +1,23 @@
 //! Type-level DSL for retrieving data from entities.
 
 use alloc::format;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
 use core::marker::PhantomData;
-use core::ptr::NonNull;
-use core::{any, fmt};
+use core::ops::{Deref, DerefMut};
+use core::ptr::{self, NonNull};
+use core::{any, fmt, slice};
 
 use evenio_macros::all_tuples;
 pub use evenio_macros::Query;
 
 use crate::access::{Access, ComponentAccessExpr};
 use crate::archetype::{Archetype, ArchetypeRow};
-use crate::assert::{AssertMutable, UnwrapDebugChecked};
+use crate::assert::{AssertDoubleBuffered, AssertMutable, UnwrapDebugChecked};
 use crate::component::{Component, ComponentIdx};
 use crate::entity::EntityId;
 use crate::handler::{Config, InitError};
+use crate::sparse::SparseIndex;
 use crate::world::World;
 
 /// Types that can be fetched from an entity.
@@ -24,7 +28,10 @@ use crate::world::World;
 /// # Deriving
 ///
 /// This trait can be safely implemented using the `Query` derive macro. For a
-/// struct to derive `Query`, all fields must also implement `Query`.
+/// struct to derive `Query`, all fields must also implement `Query`, and the
+/// type of each field must implement `From` for the field's query item. This
+/// is the case for queries whose item is the query type itself, like
+/// `&'a A`, and for [`Has`], whose item is a `bool`.
 ///
 /// ```
 /// # #[derive(Event)]
@@ -92,6 +99,31 @@ pub unsafe trait Query {
     ///
     /// [`init`]: Self::init
     unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::Item<'a>;
+
+    /// Whether [`filter_row`] can reject entities in the archetypes matched by
+    /// [`new_arch_state`]. Most queries only look at the archetype, so this
+    /// defaults to `false`.
+    ///
+    /// [`filter_row`]: Self::filter_row
+    /// [`new_arch_state`]: Self::new_arch_state
+    const FILTERS_ROWS: bool = false;
+
+    /// Returns `true` if the entity at the given row matches the query.
+    /// `last_run_tick` is the change tick of the previous run of the handler,
+    /// used by [`Added`] and [`Changed`].
+    ///
+    /// The default implementation always returns `true`.
+    ///
+    /// # Safety
+    /// - `row` must be in bounds.
+    /// - Must have the permissions described in [`get`].
+    ///
+    /// [`get`]: Self::get
+    #[inline]
+    unsafe fn filter_row(state: &Self::ArchState, row: ArchetypeRow, last_run_tick: u64) -> bool {
+        let _ = (state, row, last_run_tick);
+        true
+    }
 }
 
 /// Marker trait for queries which dot not access data mutably.
@@ -139,10 +171,15 @@ unsafe impl<C: Component> Query for &'_ C {
 
 unsafe impl<C: Component> ReadOnlyQuery for &'_ C {}
 
+/// Records a write in the component's change tick whenever the component is
+/// fetched. See [`Mut`] to only record writes when the component is actually
+/// dereferenced mutably.
 unsafe impl<C: Component> Query for &'_ mut C {
     type Item<'a> = &'a mut C;
 
-    type ArchState = ColumnPtr<C>;
+    /// Pointers to the component data, the change ticks, and the current
+    /// change tick.
+    type ArchState = (ColumnPtr<C>, ColumnPtr<u64>, ColumnPtr<u64>);
 
     type State = ComponentIdx;
 
@@ -164,11 +201,163 @@ unsafe impl<C: Component> Query for &'_ mut C {
     }
 
     fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
-        <&C>::new_arch_state(arch, state)
+        arch.column_of(*state).map(|c| {
+            (
+                ColumnPtr(c.data().cast()),
+                ColumnPtr(c.ticks_ptr()),
+                ColumnPtr(arch.change_tick_ptr()),
+            )
+        })
     }
 
-    unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::Item<'a> {
-        &mut *state.0.as_ptr().add(row.0 as usize)
+    unsafe fn get<'a>((data, ticks, now): &Self::ArchState, row: ArchetypeRow) -> Self::Item<'a> {
+        *ticks.0.as_ptr().add(row.0 as usize) = *now.0.as_ptr();
+        &mut *data.0.as_ptr().add(row.0 as usize)
+    }
+}
+
+/// Like `&mut C`, but only records a write in the component's change tick
+/// when the component is dereferenced mutably, rather than whenever it is
+/// fetched.
+///
+/// See [`Changed`] for more information.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::prelude::*;
+/// use evenio::query::{Changed, Mut};
+///
+/// #[derive(Component)]
+/// struct Health(u32);
+///
+/// #[derive(Event)]
+/// struct Damage(u32);
+///
+/// #[derive(Event)]
+/// struct Check;
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|r: Receiver<Damage>, f: Fetcher<Mut<Health>>| {
+///     for mut health in f {
+///         health.0 = health.0.saturating_sub(r.event.0);
+///     }
+/// });
+///
+/// world.add_handler(|_: Receiver<Check>, f: Fetcher<(EntityId, Changed<Health>)>| {
+///     for (e, _) in f {
+///         println!("{e:?} took damage");
+///     }
+/// });
+///
+/// let e = world.spawn();
+/// world.insert(e, Health(100));
+///
+/// world.send(Check);
+/// world.send(Damage(10));
+/// world.send(Check);
+/// ```
+pub struct Mut<'a, C> {
+    value: &'a mut C,
+    tick: &'a mut u64,
+    now: u64,
+}
+
+impl<C> Deref for Mut<'_, C> {
+    type Target = C;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+impl<C> DerefMut for Mut<'_, C> {
+    /// Records a write in the change tick. The tick is updated even if the
+    /// component is not modified, or is set to a value equal to the old one.
+    /// Use [`Mut::set`] to skip identical writes.
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        *self.tick = self.now;
+        self.value
+    }
+}
+
+impl<C: Component> Mut<'_, C> {
+    /// Overwrites the component with `value` and records a write in the
+    /// change tick. Returns `true` if the component was written.
+    ///
+    /// If the component has [`SKIP_IDENTICAL_WRITES`] and `value` is equal to
+    /// the current value, `value` is dropped instead and the change tick is
+    /// left untouched, so [`Changed`] filters don't see the write. Use
+    /// [`force_write`](Self::force_write) to always write.
+    ///
+    /// [`SKIP_IDENTICAL_WRITES`]: Component::SKIP_IDENTICAL_WRITES
+    pub fn set(&mut self, value: C) -> bool {
+        if let Some(eq) = C::SKIP_IDENTICAL_WRITES {
+            // SAFETY: Both pointers point to initialized values of type `C`.
+            let equal = unsafe {
+                eq(
+                    NonNull::from(&*self.value).cast(),
+                    NonNull::from(&value).cast(),
+                )
+            };
+
+            if equal {
+                return false;
+            }
+        }
+
+        self.force_write(value);
+
+        true
+    }
+
+    /// Overwrites the component with `value` and records a write in the
+    /// change tick, even if the component has [`SKIP_IDENTICAL_WRITES`] and
+    /// the values are equal.
+    ///
+    /// [`SKIP_IDENTICAL_WRITES`]: Component::SKIP_IDENTICAL_WRITES
+    pub fn force_write(&mut self, value: C) {
+        **self = value;
+    }
+}
+
+impl<C: fmt::Debug> fmt::Debug for Mut<'_, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+unsafe impl<C: Component> Query for Mut<'_, C> {
+    type Item<'a> = Mut<'a, C>;
+
+    /// Pointers to the component data, the change ticks, and the current
+    /// change tick.
+    type ArchState = (ColumnPtr<C>, ColumnPtr<u64>, ColumnPtr<u64>);
+
+    type State = ComponentIdx;
+
+    fn init(
+        world: &mut World,
+        config: &mut Config,
+    ) -> Result<(ComponentAccessExpr, Self::State), InitError> {
+        <&mut C>::init(world, config)
+    }
+
+    fn new_state(world: &mut World) -> Self::State {
+        world.add_component::<C>().index()
+    }
+
+    fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
+        <&mut C>::new_arch_state(arch, state)
+    }
+
+    unsafe fn get<'a>((data, ticks, now): &Self::ArchState, row: ArchetypeRow) -> Self::Item<'a> {
+        Mut {
+            value: &mut *data.0.as_ptr().add(row.0 as usize),
+            tick: &mut *ticks.0.as_ptr().add(row.0 as usize),
+            now: *now.0.as_ptr(),
+        }
     }
 }
 
@@ -194,15 +383,26 @@ macro_rules! impl_query_tuple {
                 $(
                     let (expr, $q) = $Q::init(world, config)?;
 
-                    let Ok(expr) = res.and(&expr) else {
-                        return Err(InitError(format!(
-                            "conflicting access in tuple `{}`: tuple element `{}` conflicts with previous elements",
-                            any::type_name::<Self>(),
-                            any::type_name::<$Q>(),
-                        ).into()));
+                    // Identical reads such as `(&A, &A)` are coalesced by `and`.
+                    res = match res.and(&expr) {
+                        Ok(res) => res,
+                        Err(res) => {
+                            let names = res
+                                .access
+                                .conflicts(&expr.access)
+                                .filter_map(|idx| world.components().get_by_index(idx))
+                                .map(|info| format!("`{}`", info.name()))
+                                .collect::<Vec<_>>()
+                                .join(", ");
+
+                            return Err(InitError(format!(
+                                "conflicting access in tuple `{}`: tuple element `{}` conflicts with previous elements on component(s) {}",
+                                any::type_name::<Self>(),
+                                any::type_name::<$Q>(),
+                                names,
+                            ).into()));
+                        }
                     };
-
-                    res = expr;
                 )*
 
                 Ok((res, ($($q,)*)))
@@ -231,6 +431,17 @@ macro_rules! impl_query_tuple {
                     )*
                 )
             }
+
+            const FILTERS_ROWS: bool = false $(|| $Q::FILTERS_ROWS)*;
+
+            #[inline]
+            unsafe fn filter_row(
+                ($($q,)*): &Self::ArchState,
+                row: ArchetypeRow,
+                last_run_tick: u64,
+            ) -> bool {
+                true $(&& $Q::filter_row($q, row, last_run_tick))*
+            }
         }
 
         unsafe impl<$($Q: ReadOnlyQuery),*> ReadOnlyQuery for ($($Q,)*) {}
@@ -274,6 +485,222 @@ unsafe impl<Q: Query> Query for Option<Q> {
 
 unsafe impl<Q: ReadOnlyQuery> ReadOnlyQuery for Option<Q> {}
 
+/// A [`Query`] which returns a copy of component `C`, or `C::default()` if the
+/// entity does not have the component.
+///
+/// Like `Option<&C>`, this query matches all entities and only reads `C` from
+/// archetypes that contain it.
+///
+/// For components which are not [`Copy`], see [`WithDefaultRef`].
+pub struct WithDefault<C>(PhantomData<fn() -> C>);
+
+impl<C> fmt::Debug for WithDefault<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("WithDefault").finish()
+    }
+}
+
+unsafe impl<C: Component + Default + Copy> Query for WithDefault<C> {
+    type Item<'a> = C;
+
+    type ArchState = Option<ColumnPtr<C>>;
+
+    type State = ComponentIdx;
+
+    fn init(
+        world: &mut World,
+        config: &mut Config,
+    ) -> Result<(ComponentAccessExpr, Self::State), InitError> {
+        Option::<&C>::init(world, config)
+    }
+
+    fn new_state(world: &mut World) -> Self::State {
+        Option::<&C>::new_state(world)
+    }
+
+    fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
+        Option::<&C>::new_arch_state(arch, state)
+    }
+
+    unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::Item<'a> {
+        match state {
+            Some(col) => *col.0.as_ptr().cast_const().add(row.0 as usize),
+            None => C::default(),
+        }
+    }
+}
+
+unsafe impl<C: Component + Default + Copy> ReadOnlyQuery for WithDefault<C> {}
+
+/// A [`Query`] which returns a reference to component `C`, or a reference to
+/// the world's default instance of `C` if the entity does not have the
+/// component.
+///
+/// The default instance is registered with [`World::set_query_default`].
+/// Initializing this query fails if no default has been registered.
+///
+/// Like `Option<&C>`, this query matches all entities and only reads `C` from
+/// archetypes that contain it.
+pub struct WithDefaultRef<C>(PhantomData<fn() -> C>);
+
+impl<C> fmt::Debug for WithDefaultRef<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("WithDefaultRef").finish()
+    }
+}
+
+unsafe impl<C: Component> Query for WithDefaultRef<C> {
+    type Item<'a> = &'a C;
+
+    /// Pointer to either the column or the default value, and whether the
+    /// pointer is to the column.
+    type ArchState = (ColumnPtr<C>, bool);
+
+    /// Component index and pointer to the default value.
+    type State = (ComponentIdx, Option<ColumnPtr<C>>);
+
+    fn init(
+        world: &mut World,
+        config: &mut Config,
+    ) -> Result<(ComponentAccessExpr, Self::State), InitError> {
+        let (expr, _) = Option::<&C>::init(world, config)?;
+        let state = Self::new_state(world);
+
+        if state.1.is_none() {
+            return Err(InitError(
+                format!(
+                    "no query default is registered for component `{}` (see \
+                     `World::set_query_default`)",
+                    any::type_name::<C>()
+                )
+                .into(),
+            ));
+        }
+
+        Ok((expr, state))
+    }
+
+    fn new_state(world: &mut World) -> Self::State {
+        let idx = world.add_component::<C>().index();
+        let default = world
+            .components()
+            .get_by_index(idx)
+            .and_then(|info| info.query_default_ptr())
+            .map(|ptr| ColumnPtr(ptr.cast()));
+
+        (idx, default)
+    }
+
+    fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
+        match arch.column_of(state.0) {
+            Some(col) => Some((ColumnPtr(col.data().cast()), true)),
+            None => state.1.map(|ptr| (ptr, false)),
+        }
+    }
+
+    unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::Item<'a> {
+        let (ptr, is_column) = *state;
+
+        if is_column {
+            &*ptr.0.as_ptr().cast_const().add(row.0 as usize)
+        } else {
+            &*ptr.0.as_ptr().cast_const()
+        }
+    }
+}
+
+unsafe impl<C: Component> ReadOnlyQuery for WithDefaultRef<C> {}
+
+/// A [`Query`] which returns a reference to the previous value of the
+/// [double-buffered] component `C`.
+///
+/// The previous value is the value the component had as of the last call to
+/// [`World::swap_buffers`]. Entities which received the component since then
+/// observe the value it was inserted with.
+///
+/// The previous values are separate from the current values, so this query
+/// does not conflict with `&mut C`.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::prelude::*;
+///
+/// #[derive(Component, Clone, Copy, PartialEq, Debug)]
+/// #[component(double_buffer)]
+/// struct Position(f32);
+///
+/// let mut world = World::new();
+///
+/// let e = world.spawn();
+/// world.insert(e, Position(1.0));
+/// world.swap_buffers::<Position>();
+/// world.insert(e, Position(2.0));
+///
+/// #[derive(Event)]
+/// struct Interpolate;
+///
+/// world.add_handler(
+///     |_: Receiver<Interpolate>, f: Fetcher<(&mut Position, Previous<Position>)>| {
+///         for (pos, prev) in f {
+///             assert_eq!(*prev, Position(1.0));
+///             pos.0 = (pos.0 + prev.0) / 2.0;
+///         }
+///     },
+/// );
+///
+/// world.send(Interpolate);
+///
+/// assert_eq!(world.get::<Position>(e), Some(&Position(1.5)));
+/// ```
+///
+/// [double-buffered]: crate::component::Component::DOUBLE_BUFFERED
+pub struct Previous<C>(PhantomData<fn() -> C>);
+
+impl<C> fmt::Debug for Previous<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Previous").finish()
+    }
+}
+
+unsafe impl<C: Component> Query for Previous<C> {
+    type Item<'a> = &'a C;
+
+    type ArchState = ColumnPtr<C>;
+
+    type State = ComponentIdx;
+
+    fn init(
+        world: &mut World,
+        config: &mut Config,
+    ) -> Result<(ComponentAccessExpr, Self::State), InitError> {
+        let () = AssertDoubleBuffered::<C>::COMPONENT;
+
+        let (mut expr, idx) = <&C>::init(world, config)?;
+
+        // The previous values are only written with exclusive access to the world.
+        expr.access.clear();
+
+        Ok((expr, idx))
+    }
+
+    fn new_state(world: &mut World) -> Self::State {
+        <&C>::new_state(world)
+    }
+
+    fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
+        arch.column_of(*state)
+            .and_then(|c| c.previous_data())
+            .map(|ptr| ColumnPtr(ptr.cast()))
+    }
+
+    unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::Item<'a> {
+        &*state.0.as_ptr().cast_const().add(row.0 as usize)
+    }
+}
+
+unsafe impl<C: Component> ReadOnlyQuery for Previous<C> {}
+
 /// A [`Query`] which matches if the `L` or `R` queries match.
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub enum Or<L, R> {
@@ -372,6 +799,19 @@ where
     unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::Item<'a> {
         state.as_ref().map(|l| L::get(l, row), |r| R::get(r, row))
     }
+
+    const FILTERS_ROWS: bool = L::FILTERS_ROWS || R::FILTERS_ROWS;
+
+    #[inline]
+    unsafe fn filter_row(state: &Self::ArchState, row: ArchetypeRow, last_run_tick: u64) -> bool {
+        match state {
+            Or::Left(l) => L::filter_row(l, row, last_run_tick),
+            Or::Right(r) => R::filter_row(r, row, last_run_tick),
+            Or::Both(l, r) => {
+                L::filter_row(l, row, last_run_tick) || R::filter_row(r, row, last_run_tick)
+            }
+        }
+    }
 }
 
 unsafe impl<L, R> ReadOnlyQuery for Or<L, R>
@@ -473,6 +913,11 @@ where
 }
 
 /// A [`Query`] which matches if query `Q` doesn't match.
+///
+/// `Q` can be any query, including tuples, [`Or`], and other `Not`s. For
+/// instance, `Not<(With<&A>, With<&B>)>` matches entities which do not have
+/// both `A` and `B`, but may have either one alone. `Not` does not access
+/// the data of `Q`.
 pub struct Not<Q>(PhantomData<fn() -> Q>);
 
 impl<Q> Not<Q> {
@@ -608,7 +1053,12 @@ unsafe impl<Q: Query> ReadOnlyQuery for With<Q> {}
 /// A [`Query`] which returns a boolean indicating whether the query `Q`
 /// matches.
 ///
-/// Like [`With`], `Has` does not provide access to the data returned by `Q`.
+/// `Has<Q>` matches every entity and does not narrow the archetypes matched by
+/// the queries it's combined with. Like [`With`], it does not provide access
+/// to the data returned by `Q`, so the item is a plain `bool`.
+///
+/// In a struct deriving [`Query`], a `Has<Q>` field holds the boolean
+/// wrapped in `Has`, which can be read with [`get`](Self::get).
 pub struct Has<Q> {
     has: bool,
     _marker: PhantomData<fn() -> Q>,
@@ -629,6 +1079,12 @@ impl<Q> Has<Q> {
     }
 }
 
+impl<Q> From<bool> for Has<Q> {
+    fn from(has: bool) -> Self {
+        Self::new(has)
+    }
+}
+
 impl<Q> Clone for Has<Q> {
     fn clone(&self) -> Self {
         *self
@@ -639,10 +1095,7 @@ impl<Q> Copy for Has<Q> {}
 
 impl<Q> Default for Has<Q> {
     fn default() -> Self {
-        Self {
-            has: false,
-            _marker: PhantomData,
-        }
+        Self::new(false)
     }
 }
 
@@ -653,7 +1106,7 @@ impl<Q> fmt::Debug for Has<Q> {
 }
 
 unsafe impl<Q: Query> Query for Has<Q> {
-    type Item<'a> = Self;
+    type Item<'a> = bool;
 
     type ArchState = bool;
 
@@ -677,41 +1130,895 @@ unsafe impl<Q: Query> Query for Has<Q> {
     }
 
     unsafe fn get<'a>(state: &Self::ArchState, _row: ArchetypeRow) -> Self::Item<'a> {
-        Self::new(*state)
+        *state
     }
 }
 
 unsafe impl<Q: Query> ReadOnlyQuery for Has<Q> {}
 
-/// Returns the `EntityId` of the matched entity.
-unsafe impl Query for EntityId {
-    type Item<'a> = Self;
+/// A [`Query`] which can also be fetched once per archetype instead of once
+/// per entity.
+///
+/// Column queries are used with [`Fetcher::iter_columns`],
+/// [`Fetcher::iter_columns_mut`] and [`Fetcher::iter_archetype_slices`], which
+/// yield one [`Column`] for every matching archetype. `&C` and `&mut C` yield
+/// `&[C]` and `&mut [C]`, so the columns of an archetype line up row by row.
+/// Column queries can be combined in tuples with each other and with filters
+/// like [`With`] and [`Not`], but not with queries like `Option<&C>` which
+/// don't fetch the same data for every row.
+///
+/// ```
+/// # use evenio::prelude::*;
+/// # use evenio::query::{ColumnMut, ColumnRef};
+/// #
+/// # #[derive(Event)] struct E;
+/// #
+/// #[derive(Component)]
+/// struct Pos(f32);
+///
+/// #[derive(Component)]
+/// struct Vel(f32);
+///
+/// # let mut world = World::new();
+/// world.add_handler(
+///     |_: Receiver<E>, mut f: Fetcher<(ColumnMut<Pos>, ColumnRef<Vel>)>| {
+///         for (pos, vel) in f.iter_columns_mut() {
+///             for (p, v) in pos.iter_mut().zip(vel) {
+///                 p.0 += v.0;
+///             }
+///         }
+///     },
+/// );
+/// ```
+///
+/// Queries which aren't column queries do not compile.
+///
+/// ```compile_fail
+/// # use evenio::prelude::*;
+/// # use evenio::query::ColumnRef;
+/// #
+/// # #[derive(Event)] struct E;
+/// # #[derive(Component)] struct Pos(f32);
+/// # #[derive(Component)] struct Vel(f32);
+/// #
+/// # let mut world = World::new();
+/// world.add_handler(|_: Receiver<E>, f: Fetcher<(ColumnRef<Pos>, Option<&Vel>)>| {
+///     for _ in f.iter_columns() {}
+/// });
+/// ```
+///
+/// [`Fetcher::iter_columns`]: crate::fetch::Fetcher::iter_columns
+/// [`Fetcher::iter_columns_mut`]: crate::fetch::Fetcher::iter_columns_mut
+/// [`Fetcher::iter_archetype_slices`]: crate::fetch::Fetcher::iter_archetype_slices
+/// [`Column`]: ColumnQuery::Column
+///
+/// # Safety
+///
+/// [`get_column`](ColumnQuery::get_column) must only access data that
+/// [`Query::get`] is permitted to access for rows `0..len`.
+pub unsafe trait ColumnQuery: Query {
+    /// The item returned once per matching archetype.
+    type Column<'a>;
 
-    type ArchState = ColumnPtr<EntityId>;
+    /// Returns the column item for an archetype with `len` entities.
+    ///
+    /// # Safety
+    ///
+    /// `state` must be the archetype state of an archetype containing exactly
+    /// `len` entities. The caller must ensure no aliasing occurs.
+    unsafe fn get_column<'a>(state: &Self::ArchState, len: u32) -> Self::Column<'a>;
+}
 
-    type State = ();
+unsafe impl<C: Component> ColumnQuery for &'_ C {
+    type Column<'a> = &'a [C];
 
-    fn init(
-        _world: &mut World,
-        _config: &mut Config,
-    ) -> Result<(ComponentAccessExpr, Self::State), InitError> {
-        Ok((ComponentAccessExpr::new(true), ()))
+    unsafe fn get_column<'a>(state: &Self::ArchState, len: u32) -> Self::Column<'a> {
+        // For zero-sized components the pointer is dangling but aligned, which
+        // is valid for a slice of any length.
+        slice::from_raw_parts(state.0.as_ptr().cast_const(), len as usize)
     }
+}
 
-    fn new_state(_world: &mut World) -> Self::State {}
-
-    fn new_arch_state(arch: &Archetype, (): &mut Self::State) -> Option<Self::ArchState> {
-        Some(ColumnPtr(unsafe {
-            NonNull::new(arch.entity_ids().as_ptr().cast_mut()).unwrap_debug_checked()
-        }))
-    }
+unsafe impl<C: Component> ColumnQuery for &'_ mut C {
+    type Column<'a> = &'a mut [C];
 
-    unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::Item<'a> {
-        *state.0.as_ptr().add(row.0 as usize)
+    /// Records a write in the change tick of every component in the column.
+    unsafe fn get_column<'a>((data, ticks, now): &Self::ArchState, len: u32) -> Self::Column<'a> {
+        slice::from_raw_parts_mut(ticks.0.as_ptr(), len as usize).fill(*now.0.as_ptr());
+        slice::from_raw_parts_mut(data.0.as_ptr(), len as usize)
     }
 }
 
-unsafe impl ReadOnlyQuery for EntityId {}
+/// A [`ColumnQuery`] which returns the component `C` of every entity in an
+/// archetype as `&[C]`.
+///
+/// When fetched per entity, this behaves like `&C`.
+pub struct ColumnRef<C>(PhantomData<fn() -> C>);
+
+impl<C> fmt::Debug for ColumnRef<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ColumnRef<{}>", any::type_name::<C>())
+    }
+}
+
+unsafe impl<C: Component> Query for ColumnRef<C> {
+    type Item<'a> = &'a C;
+
+    type ArchState = ColumnPtr<C>;
+
+    type State = ComponentIdx;
+
+    fn init(
+        world: &mut World,
+        config: &mut Config,
+    ) -> Result<(ComponentAccessExpr, Self::State), InitError> {
+        <&C>::init(world, config)
+    }
+
+    fn new_state(world: &mut World) -> Self::State {
+        <&C>::new_state(world)
+    }
+
+    fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
+        <&C>::new_arch_state(arch, state)
+    }
+
+    unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::Item<'a> {
+        <&C>::get(state, row)
+    }
+}
+
+unsafe impl<C: Component> ReadOnlyQuery for ColumnRef<C> {}
+
+unsafe impl<C: Component> ColumnQuery for ColumnRef<C> {
+    type Column<'a> = &'a [C];
+
+    unsafe fn get_column<'a>(state: &Self::ArchState, len: u32) -> Self::Column<'a> {
+        <&C>::get_column(state, len)
+    }
+}
+
+/// A [`ColumnQuery`] which returns the component `C` of every entity in an
+/// archetype as `&mut [C]`. Fetching a column records a write in the change
+/// tick of every component in it.
+///
+/// When fetched per entity, this behaves like `&mut C`.
+pub struct ColumnMut<C>(PhantomData<fn() -> C>);
+
+impl<C> fmt::Debug for ColumnMut<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ColumnMut<{}>", any::type_name::<C>())
+    }
+}
+
+unsafe impl<C: Component> Query for ColumnMut<C> {
+    type Item<'a> = &'a mut C;
+
+    type ArchState = (ColumnPtr<C>, ColumnPtr<u64>, ColumnPtr<u64>);
+
+    type State = ComponentIdx;
+
+    fn init(
+        world: &mut World,
+        config: &mut Config,
+    ) -> Result<(ComponentAccessExpr, Self::State), InitError> {
+        <&mut C>::init(world, config)
+    }
+
+    fn new_state(world: &mut World) -> Self::State {
+        <&mut C>::new_state(world)
+    }
+
+    fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
+        <&mut C>::new_arch_state(arch, state)
+    }
+
+    unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::Item<'a> {
+        <&mut C>::get(state, row)
+    }
+}
+
+unsafe impl<C: Component> ColumnQuery for ColumnMut<C> {
+    type Column<'a> = &'a mut [C];
+
+    unsafe fn get_column<'a>(state: &Self::ArchState, len: u32) -> Self::Column<'a> {
+        <&mut C>::get_column(state, len)
+    }
+}
+
+unsafe impl ColumnQuery for EntityId {
+    type Column<'a> = &'a [EntityId];
+
+    unsafe fn get_column<'a>(state: &Self::ArchState, len: u32) -> Self::Column<'a> {
+        slice::from_raw_parts(state.0.as_ptr().cast_const(), len as usize)
+    }
+}
+
+unsafe impl<Q: Query> ColumnQuery for With<Q> {
+    type Column<'a> = Self;
+
+    unsafe fn get_column<'a>((): &Self::ArchState, _len: u32) -> Self::Column<'a> {
+        With::new()
+    }
+}
+
+unsafe impl<Q: Query> ColumnQuery for Not<Q> {
+    type Column<'a> = Self;
+
+    unsafe fn get_column<'a>((): &Self::ArchState, _len: u32) -> Self::Column<'a> {
+        Not::new()
+    }
+}
+
+macro_rules! impl_column_query_tuple {
+    ($(($Q:ident, $q:ident)),*) => {
+        #[allow(unused_variables, clippy::unused_unit)]
+        unsafe impl<$($Q: ColumnQuery),*> ColumnQuery for ($($Q,)*) {
+            type Column<'a> = ($($Q::Column<'a>,)*);
+
+            unsafe fn get_column<'a>(($($q,)*): &Self::ArchState, len: u32) -> Self::Column<'a> {
+                (
+                    $(
+                        $Q::get_column($q, len),
+                    )*
+                )
+            }
+        }
+    }
+}
+
+all_tuples!(impl_column_query_tuple, 0, 12, Q, q);
+
+/// A [`ReadOnlyQuery`] whose items can be copied out of the world, used by
+/// [`World::copy_query`].
+///
+/// Snapshot queries are implemented for `&C` where `C` is [`Copy`],
+/// [`EntityId`], and `Option<Q>`, [`Has<Q>`], [`With<Q>`], [`Not<Q>`], and
+/// tuples of snapshot queries. A query made of a single `&C` or `EntityId` is
+/// copied with one [`ptr::copy_nonoverlapping`] per archetype. Other queries
+/// are copied row by row.
+///
+/// [`World::copy_query`]: crate::world::World::copy_query
+///
+/// # Safety
+///
+/// [`get_owned`](SnapshotQuery::get_owned) and
+/// [`extend_owned`](SnapshotQuery::extend_owned) must only read data that
+/// [`Query::get`] is permitted to access.
+pub unsafe trait SnapshotQuery: ReadOnlyQuery {
+    /// The owned copy of a query item.
+    type Owned;
+
+    /// Returns a new [`Query::State`] without registering anything in the
+    /// world. Components which aren't in the world must not match any
+    /// archetype.
+    fn snapshot_state(world: &World) -> Self::State;
+
+    /// Returns an owned copy of the item in the given row.
+    ///
+    /// # Safety
+    ///
+    /// Same as [`Query::get`].
+    unsafe fn get_owned(state: &Self::ArchState, row: ArchetypeRow) -> Self::Owned;
+
+    /// Appends owned copies of the items in rows `0..len` to `out`.
+    ///
+    /// # Safety
+    ///
+    /// `state` must be the archetype state of an archetype containing exactly
+    /// `len` entities.
+    unsafe fn extend_owned(state: &Self::ArchState, len: u32, out: &mut Vec<Self::Owned>) {
+        out.reserve(len as usize);
+
+        for row in 0..len {
+            out.push(Self::get_owned(state, ArchetypeRow(row)));
+        }
+    }
+}
+
+/// Appends the first `len` elements of `column` to `out` with a single copy.
+unsafe fn copy_column<T: Copy>(column: &ColumnPtr<T>, len: u32, out: &mut Vec<T>) {
+    let len = len as usize;
+    out.reserve(len);
+
+    // For zero-sized types both pointers are dangling but aligned, which is
+    // valid for a copy of any length.
+    ptr::copy_nonoverlapping(
+        column.0.as_ptr().cast_const(),
+        out.as_mut_ptr().add(out.len()),
+        len,
+    );
+    out.set_len(out.len() + len);
+}
+
+/// Returns the index of the component `C`, or an index no archetype contains
+/// if `C` isn't in the world.
+fn component_idx_of<C: Component>(world: &World) -> ComponentIdx {
+    world
+        .components()
+        .get_by_type_id(any::TypeId::of::<C>())
+        .map_or(ComponentIdx::MAX, |info| info.id().index())
+}
+
+unsafe impl<C: Component + Copy> SnapshotQuery for &'_ C {
+    type Owned = C;
+
+    fn snapshot_state(world: &World) -> Self::State {
+        component_idx_of::<C>(world)
+    }
+
+    unsafe fn get_owned(state: &Self::ArchState, row: ArchetypeRow) -> Self::Owned {
+        *state.0.as_ptr().add(row.0 as usize)
+    }
+
+    unsafe fn extend_owned(state: &Self::ArchState, len: u32, out: &mut Vec<Self::Owned>) {
+        copy_column(state, len, out)
+    }
+}
+
+unsafe impl SnapshotQuery for EntityId {
+    type Owned = Self;
+
+    fn snapshot_state(_world: &World) -> Self::State {}
+
+    unsafe fn get_owned(state: &Self::ArchState, row: ArchetypeRow) -> Self::Owned {
+        Self::get(state, row)
+    }
+
+    unsafe fn extend_owned(state: &Self::ArchState, len: u32, out: &mut Vec<Self::Owned>) {
+        copy_column(state, len, out)
+    }
+}
+
+unsafe impl<Q: SnapshotQuery> SnapshotQuery for Option<Q> {
+    type Owned = Option<Q::Owned>;
+
+    fn snapshot_state(world: &World) -> Self::State {
+        Q::snapshot_state(world)
+    }
+
+    unsafe fn get_owned(state: &Self::ArchState, row: ArchetypeRow) -> Self::Owned {
+        state.as_ref().map(|f| Q::get_owned(f, row))
+    }
+}
+
+unsafe impl<Q: SnapshotQuery> SnapshotQuery for Has<Q> {
+    type Owned = bool;
+
+    fn snapshot_state(world: &World) -> Self::State {
+        Q::snapshot_state(world)
+    }
+
+    unsafe fn get_owned(state: &Self::ArchState, row: ArchetypeRow) -> Self::Owned {
+        <Self as Query>::get(state, row)
+    }
+}
+
+unsafe impl<Q: SnapshotQuery> SnapshotQuery for With<Q> {
+    type Owned = Self;
+
+    fn snapshot_state(world: &World) -> Self::State {
+        Q::snapshot_state(world)
+    }
+
+    unsafe fn get_owned(state: &Self::ArchState, row: ArchetypeRow) -> Self::Owned {
+        Self::get(state, row)
+    }
+}
+
+unsafe impl<Q: SnapshotQuery> SnapshotQuery for Not<Q> {
+    type Owned = Self;
+
+    fn snapshot_state(world: &World) -> Self::State {
+        Q::snapshot_state(world)
+    }
+
+    unsafe fn get_owned(state: &Self::ArchState, row: ArchetypeRow) -> Self::Owned {
+        Self::get(state, row)
+    }
+}
+
+macro_rules! impl_snapshot_query_tuple {
+    ($(($Q:ident, $q:ident)),*) => {
+        #[allow(unused_variables, clippy::unused_unit)]
+        unsafe impl<$($Q: SnapshotQuery),*> SnapshotQuery for ($($Q,)*) {
+            type Owned = ($($Q::Owned,)*);
+
+            fn snapshot_state(world: &World) -> Self::State {
+                ($($Q::snapshot_state(world),)*)
+            }
+
+            unsafe fn get_owned(($($q,)*): &Self::ArchState, row: ArchetypeRow) -> Self::Owned {
+                (
+                    $(
+                        $Q::get_owned($q, row),
+                    )*
+                )
+            }
+        }
+    }
+}
+
+all_tuples!(impl_snapshot_query_tuple, 0, 12, Q, q);
+
+/// A query for a component chosen at runtime, such as a component added with
+/// [`World::add_component_with_descriptor`]. The item is a pointer to the
+/// bytes of the component.
+///
+/// The component is given to the handler with [`IntoHandler::with_dynamic`].
+/// If a handler has several `Dynamic` queries, they take the components in
+/// the order they were given. Like `&C`, the query matches entities with the
+/// component and accesses it immutably.
+///
+/// The pointer is aligned for the component and points to
+/// [`ComponentInfo::size`] bytes. It must only be read from, and the bytes
+/// may include uninitialized padding, which is why a pointer is returned
+/// instead of a `&[u8]`.
+///
+/// `Dynamic` can only be used by handlers. Creating its state without a
+/// handler, e.g. with [`World::subscribe`], panics.
+///
+/// [`World::add_component_with_descriptor`]: crate::world::World::add_component_with_descriptor
+/// [`World::subscribe`]: crate::world::World::subscribe
+/// [`IntoHandler::with_dynamic`]: crate::handler::IntoHandler::with_dynamic
+/// [`ComponentInfo::size`]: crate::component::ComponentInfo::size
+///
+/// # Examples
+///
+/// ```
+/// use evenio::prelude::*;
+/// use evenio::query::Dynamic;
+///
+/// #[derive(Component)]
+/// struct Health(u32);
+///
+/// #[derive(Event)]
+/// struct E;
+///
+/// let mut world = World::new();
+///
+/// let e = world.spawn();
+/// world.insert(e, Health(100));
+///
+/// let component = world.add_component::<Health>();
+///
+/// world.add_handler(
+///     (|_: Receiver<E>, f: Fetcher<Dynamic>| {
+///         for bytes in f {
+///             assert_eq!(bytes.len(), 4);
+///             assert_eq!(unsafe { bytes.cast::<u32>().read() }, 100);
+///         }
+///     })
+///     .with_dynamic(component),
+/// );
+///
+/// world.send(E);
+/// ```
+#[derive(Debug)]
+pub enum Dynamic {}
+
+unsafe impl Query for Dynamic {
+    type Item<'a> = NonNull<[u8]>;
+
+    /// Pointer to the column, the size of the component, and the stride
+    /// between values in the column.
+    type ArchState = (ColumnPtr<u8>, usize, usize);
+
+    /// The component index, the size of the component, and the stride between
+    /// values in a column.
+    type State = (ComponentIdx, usize, usize);
+
+    fn init(
+        world: &mut World,
+        config: &mut Config,
+    ) -> Result<(ComponentAccessExpr, Self::State), InitError> {
+        if config.dynamic_components.is_empty() {
+            return Err(InitError(
+                "query `Dynamic` has no component. Add one with `IntoHandler::with_dynamic`".into(),
+            ));
+        }
+
+        let id = config.dynamic_components.remove(0);
+
+        let Some(info) = world.components().get(id) else {
+            return Err(InitError(
+                format!("component {id:?} of query `Dynamic` does not exist").into(),
+            ));
+        };
+
+        let idx = id.index();
+        let state = (idx, info.size(), info.layout().size());
+        let expr = ComponentAccessExpr::with(idx, Access::Read);
+        config.referenced_components.insert(idx);
+
+        Ok((expr, state))
+    }
+
+    #[track_caller]
+    fn new_state(_world: &mut World) -> Self::State {
+        panic!("query `Dynamic` can only be used by handlers (see `IntoHandler::with_dynamic`)")
+    }
+
+    fn new_arch_state(
+        arch: &Archetype,
+        &mut (idx, size, stride): &mut Self::State,
+    ) -> Option<Self::ArchState> {
+        arch.column_of(idx)
+            .map(|c| (ColumnPtr(c.data()), size, stride))
+    }
+
+    unsafe fn get<'a>(
+        &(column, size, stride): &Self::ArchState,
+        row: ArchetypeRow,
+    ) -> Self::Item<'a> {
+        let data = NonNull::new_unchecked(column.0.as_ptr().add(row.0 as usize * stride));
+        NonNull::slice_from_raw_parts(data, size)
+    }
+}
+
+unsafe impl ReadOnlyQuery for Dynamic {}
+
+/// Returns the `EntityId` of the matched entity.
+unsafe impl Query for EntityId {
+    type Item<'a> = Self;
+
+    type ArchState = ColumnPtr<EntityId>;
+
+    type State = ();
+
+    fn init(
+        _world: &mut World,
+        _config: &mut Config,
+    ) -> Result<(ComponentAccessExpr, Self::State), InitError> {
+        Ok((ComponentAccessExpr::new(true), ()))
+    }
+
+    fn new_state(_world: &mut World) -> Self::State {}
+
+    fn new_arch_state(arch: &Archetype, (): &mut Self::State) -> Option<Self::ArchState> {
+        Some(ColumnPtr(unsafe {
+            NonNull::new(arch.entity_ids().as_ptr().cast_mut()).unwrap_debug_checked()
+        }))
+    }
+
+    unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::Item<'a> {
+        *state.0.as_ptr().add(row.0 as usize)
+    }
+}
+
+unsafe impl ReadOnlyQuery for EntityId {}
+
+/// A [`Query`] which returns the spawn sequence number of the matched entity.
+///
+/// Every entity spawned in a world is given a sequence number one greater than
+/// the entity spawned before it, including entities spawned with
+/// [`Sender::spawn`](crate::event::Sender::spawn). Unlike [`EntityId`]s,
+/// sequence numbers are never reused, so sorting by `SpawnSeq` gives a stable
+/// ordering of entities by age.
+///
+/// ```
+/// # use evenio::prelude::*;
+/// # use evenio::query::SpawnSeq;
+/// #
+/// # #[derive(Event)] struct E;
+/// #
+/// # let mut world = World::new();
+/// world.add_handler(|_: Receiver<E>, f: Fetcher<(EntityId, SpawnSeq)>| {
+///     let mut oldest_first: Vec<_> = f.iter().collect();
+///     oldest_first.sort_by_key(|&(_, seq)| seq);
+/// });
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct SpawnSeq(pub u64);
+
+unsafe impl Query for SpawnSeq {
+    type Item<'a> = Self;
+
+    type ArchState = ColumnPtr<u64>;
+
+    type State = ();
+
+    fn init(
+        _world: &mut World,
+        _config: &mut Config,
+    ) -> Result<(ComponentAccessExpr, Self::State), InitError> {
+        Ok((ComponentAccessExpr::new(true), ()))
+    }
+
+    fn new_state(_world: &mut World) -> Self::State {}
+
+    fn new_arch_state(arch: &Archetype, (): &mut Self::State) -> Option<Self::ArchState> {
+        Some(ColumnPtr(unsafe {
+            NonNull::new(arch.spawn_seqs().as_ptr().cast_mut()).unwrap_debug_checked()
+        }))
+    }
+
+    unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::Item<'a> {
+        SpawnSeq(*state.0.as_ptr().add(row.0 as usize))
+    }
+}
+
+unsafe impl ReadOnlyQuery for SpawnSeq {}
+
+/// A filter matching entities whose component `C` was last written at a
+/// change tick in the inclusive range `from..=to`. See [`World::change_tick`]
+/// for when ticks advance and which writes are recorded.
+///
+/// Unlike the other filters in this module, the range is chosen at runtime,
+/// so `ChangedBetween` is not a [`Query`]. Pass it to
+/// [`Fetcher::iter_changed_between`] instead.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::prelude::*;
+/// use evenio::query::ChangedBetween;
+///
+/// #[derive(Component)]
+/// struct Pos(f32);
+///
+/// #[derive(Event)]
+/// struct Rewind {
+///     from: u64,
+///     to: u64,
+/// }
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|r: Receiver<Rewind>, f: Fetcher<EntityId>| {
+///     let window = ChangedBetween::<Pos>::new(r.event.from, r.event.to);
+///
+///     for e in f.iter_changed_between(window) {
+///         println!("{e:?} moved");
+///     }
+/// });
+/// ```
+///
+/// [`Fetcher::iter_changed_between`]: crate::fetch::Fetcher::iter_changed_between
+pub struct ChangedBetween<C> {
+    /// The first change tick in the range.
+    pub from: u64,
+    /// The last change tick in the range.
+    pub to: u64,
+    _marker: PhantomData<fn() -> C>,
+}
+
+impl<C> ChangedBetween<C> {
+    /// Creates a new filter for the inclusive range `from..=to`.
+    pub const fn new(from: u64, to: u64) -> Self {
+        Self {
+            from,
+            to,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns `true` if `tick` is in the range.
+    pub const fn contains(&self, tick: u64) -> bool {
+        self.from <= tick && tick <= self.to
+    }
+}
+
+impl<C> Clone for ChangedBetween<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for ChangedBetween<C> {}
+
+impl<C> fmt::Debug for ChangedBetween<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChangedBetween")
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .finish()
+    }
+}
+
+/// A [`Query`] filter matching entities whose component `C` was added since
+/// the handler last ran.
+///
+/// A component counts as added when it is inserted on an entity which
+/// didn't have it. On the first run of a handler, every component counts as
+/// added, including after the handler is removed and added again. See
+/// [`Changed`] for the queries which can contain this filter.
+pub struct Added<C>(PhantomData<fn() -> C>);
+
+impl<C> Added<C> {
+    /// Creates a new `Added` filter.
+    pub const fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<C> Clone for Added<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for Added<C> {}
+
+impl<C> Default for Added<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> fmt::Debug for Added<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Added<{}>", any::type_name::<C>())
+    }
+}
+
+unsafe impl<C: Component> Query for Added<C> {
+    type Item<'a> = Self;
+
+    /// Pointer to the added ticks.
+    type ArchState = ColumnPtr<u64>;
+
+    type State = ComponentIdx;
+
+    fn init(
+        world: &mut World,
+        config: &mut Config,
+    ) -> Result<(ComponentAccessExpr, Self::State), InitError> {
+        init_tick_filter::<C>(world, config)
+    }
+
+    fn new_state(world: &mut World) -> Self::State {
+        <&C>::new_state(world)
+    }
+
+    fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
+        arch.column_of(*state).map(|c| ColumnPtr(c.added_ptr()))
+    }
+
+    unsafe fn get<'a>(_state: &Self::ArchState, _row: ArchetypeRow) -> Self::Item<'a> {
+        Self::new()
+    }
+
+    const FILTERS_ROWS: bool = true;
+
+    #[inline]
+    unsafe fn filter_row(state: &Self::ArchState, row: ArchetypeRow, last_run_tick: u64) -> bool {
+        *state.0.as_ptr().add(row.0 as usize) > last_run_tick
+    }
+}
+
+unsafe impl<C: Component> ReadOnlyQuery for Added<C> {}
+
+/// A [`Query`] filter matching entities whose component `C` was written
+/// since the handler last ran.
+///
+/// See [`World::change_tick`] for which writes are recorded. Fetching `C`
+/// with `&mut C` always counts as a write, while [`Mut`] only counts
+/// mutable dereferences. A handler doesn't see its own writes on its next
+/// run. On the first run of a handler, every component counts as changed,
+/// including after the handler is removed and added again.
+///
+/// Unlike other filters, `Added` and `Changed` match individual entities
+/// rather than whole archetypes. They can be used directly, in tuples, in
+/// [`Or`], and in derived queries. Inside [`Option`], [`Xor`], [`Not`],
+/// [`With`] and [`Has`], only the presence of `C` is considered. They can't
+/// be used in the query of a [`Receiver`].
+///
+/// Like [`With`], the filters don't give access to `C`, so they can be
+/// combined with `&mut C` in the same query.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::prelude::*;
+/// use evenio::query::Changed;
+///
+/// #[derive(Component)]
+/// struct Health(u32);
+///
+/// #[derive(Event)]
+/// struct Check;
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|_: Receiver<Check>, f: Fetcher<(&Health, Changed<Health>)>| {
+///     for (health, _) in f {
+///         println!("health is now {}", health.0);
+///     }
+/// });
+///
+/// let e = world.spawn();
+/// world.insert(e, Health(100));
+///
+/// world.send(Check);
+/// world.get_mut::<Health>(e).unwrap().0 -= 10;
+/// world.send(Check);
+/// ```
+///
+/// [`World::change_tick`]: crate::world::World::change_tick
+/// [`Receiver`]: crate::event::Receiver
+pub struct Changed<C>(PhantomData<fn() -> C>);
+
+impl<C> Changed<C> {
+    /// Creates a new `Changed` filter.
+    pub const fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<C> Clone for Changed<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for Changed<C> {}
+
+impl<C> Default for Changed<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> fmt::Debug for Changed<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Changed<{}>", any::type_name::<C>())
+    }
+}
+
+unsafe impl<C: Component> Query for Changed<C> {
+    type Item<'a> = Self;
+
+    /// Pointer to the change ticks.
+    type ArchState = ColumnPtr<u64>;
+
+    type State = ComponentIdx;
+
+    fn init(
+        world: &mut World,
+        config: &mut Config,
+    ) -> Result<(ComponentAccessExpr, Self::State), InitError> {
+        init_tick_filter::<C>(world, config)
+    }
+
+    fn new_state(world: &mut World) -> Self::State {
+        <&C>::new_state(world)
+    }
+
+    fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
+        arch.column_of(*state).map(|c| ColumnPtr(c.ticks_ptr()))
+    }
+
+    unsafe fn get<'a>(_state: &Self::ArchState, _row: ArchetypeRow) -> Self::Item<'a> {
+        Self::new()
+    }
+
+    const FILTERS_ROWS: bool = true;
+
+    #[inline]
+    unsafe fn filter_row(state: &Self::ArchState, row: ArchetypeRow, last_run_tick: u64) -> bool {
+        // Read through a raw pointer, since the query may hold mutable
+        // references to the ticks of other rows.
+        *state.0.as_ptr().add(row.0 as usize) > last_run_tick
+    }
+}
+
+unsafe impl<C: Component> ReadOnlyQuery for Changed<C> {}
+
+/// Initializes [`Added`] or [`Changed`]. Like `With<&C>`, the filters match
+/// the archetypes with `C` without accessing it, but the ticks they read are
+/// recorded in [`Config::change_tick_reads`].
+fn init_tick_filter<C: Component>(
+    world: &mut World,
+    config: &mut Config,
+) -> Result<(ComponentAccessExpr, ComponentIdx), InitError> {
+    let (mut expr, idx) = <&C>::init(world, config)?;
+
+    expr.access.clear();
+    config.change_tick_reads.insert(idx);
+
+    Ok((expr, idx))
+}
 
 /// Like `()`, the `PhantomData<T>` query always succeeds.
 unsafe impl<T: ?Sized> Query for PhantomData<T> {
@@ -768,6 +2075,9 @@ unsafe impl<T> Sync for ColumnPtr<T> {}
 
 #[cfg(test)]
 mod tests {
+    use alloc::sync::Arc;
+    use std::sync::Mutex;
+
     use super::*;
     use crate::prelude::*;
 
@@ -825,6 +2135,322 @@ mod tests {
     t!(t14, true, (Option<&A>, &A, &A));
     t!(t15, false, (Xor<(&A, &B), (&B, &C)>, &mut B));
     t!(t16, true, (Xor<(&A, &B), (&B, &C)>, &B));
+    t!(t17, false, (WithDefault<D>, &mut D));
+    t!(t18, true, (WithDefault<D>, &D));
+    t!(t19, true, (WithDefault<D>, Not<&D>, &mut D));
+    t!(t20, true, (&A, Has<&A>));
+    t!(t21, false, (&A, Has<&A>, &mut A));
+    t!(t22, false, (Option<&mut A>, &A));
+    t!(t23, true, (Option<&mut A>, Option<&B>, &C));
+    t!(t24, false, (Option<(&C, &mut A)>, &A));
+    t!(t25, true, (&A, Option<&B>, Option<(&C, &B)>));
+    t!(t26, true, (Has<&mut A>, &mut A));
+    t!(t27, true, (&A, EntityId, &mut B, EntityId));
+
+    #[test]
+    fn duplicate_reads_coalesce() {
+        let mut world = World::new();
+
+        let (single, _) = <&A>::init(&mut world, &mut Config::new()).unwrap();
+        let (double, _) = <(&A, &A)>::init(&mut world, &mut Config::new()).unwrap();
+        let (has, _) = <(&A, Has<&A>)>::init(&mut world, &mut Config::new()).unwrap();
+
+        assert_eq!(single.access, double.access);
+        assert_eq!(format!("{:?}", single.expr), format!("{:?}", double.expr));
+        assert_eq!(single.access, has.access);
+        assert_eq!(format!("{:?}", single.expr), format!("{:?}", has.expr));
+    }
+
+    #[test]
+    fn mixed_borrow_error_names_component() {
+        let mut world = World::new();
+
+        let Err(InitError(msg)) = <(&A, &B, &mut A)>::init(&mut world, &mut Config::new()) else {
+            panic!("expected conflicting access")
+        };
+
+        assert!(msg.contains(&format!("on component(s) `{}`", any::type_name::<A>())));
+    }
+
+    #[test]
+    fn optional_components() {
+        #[derive(Component)]
+        struct Position(u32);
+
+        #[derive(Component)]
+        struct Velocity(u32);
+
+        #[derive(Component, Default)]
+        struct Visited(Vec<(EntityId, Option<u32>)>);
+
+        let mut world = World::new();
+
+        let moving = world.spawn();
+        world.insert(moving, Position(0));
+        world.insert(moving, Velocity(1));
+
+        let still = world.spawn();
+        world.insert(still, Position(0));
+
+        // Skipped because the non-optional part doesn't match.
+        let velocity_only = world.spawn();
+        world.insert(velocity_only, Velocity(1));
+        world.spawn();
+
+        let log = world.spawn();
+        world.insert(log, Visited::default());
+
+        world.add_handler(
+            |_: Receiver<E>,
+             f: Fetcher<(EntityId, &mut Position, Option<&mut Velocity>)>,
+             Single(visited): Single<&mut Visited>| {
+                for (e, pos, mut vel) in f {
+                    if let Some(vel) = &mut vel {
+                        vel.0 *= 2;
+                        pos.0 += vel.0;
+                    }
+
+                    visited.0.push((e, vel.map(|v| v.0)));
+                }
+
+                visited.0.sort_by_key(|&(e, _)| e);
+            },
+        );
+
+        world.send(E);
+
+        assert_eq!(
+            world.get::<Visited>(log).unwrap().0,
+            [(moving, Some(2)), (still, None)]
+        );
+        assert_eq!(world.get::<Position>(moving).unwrap().0, 2);
+        assert_eq!(world.get::<Velocity>(velocity_only).unwrap().0, 1);
+    }
+
+    #[derive(Component, Clone, Copy, PartialEq, Debug)]
+    struct D(u32);
+
+    impl Default for D {
+        fn default() -> Self {
+            Self(u32::MAX)
+        }
+    }
+
+    #[test]
+    fn nested_optional_queries() {
+        type Nested = (
+            &'static A,
+            Option<&'static B>,
+            Option<(&'static C, &'static D)>,
+        );
+
+        let mut world = World::new();
+
+        let a = world.spawn();
+        world.insert(a, A);
+
+        let abc = world.spawn();
+        world.insert(abc, A);
+        world.insert(abc, B);
+        world.insert(abc, C);
+
+        let acd = world.spawn();
+        world.insert(acd, A);
+        world.insert(acd, C);
+        world.insert(acd, D(3));
+
+        let bcd = world.spawn();
+        world.insert(bcd, B);
+        world.insert(bcd, C);
+        world.insert(bcd, D(4));
+
+        world.add_handler(move |_: Receiver<E>, f: Fetcher<Nested>| {
+            assert!(matches!(f.get(a), Ok((_, None, None))));
+            assert!(matches!(f.get(abc), Ok((_, Some(_), None))));
+            assert!(matches!(f.get(acd), Ok((_, None, Some((_, D(3)))))));
+            assert!(f.get(bcd).is_err());
+            assert_eq!(f.iter().count(), 3);
+        });
+
+        world.send(E);
+    }
+
+    #[test]
+    fn with_default() {
+        let mut world = World::new();
+
+        let e1 = world.spawn();
+        let e2 = world.spawn();
+        world.insert(e2, D(1));
+        world.insert(e2, A);
+
+        world.add_handler(move |_: Receiver<E>, f: Fetcher<WithDefault<D>>| {
+            assert_eq!(f.get(e1), Ok(D(u32::MAX)));
+            assert_eq!(f.get(e2), Ok(D(1)));
+        });
+
+        world.send(E);
+    }
+
+    #[test]
+    fn with_default_ref() {
+        #[derive(Component, PartialEq, Debug)]
+        struct Name(String);
+
+        // No default was registered.
+        assert!(!check_query::<WithDefaultRef<Name>>());
+
+        let mut world = World::new();
+
+        world.set_query_default(Name("default".into()));
+
+        let e1 = world.spawn();
+        let e2 = world.spawn();
+        world.insert(e2, Name("e2".into()));
+
+        world.add_handler(move |_: Receiver<E>, f: Fetcher<WithDefaultRef<Name>>| {
+            assert_eq!(f.get(e1).unwrap().0, "changed");
+            assert_eq!(f.get(e2).unwrap().0, "e2");
+        });
+
+        world.set_query_default(Name("changed".into()));
+
+        world.send(E);
+    }
+
+    #[derive(Component, Clone, Copy, PartialEq, Debug)]
+    #[component(double_buffer)]
+    struct Pos(u32);
+
+    #[derive(Component, Default)]
+    struct PrevLog(Vec<(EntityId, Pos)>);
+
+    /// Returns the previous value of `Pos` for all entities, sorted by entity.
+    fn read_previous(world: &mut World) -> Vec<(EntityId, Pos)> {
+        let log = world.spawn();
+        world.insert(log, PrevLog::default());
+
+        let handler = world.add_handler(
+            |_: Receiver<E>,
+             f: Fetcher<(EntityId, Previous<Pos>)>,
+             Single(log): Single<&mut PrevLog>| {
+                log.0 = f.iter().map(|(e, p)| (e, *p)).collect();
+                log.0.sort_by_key(|&(e, _)| e);
+            },
+        );
+
+        world.send(E);
+
+        let res = world.get_mut::<PrevLog>(log).unwrap().0.split_off(0);
+
+        world.remove_handler(handler);
+        world.despawn(log);
+
+        res
+    }
+
+    #[test]
+    fn previous_lags_one_swap() {
+        let mut world = World::new();
+
+        let e1 = world.spawn();
+        world.insert(e1, Pos(1));
+
+        // New entities read the value they were inserted with.
+        assert_eq!(read_previous(&mut world), [(e1, Pos(1))]);
+
+        world.insert(e1, Pos(2));
+        assert_eq!(read_previous(&mut world), [(e1, Pos(1))]);
+
+        world.swap_buffers::<Pos>();
+        world.get_mut::<Pos>(e1).unwrap().0 = 3;
+
+        let e2 = world.spawn();
+        world.insert(e2, Pos(10));
+
+        assert_eq!(read_previous(&mut world), [(e1, Pos(2)), (e2, Pos(10))]);
+
+        world.swap_buffers::<Pos>();
+        assert_eq!(read_previous(&mut world), [(e1, Pos(3)), (e2, Pos(10))]);
+    }
+
+    #[test]
+    fn previous_survives_transfer() {
+        let mut world = World::new();
+
+        let e1 = world.spawn();
+        let e2 = world.spawn();
+        world.insert(e1, Pos(1));
+        world.insert(e2, Pos(2));
+
+        world.swap_buffers::<Pos>();
+        world.insert(e1, Pos(10));
+        world.insert(e2, Pos(20));
+
+        // Move `e1` out of the archetype, swapping `e2` into its row.
+        world.insert(e1, A);
+        assert_eq!(read_previous(&mut world), [(e1, Pos(1)), (e2, Pos(2))]);
+
+        world.remove::<A>(e1);
+        assert_eq!(read_previous(&mut world), [(e1, Pos(1)), (e2, Pos(2))]);
+
+        world.swap_buffers::<Pos>();
+        assert_eq!(read_previous(&mut world), [(e1, Pos(10)), (e2, Pos(20))]);
+    }
+
+    #[test]
+    fn spawn_seq_follows_spawn_order() {
+        #[derive(Event)]
+        struct Collect;
+
+        let spawned = Arc::new(Mutex::new(vec![]));
+        let seqs = Arc::new(Mutex::new(vec![]));
+
+        let mut world = World::new();
+
+        // A pre-existing entity is moved around by the spawns below.
+        let first = world.spawn();
+        world.insert(first, A);
+
+        let spawned_clone = spawned.clone();
+        world.add_handler(
+            move |_: Receiver<E>, mut s: Sender<(Spawn, Insert<A>, Insert<B>, Despawn)>| {
+                let mut spawned = spawned_clone.lock().unwrap();
+
+                for i in 0..6 {
+                    let e = s.spawn();
+                    if i % 2 == 0 {
+                        s.insert(e, A);
+                    }
+                    if i % 3 == 0 {
+                        s.insert(e, B);
+                    }
+                    spawned.push(e);
+                }
+
+                s.despawn(first);
+            },
+        );
+
+        let seqs_clone = seqs.clone();
+        world.add_handler(
+            move |_: Receiver<Collect>, f: Fetcher<(EntityId, SpawnSeq)>| {
+                *seqs_clone.lock().unwrap() = f.iter().collect::<Vec<_>>();
+            },
+        );
+
+        world.send(E);
+        world.send(Collect);
+
+        let mut seqs = seqs.lock().unwrap().clone();
+        seqs.sort_by_key(|&(_, seq)| seq);
+
+        let by_seq: Vec<_> = seqs.iter().map(|&(e, _)| e).collect();
+        assert_eq!(by_seq, *spawned.lock().unwrap());
+
+        assert!(seqs.windows(2).all(|w| w[0].1 < w[1].1));
+        assert!(seqs.iter().all(|&(_, seq)| seq > SpawnSeq(0)));
+    }
 
     #[test]
     #[allow(dead_code)]
@@ -859,4 +2485,231 @@ mod tests {
 
         fn assert_read_only_query<Q: ReadOnlyQuery>() {}
     }
+
+    #[test]
+    fn derived_query_with_has() {
+        #[derive(Query)]
+        struct HasQuery {
+            id: EntityId,
+            _a: With<&'static A>,
+            b: Has<&'static B>,
+        }
+
+        #[derive(Query)]
+        struct HasTupleQuery(EntityId, Has<&'static B>);
+
+        let mut world = World::new();
+
+        let e1 = world.spawn();
+        world.insert(e1, A);
+
+        let e2 = world.spawn();
+        world.insert(e2, A);
+        world.insert(e2, B);
+
+        let got = Arc::new(Mutex::new(vec![]));
+        let got_cloned = got.clone();
+
+        world.add_handler(
+            move |_: Receiver<E>, f: Fetcher<HasQuery>, g: Fetcher<HasTupleQuery>| {
+                let mut got = got_cloned.lock().unwrap();
+
+                for HasQuery { id, b, .. } in &f {
+                    let HasTupleQuery(g_id, g_b) = g.get(id).unwrap();
+
+                    assert_eq!((g_id, g_b.get()), (id, b.get()));
+                    got.push((id, b.get()));
+                }
+            },
+        );
+
+        world.send(E);
+
+        let mut got = got.lock().unwrap().clone();
+        got.sort();
+
+        assert_eq!(got, [(e1, false), (e2, true)]);
+    }
+
+    /// Spawns one entity for every combination of `A`, `B`, and `C`, and
+    /// asserts that `Q` matches exactly the combinations accepted by
+    /// `expected`.
+    fn check_truth_table<Q: ReadOnlyQuery + 'static>(expected: fn(bool, bool, bool) -> bool) {
+        let mut world = World::new();
+
+        let mut want = vec![];
+
+        for bits in 0..8 {
+            let (a, b, c) = (bits & 1 != 0, bits & 2 != 0, bits & 4 != 0);
+
+            let e = world.spawn();
+
+            if a {
+                world.insert(e, A);
+            }
+            if b {
+                world.insert(e, B);
+            }
+            if c {
+                world.insert(e, C);
+            }
+
+            if expected(a, b, c) {
+                want.push(e);
+            }
+        }
+
+        let got = Arc::new(Mutex::new(vec![]));
+        let got_cloned = got.clone();
+
+        world.add_handler(move |_: Receiver<E>, f: Fetcher<(EntityId, Q)>| {
+            *got_cloned.lock().unwrap() = f.iter().map(|(e, _)| e).collect::<Vec<_>>();
+        });
+
+        world.send(E);
+
+        let mut got = got.lock().unwrap().clone();
+        got.sort();
+        want.sort();
+
+        assert_eq!(got, want);
+    }
+
+    #[test]
+    fn negated_sub_queries() {
+        check_truth_table::<Not<(With<&A>, With<&B>)>>(|a, b, _| !(a && b));
+        check_truth_table::<Not<Or<With<&A>, With<&B>>>>(|a, b, _| !(a || b));
+        check_truth_table::<Not<Not<(With<&A>, With<&B>)>>>(|a, b, _| a && b);
+        check_truth_table::<(Not<Xor<With<&A>, With<&B>>>, With<&C>)>(|a, b, c| a == b && c);
+        check_truth_table::<Not<(Not<With<&A>>, Or<With<&B>, Not<With<&C>>>)>>(|a, b, c| {
+            !(!a && (b || !c))
+        });
+    }
+
+    #[test]
+    fn has_does_not_narrow_matching() {
+        check_truth_table::<Has<&A>>(|_, _, _| true);
+        check_truth_table::<(With<&A>, Has<&B>)>(|a, _, _| a);
+        check_truth_table::<Or<Has<&A>, With<&B>>>(|_, _, _| true);
+        check_truth_table::<Xor<Has<&A>, With<&B>>>(|_, b, _| !b);
+        check_truth_table::<Not<Has<&A>>>(|_, _, _| false);
+    }
+
+    #[test]
+    fn entity_id_and_has_in_tuples() {
+        #[derive(Component)]
+        struct Count(u32);
+
+        let mut world = World::new();
+
+        let mut want = vec![];
+
+        for bits in 0..4 {
+            let e = world.spawn();
+            world.insert(e, A);
+            world.insert(e, Count(0));
+
+            if bits & 1 != 0 {
+                world.insert(e, C);
+            }
+
+            if bits & 2 != 0 {
+                world.insert(e, B);
+            }
+
+            want.push((e, bits & 1 != 0, bits & 2 != 0));
+        }
+
+        type Q = (
+            &'static A,
+            EntityId,
+            &'static mut Count,
+            Has<&'static C>,
+            Or<Has<&'static B>, &'static C>,
+        );
+
+        let got = Arc::new(Mutex::new(vec![]));
+        let got_cloned = got.clone();
+
+        world.add_handler(move |_: Receiver<E>, f: Fetcher<Q>| {
+            let mut got = got_cloned.lock().unwrap();
+
+            for (_, id, count, has_c, or) in f {
+                count.0 += 1;
+
+                // `Has` always matches, so the left side is always present.
+                let has_b = match or {
+                    Or::Left(has_b) | Or::Both(has_b, _) => has_b,
+                    Or::Right(_) => unreachable!(),
+                };
+
+                got.push((id, has_c, has_b));
+            }
+        });
+
+        world.send(E);
+
+        let mut got = got.lock().unwrap().clone();
+        got.sort();
+
+        assert_eq!(got, want);
+
+        for (e, _, _) in want {
+            assert_eq!(world.get::<Count>(e).unwrap().0, 1);
+        }
+    }
+
+    #[test]
+    fn mut_set_skips_identical_writes() {
+        #[derive(Component, PartialEq, Debug)]
+        #[component(skip_identical_writes)]
+        struct Hp(u32);
+
+        #[derive(Event)]
+        struct Set(u32);
+
+        #[derive(Event)]
+        struct ForceWrite(u32);
+
+        let mut world = World::new();
+
+        world.add_handler(|r: Receiver<Set>, f: Fetcher<Mut<Hp>>| {
+            for mut hp in f {
+                hp.set(Hp(r.event.0));
+            }
+        });
+
+        world.add_handler(|r: Receiver<ForceWrite>, f: Fetcher<Mut<Hp>>| {
+            for mut hp in f {
+                hp.force_write(Hp(r.event.0));
+            }
+        });
+
+        let e = world.spawn();
+        world.insert(e, Hp(10));
+
+        let hp = world.add_component::<Hp>().index();
+
+        let tick = |world: &World| {
+            let loc = world.entities().get(e).unwrap();
+            let arch = world.archetypes().get(loc.archetype).unwrap();
+            arch.column_of(hp).unwrap().change_ticks()[loc.row.0 as usize]
+        };
+
+        let inserted = tick(&world);
+
+        // Identical writes keep the old tick.
+        world.send(Set(10));
+        assert_eq!(tick(&world), inserted);
+
+        // Different writes advance it.
+        world.send(Set(5));
+        let written = tick(&world);
+        assert!(written > inserted);
+        assert_eq!(world.get::<Hp>(e), Some(&Hp(5)));
+
+        // `force_write` always advances it.
+        world.send(ForceWrite(5));
+        assert!(tick(&world) > written);
+    }
 }
@@ -0,0 +1,361 @@
+//! Caps on the number of entities with a component.
+//!
+//! See [`World::set_component_quota`] for more information.
+//!
+//! [`World::set_component_quota`]: crate::world::World::set_component_quota
+
+use alloc::collections::VecDeque;
+use core::fmt;
+use core::marker::PhantomData;
+
+use ahash::RandomState;
+
+use crate::component::Component;
+use crate::entity::EntityId;
+use crate::event::{Event, EventId, EventQueue};
+use crate::map::HashMap;
+
+/// What happens when a component is inserted on an entity while the number of
+/// entities with the component is at its quota. See
+/// [`World::set_component_quota`].
+///
+/// [`World::set_component_quota`]: crate::world::World::set_component_quota
+#[derive(Clone, Copy, Debug)]
+pub enum QuotaPolicy {
+    /// The insert is dropped. A [`QuotaExceeded`] event is sent.
+    RejectInsert,
+    /// The insert goes through and the entity which gained the component the
+    /// longest time ago is despawned. If no such entity is known, the insert
+    /// is rejected as with [`QuotaPolicy::RejectInsert`].
+    DespawnOldest,
+    /// The function is called with the entity the component is being inserted
+    /// on and decides what happens.
+    Callback(fn(EntityId) -> QuotaAction),
+}
+
+/// The decision made by a [`QuotaPolicy::Callback`].
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum QuotaAction {
+    /// Reject the insert. See [`QuotaPolicy::RejectInsert`].
+    Reject,
+    /// Despawn the oldest entity. See [`QuotaPolicy::DespawnOldest`].
+    DespawnOldest,
+    /// Let the insert go through and exceed the quota.
+    Allow,
+}
+
+/// Statistics of a component quota, returned by [`World::quota_stats`].
+///
+/// [`World::quota_stats`]: crate::world::World::quota_stats
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct QuotaStats {
+    /// The maximum number of entities with the component.
+    pub limit: u32,
+    /// Number of inserts rejected because of the quota.
+    pub rejected: u64,
+    /// Number of entities despawned to make room for new ones.
+    pub despawned: u64,
+}
+
+/// An [`Event`] sent when an insert of component `C` is rejected because of
+/// the component's quota. Contains the entity the component was being
+/// inserted on.
+pub struct QuotaExceeded<C>(pub EntityId, PhantomData<fn() -> C>);
+
+impl<C> QuotaExceeded<C> {
+    /// Creates a new instance.
+    pub const fn new(entity: EntityId) -> Self {
+        Self(entity, PhantomData)
+    }
+}
+
+impl<C> Clone for QuotaExceeded<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for QuotaExceeded<C> {}
+
+impl<C> fmt::Debug for QuotaExceeded<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("QuotaExceeded").field(&self.0).finish()
+    }
+}
+
+impl<C: Component> Event for QuotaExceeded<C> {}
+
+/// Pushes a [`QuotaExceeded`] event onto the event queue.
+type PushFn = unsafe fn(&mut EventQueue, EntityId, u32);
+
+/// The quota of a component, stored in its [`ComponentInfo`].
+///
+/// [`ComponentInfo`]: crate::component::ComponentInfo
+pub(crate) struct Quota {
+    pub(crate) limit: u32,
+    pub(crate) policy: QuotaPolicy,
+    pub(crate) rejected: u64,
+    pub(crate) despawned: u64,
+    pub(crate) exceeded: (EventId, PushFn),
+    /// Entities in the order they gained the component, along with a stamp
+    /// from `next_stamp`. Entries for entities which lost the component or
+    /// gained it again later are skipped and removed lazily.
+    order: VecDeque<(EntityId, u64)>,
+    /// The stamp of the most recent entry in `order` for every entity.
+    latest: HashMap<EntityId, u64>,
+    next_stamp: u64,
+}
+
+impl Quota {
+    pub(crate) fn new<C: Component>(limit: u32, policy: QuotaPolicy, exceeded: EventId) -> Self {
+        Self {
+            limit,
+            policy,
+            rejected: 0,
+            despawned: 0,
+            exceeded: (exceeded, |queue, entity, idx| unsafe {
+                queue.push_front(QuotaExceeded::<C>::new(entity), idx)
+            }),
+            order: VecDeque::new(),
+            latest: HashMap::with_hasher(RandomState::new()),
+            next_stamp: 0,
+        }
+    }
+
+    pub(crate) fn stats(&self) -> QuotaStats {
+        QuotaStats {
+            limit: self.limit,
+            rejected: self.rejected,
+            despawned: self.despawned,
+        }
+    }
+
+    /// Records that `entity` gained the component.
+    pub(crate) fn track(&mut self, entity: EntityId) {
+        let stamp = self.next_stamp;
+        self.next_stamp += 1;
+
+        self.latest.insert(entity, stamp);
+        self.order.push_back((entity, stamp));
+    }
+
+    /// Removes and returns the entity which gained the component the longest
+    /// time ago and still has it. `has_component` reports whether an entity
+    /// currently has the component.
+    pub(crate) fn pop_oldest(
+        &mut self,
+        mut has_component: impl FnMut(EntityId) -> bool,
+    ) -> Option<EntityId> {
+        while let Some((entity, stamp)) = self.order.pop_front() {
+            if self.latest.get(&entity) != Some(&stamp) {
+                // The entity gained the component again after this entry.
+                continue;
+            }
+
+            self.latest.remove(&entity);
+
+            if has_component(entity) {
+                return Some(entity);
+            }
+        }
+
+        None
+    }
+
+    /// Removes stale entries once they outnumber the `live` entities with the
+    /// component.
+    pub(crate) fn compact(&mut self, live: usize, mut has_component: impl FnMut(EntityId) -> bool) {
+        if self.order.len() <= live * 2 + 64 {
+            return;
+        }
+
+        let latest = &mut self.latest;
+
+        self.order.retain(|&(entity, stamp)| {
+            latest.get(&entity) == Some(&stamp) && has_component(entity)
+        });
+
+        latest.clear();
+        latest.extend(self.order.iter().copied());
+    }
+
+    #[cfg(test)]
+    fn tracked_len(&self) -> usize {
+        self.order.len()
+    }
+}
+
+impl fmt::Debug for Quota {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Quota")
+            .field("limit", &self.limit)
+            .field("policy", &self.policy)
+            .field("rejected", &self.rejected)
+            .field("despawned", &self.despawned)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Component)]
+    struct Projectile(u32);
+
+    #[derive(Component, Default)]
+    struct Rejected(Vec<EntityId>);
+
+    fn spawn_projectiles(world: &mut World, n: u32) -> Vec<EntityId> {
+        (0..n)
+            .map(|i| {
+                let e = world.spawn();
+                world.insert(e, Projectile(i));
+                e
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reject_insert() {
+        let mut world = World::new();
+
+        let log = world.spawn();
+        world.insert(log, Rejected::default());
+
+        world.add_handler(
+            |r: Receiver<QuotaExceeded<Projectile>>, Single(log): Single<&mut Rejected>| {
+                log.0.push(r.event.0)
+            },
+        );
+
+        world.set_component_quota::<Projectile>(2, QuotaPolicy::RejectInsert);
+
+        let es = spawn_projectiles(&mut world, 3);
+
+        assert_eq!(world.component_count::<Projectile>(), 2);
+        assert!(world.get::<Projectile>(es[2]).is_none());
+        assert_eq!(world.get::<Rejected>(log).unwrap().0, [es[2]]);
+
+        // Replacing a value is not limited.
+        world.insert(es[0], Projectile(100));
+        assert_eq!(world.get::<Projectile>(es[0]).unwrap().0, 100);
+
+        world.despawn(es[1]);
+        world.insert(es[2], Projectile(2));
+
+        assert_eq!(world.component_count::<Projectile>(), 2);
+        assert_eq!(
+            world.quota_stats::<Projectile>(),
+            Some(QuotaStats {
+                limit: 2,
+                rejected: 1,
+                despawned: 0
+            })
+        );
+    }
+
+    #[test]
+    fn despawn_oldest() {
+        let mut world = World::new();
+
+        let old = spawn_projectiles(&mut world, 2);
+
+        world.set_component_quota::<Projectile>(3, QuotaPolicy::DespawnOldest);
+
+        let new = spawn_projectiles(&mut world, 3);
+
+        assert_eq!(world.component_count::<Projectile>(), 3);
+        assert!(!world.entities().contains(old[0]));
+        assert!(!world.entities().contains(old[1]));
+        assert!(new.iter().all(|&e| world.entities().contains(e)));
+
+        // Gaining the component again makes the entity the newest.
+        world.remove::<Projectile>(new[0]);
+        world.insert(new[0], Projectile(0));
+
+        spawn_projectiles(&mut world, 1);
+
+        assert!(!world.entities().contains(new[1]));
+        assert!(world.entities().contains(new[0]));
+        assert_eq!(world.quota_stats::<Projectile>().unwrap().despawned, 3);
+    }
+
+    #[test]
+    fn despawn_oldest_under_churn() {
+        let mut world = World::new();
+
+        world.set_component_quota::<Projectile>(10, QuotaPolicy::DespawnOldest);
+
+        let mut alive: Vec<EntityId> = vec![];
+
+        for i in 0..2000 {
+            let e = world.spawn();
+            world.insert(e, Projectile(i));
+            alive.push(e);
+
+            // Churn through removals, reinserts, and despawns which leave
+            // stale entries behind.
+            match i % 4 {
+                0 => world.despawn(e),
+                1 => world.remove::<Projectile>(e),
+                2 => {
+                    world.remove::<Projectile>(e);
+                    world.insert(e, Projectile(i));
+                }
+                _ => {}
+            }
+
+            alive.retain(|&e| world.get::<Projectile>(e).is_some());
+
+            assert!(world.component_count::<Projectile>() <= 10);
+            assert_eq!(world.component_count::<Projectile>(), alive.len());
+        }
+
+        let info = world
+            .components()
+            .get_by_type_id(core::any::TypeId::of::<Projectile>())
+            .unwrap();
+
+        assert!(info.quota.as_ref().unwrap().tracked_len() <= 10 * 2 + 64 + 1);
+
+        // The newest survivors are kept.
+        let newest = world.spawn();
+        world.insert(newest, Projectile(0));
+
+        assert!(world.entities().contains(*alive.last().unwrap()));
+        assert!(!world.entities().contains(alive[0]));
+    }
+
+    #[test]
+    fn callback() {
+        let mut world = World::new();
+
+        world.set_component_quota::<Projectile>(1, QuotaPolicy::Callback(|_| QuotaAction::Allow));
+
+        let es = spawn_projectiles(&mut world, 3);
+
+        assert_eq!(world.component_count::<Projectile>(), 3);
+
+        world.set_component_quota::<Projectile>(
+            3,
+            QuotaPolicy::Callback(|_| QuotaAction::DespawnOldest),
+        );
+
+        spawn_projectiles(&mut world, 1);
+
+        assert_eq!(world.component_count::<Projectile>(), 3);
+        assert!(!world.entities().contains(es[0]));
+        assert_eq!(
+            world.quota_stats::<Projectile>(),
+            Some(QuotaStats {
+                limit: 3,
+                rejected: 0,
+                despawned: 1
+            })
+        );
+    }
+}
@@ -0,0 +1,265 @@
+//! Runtime descriptions of the fields of events and components.
+//!
+//! Deriving [`Event`] with `#[event(reflect_fields)]`, or [`Component`] with
+//! `#[component(reflect_fields)]`, records a [`FieldInfo`] for each top-level
+//! field of the type. The table is available from [`EventInfo::fields`] and
+//! [`ComponentInfo::fields`], and lets scripting layers and inspectors read
+//! and write fields by name without knowing the type at compile time.
+//!
+//! ```
+//! use evenio::prelude::*;
+//!
+//! #[derive(Event)]
+//! #[event(reflect_fields)]
+//! struct Deposit {
+//!     account: u32,
+//!     amount: u64,
+//! }
+//!
+//! let mut world = World::new();
+//! let id = world.add_event::<Deposit>();
+//!
+//! let fields = world.events().get(id).unwrap().fields();
+//!
+//! assert_eq!(fields[1].name(), "amount");
+//! assert_eq!(fields[1].type_id(), core::any::TypeId::of::<u64>());
+//! ```
+//!
+//! [`Event`]: crate::event::Event
+//! [`Component`]: crate::component::Component
+//! [`EventInfo::fields`]: crate::event::EventInfo::fields
+//! [`ComponentInfo::fields`]: crate::component::ComponentInfo::fields
+
+use alloc::borrow::Cow;
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+use core::alloc::Layout;
+use core::any::TypeId;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+
+/// Describes one field of an event or component type.
+#[derive(Clone, Debug)]
+pub struct FieldInfo {
+    name: Cow<'static, str>,
+    offset: usize,
+    type_id: TypeId,
+    layout: Layout,
+}
+
+impl FieldInfo {
+    /// Creates the description of a field of type `T` named `name`, found
+    /// `offset` bytes from the start of its containing type.
+    ///
+    /// # Safety
+    ///
+    /// The containing type must have a field of type `T` at `offset`.
+    pub unsafe fn new<T: 'static>(name: Cow<'static, str>, offset: usize) -> Self {
+        Self {
+            name,
+            offset,
+            type_id: TypeId::of::<T>(),
+            layout: Layout::new::<T>(),
+        }
+    }
+
+    /// Gets the name of the field. Fields of tuple structs are named by their
+    /// index.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Gets the offset of the field from the start of its containing type, in
+    /// bytes.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Gets the [`TypeId`] of the field.
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// Gets the [`Layout`] of the field.
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// Returns a reference to this field of the value at `base`.
+    ///
+    /// # Safety
+    ///
+    /// `base` must point to an initialized value of the type this field belongs
+    /// to, valid for reads for `'a`.
+    pub unsafe fn get<'a>(&'a self, base: NonNull<u8>) -> FieldRef<'a> {
+        FieldRef {
+            ptr: NonNull::new_unchecked(base.as_ptr().add(self.offset)),
+            info: self,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns a mutable reference to this field of the value at `base`.
+    ///
+    /// # Safety
+    ///
+    /// `base` must point to an initialized value of the type this field belongs
+    /// to, valid for reads and writes for `'a`, and must not be aliased.
+    pub unsafe fn get_mut<'a>(&'a self, base: NonNull<u8>) -> FieldMut<'a> {
+        FieldMut {
+            ptr: NonNull::new_unchecked(base.as_ptr().add(self.offset)),
+            info: self,
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Type-erased shared reference to a field. Returned by [`FieldInfo::get`].
+#[derive(Clone, Copy, Debug)]
+pub struct FieldRef<'a> {
+    ptr: NonNull<u8>,
+    info: &'a FieldInfo,
+    _marker: PhantomData<&'a u8>,
+}
+
+impl<'a> FieldRef<'a> {
+    /// Gets the [`FieldInfo`] of the field.
+    pub fn info(&self) -> &'a FieldInfo {
+        self.info
+    }
+
+    /// Returns a pointer to the field.
+    pub fn as_ptr(&self) -> NonNull<u8> {
+        self.ptr
+    }
+
+    /// Returns a reference to the field if it has type `T`.
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&'a T> {
+        (self.info.type_id == TypeId::of::<T>()).then(|| unsafe { self.ptr.cast::<T>().as_ref() })
+    }
+}
+
+/// Type-erased mutable reference to a field. Returned by
+/// [`FieldInfo::get_mut`].
+#[derive(Debug)]
+pub struct FieldMut<'a> {
+    ptr: NonNull<u8>,
+    info: &'a FieldInfo,
+    _marker: PhantomData<&'a mut u8>,
+}
+
+impl<'a> FieldMut<'a> {
+    /// Gets the [`FieldInfo`] of the field.
+    pub fn info(&self) -> &'a FieldInfo {
+        self.info
+    }
+
+    /// Returns a pointer to the field.
+    pub fn as_ptr(&self) -> NonNull<u8> {
+        self.ptr
+    }
+
+    /// Returns a reference to the field if it has type `T`.
+    pub fn downcast_ref<T: 'static>(&self) -> Option<&T> {
+        (self.info.type_id == TypeId::of::<T>()).then(|| unsafe { self.ptr.cast::<T>().as_ref() })
+    }
+
+    /// Returns a mutable reference to the field if it has type `T`.
+    pub fn downcast_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        (self.info.type_id == TypeId::of::<T>()).then(|| unsafe { self.ptr.cast::<T>().as_mut() })
+    }
+}
+
+/// Collects the fields described by a `reflect_fields` function.
+pub(crate) fn collect_fields(reflect: fn(&mut dyn FnMut(FieldInfo))) -> Vec<FieldInfo> {
+    let mut fields = vec![];
+    reflect(&mut |field| fields.push(field));
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use core::alloc::Layout;
+    use core::any::TypeId;
+    use core::mem::offset_of;
+    use core::ptr::NonNull;
+
+    use crate::event::{AnyEvent, Events};
+    use crate::prelude::*;
+
+    #[derive(Event)]
+    #[event(reflect_fields)]
+    struct Transfer {
+        #[event(target)]
+        from: EntityId,
+        amount: u64,
+        r#type: u8,
+    }
+
+    #[derive(Component)]
+    #[component(reflect_fields)]
+    struct Named(u16, String);
+
+    #[test]
+    fn field_tables() {
+        let mut world = World::new();
+
+        let event = world.add_event::<Transfer>();
+        let fields = world.events().get(event).unwrap().fields();
+
+        let names: Vec<_> = fields.iter().map(|f| f.name()).collect();
+        assert_eq!(names, ["from", "amount", "type"]);
+        assert_eq!(fields[1].offset(), offset_of!(Transfer, amount));
+        assert_eq!(fields[1].type_id(), TypeId::of::<u64>());
+
+        let component = world.add_component::<Named>();
+        let info = world.components().get(component).unwrap();
+
+        assert_eq!(info.fields().len(), 2);
+        assert_eq!(info.field("1").unwrap().offset(), offset_of!(Named, 1));
+        assert_eq!(info.field("1").unwrap().layout(), Layout::new::<String>());
+
+        // Types which aren't reflected have no fields.
+        #[derive(Event)]
+        struct Plain(#[allow(dead_code)] u32);
+
+        let plain = world.add_event::<Plain>();
+        assert!(world.events().get(plain).unwrap().fields().is_empty());
+    }
+
+    #[test]
+    fn read_and_write_erased_event() {
+        let mut world = World::new();
+
+        let e = world.spawn();
+
+        world.add_handler(|mut r: ReceiverMut<Transfer, ()>, events: &Events| {
+            let info = events.get_by_type_id(TypeId::of::<Transfer>()).unwrap();
+            let mut event = unsafe { AnyEvent::new(NonNull::from(&mut *r.event).cast(), info) };
+
+            let from = event.field("from").unwrap();
+            assert!(from.downcast_ref::<u64>().is_none());
+            assert_eq!(from.downcast_ref::<EntityId>(), Some(&r.event.from));
+
+            assert!(event.field("missing").is_none());
+
+            let mut amount = event.field_mut("amount").unwrap();
+            assert!(amount.downcast_mut::<u32>().is_none());
+            *amount.downcast_mut::<u64>().unwrap() += 5;
+        });
+
+        world.add_handler(move |r: Receiver<Transfer, ()>| {
+            assert_eq!(r.event.from, e);
+            assert_eq!(r.event.amount, 15);
+            assert_eq!(r.event.r#type, 2);
+        });
+
+        world.send(Transfer {
+            from: e,
+            amount: 10,
+            r#type: 2,
+        });
+    }
+}
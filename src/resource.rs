@@ -0,0 +1,461 @@
+//! Global data which isn't tied to any entity.
+//!
+//! See [`World::insert_resource`] for more information.
+
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, format, vec, vec::Vec};
+use core::any::{self, Any, TypeId};
+use core::fmt;
+use core::ops::{Deref, DerefMut};
+use core::panic::{RefUnwindSafe, UnwindSafe};
+
+use crate::access::Access;
+use crate::aliased_box::AliasedBox;
+use crate::archetype::Archetype;
+use crate::entity::EntityLocation;
+use crate::event::EventPtr;
+use crate::handler::{Config, HandlerInfo, HandlerParam, InitError};
+use crate::map::TypeIdMap;
+use crate::sparse::SparseIndex;
+use crate::world::{UnsafeWorldCell, World};
+
+/// Stores the resources of a [`World`], keyed by type.
+///
+/// Every resource type used by a handler or inserted into the world is given
+/// a [`ResourceIdx`], which stays the same after the resource is removed.
+pub struct Resources {
+    infos: Vec<ResourceInfo>,
+    by_type_id: TypeIdMap<ResourceIdx>,
+}
+
+struct ResourceInfo {
+    name: &'static str,
+    value: Option<AliasedBox<dyn Any + Send + Sync>>,
+}
+
+// Like component columns, resources are only accessed through the world.
+impl UnwindSafe for Resources {}
+impl RefUnwindSafe for Resources {}
+
+impl Resources {
+    pub(crate) fn new() -> Self {
+        Self {
+            infos: vec![],
+            by_type_id: TypeIdMap::default(),
+        }
+    }
+
+    /// Returns the index of the resource type `R`, assigning a new one if `R`
+    /// wasn't seen before.
+    pub(crate) fn register<R: Send + Sync + 'static>(&mut self) -> ResourceIdx {
+        *self.by_type_id.entry(TypeId::of::<R>()).or_insert_with(|| {
+            let idx = ResourceIdx::from_index(self.infos.len());
+
+            self.infos.push(ResourceInfo {
+                name: any::type_name::<R>(),
+                value: None,
+            });
+
+            idx
+        })
+    }
+
+    pub(crate) fn insert<R: Send + Sync + 'static>(&mut self, resource: R) -> Option<R> {
+        let idx = self.register::<R>();
+        let value: Box<dyn Any + Send + Sync> = Box::new(resource);
+
+        self.infos[idx.index()]
+            .value
+            .replace(value.into())
+            .map(downcast::<R>)
+    }
+
+    pub(crate) fn remove<R: Send + Sync + 'static>(&mut self) -> Option<R> {
+        let idx = self.idx::<R>()?;
+
+        self.infos[idx.index()].value.take().map(downcast::<R>)
+    }
+
+    /// Returns the index of the resource type `R`, if it was ever used.
+    pub fn idx<R: 'static>(&self) -> Option<ResourceIdx> {
+        self.by_type_id.get(&TypeId::of::<R>()).copied()
+    }
+
+    /// Returns a reference to the resource `R`, if it exists.
+    pub fn get<R: Send + Sync + 'static>(&self) -> Option<&R> {
+        let value = self.infos[self.idx::<R>()?.index()].value.as_ref()?;
+
+        // SAFETY: Resources are keyed by the `TypeId` of their value.
+        Some(unsafe { &*AliasedBox::as_ptr(value).cast::<R>() })
+    }
+
+    pub(crate) fn get_mut<R: Send + Sync + 'static>(&mut self) -> Option<&mut R> {
+        let idx = self.idx::<R>()?;
+        let value = self.infos[idx.index()].value.as_mut()?;
+
+        // SAFETY: Resources are keyed by the `TypeId` of their value.
+        Some(unsafe { &mut *AliasedBox::as_mut_ptr(value).cast::<R>() })
+    }
+
+    /// Returns the name of the resource type at `idx`.
+    pub fn name(&self, idx: ResourceIdx) -> Option<&'static str> {
+        self.infos.get(idx.index()).map(|info| info.name)
+    }
+
+    /// Returns the number of resources in the world.
+    pub fn len(&self) -> usize {
+        self.infos
+            .iter()
+            .filter(|info| info.value.is_some())
+            .count()
+    }
+
+    /// Returns `true` if the world has no resources.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a pointer to the resource `R` at `idx`, or panics if it
+    /// doesn't exist.
+    ///
+    /// # Safety
+    ///
+    /// `idx` must be the index of `R`.
+    #[track_caller]
+    unsafe fn ptr<R: 'static>(&self, idx: ResourceIdx) -> *mut R {
+        match &self.infos[idx.index()].value {
+            Some(value) => AliasedBox::as_non_null(value).as_ptr().cast(),
+            None => panic!(
+                "resource `{}` does not exist. Add it with `World::insert_resource`",
+                any::type_name::<R>()
+            ),
+        }
+    }
+}
+
+fn downcast<R: 'static>(value: AliasedBox<dyn Any + Send + Sync>) -> R {
+    match AliasedBox::into_box(value).downcast::<R>() {
+        Ok(r) => *r,
+        Err(_) => unreachable!("resources are keyed by the `TypeId` of their value"),
+    }
+}
+
+impl fmt::Debug for Resources {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(
+                self.infos
+                    .iter()
+                    .filter(|info| info.value.is_some())
+                    .map(|info| info.name),
+            )
+            .finish()
+    }
+}
+
+/// The index of a resource type in [`Resources`].
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
+pub struct ResourceIdx(u32);
+
+impl ResourceIdx {
+    /// Creates a resource index from its raw value.
+    ///
+    /// The index is only meaningful for the [`Resources`] it came from.
+    pub const fn from_raw(raw: u32) -> Self {
+        Self(raw)
+    }
+
+    /// Returns the raw value of this index.
+    pub const fn to_raw(self) -> u32 {
+        self.0
+    }
+
+    /// Returns this index as a `usize`.
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+unsafe impl SparseIndex for ResourceIdx {
+    const MAX: Self = Self(u32::MAX);
+
+    fn index(self) -> usize {
+        ResourceIdx::index(self)
+    }
+
+    fn from_index(idx: usize) -> Self {
+        Self(u32::from_index(idx))
+    }
+}
+
+/// A [`HandlerParam`] with shared access to the resource `R`.
+///
+/// The handler panics if the resource doesn't exist when it runs. Handlers
+/// reading the same resource can run concurrently with
+/// [`World::send_parallel`].
+///
+/// # Examples
+///
+/// ```
+/// use evenio::prelude::*;
+/// use evenio::resource::{Res, ResMut};
+///
+/// #[derive(Event)]
+/// struct Tick;
+///
+/// struct FrameCount(u64);
+///
+/// let mut world = World::new();
+///
+/// world.insert_resource(FrameCount(0));
+///
+/// world.add_handler(|_: Receiver<Tick>, mut frames: ResMut<FrameCount>| {
+///     frames.0 += 1;
+/// });
+///
+/// world.add_handler(|_: Receiver<Tick>, frames: Res<FrameCount>| {
+///     println!("frame {}", frames.0);
+/// });
+///
+/// world.send(Tick);
+/// world.send(Tick);
+///
+/// assert_eq!(world.resource::<FrameCount>().unwrap().0, 2);
+/// ```
+///
+/// [`World::send_parallel`]: crate::world::World::send_parallel
+pub struct Res<'a, R> {
+    resource: &'a R,
+}
+
+unsafe impl<R: Send + Sync + 'static> HandlerParam for Res<'_, R> {
+    type State = ResourceIdx;
+
+    type Item<'a> = Res<'a, R>;
+
+    fn init(world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+        init_resource::<Self, R>(world, config, Access::Read)
+    }
+
+    #[track_caller]
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        _info: &'a HandlerInfo,
+        _event_ptr: EventPtr<'a>,
+        _target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::Item<'a> {
+        Res {
+            resource: &*world.resources().ptr::<R>(*state),
+        }
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
+impl<R> Deref for Res<'_, R> {
+    type Target = R;
+
+    fn deref(&self) -> &Self::Target {
+        self.resource
+    }
+}
+
+impl<R: fmt::Debug> fmt::Debug for Res<'_, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.resource.fmt(f)
+    }
+}
+
+/// A [`HandlerParam`] with exclusive access to the resource `R`.
+///
+/// The handler panics if the resource doesn't exist when it runs. Handlers
+/// accessing the same resource never run concurrently with
+/// [`World::send_parallel`]. See [`Res`] for an example.
+///
+/// [`World::send_parallel`]: crate::world::World::send_parallel
+pub struct ResMut<'a, R> {
+    resource: &'a mut R,
+}
+
+unsafe impl<R: Send + Sync + 'static> HandlerParam for ResMut<'_, R> {
+    type State = ResourceIdx;
+
+    type Item<'a> = ResMut<'a, R>;
+
+    fn init(world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+        init_resource::<Self, R>(world, config, Access::ReadWrite)
+    }
+
+    #[track_caller]
+    unsafe fn get<'a>(
+        state: &'a mut Self::State,
+        _info: &'a HandlerInfo,
+        _event_ptr: EventPtr<'a>,
+        _target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::Item<'a> {
+        ResMut {
+            resource: &mut *world.resources().ptr::<R>(*state),
+        }
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
+impl<R> Deref for ResMut<'_, R> {
+    type Target = R;
+
+    fn deref(&self) -> &Self::Target {
+        self.resource
+    }
+}
+
+impl<R> DerefMut for ResMut<'_, R> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.resource
+    }
+}
+
+impl<R: fmt::Debug> fmt::Debug for ResMut<'_, R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.resource.fmt(f)
+    }
+}
+
+/// Declares access to the resource `R` on behalf of the handler param `P`.
+fn init_resource<P: ?Sized, R: Send + Sync + 'static>(
+    world: &mut World,
+    config: &mut Config,
+    access: Access,
+) -> Result<ResourceIdx, InitError> {
+    // The resource could be removed by a `WorldMut`, or accessed by the
+    // handlers of events handled by a `Flush`.
+    config.read_world::<P>()?;
+
+    let idx = world.resources_mut().register::<R>();
+
+    let prev = config.resource_access.get(idx);
+
+    if !prev.is_compatible(access) {
+        return Err(InitError(
+            format!(
+                "`{}` has conflicting access with a previous parameter accessing resource `{}`",
+                any::type_name::<P>(),
+                any::type_name::<R>()
+            )
+            .into(),
+        ));
+    }
+
+    config.resource_access.set(idx, prev.max(access));
+
+    Ok(idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Event)]
+    struct E;
+
+    #[derive(Debug, PartialEq)]
+    struct Counter(u32);
+
+    #[test]
+    fn insert_and_remove() {
+        let mut world = World::new();
+
+        assert_eq!(world.insert_resource(Counter(1)), None);
+        assert_eq!(world.insert_resource(Counter(2)), Some(Counter(1)));
+        assert_eq!(world.resources().len(), 1);
+
+        world.resource_mut::<Counter>().unwrap().0 += 1;
+
+        assert_eq!(world.remove_resource::<Counter>(), Some(Counter(3)));
+        assert_eq!(world.remove_resource::<Counter>(), None);
+        assert!(world.resources().is_empty());
+    }
+
+    #[test]
+    fn resource_idx_round_trip() {
+        let mut world = World::new();
+
+        world.insert_resource(Counter(0));
+
+        let idx = world.resources().idx::<Counter>().unwrap();
+
+        assert_eq!(ResourceIdx::from_raw(idx.to_raw()), idx);
+        assert_eq!(idx.index(), idx.to_raw() as usize);
+        assert_eq!(
+            world.resources().name(idx),
+            Some(any::type_name::<Counter>())
+        );
+    }
+
+    #[test]
+    fn res_and_res_mut() {
+        let mut world = World::new();
+
+        world.insert_resource(Counter(0));
+
+        world.add_handler(|_: Receiver<E>, mut c: ResMut<Counter>| {
+            c.0 += 1;
+        });
+
+        world.add_handler(|_: Receiver<E>, c: Res<Counter>, d: Res<Counter>| {
+            assert_eq!(c.0, d.0);
+        });
+
+        world.send(E);
+        world.send(E);
+
+        assert_eq!(world.resource::<Counter>(), Some(&Counter(2)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Counter")]
+    fn conflicting_access_in_handler() {
+        let mut world = World::new();
+
+        world.add_handler(|_: Receiver<E>, _: Res<Counter>, _: ResMut<Counter>| {});
+    }
+
+    #[test]
+    #[should_panic(expected = "resource `evenio::resource::tests::Counter` does not exist")]
+    fn missing_resource() {
+        let mut world = World::new();
+
+        world.add_handler(|_: Receiver<E>, _: Res<Counter>| {});
+
+        world.send(E);
+    }
+
+    #[test]
+    fn resource_access_compatibility() {
+        struct Other;
+
+        let mut world = World::new();
+
+        let a = world.add_handler(|_: Receiver<E>, _: ResMut<Counter>| {});
+        let b = world.add_handler(|_: Receiver<E>, _: ResMut<Counter>| {});
+        let c = world.add_handler(|_: Receiver<E>, _: Res<Other>| {});
+        let d = world.add_handler(|_: Receiver<E>, _: Res<Other>| {});
+
+        let info = |id| world.handlers().get(id).unwrap();
+
+        assert!(!info(a).is_compatible(info(b)));
+        assert!(info(a).is_compatible(info(c)));
+        assert!(info(c).is_compatible(info(d)));
+
+        let e = world.add_event::<E>();
+
+        assert_eq!(world.dispatch_groups(e), [vec![a], vec![b, c, d]]);
+    }
+}
@@ -0,0 +1,133 @@
+//! Internal safety invariants.
+//!
+//! This module documents the invariants the library's `unsafe` code relies
+//! on, which part of the library is responsible for upholding each one, and
+//! what checks them. It is intended for contributors and for auditing; none
+//! of it is needed to use the library.
+//!
+//! Invariants are checked in three ways:
+//!
+//! - **Debug assertions.** Every invariant listed here is checked with a
+//!   `debug_assert!` (or an `assert!` behind `cfg!(debug_assertions)`) at the
+//!   point where it could first be broken. These checks are compiled out of
+//!   release builds.
+//! - **Unit tests** next to the code, named below.
+//! - **Miri.** CI runs the whole test suite under Miri with tree borrows. The
+//!   `invariants` integration test exercises every subsystem below through the
+//!   public API with small sizes, so it can be run on its own:
+//!
+//!   ```text
+//!   MIRIFLAGS='-Zmiri-tree-borrows' cargo +nightly miri test --test invariants
+//!   ```
+//!
+//! _Note: This module's contents are not stable_
+//!
+//! # Debug-checked accessors
+//!
+//! `GetDebugChecked`, `UnwrapDebugChecked`, `assume_debug_checked` and
+//! `unreachable_debug_checked` are unchecked operations which panic in debug
+//! builds and are Undefined Behavior in release builds if their precondition
+//! doesn't hold.
+//!
+//! - **Invariant:** The index is in bounds, the `Option` is `Some`, or the
+//!   condition is true.
+//! - **Upheld by:** The caller, which must state why in a `// SAFETY:` comment
+//!   or the `# Safety` section of the enclosing function.
+//! - **Checked by:** The accessors themselves in debug builds. Every test which
+//!   reaches them, and so every Miri run, checks them.
+//!
+//! # Type-erased vectors
+//!
+//! `BlobVec` is a `Vec<T>` with `T` erased to a [`Layout`] and an optional
+//! [`DropFn`]. It stores component columns and the previous values of
+//! [double-buffered] components.
+//!
+//! - **Invariant:** The first `len` elements are initialized values of the
+//!   erased type, each at a multiple of the padded element size from an
+//!   allocation aligned to the element's alignment.
+//! - **Invariant:** Elements which have been moved out with `transfer_elem` or
+//!   `forget_elements` are not dropped again.
+//! - **Invariant:** For zero-sized types, no allocation is made and the
+//!   capacity is `usize::MAX`.
+//! - **Upheld by:** `BlobVec` itself, given that callers only write values of
+//!   the erased type.
+//! - **Checked by:** The `blob_vec` unit tests `calls_drop_on_elements`,
+//!   `swap_remove`, `unusual_alignment` and `size_not_multiple_of_align`.
+//!
+//! # Archetype rows and columns
+//!
+//! An [`Archetype`] stores one [`Column`] per component, plus the entity IDs
+//! and spawn sequence numbers of its entities.
+//!
+//! - **Invariant:** Each column's data, change ticks, added ticks and previous
+//!   values (if any) have exactly one element per entity in the archetype. Row
+//!   `i` of every column belongs to the entity at
+//!   [`Archetype::entity_ids`]`[i]`.
+//! - **Invariant:** Columns are sorted by [`ComponentIdx`] and parallel to
+//!   [`Archetype::component_indices`].
+//! - **Invariant:** The location stored in [`Entities`] for every entity names
+//!   the archetype and row it is actually in.
+//! - **Invariant:** Handlers which cache column pointers are notified with
+//!   [`Handler::refresh_archetype`] whenever a column may have reallocated, and
+//!   with [`Handler::remove_archetype`] when an archetype becomes empty.
+//! - **Upheld by:** `Archetypes`, in `spawn`, `move_entity`, `remove_entity`,
+//!   `take_entity` and `permute_rows`. Row and column lengths are asserted
+//!   after each of them.
+//! - **Checked by:** The `archetype` unit tests and the `world` tests for
+//!   inserting, removing and despawning, as well as
+//!   `previous_survives_transfer` and `iter_previously_nonempty`.
+//!
+//! # Event queue payloads
+//!
+//! Events sent from handlers and [`World::send`] are moved into a bump
+//! allocator owned by the event queue. Queue items hold type-erased pointers
+//! to them.
+//!
+//! - **Invariant:** Every item's payload pointer is aligned for, and points to
+//!   an initialized value of, the event type named by the item.
+//! - **Invariant:** Each payload is dropped exactly once, when it leaves the
+//!   queue, unless a handler took ownership of it with [`EventMut::take`]. This
+//!   holds if a handler panics.
+//! - **Invariant:** The bump allocator is only reset once the queue is empty,
+//!   so no item outlives its payload.
+//! - **Invariant:** Events sent by a handler are popped in the order they were
+//!   sent, so their sequence numbers are popped in increasing order.
+//! - **Upheld by:** `EventQueue` and `World`'s dispatch loop.
+//! - **Checked by:** The `world` tests `world_drops_events` and
+//!   `world_drops_events_on_panic`, and the `event` tests `event_order`,
+//!   `event_order_send_many` and `event_sequence_is_send_order`.
+//!
+//! # Query aliasing
+//!
+//! Queries hand out `&mut` references to component data through raw column
+//! pointers, so the library must ensure no two live references alias.
+//!
+//! - **Invariant:** Within one query, a component is never accessed mutably
+//!   together with any other access to it. This is rejected when the query is
+//!   initialized, using [`ComponentAccessExpr`].
+//! - **Invariant:** Handler parameters don't conflict with each other. This is
+//!   rejected when the handler is initialized.
+//! - **Invariant:** Methods returning several mutable items at once, such as
+//!   [`Fetcher::get_many_mut`], never return the same entity twice.
+//! - **Upheld by:** [`Config`] and [`Fetcher`].
+//! - **Checked by:** The `query` test `mixed_borrow_error_names_component`, the
+//!   `handler` test `handler_info_aliasing` and the `fetch` tests
+//!   `column_mut_conflicts` and `get_many_mut`.
+//!
+//! [`Layout`]: core::alloc::Layout
+//! [`DropFn`]: crate::drop::DropFn
+//! [double-buffered]: crate::component::Component::DOUBLE_BUFFERED
+//! [`Archetype`]: crate::archetype::Archetype
+//! [`Archetype::entity_ids`]: crate::archetype::Archetype::entity_ids
+//! [`Archetype::component_indices`]: crate::archetype::Archetype::component_indices
+//! [`Column`]: crate::archetype::Column
+//! [`ComponentIdx`]: crate::component::ComponentIdx
+//! [`Entities`]: crate::entity::Entities
+//! [`Handler::refresh_archetype`]: crate::handler::Handler::refresh_archetype
+//! [`Handler::remove_archetype`]: crate::handler::Handler::remove_archetype
+//! [`World::send`]: crate::world::World::send
+//! [`EventMut::take`]: crate::event::EventMut::take
+//! [`ComponentAccessExpr`]: crate::access::ComponentAccessExpr
+//! [`Fetcher::get_many_mut`]: crate::fetch::Fetcher::get_many_mut
+//! [`Fetcher`]: crate::fetch::Fetcher
+//! [`Config`]: crate::handler::Config
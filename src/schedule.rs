@@ -0,0 +1,187 @@
+//! Named groups of handlers which run on demand.
+//!
+//! See [`World::run_schedule`] for more information.
+//!
+//! [`World::run_schedule`]: crate::world::World::run_schedule
+
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, format, vec};
+use core::alloc::Layout;
+use core::any::TypeId;
+
+use crate::access::Access;
+use crate::archetype::Archetype;
+use crate::entity::EntityLocation;
+use crate::event::{Event, EventDescriptor, EventId, EventKind, EventPtr};
+use crate::handler::{Config, Handler, HandlerInfo, InitError, Locals};
+use crate::world::{UnsafeWorldCell, World};
+
+/// The event sent by [`World::run_schedule`]. Every schedule is registered
+/// as a separate event with this layout.
+///
+/// [`World::run_schedule`]: crate::world::World::run_schedule
+pub(crate) struct RunSchedule;
+
+impl Event for RunSchedule {
+    const IS_IMMUTABLE: bool = true;
+}
+
+/// The events of every schedule in a world, by name.
+#[derive(Debug)]
+pub(crate) struct Schedules {
+    by_name: BTreeMap<Box<str>, EventId>,
+}
+
+impl Schedules {
+    pub(crate) fn new() -> Self {
+        Self {
+            by_name: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the event of the schedule if it exists.
+    pub(crate) fn get(&self, schedule: &str) -> Option<EventId> {
+        self.by_name.get(schedule).copied()
+    }
+
+    pub(crate) fn insert(&mut self, schedule: &str, event: EventId) {
+        self.by_name.insert(schedule.into(), event);
+    }
+}
+
+/// Returns the descriptor of the event sent by a schedule.
+pub(crate) fn schedule_event_descriptor(schedule: &str) -> EventDescriptor {
+    EventDescriptor {
+        name: Cow::Owned(format!("schedule `{schedule}`")),
+        type_id: None,
+        is_targeted: false,
+        kind: EventKind::Normal,
+        layout: Layout::new::<RunSchedule>(),
+        drop: None,
+        fields: vec![],
+        is_immutable: true,
+    }
+}
+
+/// The wrapper handler used by [`World::add_to_schedule`]. Receives the
+/// event of the schedule in place of a [`Receiver`].
+///
+/// [`World::add_to_schedule`]: crate::world::World::add_to_schedule
+/// [`Receiver`]: crate::event::Receiver
+#[derive(Clone, Debug)]
+pub(crate) struct Scheduled<H> {
+    event: EventId,
+    handler: H,
+}
+
+impl<H> Scheduled<H> {
+    pub(crate) fn new(event: EventId, handler: H) -> Self {
+        Self { event, handler }
+    }
+}
+
+impl<H: Handler> Handler for Scheduled<H> {
+    fn type_id(&self) -> Option<TypeId> {
+        // The same handler may be added to more than one schedule.
+        None
+    }
+
+    fn name(&self) -> Cow<'static, str> {
+        self.handler.name()
+    }
+
+    fn init(&mut self, world: &mut World, config: &mut Config) -> Result<(), InitError> {
+        config.received_event = Some(self.event);
+        config.received_event_access = Access::Read;
+
+        self.handler.init(world, config)
+    }
+
+    unsafe fn run(
+        &mut self,
+        info: &HandlerInfo,
+        event_ptr: EventPtr,
+        target_location: EntityLocation,
+        world: UnsafeWorldCell,
+    ) {
+        self.handler.run(info, event_ptr, target_location, world)
+    }
+
+    unsafe fn run_deferred(&mut self, info: &HandlerInfo, world: UnsafeWorldCell) -> bool {
+        self.handler.run_deferred(info, world)
+    }
+
+    fn refresh_archetype(&mut self, arch: &Archetype) {
+        self.handler.refresh_archetype(arch)
+    }
+
+    fn remove_archetype(&mut self, arch: &Archetype) {
+        self.handler.remove_archetype(arch)
+    }
+
+    fn teardown(&mut self, world: &mut World) {
+        self.handler.teardown(world)
+    }
+
+    fn take_locals(&mut self, locals: &mut Locals) {
+        self.handler.take_locals(locals)
+    }
+
+    fn restore_locals(&mut self, locals: &mut Locals) {
+        self.handler.restore_locals(locals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use crate::prelude::*;
+
+    #[derive(Component, Default)]
+    struct Log(Vec<&'static str>);
+
+    #[test]
+    fn run_schedules_in_sequence() {
+        let mut world = World::new();
+
+        let e = world.spawn();
+        world.insert(e, Log::default());
+
+        world.add_to_schedule("input", |Single(log): Single<&mut Log>| {
+            log.0.push("read input")
+        });
+        world.add_to_schedule("simulation", |Single(log): Single<&mut Log>| {
+            log.0.push("move")
+        });
+        world.add_to_schedule("input", |Single(log): Single<&mut Log>| {
+            log.0.push("map actions")
+        });
+        world.add_to_schedule("simulation", |Single(log): Single<&mut Log>| {
+            log.0.push("collide")
+        });
+
+        world.run_schedule("input");
+        world.run_schedule("simulation");
+        world.run_schedule("missing");
+
+        assert_eq!(
+            world.get::<Log>(e).unwrap().0,
+            vec!["read input", "map actions", "move", "collide"]
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn schedule_handler_with_receiver() {
+        #[derive(Event)]
+        struct E;
+
+        let mut world = World::new();
+
+        world.add_to_schedule("update", |_: Receiver<E>| {});
+    }
+}
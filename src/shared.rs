@@ -0,0 +1,266 @@
+//! Copy-on-write components shared between entities.
+//!
+//! See [`Shared`] for more information.
+
+use alloc::sync::Arc;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::any::TypeId;
+use core::ops::{Deref, DerefMut};
+use core::{fmt, mem};
+
+use crate::component::Component;
+use crate::world::World;
+
+/// A component holding a reference-counted value of `T` which many entities
+/// can share until one of them changes it.
+///
+/// Cloning a `Shared<T>` shares the value instead of copying it, so giving
+/// every instance of a prefab a clone of the same `Shared<T>` stores `T`
+/// once. Reading the value through `&Shared<T>` is the same as reading an
+/// owned `T`. Writing to it through `&mut Shared<T>`, such as from a
+/// `Fetcher<&mut Shared<T>>` or [`World::get_mut`], first gives the entity a
+/// private copy of the value if it is shared. From then on the entity owns
+/// its value like any other component, until it is shared again with
+/// [`World::make_shared`].
+///
+/// Since writing requires mutable access to the component, giving an entity
+/// its own copy is tracked by change detection like any other write.
+///
+/// [`World::shared_stats`] reports how many values are shared.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::prelude::*;
+/// use evenio::shared::Shared;
+///
+/// #[derive(Clone)]
+/// struct WeaponStats {
+///     damage: u32,
+/// }
+///
+/// let mut world = World::new();
+///
+/// let prefab = Shared::new(WeaponStats { damage: 10 });
+///
+/// let a = world.spawn();
+/// world.insert(a, prefab.clone());
+/// let b = world.spawn();
+/// world.insert(b, prefab.clone());
+///
+/// // Only `a` is affected.
+/// world.get_mut::<Shared<WeaponStats>>(a).unwrap().damage += 5;
+///
+/// assert_eq!(world.get::<Shared<WeaponStats>>(a).unwrap().damage, 15);
+/// assert_eq!(world.get::<Shared<WeaponStats>>(b).unwrap().damage, 10);
+/// ```
+pub struct Shared<T>(Arc<T>);
+
+impl<T> Shared<T> {
+    /// Creates a new shared value.
+    pub fn new(value: T) -> Self {
+        Self(Arc::new(value))
+    }
+
+    /// Returns `true` if the two components share the same value.
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.0, &other.0)
+    }
+
+    /// Returns the number of components sharing the value, including `this`.
+    pub fn ref_count(this: &Self) -> usize {
+        Arc::strong_count(&this.0)
+    }
+
+    /// Returns `true` if no other component shares the value.
+    pub fn is_unique(this: &Self) -> bool {
+        Self::ref_count(this) == 1
+    }
+}
+
+impl<T: Clone> Shared<T> {
+    /// Returns the value, cloning it if it is shared.
+    pub fn into_inner(this: Self) -> T {
+        Arc::try_unwrap(this.0).unwrap_or_else(|arc| (*arc).clone())
+    }
+}
+
+impl<T: Clone + Send + Sync + 'static> Component for Shared<T> {}
+
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> Deref for Shared<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: Clone> DerefMut for Shared<T> {
+    /// Gives this component its own copy of the value if it is shared.
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        Arc::make_mut(&mut self.0)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Shared<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Shared").field(&*self.0).finish()
+    }
+}
+
+/// Sharing statistics of the [`Shared<T>`] component, returned by
+/// [`World::shared_stats`].
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct SharedStats {
+    /// Number of entities with the component.
+    pub entities: usize,
+    /// Number of distinct values held by those entities.
+    pub values: usize,
+    /// Number of bytes of `T` which would be needed if every entity owned its
+    /// value, minus the bytes which are actually used.
+    pub bytes_saved: usize,
+}
+
+pub(crate) fn shared_stats<T: Clone + Send + Sync + 'static>(world: &World) -> SharedStats {
+    let Some(info) = world.components().get_by_type_id(TypeId::of::<Shared<T>>()) else {
+        return SharedStats::default();
+    };
+
+    let mut ptrs = Vec::new();
+
+    for arch in world.archetypes().iter() {
+        let Some(col) = arch.column_of(info.id().index()) else {
+            continue;
+        };
+
+        let data = col.data().as_ptr().cast::<Shared<T>>();
+
+        for row in 0..arch.entity_count() as usize {
+            // SAFETY: The column holds `entity_count` values of `Shared<T>`.
+            ptrs.push(Arc::as_ptr(unsafe { &(*data.add(row)).0 }));
+        }
+    }
+
+    let entities = ptrs.len();
+
+    ptrs.sort_unstable();
+    ptrs.dedup();
+
+    SharedStats {
+        entities,
+        values: ptrs.len(),
+        bytes_saved: (entities - ptrs.len()) * mem::size_of::<T>(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Clone, PartialEq, Debug)]
+    struct WeaponStats {
+        damage: u32,
+        table: [u8; 296],
+    }
+
+    #[derive(Event)]
+    struct Buff(EntityId);
+
+    fn prefab() -> Shared<WeaponStats> {
+        Shared::new(WeaponStats {
+            damage: 10,
+            table: [0; 296],
+        })
+    }
+
+    #[test]
+    fn copy_on_write() {
+        let mut world = World::new();
+
+        let prefab = prefab();
+        let ids: Vec<_> = (0..3).map(|_| world.spawn()).collect();
+
+        for &e in &ids {
+            world.insert(e, prefab.clone());
+        }
+
+        let get = |world: &World, e| world.get::<Shared<WeaponStats>>(e).unwrap().clone();
+
+        assert!(ids
+            .iter()
+            .all(|&e| Shared::ptr_eq(&get(&world, e), &prefab)));
+        assert_eq!(Shared::ref_count(&prefab), 4);
+
+        world.add_handler(
+            |r: Receiver<Buff>, mut f: Fetcher<&mut Shared<WeaponStats>>| {
+                f.get_mut(r.event.0).unwrap().damage *= 2;
+            },
+        );
+
+        world.send(Buff(ids[1]));
+
+        assert_eq!(get(&world, ids[0]).damage, 10);
+        assert_eq!(get(&world, ids[1]).damage, 20);
+        assert_eq!(get(&world, ids[2]).damage, 10);
+        assert!(Shared::is_unique(
+            world.get::<Shared<WeaponStats>>(ids[1]).unwrap()
+        ));
+        assert_eq!(Shared::ref_count(&prefab), 3);
+
+        // Writing to an unshared value doesn't copy it again.
+        let ptr = &**world.get::<Shared<WeaponStats>>(ids[1]).unwrap() as *const _;
+        world.send(Buff(ids[1]));
+        assert_eq!(
+            &**world.get::<Shared<WeaponStats>>(ids[1]).unwrap() as *const _,
+            ptr
+        );
+        assert_eq!(get(&world, ids[1]).damage, 40);
+
+        world.make_shared(ids[1], &prefab);
+
+        assert!(Shared::ptr_eq(&get(&world, ids[1]), &prefab));
+        assert_eq!(Shared::ref_count(&prefab), 4);
+    }
+
+    #[test]
+    fn stats_and_reclaim() {
+        let mut world = World::new();
+
+        assert_eq!(world.shared_stats::<WeaponStats>(), SharedStats::default());
+
+        let prefab = prefab();
+        let ids: Vec<_> = (0..4).map(|_| world.spawn()).collect();
+
+        for &e in &ids {
+            world.insert(e, prefab.clone());
+        }
+
+        world.get_mut::<Shared<WeaponStats>>(ids[0]).unwrap().damage = 1;
+
+        let size = mem::size_of::<WeaponStats>();
+
+        assert_eq!(
+            world.shared_stats::<WeaponStats>(),
+            SharedStats {
+                entities: 4,
+                values: 2,
+                bytes_saved: 2 * size,
+            }
+        );
+
+        for e in ids {
+            world.despawn(e);
+        }
+
+        assert!(Shared::is_unique(&prefab));
+        assert_eq!(world.shared_stats::<WeaponStats>().entities, 0);
+    }
+}
@@ -11,19 +11,43 @@ use crate::assert::UnwrapDebugChecked;
 #[derive(Clone, Debug)]
 pub(crate) struct SlotMap<T> {
     slots: Vec<Slot<T>>,
+    /// Head of the free list.
     next_free: u32,
+    /// Tail of the free list if vacant slots are reused in FIFO order, or
+    /// `None` if they are reused in LIFO order.
+    last_free: Option<u32>,
     len: u32,
 }
 
 impl<T> SlotMap<T> {
+    /// Returns the number of slots that can be held without reallocating.
+    pub(crate) fn capacity(&self) -> usize {
+        self.slots.capacity()
+    }
+
+    /// Reserves capacity for at least `additional` more slots.
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        self.slots.reserve(additional);
+    }
+
     pub(crate) fn new() -> Self {
         Self {
             slots: vec![],
             next_free: u32::MAX,
+            last_free: None,
             len: 0,
         }
     }
 
+    /// Creates a slot map which reuses vacant slots in the order they were
+    /// vacated instead of the default LIFO order.
+    pub(crate) fn new_fifo() -> Self {
+        Self {
+            last_free: Some(u32::MAX),
+            ..Self::new()
+        }
+    }
+
     pub(crate) fn insert(&mut self, value: T) -> Option<Key> {
         self.insert_with(|_| value)
     }
@@ -47,6 +71,12 @@ impl<T> SlotMap<T> {
 
             self.next_free = unsafe { slot.union.next_free };
 
+            if self.next_free == u32::MAX {
+                if let Some(last_free) = &mut self.last_free {
+                    *last_free = u32::MAX;
+                }
+            }
+
             slot.union.value = ManuallyDrop::new(value);
         } else {
             let index = self.slots.len() as u32;
@@ -86,8 +116,25 @@ impl<T> SlotMap<T> {
         // If the generation overflowed then we consider the slot retired and won't try
         // to use it again.
         if slot.generation != 0 {
-            slot.union.next_free = self.next_free;
-            self.next_free = key.index();
+            match self.last_free {
+                // Append to the back of the free list.
+                Some(last_free) => {
+                    slot.union.next_free = u32::MAX;
+
+                    if let Some(last) = self.slots.get_mut(last_free as usize) {
+                        last.union.next_free = key.index();
+                    } else {
+                        self.next_free = key.index();
+                    }
+
+                    self.last_free = Some(key.index());
+                }
+                // Push to the front of the free list.
+                None => {
+                    slot.union.next_free = self.next_free;
+                    self.next_free = key.index();
+                }
+            }
         }
 
         self.len -= 1;
@@ -172,6 +219,76 @@ impl<T> SlotMap<T> {
             })
     }
 
+    /// Returns the generation of every slot, occupied or not.
+    #[cfg(feature = "serde")]
+    pub(crate) fn generations(&self) -> impl ExactSizeIterator<Item = u32> + '_ {
+        self.slots.iter().map(|s| s.generation)
+    }
+
+    /// Replaces all slots with slots of the given generations. Occupied slots
+    /// (odd generations) get their value from `f`, and vacant slots are
+    /// reused in index order. Slots with a generation of zero stay retired.
+    ///
+    /// The slot map must be empty.
+    #[cfg(feature = "serde")]
+    pub(crate) fn restore<F>(&mut self, generations: &[u32], mut f: F)
+    where
+        F: FnMut(Key) -> T,
+    {
+        assert_eq!(self.len, 0);
+        assert!(generations.len() < u32::MAX as usize);
+
+        self.slots.clear();
+        self.slots.reserve(generations.len());
+        self.next_free = u32::MAX;
+
+        if let Some(last_free) = &mut self.last_free {
+            *last_free = u32::MAX;
+        }
+
+        let mut prev_free = u32::MAX;
+
+        for (index, &generation) in generations.iter().enumerate() {
+            let index = index as u32;
+
+            if let Some(key) = Key::new(index, generation) {
+                self.slots.push(Slot {
+                    union: SlotUnion {
+                        value: ManuallyDrop::new(f(key)),
+                    },
+                    generation,
+                });
+
+                self.len += 1;
+                continue;
+            }
+
+            self.slots.push(Slot {
+                union: SlotUnion {
+                    next_free: u32::MAX,
+                },
+                generation,
+            });
+
+            if generation == 0 {
+                continue;
+            }
+
+            match self.slots.get_mut(prev_free as usize) {
+                Some(prev) => prev.union.next_free = index,
+                None => self.next_free = index,
+            }
+
+            prev_free = index;
+        }
+
+        if self.next_free != u32::MAX {
+            if let Some(last_free) = &mut self.last_free {
+                *last_free = prev_free;
+            }
+        }
+    }
+
     #[allow(dead_code)]
     pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = (Key, &mut T)> {
         self.slots.iter_mut().enumerate().filter_map(|(idx, slot)| {
@@ -218,7 +335,7 @@ struct Slot<T> {
 
 impl<T> Slot<T> {
     const fn is_vacant(&self) -> bool {
-        self.generation % 2 == 0
+        self.generation.is_multiple_of(2)
     }
 }
 
@@ -304,6 +421,12 @@ impl Key {
         self.n.get() as u32
     }
 
+    /// Returns the index and generation packed into a `u64`.
+    #[inline]
+    pub(crate) const fn to_bits(self) -> u64 {
+        self.n.get()
+    }
+
     #[inline]
     pub(crate) const fn generation(self) -> NonZeroU32 {
         // SAFETY: Generation is always non-zero, so resulting `NonZeroU32` must be
@@ -486,6 +609,60 @@ mod tests {
         assert_eq!(iter.next(&sm), sm.insert(0));
     }
 
+    #[test]
+    fn fifo_reuse() {
+        let mut sm = SlotMap::new_fifo();
+
+        let k0 = sm.insert(0).unwrap();
+        let k1 = sm.insert(1).unwrap();
+        let k2 = sm.insert(2).unwrap();
+
+        sm.remove(k1);
+        sm.remove(k0);
+        sm.remove(k2);
+
+        let mut iter = sm.next_key_iter();
+
+        for idx in [1, 0, 2, 3] {
+            let k = iter.next(&sm).unwrap();
+            assert_eq!(k.index(), idx);
+            assert_eq!(sm.insert(0), Some(k));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn restore() {
+        let mut sm = SlotMap::new();
+
+        let k0 = sm.insert(0).unwrap();
+        let k1 = sm.insert(1).unwrap();
+        sm.insert(2).unwrap();
+        let k3 = sm.insert(3).unwrap();
+
+        sm.remove(k1);
+        sm.remove(k3);
+
+        let generations: Vec<_> = sm.generations().collect();
+
+        let mut restored = SlotMap::new();
+        restored.restore(&generations, |k| k.index());
+
+        assert_eq!(restored.len(), 2);
+        assert_eq!(restored.get(k0), Some(&0));
+        assert_eq!(restored.get(k1), None);
+        assert!(restored.generations().eq(sm.generations()));
+
+        let mut iter = restored.next_key_iter();
+
+        for idx in [1, 3, 4] {
+            let k = iter.next(&restored).unwrap();
+            assert_eq!(k.index(), idx);
+            assert_ne!(k, k1);
+            assert_eq!(restored.insert(0), Some(k));
+        }
+    }
+
     #[test]
     fn next_key_iter_null_next_free() {
         let mut sm = SlotMap::new();
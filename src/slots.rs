@@ -0,0 +1,383 @@
+//! Queries over the elements of components which hold a fixed set of slots.
+//!
+//! See [`SlotContainer`] for more information.
+
+use core::iter::{Enumerate, FusedIterator};
+use core::marker::PhantomData;
+use core::{any, fmt, slice};
+
+use crate::access::ComponentAccessExpr;
+use crate::archetype::{Archetype, ArchetypeRow};
+use crate::component::Component;
+use crate::entity::EntityId;
+use crate::fetch::Fetcher;
+use crate::handler::{Config, InitError};
+use crate::query::{Query, ReadOnlyQuery};
+use crate::world::World;
+
+/// A component made of slots of type `T`, such as an inventory holding an
+/// array of item stacks.
+///
+/// The [`Slots`] and [`SlotsMut`] queries fetch the slots of the component,
+/// and [`Fetcher::iter_slots`] and [`Fetcher::iter_slots_mut`] iterate over
+/// every slot of every matching entity along with its index.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::prelude::*;
+/// use evenio::slots::{SlotContainer, Slots};
+///
+/// #[derive(Component)]
+/// struct Inventory([Option<u32>; 4]);
+///
+/// impl SlotContainer<Option<u32>> for Inventory {
+///     fn slots(&self) -> &[Option<u32>] {
+///         &self.0
+///     }
+///
+///     fn slots_mut(&mut self) -> &mut [Option<u32>] {
+///         &mut self.0
+///     }
+/// }
+///
+/// #[derive(Event)]
+/// struct Count;
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(
+///     |_: Receiver<Count>, f: Fetcher<Slots<Inventory, Option<u32>>>| {
+///         let items: u32 = f.iter_slots().filter_map(|(_, _, slot)| *slot).sum();
+///         assert_eq!(items, 12);
+///     },
+/// );
+///
+/// let e = world.spawn();
+/// world.insert(e, Inventory([Some(5), None, Some(7), None]));
+///
+/// world.send(Count);
+/// ```
+pub trait SlotContainer<T>: Component {
+    /// Returns the slots of the component.
+    fn slots(&self) -> &[T];
+
+    /// Returns the slots of the component mutably.
+    fn slots_mut(&mut self) -> &mut [T];
+}
+
+/// A [`Query`] for the slots of the [`SlotContainer`] component `C`, which
+/// returns a [`SlotsOf`]. Accesses `C` immutably.
+pub struct Slots<C, T>(PhantomData<fn() -> (C, T)>);
+
+unsafe impl<C: SlotContainer<T>, T: 'static> Query for Slots<C, T> {
+    type Item<'a> = SlotsOf<'a, T>;
+
+    type ArchState = <(EntityId, &'static C) as Query>::ArchState;
+
+    type State = <(EntityId, &'static C) as Query>::State;
+
+    fn init(
+        world: &mut World,
+        config: &mut Config,
+    ) -> Result<(ComponentAccessExpr, Self::State), InitError> {
+        <(EntityId, &C)>::init(world, config)
+    }
+
+    fn new_state(world: &mut World) -> Self::State {
+        <(EntityId, &C)>::new_state(world)
+    }
+
+    fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
+        <(EntityId, &C)>::new_arch_state(arch, state)
+    }
+
+    unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::Item<'a> {
+        let (entity, component) = <(EntityId, &C)>::get(state, row);
+
+        SlotsOf {
+            entity,
+            slots: component.slots(),
+        }
+    }
+}
+
+unsafe impl<C: SlotContainer<T>, T: 'static> ReadOnlyQuery for Slots<C, T> {}
+
+impl<C, T> fmt::Debug for Slots<C, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Slots<{}>", any::type_name::<C>())
+    }
+}
+
+/// A [`Query`] for the slots of the [`SlotContainer`] component `C`, which
+/// returns a [`SlotsOfMut`]. Accesses `C` mutably.
+pub struct SlotsMut<C, T>(PhantomData<fn() -> (C, T)>);
+
+unsafe impl<C: SlotContainer<T>, T: 'static> Query for SlotsMut<C, T> {
+    type Item<'a> = SlotsOfMut<'a, T>;
+
+    type ArchState = <(EntityId, &'static mut C) as Query>::ArchState;
+
+    type State = <(EntityId, &'static mut C) as Query>::State;
+
+    fn init(
+        world: &mut World,
+        config: &mut Config,
+    ) -> Result<(ComponentAccessExpr, Self::State), InitError> {
+        <(EntityId, &mut C)>::init(world, config)
+    }
+
+    fn new_state(world: &mut World) -> Self::State {
+        <(EntityId, &mut C)>::new_state(world)
+    }
+
+    fn new_arch_state(arch: &Archetype, state: &mut Self::State) -> Option<Self::ArchState> {
+        <(EntityId, &mut C)>::new_arch_state(arch, state)
+    }
+
+    unsafe fn get<'a>(state: &Self::ArchState, row: ArchetypeRow) -> Self::Item<'a> {
+        let (entity, component) = <(EntityId, &mut C)>::get(state, row);
+
+        SlotsOfMut {
+            entity,
+            slots: component.slots_mut(),
+        }
+    }
+}
+
+impl<C, T> fmt::Debug for SlotsMut<C, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SlotsMut<{}>", any::type_name::<C>())
+    }
+}
+
+/// The slots of one entity, returned by the [`Slots`] query. Iterating over
+/// it yields the entity, the index of each slot, and the slot.
+#[derive(Debug)]
+pub struct SlotsOf<'a, T> {
+    /// The entity the slots belong to.
+    pub entity: EntityId,
+    /// The slots of the entity's component.
+    pub slots: &'a [T],
+}
+
+impl<'a, T> IntoIterator for SlotsOf<'a, T> {
+    type Item = (EntityId, usize, &'a T);
+
+    type IntoIter = SlotsIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        SlotsIter {
+            entity: self.entity,
+            iter: self.slots.iter().enumerate(),
+        }
+    }
+}
+
+/// The slots of one entity, returned by the [`SlotsMut`] query. Iterating
+/// over it yields the entity, the index of each slot, and the slot.
+#[derive(Debug)]
+pub struct SlotsOfMut<'a, T> {
+    /// The entity the slots belong to.
+    pub entity: EntityId,
+    /// The slots of the entity's component.
+    pub slots: &'a mut [T],
+}
+
+impl<'a, T> IntoIterator for SlotsOfMut<'a, T> {
+    type Item = (EntityId, usize, &'a mut T);
+
+    type IntoIter = SlotsIterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        SlotsIterMut {
+            entity: self.entity,
+            iter: self.slots.iter_mut().enumerate(),
+        }
+    }
+}
+
+/// Iterator over the slots of a [`SlotsOf`].
+#[derive(Clone, Debug)]
+pub struct SlotsIter<'a, T> {
+    entity: EntityId,
+    iter: Enumerate<slice::Iter<'a, T>>,
+}
+
+impl<'a, T> Iterator for SlotsIter<'a, T> {
+    type Item = (EntityId, usize, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (i, slot) = self.iter.next()?;
+        Some((self.entity, i, slot))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for SlotsIter<'_, T> {}
+
+impl<T> FusedIterator for SlotsIter<'_, T> {}
+
+/// Iterator over the slots of a [`SlotsOfMut`].
+#[derive(Debug)]
+pub struct SlotsIterMut<'a, T> {
+    entity: EntityId,
+    iter: Enumerate<slice::IterMut<'a, T>>,
+}
+
+impl<'a, T> Iterator for SlotsIterMut<'a, T> {
+    type Item = (EntityId, usize, &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (i, slot) = self.iter.next()?;
+        Some((self.entity, i, slot))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for SlotsIterMut<'_, T> {}
+
+impl<T> FusedIterator for SlotsIterMut<'_, T> {}
+
+impl<C: SlotContainer<T>, T: 'static> Fetcher<'_, Slots<C, T>> {
+    /// Returns an iterator over every slot of every entity matching the
+    /// fetcher, along with the entity and the index of the slot. Slots are
+    /// visited archetype by archetype, and in order within each entity.
+    ///
+    /// Use iterator adapters to skip slots, e.g. `.filter(|(_, _, slot)|
+    /// slot.is_some())`.
+    pub fn iter_slots(&self) -> impl Iterator<Item = (EntityId, usize, &T)> + '_ {
+        self.iter().flatten()
+    }
+}
+
+impl<C: SlotContainer<T>, T: 'static> Fetcher<'_, SlotsMut<C, T>> {
+    /// Like [`iter_slots`](Fetcher::iter_slots), but returns the slots
+    /// mutably.
+    pub fn iter_slots_mut(&mut self) -> impl Iterator<Item = (EntityId, usize, &mut T)> + '_ {
+        self.iter_mut().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Component)]
+    struct Inventory([Option<u32>; 8]);
+
+    impl SlotContainer<Option<u32>> for Inventory {
+        fn slots(&self) -> &[Option<u32>] {
+            &self.0
+        }
+
+        fn slots_mut(&mut self) -> &mut [Option<u32>] {
+            &mut self.0
+        }
+    }
+
+    #[derive(Component)]
+    struct Bag(Vec<u32>);
+
+    impl SlotContainer<u32> for Bag {
+        fn slots(&self) -> &[u32] {
+            &self.0
+        }
+
+        fn slots_mut(&mut self) -> &mut [u32] {
+            &mut self.0
+        }
+    }
+
+    #[derive(Component)]
+    struct Marker;
+
+    #[derive(Event)]
+    struct E;
+
+    #[test]
+    fn flattened_slots() {
+        let mut world = World::new();
+
+        for i in 0..20_u32 {
+            let e = world.spawn();
+            let mut slots = [None; 8];
+
+            for (j, slot) in slots.iter_mut().enumerate() {
+                if (i + j as u32) % 3 == 1 {
+                    *slot = Some(i * 10 + j as u32);
+                }
+            }
+
+            world.insert(e, Inventory(slots));
+
+            // Split the entities across archetypes.
+            if i % 2 == 0 {
+                world.insert(e, Marker);
+            }
+        }
+
+        world.add_handler(
+            |_: Receiver<E>,
+             slots: Fetcher<Slots<Inventory, Option<u32>>>,
+             inventories: Fetcher<(EntityId, &Inventory)>| {
+                let mut expected = vec![];
+
+                for (id, inv) in &inventories {
+                    for (i, slot) in inv.0.iter().enumerate() {
+                        if let Some(item) = slot {
+                            expected.push((id, i, *item));
+                        }
+                    }
+                }
+
+                let mut flattened: Vec<_> = slots
+                    .iter_slots()
+                    .filter_map(|(id, i, slot)| Some((id, i, (*slot)?)))
+                    .collect();
+
+                expected.sort_unstable();
+                flattened.sort_unstable();
+
+                assert!(!expected.is_empty());
+                assert_eq!(flattened, expected);
+                assert_eq!(slots.iter_slots().count(), 20 * 8);
+            },
+        );
+
+        world.send(E);
+    }
+
+    #[test]
+    fn mutate_slots() {
+        let mut world = World::new();
+
+        let empty = world.spawn();
+        world.insert(empty, Bag(vec![]));
+
+        let full = world.spawn();
+        world.insert(full, Bag(vec![1, 2, 3]));
+
+        world.add_handler(|_: Receiver<E>, mut f: Fetcher<SlotsMut<Bag, u32>>| {
+            for (_, i, slot) in f.iter_slots_mut() {
+                *slot += i as u32 * 100;
+            }
+        });
+
+        world.send(E);
+
+        assert!(world.get::<Bag>(empty).unwrap().0.is_empty());
+        assert_eq!(world.get::<Bag>(full).unwrap().0, [1, 102, 203]);
+    }
+}
@@ -0,0 +1,689 @@
+//! Saving and loading the entities and components of a [`World`] with
+//! [`serde`].
+//!
+//! Component types opt into serialization with
+//! [`World::add_serde_component`], which gives each one a key that must stay
+//! the same between the world that is saved and the world that is loaded.
+//! [`World::serialize`] then writes every entity and the values of its
+//! serializable components, and [`World::deserialize`] rebuilds them in
+//! another world.
+//!
+//! Entity IDs are saved with their generation counts, along with the
+//! generation of every vacant entity slot. An [`EntityId`] stored in a
+//! component therefore refers to the same entity after loading, and the ID of
+//! an entity which was despawned before saving stays invalid.
+//!
+//! Only entities and component values are saved. Handlers, events, and
+//! resources are not, and must be added to the loading world separately.
+//!
+//! # Unregistered components
+//!
+//! Components without a key are skipped. Their entities are still saved, but
+//! are loaded without them. Loading a snapshot which mentions a key that
+//! isn't registered in the loading world fails.
+//!
+//! # Format
+//!
+//! A world is serialized as a pair of the generation of every entity slot and
+//! a sequence of archetypes. Each archetype is a triple of its component keys,
+//! its entity IDs, and a sequence holding one column of values per key.
+//! Entity IDs are `(index, generation)` pairs. Empty archetypes are omitted.
+//!
+//! # Examples
+//!
+//! ```
+//! use evenio::prelude::*;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Component, Serialize, Deserialize, PartialEq, Debug)]
+//! struct Health(u32);
+//!
+//! let mut world = World::new();
+//! world.add_serde_component::<Health>("health");
+//!
+//! let e = world.spawn();
+//! world.insert(e, Health(100));
+//!
+//! let json = world.serialize(serde_json::value::Serializer).unwrap();
+//!
+//! let mut loaded = World::new();
+//! loaded.add_serde_component::<Health>("health");
+//! loaded.deserialize(json).unwrap();
+//!
+//! assert_eq!(loaded.get::<Health>(e), Some(&Health(100)));
+//! ```
+//!
+//! [`World`]: crate::world::World
+//! [`World::add_serde_component`]: crate::world::World::add_serde_component
+//! [`World::serialize`]: crate::world::World::serialize
+//! [`World::deserialize`]: crate::world::World::deserialize
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+use core::fmt;
+use core::ptr::NonNull;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IgnoredAny, SeqAccess, Visitor};
+use serde::ser::{self, SerializeSeq, SerializeTuple};
+use serde::{Serialize, Serializer};
+
+use crate::archetype::{Archetype, Archetypes};
+use crate::blob_vec::BlobVec;
+use crate::component::{Component, ComponentIdx, ComponentInfo, Components};
+use crate::entity::{Entities, EntityId};
+
+/// Serialization functions of a component, stored in its
+/// [`ComponentInfo`].
+pub(crate) struct ComponentSerde {
+    key: &'static str,
+    /// Reinterprets a pointer to a value of the component.
+    as_serialize: for<'a> unsafe fn(&'a NonNull<u8>) -> &'a dyn erased_serde::Serialize,
+    /// Deserializes one value of the component and pushes it onto a
+    /// [`BlobVec`] of the component.
+    deserialize_into: DeserializeIntoFn,
+}
+
+type DeserializeIntoFn = unsafe fn(
+    &mut dyn erased_serde::Deserializer<'_>,
+    &mut BlobVec,
+) -> Result<(), erased_serde::Error>;
+
+impl ComponentSerde {
+    pub(crate) fn new<C>(key: &'static str) -> Self
+    where
+        C: Component + Serialize + DeserializeOwned,
+    {
+        unsafe fn as_serialize<C: Serialize + 'static>(
+            ptr: &NonNull<u8>,
+        ) -> &dyn erased_serde::Serialize {
+            ptr.cast::<C>().as_ref()
+        }
+
+        unsafe fn deserialize_into<C: DeserializeOwned>(
+            deserializer: &mut dyn erased_serde::Deserializer<'_>,
+            out: &mut BlobVec,
+        ) -> Result<(), erased_serde::Error> {
+            let value = erased_serde::deserialize::<C>(deserializer)?;
+            out.push().cast::<C>().as_ptr().write(value);
+            Ok(())
+        }
+
+        Self {
+            key,
+            as_serialize: as_serialize::<C>,
+            deserialize_into: deserialize_into::<C>,
+        }
+    }
+
+    pub(crate) fn key(&self) -> &'static str {
+        self.key
+    }
+}
+
+impl fmt::Debug for ComponentSerde {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ComponentSerde")
+            .field("key", &self.key)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Serializes the entities and serializable components of a world.
+pub(crate) struct WorldSer<'a> {
+    pub(crate) entities: &'a Entities,
+    pub(crate) archetypes: &'a Archetypes,
+    pub(crate) components: &'a Components,
+}
+
+impl Serialize for WorldSer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(2)?;
+        tuple.serialize_element(&Generations(self.entities))?;
+        tuple.serialize_element(&ArchetypesSer {
+            archetypes: self.archetypes,
+            components: self.components,
+        })?;
+        tuple.end()
+    }
+}
+
+struct Generations<'a>(&'a Entities);
+
+impl Serialize for Generations<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.generations())
+    }
+}
+
+struct ArchetypesSer<'a> {
+    archetypes: &'a Archetypes,
+    components: &'a Components,
+}
+
+impl Serialize for ArchetypesSer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let nonempty = || self.archetypes.iter().filter(|a| a.entity_count() > 0);
+
+        let mut seq = serializer.serialize_seq(Some(nonempty().count()))?;
+
+        for arch in nonempty() {
+            seq.serialize_element(&ArchetypeSer {
+                arch,
+                components: self.components,
+            })?;
+        }
+
+        seq.end()
+    }
+}
+
+struct ArchetypeSer<'a> {
+    arch: &'a Archetype,
+    components: &'a Components,
+}
+
+impl<'a> ArchetypeSer<'a> {
+    /// Returns the serializable columns of the archetype along with the
+    /// components they belong to.
+    fn columns(
+        &self,
+    ) -> impl Iterator<Item = (NonNull<u8>, &'a ComponentInfo, &'a ComponentSerde)> {
+        let components = self.components;
+
+        self.arch
+            .component_indices()
+            .iter()
+            .zip(self.arch.columns())
+            .filter_map(move |(&idx, col)| {
+                let info = components.get_by_index(idx)?;
+                Some((col.data(), info, info.serde.as_ref()?))
+            })
+    }
+}
+
+impl Serialize for ArchetypeSer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(3)?;
+
+        tuple.serialize_element(&KeysSer(self))?;
+        tuple.serialize_element(self.arch.entity_ids())?;
+        tuple.serialize_element(&ColumnsSer(self))?;
+
+        tuple.end()
+    }
+}
+
+struct KeysSer<'a, 'b>(&'b ArchetypeSer<'a>);
+
+impl Serialize for KeysSer<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.columns().map(|(_, _, serde)| serde.key()))
+    }
+}
+
+struct ColumnsSer<'a, 'b>(&'b ArchetypeSer<'a>);
+
+impl Serialize for ColumnsSer<'_, '_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let len = self.0.arch.entity_count() as usize;
+
+        serializer.collect_seq(self.0.columns().map(|(data, info, serde)| ColumnSer {
+            data,
+            stride: info.layout().size(),
+            len,
+            serde,
+        }))
+    }
+}
+
+struct ColumnSer<'a> {
+    data: NonNull<u8>,
+    stride: usize,
+    len: usize,
+    serde: &'a ComponentSerde,
+}
+
+impl Serialize for ColumnSer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len))?;
+
+        for row in 0..self.len {
+            // SAFETY: The column holds `len` values of the component.
+            let ptr = unsafe { NonNull::new_unchecked(self.data.as_ptr().add(row * self.stride)) };
+            let value = unsafe { (self.serde.as_serialize)(&ptr) };
+
+            seq.serialize_element(&ErasedSer(value))?;
+        }
+
+        seq.end()
+    }
+}
+
+struct ErasedSer<'a>(&'a dyn erased_serde::Serialize);
+
+impl Serialize for ErasedSer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        erased_serde::serialize(self.0, serializer).map_err(ser::Error::custom)
+    }
+}
+
+/// A deserialized world, validated against the components of the world being
+/// loaded.
+pub(crate) struct Snapshot {
+    pub(crate) generations: Vec<u32>,
+    pub(crate) archetypes: Vec<ArchetypeSnapshot>,
+}
+
+pub(crate) struct ArchetypeSnapshot {
+    pub(crate) entities: Vec<EntityId>,
+    /// Columns sorted by component index, each holding a value for every
+    /// entity.
+    pub(crate) columns: Vec<(ComponentIdx, BlobVec)>,
+}
+
+/// Deserializes a [`Snapshot`] using the serialization functions in
+/// `components`.
+pub(crate) struct SnapshotSeed<'a> {
+    pub(crate) components: &'a Components,
+}
+
+impl<'de> DeserializeSeed<'de> for SnapshotSeed<'_> {
+    type Value = Snapshot;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Snapshot, D::Error> {
+        deserializer.deserialize_tuple(2, self)
+    }
+}
+
+impl<'de> Visitor<'de> for SnapshotSeed<'_> {
+    type Value = Snapshot;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a world snapshot")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Snapshot, A::Error> {
+        let generations: Vec<u32> = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+
+        let keys = self
+            .components
+            .iter()
+            .filter_map(|info| Some((info.serde.as_ref()?.key(), info)))
+            .collect();
+
+        let mut loader = Loader {
+            keys,
+            generations: &generations,
+            loaded: vec![false; generations.len()],
+        };
+
+        let archetypes = seq
+            .next_element_seed(ArchetypesSeed(&mut loader))?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+
+        let live = generations.iter().filter(|&&g| g % 2 == 1).count();
+        let loaded = loader.loaded.iter().filter(|&&l| l).count();
+
+        if live != loaded {
+            return Err(de::Error::custom(format_args!(
+                "{} live entities have no archetype",
+                live - loaded
+            )));
+        }
+
+        Ok(Snapshot {
+            generations,
+            archetypes,
+        })
+    }
+}
+
+struct Loader<'a> {
+    keys: BTreeMap<&'a str, &'a ComponentInfo>,
+    generations: &'a [u32],
+    /// Whether each entity slot was found in an archetype.
+    loaded: Vec<bool>,
+}
+
+struct ArchetypesSeed<'a, 'b>(&'b mut Loader<'a>);
+
+impl<'de> DeserializeSeed<'de> for ArchetypesSeed<'_, '_> {
+    type Value = Vec<ArchetypeSnapshot>;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ArchetypesSeed<'_, '_> {
+    type Value = Vec<ArchetypeSnapshot>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a sequence of archetypes")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut archetypes = vec![];
+
+        while let Some(arch) = seq.next_element_seed(ArchetypeSeed(&mut *self.0))? {
+            archetypes.push(arch);
+        }
+
+        Ok(archetypes)
+    }
+}
+
+struct ArchetypeSeed<'a, 'b>(&'b mut Loader<'a>);
+
+impl<'de> DeserializeSeed<'de> for ArchetypeSeed<'_, '_> {
+    type Value = ArchetypeSnapshot;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_tuple(3, self)
+    }
+}
+
+impl<'de> Visitor<'de> for ArchetypeSeed<'_, '_> {
+    type Value = ArchetypeSnapshot;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an archetype")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let keys: Vec<String> = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+
+        let mut infos = Vec::with_capacity(keys.len());
+
+        for key in &keys {
+            let Some(&info) = self.0.keys.get(key.as_str()) else {
+                return Err(de::Error::custom(format_args!(
+                    "unknown component key `{key}`"
+                )));
+            };
+
+            if infos.iter().any(|i: &&ComponentInfo| i.id() == info.id()) {
+                return Err(de::Error::custom(format_args!(
+                    "duplicate component key `{key}`"
+                )));
+            }
+
+            infos.push(info);
+        }
+
+        let entities: Vec<EntityId> = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+
+        for &id in &entities {
+            let idx = id.index().0 as usize;
+
+            if self.0.generations.get(idx) != Some(&id.generation()) || self.0.loaded[idx] {
+                return Err(de::Error::custom(format_args!(
+                    "entity {id:?} is not live or appears more than once"
+                )));
+            }
+
+            self.0.loaded[idx] = true;
+        }
+
+        let data = seq
+            .next_element_seed(ColumnsSeed {
+                infos: &infos,
+                len: entities.len(),
+            })?
+            .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+
+        let mut columns: Vec<_> = infos
+            .iter()
+            .map(|info| info.id().index())
+            .zip(data)
+            .collect();
+
+        columns.sort_unstable_by_key(|&(idx, _)| idx);
+
+        Ok(ArchetypeSnapshot { entities, columns })
+    }
+}
+
+struct ColumnsSeed<'a> {
+    infos: &'a [&'a ComponentInfo],
+    len: usize,
+}
+
+impl<'de> DeserializeSeed<'de> for ColumnsSeed<'_> {
+    type Value = Vec<BlobVec>;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ColumnsSeed<'_> {
+    type Value = Vec<BlobVec>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} columns", self.infos.len())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut columns = Vec::with_capacity(self.infos.len());
+
+        for (i, &info) in self.infos.iter().enumerate() {
+            let column = seq
+                .next_element_seed(ColumnSeed {
+                    info,
+                    len: self.len,
+                })?
+                .ok_or_else(|| de::Error::invalid_length(i, &self))?;
+
+            columns.push(column);
+        }
+
+        if seq.next_element::<IgnoredAny>()?.is_some() {
+            return Err(de::Error::invalid_length(self.infos.len() + 1, &self));
+        }
+
+        Ok(columns)
+    }
+}
+
+struct ColumnSeed<'a> {
+    info: &'a ComponentInfo,
+    len: usize,
+}
+
+impl<'de> DeserializeSeed<'de> for ColumnSeed<'_> {
+    type Value = BlobVec;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<BlobVec, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for ColumnSeed<'_> {
+    type Value = BlobVec;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} values of `{}`", self.len, self.info.name())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<BlobVec, A::Error> {
+        // SAFETY: The drop function belongs to the component.
+        let mut column = unsafe { BlobVec::new(self.info.layout(), self.info.drop()) };
+        column.reserve(self.len);
+
+        // SAFETY: Components with a key always have serialization functions.
+        let deserialize_into =
+            unsafe { self.info.serde.as_ref().unwrap_unchecked() }.deserialize_into;
+
+        while column.len() < self.len {
+            let seed = ValueSeed {
+                deserialize_into,
+                out: &mut column,
+            };
+
+            if seq.next_element_seed(seed)?.is_none() {
+                return Err(de::Error::invalid_length(column.len(), &self));
+            }
+        }
+
+        if seq.next_element::<IgnoredAny>()?.is_some() {
+            return Err(de::Error::invalid_length(self.len + 1, &self));
+        }
+
+        Ok(column)
+    }
+}
+
+struct ValueSeed<'a> {
+    deserialize_into: DeserializeIntoFn,
+    out: &'a mut BlobVec,
+}
+
+impl<'de> DeserializeSeed<'de> for ValueSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        let mut erased = <dyn erased_serde::Deserializer<'de>>::erase(deserializer);
+
+        // SAFETY: `out` holds values of the component `deserialize_into` belongs
+        // to.
+        unsafe { (self.deserialize_into)(&mut erased, self.out) }.map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::{String, ToString};
+    use alloc::vec::Vec;
+
+    use serde::{Deserialize, Serialize};
+
+    use crate::prelude::*;
+
+    #[derive(Component, Serialize, Deserialize, PartialEq, Debug)]
+    struct Name(String);
+
+    #[derive(Component, Serialize, Deserialize, PartialEq, Debug)]
+    struct Parent(EntityId);
+
+    #[derive(Component, Serialize, Deserialize, PartialEq, Debug)]
+    struct Marker;
+
+    #[derive(Component, PartialEq, Debug)]
+    struct Unsaved(u32);
+
+    fn register(world: &mut World) {
+        world.add_serde_component::<Name>("name");
+        world.add_serde_component::<Parent>("parent");
+        world.add_serde_component::<Marker>("marker");
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut world = World::new();
+        register(&mut world);
+
+        let ids: Vec<_> = (0..5).map(|_| world.spawn()).collect();
+        let [root, child, dead, bare, unsaved] = ids[..] else {
+            unreachable!()
+        };
+
+        world.insert(root, Name("root".into()));
+        world.insert(child, Name("child".into()));
+        world.insert(child, Parent(root));
+        world.insert(child, Marker);
+        world.insert(dead, Parent(root));
+        world.insert(unsaved, Unsaved(1));
+        world.insert(unsaved, Parent(dead));
+        world.despawn(dead);
+
+        let json = world.serialize(serde_json::value::Serializer).unwrap();
+
+        let mut loaded = World::new();
+        register(&mut loaded);
+        loaded.deserialize(json).unwrap();
+
+        assert_eq!(loaded.entities().len(), 4);
+        assert_eq!(loaded.get::<Name>(root), Some(&Name("root".into())));
+        assert_eq!(loaded.get::<Name>(child), Some(&Name("child".into())));
+        assert_eq!(loaded.get::<Parent>(child), Some(&Parent(root)));
+        assert_eq!(loaded.get::<Marker>(child), Some(&Marker));
+        assert!(loaded.entities().contains(bare));
+
+        // Unregistered components are skipped, but the entity is kept.
+        assert_eq!(loaded.get::<Unsaved>(unsaved), None);
+        assert_eq!(loaded.get::<Parent>(unsaved), Some(&Parent(dead)));
+
+        // The slot of the despawned entity is reused with a new generation.
+        assert!(!loaded.entities().contains(dead));
+        let new = loaded.spawn();
+        assert_eq!(new.index(), dead.index());
+        assert_ne!(new, dead);
+        assert_eq!(new, world.spawn());
+    }
+
+    #[test]
+    fn invalid_snapshot() {
+        let mut world = World::new();
+        register(&mut world);
+
+        let e = world.spawn();
+        world.insert(e, Name("e".into()));
+
+        let json = world.serialize(serde_json::value::Serializer).unwrap();
+
+        // Keys must be registered in the loading world.
+        let mut loaded = World::new();
+        loaded.add_serde_component::<Parent>("parent");
+
+        let err = loaded.deserialize(json.clone()).unwrap_err();
+        assert!(err.to_string().contains("unknown component key `name`"));
+        assert_eq!(loaded.entities().len(), 0);
+
+        // Every live entity must be in an archetype.
+        let mut loaded = World::new();
+        register(&mut loaded);
+
+        let err = loaded
+            .deserialize(serde_json::json!([[1, 1], [[["name"], [[0, 1]], [["e"]]]]]))
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("1 live entities have no archetype"));
+
+        // Columns must have a value for every entity.
+        let err = loaded
+            .deserialize(serde_json::json!([[1], [[["name"], [[0, 1]], [[]]]]]))
+            .unwrap_err();
+        assert!(err.to_string().contains("invalid length 0"));
+
+        loaded.deserialize(json).unwrap();
+        assert_eq!(loaded.get::<Name>(e), Some(&Name("e".into())));
+    }
+
+    #[test]
+    #[should_panic = "component key `name` is already used by component"]
+    fn duplicate_key() {
+        let mut world = World::new();
+
+        world.add_serde_component::<Name>("name");
+        world.add_serde_component::<Marker>("name");
+    }
+}
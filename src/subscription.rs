@@ -0,0 +1,445 @@
+//! Notifications for entities entering and leaving a [`Query`].
+//!
+//! See [`World::subscribe`] for more information.
+//!
+//! [`World::subscribe`]: crate::world::World::subscribe
+
+use alloc::collections::BTreeMap;
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, vec::Vec};
+use core::marker::PhantomData;
+use core::panic::{RefUnwindSafe, UnwindSafe};
+use core::{fmt, mem};
+
+use crate::archetype::Archetype;
+use crate::assert::UnwrapDebugChecked;
+use crate::component::ComponentIdx;
+use crate::entity::EntityId;
+use crate::event::{Event, EventId, EventQueue};
+use crate::query::Query;
+use crate::slot_map::{Key, SlotMap};
+
+/// Handle to a query subscription created with [`World::subscribe`].
+///
+/// [`World::subscribe`]: crate::world::World::subscribe
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct QuerySubscription(Key);
+
+/// A change in whether an entity matches a subscribed query.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum QueryTransition {
+    /// The entity started matching the query.
+    Entered(EntityId),
+    /// The entity stopped matching the query.
+    Exited(EntityId),
+}
+
+/// An [`Event`] sent when an entity starts matching the subscribed query `Q`.
+///
+/// This event is untargeted so that it is still delivered when the entity no
+/// longer exists, such as after [`QueryExited`] from a despawn.
+pub struct QueryEntered<Q>(pub EntityId, PhantomData<fn() -> Q>);
+
+/// An [`Event`] sent when an entity stops matching the subscribed query `Q`.
+///
+/// This event is untargeted so that it is delivered even if the entity was
+/// despawned.
+pub struct QueryExited<Q>(pub EntityId, PhantomData<fn() -> Q>);
+
+macro_rules! impl_transition_event {
+    ($name:ident) => {
+        impl<Q> $name<Q> {
+            /// Creates a new instance.
+            pub const fn new(entity: EntityId) -> Self {
+                Self(entity, PhantomData)
+            }
+        }
+
+        impl<Q> Clone for $name<Q> {
+            fn clone(&self) -> Self {
+                *self
+            }
+        }
+
+        impl<Q> Copy for $name<Q> {}
+
+        impl<Q> fmt::Debug for $name<Q> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_tuple(stringify!($name)).field(&self.0).finish()
+            }
+        }
+
+        impl<Q: Query + 'static> Event for $name<Q> {}
+    };
+}
+
+impl_transition_event!(QueryEntered);
+impl_transition_event!(QueryExited);
+
+/// Pushes a transition event for the query onto the event queue.
+type PushFn = unsafe fn(&mut EventQueue, EntityId, u32);
+
+struct Subscription {
+    /// Returns whether the query matches the archetype.
+    matches: Box<dyn FnMut(&Archetype) -> bool + Send + Sync>,
+    records: Vec<QueryTransition>,
+    entered: (EventId, PushFn),
+    exited: (EventId, PushFn),
+    /// The components the query depends on, or `None` if they are unknown.
+    components: Option<Vec<ComponentIdx>>,
+    /// Whether the query matches the empty archetype.
+    matches_empty: bool,
+}
+
+/// All query subscriptions in a world.
+///
+/// Whether a query matches an archetype only depends on the components it
+/// references, so subscriptions are indexed by those components. A move
+/// between two archetypes only checks the subscriptions referencing a
+/// component which differs between them.
+#[derive(Default)]
+pub(crate) struct Subscriptions {
+    subs: SlotMap<Subscription>,
+    /// Subscriptions referencing each component.
+    by_component: BTreeMap<ComponentIdx, Vec<Key>>,
+    /// Subscriptions matching the empty archetype. These also match every
+    /// archetype without any of their components, so they are checked
+    /// whenever an entity is spawned or despawned.
+    matches_empty: Vec<Key>,
+    /// Subscriptions whose components are unknown, which are checked on every
+    /// move.
+    unindexed: Vec<Key>,
+    /// Reused buffer of the subscriptions to check in [`notify`].
+    ///
+    /// [`notify`]: Self::notify
+    candidates: Vec<Key>,
+}
+
+impl Subscriptions {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a subscription to `Q`. `components` are the components the query
+    /// depends on, or `None` if they are unknown. `empty` is the empty
+    /// archetype.
+    pub(crate) fn add<Q: Query + 'static>(
+        &mut self,
+        mut state: Q::State,
+        components: Option<Vec<ComponentIdx>>,
+        empty: &Archetype,
+        entered: EventId,
+        exited: EventId,
+    ) -> QuerySubscription {
+        let matches_empty = Q::new_arch_state(empty, &mut state).is_some();
+
+        let Some(k) = self.subs.insert(Subscription {
+            matches: Box::new(move |arch| Q::new_arch_state(arch, &mut state).is_some()),
+            records: Vec::new(),
+            entered: (entered, |queue, id, idx| unsafe {
+                queue.push_front(QueryEntered::<Q>::new(id), idx)
+            }),
+            exited: (exited, |queue, id, idx| unsafe {
+                queue.push_front(QueryExited::<Q>::new(id), idx)
+            }),
+            components: components.clone(),
+            matches_empty,
+        }) else {
+            panic!("too many subscriptions")
+        };
+
+        match components {
+            Some(components) => {
+                for idx in components {
+                    self.by_component.entry(idx).or_default().push(k);
+                }
+            }
+            None => self.unindexed.push(k),
+        }
+
+        if matches_empty {
+            self.matches_empty.push(k);
+        }
+
+        QuerySubscription(k)
+    }
+
+    pub(crate) fn remove(&mut self, sub: QuerySubscription) -> bool {
+        let Some(removed) = self.subs.remove(sub.0) else {
+            return false;
+        };
+
+        match removed.components {
+            Some(components) => {
+                for idx in components {
+                    if let Some(keys) = self.by_component.get_mut(&idx) {
+                        keys.retain(|&k| k != sub.0);
+
+                        if keys.is_empty() {
+                            self.by_component.remove(&idx);
+                        }
+                    }
+                }
+            }
+            None => self.unindexed.retain(|&k| k != sub.0),
+        }
+
+        if removed.matches_empty {
+            self.matches_empty.retain(|&k| k != sub.0);
+        }
+
+        true
+    }
+
+    pub(crate) fn drain(&mut self, sub: QuerySubscription) -> Vec<QueryTransition> {
+        self.subs
+            .get_mut(sub.0)
+            .map(|s| mem::take(&mut s.records))
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.subs.len() == 0
+    }
+
+    /// Records transitions of `entity` moving from archetype `src` to
+    /// archetype `dst` for every affected subscription, and pushes the
+    /// corresponding events onto the event queue. `None` indicates the entity
+    /// did not exist before or after the move.
+    ///
+    /// `is_live_event` is used to check that the transition events have not
+    /// been removed from the world.
+    pub(crate) fn notify(
+        &mut self,
+        entity: EntityId,
+        src: Option<&Archetype>,
+        dst: Option<&Archetype>,
+        queue: &mut EventQueue,
+        mut is_live_event: impl FnMut(EventId) -> bool,
+    ) {
+        let mut candidates = mem::take(&mut self.candidates);
+        candidates.extend_from_slice(&self.unindexed);
+
+        let mut add_component = |idx: &ComponentIdx| {
+            if let Some(keys) = self.by_component.get(idx) {
+                candidates.extend_from_slice(keys);
+            }
+        };
+
+        match (src, dst) {
+            (Some(src), Some(dst)) => {
+                let (src_components, dst_components) =
+                    (src.component_indices(), dst.component_indices());
+
+                // Component indices of an archetype are sorted.
+                src_components
+                    .iter()
+                    .filter(|idx| dst_components.binary_search(idx).is_err())
+                    .for_each(&mut add_component);
+                dst_components
+                    .iter()
+                    .filter(|idx| src_components.binary_search(idx).is_err())
+                    .for_each(&mut add_component);
+            }
+            (Some(arch), None) | (None, Some(arch)) => {
+                arch.component_indices().iter().for_each(&mut add_component);
+                candidates.extend_from_slice(&self.matches_empty);
+            }
+            (None, None) => {}
+        }
+
+        // Check subscriptions once each, in the order they were added.
+        candidates.sort_unstable_by_key(|k| k.index());
+        candidates.dedup();
+
+        for &k in &candidates {
+            // SAFETY: Candidates are removed from the index along with the subscription.
+            let sub = unsafe { self.subs.get_mut(k).unwrap_debug_checked() };
+
+            let before = src.is_some_and(|arch| (sub.matches)(arch));
+            let after = dst.is_some_and(|arch| (sub.matches)(arch));
+
+            let (record, (event_id, push)) = match (before, after) {
+                (false, true) => (QueryTransition::Entered(entity), sub.entered),
+                (true, false) => (QueryTransition::Exited(entity), sub.exited),
+                _ => continue,
+            };
+
+            sub.records.push(record);
+
+            if is_live_event(event_id) {
+                // SAFETY: The event exists and was registered with the type pushed by
+                // `push`.
+                unsafe { push(queue, entity, event_id.index().as_u32()) };
+            }
+        }
+
+        candidates.clear();
+        self.candidates = candidates;
+    }
+}
+
+impl fmt::Debug for Subscriptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subscriptions")
+            .field("len", &self.subs.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl UnwindSafe for Subscriptions {}
+impl RefUnwindSafe for Subscriptions {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Component)]
+    struct A;
+
+    #[derive(Component)]
+    struct B;
+
+    #[test]
+    fn transitions() {
+        let mut world = World::new();
+
+        let sub = world.subscribe::<(With<&A>, Not<&B>)>();
+        let empty = world.subscribe::<Not<&A>>();
+
+        let e = world.spawn();
+        assert_eq!(
+            world.drain_subscription(empty),
+            [QueryTransition::Entered(e)]
+        );
+
+        world.insert(e, A);
+        world.insert(e, B);
+        world.remove::<B>(e);
+        world.despawn(e);
+
+        assert_eq!(
+            world.drain_subscription(sub),
+            [
+                QueryTransition::Entered(e),
+                QueryTransition::Exited(e),
+                QueryTransition::Entered(e),
+                QueryTransition::Exited(e),
+            ]
+        );
+        assert_eq!(
+            world.drain_subscription(empty),
+            [QueryTransition::Exited(e)]
+        );
+
+        assert!(world.unsubscribe(sub));
+        assert!(world.drain_subscription(sub).is_empty());
+    }
+
+    #[test]
+    fn transitions_of_indexed_and_unindexed() {
+        #[derive(Component)]
+        struct C;
+
+        let mut world = World::new();
+
+        let sub = world.subscribe::<Or<With<&A>, Not<&B>>>();
+        // Conflicting access, so the components are unknown.
+        let unindexed = world.subscribe::<(&A, &mut A)>();
+
+        let e = world.spawn();
+        world.insert(e, C);
+        world.insert(e, B);
+        world.insert(e, A);
+        world.despawn(e);
+
+        assert_eq!(
+            world.drain_subscription(sub),
+            [
+                QueryTransition::Entered(e),
+                QueryTransition::Exited(e),
+                QueryTransition::Entered(e),
+                QueryTransition::Exited(e),
+            ]
+        );
+        assert_eq!(
+            world.drain_subscription(unindexed),
+            [QueryTransition::Entered(e), QueryTransition::Exited(e)]
+        );
+    }
+
+    #[test]
+    fn index() {
+        fn add<Q: Query + 'static>(
+            world: &mut World,
+            subs: &mut Subscriptions,
+            components: Option<Vec<ComponentIdx>>,
+        ) -> Key {
+            let state = Q::new_state(world);
+            let entered = world.add_event::<QueryEntered<Q>>();
+            let exited = world.add_event::<QueryExited<Q>>();
+
+            subs.add::<Q>(
+                state,
+                components,
+                world.archetypes().empty(),
+                entered,
+                exited,
+            )
+            .0
+        }
+
+        let mut world = World::new();
+        let mut subs = Subscriptions::new();
+
+        let a = world.add_component::<A>().index();
+        let b = world.add_component::<B>().index();
+
+        let with_a = add::<With<&A>>(&mut world, &mut subs, Some(vec![a]));
+        let not_b = add::<Not<&B>>(&mut world, &mut subs, Some(vec![b]));
+        let unknown = add::<With<&A>>(&mut world, &mut subs, None);
+
+        assert_eq!(subs.by_component[&a], [with_a]);
+        assert_eq!(subs.by_component[&b], [not_b]);
+        assert_eq!(subs.matches_empty, [not_b]);
+        assert_eq!(subs.unindexed, [unknown]);
+
+        assert!(subs.remove(QuerySubscription(with_a)));
+        assert!(subs.remove(QuerySubscription(not_b)));
+        assert!(subs.remove(QuerySubscription(unknown)));
+        assert!(!subs.remove(QuerySubscription(unknown)));
+
+        assert!(subs.by_component.is_empty());
+        assert!(subs.matches_empty.is_empty());
+        assert!(subs.unindexed.is_empty());
+    }
+
+    #[test]
+    fn reentrant_removal() {
+        let mut world = World::new();
+
+        let sub = world.subscribe::<With<&A>>();
+
+        world.add_handler(
+            |r: Receiver<QueryEntered<With<&'static A>>>, mut s: Sender<Remove<A>>| {
+                s.remove::<A>(r.event.0);
+            },
+        );
+
+        let sub2 = world.subscribe::<With<&A>>();
+
+        let e = world.spawn();
+        world.insert(e, A);
+
+        assert!(world.get::<A>(e).is_none());
+        assert_eq!(
+            world.drain_subscription(sub),
+            [QueryTransition::Entered(e), QueryTransition::Exited(e)]
+        );
+        assert_eq!(
+            world.drain_subscription(sub2),
+            [QueryTransition::Entered(e), QueryTransition::Exited(e)]
+        );
+    }
+}
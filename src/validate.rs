@@ -0,0 +1,295 @@
+//! Validation of events before they are handled.
+//!
+//! See [`World::set_event_validator`] for more information.
+//!
+//! [`World::set_event_validator`]: crate::world::World::set_event_validator
+
+use alloc::borrow::Cow;
+#[cfg(not(feature = "std"))]
+use alloc::boxed::Box;
+use core::fmt;
+use core::panic::{RefUnwindSafe, UnwindSafe};
+use core::ptr::{self, NonNull};
+
+use crate::event::{Event, EventId, EventQueue};
+
+/// An error returned by the validator of an event. Contains a description of
+/// what is wrong with the event.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct ValidationError(pub Cow<'static, str>);
+
+impl ValidationError {
+    /// Creates a new error with the given description.
+    pub fn new<D: Into<Cow<'static, str>>>(description: D) -> Self {
+        Self(description.into())
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", &self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+#[cfg_attr(docsrs, doc(cfg(feature = "std")))]
+impl core::error::Error for ValidationError {}
+
+/// The event sent in place of an event of type `E` which failed validation,
+/// if any handler receives it. See [`World::set_event_validator`].
+///
+/// `Invalid<E>` is untargeted even if `E` is targeted.
+///
+/// [`World::set_event_validator`]: crate::world::World::set_event_validator
+#[derive(Debug)]
+pub struct Invalid<E> {
+    /// The error returned by the validator.
+    pub error: ValidationError,
+    /// The event which failed validation.
+    pub event: E,
+}
+
+impl<E: Event> Event for Invalid<E> {}
+
+/// Statistics of the validation of an event, returned by
+/// [`World::validation_stats`].
+///
+/// [`World::validation_stats`]: crate::world::World::validation_stats
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ValidationStats {
+    /// Number of events which passed validation.
+    pub passed: u64,
+    /// Number of invalid events which were dropped because no handler
+    /// receives [`Invalid`].
+    pub dropped: u64,
+    /// Number of invalid events which were sent as [`Invalid`].
+    pub dead_lettered: u64,
+}
+
+/// What happened to an event after validation.
+pub(crate) enum Validity {
+    Valid,
+    /// The event is invalid and should be dropped.
+    Dropped,
+    /// The event is invalid and was moved into an [`Invalid`] event.
+    Moved,
+}
+
+type CheckFn = dyn Fn(NonNull<u8>) -> Result<(), ValidationError> + Send + Sync;
+
+/// Moves the event behind the pointer into an [`Invalid`] event and pushes it
+/// to the front of the queue with the given event index.
+type DeadLetterFn = unsafe fn(NonNull<u8>, ValidationError, &mut EventQueue, u32);
+
+/// The validator of an event, stored in the [`EventInfo`] of the event.
+///
+/// [`EventInfo`]: crate::event::EventInfo
+pub(crate) struct Validator {
+    check: Box<CheckFn>,
+    dead_letter: DeadLetterFn,
+    /// The [`Invalid`] event of the validated event.
+    invalid_event: EventId,
+    passed: u64,
+    dropped: u64,
+    dead_lettered: u64,
+}
+
+// The validator is only called while the world is borrowed mutably.
+impl UnwindSafe for Validator {}
+impl RefUnwindSafe for Validator {}
+
+impl Validator {
+    pub(crate) fn new<E, F>(f: F, invalid_event: EventId) -> Self
+    where
+        E: Event,
+        F: Fn(&E) -> Result<(), ValidationError> + Send + Sync + 'static,
+    {
+        Self {
+            check: Box::new(move |ptr| f(unsafe { ptr.cast::<E>().as_ref() })),
+            dead_letter: |ptr, error, queue, idx| unsafe {
+                let event = ptr::read(ptr.cast::<E>().as_ptr());
+                queue.push_front(Invalid { error, event }, idx);
+            },
+            invalid_event,
+            passed: 0,
+            dropped: 0,
+            dead_lettered: 0,
+        }
+    }
+
+    pub(crate) fn stats(&self) -> ValidationStats {
+        ValidationStats {
+            passed: self.passed,
+            dropped: self.dropped,
+            dead_lettered: self.dead_lettered,
+        }
+    }
+
+    pub(crate) fn invalid_event(&self) -> EventId {
+        self.invalid_event
+    }
+
+    /// Runs the validator on `event`. If the event is invalid, it's moved
+    /// into an [`Invalid`] event pushed onto `queue` with the index
+    /// `dead_letter_idx`, or dropped if that is `None`.
+    ///
+    /// # Safety
+    ///
+    /// - `event` must point to an event of the type this was created with.
+    /// - `dead_letter_idx` must be the index of the [`Invalid`] event.
+    /// - The caller must not drop the event if [`Validity::Moved`] is returned.
+    pub(crate) unsafe fn validate(
+        &mut self,
+        event: NonNull<u8>,
+        queue: &mut EventQueue,
+        dead_letter_idx: Option<u32>,
+    ) -> Validity {
+        let Err(error) = (self.check)(event) else {
+            self.passed += 1;
+            return Validity::Valid;
+        };
+
+        match dead_letter_idx {
+            Some(idx) => {
+                (self.dead_letter)(event, error, queue, idx);
+                self.dead_lettered += 1;
+                Validity::Moved
+            }
+            None => {
+                self.dropped += 1;
+                Validity::Dropped
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Validator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Validator")
+            .field("invalid_event", &self.invalid_event)
+            .field("stats", &self.stats())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Event)]
+    struct Move(i32, Arc<()>);
+
+    #[derive(Component, Default)]
+    struct Log(Vec<i32>);
+
+    fn non_negative(e: &Move) -> Result<(), ValidationError> {
+        if e.0 >= 0 {
+            Ok(())
+        } else {
+            Err(ValidationError::new("negative"))
+        }
+    }
+
+    #[test]
+    fn invalid_events_are_diverted() {
+        let mut world = World::new();
+
+        let log = world.spawn();
+        world.insert(log, Log::default());
+
+        let runs = Arc::new(AtomicUsize::new(0));
+        let runs_cloned = runs.clone();
+
+        world.set_event_validator(move |e: &Move| {
+            runs_cloned.fetch_add(1, Ordering::Relaxed);
+            non_negative(e)
+        });
+
+        // Handlers of the same type are only added once.
+        fn receiver<const N: usize>(r: Receiver<Move>, Single(log): Single<&mut Log>) {
+            assert!(r.event.0 >= 0);
+            log.0.push(r.event.0);
+        }
+
+        world.add_handler(receiver::<0>);
+        world.add_handler(receiver::<1>);
+        world.add_handler(receiver::<2>);
+        world.add_handler(receiver::<3>);
+        world.add_handler(receiver::<4>);
+
+        let payload = Arc::new(());
+        let p = payload.clone();
+
+        world.add_handler(
+            move |r: Receiver<Invalid<Move>>, Single(log): Single<&mut Log>| {
+                assert_eq!(r.event.error.0, "negative");
+                // The event was moved into the wrapper, not cloned.
+                assert!(Arc::ptr_eq(&r.event.event.1, &p));
+                log.0.push(r.event.event.0 * 100);
+            },
+        );
+
+        world.send(Move(1, payload.clone()));
+        world.send(Move(-2, payload.clone()));
+        world.send(Move(3, payload.clone()));
+
+        assert_eq!(
+            world.get::<Log>(log).unwrap().0,
+            [1, 1, 1, 1, 1, -200, 3, 3, 3, 3, 3]
+        );
+        assert_eq!(runs.load(Ordering::Relaxed), 3);
+        assert_eq!(
+            world.validation_stats::<Move>(),
+            Some(ValidationStats {
+                passed: 2,
+                dropped: 0,
+                dead_lettered: 1,
+            })
+        );
+
+        // Only the handlers' clone is left.
+        assert_eq!(Arc::strong_count(&payload), 2);
+    }
+
+    #[test]
+    fn invalid_events_without_receivers_are_dropped() {
+        let mut world = World::new();
+
+        world.set_event_validator(non_negative);
+
+        let received = Arc::new(AtomicUsize::new(0));
+        let r = received.clone();
+
+        world.add_handler(move |_: Receiver<Move>| {
+            r.fetch_add(1, Ordering::Relaxed);
+        });
+
+        let payload = Arc::new(());
+
+        world.send(Move(-1, payload.clone()));
+        world.send(Move(1, payload.clone()));
+
+        assert_eq!(received.load(Ordering::Relaxed), 1);
+        assert_eq!(Arc::strong_count(&payload), 1);
+        assert_eq!(
+            world.validation_stats::<Move>(),
+            Some(ValidationStats {
+                passed: 1,
+                dropped: 1,
+                dead_lettered: 0,
+            })
+        );
+
+        // Replacing the validator resets the statistics.
+        world.set_event_validator(|_: &Move| Ok(()));
+        world.send(Move(-1, payload.clone()));
+
+        assert_eq!(received.load(Ordering::Relaxed), 2);
+        assert_eq!(world.validation_stats::<Move>().unwrap().passed, 1);
+    }
+}
@@ -1,30 +1,78 @@
 //! Defines the [`World`] and related APIs.
 
+use alloc::sync::Arc;
 #[cfg(not(feature = "std"))]
-use alloc::{vec, vec::Vec};
+use alloc::{boxed::Box, format, vec, vec::Vec};
 use core::alloc::Layout;
 use core::any::{self, TypeId};
 use core::cell::UnsafeCell;
+#[cfg(feature = "async-bridge")]
+use core::future::Future;
+use core::hash::Hash;
+use core::iter::FusedIterator;
 use core::marker::PhantomData;
-use core::mem;
+use core::mem::ManuallyDrop;
+use core::ops::Deref;
 use core::ptr::NonNull;
+use core::{fmt, mem, ptr};
 
-use crate::archetype::Archetypes;
-use crate::assert::{AssertMutable, UnwrapDebugChecked};
+use ahash::RandomState;
+
+use crate::access::Access;
+use crate::archetype::{
+    Archetype, ArchetypeEdge, ArchetypeIdx, Archetypes, MatchExprIdx, RemovedComponent,
+};
+use crate::arena::Arena;
+use crate::assert::{AssertMutable, GetDebugChecked, UnwrapDebugChecked};
+#[cfg(feature = "async-bridge")]
+use crate::async_bridge::{AsyncBridge, BoxFuture, TaskHandle};
+#[cfg(feature = "tracing")]
+use crate::bit_set::INLINE_BITS;
+use crate::blob_vec::BlobVec;
+use crate::commands::{Command, CommandQueue};
 use crate::component::{
-    AddComponent, Component, ComponentDescriptor, ComponentId, ComponentInfo, Components,
-    RemoveComponent,
+    AddComponent, Bundle, Component, ComponentDescriptor, ComponentId, ComponentIdx, ComponentInfo,
+    ComponentMemory, Components, DropHook, Invariant, QueryDefault, RemoveComponent, SizeWarning,
+    StaleComponentId,
 };
+use crate::dedup::{Dedup, DedupStats, Window};
+use crate::determinism::{Manifest, StableHasher};
+use crate::dirty::{DirtyDelivery, DirtyScope, DirtyTracker, EntitiesDirty};
 use crate::drop::{drop_fn_of, DropFn};
-use crate::entity::{Entities, EntityId, EntityLocation, ReservedEntities};
+use crate::dyn_component::DynComponent;
+use crate::entity::{
+    Entities, EntityId, EntityLocation, OwnedComponent, OwnedEntity, ReservedEntities,
+};
 use crate::event::{
-    AddEvent, Despawn, Event, EventDescriptor, EventId, EventIdx, EventInfo, EventKind, EventMeta,
-    EventPtr, EventQueue, Events, Insert, Remove, RemoveEvent, Spawn, SpawnQueued,
+    insert_bundle_components, AddEvent, ArchetypeMoved, Despawn, Event, EventCursor,
+    EventDescriptor, EventId, EventIdx, EventInfo, EventKind, EventLog, EventMeta, EventOwnership,
+    EventPtr, EventQueue, EventQueueItem, EventRecord, Events, Insert, InsertBundleFn, Remove,
+    RemoveEvent, Spawn, SpawnQueued, MAX_EVENT_DEFERRALS,
 };
+#[cfg(feature = "rayon")]
+use crate::handler::HandlerInfoPtr;
 use crate::handler::{
-    AddHandler, Config, Handler, HandlerId, HandlerInfo, HandlerInfoInner, HandlerList, Handlers,
-    IntoHandler, RemoveHandler,
+    AddHandler, Config, Handler, HandlerId, HandlerInfo, HandlerInfoInner, HandlerList,
+    HandlerParam, Handlers, InitError, IntoHandler, Locals, RemoveHandler, ReplaceHandlerError,
+    ThrottleCounters, ThrottleStats,
+};
+#[cfg(feature = "entity-history")]
+use crate::history::{EntityHistories, TransitionCause, TransitionKind, TransitionRecord};
+use crate::layout_util::pad_to_align;
+use crate::map::{IndexSet, TypeIdMap};
+use crate::morton::{MortonPoint, MortonSort};
+use crate::query::{Query, SnapshotQuery};
+use crate::quota::{Quota, QuotaAction, QuotaExceeded, QuotaPolicy, QuotaStats};
+use crate::reflect::collect_fields;
+use crate::resource::Resources;
+use crate::schedule::{schedule_event_descriptor, RunSchedule, Scheduled, Schedules};
+use crate::shared::{shared_stats, Shared, SharedStats};
+#[cfg(feature = "serde")]
+use crate::snapshot::{ComponentSerde, SnapshotSeed, WorldSer};
+use crate::subscription::{
+    QueryEntered, QueryExited, QuerySubscription, QueryTransition, Subscriptions,
 };
+use crate::validate::{Invalid, ValidationError, ValidationStats, Validator, Validity};
 
 /// A container for all data in the ECS. This includes entities, components,
 /// handlers, and events.
@@ -37,6 +85,220 @@ pub struct World {
     archetypes: Archetypes,
     events: Events,
     event_queue: EventQueue,
+    /// Sequence number of the event currently being handled.
+    event_sequence: u64,
+    /// Length of the event queue when the running handler started. Events
+    /// above this index were sent by the handler.
+    handler_events_from: usize,
+    event_log: EventLog,
+    /// Whether unlisted components and events are rejected. See
+    /// [`World::new_deterministic`].
+    manifest_locked: bool,
+    /// Whether adding components, events, and handlers panics. See
+    /// [`World::freeze_registries`].
+    registries_frozen: bool,
+    subscriptions: Subscriptions,
+    /// Schedules added with [`World::add_to_schedule`].
+    schedules: Schedules,
+    /// Incremented at the start of every cascade of events.
+    cascade: u64,
+    /// Set by [`World::enable_dirty_tracking`].
+    dirty: Option<DirtyTracker>,
+    /// Whether [`World::dedup_window`] was ever called.
+    has_dedup: bool,
+    /// Whether [`World::set_event_validator`] was ever called.
+    has_validators: bool,
+    /// Advanced by [`World::advance_handler_cooldowns`].
+    handler_cooldown_tick: u64,
+    /// Handlers waiting for [`Handler::run_deferred`] to be called.
+    deferred_handlers: Vec<HandlerId>,
+    /// Removed components waiting to be passed to their drop hooks.
+    drop_hook_queue: Vec<(EntityId, RemovedComponent)>,
+    /// Set by [`World::set_remove_on_despawn`].
+    remove_on_despawn: bool,
+    /// Entities whose [`Remove`] events were sent ahead of despawning them,
+    /// along with the sequence number of the [`Despawn`] event which follows.
+    despawning: Vec<(EntityId, u64)>,
+    /// Entities spawned with [`Sender::spawn_scoped`] which are despawned at
+    /// the end of the cascade.
+    ///
+    /// [`Sender::spawn_scoped`]: crate::event::Sender::spawn_scoped
+    scoped_entities: IndexSet<EntityId>,
+    /// Operations recorded by the [`Commands`] of the running handler.
+    ///
+    /// [`Commands`]: crate::commands::Commands
+    commands: CommandQueue,
+    /// Sequence number of the event traced by [`World::send_traced`], along
+    /// with the handlers which have run for it so far.
+    trace: Option<(u64, Vec<HandlerId>)>,
+    /// Events in the queue which were deferred with [`EventMut::defer`].
+    deferred_events: Vec<DeferredEvent>,
+    /// The handler calling a method of the world through its [`WorldMut`],
+    /// along with the length of the event queue when the handler started.
+    exclusive_handler: Option<(HandlerId, usize)>,
+    /// Whether dispatch groups run concurrently. See [`World::send_parallel`].
+    #[cfg(feature = "rayon")]
+    parallel_dispatch: bool,
+    /// Set by [`World::set_component_size_warning`].
+    size_warning: Option<SizeWarning>,
+    /// Whether the notice about component indices exceeding
+    /// [`INLINE_BITS`](crate::bit_set::INLINE_BITS) has been logged.
+    #[cfg(feature = "tracing")]
+    bit_set_spill_noticed: bool,
+    /// Functions for finding the components of
+    /// [`InsertBundle`](crate::event::InsertBundle) events, indexed by
+    /// [`EventKind::InsertBundle::bundle_idx`].
+    bundles: Vec<InsertBundleFn>,
+    /// Maps bundle types to their index in `bundles`.
+    bundle_indices: TypeIdMap<u32>,
+    /// Resources added with [`World::insert_resource`].
+    resources: Resources,
+    #[cfg(feature = "async-bridge")]
+    async_bridge: AsyncBridge,
+    #[cfg(feature = "entity-history")]
+    entity_history: EntityHistories,
+}
+
+/// An event which was deferred with [`EventMut::defer`] and is waiting in the
+/// event queue.
+#[derive(Debug)]
+struct DeferredEvent {
+    /// The [`EventSequence`] of the event.
+    sequence: u64,
+    /// Number of times the event was deferred.
+    count: u32,
+    /// Handlers which already received the event.
+    received: Vec<HandlerId>,
+}
+
+/// Owns the event being dispatched. In case `Handler::run` unwinds, we need to
+/// drop the event we're holding on the stack. The other events in the event
+/// queue will be handled by `World`'s destructor.
+struct EventDropper {
+    event: NonNull<u8>,
+    drop: DropFn,
+    ownership: EventOwnership,
+}
+
+impl EventDropper {
+    /// Extracts the event pointer and drop fn without running the destructor.
+    #[inline]
+    fn unpack(self) -> (NonNull<u8>, DropFn) {
+        let event = self.event;
+        let drop = self.drop;
+        mem::forget(self);
+
+        (event, drop)
+    }
+}
+
+impl Drop for EventDropper {
+    fn drop(&mut self) {
+        if self.ownership != EventOwnership::Owned {
+            if let Some(drop) = self.drop {
+                unsafe { drop(self.event) };
+            }
+        }
+    }
+}
+
+/// What happened when the handlers of an event were run.
+enum Delivery {
+    /// Every handler ran.
+    Finished,
+    /// The target of the event doesn't exist, so no handler ran.
+    NoTarget,
+    /// A handler took ownership of the event.
+    Taken,
+    /// A handler deferred the event with [`EventMut::defer`].
+    ///
+    /// [`EventMut::defer`]: crate::event::EventMut::defer
+    Deferred,
+}
+
+/// Iterator returned by [`World::spawn_batch`].
+#[derive(Debug)]
+pub struct SpawnBatchIter<'a> {
+    world: &'a mut World,
+    remaining: u32,
+    /// Whether to send [`Spawn`] for each entity.
+    observed: bool,
+}
+
+impl Iterator for SpawnBatchIter<'_> {
+    type Item = EntityId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        self.remaining -= 1;
+
+        if self.observed {
+            return Some(self.world.spawn());
+        }
+
+        let id = self.world.reserved_entities.reserve(&self.world.entities);
+
+        self.world.spawn_reserved(
+            #[cfg(feature = "entity-history")]
+            TransitionCause::External,
+        );
+
+        Some(id)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining as usize, Some(self.remaining as usize))
+    }
+}
+
+impl ExactSizeIterator for SpawnBatchIter<'_> {}
+
+impl FusedIterator for SpawnBatchIter<'_> {}
+
+/// Capacities of the buffers reserved by [`World::prewarm`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PrewarmReport {
+    /// Number of events that can be queued.
+    pub event_queue_capacity: usize,
+    /// Number of bytes allocated for storing queued events.
+    pub event_bytes: usize,
+    /// Number of handlers which can defer a delivery to the end of a cascade.
+    pub deferred_handlers_capacity: usize,
+}
+
+/// Memory statistics of the arena of a world created with
+/// [`World::new_in_arena`], returned by [`World::arena_stats`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ArenaStats {
+    /// Size of the arena in bytes.
+    pub capacity: usize,
+    /// Bytes of the arena handed out so far, including abandoned blocks and
+    /// alignment padding.
+    pub used: usize,
+    /// Bytes of the arena in blocks which are no longer used, such as column
+    /// buffers which were outgrown. Arena memory is not reused until the
+    /// world is dropped.
+    pub abandoned: usize,
+    /// Bytes currently allocated from the global allocator because the arena
+    /// was full.
+    pub fallback_bytes: usize,
+    /// Number of allocations which did not fit in the arena.
+    pub fallback_count: usize,
+}
+
+/// A buffer checked by [`World::assert_no_realloc`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum WorldBuffer {
+    EventQueue,
+    EventStorage,
+    DeferredHandlers,
+    DropHookQueue,
+    Entities,
+    Archetypes,
+    Archetype(ArchetypeIdx),
 }
 
 impl World {
@@ -58,7 +320,251 @@ impl World {
             archetypes: Archetypes::new(),
             events: Events::new(),
             event_queue: EventQueue::new(),
+            event_sequence: 0,
+            handler_events_from: 0,
+            event_log: EventLog::new(),
+            manifest_locked: false,
+            registries_frozen: false,
+            subscriptions: Subscriptions::new(),
+            schedules: Schedules::new(),
+            cascade: 0,
+            dirty: None,
+            has_dedup: false,
+            has_validators: false,
+            handler_cooldown_tick: 0,
+            deferred_handlers: vec![],
+            drop_hook_queue: vec![],
+            remove_on_despawn: false,
+            despawning: vec![],
+            scoped_entities: IndexSet::with_hasher(RandomState::new()),
+            commands: CommandQueue::new(),
+            trace: None,
+            deferred_events: vec![],
+            exclusive_handler: None,
+            #[cfg(feature = "rayon")]
+            parallel_dispatch: false,
+            size_warning: None,
+            #[cfg(feature = "tracing")]
+            bit_set_spill_noticed: false,
+            bundles: vec![],
+            bundle_indices: TypeIdMap::default(),
+            resources: Resources::new(),
+            #[cfg(feature = "async-bridge")]
+            async_bridge: AsyncBridge::default(),
+            #[cfg(feature = "entity-history")]
+            entity_history: EntityHistories::new(),
+        }
+    }
+
+    /// Creates a new world suitable for lockstep simulation across multiple
+    /// machines.
+    ///
+    /// Executing the same sequence of operations on two deterministic worlds
+    /// created from the same [`Manifest`] produces identical [`EntityId`]s,
+    /// [`ComponentId`]s, and [`EventId`]s. Specifically:
+    ///
+    /// - Components and events are registered up front in manifest order, so
+    ///   their IDs do not depend on the order in which they are first used.
+    /// - Entity slots are reused in the order they were freed.
+    /// - Adding a component or event with a [`TypeId`] not listed in the
+    ///   manifest panics.
+    ///
+    /// [`ArchetypeIdx`]s are not deterministic. Archetypes are numbered in the
+    /// order they are created, which depends on the order components are
+    /// inserted into entities: inserting `A` then `B` creates the archetype
+    /// `{A}` on the way, while inserting `B` then `A` creates `{B}`. Identify
+    /// archetypes by their [component indices] instead, as [`determinism_hash`]
+    /// does.
+    ///
+    /// Use [`determinism_hash`] to detect desyncs.
+    ///
+    /// [`determinism_hash`]: World::determinism_hash
+    /// [component indices]: Archetype::component_indices
+    ///
+    /// # Panics
+    ///
+    /// Panics if a component or event not listed in the manifest is added.
+    ///
+    /// ```should_panic
+    /// use evenio::determinism::Manifest;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Unlisted;
+    ///
+    /// let mut world = World::new_deterministic(&Manifest::new());
+    ///
+    /// world.add_component::<Unlisted>(); // Panics
+    /// ```
+    pub fn new_deterministic(manifest: &Manifest) -> Self {
+        let mut world = Self::new();
+        world.entities = Entities::new_fifo();
+
+        manifest.apply(&mut world);
+        world.manifest_locked = true;
+
+        world
+    }
+
+    /// Freezes the world's registries. Until [`unfreeze_registries`] is
+    /// called, adding a component, event, or handler which doesn't already
+    /// exist panics with the name of the offending type.
+    ///
+    /// This catches types which are registered implicitly after setup, such
+    /// as by sending an event or inserting a component for the first time.
+    /// Everything else, including sending and inserting types which were
+    /// already added, works as usual.
+    ///
+    /// [`unfreeze_registries`]: World::unfreeze_registries
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// struct Registered;
+    ///
+    /// #[derive(Event)]
+    /// struct Late;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|_: Receiver<Registered>| {});
+    /// world.freeze_registries();
+    ///
+    /// world.send(Registered);
+    /// world.send(Late); // Panics
+    /// ```
+    pub fn freeze_registries(&mut self) {
+        // The world sends these itself, so they must not be added late.
+        self.add_builtin_events();
+        self.registries_frozen = true;
+    }
+
+    /// Allows components, events, and handlers to be added again after
+    /// [`freeze_registries`](World::freeze_registries).
+    pub fn unfreeze_registries(&mut self) {
+        self.registries_frozen = false;
+    }
+
+    /// Returns whether the world's registries are
+    /// [frozen](World::freeze_registries).
+    pub fn registries_frozen(&self) -> bool {
+        self.registries_frozen
+    }
+
+    /// Adds the events which are sent by the world itself.
+    pub(crate) fn add_builtin_events(&mut self) {
+        self.add_event::<AddComponent>();
+        self.add_event::<RemoveComponent>();
+        self.add_event::<AddEvent>();
+        self.add_event::<RemoveEvent>();
+        self.add_event::<AddHandler>();
+        self.add_event::<RemoveHandler>();
+        self.add_event::<Spawn>();
+        self.add_event::<Despawn>();
+    }
+
+    /// Creates a new, empty world whose component columns are allocated from
+    /// a single region of `arena_size` bytes owned by the world.
+    ///
+    /// Columns are carved out of the region in order and are never freed
+    /// individually. When a column grows, its old buffer is abandoned in
+    /// place. Once the region is full, columns fall back to the global
+    /// allocator. Dropping the world frees the whole region at once, after
+    /// the drop functions of any remaining components have run. Columns in
+    /// the region are not freed individually.
+    ///
+    /// Only component data is allocated from the region. The event queue,
+    /// handlers, entity locations, and the change ticks of components use the
+    /// global allocator as usual, so they still fragment it.
+    ///
+    /// Each column keeps the region alive on its own, so columns may be
+    /// dropped in any order. Columns which outgrew the region are freed with
+    /// the global allocator when they are dropped.
+    ///
+    /// This suits worlds which are created and dropped often, such as one
+    /// world per match of a game, where it avoids freeing every column
+    /// separately and fragmenting the global allocator. Since growing a
+    /// column abandons its old buffer, columns use about twice the memory of
+    /// their final size, so `arena_size` should be sized with that in mind.
+    /// Use [`arena_stats`] to see how much of the arena was used.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Position(f32, f32);
+    ///
+    /// let mut world = World::new_in_arena(1 << 20);
+    ///
+    /// for _ in 0..100 {
+    ///     let e = world.spawn();
+    ///     world.insert(e, Position(0.0, 0.0));
+    /// }
+    ///
+    /// let stats = world.arena_stats().unwrap();
+    ///
+    /// assert!(stats.used >= 100 * 8);
+    /// assert_eq!(stats.fallback_count, 0);
+    /// ```
+    ///
+    /// [`arena_stats`]: World::arena_stats
+    pub fn new_in_arena(arena_size: usize) -> Self {
+        let mut world = Self::new();
+        world.archetypes.set_arena(Arena::new(arena_size));
+        world
+    }
+
+    /// Returns the statistics of the arena of a world created with
+    /// [`World::new_in_arena`], or `None` if the world has no arena.
+    pub fn arena_stats(&self) -> Option<ArenaStats> {
+        self.archetypes.arena().map(|arena| ArenaStats {
+            capacity: arena.capacity(),
+            used: arena.used(),
+            abandoned: arena.abandoned(),
+            fallback_bytes: arena.fallback_bytes(),
+            fallback_count: arena.fallback_count(),
+        })
+    }
+
+    /// Returns a hash of the structural state of the world, consisting of the
+    /// live entity IDs and the components each entity has. Component values
+    /// and [`ArchetypeIdx`]s are not included.
+    ///
+    /// The hash is stable across platforms, so deterministic worlds (see
+    /// [`new_deterministic`]) can compare hashes to detect desyncs.
+    ///
+    /// [`new_deterministic`]: World::new_deterministic
+    pub fn determinism_hash(&self) -> u64 {
+        let mut entities = self
+            .archetypes
+            .iter()
+            .flat_map(|arch| {
+                arch.entity_ids()
+                    .iter()
+                    .map(|&id| (id, arch.component_indices()))
+            })
+            .collect::<Vec<_>>();
+
+        entities.sort_unstable_by_key(|&(id, _)| id);
+
+        let mut hasher = StableHasher::new();
+
+        for (id, component_indices) in entities {
+            hasher.write_u32(id.index().0);
+            hasher.write_u32(id.generation());
+            hasher.write_u32(component_indices.len() as u32);
+
+            for idx in component_indices {
+                hasher.write_u32(idx.0);
+            }
         }
+
+        hasher.finish()
     }
 
     /// Broadcast an event to all handlers in this world.
@@ -66,6 +572,10 @@ impl World {
     /// Any events sent by handlers will also broadcast. This process continues
     /// recursively until all events have finished broadcasting.
     ///
+    /// With the `tracing` feature enabled, every broadcast event is wrapped in
+    /// an `event` span and every handler run in a child `handler` span. Events
+    /// sent by a handler are children of the handler's span.
+    ///
     /// # Examples
     ///
     /// ```
@@ -93,6 +603,147 @@ impl World {
         self.send_many(|mut s| s.send(event))
     }
 
+    /// Like [`send`], but returns the IDs of the handlers which ran for
+    /// `event`, in the order they ran.
+    ///
+    /// Handlers which did not receive the event, such as targeted handlers
+    /// whose query does not match the target, or [throttled] handlers which
+    /// skipped it, are not included. Neither are handlers which ran for
+    /// other events sent as a result of `event`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// struct E;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let h1 = world.add_handler(|_: Receiver<E>| {});
+    /// let h2 = world.add_handler(|_: Receiver<E>| {});
+    ///
+    /// assert_eq!(world.send_traced(E), [h1, h2]);
+    /// ```
+    ///
+    /// [`send`]: World::send
+    /// [throttled]: crate::handler::Throttle
+    pub fn send_traced<E: Event>(&mut self, event: E) -> Vec<HandlerId> {
+        // Adding the event may send events of its own, so do it before taking
+        // the sequence number.
+        self.add_event::<E>();
+
+        self.trace = Some((self.event_queue.next_sequence(), vec![]));
+        self.send(event);
+
+        self.trace.take().map(|(_, ids)| ids).unwrap_or_default()
+    }
+
+    /// Like [`send`], but returns `true` if at least one handler ran for
+    /// `event`. See [`send_traced`] for which handlers count as having run.
+    ///
+    /// A handler which consumes the event still counts, even though the
+    /// handlers after it don't receive the event.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// struct Handled;
+    ///
+    /// #[derive(Event)]
+    /// struct Ignored;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|_: Receiver<Handled>| {});
+    ///
+    /// assert!(world.send_checked(Handled));
+    /// assert!(!world.send_checked(Ignored));
+    /// ```
+    ///
+    /// [`send`]: World::send
+    /// [`send_traced`]: World::send_traced
+    pub fn send_checked<E: Event>(&mut self, event: E) -> bool {
+        !self.send_traced(event).is_empty()
+    }
+
+    /// Like [`send`], but runs the handlers of each [dispatch group]
+    /// concurrently on the rayon thread pool. This applies to `event` and
+    /// every untargeted event sent as a result of it.
+    ///
+    /// The handlers of a group have disjoint access to components, the world,
+    /// and the event queue, so at most one of them sends events. Those events
+    /// are queued and broadcast after every handler of the current event has
+    /// run, just like with [`send`]. Groups are run one after the other, so
+    /// handlers with overlapping access still run in order.
+    ///
+    /// Handlers with overlapping mutable access are never in the same group,
+    /// so they fall back to serial execution. The same goes for groups
+    /// containing a [throttled] handler or a handler with a [`Flush`]
+    /// parameter, and for targeted events.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// struct Tick;
+    ///
+    /// #[derive(Component)]
+    /// struct Position(f32);
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, Position(0.0));
+    /// world.insert(e, Health(10));
+    ///
+    /// // These handlers don't conflict, so they run concurrently.
+    /// world.add_handler(|_: Receiver<Tick>, f: Fetcher<&mut Position>| {
+    ///     for pos in f {
+    ///         pos.0 += 1.0;
+    ///     }
+    /// });
+    /// world.add_handler(|_: Receiver<Tick>, f: Fetcher<&mut Health>| {
+    ///     for health in f {
+    ///         health.0 -= 1;
+    ///     }
+    /// });
+    ///
+    /// world.send_parallel(Tick);
+    ///
+    /// assert_eq!(world.get::<Health>(e).unwrap().0, 9);
+    /// ```
+    ///
+    /// [`send`]: World::send
+    /// [dispatch group]: World::dispatch_groups
+    /// [throttled]: crate::handler::Throttle
+    /// [`Flush`]: crate::flush::Flush
+    #[cfg(feature = "rayon")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rayon")))]
+    pub fn send_parallel<E: Event>(&mut self, event: E) {
+        struct Restore<'a>(&'a mut World, bool);
+
+        impl Drop for Restore<'_> {
+            fn drop(&mut self) {
+                self.0.parallel_dispatch = self.1;
+            }
+        }
+
+        let was_parallel = mem::replace(&mut self.parallel_dispatch, true);
+        let restore = Restore(self, was_parallel);
+
+        restore.0.send(event);
+    }
+
     /// Enqueue an arbitrary number of events and send them all at once.
     ///
     /// The closure `f` is passed a [`Sender`] used to add events to a queue.
@@ -125,7 +776,7 @@ impl World {
     {
         let event_count = self.event_queue.len();
         let res = f(Sender { world: self });
-        unsafe { self.event_queue.reverse_from(event_count) };
+        self.enqueue_pushed_events(event_count);
 
         self.flush_event_queue();
 
@@ -152,57 +803,220 @@ impl World {
         self.send_many(|mut s| s.spawn())
     }
 
-    /// Sends the [`Insert`] event.
+    /// Returns an iterator which spawns up to `count` entities, one per call
+    /// to [`Iterator::next`], and yields their [`EntityId`]s. Room for all
+    /// `count` entities is reserved up front. Dropping the iterator early
+    /// leaves the entities spawned so far alive.
     ///
-    /// This is equivalent to:
+    /// Entities are spawned into the empty archetype just like
+    /// [`World::spawn`]. If any handler could receive the [`Spawn`] event of a
+    /// new entity, or [`Spawn`] events are recorded, then [`Spawn`] is sent
+    /// for each entity as it is yielded. Otherwise, no events are sent, which
+    /// makes spawning many entities much cheaper.
+    ///
+    /// # Examples
     ///
     /// ```
-    /// # use evenio::prelude::*;
-    /// #
-    /// # let mut world = World::new();
-    /// #
-    /// # let entity = world.spawn();
-    /// #
-    /// # #[derive(Component)]
-    /// # struct C;
-    /// #
-    /// # let component = C;
-    /// #
-    /// world.send(Insert::new(entity, component));
-    /// ```
-    pub fn insert<C: Component>(&mut self, entity: EntityId, component: C) {
-        self.send(Insert::new(entity, component))
-    }
-
-    /// Sends the [`Remove`] event.
+    /// use evenio::prelude::*;
     ///
-    /// This is equivalent to:
+    /// #[derive(Component)]
+    /// struct Particle(f32);
     ///
+    /// let mut world = World::new();
+    ///
+    /// let speeds = [1.0, 2.0, 3.0];
+    ///
+    /// let ids: Vec<EntityId> = world.spawn_batch(3).collect();
+    ///
+    /// for (id, speed) in ids.into_iter().zip(speeds) {
+    ///     world.insert(id, Particle(speed));
+    /// }
+    ///
+    /// assert_eq!(world.component_count::<Particle>(), 3);
     /// ```
-    /// # use evenio::prelude::*;
-    /// #
-    /// # let mut world = World::new();
-    /// #
-    /// # let entity = world.spawn();
-    /// #
-    /// # #[derive(Component)]
-    /// # struct C;
-    /// #
-    /// world.send(Remove::<C>::new(entity));
-    /// ```
-    pub fn remove<C: Component>(&mut self, entity: EntityId) {
-        self.send(Remove::<C>::new(entity))
+    pub fn spawn_batch(&mut self, count: u32) -> SpawnBatchIter<'_> {
+        self.entities.reserve(count as usize);
+        self.archetypes.reserve_spawns(count as usize);
+
+        let observed = self.is_spawn_observed(ArchetypeIdx::EMPTY);
+
+        SpawnBatchIter {
+            world: self,
+            remaining: count,
+            observed,
+        }
     }
 
-    /// Sends the [`Despawn`] event.
+    /// Spawns an entity for each [`Bundle`] of components in `bundles` and
+    /// returns their [`EntityId`]s in spawn order.
     ///
-    /// This is equivalent to:
+    /// Each entity is moved straight into the archetype with all of the
+    /// components of its bundle, instead of once per [`Insert`]. Room for the
+    /// entities is reserved in that archetype up front, using the iterator's
+    /// [size hint](Iterator::size_hint). No [`Insert`] events are sent, and
+    /// bundles rejected by a component's [quota](crate::quota) leave their
+    /// entity without components.
     ///
-    /// ```
-    /// # use evenio::prelude::*;
-    /// #
-    /// # let mut world = World::new();
-    /// #
+    /// Like [`spawn_batch`](World::spawn_batch), [`Spawn`] is sent for each
+    /// entity only if any handler could receive it, or [`Spawn`] events are
+    /// recorded. The entity has all of its components by the time [`Spawn`] is
+    /// received.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `B` contains the same component type more than once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Pos(f32, f32);
+    ///
+    /// #[derive(Component)]
+    /// struct Vel(f32, f32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let ids = world.spawn_bundles((0..3).map(|i| (Pos(i as f32, 0.0), Vel(1.0, 0.0))));
+    ///
+    /// assert_eq!(ids.len(), 3);
+    /// assert_eq!(world.component_count::<Pos>(), 3);
+    /// assert_eq!(world.component_count::<Vel>(), 3);
+    /// ```
+    pub fn spawn_bundles<B, I>(&mut self, bundles: I) -> Vec<EntityId>
+    where
+        B: Bundle,
+        I: IntoIterator<Item = B>,
+    {
+        let mut order = self.bundle_components::<B>();
+
+        let bundles = bundles.into_iter();
+        let (additional, _) = bundles.size_hint();
+
+        let dst = order
+            .iter()
+            .fold(ArchetypeIdx::EMPTY, |arch, &(idx, _)| unsafe {
+                self.archetypes
+                    .traverse_insert(arch, idx, &mut self.components, &mut self.handlers)
+            });
+
+        self.entities.reserve(additional);
+        self.archetypes.reserve(dst, additional);
+
+        let observed = self.is_spawn_observed(dst);
+
+        let mut ids = Vec::with_capacity(additional);
+        let mut ptrs = Vec::with_capacity(order.len());
+        let mut components = Vec::with_capacity(order.len());
+
+        for bundle in bundles {
+            let mut bundle = ManuallyDrop::new(bundle);
+
+            ptrs.clear();
+            bundle.for_each_ptr(|ptr| ptrs.push(ptr.as_ptr().cast_const()));
+
+            components.clear();
+            components.extend(order.iter().map(|&(idx, i)| (idx, ptrs[i])));
+
+            let entity = self.reserved_entities.reserve(&self.entities);
+            self.reserved_entities
+                .spawn_one(&mut self.entities, |id| self.archetypes.spawn(id));
+
+            let loc = unsafe { self.entities.get(entity).unwrap_debug_checked() };
+
+            let dst = unsafe { self.insert_components(entity, loc, &components) };
+
+            if dst.is_none() {
+                // Rejected by a quota.
+                unsafe { ManuallyDrop::drop(&mut bundle) };
+            }
+
+            #[cfg(feature = "entity-history")]
+            self.record_transition(
+                entity,
+                TransitionKind::Spawn,
+                None,
+                TransitionCause::External,
+            );
+
+            self.on_archetype_move(entity, None, Some(dst.unwrap_or(ArchetypeIdx::EMPTY)));
+
+            if dst.is_some() {
+                for &(idx, _) in &order {
+                    self.check_invariants(idx);
+                }
+            }
+
+            ids.push(entity);
+
+            if observed {
+                self.send(Spawn(entity));
+
+                // `Spawn` handlers may have removed components from the world.
+                order = self.bundle_components::<B>();
+            }
+        }
+
+        if !self.event_queue.is_empty() {
+            self.flush_event_queue();
+        }
+
+        ids
+    }
+
+    /// Sends the [`Insert`] event.
+    ///
+    /// This is equivalent to:
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// #
+    /// # let mut world = World::new();
+    /// #
+    /// # let entity = world.spawn();
+    /// #
+    /// # #[derive(Component)]
+    /// # struct C;
+    /// #
+    /// # let component = C;
+    /// #
+    /// world.send(Insert::new(entity, component));
+    /// ```
+    pub fn insert<C: Component>(&mut self, entity: EntityId, component: C) {
+        self.send(Insert::new(entity, component))
+    }
+
+    /// Sends the [`Remove`] event.
+    ///
+    /// This is equivalent to:
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// #
+    /// # let mut world = World::new();
+    /// #
+    /// # let entity = world.spawn();
+    /// #
+    /// # #[derive(Component)]
+    /// # struct C;
+    /// #
+    /// world.send(Remove::<C>::new(entity));
+    /// ```
+    pub fn remove<C: Component>(&mut self, entity: EntityId) {
+        self.send(Remove::<C>::new(entity))
+    }
+
+    /// Sends the [`Despawn`] event.
+    ///
+    /// This is equivalent to:
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// #
+    /// # let mut world = World::new();
+    /// #
     /// # let entity = world.spawn();
     /// #
     /// world.send(Despawn(entity));
@@ -211,6 +1025,151 @@ impl World {
         self.send(Despawn(entity))
     }
 
+    /// Sets whether despawning an entity first removes its components by
+    /// sending their [`Remove`] events. Disabled by default.
+    ///
+    /// When enabled, a [`Despawn`] event is handled in this order:
+    ///
+    /// 1. A `Remove<C>` event is sent for every component `C` of the entity
+    ///    whose `Remove<C>` event was added to the world, such as by a handler
+    ///    which receives it. Handlers of `Remove<C>` can read the final value
+    ///    of the component, which is then removed as usual.
+    /// 2. Once the `Remove` events and the events they cause were handled, the
+    ///    handlers of `Despawn` run.
+    /// 3. The entity and its remaining components are removed.
+    ///
+    /// Despawning the entity again while its `Remove` events are being
+    /// handled, such as from one of their handlers, has no effect. The
+    /// components are removed even if a handler of `Despawn` consumes the
+    /// event.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Connection(u32);
+    ///
+    /// #[derive(Component)]
+    /// struct Closed(Vec<u32>);
+    ///
+    /// let mut world = World::new();
+    /// world.set_remove_on_despawn(true);
+    ///
+    /// let log = world.spawn();
+    /// world.insert(log, Closed(vec![]));
+    ///
+    /// world.add_handler(
+    ///     |r: Receiver<Remove<Connection>, &Connection>, Single(closed): Single<&mut Closed>| {
+    ///         closed.0.push(r.query.0);
+    ///     },
+    /// );
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, Connection(7));
+    /// world.despawn(e);
+    ///
+    /// assert_eq!(world.get::<Closed>(log).unwrap().0, [7]);
+    /// ```
+    pub fn set_remove_on_despawn(&mut self, enabled: bool) {
+        self.remove_on_despawn = enabled;
+    }
+
+    /// Despawns every entity in `ids` and returns the number of entities that
+    /// were despawned. Entities which do not exist and duplicate IDs are
+    /// skipped.
+    ///
+    /// A [`Despawn`] event is sent for each entity as usual. The events are
+    /// ordered by archetype, and by descending row within each archetype, so
+    /// that removing entities from their columns moves as little data as
+    /// possible.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let a = world.spawn();
+    /// let b = world.spawn();
+    /// world.despawn(b);
+    ///
+    /// assert_eq!(world.despawn_batch([a, b, a]), 1);
+    /// assert!(!world.entities().contains(a));
+    /// ```
+    pub fn despawn_batch<I: IntoIterator<Item = EntityId>>(&mut self, ids: I) -> usize {
+        let mut live = ids
+            .into_iter()
+            .filter_map(|id| self.entities.get(id).map(|loc| (loc, id)))
+            .collect::<Vec<_>>();
+
+        live.sort_unstable_by(|(a, _), (b, _)| {
+            a.archetype.cmp(&b.archetype).then(b.row.0.cmp(&a.row.0))
+        });
+        live.dedup_by_key(|&mut (_, id)| id);
+
+        self.send_many(|mut s| {
+            for &(_, id) in &live {
+                s.despawn(id);
+            }
+        });
+
+        // A handler may have taken ownership of a `Despawn` event, so count what
+        // was actually removed.
+        live.iter()
+            .filter(|&&(_, id)| !self.entities.contains(id))
+            .count()
+    }
+
+    /// Despawns all entities for which the predicate `f` returns `false`.
+    ///
+    /// The predicate is evaluated for every entity before any entities are
+    /// despawned, so `f` always observes the world in its original state.
+    /// Entities are then despawned by sending [`Despawn`] events.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Keep;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e1 = world.spawn();
+    /// let e2 = world.spawn();
+    /// world.insert(e1, Keep);
+    ///
+    /// world.retain_entities(|world, id| world.get::<Keep>(id).is_some());
+    ///
+    /// assert!(world.entities().contains(e1));
+    /// assert!(!world.entities().contains(e2));
+    /// ```
+    pub fn retain_entities<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&World, EntityId) -> bool,
+    {
+        let ids = self
+            .archetypes
+            .iter()
+            .flat_map(|arch| arch.entity_ids().iter().copied())
+            .collect::<Vec<_>>();
+
+        let to_despawn = ids
+            .into_iter()
+            .filter(|&id| !f(self, id))
+            .collect::<Vec<_>>();
+
+        self.send_many(|mut s| {
+            for id in to_despawn {
+                s.despawn(id);
+            }
+        });
+    }
+
     /// Gets an immutable reference to component `C` on `entity`. Returns `None`
     /// if `entity` doesn't exist or doesn't have the requested component.
     ///
@@ -252,9 +1211,74 @@ impl World {
         })
     }
 
+    /// Gets a pointer to the component identified by `component` on `entity`.
+    /// Returns `Ok(None)` if `entity` doesn't exist or doesn't have the
+    /// component.
+    ///
+    /// This is the untyped counterpart of [`get`], intended for components
+    /// without a Rust type such as those added with
+    /// [`add_component_with_descriptor`]. The layout of the data is given by
+    /// the component's [`ComponentInfo`]. The pointer must not be written
+    /// through, and is invalidated by any change to the world.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StaleComponentId`] if `component` does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct C(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, C(123));
+    ///
+    /// let component = world.add_component::<C>();
+    /// let ptr = world.get_dynamic(e, component).unwrap().unwrap();
+    ///
+    /// assert_eq!(unsafe { ptr.cast::<C>().as_ref().0 }, 123);
+    /// ```
+    ///
+    /// [`get`]: World::get
+    /// [`add_component_with_descriptor`]: World::add_component_with_descriptor
+    pub fn get_dynamic(
+        &self,
+        entity: EntityId,
+        component: ComponentId,
+    ) -> Result<Option<NonNull<u8>>, StaleComponentId> {
+        let info = self
+            .components
+            .get(component)
+            .ok_or(StaleComponentId(component))?;
+
+        let Some(loc) = self.entities.get(entity) else {
+            return Ok(None);
+        };
+
+        let arch = unsafe { self.archetypes.get(loc.archetype).unwrap_debug_checked() };
+
+        let Some(col) = arch.column_of(component.index()) else {
+            return Ok(None);
+        };
+
+        let stride = info.layout().size();
+
+        Ok(Some(unsafe {
+            NonNull::new_unchecked(col.data().as_ptr().add(loc.row.0 as usize * stride))
+        }))
+    }
+
     /// Gets a mutable reference to component `C` on `entity`. Returns `None` if
     /// `entity` doesn't exist or doesn't have the requested component.
     ///
+    /// The component's change tick is updated, so the write is seen by
+    /// [`Changed`] filters even if the component is not modified.
+    ///
     /// # Examples
     ///
     /// ```
@@ -270,6 +1294,8 @@ impl World {
     ///
     /// assert_eq!(world.get_mut::<MyComponent>(e), Some(&mut MyComponent(123)));
     /// ```
+    ///
+    /// [`Changed`]: crate::query::Changed
     pub fn get_mut<C: Component>(&mut self, entity: EntityId) -> Option<&mut C> {
         let () = AssertMutable::<C>::COMPONENT;
 
@@ -284,944 +1310,7499 @@ impl World {
         let arch = unsafe { self.archetypes().get(loc.archetype).unwrap_debug_checked() };
 
         let col = arch.column_of(component_idx)?;
+        let row = loc.row.0 as usize;
 
-        Some(unsafe { &mut *col.data().as_ptr().cast::<C>().add(loc.row.0 as usize) })
+        // SAFETY: We have exclusive access to the world, and `row` is in bounds.
+        unsafe {
+            *col.ticks_ptr().as_ptr().add(row) = self.archetypes.change_tick();
+            Some(&mut *col.data().as_ptr().cast::<C>().add(row))
+        }
     }
 
-    /// Adds a new handler to the world, returns its [`HandlerId`], and sends
-    /// the [`AddHandler`] event to signal its creation.
+    /// Returns an [`EntityRef`] for reading the components of `entity`, or
+    /// `None` if `entity` doesn't exist.
     ///
-    /// If the handler already exists (as determined by [`Handler::type_id`])
-    /// then the `HandlerId` of the existing handler is returned and no
-    /// event is sent.
+    /// # Examples
     ///
-    /// # Panics
+    /// ```
+    /// use evenio::prelude::*;
     ///
-    /// Panics if the configuration of the handler is invalid. This can occur
-    /// when, for instance, the handler does not specify an event to receive.
+    /// #[derive(Component)]
+    /// struct Name(&'static str);
     ///
-    /// ```should_panic
-    /// # use evenio::prelude::*;
-    /// #
-    /// # let mut world = World::new();
-    /// #
-    /// world.add_handler(|| {}); // Panics
+    /// #[derive(Component)]
+    /// struct Player;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, Name("Alice"));
+    ///
+    /// let entity = world.entity(e).unwrap();
+    ///
+    /// assert_eq!(entity.get::<Name>().unwrap().0, "Alice");
+    /// assert!(!entity.contains::<Player>());
     /// ```
+    pub fn entity(&self, entity: EntityId) -> Option<EntityRef<'_>> {
+        self.entities.contains(entity).then_some(EntityRef {
+            world: self,
+            id: entity,
+        })
+    }
+
+    /// Returns an [`EntityMut`] for reading, writing, and changing the
+    /// components of `entity`, or `None` if `entity` doesn't exist.
     ///
     /// # Examples
     ///
     /// ```
     /// use evenio::prelude::*;
-    /// # #[derive(Event)]
-    /// # struct MyEvent;
     ///
-    /// fn my_handler(_: Receiver<MyEvent>) {};
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// #[derive(Component)]
+    /// struct Poisoned;
     ///
     /// let mut world = World::new();
-    /// let id = world.add_handler(my_handler);
     ///
-    /// assert!(world.handlers().contains(id));
-    /// ```
+    /// let e = world.spawn();
     ///
-    /// [`Handler::type_id`]: crate::handler::Handler::type_id
-    #[track_caller]
-    pub fn add_handler<H: IntoHandler<M>, M>(&mut self, handler: H) -> HandlerId {
-        let mut handler = handler.into_handler();
-        let mut config = Config::default();
-
-        let type_id = handler.type_id();
-
-        if let Some(type_id) = type_id {
-            if let Some(info) = self.handlers.get_by_type_id(type_id) {
-                return info.id();
-            }
-        }
+    /// let mut entity = world.entity_mut(e).unwrap();
+    /// entity.insert(Health(10)).insert(Poisoned);
+    /// entity.get_mut::<Health>().unwrap().0 -= 1;
+    /// entity.remove::<Poisoned>();
+    ///
+    /// assert_eq!(world.get::<Health>(e).unwrap().0, 9);
+    /// assert!(world.get::<Poisoned>(e).is_none());
+    /// ```
+    pub fn entity_mut(&mut self, entity: EntityId) -> Option<EntityMut<'_>> {
+        self.entities.contains(entity).then_some(EntityMut {
+            world: self,
+            id: entity,
+        })
+    }
 
-        if let Err(e) = handler.init(self, &mut config) {
-            panic!("{e}");
-        }
+    /// Calls `f` with mutable references to component `C` on both entities of
+    /// each pair in `pairs`. Pairs where the two entities are the same, or
+    /// where either entity doesn't exist or doesn't have `C`, are skipped.
+    ///
+    /// Like [`get_mut`](Self::get_mut), the change ticks of both components
+    /// are updated for every pair passed to `f`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Velocity(f32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let a = world.spawn();
+    /// world.insert(a, Velocity(1.0));
+    /// let b = world.spawn();
+    /// world.insert(b, Velocity(-2.0));
+    ///
+    /// // Elastic collision between equal masses.
+    /// world.pairs_mut::<Velocity, _>(&[(a, b)], |va, vb| core::mem::swap(&mut va.0, &mut vb.0));
+    ///
+    /// assert_eq!(world.get::<Velocity>(a).unwrap().0, -2.0);
+    /// assert_eq!(world.get::<Velocity>(b).unwrap().0, 1.0);
+    /// ```
+    pub fn pairs_mut<C, F>(&mut self, pairs: &[(EntityId, EntityId)], mut f: F)
+    where
+        C: Component,
+        F: FnMut(&mut C, &mut C),
+    {
+        let () = AssertMutable::<C>::COMPONENT;
 
-        let Some(received_event) = config.received_event else {
-            panic!(
-                "handler `{}` did not specify an event to receive. All handlers must listen for \
-                 exactly one event type (see `Receiver`)",
-                any::type_name::<H>()
-            )
+        let Some(component_idx) = self
+            .components()
+            .get_by_type_id(TypeId::of::<C>())
+            .map(|info| info.id().index())
+        else {
+            return;
         };
 
-        let info = HandlerInfo::new(HandlerInfoInner {
-            name: handler.name(),
-            id: HandlerId::NULL, // Filled in later.
-            type_id,
-            order: 0, // Filled in later.
-            received_event,
-            received_event_access: config.received_event_access,
-            targeted_event_expr: config.targeted_event_expr,
-            sent_untargeted_events: config.sent_untargeted_events,
-            sent_targeted_events: config.sent_targeted_events,
-            event_queue_access: config.event_queue_access,
-            component_access: config.component_access,
-            referenced_components: config.referenced_components,
-            priority: config.priority,
-            handler,
-        });
+        let now = self.archetypes.change_tick();
 
-        let id = self.handlers.add(info);
-        let info = self.handlers.get_mut(id).unwrap();
+        let ptr_of = |entity: EntityId| {
+            let loc = self.entities.get(entity)?;
+            let arch = unsafe { self.archetypes.get(loc.archetype).unwrap_debug_checked() };
+            let col = arch.column_of(component_idx)?;
+            let row = loc.row.0 as usize;
 
-        self.archetypes.register_handler(info);
+            Some(unsafe {
+                (
+                    col.data().as_ptr().cast::<C>().add(row),
+                    col.ticks_ptr().as_ptr().add(row),
+                )
+            })
+        };
 
-        self.send(AddHandler(id));
+        for &(a, b) in pairs {
+            if a == b {
+                continue;
+            }
 
-        id
+            let (Some((a, a_tick)), Some((b, b_tick))) = (ptr_of(a), ptr_of(b)) else {
+                continue;
+            };
+
+            // SAFETY: Distinct entities occupy distinct rows, so the references
+            // don't alias. We have exclusive access to the world.
+            unsafe {
+                *a_tick = now;
+                *b_tick = now;
+                f(&mut *a, &mut *b);
+            }
+        }
     }
 
-    /// Removes a handler from the world, returns its [`HandlerInfo`], and sends
-    /// the [`RemoveHandler`] event. If the `handler` ID is invalid, then `None`
-    /// is returned and no event is sent.
+    /// Inserts the component identified by `component` on `entity` by copying
+    /// the bytes pointed to by `value`. If the entity already has the
+    /// component, the old value is dropped and replaced.
     ///
-    /// # Example
+    /// This is intended for components without a Rust type, such as those
+    /// added with [`add_component_with_descriptor`]. Unlike [`insert`], no
+    /// [`Insert`] event is sent.
     ///
-    /// ```
-    /// use evenio::prelude::*;
+    /// If `entity` does not exist, the value is dropped and the function has
+    /// no other effect.
     ///
-    /// let mut world = World::new();
+    /// # Errors
     ///
-    /// # #[derive(Event)]
-    /// # struct MyEvent;
-    /// let handler_id = world.add_handler(|_: Receiver<MyEvent>| {});
+    /// Returns [`StaleComponentId`] if `component` does not exist. The value's
+    /// layout is unknown in that case, so ownership of the value stays with
+    /// the caller.
     ///
-    /// let info = world.remove_handler(handler_id).unwrap();
+    /// [`add_component_with_descriptor`]: World::add_component_with_descriptor
+    /// [`insert`]: World::insert
     ///
-    /// assert_eq!(info.id(), handler_id);
-    /// assert!(!world.handlers().contains(handler_id));
-    /// ```
-    pub fn remove_handler(&mut self, handler: HandlerId) -> Option<HandlerInfo> {
-        if !self.handlers.contains(handler) {
-            return None;
-        }
+    /// # Safety
+    ///
+    /// - `value` must point to an initialized value of the component, valid for
+    ///   reads of [`ComponentInfo::size`] bytes.
+    /// - If `Ok` is returned, ownership of the value is transferred to the
+    ///   world. The caller must not use or drop the value afterwards.
+    pub unsafe fn insert_dynamic(
+        &mut self,
+        entity: EntityId,
+        component: ComponentId,
+        value: NonNull<u8>,
+    ) -> Result<(), StaleComponentId> {
+        let Some(info) = self.components.get(component) else {
+            return Err(StaleComponentId(component));
+        };
 
-        self.send(RemoveHandler(handler));
+        let layout = info.layout();
+        let size = info.size();
+        let drop = info.drop();
 
-        let info = self.handlers.remove(handler).unwrap();
+        // Copy the value into an aligned buffer so that it can be dropped if the
+        // entity doesn't exist.
+        let mut buf = vec![0_u8; layout.size() + layout.align()];
+        let offset = buf.as_ptr().align_offset(layout.align());
+        let ptr = buf.as_mut_ptr().add(offset);
+        ptr::copy_nonoverlapping(value.as_ptr().cast_const(), ptr, size);
 
-        self.archetypes.remove_handler(&info);
+        let Some(loc) = self.entities.get(entity) else {
+            if let Some(drop) = drop {
+                drop(NonNull::new_unchecked(ptr));
+            }
+            return Ok(());
+        };
 
-        Some(info)
+        let dst = self.archetypes.traverse_insert(
+            loc.archetype,
+            component.index(),
+            &mut self.components,
+            &mut self.handlers,
+        );
+
+        self.archetypes.move_entity(
+            loc,
+            dst,
+            [(component.index(), ptr.cast_const())],
+            &mut self.entities,
+        );
+
+        self.on_archetype_move(entity, Some(loc.archetype), Some(dst));
+        self.flush_event_queue();
+
+        Ok(())
     }
 
-    /// Adds the component `C` to the world, returns its [`ComponentId`], and
-    /// sends the [`AddComponent`] event to signal its creation.
+    /// Removes `entity` from the world and returns all of its components
+    /// without dropping them. Returns `None` if the entity doesn't exist.
     ///
-    /// If the component already exists, then the [`ComponentId`] of the
-    /// existing component is returned and no event is sent.
+    /// The entity can be reconstructed with [`put_entity`], possibly in a
+    /// different world. Unlike [`despawn`], no [`Despawn`] event is sent and
+    /// drop hooks are not run, since the components are not dropped.
     ///
     /// # Examples
     ///
     /// ```
     /// use evenio::prelude::*;
     ///
-    /// #[derive(Component)]
-    /// struct MyComponent;
+    /// #[derive(Component, PartialEq, Debug)]
+    /// struct Name(&'static str);
     ///
     /// let mut world = World::new();
-    /// let id = world.add_component::<MyComponent>();
+    /// let e = world.spawn();
+    /// world.insert(e, Name("alice"));
     ///
-    /// assert_eq!(id, world.add_component::<MyComponent>());
+    /// let owned = world.take_entity(e).unwrap();
+    ///
+    /// assert!(!world.entities().contains(e));
+    /// assert_eq!(owned.get::<Name>(), Some(&Name("alice")));
     /// ```
-    pub fn add_component<C: Component>(&mut self) -> ComponentId {
-        let desc = ComponentDescriptor {
-            name: any::type_name::<C>().into(),
-            type_id: Some(TypeId::of::<C>()),
-            layout: Layout::new::<C>(),
-            drop: drop_fn_of::<C>(),
-            is_immutable: C::IS_IMMUTABLE,
-        };
+    ///
+    /// [`put_entity`]: World::put_entity
+    /// [`despawn`]: World::despawn
+    pub fn take_entity(&mut self, entity: EntityId) -> Option<OwnedEntity> {
+        let src = self.entities.get(entity)?.archetype;
+
+        let taken = self
+            .archetypes
+            .take_entity(entity, &mut self.entities)?
+            .into_iter()
+            .map(|(idx, data)| {
+                let info = unsafe { self.components.get_by_index(idx).unwrap_debug_checked() };
+
+                OwnedComponent {
+                    id: info.id(),
+                    type_id: info.type_id(),
+                    name: info.name.clone(),
+                    data,
+                }
+            })
+            .collect();
+
+        #[cfg(feature = "entity-history")]
+        self.record_transition(
+            entity,
+            TransitionKind::Despawn,
+            None,
+            TransitionCause::External,
+        );
+
+        self.on_archetype_move(entity, Some(src), None);
+        self.reserved_entities.refresh(&self.entities);
+        self.flush_event_queue();
 
-        unsafe { self.add_component_with_descriptor(desc) }
+        Some(OwnedEntity::new(taken))
     }
 
-    /// Adds a component described by a given [`ComponentDescriptor`].
-    ///
-    /// Like [`add_component`], an [`AddComponent`] event is sent if the
-    /// component is newly added. If the [`TypeId`] of the component matches an
-    /// existing component, then the existing component's [`ComponentId`] is
-    /// returned and no event is sent.
-    ///
-    /// # Safety
+    /// Returns whether the [`Spawn`] event of an entity in `arch` could be
+    /// observed, either by a handler or because `Spawn` events are recorded.
+    fn is_spawn_observed(&self, arch: ArchetypeIdx) -> bool {
+        self.events
+            .get_by_type_id(TypeId::of::<Spawn>())
+            .is_some_and(|info| match info.id().index() {
+                EventIdx::Targeted(idx) => self
+                    .archetypes
+                    .get(arch)
+                    .and_then(|arch| arch.handler_list_for(idx))
+                    .is_some_and(|list| !list.handlers().is_empty()),
+                EventIdx::Untargeted(_) => true,
+            })
+            || self.event_log.is_recorded::<Spawn>()
+    }
+
+    /// Adds the components of the [`Bundle`] `B` to the world and returns
+    /// their indices along with their positions in `B`, sorted by index.
     ///
-    /// - If the component is given a [`TypeId`], then the `layout` and `drop`
-    ///   function must be compatible with the Rust type identified by the type
-    ///   ID.
-    /// - Drop function must be safe to call with a pointer to the component as
-    ///   described by [`DropFn`]'s documentation.
+    /// # Panics
     ///
-    /// [`add_component`]: World::add_component
-    pub unsafe fn add_component_with_descriptor(
-        &mut self,
-        desc: ComponentDescriptor,
-    ) -> ComponentId {
-        let (id, is_new) = self.components.add(desc);
+    /// Panics if `B` contains the same component type more than once.
+    fn bundle_components<B: Bundle>(&mut self) -> Vec<(ComponentIdx, usize)> {
+        let mut ids = vec![];
+        B::add_components(self, &mut ids);
 
-        if is_new {
-            self.send(AddComponent(id));
+        let mut components: Vec<_> = ids
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (id.index(), i))
+            .collect();
+
+        components.sort_unstable();
+
+        for pair in components.windows(2) {
+            if pair[0].0 == pair[1].0 {
+                let name = self
+                    .components
+                    .get_by_index(pair[0].0)
+                    .map_or("", |info| info.name());
+
+                panic!(
+                    "bundle `{}` contains component `{name}` more than once",
+                    any::type_name::<B>()
+                );
+            }
         }
 
-        id
+        components
     }
 
-    /// Removes a component from the world and returns its [`ComponentInfo`]. If
-    /// the `component` ID is invalid, then `None` is returned and the function
-    /// has no effect.
-    ///
-    /// Removing a component has the following effects in the order listed:
-    /// 1. The [`RemoveComponent`] event is sent.
-    /// 2. All entities with the component are despawned.
-    /// 3. All handlers that reference the component are removed.
-    /// 4. The corresponding [`Insert`] events for the component are removed.
-    /// 5. The corresponding [`Remove`] events for the component are removed.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// # use evenio::prelude::*;
-    /// # let mut world = World::new();
-    /// # #[derive(Component)] struct C;
-    /// # #[derive(Event)] struct E;
-    /// #
-    /// let component = world.add_component::<C>();
-    /// let handler = world.add_handler(|_: Receiver<E>, _: Fetcher<&C>| {});
+    /// Registers the [`Bundle`] `B` for use by
+    /// [`InsertBundle`](crate::event::InsertBundle) events and
+    /// returns its index.
     ///
-    /// assert!(world.components().contains(component));
-    /// assert!(world.handlers().contains(handler));
-    ///
-    /// world.remove_component(component);
+    /// # Panics
     ///
-    /// assert!(!world.components().contains(component));
-    /// // Handler was also removed because it references `C` in its `Fetcher`.
-    /// assert!(!world.handlers().contains(handler));
-    /// ```
-    pub fn remove_component(&mut self, component: ComponentId) -> Option<ComponentInfo> {
-        if !self.components.contains(component) {
-            return None;
+    /// Panics if `B` contains the same component type more than once.
+    pub(crate) fn add_bundle<B: Bundle>(&mut self) -> u32 {
+        if let Some(&idx) = self.bundle_indices.get(&TypeId::of::<B>()) {
+            return idx;
         }
 
-        // Send event first.
-        self.send(RemoveComponent(component));
+        self.bundle_components::<B>();
 
-        let despawn_idx = self.add_event::<Despawn>().index().as_u32();
+        let idx = self.bundles.len() as u32;
+        self.bundles.push(insert_bundle_components::<B>);
+        self.bundle_indices.insert(TypeId::of::<B>(), idx);
 
-        // Attempt to despawn all entities that still have this component.
-        for arch in self.archetypes.iter() {
-            if arch.column_of(component.index()).is_some() {
-                for &entity_id in arch.entity_ids() {
-                    unsafe { self.event_queue.push_front(Despawn(entity_id), despawn_idx) };
-                }
+        idx
+    }
+
+    /// Moves `entity` from `loc` into the archetype with `components` added,
+    /// replacing any it already has, and returns the archetype. Returns `None`
+    /// without moving the entity if a quota rejected one of the components.
+    ///
+    /// # Safety
+    ///
+    /// - `loc` must be the location of `entity`.
+    /// - `components` must be sorted by index without duplicates, and each
+    ///   pointer must point to an initialized value of its component.
+    /// - If `Some` is returned, the archetype owns the values and the caller
+    ///   must not use or drop them afterwards.
+    unsafe fn insert_components(
+        &mut self,
+        entity: EntityId,
+        loc: EntityLocation,
+        components: &[(ComponentIdx, *const u8)],
+    ) -> Option<ArchetypeIdx> {
+        for &(idx, _) in components {
+            if !self.apply_quota(entity, loc.archetype, idx) {
+                return None;
             }
         }
 
-        self.flush_event_queue();
-
-        // Remove all handlers that reference this component.
-        let mut handlers_to_remove = vec![];
+        let src = self.archetypes.get(loc.archetype).unwrap_debug_checked();
 
-        for handler in self.handlers.iter() {
-            if handler.referenced_components().contains(component.index()) {
-                handlers_to_remove.push(handler.id());
+        if !components
+            .iter()
+            .any(|&(idx, _)| src.column_of(idx).is_some())
+        {
+            let mut dst = loc.archetype;
+
+            for &(idx, _) in components {
+                dst = self.archetypes.traverse_insert(
+                    dst,
+                    idx,
+                    &mut self.components,
+                    &mut self.handlers,
+                );
             }
-        }
 
-        for handler_id in handlers_to_remove {
-            self.remove_handler(handler_id);
-        }
+            self.archetypes
+                .move_entity(loc, dst, components.iter().copied(), &mut self.entities);
 
-        let info = &self.components[component];
+            return Some(dst);
+        }
 
-        // Remove all the `Insert` and `Remove` events for this component.
-        let events_to_remove = info
-            .insert_events()
+        // Moving between archetypes only takes the values of new components, so
+        // replace the existing ones in place first.
+        let (replaced, added): (Vec<_>, Vec<_>) = components
             .iter()
-            .copied()
-            .chain(info.remove_events().iter().copied())
-            .collect::<Vec<_>>();
+            .partition(|&&(idx, _)| src.column_of(idx).is_some());
 
-        for event in events_to_remove {
-            self.remove_event(event);
-        }
+        self.archetypes
+            .move_entity(loc, loc.archetype, replaced, &mut self.entities);
 
-        let mut info = self
-            .components
-            .remove(component)
-            .expect("component should still exist");
+        let mut dst = loc.archetype;
+
+        for &(idx, _) in &added {
+            dst =
+                self.archetypes
+                    .traverse_insert(dst, idx, &mut self.components, &mut self.handlers);
+        }
 
-        // Remove all archetypes with this component. If there are still entities with
-        // the component by this point, then they will be silently removed.
         self.archetypes
-            .remove_component(&mut info, &mut self.components, |id| {
-                self.entities.remove(id);
-            });
+            .move_entity(loc, dst, added, &mut self.entities);
 
-        Some(info)
+        Some(dst)
     }
 
-    /// Adds the event `E` to the world, returns its [`EventId`], and sends the
-    /// [`AddEvent`] event to signal its creation.
-    ///
-    /// If the event already exists, then the [`EventId`] of the existing event
-    /// is returned and no event is sent.
+    /// Spawns a new entity with the components of an entity returned by
+    /// [`take_entity`] and returns its ID. The [`Spawn`] event is sent after
+    /// the entity has all of its components.
     ///
-    /// # Examples
-    ///
-    /// ```
-    /// use evenio::prelude::*;
+    /// The entity may come from a different world, in which case both worlds
+    /// must have added the same components in the same order so that their
+    /// [`ComponentId`]s agree.
     ///
-    /// #[derive(Event)]
-    /// struct MyEvent;
+    /// # Panics
     ///
-    /// let mut world = World::new();
-    /// let id = world.add_event::<MyEvent>();
+    /// Panics if any component of `owned` doesn't exist in this world, or
+    /// exists with a different type, name, layout, or drop function.
     ///
-    /// assert_eq!(id, world.add_event::<MyEvent>());
-    /// ```
-    pub fn add_event<E: Event>(&mut self) -> EventId {
-        let desc = EventDescriptor {
-            name: any::type_name::<E>().into(),
-            type_id: Some(TypeId::of::<E>()),
-            is_targeted: E::IS_TARGETED,
-            kind: unsafe { E::init(self) },
-            layout: Layout::new::<E>(),
-            drop: drop_fn_of::<E>(),
-            is_immutable: E::IS_IMMUTABLE,
+    /// [`take_entity`]: World::take_entity
+    pub fn put_entity(&mut self, owned: OwnedEntity) -> EntityId {
+        let components = owned.into_components();
+
+        for c in &components {
+            let matches = self.components.get(c.id).is_some_and(|info| {
+                c.type_id == info.type_id()
+                    && c.name == info.name()
+                    && c.data.elem_size() == info.size()
+                    && c.data.elem_layout().align() == info.layout().align()
+                    && c.data.drop_fn().map(|f| f as usize) == info.drop().map(|f| f as usize)
+            });
+
+            assert!(
+                matches,
+                "component {:?} of the owned entity does not match a component in this world",
+                c.id
+            );
+        }
+
+        let entity = self.reserved_entities.reserve(&self.entities);
+        self.reserved_entities
+            .spawn_one(&mut self.entities, |id| self.archetypes.spawn(id));
+
+        let loc = unsafe { self.entities.get(entity).unwrap_debug_checked() };
+
+        let mut dst = loc.archetype;
+
+        for c in &components {
+            dst = unsafe {
+                self.archetypes.traverse_insert(
+                    dst,
+                    c.id.index(),
+                    &mut self.components,
+                    &mut self.handlers,
+                )
+            };
+        }
+
+        // Components are sorted by index, which is the order `move_entity`
+        // expects.
+        unsafe {
+            self.archetypes.move_entity(
+                loc,
+                dst,
+                components
+                    .iter()
+                    .map(|c| (c.id.index(), c.data.as_ptr().as_ptr().cast_const())),
+                &mut self.entities,
+            )
         };
 
-        unsafe { self.add_event_with_descriptor(desc) }
-    }
+        for mut c in components {
+            // Values are owned by the archetype now.
+            unsafe { c.data.forget_elements() };
+        }
 
-    /// Adds an event described by a given [`EventDescriptor`].
+        #[cfg(feature = "entity-history")]
+        self.record_transition(
+            entity,
+            TransitionKind::Spawn,
+            None,
+            TransitionCause::External,
+        );
+
+        self.on_archetype_move(entity, None, Some(dst));
+        self.send(Spawn(entity));
+
+        entity
+    }
+
+    /// Adds the component `C` to the world if it doesn't exist yet, and saves
+    /// its values under `key` in [`World::serialize`]. Calling this again
+    /// replaces the key. Returns the component's ID.
     ///
-    /// Like [`add_event`], an [`AddEvent`] event is sent if the
-    /// event is newly added. If the [`TypeId`] of the event matches an
-    /// existing event, then the existing event's [`EventId`] is
-    /// returned and no event is sent.
+    /// Keys identify components in saved worlds, so they must stay the same
+    /// between the world that is saved and the world that is loaded. See the
+    /// [`snapshot`](crate::snapshot) module for details.
     ///
-    /// # Safety
+    /// # Panics
     ///
-    /// - If the event is given a [`TypeId`], then the `layout` and `drop`
-    ///   function must be compatible with the Rust type identified by the type
-    ///   ID.
-    /// - Drop function must be safe to call with a pointer to the event as
-    ///   described by [`DropFn`]'s documentation.
-    /// - The event's kind must be correct for the descriptor. See
-    ///   [`EventKind`]'s documentation for more information.
+    /// Panics if `key` is already used by a different component.
+    #[cfg(feature = "serde")]
+    pub fn add_serde_component<C>(&mut self, key: &'static str) -> ComponentId
+    where
+        C: Component + serde::Serialize + serde::de::DeserializeOwned,
+    {
+        let id = self.add_component::<C>();
+
+        if let Some(other) = self
+            .components
+            .iter()
+            .find(|info| info.id() != id && info.serde_key() == Some(key))
+        {
+            panic!(
+                "component key `{key}` is already used by component `{}`",
+                other.name()
+            );
+        }
+
+        if let Some(info) = self.components.get_by_index_mut(id.index()) {
+            info.serde = Some(ComponentSerde::new::<C>(key));
+        }
+
+        id
+    }
+
+    /// Serializes all entities in the world along with the components added
+    /// with [`World::add_serde_component`]. Other components are skipped.
     ///
-    /// [`add_event`]: World::add_event
-    pub unsafe fn add_event_with_descriptor(&mut self, desc: EventDescriptor) -> EventId {
-        let kind = desc.kind;
+    /// The result can be loaded with [`World::deserialize`]. See the
+    /// [`snapshot`](crate::snapshot) module for the format.
+    #[cfg(feature = "serde")]
+    pub fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serde::Serialize::serialize(
+            &WorldSer {
+                entities: &self.entities,
+                archetypes: &self.archetypes,
+                components: &self.components,
+            },
+            serializer,
+        )
+    }
+
+    /// Loads the entities and components saved by [`World::serialize`] into
+    /// this world. Every component key in the input must have been added with
+    /// [`World::add_serde_component`].
+    ///
+    /// Entities keep their IDs, including the IDs of entities despawned
+    /// before saving, which stay invalid. No [`Spawn`] or [`Insert`] events
+    /// are sent for the loaded entities, but [subscribed](World::subscribe)
+    /// queries see them enter.
+    ///
+    /// If an error is returned, the world is left unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the world contains any entities.
+    #[cfg(feature = "serde")]
+    pub fn deserialize<'de, D: serde::Deserializer<'de>>(
+        &mut self,
+        deserializer: D,
+    ) -> Result<(), D::Error> {
+        use serde::de::DeserializeSeed;
+
+        assert!(
+            self.entities.len() == 0 && self.reserved_entities.count() == 0,
+            "world must not contain entities when deserializing"
+        );
+
+        let snapshot = SnapshotSeed {
+            components: &self.components,
+        }
+        .deserialize(deserializer)?;
+
+        self.entities.restore(&snapshot.generations);
+        self.reserved_entities.refresh(&self.entities);
+
+        for arch in snapshot.archetypes {
+            let dst = arch
+                .columns
+                .iter()
+                .fold(ArchetypeIdx::EMPTY, |dst, &(idx, _)| unsafe {
+                    self.archetypes.traverse_insert(
+                        dst,
+                        idx,
+                        &mut self.components,
+                        &mut self.handlers,
+                    )
+                });
+
+            self.archetypes.reserve(dst, arch.entities.len());
+
+            for (row, &entity) in arch.entities.iter().enumerate() {
+                let loc = self.archetypes.spawn(entity);
+                *unsafe { self.entities.get_mut(entity).unwrap_debug_checked() } = loc;
+
+                // Columns are sorted by index, which is the order `move_entity`
+                // expects.
+                unsafe {
+                    self.archetypes.move_entity(
+                        loc,
+                        dst,
+                        arch.columns.iter().map(|(idx, data)| {
+                            let ptr = data.as_ptr().as_ptr().add(row * data.elem_layout().size());
+                            (*idx, ptr.cast_const())
+                        }),
+                        &mut self.entities,
+                    )
+                };
+
+                #[cfg(feature = "entity-history")]
+                self.record_transition(
+                    entity,
+                    TransitionKind::Spawn,
+                    None,
+                    TransitionCause::External,
+                );
+
+                self.on_archetype_move(entity, None, Some(dst));
+            }
+
+            for (idx, mut data) in arch.columns {
+                // Values are owned by the archetype now.
+                unsafe { data.forget_elements() };
+
+                self.check_invariants(idx);
+            }
+        }
+
+        if !self.event_queue.is_empty() {
+            self.flush_event_queue();
+        }
+
+        Ok(())
+    }
+
+    /// Returns an iterator over all entities with the component identified by
+    /// `component`, along with a pointer to the component's data.
+    ///
+    /// The pointer is [`ComponentInfo::size`] bytes long. Like the items of
+    /// the [`Dynamic`] query, the bytes may include uninitialized padding.
+    /// This is useful for reading components whose type is not known at
+    /// compile time.
+    ///
+    /// [`Dynamic`]: crate::query::Dynamic
+    ///
+    /// # Errors
+    ///
+    /// Returns [`StaleComponentId`] if `component` does not exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct C(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, C(123));
+    ///
+    /// let component = world.add_component::<C>();
+    ///
+    /// for (id, ptr) in world.iter_dynamic(component).unwrap() {
+    ///     assert_eq!(id, e);
+    ///     assert_eq!(ptr.len(), 4);
+    ///     assert_eq!(unsafe { ptr.cast::<C>().as_ref().0 }, 123);
+    /// }
+    /// ```
+    pub fn iter_dynamic(
+        &self,
+        component: ComponentId,
+    ) -> Result<impl Iterator<Item = (EntityId, NonNull<[u8]>)> + '_, StaleComponentId> {
+        let info = self
+            .components
+            .get(component)
+            .ok_or(StaleComponentId(component))?;
+        let size = info.size();
+        let stride = info.layout().size();
+
+        Ok(info.member_of.iter().flat_map(move |&arch_idx| {
+            let arch = unsafe { self.archetypes.get(arch_idx).unwrap_debug_checked() };
+            let col = unsafe { arch.column_of(component.index()).unwrap_debug_checked() };
+            let data = col.data();
+
+            arch.entity_ids().iter().enumerate().map(move |(row, &id)| {
+                let ptr = unsafe { NonNull::new_unchecked(data.as_ptr().add(row * stride)) };
+                (id, NonNull::slice_from_raw_parts(ptr, size))
+            })
+        }))
+    }
+
+    /// Adds a new handler to the world, returns its [`HandlerId`], and sends
+    /// the [`AddHandler`] event to signal its creation.
+    ///
+    /// If the handler already exists (as determined by [`Handler::type_id`])
+    /// then the `HandlerId` of the existing handler is returned and no
+    /// event is sent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the configuration of the handler is invalid. This can occur
+    /// when, for instance, the handler does not specify an event to receive.
+    ///
+    /// ```should_panic
+    /// # use evenio::prelude::*;
+    /// #
+    /// # let mut world = World::new();
+    /// #
+    /// world.add_handler(|| {}); // Panics
+    /// ```
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    /// # #[derive(Event)]
+    /// # struct MyEvent;
+    ///
+    /// fn my_handler(_: Receiver<MyEvent>) {};
+    ///
+    /// let mut world = World::new();
+    /// let id = world.add_handler(my_handler);
+    ///
+    /// assert!(world.handlers().contains(id));
+    /// ```
+    ///
+    /// [`Handler::type_id`]: crate::handler::Handler::type_id
+    #[track_caller]
+    pub fn add_handler<H: IntoHandler<M>, M>(&mut self, handler: H) -> HandlerId {
+        self.add_handler_with_budget(handler, None)
+    }
+
+    /// Like [`add_handler`](Self::add_handler), but spreads the work of
+    /// matching the handler against existing archetypes over several
+    /// cascades instead of doing it all at once. At the start of every
+    /// cascade, the handler is matched against up to `budget` more
+    /// archetypes.
+    ///
+    /// Until then, the handler only sees the archetypes it was matched
+    /// against so far. Archetypes created in the meantime are matched
+    /// immediately. Once every archetype is matched, the handler behaves
+    /// exactly as if it was added with `add_handler`, which is reported by
+    /// [`HandlerInfo::fully_matched`].
+    ///
+    /// This is useful to avoid a stall when adding a handler to a world
+    /// with many archetypes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `budget` is zero, or in the same cases as `add_handler`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// # #[derive(Event)] struct E;
+    /// #[derive(Component)]
+    /// struct C<const N: usize>;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, C::<0>);
+    /// world.insert(e, C::<1>);
+    /// world.insert(e, C::<2>);
+    ///
+    /// let id = world.add_handler_incremental(|_: Receiver<E>, _: Fetcher<&C<0>>| {}, 1);
+    /// assert!(!world.handlers()[id].fully_matched());
+    ///
+    /// world.send(E);
+    /// world.send(E);
+    /// assert!(world.handlers()[id].fully_matched());
+    /// ```
+    ///
+    /// [`HandlerInfo::fully_matched`]: crate::handler::HandlerInfo::fully_matched
+    #[track_caller]
+    pub fn add_handler_incremental<H: IntoHandler<M>, M>(
+        &mut self,
+        handler: H,
+        budget: usize,
+    ) -> HandlerId {
+        assert!(budget > 0, "archetype matching budget must be nonzero");
+
+        self.add_handler_with_budget(handler, Some(budget))
+    }
+
+    #[track_caller]
+    fn add_handler_with_budget<H: IntoHandler<M>, M>(
+        &mut self,
+        handler: H,
+        budget: Option<usize>,
+    ) -> HandlerId {
+        let mut handler = handler.into_handler();
+        let mut config = Config::default();
+
+        let type_id = handler.type_id();
+
+        if let Some(type_id) = type_id {
+            if let Some(info) = self.handlers.get_by_type_id(type_id) {
+                return info.id();
+            }
+        }
+
+        assert!(
+            !self.registries_frozen,
+            "cannot add handler `{}` while registries are frozen",
+            handler.name()
+        );
+
+        if let Err(e) = handler.init(self, &mut config) {
+            panic!("{e}");
+        }
+
+        let Some(received_event) = config.received_event else {
+            panic!(
+                "handler `{}` did not specify an event to receive. All handlers must listen for \
+                 exactly one event type (see `Receiver`)",
+                any::type_name::<H>()
+            )
+        };
+
+        if let Err(e) =
+            self.check_exclusive_receiver(&handler.name(), received_event, config.takes_event, None)
+        {
+            panic!("{e}");
+        }
+
+        let info = HandlerInfo::new(HandlerInfoInner {
+            name: handler.name(),
+            id: HandlerId::NULL, // Filled in later.
+            type_id,
+            order: 0, // Filled in later.
+            received_event,
+            received_event_access: config.received_event_access,
+            targeted_event_expr: config.targeted_event_expr,
+            sent_untargeted_events: config.sent_untargeted_events,
+            sent_targeted_events: config.sent_targeted_events,
+            event_queue_access: config.event_queue_access,
+            world_access: config.world_access,
+            component_access: config.component_access,
+            referenced_components: config.referenced_components,
+            change_tick_reads: config.change_tick_reads,
+            resource_access: config.resource_access,
+            priority: config.priority,
+            run_before: config.run_before,
+            run_after: config.run_after,
+            rank: 0, // Filled in later.
+            throttle: config.throttle.map(ThrottleCounters::new),
+            match_exprs: [MatchExprIdx::NULL; 2], // Filled in later.
+            fully_matched: budget.is_none(),
+            handler,
+        });
+
+        let id = self.handlers.add(info);
+
+        if config.takes_event {
+            self.set_exclusive_receiver(received_event, Some(id));
+        }
+
+        let info = self.handlers.get_mut(id).unwrap();
+
+        match budget {
+            Some(budget) => self.archetypes.register_handler_incremental(info, budget),
+            None => self.archetypes.register_handler(info),
+        }
+
+        if !info.run_before().is_empty() || !info.run_after().is_empty() {
+            // Adding the handler may have reordered other handlers.
+            if let EventIdx::Targeted(idx) = info.received_event().index() {
+                self.archetypes.sort_handler_lists(idx);
+            }
+        }
+
+        self.send(AddHandler(id));
+
+        id
+    }
+
+    /// Checks that the handler `name` can receive `event` without breaking
+    /// the exclusivity of a [`TakeReceiver`]. `replacing` is the handler being
+    /// replaced by `name`, which is ignored.
+    ///
+    /// [`TakeReceiver`]: crate::event::TakeReceiver
+    fn check_exclusive_receiver(
+        &self,
+        name: &str,
+        event: EventId,
+        takes_event: bool,
+        replacing: Option<HandlerId>,
+    ) -> Result<(), InitError> {
+        let event_info = self.events.get(event).unwrap();
+
+        if let Some(owner) = event_info.exclusive_receiver {
+            if Some(owner) != replacing {
+                return Err(InitError(
+                    format!(
+                        "handler `{name}` cannot receive event `{}` because handler `{}` takes \
+                         ownership of it with a `TakeReceiver`",
+                        event_info.name(),
+                        self.handlers[owner].name(),
+                    )
+                    .into(),
+                ));
+            }
+        }
+
+        if takes_event {
+            let other = self
+                .handlers
+                .iter()
+                .find(|h| h.received_event() == event && Some(h.id()) != replacing);
+
+            if let Some(other) = other {
+                return Err(InitError(
+                    format!(
+                        "handler `{name}` cannot take ownership of event `{}` with a \
+                         `TakeReceiver` because handler `{}` also receives it",
+                        event_info.name(),
+                        other.name(),
+                    )
+                    .into(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_exclusive_receiver(&mut self, event: EventId, handler: Option<HandlerId>) {
+        if let Some(info) = self.events.get_mut(event) {
+            info.exclusive_receiver = handler;
+        }
+    }
+
+    /// Advances the tick counter used by handlers throttled with
+    /// [`Every::CooldownTicks`], then delivers any coalesced events whose
+    /// cooldown has elapsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// struct E;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let id = world.add_handler((|_: Receiver<E>| {}).cooldown_ticks(2));
+    ///
+    /// world.send(E); // Runs.
+    /// world.advance_handler_cooldowns();
+    /// world.send(E); // Skipped.
+    /// world.advance_handler_cooldowns();
+    /// world.send(E); // Runs.
+    ///
+    /// let stats = world.handlers()[id].throttle_stats().unwrap();
+    /// assert_eq!((stats.runs, stats.skipped), (2, 1));
+    /// ```
+    ///
+    /// [`Every::CooldownTicks`]: crate::handler::Every::CooldownTicks
+    pub fn advance_handler_cooldowns(&mut self) {
+        self.handler_cooldown_tick += 1;
+
+        if !self.deferred_handlers.is_empty() {
+            self.cascade += 1;
+            // SAFETY: Called outside of handlers.
+            unsafe { self.archetypes.continue_matching() };
+            self.run_deferred_handlers();
+            self.dispatch_event_queue();
+            self.despawn_scoped_entities();
+            self.end_dirty_cascade();
+        }
+    }
+
+    /// Returns the current change tick. The tick starts at zero and advances
+    /// by one before each event is dispatched and after each handler runs.
+    /// Ticks are 64 bits wide and compared directly, so they never need to
+    /// wrap around: advancing the tick a billion times per second would take
+    /// centuries to overflow it.
+    ///
+    /// Every component records the tick of its last write, which can be read
+    /// with [`Column::change_ticks`] and filtered on with [`ChangedBetween`]
+    /// and [`Changed`]. A component is written when it is inserted with the
+    /// [`Insert`] event, [`insert_dynamic`], or [`put_entity`], when it is
+    /// fetched mutably with a `&mut` query or a method like [`get_mut`], or
+    /// when it is dereferenced mutably through the [`Mut`] query.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct C;
+    ///
+    /// let mut world = World::new();
+    /// let e = world.spawn();
+    ///
+    /// let before = world.change_tick();
+    /// world.insert(e, C);
+    ///
+    /// assert!(world.change_tick() > before);
+    /// ```
+    ///
+    /// [`Column::change_ticks`]: crate::archetype::Column::change_ticks
+    /// [`ChangedBetween`]: crate::query::ChangedBetween
+    /// [`Changed`]: crate::query::Changed
+    /// [`Mut`]: crate::query::Mut
+    /// [`insert_dynamic`]: World::insert_dynamic
+    /// [`put_entity`]: World::put_entity
+    /// [`get_mut`]: World::get_mut
+    pub fn change_tick(&self) -> u64 {
+        self.archetypes.change_tick()
+    }
+
+    /// Adds a handler to the schedule named `schedule`, creating the schedule
+    /// if it doesn't exist. Returns the ID of the added handler.
+    ///
+    /// Instead of a [`Receiver`], the handler runs when the schedule is run
+    /// with [`run_schedule`]. Handlers of a schedule run in the order they
+    /// were added, subject to their [priority]. Every call adds a new handler,
+    /// even if the same handler was added before.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the handler has a [`Receiver`] or fails to initialize for
+    /// any other reason.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Position(f32);
+    ///
+    /// #[derive(Component)]
+    /// struct Velocity(f32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_to_schedule("simulation", |f: Fetcher<(&mut Position, &Velocity)>| {
+    ///     for (pos, vel) in f {
+    ///         pos.0 += vel.0;
+    ///     }
+    /// });
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, Position(0.0));
+    /// world.insert(e, Velocity(1.5));
+    ///
+    /// world.run_schedule("simulation");
+    ///
+    /// assert_eq!(world.get::<Position>(e).unwrap().0, 1.5);
+    /// ```
+    ///
+    /// [`Receiver`]: crate::event::Receiver
+    /// [`run_schedule`]: World::run_schedule
+    /// [priority]: IntoHandler::high
+    pub fn add_to_schedule<H: IntoHandler<M>, M>(
+        &mut self,
+        schedule: &str,
+        handler: H,
+    ) -> HandlerId {
+        let event = match self.schedules.get(schedule) {
+            Some(id) if self.events.contains(id) => id,
+            _ => {
+                // SAFETY: The descriptor matches `RunSchedule`.
+                let id =
+                    unsafe { self.add_event_with_descriptor(schedule_event_descriptor(schedule)) };
+                self.schedules.insert(schedule, id);
+                id
+            }
+        };
+
+        self.add_handler(Scheduled::new(event, handler.into_handler()))
+    }
+
+    /// Runs every handler added to the schedule named `schedule` with
+    /// [`add_to_schedule`]. Events sent by the handlers are handled before this
+    /// function returns. Does nothing if the schedule doesn't exist.
+    ///
+    /// The schedule is run by sending an untargeted event, so handlers of the
+    /// schedule see the same world state as handlers of an ordinary event.
+    ///
+    /// [`add_to_schedule`]: World::add_to_schedule
+    pub fn run_schedule(&mut self, schedule: &str) {
+        let Some(event) = self.schedules.get(schedule) else {
+            return;
+        };
+
+        if !self.events.contains(event) {
+            return;
+        }
+
+        self.send_many(|s| unsafe {
+            s.world
+                .event_queue
+                .push_front(RunSchedule, event.index().as_u32())
+        });
+    }
+
+    /// Removes a handler from the world, returns its [`HandlerInfo`], and sends
+    /// the [`RemoveHandler`] event. If the `handler` ID is invalid, then `None`
+    /// is returned and no event is sent.
+    ///
+    /// Once the handler is removed, [`Handler::teardown`] is called so the
+    /// handler and its params can free any world-side state.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// # #[derive(Event)]
+    /// # struct MyEvent;
+    /// let handler_id = world.add_handler(|_: Receiver<MyEvent>| {});
+    ///
+    /// let info = world.remove_handler(handler_id).unwrap();
+    ///
+    /// assert_eq!(info.id(), handler_id);
+    /// assert!(!world.handlers().contains(handler_id));
+    /// ```
+    pub fn remove_handler(&mut self, handler: HandlerId) -> Option<HandlerInfo> {
+        if !self.handlers.contains(handler) {
+            return None;
+        }
+
+        self.send(RemoveHandler(handler));
+
+        let mut info = self.handlers.remove(handler).unwrap();
+
+        if self
+            .events
+            .get(info.received_event())
+            .is_some_and(|e| e.exclusive_receiver == Some(handler))
+        {
+            self.set_exclusive_receiver(info.received_event(), None);
+        }
+
+        self.archetypes.remove_handler(&info);
+
+        info.handler_mut().teardown(self);
+
+        Some(info)
+    }
+
+    /// Replaces the implementation of an existing handler with `handler`
+    /// without removing it from the world.
+    ///
+    /// The handler keeps its [`HandlerId`] and its position relative to other
+    /// handlers. The state of every [`Local`] in the old handler is moved to
+    /// the `Local` of the same type in the replacement (see [`Locals`]).
+    /// Locals without a counterpart of the same type start out with their
+    /// default value. Once the replacement is in place, [`Handler::teardown`]
+    /// is called on the old handler. No [`AddHandler`] or [`RemoveHandler`]
+    /// events are sent.
+    ///
+    /// The replacement may access a different set of components than the old
+    /// handler, but must receive the same event and have the same
+    /// [`Priority`]. If the replacement is incompatible or fails to
+    /// initialize, an error is returned and the old handler stays in place.
+    /// Components and events registered while initializing the replacement
+    /// are not removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::handler::Local;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// struct Tick;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let id = world.add_handler(|_: Receiver<Tick>, mut n: Local<u32>| *n += 1);
+    ///
+    /// world.send(Tick);
+    ///
+    /// world
+    ///     .replace_handler(id, |_: Receiver<Tick>, mut n: Local<u32>| {
+    ///         *n += 1;
+    ///         assert_eq!(*n, 2);
+    ///     })
+    ///     .unwrap();
+    ///
+    /// world.send(Tick);
+    /// ```
+    ///
+    /// [`Local`]: crate::handler::Local
+    /// [`Priority`]: crate::handler::Priority
+    pub fn replace_handler<H: IntoHandler<M>, M>(
+        &mut self,
+        id: HandlerId,
+        handler: H,
+    ) -> Result<(), ReplaceHandlerError> {
+        let Some(old) = self.handlers.get(id) else {
+            return Err(ReplaceHandlerError::NoSuchHandler);
+        };
+
+        let expected_event = old.received_event();
+        let expected_priority = old.priority();
+        let old_run_before = old.run_before().to_vec();
+        let old_run_after = old.run_after().to_vec();
+
+        let mut handler = handler.into_handler();
+        let mut config = Config::default();
+
+        let type_id = handler.type_id();
+
+        if let Some(type_id) = type_id {
+            if let Some(info) = self.handlers.get_by_type_id(type_id) {
+                if info.id() != id {
+                    return Err(ReplaceHandlerError::DuplicateTypeId(info.id()));
+                }
+            }
+        }
+
+        if let Err(e) = handler.init(self, &mut config) {
+            return Err(ReplaceHandlerError::Init(e));
+        }
+
+        let error = if config.received_event != Some(expected_event) {
+            Some(ReplaceHandlerError::ReceivedEventMismatch {
+                expected: expected_event,
+                found: config.received_event,
+            })
+        } else if config.priority != expected_priority {
+            Some(ReplaceHandlerError::PriorityMismatch {
+                expected: expected_priority,
+                found: config.priority,
+            })
+        } else {
+            self.check_exclusive_receiver(
+                &handler.name(),
+                expected_event,
+                config.takes_event,
+                Some(id),
+            )
+            .err()
+            .map(ReplaceHandlerError::Init)
+        };
+
+        if let Some(e) = error {
+            handler.teardown(self);
+            return Err(e);
+        }
+
+        let info = HandlerInfo::new(HandlerInfoInner {
+            name: handler.name(),
+            id: HandlerId::NULL, // Filled in later.
+            type_id,
+            order: 0, // Filled in later.
+            received_event: expected_event,
+            received_event_access: config.received_event_access,
+            targeted_event_expr: config.targeted_event_expr,
+            sent_untargeted_events: config.sent_untargeted_events,
+            sent_targeted_events: config.sent_targeted_events,
+            event_queue_access: config.event_queue_access,
+            world_access: config.world_access,
+            component_access: config.component_access,
+            referenced_components: config.referenced_components,
+            change_tick_reads: config.change_tick_reads,
+            resource_access: config.resource_access,
+            priority: config.priority,
+            // The replacement takes the place of the old handler, including its
+            // run order constraints.
+            run_before: old_run_before,
+            run_after: old_run_after,
+            rank: 0, // Filled in later.
+            throttle: config.throttle.map(ThrottleCounters::new),
+            match_exprs: [MatchExprIdx::NULL; 2], // Filled in later.
+            fully_matched: true,
+            handler,
+        });
+
+        self.set_exclusive_receiver(expected_event, config.takes_event.then_some(id));
+
+        let mut old = self.handlers.replace(id, info).unwrap();
+        let info = self.handlers.get_mut(id).unwrap();
+
+        let mut locals = Locals::default();
+        old.handler_mut().take_locals(&mut locals);
+        info.handler_mut().restore_locals(&mut locals);
+
+        self.archetypes.replace_handler(&old, info);
+
+        old.handler_mut().teardown(self);
+
+        Ok(())
+    }
+
+    /// Adds the component `C` to the world, returns its [`ComponentId`], and
+    /// sends the [`AddComponent`] event to signal its creation.
+    ///
+    /// If the component already exists, then the [`ComponentId`] of the
+    /// existing component is returned and no event is sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct MyComponent;
+    ///
+    /// let mut world = World::new();
+    /// let id = world.add_component::<MyComponent>();
+    ///
+    /// assert_eq!(id, world.add_component::<MyComponent>());
+    /// ```
+    pub fn add_component<C: Component>(&mut self) -> ComponentId {
+        let desc = ComponentDescriptor {
+            name: any::type_name::<C>().into(),
+            type_id: Some(TypeId::of::<C>()),
+            layout: Layout::new::<C>(),
+            drop: drop_fn_of::<C>(),
+            is_immutable: C::IS_IMMUTABLE,
+            is_double_buffered: C::DOUBLE_BUFFERED.is_some(),
+            skip_identical_writes: C::SKIP_IDENTICAL_WRITES,
+            fields: collect_fields(C::reflect_fields),
+        };
+
+        unsafe { self.add_component_with_descriptor(desc) }
+    }
+
+    /// Adds the [`DynComponent<T>`] component holding trait objects `T` to
+    /// the world and returns its [`ComponentId`]. See [`World::add_component`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::dyn_component::DynComponent;
+    /// use evenio::prelude::*;
+    ///
+    /// trait Behavior: Send + Sync {}
+    ///
+    /// let mut world = World::new();
+    /// let id = world.init_dyn_component::<dyn Behavior>();
+    ///
+    /// assert_eq!(id, world.add_component::<DynComponent<dyn Behavior>>());
+    /// ```
+    pub fn init_dyn_component<T: ?Sized + Send + Sync + 'static>(&mut self) -> ComponentId {
+        self.add_component::<DynComponent<T>>()
+    }
+
+    /// Makes `entity` share the value of `handle` by inserting a clone of it,
+    /// replacing the entity's current [`Shared<T>`] component, if any. Does
+    /// nothing if the entity already shares the value.
+    ///
+    /// This is how an entity which was given its own copy of a shared value
+    /// by writing to it can share the original value again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    /// use evenio::shared::Shared;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let prefab = Shared::new([0_u64; 32]);
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, prefab.clone());
+    /// world.get_mut::<Shared<[u64; 32]>>(e).unwrap()[0] = 1;
+    ///
+    /// assert_eq!(Shared::ref_count(&prefab), 1);
+    ///
+    /// world.make_shared(e, &prefab);
+    ///
+    /// assert_eq!(Shared::ref_count(&prefab), 2);
+    /// ```
+    pub fn make_shared<T>(&mut self, entity: EntityId, handle: &Shared<T>)
+    where
+        T: Clone + Send + Sync + 'static,
+    {
+        if self
+            .get::<Shared<T>>(entity)
+            .is_some_and(|current| Shared::ptr_eq(current, handle))
+        {
+            return;
+        }
+
+        self.insert(entity, handle.clone());
+    }
+
+    /// Returns how many entities with the [`Shared<T>`] component share each
+    /// value, and how much memory that saves.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    /// use evenio::shared::Shared;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let prefab = Shared::new(0_u64);
+    ///
+    /// for _ in 0..3 {
+    ///     let e = world.spawn();
+    ///     world.insert(e, prefab.clone());
+    /// }
+    ///
+    /// let stats = world.shared_stats::<u64>();
+    ///
+    /// assert_eq!((stats.entities, stats.values), (3, 1));
+    /// assert_eq!(stats.bytes_saved, 16);
+    /// ```
+    pub fn shared_stats<T: Clone + Send + Sync + 'static>(&self) -> SharedStats {
+        shared_stats::<T>(self)
+    }
+
+    /// Sets the value returned by the [`WithDefaultRef<C>`] query for entities
+    /// which do not have component `C`. The component is added to the world
+    /// if it does not already exist.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component, PartialEq, Debug)]
+    /// struct Name(String);
+    ///
+    /// #[derive(Event)]
+    /// struct E;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.set_query_default(Name("unnamed".into()));
+    ///
+    /// world.add_handler(|_: Receiver<E>, f: Fetcher<WithDefaultRef<Name>>| {
+    ///     for name in f {
+    ///         assert_eq!(name.0, "unnamed");
+    ///     }
+    /// });
+    ///
+    /// world.spawn();
+    /// world.send(E);
+    /// ```
+    ///
+    /// [`WithDefaultRef<C>`]: crate::query::WithDefaultRef
+    pub fn set_query_default<C: Component>(&mut self, value: C) {
+        let idx = self.add_component::<C>().index();
+
+        let Some(info) = self.components.get_by_index_mut(idx) else {
+            // Component was removed by a handler of `AddComponent`.
+            return;
+        };
+
+        match &mut info.query_default {
+            // Assign in place so existing fetchers observe the new value.
+            Some(QueryDefault(v)) => *v.downcast_mut::<C>().unwrap() = value,
+            None => info.query_default = Some(QueryDefault(Box::new(value))),
+        }
+    }
+
+    /// Sets a function to call with component `C` whenever it is removed from
+    /// an entity, either by [`Remove`] or [`Despawn`]. The hook is given the
+    /// ID of the entity the component was removed from and runs before the
+    /// component's own [`Drop`] implementation. The component is added to the
+    /// world if it does not already exist.
+    ///
+    /// Hooks run once the events of the current cascade have been handled, so
+    /// the world can be freely accessed from within the hook. Hooks are not
+    /// called for components dropped by replacing them with [`Insert`], by
+    /// removing the component type from the world, or by dropping the world.
+    ///
+    /// Setting a new hook replaces the previous one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Texture(u32);
+    ///
+    /// #[derive(Event)]
+    /// struct TextureFreed(u32);
+    ///
+    /// #[derive(Component)]
+    /// struct FreedTextures(Vec<u32>);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let textures = world.spawn();
+    /// world.insert(textures, FreedTextures(vec![]));
+    ///
+    /// world.set_component_drop_hook::<Texture>(|world, _entity, texture| {
+    ///     world.send(TextureFreed(texture.0));
+    /// });
+    ///
+    /// world.add_handler(
+    ///     |r: Receiver<TextureFreed>, Single(freed): Single<&mut FreedTextures>| {
+    ///         freed.0.push(r.event.0);
+    ///     },
+    /// );
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, Texture(7));
+    /// world.despawn(e);
+    ///
+    /// assert_eq!(world.get::<FreedTextures>(textures).unwrap().0, [7]);
+    /// ```
+    pub fn set_component_drop_hook<C: Component>(
+        &mut self,
+        hook: fn(&mut World, EntityId, &mut C),
+    ) {
+        let idx = self.add_component::<C>().index();
+
+        let Some(info) = self.components.get_by_index_mut(idx) else {
+            // Component was removed by a handler of `AddComponent`.
+            return;
+        };
+
+        info.drop_hook = Some(DropHook(Arc::new(
+            move |world, entity, mut data: BlobVec| {
+                // SAFETY: The buffer holds a single `C`, whose ownership is moved out of the
+                // buffer before the hook can panic.
+                let mut value = unsafe { ptr::read(data.as_ptr().cast::<C>().as_ptr()) };
+                unsafe { data.forget_elements() };
+
+                hook(world, entity, &mut value);
+            },
+        )));
+
+        self.archetypes.refresh_drop_hook(info);
+    }
+
+    /// Registers a check which every value of component `C` must pass. The
+    /// component is added to the world if it does not already exist.
+    ///
+    /// When debug assertions are enabled, every value of `C` in the world is
+    /// checked after `C` is inserted on or removed from an entity, and the
+    /// world panics with the offending entity if `check` returns `false`.
+    /// Values changed through `&mut C` are not checked until the next insert
+    /// or remove. When debug assertions are disabled, this does nothing.
+    ///
+    /// This is intended as a debugging aid for catching corrupted components
+    /// close to where the corruption happened.
+    ///
+    /// # Examples
+    ///
+    /// ```should_panic
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(i32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_invariant::<Health>(|h| h.0 >= 0);
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, Health(-1)); // Panics in debug builds.
+    /// # if !cfg!(debug_assertions) { panic!() }
+    /// ```
+    pub fn add_invariant<C: Component>(&mut self, check: fn(&C) -> bool) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+
+        let idx = self.add_component::<C>().index();
+
+        let Some(info) = self.components.get_by_index_mut(idx) else {
+            // Component was removed by a handler of `AddComponent`.
+            return;
+        };
+
+        info.invariants.push(Invariant(Box::new(move |data, len| {
+            // SAFETY: The caller passes a column of `C` with `len` values.
+            (0..len).position(|row| !check(unsafe { &*data.as_ptr().cast::<C>().add(row) }))
+        })));
+
+        self.check_invariants(idx);
+    }
+
+    /// Panics if a value of the component violates an invariant registered
+    /// with [`World::add_invariant`].
+    fn check_invariants(&self, component_idx: ComponentIdx) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+
+        let Some(info) = self.components.get_by_index(component_idx) else {
+            return;
+        };
+
+        if info.invariants.is_empty() {
+            return;
+        }
+
+        for arch in self.archetypes.iter() {
+            let Some(col) = arch.column_of(component_idx) else {
+                continue;
+            };
+
+            for invariant in &info.invariants {
+                if let Some(row) = (invariant.0)(col.data(), arch.entity_count() as usize) {
+                    panic!(
+                        "invariant of component `{}` violated by entity {:?}",
+                        info.name(),
+                        arch.entity_ids()[row]
+                    );
+                }
+            }
+        }
+    }
+
+    /// Registers a hook which is called once for every component whose size
+    /// exceeds `threshold` bytes. This helps catch components which are
+    /// accidentally large, such as those containing big arrays.
+    ///
+    /// The hook is called when an offending component is added to the world.
+    /// Components which were added before this function was called are
+    /// checked immediately. Each component is reported at most once, even if
+    /// the hook is replaced.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Big([u8; 4096]);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.set_component_size_warning(1024, |info| {
+    ///     eprintln!("component `{}` is {} bytes", info.name(), info.size());
+    /// });
+    ///
+    /// world.add_component::<Big>();
+    /// ```
+    pub fn set_component_size_warning<F>(&mut self, threshold: usize, hook: F)
+    where
+        F: FnMut(&ComponentInfo) + Send + Sync + 'static,
+    {
+        let mut warning = SizeWarning {
+            threshold,
+            hook: Box::new(hook),
+        };
+
+        for info in self.components.iter_mut() {
+            warning.check(info);
+        }
+
+        self.size_warning = Some(warning);
+    }
+
+    /// Returns the memory used by a component's columns across all
+    /// archetypes. Returns `None` if the component ID is invalid.
+    ///
+    /// Column buffers are never shrunk, so the allocated bytes stay the same
+    /// when entities are despawned or moved to other archetypes. They are
+    /// freed when the archetype is removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct C(u64);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, C(123));
+    ///
+    /// let c = world.add_component::<C>();
+    /// let memory = world.component_memory(c).unwrap();
+    ///
+    /// assert_eq!(memory.bytes_used, 8);
+    /// assert!(memory.bytes_allocated >= 8);
+    /// assert_eq!(memory.archetype_count, 1);
+    /// ```
+    pub fn component_memory(&self, component: ComponentId) -> Option<ComponentMemory> {
+        Some(self.components.get(component)?.memory(&self.archetypes))
+    }
+
+    /// Returns the number of entities with component `C`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct C;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// assert_eq!(world.component_count::<C>(), 0);
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, C);
+    ///
+    /// assert_eq!(world.component_count::<C>(), 1);
+    /// ```
+    pub fn component_count<C: Component>(&self) -> usize {
+        self.components
+            .get_by_type_id(TypeId::of::<C>())
+            .map_or(0, |info| info.entity_count(&self.archetypes))
+    }
+
+    /// Returns the number of components registered in the world.
+    ///
+    /// Archetype matching and access checks operate on sets of components
+    /// which are stored inline while every component index is below
+    /// [`INLINE_BITS`]. Registering more components than that makes those
+    /// sets heap-allocated, which slows down adding handlers and moving
+    /// entities between archetypes. See the [`bit_set`] module for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// # #[derive(Component)] struct A;
+    /// # #[derive(Component)] struct B;
+    /// let mut world = World::new();
+    /// let before = world.component_count_registered();
+    ///
+    /// world.add_component::<A>();
+    /// world.add_component::<B>();
+    ///
+    /// assert_eq!(world.component_count_registered(), before + 2);
+    /// ```
+    ///
+    /// [`INLINE_BITS`]: crate::bit_set::INLINE_BITS
+    /// [`bit_set`]: crate::bit_set
+    pub fn component_count_registered(&self) -> usize {
+        self.components.len() as usize
+    }
+
+    /// Limits the number of entities with component `C` to `limit`. The
+    /// component is added to the world if it does not already exist.
+    ///
+    /// The quota is checked whenever `C` is inserted on an entity which does
+    /// not have it yet. If `limit` entities already have `C`, the `policy`
+    /// decides whether the insert is rejected, whether the entity which gained
+    /// `C` the longest time ago is despawned, or whether the quota is
+    /// exceeded. Rejected inserts send the [`QuotaExceeded<C>`] event and are
+    /// counted in [`World::quota_stats`]. Replacing the value of `C` on an
+    /// entity which already has it is never rejected.
+    ///
+    /// Entities which already have `C` when the quota is set are considered
+    /// older than any entity which gains `C` afterwards. Setting a new quota
+    /// replaces the previous one and resets its statistics.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    /// use evenio::quota::QuotaPolicy;
+    ///
+    /// #[derive(Component)]
+    /// struct Projectile;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.set_component_quota::<Projectile>(2, QuotaPolicy::DespawnOldest);
+    ///
+    /// let a = world.spawn();
+    /// world.insert(a, Projectile);
+    /// let b = world.spawn();
+    /// world.insert(b, Projectile);
+    /// let c = world.spawn();
+    /// world.insert(c, Projectile);
+    ///
+    /// assert!(!world.entities().contains(a));
+    /// assert_eq!(world.component_count::<Projectile>(), 2);
+    /// ```
+    pub fn set_component_quota<C: Component>(&mut self, limit: u32, policy: QuotaPolicy) {
+        let idx = self.add_component::<C>().index();
+        let exceeded = self.add_event::<QuotaExceeded<C>>();
+        self.add_event::<Despawn>();
+
+        let Some(info) = self.components.get_by_index_mut(idx) else {
+            // Component was removed by a handler of `AddComponent`.
+            return;
+        };
+
+        let mut quota = Quota::new::<C>(limit, policy, exceeded);
+
+        for &arch_idx in &info.member_of {
+            if let Some(arch) = self.archetypes.get(arch_idx) {
+                for &entity in arch.entity_ids() {
+                    quota.track(entity);
+                }
+            }
+        }
+
+        info.quota = Some(quota);
+    }
+
+    /// Returns the statistics of the quota set on component `C` with
+    /// [`World::set_component_quota`], or `None` if there is none.
+    pub fn quota_stats<C: Component>(&self) -> Option<QuotaStats> {
+        self.components
+            .get_by_type_id(TypeId::of::<C>())?
+            .quota
+            .as_ref()
+            .map(Quota::stats)
+    }
+
+    /// Drops events of type `E` which are equal to an event of type `E` sent
+    /// earlier in the `window`. The event is added to the world if it does
+    /// not already exist.
+    ///
+    /// Events are compared when they are about to be handled, so an event
+    /// which is equal to one which is still queued is handled. Targeted events
+    /// are only equal if their targets are also equal. Dropped events are
+    /// counted in [`World::dedup_stats`].
+    ///
+    /// Setting a new window replaces the previous one and resets its
+    /// statistics.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `window` is [`Window::Cascades(0)`](Window::Cascades).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::dedup::Window;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event, Clone, PartialEq, Eq, Hash)]
+    /// struct DirtyChunk(i32, i32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.dedup_window::<DirtyChunk>(Window::UntilExplicitReset);
+    ///
+    /// world.add_handler(|r: Receiver<DirtyChunk>| {
+    ///     println!("rebuilding chunk ({}, {})", r.event.0, r.event.1);
+    /// });
+    ///
+    /// world.send(DirtyChunk(0, 0)); // Rebuilds.
+    /// world.send(DirtyChunk(0, 0)); // Dropped.
+    /// world.send(DirtyChunk(0, 1)); // Rebuilds.
+    ///
+    /// world.reset_dedup::<DirtyChunk>();
+    /// world.send(DirtyChunk(0, 0)); // Rebuilds.
+    ///
+    /// assert_eq!(world.dedup_stats::<DirtyChunk>().unwrap().suppressed, 1);
+    /// ```
+    pub fn dedup_window<E: Event + Eq + Hash + Clone>(&mut self, window: Window) {
+        assert!(
+            window != Window::Cascades(0),
+            "deduplication window must be nonzero"
+        );
+
+        let id = self.add_event::<E>();
+
+        let Some(info) = self.events.get_mut(id) else {
+            // Event was removed by a handler of `AddEvent`.
+            return;
+        };
+
+        info.dedup = Some(Dedup::new::<E>(window));
+        self.has_dedup = true;
+    }
+
+    /// Forgets the events of type `E` seen in the current window of
+    /// [`World::dedup_window`], so that they are handled again. Does nothing if
+    /// no window is set for `E`.
+    pub fn reset_dedup<E: Event>(&mut self) {
+        let Some(id) = self
+            .events
+            .get_by_type_id(TypeId::of::<E>())
+            .map(EventInfo::id)
+        else {
+            return;
+        };
+
+        if let Some(dedup) = self.events.get_mut(id).and_then(|info| info.dedup.as_mut()) {
+            dedup.reset();
+        }
+    }
+
+    /// Returns the statistics of the deduplication of event `E` set up with
+    /// [`World::dedup_window`], or `None` if there is none.
+    pub fn dedup_stats<E: Event>(&self) -> Option<DedupStats> {
+        self.events
+            .get_by_type_id(TypeId::of::<E>())?
+            .dedup
+            .as_ref()
+            .map(Dedup::stats)
+    }
+
+    /// Runs `validator` on every event of type `E` before it is handled. The
+    /// event is added to the world if it does not already exist.
+    ///
+    /// Every event is validated exactly once before any handler receives it,
+    /// so handlers of `E` only ever see events which passed. An event which
+    /// fails is moved into an [`Invalid<E>`] event sent in its place if any
+    /// handler receives `Invalid<E>`, and dropped otherwise. Both outcomes
+    /// are counted in [`World::validation_stats`].
+    ///
+    /// Setting a new validator replaces the previous one and resets its
+    /// statistics.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    /// use evenio::validate::{Invalid, ValidationError};
+    ///
+    /// #[derive(Event)]
+    /// struct MoveCommand {
+    ///     x: f32,
+    ///     y: f32,
+    /// }
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.set_event_validator(|e: &MoveCommand| {
+    ///     if e.x.is_finite() && e.y.is_finite() {
+    ///         Ok(())
+    ///     } else {
+    ///         Err(ValidationError::new("position is not finite"))
+    ///     }
+    /// });
+    ///
+    /// world.add_handler(|r: Receiver<MoveCommand>| {
+    ///     // No need to check the position again.
+    ///     println!("moving to ({}, {})", r.event.x, r.event.y);
+    /// });
+    ///
+    /// world.add_handler(|r: Receiver<Invalid<MoveCommand>>| {
+    ///     println!("rejected move command: {}", r.event.error);
+    /// });
+    ///
+    /// world.send(MoveCommand { x: 1.0, y: 2.0 });
+    /// world.send(MoveCommand { x: f32::NAN, y: 0.0 });
+    ///
+    /// let stats = world.validation_stats::<MoveCommand>().unwrap();
+    /// assert_eq!(stats.passed, 1);
+    /// assert_eq!(stats.dead_lettered, 1);
+    /// ```
+    pub fn set_event_validator<E, F>(&mut self, validator: F)
+    where
+        E: Event,
+        F: Fn(&E) -> Result<(), ValidationError> + Send + Sync + 'static,
+    {
+        let invalid = self.add_event::<Invalid<E>>();
+        let id = self.add_event::<E>();
+
+        let Some(info) = self.events.get_mut(id) else {
+            // Event was removed by a handler of `AddEvent`.
+            return;
+        };
+
+        info.validator = Some(Validator::new(validator, invalid));
+        self.has_validators = true;
+    }
+
+    /// Returns the statistics of the validator of event `E` set with
+    /// [`World::set_event_validator`], or `None` if there is none.
+    pub fn validation_stats<E: Event>(&self) -> Option<ValidationStats> {
+        self.events
+            .get_by_type_id(TypeId::of::<E>())?
+            .validator
+            .as_ref()
+            .map(Validator::stats)
+    }
+
+    /// Returns the index of the [`Invalid`] event `invalid` if it still exists
+    /// and any handler receives it.
+    fn dead_letter_idx(&self, invalid: EventId) -> Option<u32> {
+        self.events.get(invalid)?;
+
+        let EventIdx::Untargeted(idx) = invalid.index() else {
+            return None;
+        };
+
+        let list = self.handlers.get_untargeted_list(idx)?;
+
+        (!list.handlers().is_empty()).then_some(idx.0)
+    }
+
+    /// Applies the quota of a component to an entity in archetype `arch`
+    /// which is about to gain it. Returns `false` if the insert is rejected.
+    fn apply_quota(
+        &mut self,
+        entity: EntityId,
+        arch: ArchetypeIdx,
+        component_idx: ComponentIdx,
+    ) -> bool {
+        let Some(info) = self.components.get_by_index_mut(component_idx) else {
+            return true;
+        };
+
+        let Some(quota) = &mut info.quota else {
+            return true;
+        };
+
+        let archetypes = &self.archetypes;
+        let entities = &self.entities;
+
+        if archetypes
+            .get(arch)
+            .is_some_and(|arch| arch.column_of(component_idx).is_some())
+        {
+            // Replacing an existing value.
+            return true;
+        }
+
+        let has_component = |entity| {
+            entities
+                .get(entity)
+                .and_then(|loc| archetypes.get(loc.archetype))
+                .is_some_and(|arch| arch.column_of(component_idx).is_some())
+        };
+
+        let live = info
+            .member_of
+            .iter()
+            .filter_map(|&idx| archetypes.get(idx))
+            .map(|arch| arch.entity_count() as usize)
+            .sum::<usize>();
+
+        let action = if live < quota.limit as usize {
+            QuotaAction::Allow
+        } else {
+            match quota.policy {
+                QuotaPolicy::RejectInsert => QuotaAction::Reject,
+                QuotaPolicy::DespawnOldest => QuotaAction::DespawnOldest,
+                QuotaPolicy::Callback(f) => f(entity),
+            }
+        };
+
+        let oldest = match action {
+            QuotaAction::Reject => None,
+            QuotaAction::DespawnOldest => quota.pop_oldest(has_component),
+            QuotaAction::Allow => {
+                quota.track(entity);
+                quota.compact(live, has_component);
+                return true;
+            }
+        };
+
+        let events_before = self.event_queue.len();
+
+        let admitted = if let Some(oldest) = oldest {
+            quota.despawned += 1;
+            quota.track(entity);
+            quota.compact(live, has_component);
+
+            if let Some(info) = self.events.get_by_type_id(TypeId::of::<Despawn>()) {
+                let idx = info.id().index().as_u32();
+                unsafe { self.event_queue.push_front(Despawn(oldest), idx) };
+            }
+
+            true
+        } else {
+            quota.rejected += 1;
+
+            let (event, push) = quota.exceeded;
+            if let Some(info) = self.events.get(event) {
+                let idx = info.id().index().as_u32();
+                unsafe { push(&mut self.event_queue, entity, idx) };
+            }
+
+            false
+        };
+
+        self.enqueue_pushed_events(events_before);
+
+        admitted
+    }
+
+    /// Passes removed components to their drop hooks.
+    fn run_drop_hooks(&mut self) {
+        for (entity, removed) in mem::take(&mut self.drop_hook_queue) {
+            let hook = self
+                .components
+                .get_by_index(removed.idx)
+                .and_then(|info| info.drop_hook.clone());
+
+            // The component is dropped normally if the hook is gone.
+            if let Some(DropHook(hook)) = hook {
+                hook(self, entity, removed.data);
+            }
+        }
+    }
+
+    /// Sets the function used by [`spawn_future`] to run futures. This is
+    /// typically a call to the `spawn` function of an async runtime. No
+    /// executor is included in this library.
+    ///
+    /// [`spawn_future`]: World::spawn_future
+    #[cfg(feature = "async-bridge")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "async-bridge")))]
+    pub fn set_future_spawner<F>(&mut self, spawner: F)
+    where
+        F: Fn(BoxFuture) + Send + Sync + 'static,
+    {
+        self.async_bridge.set_spawner(Box::new(spawner));
+    }
+
+    /// Runs `future` using the spawner registered with [`set_future_spawner`].
+    /// When the future completes, its output is passed to `map` and the
+    /// resulting event is staged. Staged events are sent by the next call to
+    /// [`poll_completed`].
+    ///
+    /// Dropping the returned [`TaskHandle`] cancels delivery of the event. If
+    /// the world is dropped first, the event is discarded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no spawner has been registered.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// use evenio::async_bridge::BoxFuture;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// struct TextureLoaded(Vec<u8>);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// // A stand-in for an async runtime.
+    /// let tasks = Arc::new(Mutex::new(Vec::<BoxFuture>::new()));
+    /// let spawned = tasks.clone();
+    /// world.set_future_spawner(move |fut| spawned.lock().unwrap().push(fut));
+    ///
+    /// world.add_handler(|r: Receiver<TextureLoaded>| {
+    ///     assert_eq!(r.event.0, [1, 2, 3]);
+    /// });
+    ///
+    /// let handle = world.spawn_future(async { vec![1, 2, 3] }, TextureLoaded);
+    ///
+    /// for task in tasks.lock().unwrap().drain(..) {
+    ///     futures_executor::block_on(task);
+    /// }
+    ///
+    /// assert_eq!(world.poll_completed(), 1);
+    /// # drop(handle);
+    /// ```
+    ///
+    /// [`set_future_spawner`]: World::set_future_spawner
+    /// [`poll_completed`]: World::poll_completed
+    #[cfg(feature = "async-bridge")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "async-bridge")))]
+    pub fn spawn_future<F, M, E>(&mut self, future: F, map: M) -> TaskHandle
+    where
+        F: Future + Send + 'static,
+        M: FnOnce(F::Output) -> E + Send + 'static,
+        E: Event,
+    {
+        self.async_bridge.spawn(future, map)
+    }
+
+    /// Sends the events of futures started with [`spawn_future`] which have
+    /// completed since the last call, in the order they completed. Each event
+    /// is sent as if by [`send`]. Returns the number of events sent.
+    ///
+    /// [`spawn_future`]: World::spawn_future
+    /// [`send`]: World::send
+    #[cfg(feature = "async-bridge")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "async-bridge")))]
+    pub fn poll_completed(&mut self) -> usize {
+        let completed = self.async_bridge.take_completed();
+        let count = completed.len();
+
+        for send in completed {
+            send(self);
+        }
+
+        count
+    }
+
+    /// Reserves the buffers used while sending events so that cascades of up
+    /// to `events` events of the currently registered types can be sent
+    /// without allocating. Returns a report of the capacities afterwards.
+    ///
+    /// Handlers are matched against archetypes eagerly when the archetypes are
+    /// created, so there are no lazily built caches for a send to rebuild.
+    /// Allocations that can still occur while sending are those proportional
+    /// to the work performed, such as creating archetypes, growing columns
+    /// for spawned entities, or adding handlers. Use [`assert_no_realloc`] to
+    /// check that a workload avoids them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// struct Tick;
+    ///
+    /// let mut world = World::new();
+    /// world.add_handler(|_: Receiver<Tick>| {});
+    ///
+    /// let report = world.prewarm(64);
+    /// assert!(report.event_queue_capacity >= 64);
+    ///
+    /// world.assert_no_realloc(|world| world.send(Tick));
+    /// ```
+    ///
+    /// [`assert_no_realloc`]: World::assert_no_realloc
+    pub fn prewarm(&mut self, events: usize) -> PrewarmReport {
+        assert!(self.event_queue.is_empty());
+
+        let largest_event = self
+            .events
+            .iter()
+            .map(|info| pad_to_align(&info.layout()).size())
+            .max()
+            .unwrap_or(0);
+
+        // Leave room for aligning each event.
+        let bytes = events.saturating_mul(largest_event.max(1) + 15);
+
+        self.event_queue.reserve(events, bytes);
+        self.deferred_handlers.reserve(self.handlers.iter().count());
+
+        PrewarmReport {
+            event_queue_capacity: self.event_queue.capacity(),
+            event_bytes: self.event_queue.allocated_bytes(),
+            deferred_handlers_capacity: self.deferred_handlers.capacity(),
+        }
+    }
+
+    /// Calls `f` with the world and panics if any of the world's internal
+    /// buffers were reallocated during the call, when debug assertions are
+    /// enabled. Returns the result of `f`.
+    ///
+    /// This covers the event queue, entity storage, and the rows of every
+    /// archetype. Creating an archetype counts as an allocation. See
+    /// [`prewarm`] for reserving the buffers used when sending events.
+    ///
+    /// [`prewarm`]: World::prewarm
+    #[track_caller]
+    pub fn assert_no_realloc<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(&mut World) -> R,
+    {
+        if !cfg!(debug_assertions) {
+            return f(self);
+        }
+
+        let before = self.buffer_capacities();
+        let res = f(self);
+        let after = self.buffer_capacities();
+
+        for (buffer, before) in before {
+            let after = after
+                .iter()
+                .find(|&&(b, _)| b == buffer)
+                .map_or(0, |&(_, cap)| cap);
+
+            assert!(
+                after <= before,
+                "{buffer:?} buffer was reallocated from a capacity of {before} to {after}"
+            );
+        }
+
+        res
+    }
+
+    /// Returns the capacity of every buffer checked by
+    /// [`World::assert_no_realloc`].
+    fn buffer_capacities(&self) -> Vec<(WorldBuffer, usize)> {
+        let mut caps = vec![
+            (WorldBuffer::EventQueue, self.event_queue.capacity()),
+            (
+                WorldBuffer::EventStorage,
+                self.event_queue.allocated_bytes(),
+            ),
+            (
+                WorldBuffer::DeferredHandlers,
+                self.deferred_handlers.capacity(),
+            ),
+            (WorldBuffer::DropHookQueue, self.drop_hook_queue.capacity()),
+            (WorldBuffer::Entities, self.entities.capacity()),
+            (WorldBuffer::Archetypes, self.archetypes.len()),
+        ];
+
+        caps.extend(
+            self.archetypes
+                .iter()
+                .map(|arch| (WorldBuffer::Archetype(arch.index()), arch.buffer_capacity())),
+        );
+
+        caps
+    }
+
+    /// Panics if the world has work left undone, when debug assertions are
+    /// enabled. This is intended for tests which expect the world to have
+    /// settled after a sequence of operations.
+    ///
+    /// The world is considered settled if there are no queued events and no
+    /// events waiting to be delivered later, such as the coalesced events of a
+    /// [throttled] handler or the events of completed futures which have not
+    /// been [polled].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// struct E;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.add_handler(|_: Receiver<E>| {});
+    /// world.send(E);
+    ///
+    /// world.assert_settled();
+    /// ```
+    ///
+    /// [throttled]: crate::handler::Throttle
+    /// [polled]: World::poll_completed
+    #[track_caller]
+    pub fn assert_settled(&self) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+
+        assert!(
+            self.event_queue.is_empty(),
+            "world is not settled: {} events are queued",
+            self.event_queue.iter().count()
+        );
+
+        for handler in self.handlers.iter() {
+            assert!(
+                !handler.throttle_stats().is_some_and(|stats| stats.pending),
+                "world is not settled: handler `{}` has a pending coalesced event",
+                handler.name()
+            );
+        }
+
+        #[cfg(feature = "async-bridge")]
+        {
+            let completed = self.async_bridge.completed_count();
+
+            assert!(
+                completed == 0,
+                "world is not settled: {completed} completed futures have not been polled"
+            );
+        }
+    }
+
+    /// Reorders the rows of every archetype containing component `K` so that
+    /// the values of `K` are in ascending order. Entities with equal keys keep
+    /// their relative order.
+    ///
+    /// Handlers iterate over an archetype in row order, so this can improve
+    /// cache locality when entities processed together are given nearby keys.
+    /// Components are moved rather than cloned, and [`EntityId`]s are not
+    /// affected. Sorting takes _O_(_n_ log _n_) time and allocates a new buffer
+    /// for every column, so it is best done occasionally, such as during a
+    /// loading screen.
+    ///
+    /// Does nothing if `K` has not been added to the world.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component, PartialEq, Eq, PartialOrd, Ord, Debug)]
+    /// struct Squad(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// for squad in [2, 0, 1, 0] {
+    ///     let e = world.spawn();
+    ///     world.insert(e, Squad(squad));
+    /// }
+    ///
+    /// world.sort_archetype_rows::<Squad>();
+    ///
+    /// #[derive(Event)]
+    /// struct Check;
+    ///
+    /// world.add_handler(|_: Receiver<Check>, f: Fetcher<&Squad>| {
+    ///     let squads: Vec<_> = f.iter().map(|s| s.0).collect();
+    ///     assert_eq!(squads, [0, 0, 1, 2]);
+    /// });
+    ///
+    /// world.send(Check);
+    /// ```
+    pub fn sort_archetype_rows<K: Component + Ord>(&mut self) {
+        let Some(info) = self.components.get_by_type_id(TypeId::of::<K>()) else {
+            return;
+        };
+
+        let component_idx = info.id().index();
+
+        for &arch_idx in &info.member_of {
+            let arch = unsafe { self.archetypes.get(arch_idx).unwrap_debug_checked() };
+            let col = unsafe { arch.column_of(component_idx).unwrap_debug_checked() };
+            let keys = col.data().as_ptr().cast::<K>().cast_const();
+
+            let key = |row: u32| unsafe { &*keys.add(row as usize) };
+
+            if (1..arch.entity_count()).all(|row| key(row - 1) <= key(row)) {
+                continue;
+            }
+
+            let mut perm: Vec<u32> = (0..arch.entity_count()).collect();
+            perm.sort_by(|&a, &b| key(a).cmp(key(b)));
+
+            unsafe {
+                self.archetypes
+                    .permute_rows(arch_idx, &perm, &mut self.entities)
+            };
+        }
+    }
+
+    /// Reorders the rows of every archetype containing component `C` along a
+    /// Morton (Z-order) curve through the positions returned by `f`, so that
+    /// entities which are close together in space are also close together in
+    /// memory. `f` projects the component onto a 2D (`[f32; 2]`) or 3D
+    /// (`[f32; 3]`) position.
+    ///
+    /// Positions are quantized within the smallest bounds containing all of
+    /// them. Like [`sort_archetype_rows`], this moves every component of the
+    /// affected archetypes. To only sort archetypes which have become
+    /// noticeably unsorted since the last call, use
+    /// [`sort_rows_morton_with`].
+    ///
+    /// Does nothing if `C` has not been added to the world.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Position {
+    ///     x: f32,
+    ///     y: f32,
+    /// }
+    ///
+    /// let mut world = World::new();
+    ///
+    /// for (x, y) in [(1.0, 1.0), (0.0, 0.0), (1.0, 0.0), (0.0, 1.0)] {
+    ///     let e = world.spawn();
+    ///     world.insert(e, Position { x, y });
+    /// }
+    ///
+    /// world.sort_rows_morton(|p: &Position| [p.x, p.y]);
+    ///
+    /// #[derive(Event)]
+    /// struct Check;
+    ///
+    /// world.add_handler(|_: Receiver<Check>, f: Fetcher<&Position>| {
+    ///     let order: Vec<_> = f.iter().map(|p| (p.x, p.y)).collect();
+    ///     assert_eq!(order, [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)]);
+    /// });
+    ///
+    /// world.send(Check);
+    /// ```
+    ///
+    /// [`sort_archetype_rows`]: World::sort_archetype_rows
+    /// [`sort_rows_morton_with`]: World::sort_rows_morton_with
+    pub fn sort_rows_morton<C, P, F>(&mut self, f: F)
+    where
+        C: Component,
+        P: MortonPoint,
+        F: FnMut(&C) -> P,
+    {
+        self.sort_rows_morton_with(&mut MortonSort::new(), f);
+    }
+
+    /// Like [`sort_rows_morton`], but keeps the rows in approximate order
+    /// across calls by only sorting archetypes whose estimated unsortedness
+    /// exceeds the threshold of `sort`. Returns the number of archetypes
+    /// which were sorted.
+    ///
+    /// If `sort` tracks the bounds of the positions, every position is
+    /// projected with `f` on each call to update them. See [`MortonSort`] for
+    /// more information.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::morton::MortonSort;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Position([f32; 3]);
+    ///
+    /// let mut world = World::new();
+    /// let mut sort = MortonSort::with_bounds([0.0; 3], [100.0; 3]).threshold(0.25);
+    ///
+    /// for i in 0..100 {
+    ///     let e = world.spawn();
+    ///     world.insert(e, Position([(i * 37 % 100) as f32; 3]));
+    /// }
+    ///
+    /// assert_eq!(
+    ///     world.sort_rows_morton_with(&mut sort, |p: &Position| p.0),
+    ///     1
+    /// );
+    ///
+    /// // Nothing changed, so nothing needs to be sorted.
+    /// assert_eq!(
+    ///     world.sort_rows_morton_with(&mut sort, |p: &Position| p.0),
+    ///     0
+    /// );
+    /// ```
+    ///
+    /// [`sort_rows_morton`]: World::sort_rows_morton
+    pub fn sort_rows_morton_with<C, P, F>(&mut self, sort: &mut MortonSort<P>, mut f: F) -> usize
+    where
+        C: Component,
+        P: MortonPoint,
+        F: FnMut(&C) -> P,
+    {
+        let Some(info) = self.components.get_by_type_id(TypeId::of::<C>()) else {
+            return 0;
+        };
+
+        let component_idx = info.id().index();
+
+        let values = |arch: &Archetype| unsafe {
+            let col = arch.column_of(component_idx).unwrap_debug_checked();
+            col.data().as_ptr().cast::<C>().cast_const()
+        };
+
+        let mut bounds_changed = false;
+
+        for &arch_idx in &info.member_of {
+            let arch = unsafe { self.archetypes.get(arch_idx).unwrap_debug_checked() };
+            let values = values(arch);
+
+            for row in 0..arch.entity_count() as usize {
+                bounds_changed |= sort.include(f(unsafe { &*values.add(row) }));
+            }
+        }
+
+        let Some((min, max)) = sort.bounds() else {
+            return 0;
+        };
+
+        let mut sorted = 0;
+
+        for &arch_idx in &info.member_of {
+            let arch = unsafe { self.archetypes.get(arch_idx).unwrap_debug_checked() };
+            let values = values(arch);
+
+            let mut code =
+                |row: u32| f(unsafe { &*values.add(row as usize) }).morton_code(min, max);
+
+            if !bounds_changed && !sort.needs_sort(arch, &mut code) {
+                continue;
+            }
+
+            let codes: Vec<u64> = (0..arch.entity_count()).map(code).collect();
+
+            if codes.windows(2).all(|w| w[0] <= w[1]) {
+                self.archetypes.clear_row_churn(arch_idx);
+                continue;
+            }
+
+            let mut perm: Vec<u32> = (0..arch.entity_count()).collect();
+            perm.sort_by_key(|&row| codes[row as usize]);
+
+            unsafe {
+                self.archetypes
+                    .permute_rows(arch_idx, &perm, &mut self.entities)
+            };
+
+            sorted += 1;
+        }
+
+        sorted
+    }
+
+    /// Copies the current value of the [double-buffered] component `C` into
+    /// its previous value for every entity, making the values visible to the
+    /// [`Previous<C>`] query. Does nothing if `C` has not been added to the
+    /// world.
+    ///
+    /// The copy is performed one column at a time rather than per entity.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `C` is not double-buffered.
+    ///
+    /// [double-buffered]: Component::DOUBLE_BUFFERED
+    /// [`Previous<C>`]: crate::query::Previous
+    pub fn swap_buffers<C: Component>(&mut self) {
+        let Some(info) = self.components.get_by_type_id(TypeId::of::<C>()) else {
+            return;
+        };
+
+        assert!(
+            info.is_double_buffered(),
+            "component `{}` is not double-buffered",
+            info.name()
+        );
+
+        self.archetypes.copy_to_previous(info);
+    }
+
+    /// Adds a component described by a given [`ComponentDescriptor`].
+    ///
+    /// Like [`add_component`], an [`AddComponent`] event is sent if the
+    /// component is newly added. If the [`TypeId`] of the component matches an
+    /// existing component, then the existing component's [`ComponentId`] is
+    /// returned and no event is sent.
+    ///
+    /// # Safety
+    ///
+    /// - If the component is given a [`TypeId`], then the `layout` and `drop`
+    ///   function must be compatible with the Rust type identified by the type
+    ///   ID.
+    /// - Drop function must be safe to call with a pointer to the component as
+    ///   described by [`DropFn`]'s documentation.
+    /// - If the component is double-buffered, then it must be valid to copy the
+    ///   component bitwise.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the component is double-buffered and has a drop function.
+    ///
+    /// [`add_component`]: World::add_component
+    pub unsafe fn add_component_with_descriptor(
+        &mut self,
+        desc: ComponentDescriptor,
+    ) -> ComponentId {
+        assert!(
+            !desc.is_double_buffered || desc.drop.is_none(),
+            "double-buffered component `{}` must not have a drop function",
+            desc.name
+        );
+
+        if self.manifest_locked {
+            if let Some(type_id) = desc.type_id {
+                assert!(
+                    self.components.get_by_type_id(type_id).is_some(),
+                    "component `{}` is not listed in the determinism manifest",
+                    desc.name
+                );
+            }
+        }
+
+        if self.registries_frozen {
+            let exists = desc
+                .type_id
+                .is_some_and(|type_id| self.components.get_by_type_id(type_id).is_some());
+
+            assert!(
+                exists,
+                "cannot add component `{}` while registries are frozen",
+                desc.name
+            );
+        }
+
+        let (id, is_new) = self.components.add(desc);
+
+        if is_new {
+            if let Some(warning) = &mut self.size_warning {
+                // SAFETY: The component was just added.
+                let info = unsafe {
+                    self.components
+                        .get_by_index_mut(id.index())
+                        .unwrap_debug_checked()
+                };
+                warning.check(info);
+            }
+
+            #[cfg(feature = "tracing")]
+            if !self.bit_set_spill_noticed && id.index().0 as usize >= INLINE_BITS {
+                self.bit_set_spill_noticed = true;
+                tracing::info!(
+                    component = %self.components[id].name(),
+                    "more than {INLINE_BITS} components registered; component sets will be \
+                     heap-allocated (enable the `wide-bit-set` feature to raise the limit)"
+                );
+            }
+
+            self.send(AddComponent(id));
+        }
+
+        id
+    }
+
+    /// Removes a component from the world and returns its [`ComponentInfo`]. If
+    /// the `component` ID is invalid, then `None` is returned and the function
+    /// has no effect.
+    ///
+    /// Removing a component has the following effects in the order listed:
+    /// 1. The [`RemoveComponent`] event is sent.
+    /// 2. All entities with the component are despawned.
+    /// 3. All handlers that reference the component are removed.
+    /// 4. The corresponding [`Insert`] events for the component are removed.
+    /// 5. The corresponding [`Remove`] events for the component are removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use evenio::prelude::*;
+    /// # let mut world = World::new();
+    /// # #[derive(Component)] struct C;
+    /// # #[derive(Event)] struct E;
+    /// #
+    /// let component = world.add_component::<C>();
+    /// let handler = world.add_handler(|_: Receiver<E>, _: Fetcher<&C>| {});
+    ///
+    /// assert!(world.components().contains(component));
+    /// assert!(world.handlers().contains(handler));
+    ///
+    /// world.remove_component(component);
+    ///
+    /// assert!(!world.components().contains(component));
+    /// // Handler was also removed because it references `C` in its `Fetcher`.
+    /// assert!(!world.handlers().contains(handler));
+    /// ```
+    pub fn remove_component(&mut self, component: ComponentId) -> Option<ComponentInfo> {
+        if !self.components.contains(component) {
+            return None;
+        }
+
+        // Send event first.
+        self.send(RemoveComponent(component));
+
+        let despawn_idx = self.add_event::<Despawn>().index().as_u32();
+
+        // Attempt to despawn all entities that still have this component.
+        for arch in self.archetypes.iter() {
+            if arch.column_of(component.index()).is_some() {
+                for &entity_id in arch.entity_ids() {
+                    unsafe { self.event_queue.push_front(Despawn(entity_id), despawn_idx) };
+                }
+            }
+        }
+
+        self.flush_event_queue();
+
+        // Remove all handlers that reference this component.
+        let mut handlers_to_remove = vec![];
+
+        for handler in self.handlers.iter() {
+            if handler.referenced_components().contains(component.index()) {
+                handlers_to_remove.push(handler.id());
+            }
+        }
+
+        for handler_id in handlers_to_remove {
+            self.remove_handler(handler_id);
+        }
+
+        let info = &self.components[component];
+
+        // Remove all the `Insert` and `Remove` events for this component.
+        let events_to_remove = info
+            .insert_events()
+            .iter()
+            .copied()
+            .chain(info.remove_events().iter().copied())
+            .collect::<Vec<_>>();
+
+        for event in events_to_remove {
+            self.remove_event(event);
+        }
+
+        let mut info = self
+            .components
+            .remove(component)
+            .expect("component should still exist");
+
+        // Remove all archetypes with this component. If there are still entities with
+        // the component by this point, then they will be silently removed.
+        self.archetypes
+            .remove_component(&mut info, &mut self.components, |id| {
+                self.entities.remove(id);
+            });
+
+        Some(info)
+    }
+
+    /// Adds the event `E` to the world, returns its [`EventId`], and sends the
+    /// [`AddEvent`] event to signal its creation.
+    ///
+    /// If the event already exists, then the [`EventId`] of the existing event
+    /// is returned and no event is sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// struct MyEvent;
+    ///
+    /// let mut world = World::new();
+    /// let id = world.add_event::<MyEvent>();
+    ///
+    /// assert_eq!(id, world.add_event::<MyEvent>());
+    /// ```
+    pub fn add_event<E: Event>(&mut self) -> EventId {
+        let desc = EventDescriptor {
+            name: any::type_name::<E>().into(),
+            type_id: Some(TypeId::of::<E>()),
+            is_targeted: E::IS_TARGETED,
+            kind: unsafe { E::init(self) },
+            layout: Layout::new::<E>(),
+            drop: drop_fn_of::<E>(),
+            is_immutable: E::IS_IMMUTABLE,
+            fields: collect_fields(E::reflect_fields),
+        };
+
+        unsafe { self.add_event_with_descriptor(desc) }
+    }
+
+    /// Adds an event described by a given [`EventDescriptor`].
+    ///
+    /// Like [`add_event`], an [`AddEvent`] event is sent if the
+    /// event is newly added. If the [`TypeId`] of the event matches an
+    /// existing event, then the existing event's [`EventId`] is
+    /// returned and no event is sent.
+    ///
+    /// # Safety
+    ///
+    /// - If the event is given a [`TypeId`], then the `layout` and `drop`
+    ///   function must be compatible with the Rust type identified by the type
+    ///   ID.
+    /// - Drop function must be safe to call with a pointer to the event as
+    ///   described by [`DropFn`]'s documentation.
+    /// - The event's kind must be correct for the descriptor. See
+    ///   [`EventKind`]'s documentation for more information.
+    ///
+    /// [`add_event`]: World::add_event
+    pub unsafe fn add_event_with_descriptor(&mut self, desc: EventDescriptor) -> EventId {
+        if self.manifest_locked {
+            if let Some(type_id) = desc.type_id {
+                assert!(
+                    self.events.get_by_type_id(type_id).is_some(),
+                    "event `{}` is not listed in the determinism manifest",
+                    desc.name
+                );
+            }
+        }
+
+        if self.registries_frozen {
+            let exists = desc
+                .type_id
+                .is_some_and(|type_id| self.events.get_by_type_id(type_id).is_some());
+
+            assert!(
+                exists,
+                "cannot add event `{}` while registries are frozen",
+                desc.name
+            );
+        }
+
+        let kind = desc.kind;
+
+        let (id, is_new) = self.events.add(desc);
+
+        if is_new {
+            self.handlers.register_event(id.index());
+
+            match kind {
+                EventKind::Normal => {}
+                EventKind::Insert { component_idx, .. } => {
+                    if let Some(info) = self.components.get_by_index_mut(component_idx) {
+                        info.insert_events.insert(id);
+                    }
+                }
+                EventKind::InsertBundle { .. } => {}
+                EventKind::Remove { component_idx } => {
+                    if let Some(info) = self.components.get_by_index_mut(component_idx) {
+                        info.remove_events.insert(id);
+                    }
+                }
+                EventKind::SpawnQueued => {}
+                EventKind::Despawn => {}
+            }
+
+            self.send(AddEvent(id));
+        }
+
+        id
+    }
+
+    /// Removes an event from the world and returns its [`EventInfo`]. If
+    /// the `event` ID is invalid, then `None` is returned and the function
+    /// has no effect.
+    ///
+    /// Removing an event has the following effects in the order listed:
+    /// 1. The [`RemoveEvent`] event is sent.
+    /// 2. All handlers that send or receive the event are removed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// struct MyEvent;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let id = world.add_event::<MyEvent>();
+    /// world.remove_event(id);
+    ///
+    /// assert!(!world.events().contains(id));
+    /// ```
+    pub fn remove_event(&mut self, event: EventId) -> Option<EventInfo> {
+        assert!(self.event_queue.is_empty());
+
+        if !self.events.contains(event) || event == EventId::SPAWN_QUEUED {
+            return None;
+        }
+
+        // Send event before removing anything.
+        self.send(RemoveEvent(event));
+
+        // Remove all handlers that send or receive this event.
+        let mut to_remove = vec![];
+
+        for handler in self.handlers.iter() {
+            if handler.received_event() == event
+                || match event.index() {
+                    EventIdx::Targeted(idx) => handler.sent_targeted_events().contains(idx),
+                    EventIdx::Untargeted(idx) => handler.sent_untargeted_events().contains(idx),
+                }
+            {
+                to_remove.push(handler.id());
+            }
+        }
+
+        for id in to_remove {
+            self.remove_handler(id);
+        }
+
+        let info = self.events.remove(event).unwrap();
+
+        match info.kind() {
+            EventKind::Normal => {}
+            EventKind::Insert { component_idx, .. } => {
+                if let Some(info) = self.components.get_by_index_mut(component_idx) {
+                    info.insert_events.remove(&event);
+                }
+            }
+            EventKind::InsertBundle { .. } => {}
+            EventKind::Remove { component_idx } => {
+                if let Some(info) = self.components.get_by_index_mut(component_idx) {
+                    info.remove_events.remove(&event);
+                }
+            }
+            EventKind::SpawnQueued => {}
+            EventKind::Despawn => {}
+        }
+
+        Some(info)
+    }
+
+    /// Inserts the resource `R` into the world, returning the previous value
+    /// if there was one.
+    ///
+    /// Resources hold global data which isn't tied to any entity, such as a
+    /// frame counter or a random number generator. There is at most one
+    /// resource of each type. Handlers access resources with [`Res`] and
+    /// [`ResMut`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// struct Seed(u64);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// assert!(world.insert_resource(Seed(1)).is_none());
+    /// assert_eq!(world.insert_resource(Seed(2)).unwrap().0, 1);
+    /// assert_eq!(world.resource::<Seed>().unwrap().0, 2);
+    /// ```
+    ///
+    /// [`Res`]: crate::resource::Res
+    /// [`ResMut`]: crate::resource::ResMut
+    pub fn insert_resource<R: Send + Sync + 'static>(&mut self, resource: R) -> Option<R> {
+        self.resources.insert(resource)
+    }
+
+    /// Removes the resource `R` from the world and returns it, or returns
+    /// `None` if it doesn't exist.
+    ///
+    /// Handlers accessing `R` panic if they run while it doesn't exist.
+    pub fn remove_resource<R: Send + Sync + 'static>(&mut self) -> Option<R> {
+        self.resources.remove()
+    }
+
+    /// Returns a reference to the resource `R`, or `None` if it doesn't
+    /// exist.
+    pub fn resource<R: Send + Sync + 'static>(&self) -> Option<&R> {
+        self.resources.get()
+    }
+
+    /// Returns a mutable reference to the resource `R`, or `None` if it
+    /// doesn't exist.
+    pub fn resource_mut<R: Send + Sync + 'static>(&mut self) -> Option<&mut R> {
+        self.resources.get_mut()
+    }
+
+    /// Returns the [`Entities`] for this world.
+    pub fn entities(&self) -> &Entities {
+        &self.entities
+    }
+
+    /// Returns the [`Components`] for this world.  
+    pub fn components(&self) -> &Components {
+        &self.components
+    }
+
+    /// Returns the handlers of `event` in the order they run, split into
+    /// groups of consecutive handlers which are pairwise
+    /// [compatible](HandlerInfo::is_compatible). Each group is as long as
+    /// possible, so the first handler of a group conflicts with a handler of
+    /// the previous group. Returns an empty list if the event is invalid.
+    ///
+    /// The handlers of a group may run in any order, or concurrently, without
+    /// changing the outcome of dispatching the event. Targeted events are only
+    /// delivered to the handlers which match the target, but a subset of a
+    /// group is also compatible.
+    ///
+    /// Groups are kept up to date as handlers are added, removed, and
+    /// replaced.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event)]
+    /// struct E;
+    ///
+    /// #[derive(Component)]
+    /// struct A;
+    ///
+    /// #[derive(Component)]
+    /// struct B;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let a = world.add_handler(|_: Receiver<E>, _: Fetcher<&mut A>| {});
+    /// let b = world.add_handler(|_: Receiver<E>, _: Fetcher<&mut B>| {});
+    /// let c = world.add_handler(|_: Receiver<E>, _: Fetcher<&A>| {});
+    ///
+    /// let e = world.add_event::<E>();
+    ///
+    /// assert_eq!(world.dispatch_groups(e), [vec![a, b], vec![c]]);
+    /// ```
+    pub fn dispatch_groups(&self, event: EventId) -> Vec<Vec<HandlerId>> {
+        if self.events.get(event).is_none() {
+            return vec![];
+        }
+
+        self.handlers.dispatch_groups(event.index())
+    }
+
+    /// Returns the [`Handlers`] for this world.
+    pub fn handlers(&self) -> &Handlers {
+        &self.handlers
+    }
+
+    /// Returns the [`Resources`] for this world.
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    pub(crate) fn resources_mut(&mut self) -> &mut Resources {
+        &mut self.resources
+    }
+
+    /// Returns the [`Archetypes`] for this world.
+    pub fn archetypes(&self) -> &Archetypes {
+        &self.archetypes
+    }
+
+    /// Returns every cached edge of the archetype graph.
+    ///
+    /// An edge from archetype `A` to archetype `B` exists once an entity has
+    /// moved from `A` to `B` by inserting or removing a single component. This
+    /// is intended for debugging and visualizing structural changes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::archetype::ArchetypeEdgeKind;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct C;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, C);
+    ///
+    /// let c = world.add_component::<C>();
+    /// let graph = world.archetype_graph();
+    ///
+    /// assert!(graph
+    ///     .iter()
+    ///     .any(|e| e.component == c && e.kind == ArchetypeEdgeKind::Insert));
+    /// ```
+    pub fn archetype_graph(&self) -> Vec<ArchetypeEdge> {
+        self.archetypes.graph(&self.components)
+    }
+
+    /// Returns the [`Events`] for this world.
+    pub fn events(&self) -> &Events {
+        &self.events
+    }
+
+    /// Returns the most recent structural transitions of `entity`, oldest
+    /// first.
+    ///
+    /// At most [`ENTITY_HISTORY_LEN`] transitions are kept for each entity.
+    /// The history of a despawned entity remains available until enough other
+    /// entities are despawned. See [`set_entity_history_retention`]. If
+    /// nothing is known about `entity`, the returned slice is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::history::TransitionKind;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct C;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, C);
+    /// world.despawn(e);
+    ///
+    /// let kinds: Vec<_> = world.entity_history(e).iter().map(|r| r.kind).collect();
+    ///
+    /// assert_eq!(
+    ///     kinds,
+    ///     [
+    ///         TransitionKind::Spawn,
+    ///         TransitionKind::Insert,
+    ///         TransitionKind::Despawn
+    ///     ]
+    /// );
+    /// ```
+    ///
+    /// [`ENTITY_HISTORY_LEN`]: crate::history::ENTITY_HISTORY_LEN
+    /// [`set_entity_history_retention`]: World::set_entity_history_retention
+    #[cfg(feature = "entity-history")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "entity-history")))]
+    pub fn entity_history(&self, entity: EntityId) -> &[TransitionRecord] {
+        self.entity_history.get(entity)
+    }
+
+    /// Sets the number of despawned entities whose history is kept for
+    /// [`entity_history`]. Defaults to [`DEFAULT_HISTORY_RETENTION`].
+    ///
+    /// [`entity_history`]: World::entity_history
+    /// [`DEFAULT_HISTORY_RETENTION`]: crate::history::DEFAULT_HISTORY_RETENTION
+    #[cfg(feature = "entity-history")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "entity-history")))]
+    pub fn set_entity_history_retention(&mut self, retention: usize) {
+        self.entity_history.set_retention(retention);
+    }
+
+    /// Calls `f` with a read-only [`WorldView`] of this world and returns the
+    /// result.
+    ///
+    /// This is intended for third-party inspectors which should only be able
+    /// to observe the world.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// let mut world = World::new();
+    /// world.spawn();
+    ///
+    /// let count = world.inspect(|view| view.entities().len());
+    /// assert_eq!(count, 1);
+    /// ```
+    ///
+    /// The view has no methods which modify the world.
+    ///
+    /// ```compile_fail
+    /// use evenio::prelude::*;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.inspect(|view| view.spawn());
+    /// ```
+    pub fn inspect<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&WorldView) -> R,
+    {
+        f(&WorldView { world: self })
+    }
+
+    /// Starts recording top-level events of type `E` so they can be polled
+    /// with [`drain_events_since`].
+    ///
+    /// Only events sent directly to the world (via [`send`], [`send_many`],
+    /// etc.) are recorded. Events sent from within handlers are not.
+    ///
+    /// [`drain_events_since`]: World::drain_events_since
+    /// [`send`]: World::send
+    /// [`send_many`]: World::send_many
+    pub fn record_events<E: Event + Clone>(&mut self) {
+        self.event_log.add_recorder::<E>();
+    }
+
+    /// Removes all recorded events from the world's event log and returns the
+    /// ones which were sent since `cursor`, along with a new cursor pointing
+    /// past the end of the log.
+    ///
+    /// This is useful for observing the world from outside of handlers, such
+    /// as from a render loop. Only event types registered with
+    /// [`record_events`] are captured.
+    ///
+    /// [`record_events`]: World::record_events
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::event::EventCursor;
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Event, Clone)]
+    /// struct MyEvent(i32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.record_events::<MyEvent>();
+    /// world.send(MyEvent(123));
+    ///
+    /// let (records, _cursor) = world.drain_events_since(EventCursor::default());
+    ///
+    /// assert_eq!(records[0].downcast_ref::<MyEvent>().unwrap().0, 123);
+    /// ```
+    pub fn drain_events_since(&mut self, cursor: EventCursor) -> (Vec<EventRecord>, EventCursor) {
+        self.event_log.drain_since(cursor)
+    }
+
+    /// Copies the items of every entity matching the query `Q` into a `Vec`.
+    ///
+    /// The returned `Vec` reflects the world at the time of the call and is
+    /// not affected by later changes, so it can be handed to another thread
+    /// (e.g. for rendering) while the world continues to handle events. Items
+    /// are grouped by archetype in an arbitrary order.
+    ///
+    /// Queries made of a single `Copy` component or [`EntityId`] are copied
+    /// with one `memcpy` per archetype, and other queries are copied row by
+    /// row. See [`SnapshotQuery`] for the supported queries.
+    ///
+    /// Components which were never added to the world simply match no
+    /// entities, since the world isn't modified.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component, Clone, Copy, PartialEq, Debug)]
+    /// struct Pos(f32, f32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, Pos(1.0, 2.0));
+    ///
+    /// let positions = world.copy_query::<(EntityId, &Pos)>();
+    ///
+    /// world.insert(e, Pos(3.0, 4.0));
+    ///
+    /// assert_eq!(positions, [(e, Pos(1.0, 2.0))]);
+    /// ```
+    pub fn copy_query<Q: SnapshotQuery>(&self) -> Vec<Q::Owned> {
+        let mut state = Q::snapshot_state(self);
+        let mut res = Vec::new();
+
+        for arch in self.archetypes.iter() {
+            let len = arch.entity_count();
+
+            if len == 0 {
+                continue;
+            }
+
+            if let Some(arch_state) = Q::new_arch_state(arch, &mut state) {
+                // SAFETY: The query is read-only, the world is borrowed for
+                // the whole copy, and the archetype state was created from an
+                // archetype with `len` entities.
+                unsafe { Q::extend_owned(&arch_state, len, &mut res) };
+            }
+        }
+
+        res
+    }
+
+    /// Subscribes to entities entering and leaving the query `Q`.
+    ///
+    /// Whenever an entity starts or stops matching `Q` as a result of a
+    /// spawn, despawn, or component insertion or removal, a
+    /// [`QueryTransition`] is recorded in the subscription and the
+    /// [`QueryEntered<Q>`] or [`QueryExited<Q>`] event is sent. Recorded
+    /// transitions are retrieved with [`drain_subscription`].
+    ///
+    /// Entities which already match `Q` when the subscription is created are
+    /// not reported.
+    ///
+    /// [`drain_subscription`]: World::drain_subscription
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::prelude::*;
+    /// use evenio::subscription::QueryTransition;
+    ///
+    /// #[derive(Component)]
+    /// struct Burning;
+    ///
+    /// let mut world = World::new();
+    ///
+    /// let sub = world.subscribe::<With<&Burning>>();
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, Burning);
+    /// world.remove::<Burning>(e);
+    ///
+    /// assert_eq!(
+    ///     world.drain_subscription(sub),
+    ///     [QueryTransition::Entered(e), QueryTransition::Exited(e)]
+    /// );
+    /// ```
+    pub fn subscribe<Q: Query + 'static>(&mut self) -> QuerySubscription {
+        let state = Q::new_state(self);
+
+        // Queries which fail to initialize are checked on every move instead.
+        let mut config = Config::default();
+        let components = Q::init(self, &mut config)
+            .is_ok()
+            .then(|| config.referenced_components.iter().collect());
+
+        let entered = self.add_event::<QueryEntered<Q>>();
+        let exited = self.add_event::<QueryExited<Q>>();
+
+        self.subscriptions
+            .add::<Q>(state, components, self.archetypes.empty(), entered, exited)
+    }
+
+    /// Removes a subscription created with [`subscribe`]. Returns `false` if
+    /// the subscription did not exist.
+    ///
+    /// [`subscribe`]: World::subscribe
+    pub fn unsubscribe(&mut self, sub: QuerySubscription) -> bool {
+        self.subscriptions.remove(sub)
+    }
+
+    /// Removes and returns all transitions recorded by the subscription since
+    /// the last call. Returns an empty `Vec` if the subscription does not
+    /// exist.
+    pub fn drain_subscription(&mut self, sub: QuerySubscription) -> Vec<QueryTransition> {
+        self.subscriptions.drain(sub)
+    }
+
+    /// Starts collecting the entities which change during each cascade of
+    /// events, replacing any previous configuration.
+    ///
+    /// An entity changes when it is spawned or despawned or a component is
+    /// added to or removed from it, and also when a component value is
+    /// written if `scope` is [`DirtyScope::StructuralAndValues`]. However
+    /// often an entity changes during a cascade, it is reported once. Changes
+    /// to components excluded with [`exclude_from_dirty_tracking`] are
+    /// ignored.
+    ///
+    /// With [`DirtyDelivery::Event`], an [`EntitiesDirty`] event is sent at the
+    /// end of every cascade which changed entities. With
+    /// [`DirtyDelivery::Pull`], the entities accumulate until
+    /// [`take_dirty_entities`] is called.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use evenio::dirty::{DirtyDelivery, DirtyScope};
+    /// use evenio::prelude::*;
+    ///
+    /// #[derive(Component)]
+    /// struct Health(u32);
+    ///
+    /// let mut world = World::new();
+    ///
+    /// world.enable_dirty_tracking(DirtyScope::Structural, DirtyDelivery::Pull);
+    ///
+    /// let e = world.spawn();
+    /// world.insert(e, Health(100));
+    /// world.remove::<Health>(e);
+    ///
+    /// assert_eq!(world.take_dirty_entities(), [e]);
+    /// ```
+    ///
+    /// [`exclude_from_dirty_tracking`]: World::exclude_from_dirty_tracking
+    /// [`take_dirty_entities`]: World::take_dirty_entities
+    pub fn enable_dirty_tracking(&mut self, scope: DirtyScope, delivery: DirtyDelivery) {
+        self.dirty = Some(DirtyTracker::new(scope, delivery, self.change_tick()));
+    }
+
+    /// Stops collecting changed entities and discards any which were not
+    /// reported yet. See [`World::enable_dirty_tracking`].
+    pub fn disable_dirty_tracking(&mut self) {
+        self.dirty = None;
+    }
+
+    /// Makes changes to component `C` no longer mark entities as dirty, such
+    /// as for render-only state. See [`World::enable_dirty_tracking`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if dirty tracking is not enabled.
+    pub fn exclude_from_dirty_tracking<C: Component>(&mut self) {
+        let idx = self.add_component::<C>().index();
+
+        self.dirty
+            .as_mut()
+            .expect("dirty tracking is not enabled")
+            .exclude(idx);
+    }
+
+    /// Returns the entities which changed since the last call, in the order
+    /// they first changed, if dirty tracking is enabled with
+    /// [`DirtyDelivery::Pull`]. See [`World::enable_dirty_tracking`].
+    pub fn take_dirty_entities(&mut self) -> Vec<EntityId> {
+        match &mut self.dirty {
+            Some(dirty) if dirty.delivery == DirtyDelivery::Pull => {
+                dirty.scan_values(&self.archetypes);
+                dirty.take()
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Queues an [`ArchetypeMoved`] event and records query subscription
+    /// transitions for `entity` moving from archetype `src` to archetype
+    /// `dst`. `None` indicates the entity did not exist before or after the
+    /// move.
+    fn on_archetype_move(
+        &mut self,
+        entity: EntityId,
+        src: Option<ArchetypeIdx>,
+        dst: Option<ArchetypeIdx>,
+    ) {
+        if src == dst {
+            return;
+        }
+
+        if let Some(dirty) = &mut self.dirty {
+            dirty.on_move(
+                entity,
+                src.and_then(|idx| self.archetypes.get(idx)),
+                dst.and_then(|idx| self.archetypes.get(idx)),
+            );
+        }
+
+        let events_before = self.event_queue.len();
+
+        if let (Some(from), Some(to)) = (src, dst) {
+            if let Some(info) = self.events.get_by_type_id(TypeId::of::<ArchetypeMoved>()) {
+                let idx = info.id().index().as_u32();
+                let event = ArchetypeMoved { entity, from, to };
+
+                // SAFETY: The event index was registered with type `ArchetypeMoved`.
+                unsafe { self.event_queue.push_front(event, idx) };
+            }
+        }
+
+        if !self.subscriptions.is_empty() {
+            let src = src.and_then(|idx| self.archetypes.get(idx));
+            let dst = dst.and_then(|idx| self.archetypes.get(idx));
+            let events = &self.events;
+
+            self.subscriptions
+                .notify(entity, src, dst, &mut self.event_queue, |id| {
+                    events.contains(id)
+                });
+        }
+
+        self.enqueue_pushed_events(events_before);
+    }
+
+    /// Send all queued events to handlers as a new cascade. The event queue
+    /// will be empty after this call.
+    fn flush_event_queue(&mut self) {
+        if let Some((handler, from)) = self.exclusive_handler.take() {
+            // Called through a `WorldMut`. The rest of the queue belongs to the
+            // cascade the handler is part of, so only handle the new events.
+            // Handlers can't be removed through a `WorldMut`, so the handler
+            // still exists.
+            let info: *const HandlerInfo =
+                unsafe { self.handlers.get(handler).unwrap_debug_checked() };
+            let info = unsafe { &*info };
+
+            self.spawn_all_reserved(
+                #[cfg(feature = "entity-history")]
+                info.id(),
+            );
+
+            #[cfg(feature = "entity-history")]
+            self.event_queue.set_sender_from(from, info.id());
+
+            self.dispatch_handler_events(info, from);
+
+            self.exclusive_handler = Some((handler, from));
+            return;
+        }
+
+        // Left behind by a handler which unwound.
+        self.commands.clear();
+        self.despawning.clear();
+
+        self.cascade += 1;
+        // SAFETY: Handlers only flush the queue through a `WorldMut`, so none
+        // are running.
+        unsafe { self.archetypes.continue_matching() };
+        self.dispatch_event_queue();
+        self.despawn_scoped_entities();
+        self.end_dirty_cascade();
+    }
+
+    /// Despawns the entities spawned with [`Sender::spawn_scoped`] during the
+    /// cascade which still exist, along with any scoped entities spawned by
+    /// the handlers of their [`Despawn`] events.
+    ///
+    /// [`Sender::spawn_scoped`]: crate::event::Sender::spawn_scoped
+    fn despawn_scoped_entities(&mut self) {
+        while !self.scoped_entities.is_empty() {
+            let scoped = mem::replace(
+                &mut self.scoped_entities,
+                IndexSet::with_hasher(RandomState::new()),
+            );
+
+            let mut sender = Sender { world: self };
+
+            for &entity in &scoped {
+                // Despawned early, or never spawned because a handler unwound.
+                if sender.world.entities.contains(entity) {
+                    sender.despawn(entity);
+                }
+            }
+
+            self.enqueue_pushed_events(0);
+
+            self.dispatch_event_queue();
+        }
+    }
+
+    /// Reports the entities which changed during the cascade which just
+    /// ended, if dirty tracking is enabled.
+    fn end_dirty_cascade(&mut self) {
+        let Some(dirty) = &mut self.dirty else {
+            return;
+        };
+
+        // Changes made while the event is sent roll over into the next cascade.
+        if dirty.delivering {
+            return;
+        }
+
+        dirty.scan_values(&self.archetypes);
+
+        if dirty.delivery == DirtyDelivery::Event && !dirty.is_empty() {
+            let entities = dirty.take();
+            dirty.delivering = true;
+
+            // In case a handler unwinds.
+            struct Restore<'a>(&'a mut World);
+
+            impl Drop for Restore<'_> {
+                fn drop(&mut self) {
+                    if let Some(dirty) = &mut self.0.dirty {
+                        dirty.delivering = false;
+                    }
+                }
+            }
+
+            Restore(self).0.send(EntitiesDirty { entities });
+        }
+    }
+
+    /// Spawns one entity from the reserved entity queue, if any.
+    fn spawn_reserved(&mut self, #[cfg(feature = "entity-history")] cause: TransitionCause) {
+        let mut spawned = None;
+
+        self.reserved_entities.spawn_one(&mut self.entities, |id| {
+            spawned = Some(id);
+            self.archetypes.spawn(id)
+        });
+
+        if let Some(id) = spawned {
+            #[cfg(feature = "entity-history")]
+            self.record_transition(id, TransitionKind::Spawn, None, cause);
+
+            self.on_archetype_move(id, None, Some(ArchetypeIdx::EMPTY));
+        }
+    }
+
+    /// Handles the events sent so far by the running handler identified by
+    /// `handler`, which started at index `from` of the event queue. The
+    /// handler is suspended in the meantime. See [`Flush`].
+    ///
+    /// [`Flush`]: crate::flush::Flush
+    fn flush_handler_events(&mut self, handler: &HandlerInfo, from: usize) {
+        self.spawn_all_reserved(
+            #[cfg(feature = "entity-history")]
+            handler.id(),
+        );
+
+        #[cfg(feature = "entity-history")]
+        self.event_queue.set_sender_from(from, handler.id());
+
+        self.enqueue_pushed_events(from);
+
+        self.dispatch_handler_events(handler, from);
+    }
+
+    /// Realizes every entity reservation on behalf of a running handler.
+    fn spawn_all_reserved(&mut self, #[cfg(feature = "entity-history")] handler: HandlerId) {
+        // Entities are reserved in order, so realize every reservation before
+        // any of the handler's own.
+        while self.reserved_entities.count() > 0 {
+            self.spawn_reserved(
+                #[cfg(feature = "entity-history")]
+                TransitionCause::Handler(handler),
+            );
+        }
+    }
+
+    /// Handles the events in the range `from..` of the event queue, which are
+    /// already in FIFO order, on behalf of the running handler identified by
+    /// `handler`. The handler is suspended in the meantime.
+    fn dispatch_handler_events(&mut self, handler: &HandlerInfo, from: usize) {
+        let event_sequence = self.event_sequence;
+
+        self.archetypes.suspend_handler(handler.ptr());
+
+        struct Resume<'a>(&'a mut World);
+
+        impl Drop for Resume<'_> {
+            fn drop(&mut self) {
+                self.0.archetypes.resume_handler();
+            }
+        }
+
+        let resume = Resume(self);
+        resume.0.dispatch_events_above(from);
+        drop(resume);
+
+        self.event_sequence = event_sequence;
+        self.handler_events_from = from;
+    }
+
+    /// Runs the handlers of `group` concurrently for the untargeted event
+    /// behind `event_ptr`.
+    ///
+    /// # Safety
+    ///
+    /// - `group` must be a group returned by [`HandlerList::parallel_group_at`]
+    ///   for the list of the event.
+    /// - `event_ptr` must point to the event.
+    #[cfg(feature = "rayon")]
+    unsafe fn run_handler_group(&mut self, group: &[HandlerInfoPtr], event_ptr: EventPtr) {
+        use rayon::prelude::*;
+
+        for ptr in group {
+            let info = ptr.as_info();
+
+            assert!(
+                !self.archetypes.is_suspended(info.ptr()),
+                "handler `{}` received an event while flushing its own events",
+                info.name()
+            );
+        }
+
+        let from = self.event_queue.len();
+        self.handler_events_from = from;
+
+        #[cfg(feature = "tracing")]
+        let spans: Vec<_> = group
+            .iter()
+            .map(|ptr| handler_span(ptr.as_info().name()))
+            .collect();
+
+        struct GroupRun<'a> {
+            group: &'a [HandlerInfoPtr],
+            event_ptr: EventPtr<'a>,
+            world: UnsafeWorldCell<'a>,
+            #[cfg(feature = "tracing")]
+            spans: &'a [tracing::Span],
+        }
+
+        // SAFETY: The handlers of the group are distinct and have compatible
+        // access, so they can run on any thread at the same time. Events and
+        // handlers are `Send + Sync`.
+        unsafe impl Sync for GroupRun<'_> {}
+
+        let run = GroupRun {
+            group,
+            event_ptr,
+            world: self.unsafe_cell_mut(),
+            #[cfg(feature = "tracing")]
+            spans: &spans,
+        };
+
+        (0..group.len()).into_par_iter().for_each(|i| {
+            let run = &run;
+
+            let mut info_ptr = run.group[i];
+            let info = unsafe { info_ptr.as_info_mut() };
+            let handler: *mut dyn Handler = info.handler_mut();
+
+            #[cfg(feature = "tracing")]
+            let _handler_guard = run.spans[i].enter();
+
+            unsafe { (*handler).run(info, run.event_ptr, EntityLocation::NULL, run.world) };
+        });
+
+        // Handlers of a group only read the event, so none of them took it.
+        debug_assert!(group
+            .iter()
+            .all(|ptr| ptr.as_info().received_event_access() != Access::ReadWrite));
+
+        self.archetypes.advance_change_tick();
+
+        if self.archetypes.has_stale_handlers() {
+            self.archetypes.refresh_stale_handlers();
+        }
+
+        // Only one handler of the group can have access to the event queue, so
+        // it sent all of the new events.
+        #[cfg(any(feature = "tracing", feature = "entity-history"))]
+        let sender = group
+            .iter()
+            .position(|ptr| ptr.as_info().event_queue_access() == Access::ReadWrite);
+
+        #[cfg(feature = "tracing")]
+        for (i, span) in spans.iter().enumerate() {
+            if sender == Some(i) {
+                self.finish_handler_span(span, from);
+            } else {
+                span.record("events_sent", 0);
+            }
+        }
+
+        #[cfg(feature = "entity-history")]
+        if let Some(i) = sender {
+            self.event_queue
+                .set_sender_from(from, group[i].as_info().id());
+        }
+
+        if !self.commands.is_empty() {
+            let info = group
+                .iter()
+                .map(|ptr| ptr.as_info())
+                .find(|info| info.event_queue_access() == Access::ReadWrite)
+                .unwrap_debug_checked();
+
+            self.apply_commands(info);
+        }
+    }
+
+    /// Sends the events of the operations recorded by the [`Commands`] of
+    /// `handler`, which just returned, and handles them along with the events
+    /// they cause. Returns `false` if nothing was recorded.
+    ///
+    /// [`Commands`]: crate::commands::Commands
+    fn apply_commands(&mut self, handler: &HandlerInfo) -> bool {
+        if self.commands.is_empty() {
+            return false;
+        }
+
+        let from = self.handler_events_from;
+        let commands = self.commands.take();
+
+        {
+            // Events sent while registering are handled on behalf of the handler,
+            // like the events sent through a `WorldMut`.
+            struct Restore<'a>(&'a mut World, Option<(HandlerId, usize)>);
+
+            impl Drop for Restore<'_> {
+                fn drop(&mut self) {
+                    self.0.exclusive_handler = self.1;
+                }
+            }
+
+            let len = self.event_queue.len();
+            let prev = self.exclusive_handler.replace((handler.id(), len));
+            let restore = Restore(self, prev);
+
+            for command in &commands {
+                (command.register)(restore.0);
+            }
+        }
+
+        for command in commands {
+            (command.send)(&mut Sender { world: self });
+        }
+
+        self.flush_handler_events(handler, from);
+        true
+    }
+
+    /// Called before the `Despawn` event of `entity` with sequence number
+    /// `sequence` and event index `idx` is handled. Returns `false` if the
+    /// event should be skipped instead, because the [`Remove`] events of the
+    /// entity's components were queued ahead of a new `Despawn` event, or are
+    /// being handled. See [`World::set_remove_on_despawn`].
+    fn remove_before_despawn(&mut self, entity: EntityId, sequence: u64, idx: u32) -> bool {
+        if let Some(i) = self.despawning.iter().position(|&(e, _)| e == entity) {
+            if self.despawning[i].1 != sequence {
+                // Despawned again while the `Remove` events are handled.
+                return false;
+            }
+
+            self.despawning.swap_remove(i);
+            return true;
+        }
+
+        if !self.remove_on_despawn {
+            return true;
+        }
+
+        let Some(loc) = self.entities.get(entity) else {
+            return true;
+        };
+
+        let arch = unsafe { self.archetypes.get(loc.archetype).unwrap_debug_checked() };
+
+        let events_before = self.event_queue.len();
+
+        for &component in arch.component_indices() {
+            let info = unsafe {
+                self.components
+                    .get_by_index(component)
+                    .unwrap_debug_checked()
+            };
+
+            for event in info.remove_events() {
+                if let EventIdx::Targeted(remove_idx) = event.index() {
+                    // SAFETY: The event was added as the `Remove` event of the component.
+                    unsafe { self.event_queue.push_front_remove(entity, remove_idx) };
+                }
+            }
+        }
+
+        if self.event_queue.len() == events_before {
+            return true;
+        }
+
+        self.despawning
+            .push((entity, self.event_queue.next_sequence()));
+
+        unsafe { self.event_queue.push_front(Despawn(entity), idx) };
+        self.enqueue_pushed_events(events_before);
+
+        false
+    }
+
+    /// Queues components removed from `entity` which have a drop hook.
+    fn queue_drop_hooks(&mut self, entity: EntityId) {
+        for removed in self.archetypes.take_removed() {
+            self.drop_hook_queue.push((entity, removed));
+        }
+    }
+
+    /// Adds `handler` to the current trace unless the handler's throttle
+    /// skipped the event. `stats` are the throttle stats from before the
+    /// handler ran.
+    #[cold]
+    fn trace_handler_run(&mut self, handler: HandlerId, stats: Option<ThrottleStats>) {
+        let skipped = stats.is_some_and(|before| {
+            self.handlers
+                .get(handler)
+                .and_then(|info| info.throttle_stats())
+                .is_some_and(|after| after.skipped > before.skipped)
+        });
+
+        if let (false, Some((_, ids))) = (skipped, &mut self.trace) {
+            ids.push(handler);
+        }
+    }
+
+    #[cfg(feature = "entity-history")]
+    fn record_transition(
+        &mut self,
+        entity: EntityId,
+        kind: TransitionKind,
+        component: Option<ComponentIdx>,
+        cause: TransitionCause,
+    ) {
+        let component = component
+            .map(|idx| unsafe { self.components.get_by_index(idx).unwrap_debug_checked() }.id());
+
+        self.entity_history.record(
+            entity,
+            TransitionRecord {
+                sequence: self.event_sequence,
+                kind,
+                component,
+                cause,
+            },
+        );
+    }
+
+    /// Makes `span` the parent of the events sent by its handler, which are
+    /// the events in the range `from..` of the event queue, and records how
+    /// many there were.
+    #[cfg(feature = "tracing")]
+    fn finish_handler_span(&mut self, span: &tracing::Span, from: usize) {
+        let sent = self.event_queue.len() - from;
+
+        if sent > 0 && !span.is_disabled() {
+            self.event_queue.set_span_from(from, span);
+        }
+
+        span.record("events_sent", sent);
+    }
+
+    /// Calls [`Handler::run_deferred`] on every handler waiting for it.
+    /// Handlers which still have a postponed delivery remain in the list.
+    fn run_deferred_handlers(&mut self) {
+        let events_before = self.event_queue.len();
+
+        for id in mem::take(&mut self.deferred_handlers) {
+            let Some(info) = self.handlers.get_mut(id) else {
+                // Handler was removed.
+                continue;
+            };
+
+            let handler: *mut dyn Handler = info.handler_mut();
+            let info_ptr = info.ptr();
+            let info: *const HandlerInfo = info;
+
+            if self.archetypes.is_suspended(info_ptr) {
+                // Still running further up the stack. Try again next time.
+                self.deferred_handlers.push(id);
+                continue;
+            }
+
+            #[cfg(feature = "entity-history")]
+            let sender_from = self.event_queue.len();
+
+            #[cfg(feature = "tracing")]
+            let (handler_span, span_from) = (
+                handler_span(unsafe { (*info).name() }),
+                self.event_queue.len(),
+            );
+            #[cfg(feature = "tracing")]
+            let handler_guard = handler_span.enter();
+
+            self.handler_events_from = self.event_queue.len();
+
+            let world_cell = self.unsafe_cell_mut();
+
+            if unsafe { (*handler).run_deferred(&*info, world_cell) } {
+                self.deferred_handlers.push(id);
+            }
+
+            if self.archetypes.has_stale_handlers() {
+                unsafe { self.archetypes.refresh_stale_handlers() };
+            }
+
+            #[cfg(feature = "tracing")]
+            {
+                drop(handler_guard);
+                self.finish_handler_span(&handler_span, span_from);
+            }
+
+            #[cfg(feature = "entity-history")]
+            self.event_queue.set_sender_from(sender_from, id);
+        }
+
+        self.enqueue_pushed_events(events_before);
+    }
+
+    /// Send all queued events to handlers, followed by any deliveries that
+    /// were deferred to the end of the cascade. The event queue will be empty
+    /// after this call.
+    fn dispatch_event_queue(&mut self) {
+        self.dispatch_events_above(0);
+
+        // Resetting the bump allocator would leak the payloads of any events
+        // still in the queue.
+        debug_assert!(self.event_queue.is_empty());
+
+        self.event_queue.clear();
+        self.deferred_events.clear();
+
+        if !self.drop_hook_queue.is_empty() {
+            self.run_drop_hooks();
+        }
+
+        if !self.deferred_handlers.is_empty() {
+            self.run_deferred_handlers();
+
+            if !self.event_queue.is_empty() {
+                self.dispatch_event_queue();
+            }
+        }
+    }
+
+    /// Send the events at index `floor` and above in the event queue to
+    /// handlers, along with the events they cause. Events below `floor` are
+    /// left in the queue.
+    fn dispatch_events_above(&mut self, floor: usize) {
+        while let Some(item) = self.event_queue.pop_front_above(floor) {
+            self.dispatch_event(item);
+        }
+    }
+
+    /// Sends an event popped from the event queue to its handlers and then
+    /// applies the event to the world, if it's one of the special events.
+    fn dispatch_event(&mut self, item: EventQueueItem) {
+        self.event_sequence = item.sequence;
+        self.archetypes.advance_change_tick();
+
+        if !self.prepare_despawn(item.meta, item.sequence) {
+            // `Despawn` doesn't need drop, so it can be skipped.
+            return;
+        }
+
+        #[cfg(feature = "entity-history")]
+        let cause = item
+            .sender
+            .map_or(TransitionCause::External, TransitionCause::Handler);
+
+        let event_info = unsafe {
+            self.events
+                .get_by_index(item.meta.event_idx())
+                .unwrap_debug_checked()
+        };
+        let event_kind = event_info.kind();
+
+        let mut event = EventDropper {
+            event: item.event,
+            drop: event_info.drop(),
+            ownership: EventOwnership::Borrowed,
+        };
+
+        #[cfg(feature = "tracing")]
+        let event_span = event_span(&item.span, event_info.name(), item.meta);
+        #[cfg(feature = "tracing")]
+        let _event_guard = event_span.enter();
+
+        let deferral = self.take_deferral(item.sequence);
+
+        // Redelivered events were already validated and checked for
+        // duplicates.
+        if deferral.is_none() {
+            match self.validate_event(item.meta, event.event) {
+                Validity::Valid => {}
+                Validity::Dropped => return,
+                Validity::Moved => {
+                    event.ownership = EventOwnership::Owned;
+                    return;
+                }
+            }
+
+            if self.is_duplicate_event(item.meta, event.event) {
+                return;
+            }
+        }
+
+        match self.run_event_handlers(&item, &mut event, deferral) {
+            Delivery::Finished => {}
+            Delivery::NoTarget => return,
+            Delivery::Taken => {
+                // Don't drop event since we don't own it anymore.
+                event.unpack();
+                return;
+            }
+            Delivery::Deferred => {
+                // The event is still owned by the queue.
+                event.unpack();
+                self.event_queue.push_back(item);
+                return;
+            }
+        }
+
+        match event_kind {
+            EventKind::Normal => {
+                // Ordinary event. Run drop fn.
+                if let (ptr, Some(drop)) = event.unpack() {
+                    unsafe { drop(ptr) };
+                }
+            }
+            EventKind::Insert {
+                component_idx,
+                component_offset,
+            } => self.apply_insert(
+                event,
+                component_idx,
+                component_offset,
+                #[cfg(feature = "entity-history")]
+                cause,
+            ),
+            EventKind::InsertBundle { bundle_idx } => self.apply_insert_bundle(
+                event,
+                bundle_idx,
+                #[cfg(feature = "entity-history")]
+                cause,
+            ),
+            EventKind::Remove { component_idx } => self.apply_remove(
+                event,
+                component_idx,
+                #[cfg(feature = "entity-history")]
+                cause,
+            ),
+            EventKind::SpawnQueued => {
+                // `SpawnQueued` doesn't need drop.
+                let _ = event.unpack();
+
+                self.spawn_reserved(
+                    #[cfg(feature = "entity-history")]
+                    cause,
+                );
+            }
+            EventKind::Despawn => self.apply_despawn(
+                event,
+                #[cfg(feature = "entity-history")]
+                cause,
+            ),
+        }
+    }
+
+    /// Called before an event with metadata `meta` and sequence number
+    /// `sequence` is dispatched. Returns `false` if the event is a `Despawn`
+    /// event which should be skipped. See [`remove_before_despawn`].
+    ///
+    /// [`remove_before_despawn`]: World::remove_before_despawn
+    fn prepare_despawn(&mut self, meta: EventMeta, sequence: u64) -> bool {
+        if !self.remove_on_despawn && self.despawning.is_empty() {
+            return true;
+        }
+
+        let EventMeta::Targeted { idx, target } = meta else {
+            return true;
+        };
+
+        let is_despawn = self
+            .events
+            .get_by_type_id(TypeId::of::<Despawn>())
+            .is_some_and(|info| info.id().index() == EventIdx::Targeted(idx));
+
+        !is_despawn || self.remove_before_despawn(target, sequence, idx.0)
+    }
+
+    /// Removes and returns the deferral of the event with sequence number
+    /// `sequence`, if the event is being redelivered.
+    fn take_deferral(&mut self, sequence: u64) -> Option<DeferredEvent> {
+        if self.deferred_events.is_empty() {
+            return None;
+        }
+
+        self.deferred_events
+            .iter()
+            .position(|d| d.sequence == sequence)
+            .map(|i| self.deferred_events.swap_remove(i))
+    }
+
+    /// Runs the validator of the event behind `event`, if any. See
+    /// [`World::set_event_validator`].
+    fn validate_event(&mut self, meta: EventMeta, event: NonNull<u8>) -> Validity {
+        if !self.has_validators {
+            return Validity::Valid;
+        }
+
+        let invalid_event = unsafe {
+            self.events
+                .get_by_index(meta.event_idx())
+                .unwrap_debug_checked()
+        }
+        .validator
+        .as_ref()
+        .map(Validator::invalid_event);
+
+        let Some(invalid_event) = invalid_event else {
+            return Validity::Valid;
+        };
+
+        let dead_letter_idx = self.dead_letter_idx(invalid_event);
+
+        let validator = unsafe {
+            self.events
+                .get_by_index_mut(meta.event_idx())
+                .and_then(|info| info.validator.as_mut())
+                .unwrap_debug_checked()
+        };
+
+        unsafe { validator.validate(event, &mut self.event_queue, dead_letter_idx) }
+    }
+
+    /// Returns whether the event behind `event` is a duplicate of an earlier
+    /// event. See [`World::dedup_window`].
+    fn is_duplicate_event(&mut self, meta: EventMeta, event: NonNull<u8>) -> bool {
+        if !self.has_dedup {
+            return false;
+        }
+
+        let info = unsafe {
+            self.events
+                .get_by_index_mut(meta.event_idx())
+                .unwrap_debug_checked()
+        };
+
+        let Some(dedup) = &mut info.dedup else {
+            return false;
+        };
+
+        let target = match meta {
+            EventMeta::Untargeted { .. } => None,
+            EventMeta::Targeted { target, .. } => Some(target),
+        };
+
+        unsafe { dedup.is_duplicate(event, target, self.cascade) }
+    }
+
+    /// Runs the handlers of the event `item`, skipping the ones which
+    /// received the event before it was deferred. The events sent by the
+    /// handlers are left at the front of the queue in FIFO order.
+    fn run_event_handlers(
+        &mut self,
+        item: &EventQueueItem,
+        event: &mut EventDropper,
+        mut deferral: Option<DeferredEvent>,
+    ) -> Delivery {
+        let event_meta = item.meta;
+
+        let (handler_list, mut target_location) = match event_meta {
+            EventMeta::Untargeted { idx } => (
+                unsafe {
+                    self.handlers
+                        .get_untargeted_list(idx)
+                        .unwrap_debug_checked()
+                },
+                EntityLocation::NULL,
+            ),
+            EventMeta::Targeted { idx, target } => {
+                let Some(location) = self.entities.get(target) else {
+                    // Entity doesn't exist. Skip the event.
+                    return Delivery::NoTarget;
+                };
+
+                let arch = unsafe {
+                    self.archetypes
+                        .get(location.archetype)
+                        .unwrap_debug_checked()
+                };
+
+                static EMPTY: HandlerList = HandlerList::new();
+
+                // Return an empty handler list instead of skipping the event in
+                // case this event is special.
+                (arch.handler_list_for(idx).unwrap_or(&EMPTY), location)
+            }
+        };
+
+        let handlers: *const [_] = handler_list.handlers();
+
+        // Groups are only tracked for untargeted events.
+        #[cfg(feature = "rayon")]
+        let parallel_idx = match event_meta {
+            EventMeta::Untargeted { idx } if self.parallel_dispatch => Some(idx),
+            _ => None,
+        };
+        #[cfg(feature = "rayon")]
+        let mut group_end = 0;
+
+        let events_before = self.event_queue.len();
+
+        let traced = self
+            .trace
+            .as_ref()
+            .is_some_and(|&(seq, _)| seq == item.sequence);
+
+        for (i, mut info_ptr) in unsafe { (*handlers).iter().copied().enumerate() } {
+            #[cfg(feature = "rayon")]
+            {
+                if i < group_end {
+                    // Already ran as part of a parallel group.
+                    continue;
+                }
+
+                let group: Option<*const [_]> = parallel_idx
+                    .filter(|_| deferral.is_none() && !traced)
+                    .and_then(|idx| self.handlers.get_untargeted_list(idx))
+                    .and_then(|list| list.parallel_group_at(i))
+                    .map(|group| group as *const [_]);
+
+                if let Some(group) = group {
+                    let group = unsafe { &*group };
+                    let event_ptr = EventPtr::new(event.event, NonNull::from(&mut event.ownership));
+
+                    unsafe { self.run_handler_group(group, event_ptr) };
+
+                    group_end = i + group.len();
+                    continue;
+                }
+            }
+
+            let info = unsafe { info_ptr.as_info_mut() };
+
+            if deferral
+                .as_ref()
+                .is_some_and(|d| d.received.contains(&info.id()))
+            {
+                continue;
+            }
+
+            #[cfg(feature = "entity-history")]
+            let (handler_id, sender_from) = (info.id(), self.event_queue.len());
+
+            assert!(
+                !self.archetypes.is_suspended(info.ptr()),
+                "handler `{}` received an event while flushing its own events",
+                info.name()
+            );
+
+            let handler: *mut dyn Handler = info.handler_mut();
+
+            let event_ptr = EventPtr::new(event.event, NonNull::from(&mut event.ownership));
+
+            #[cfg(feature = "tracing")]
+            let (handler_span, span_from) = (handler_span(info.name()), self.event_queue.len());
+            #[cfg(feature = "tracing")]
+            let handler_guard = handler_span.enter();
+
+            self.handler_events_from = self.event_queue.len();
+
+            let world_cell = self.unsafe_cell_mut();
+
+            let skipped_before = traced.then(|| (info.id(), info.throttle_stats()));
+
+            unsafe { (*handler).run(info, event_ptr, target_location, world_cell) };
+
+            // Writes made by later handlers must have a newer tick than the
+            // ones made by this handler.
+            self.archetypes.advance_change_tick();
+
+            if self.archetypes.has_stale_handlers() {
+                unsafe { self.archetypes.refresh_stale_handlers() };
+            }
+
+            #[cfg(feature = "tracing")]
+            {
+                drop(handler_guard);
+                self.finish_handler_span(&handler_span, span_from);
+            }
+
+            if let Some((id, stats)) = skipped_before {
+                self.trace_handler_run(id, stats);
+            }
+
+            #[cfg(feature = "entity-history")]
+            self.event_queue.set_sender_from(sender_from, handler_id);
+
+            if self.apply_commands(info) {
+                if let EventMeta::Targeted { target, .. } = event_meta {
+                    // The remaining handlers were selected by the archetype of the
+                    // target, so they can't receive the event if it moved to another
+                    // one.
+                    match self.entities.get(target) {
+                        Some(loc) if loc.archetype == target_location.archetype => {
+                            target_location = loc;
+                        }
+                        _ => break,
+                    }
+                }
+            }
+
+            match event.ownership {
+                EventOwnership::Borrowed => {}
+                // Did the handler take ownership of the event?
+                EventOwnership::Owned => {
+                    self.enqueue_pushed_events(events_before);
+                    return Delivery::Taken;
+                }
+                EventOwnership::Deferred => {
+                    let mut deferral = deferral.take().unwrap_or(DeferredEvent {
+                        sequence: item.sequence,
+                        count: 0,
+                        received: vec![],
+                    });
+
+                    deferral.count += 1;
+
+                    if deferral.count > MAX_EVENT_DEFERRALS {
+                        let name = self
+                            .events
+                            .get_by_index(event_meta.event_idx())
+                            .map_or("<removed>", |info| info.name());
+
+                        panic!("event `{name}` was deferred more than {MAX_EVENT_DEFERRALS} times");
+                    }
+
+                    deferral
+                        .received
+                        .extend(unsafe { (&(*handlers))[..=i].iter().map(|p| p.as_info().id()) });
+
+                    self.deferred_events.push(deferral);
+
+                    self.enqueue_pushed_events(events_before);
+                    return Delivery::Deferred;
+                }
+            }
+        }
+
+        self.enqueue_pushed_events(events_before);
+        Delivery::Finished
+    }
+
+    /// Applies an [`Insert`] event whose handlers have run.
+    fn apply_insert(
+        &mut self,
+        event: EventDropper,
+        component_idx: ComponentIdx,
+        component_offset: u32,
+        #[cfg(feature = "entity-history")] cause: TransitionCause,
+    ) {
+        let entity_id = unsafe { *event.event.as_ptr().cast::<EntityId>() };
+
+        let Some(loc) = self.entities.get(entity_id) else {
+            return;
+        };
+
+        if !self.apply_quota(entity_id, loc.archetype, component_idx) {
+            // Rejected by the component's quota. The component is dropped
+            // along with the event.
+            return;
+        }
+
+        let dst = unsafe {
+            self.archetypes.traverse_insert(
+                loc.archetype,
+                component_idx,
+                &mut self.components,
+                &mut self.handlers,
+            )
+        };
+
+        let component_ptr =
+            unsafe { event.event.as_ptr().add(component_offset as usize) }.cast_const();
+
+        unsafe {
+            self.archetypes.move_entity(
+                loc,
+                dst,
+                [(component_idx, component_ptr)],
+                &mut self.entities,
+            )
+        };
+
+        // Inserted component is owned by the archetype now. We wait to unpack
+        // in case one of the above functions panics.
+        event.unpack();
+
+        #[cfg(feature = "entity-history")]
+        self.record_transition(
+            entity_id,
+            TransitionKind::Insert,
+            Some(component_idx),
+            cause,
+        );
+
+        self.on_archetype_move(entity_id, Some(loc.archetype), Some(dst));
+
+        self.check_invariants(component_idx);
+    }
+
+    /// Applies an [`InsertBundle`] event whose handlers have run.
+    ///
+    /// [`InsertBundle`]: crate::event::InsertBundle
+    fn apply_insert_bundle(
+        &mut self,
+        event: EventDropper,
+        bundle_idx: u32,
+        #[cfg(feature = "entity-history")] cause: TransitionCause,
+    ) {
+        let entity_id = unsafe { *event.event.as_ptr().cast::<EntityId>() };
+
+        let Some(loc) = self.entities.get(entity_id) else {
+            return;
+        };
+
+        let bundle_components = unsafe { *self.bundles.get_debug_checked(bundle_idx as usize) };
+
+        let mut components = vec![];
+
+        if !unsafe { bundle_components(&self.components, event.event, &mut components) } {
+            // A component of the bundle was removed from the world.
+            return;
+        }
+
+        let Some(dst) = (unsafe { self.insert_components(entity_id, loc, &components) }) else {
+            // Rejected by a quota. The bundle is dropped along with the event.
+            return;
+        };
+
+        // Inserted components are owned by the archetype now.
+        event.unpack();
+
+        #[cfg(feature = "entity-history")]
+        for &(component_idx, _) in &components {
+            self.record_transition(
+                entity_id,
+                TransitionKind::Insert,
+                Some(component_idx),
+                cause,
+            );
+        }
+
+        self.on_archetype_move(entity_id, Some(loc.archetype), Some(dst));
+
+        for &(component_idx, _) in &components {
+            self.check_invariants(component_idx);
+        }
+    }
+
+    /// Applies a [`Remove`] event whose handlers have run.
+    fn apply_remove(
+        &mut self,
+        event: EventDropper,
+        component_idx: ComponentIdx,
+        #[cfg(feature = "entity-history")] cause: TransitionCause,
+    ) {
+        // `Remove` doesn't need drop.
+        let (event, _) = event.unpack();
+
+        // SAFETY: `Remove` is `repr(transparent)` with the first field being the
+        // `EntityId`, so we can safely reinterpret this pointer.
+        let entity_id = unsafe { *event.as_ptr().cast::<EntityId>() };
+
+        let Some(loc) = self.entities.get(entity_id) else {
+            return;
+        };
+
+        let dst = unsafe {
+            self.archetypes.traverse_remove(
+                loc.archetype,
+                component_idx,
+                &mut self.components,
+                &mut self.handlers,
+            )
+        };
+
+        unsafe {
+            self.archetypes
+                .move_entity(loc, dst, [], &mut self.entities)
+        };
+
+        self.queue_drop_hooks(entity_id);
+
+        #[cfg(feature = "entity-history")]
+        self.record_transition(
+            entity_id,
+            TransitionKind::Remove,
+            Some(component_idx),
+            cause,
+        );
+
+        self.on_archetype_move(entity_id, Some(loc.archetype), Some(dst));
+
+        self.check_invariants(component_idx);
+    }
+
+    /// Applies a [`Despawn`] event whose handlers have run.
+    fn apply_despawn(
+        &mut self,
+        event: EventDropper,
+        #[cfg(feature = "entity-history")] cause: TransitionCause,
+    ) {
+        // `Despawn` doesn't need drop.
+        let (event, _) = event.unpack();
+
+        let entity_id = unsafe { *event.as_ptr().cast::<Despawn>() }.0;
+
+        let src = self.entities.get(entity_id).map(|loc| loc.archetype);
+
+        self.archetypes.remove_entity(entity_id, &mut self.entities);
+
+        self.queue_drop_hooks(entity_id);
+
+        #[cfg(feature = "entity-history")]
+        if src.is_some() {
+            self.record_transition(entity_id, TransitionKind::Despawn, None, cause);
+        }
+
+        self.on_archetype_move(entity_id, src, None);
+
+        // Reset next key iter.
+        self.reserved_entities.refresh(&self.entities);
+    }
+
+    /// Reverses the events pushed to the front of the event queue since it
+    /// had `from` items, so they're handled in FIFO order.
+    fn enqueue_pushed_events(&mut self, from: usize) {
+        if from < self.event_queue.len() {
+            // SAFETY: `from` is in bounds.
+            unsafe { self.event_queue.reverse_from(from) };
+        }
+    }
+
+    /// Returns a new [`UnsafeWorldCell`] with permission to _read_ all data in
+    /// this world.
+    pub fn unsafe_cell(&self) -> UnsafeWorldCell<'_> {
+        UnsafeWorldCell {
+            world: NonNull::from(self),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Returns a new [`UnsafeWorldCell`] with permission to _read and write_
+    /// all data in this world.
+    pub fn unsafe_cell_mut(&mut self) -> UnsafeWorldCell<'_> {
+        UnsafeWorldCell {
+            world: NonNull::from(self),
+            _marker: PhantomData,
+        }
+    }
+}
+
+/// Creates the span of a dispatched event. Nested events are children of the
+/// span of the handler which sent them.
+#[cfg(feature = "tracing")]
+fn event_span(parent: &tracing::Span, name: &str, meta: EventMeta) -> tracing::Span {
+    let span = if parent.is_disabled() {
+        tracing::info_span!("event", name, target = tracing::field::Empty)
+    } else {
+        tracing::info_span!(parent: parent, "event", name, target = tracing::field::Empty)
+    };
+
+    if let EventMeta::Targeted { target, .. } = meta {
+        span.record("target", tracing::field::debug(target));
+    }
+
+    span
+}
+
+/// Creates the span of a handler invocation. `events_sent` is recorded once
+/// the handler returns.
+#[cfg(feature = "tracing")]
+fn handler_span(name: &str) -> tracing::Span {
+    tracing::info_span!("handler", name, events_sent = tracing::field::Empty)
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for World {
+    fn drop(&mut self) {
+        // Drop in-flight events still in the event queue. This can happen if a panic
+        // occurs.
+        for item in self.event_queue.iter() {
+            let info = unsafe {
+                self.events
+                    .get_by_index(item.meta.event_idx())
+                    .unwrap_debug_checked()
+            };
+
+            if let Some(drop) = info.drop() {
+                unsafe { drop(item.event) };
+            }
+        }
+    }
+}
+
+/// Used for queueing events. Passed to the closure given in [`send_many`].
+///
+/// [`send_many`]: World::send_many
+#[derive(Debug)]
+pub struct Sender<'a> {
+    world: &'a mut World,
+}
+
+impl Sender<'_> {
+    /// Enqueue an event.
+    pub fn send<E: Event>(&mut self, event: E) {
+        let id = self.world.add_event::<E>();
+        self.world.event_log.record(id, &event);
+        unsafe {
+            self.world
+                .event_queue
+                .push_front(event, id.index().as_u32())
+        };
+    }
+
+    /// Enqueue the spawning of an entity and [`Spawn`] event. Returns the
+    /// [`EntityId`] of the entity that will be spawned.
+    pub fn spawn(&mut self) -> EntityId {
+        let id = self.world.reserved_entities.reserve(&self.world.entities);
+        unsafe {
+            self.world
+                .event_queue
+                .push_front(SpawnQueued, EventId::SPAWN_QUEUED.index().as_u32())
+        }
+        self.send(Spawn(id));
+        id
+    }
+
+    /// Like [`spawn`](Self::spawn), but the entity is despawned once every
+    /// event sent with this `Sender` and the events they cause have been
+    /// handled, unless it is passed to [`persist`](Self::persist) first. See
+    /// [`event::Sender::spawn_scoped`].
+    ///
+    /// [`event::Sender::spawn_scoped`]: crate::event::Sender::spawn_scoped
+    pub fn spawn_scoped(&mut self) -> EntityId {
+        let id = self.spawn();
+        self.world.scoped_entities.insert(id);
+        id
+    }
+
+    /// Keeps an entity spawned with [`spawn_scoped`](Self::spawn_scoped)
+    /// from being despawned. Has no effect on other entities.
+    pub fn persist(&mut self, entity: EntityId) {
+        self.world.scoped_entities.swap_remove(&entity);
+    }
+
+    /// Enqueue an [`Insert`] event.
+    pub fn insert<C: Component>(&mut self, entity: EntityId, component: C) {
+        self.send(Insert::new(entity, component))
+    }
+
+    /// Enqueue a [`Remove`] event.
+    pub fn remove<C: Component>(&mut self, entity: EntityId) {
+        self.send(Remove::<C>::new(entity))
+    }
+
+    /// Enqueue a [`Despawn`] event.
+    pub fn despawn(&mut self, entity: EntityId) {
+        self.send(Despawn(entity))
+    }
+}
+
+/// Reference to a [`World`] where all methods take `&self` and aliasing rules
+/// are not checked. It is the caller's responsibility to ensure that
+/// Rust's aliasing rules are not violated.
+#[derive(Clone, Copy, Debug)]
+pub struct UnsafeWorldCell<'a> {
+    world: NonNull<World>,
+    _marker: PhantomData<(&'a World, &'a UnsafeCell<World>)>,
+}
+
+impl<'a> UnsafeWorldCell<'a> {
+    /// # Safety
+    ///
+    /// - Must be called from within a handler.
+    /// - Must have permission to access the event queue mutably.
+    /// - Event index must be correct for the given event.
+    pub unsafe fn send_with_index<E: Event>(self, event: E, idx: u32) {
+        unsafe { (*self.world.as_ptr()).event_queue.push_front(event, idx) }
+    }
+
+    /// # Safety
+    ///
+    /// - Must be called from within a handler.
+    /// - Must have permission to access the event queue mutably.
+    pub unsafe fn queue_spawn(self) -> EntityId {
+        let entity_id = (*self.world.as_ptr())
+            .reserved_entities
+            .reserve(self.entities());
+        self.send_with_index(SpawnQueued, EventId::SPAWN_QUEUED.index().as_u32());
+        entity_id
+    }
+
+    /// Marks `entity` to be despawned at the end of the cascade. See
+    /// [`Sender::spawn_scoped`].
+    ///
+    /// # Safety
+    ///
+    /// - Must be called from within a handler.
+    /// - Must have permission to access the event queue mutably.
+    ///
+    /// [`Sender::spawn_scoped`]: crate::event::Sender::spawn_scoped
+    pub(crate) unsafe fn scope_entity(self, entity: EntityId) {
+        (*self.world.as_ptr()).scoped_entities.insert(entity);
+    }
+
+    /// Undoes [`Self::scope_entity`]. See [`Sender::persist`].
+    ///
+    /// # Safety
+    ///
+    /// - Must be called from within a handler.
+    /// - Must have permission to access the event queue mutably.
+    ///
+    /// [`Sender::persist`]: crate::event::Sender::persist
+    pub(crate) unsafe fn persist_entity(self, entity: EntityId) {
+        (*self.world.as_ptr()).scoped_entities.swap_remove(&entity);
+    }
+
+    /// Requests that [`Handler::run_deferred`] be called for the handler
+    /// identified by `handler` at the end of the current cascade.
+    ///
+    /// # Safety
+    ///
+    /// - Must be called from within a handler.
+    pub unsafe fn defer_handler(self, handler: HandlerId) {
+        (*self.world.as_ptr()).deferred_handlers.push(handler);
+    }
+
+    /// Records an operation of the running handler's [`Commands`].
+    ///
+    /// [`Commands`]: crate::commands::Commands
+    ///
+    /// # Safety
+    ///
+    /// - Must be called from within a handler.
+    /// - Must have permission to access the event queue mutably.
+    pub(crate) unsafe fn push_command(self, command: Command) {
+        (*self.world.as_ptr()).commands.push(command);
+    }
+
+    /// Returns the length of the event queue when the running handler
+    /// started.
+    pub(crate) fn handler_events_from(self) -> usize {
+        unsafe { (*self.world.as_ptr()).handler_events_from }
+    }
+
+    /// Handles the events sent so far by the running handler. See
+    /// [`Flush`].
+    ///
+    /// # Safety
+    ///
+    /// - Must be called from within the handler identified by `handler`.
+    /// - `from` must be the value of [`Self::handler_events_from`] when the
+    ///   handler started.
+    /// - Nothing in the world may be borrowed except the event queue through
+    ///   the handler's own parameters.
+    ///
+    /// [`Flush`]: crate::flush::Flush
+    pub(crate) unsafe fn flush_handler_events(self, handler: &HandlerInfo, from: usize) {
+        (*self.world.as_ptr()).flush_handler_events(handler, from)
+    }
+
+    /// Returns the sequence number of the event currently being handled.
+    pub(crate) fn event_sequence(self) -> u64 {
+        unsafe { (*self.world.as_ptr()).event_sequence }
+    }
+
+    /// Returns a number identifying the current cascade of events.
+    pub(crate) fn cascade(self) -> u64 {
+        unsafe { (*self.world.as_ptr()).cascade }
+    }
+
+    /// Returns the tick counter advanced by
+    /// [`World::advance_handler_cooldowns`].
+    pub(crate) fn handler_cooldown_tick(self) -> u64 {
+        unsafe { (*self.world.as_ptr()).handler_cooldown_tick }
+    }
+
+    /// Returns the [`Entities`] for this world.
+    pub fn entities(self) -> &'a Entities {
+        unsafe { &(*self.world.as_ptr()).entities }
+    }
+
+    /// Returns the [`Components`] for this world.
+    pub fn components(self) -> &'a Components {
+        unsafe { &(*self.world.as_ptr()).components }
+    }
+
+    /// Returns the [`Handlers`] for this world.
+    pub fn handlers(self) -> &'a Handlers {
+        unsafe { &(*self.world.as_ptr()).handlers }
+    }
+
+    /// Returns the [`Archetypes`] for this world.
+    pub fn archetypes(self) -> &'a Archetypes {
+        unsafe { &(*self.world.as_ptr()).archetypes }
+    }
+
+    /// Returns the [`Events`] for this world.
+    pub fn events(self) -> &'a Events {
+        unsafe { &(*self.world.as_ptr()).events }
+    }
+
+    /// Returns the [`Resources`] for this world. Values of resources may be
+    /// borrowed mutably by running handlers.
+    pub(crate) fn resources(self) -> &'a Resources {
+        unsafe { &(*self.world.as_ptr()).resources }
+    }
+
+    /// Returns an immutable reference to the underlying world.
+    ///
+    /// # Safety
+    ///
+    /// Must have permission to access the entire world immutably.
+    pub fn world(self) -> &'a World {
+        unsafe { &*self.world.as_ptr() }
+    }
+
+    /// Returns a mutable reference to the underlying world.
+    ///
+    /// # Safety
+    ///
+    /// Must have permission the access the entire world immutably.
+    pub unsafe fn world_mut(self) -> &'a mut World {
+        &mut *self.world.as_ptr()
+    }
+}
+
+/// A read-only view of a [`World`], obtained with [`World::inspect`].
+///
+/// Only the introspection and read APIs of the world are exposed.
+#[derive(Clone, Copy, Debug)]
+pub struct WorldView<'a> {
+    world: &'a World,
+}
+
+impl<'a> WorldView<'a> {
+    /// Returns the [`Entities`] for the world.
+    pub fn entities(&self) -> &'a Entities {
+        self.world.entities()
+    }
+
+    /// Returns the [`Components`] for the world.
+    pub fn components(&self) -> &'a Components {
+        self.world.components()
+    }
+
+    /// Returns the [`Handlers`] for the world.
+    pub fn handlers(&self) -> &'a Handlers {
+        self.world.handlers()
+    }
+
+    /// Returns the [`Archetypes`] for the world.
+    pub fn archetypes(&self) -> &'a Archetypes {
+        self.world.archetypes()
+    }
+
+    /// Returns the [`Events`] for the world.
+    pub fn events(&self) -> &'a Events {
+        self.world.events()
+    }
+
+    /// Gets a reference to component `C` on `entity`. See [`World::get`].
+    pub fn get<C: Component>(&self, entity: EntityId) -> Option<&'a C> {
+        self.world.get(entity)
+    }
+
+    /// Gets a pointer to the component identified by `component` on `entity`.
+    /// See [`World::get_dynamic`].
+    pub fn get_dynamic(
+        &self,
+        entity: EntityId,
+        component: ComponentId,
+    ) -> Result<Option<NonNull<u8>>, StaleComponentId> {
+        self.world.get_dynamic(entity, component)
+    }
+
+    /// Returns an iterator over all entities with the component identified by
+    /// `component`. See [`World::iter_dynamic`].
+    pub fn iter_dynamic(
+        &self,
+        component: ComponentId,
+    ) -> Result<impl Iterator<Item = (EntityId, NonNull<[u8]>)> + 'a, StaleComponentId> {
+        self.world.iter_dynamic(component)
+    }
+
+    /// Returns a hash of the world's schema. See [`World::determinism_hash`].
+    pub fn determinism_hash(&self) -> u64 {
+        self.world.determinism_hash()
+    }
+}
+
+/// Read access to the components of a single entity, obtained with
+/// [`World::entity`].
+#[derive(Clone, Copy, Debug)]
+pub struct EntityRef<'a> {
+    world: &'a World,
+    id: EntityId,
+}
+
+impl<'a> EntityRef<'a> {
+    /// Returns the ID of the entity.
+    pub fn id(&self) -> EntityId {
+        self.id
+    }
+
+    /// Gets a reference to component `C` on the entity, or `None` if the
+    /// entity doesn't have it.
+    pub fn get<C: Component>(&self) -> Option<&'a C> {
+        self.world.get(self.id)
+    }
+
+    /// Returns `true` if the entity has component `C`.
+    pub fn contains<C: Component>(&self) -> bool {
+        has_component::<C>(self.world, self.id)
+    }
+
+    /// Returns the index of the archetype the entity is in.
+    pub fn archetype_id(&self) -> ArchetypeIdx {
+        // SAFETY: The entity can't be despawned while the world is borrowed.
+        unsafe { self.world.entities.get(self.id).unwrap_debug_checked() }.archetype
+    }
+}
+
+/// Read and write access to a single entity, obtained with
+/// [`World::entity_mut`].
+///
+/// Structural changes like [`insert`](Self::insert) send the same events as
+/// the methods of [`World`] with the same name, so handlers of [`Insert`],
+/// [`Remove`] and [`Despawn`] run as usual. Since those handlers may despawn
+/// the entity, the methods of `EntityMut` keep working after the entity is
+/// gone, as if it had no components.
+///
+/// References to components borrow the `EntityMut`, so they can't be held
+/// across a structural change.
+#[derive(Debug)]
+pub struct EntityMut<'a> {
+    world: &'a mut World,
+    id: EntityId,
+}
+
+impl EntityMut<'_> {
+    /// Returns the ID of the entity.
+    pub fn id(&self) -> EntityId {
+        self.id
+    }
+
+    /// Returns `true` if the entity still exists. It may have been despawned
+    /// by the handlers of a structural change.
+    pub fn exists(&self) -> bool {
+        self.world.entities.contains(self.id)
+    }
+
+    /// Gets a reference to component `C` on the entity, or `None` if the
+    /// entity doesn't have it.
+    pub fn get<C: Component>(&self) -> Option<&C> {
+        self.world.get(self.id)
+    }
+
+    /// Gets a mutable reference to component `C` on the entity, or `None` if
+    /// the entity doesn't have it. See [`World::get_mut`].
+    pub fn get_mut<C: Component>(&mut self) -> Option<&mut C> {
+        self.world.get_mut(self.id)
+    }
+
+    /// Returns `true` if the entity has component `C`.
+    pub fn contains<C: Component>(&self) -> bool {
+        has_component::<C>(self.world, self.id)
+    }
+
+    /// Returns the index of the archetype the entity is in, or `None` if the
+    /// entity no longer exists.
+    pub fn archetype_id(&self) -> Option<ArchetypeIdx> {
+        self.world.entities.get(self.id).map(|loc| loc.archetype)
+    }
+
+    /// Inserts `component` on the entity. See [`World::insert`].
+    pub fn insert<C: Component>(&mut self, component: C) -> &mut Self {
+        self.world.insert(self.id, component);
+        self
+    }
+
+    /// Removes component `C` from the entity. See [`World::remove`].
+    pub fn remove<C: Component>(&mut self) -> &mut Self {
+        self.world.remove::<C>(self.id);
+        self
+    }
+
+    /// Despawns the entity. See [`World::despawn`].
+    pub fn despawn(self) {
+        self.world.despawn(self.id);
+    }
+}
+
+fn has_component<C: Component>(world: &World, entity: EntityId) -> bool {
+    let Some(loc) = world.entities.get(entity) else {
+        return false;
+    };
+
+    let Some(info) = world.components.get_by_type_id(TypeId::of::<C>()) else {
+        return false;
+    };
+
+    let arch = unsafe { world.archetypes.get(loc.archetype).unwrap_debug_checked() };
+
+    arch.column_of(info.id().index()).is_some()
+}
+
+/// A [`HandlerParam`] which gives a handler exclusive access to the whole
+/// [`World`].
+///
+/// `WorldMut` dereferences to the world, and provides the methods of the world
+/// which change it without invalidating the handlers further up the stack.
+/// Events sent through a `WorldMut`, including the ones sent by structural
+/// changes like [`WorldMut::despawn`], are handled along with the events they
+/// cause before the method returns, as if the handler used a [`Flush`]. The
+/// handler itself must not receive any of them.
+///
+/// Adding and removing handlers is not possible through a `WorldMut`.
+///
+/// A `WorldMut` has write access to the world and the event queue, so it
+/// conflicts with every other handler parameter which accesses the world or
+/// sends events, and the handler never runs concurrently with another
+/// handler. This includes the [`Receiver`] of a targeted event, whose target
+/// could be moved by the handler.
+///
+/// # Examples
+///
+/// ```
+/// use evenio::prelude::*;
+/// use evenio::world::WorldMut;
+///
+/// #[derive(Event)]
+/// struct Damage(EntityId, i32);
+///
+/// #[derive(Component)]
+/// struct Health(i32);
+///
+/// let mut world = World::new();
+///
+/// world.add_handler(|r: Receiver<Damage>, mut world: WorldMut| {
+///     let Damage(e, amount) = *r.event;
+///
+///     let health = world.get_mut::<Health>(e).unwrap();
+///     health.0 -= amount;
+///
+///     if health.0 <= 0 {
+///         world.despawn(e);
+///
+///         // The entity is gone as soon as `despawn` returns.
+///         assert!(!world.entities().contains(e));
+///     }
+/// });
+///
+/// let e = world.spawn();
+/// world.insert(e, Health(10));
+///
+/// world.send(Damage(e, 15));
+///
+/// assert!(!world.entities().contains(e));
+/// ```
+///
+/// [`Flush`]: crate::flush::Flush
+/// [`Receiver`]: crate::event::Receiver
+pub struct WorldMut<'a> {
+    world: UnsafeWorldCell<'a>,
+    info: &'a HandlerInfo,
+    /// Length of the event queue when the handler started.
+    from: usize,
+}
+
+impl WorldMut<'_> {
+    /// Calls `f` with the world, which handles the events sent by `f` on
+    /// behalf of the handler.
+    fn exclusive<R, F: FnOnce(&mut World) -> R>(&mut self, f: F) -> R {
+        struct Restore<'a>(&'a mut World, Option<(HandlerId, usize)>);
+
+        impl Drop for Restore<'_> {
+            fn drop(&mut self) {
+                self.0.exclusive_handler = self.1;
+            }
+        }
+
+        // SAFETY: The handler has access to the entire world, and nothing else
+        // in the world is borrowed by its parameters.
+        let world = unsafe { self.world.world_mut() };
+
+        let prev = world.exclusive_handler.replace((self.info.id(), self.from));
+        let restore = Restore(world, prev);
+
+        f(restore.0)
+    }
+
+    /// Sends an event. See [`World::send`].
+    pub fn send<E: Event>(&mut self, event: E) {
+        self.exclusive(|world| world.send(event))
+    }
+
+    /// Sends all events enqueued by `f`. See [`World::send_many`].
+    pub fn send_many<F, R>(&mut self, f: F) -> R
+    where
+        F: FnOnce(Sender) -> R,
+    {
+        self.exclusive(|world| world.send_many(f))
+    }
+
+    /// Spawns an entity. See [`World::spawn`].
+    pub fn spawn(&mut self) -> EntityId {
+        self.exclusive(|world| world.spawn())
+    }
+
+    /// Inserts a component on an entity. See [`World::insert`].
+    pub fn insert<C: Component>(&mut self, entity: EntityId, component: C) {
+        self.exclusive(|world| world.insert(entity, component))
+    }
+
+    /// Removes a component from an entity. See [`World::remove`].
+    pub fn remove<C: Component>(&mut self, entity: EntityId) {
+        self.exclusive(|world| world.remove::<C>(entity))
+    }
+
+    /// Despawns an entity. See [`World::despawn`].
+    pub fn despawn(&mut self, entity: EntityId) {
+        self.exclusive(|world| world.despawn(entity))
+    }
+
+    /// Gets a mutable reference to a component of an entity. See
+    /// [`World::get_mut`].
+    pub fn get_mut<C: Component>(&mut self, entity: EntityId) -> Option<&mut C> {
+        // SAFETY: The handler has access to the entire world.
+        unsafe { self.world.world_mut() }.get_mut(entity)
+    }
+
+    /// Adds a component type to the world. See [`World::add_component`].
+    pub fn add_component<C: Component>(&mut self) -> ComponentId {
+        self.exclusive(|world| world.add_component::<C>())
+    }
+
+    /// Adds an event type to the world. See [`World::add_event`].
+    pub fn add_event<E: Event>(&mut self) -> EventId {
+        self.exclusive(|world| world.add_event::<E>())
+    }
+}
+
+impl Deref for WorldMut<'_> {
+    type Target = World;
+
+    fn deref(&self) -> &Self::Target {
+        self.world.world()
+    }
+}
+
+impl fmt::Debug for WorldMut<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WorldMut")
+            .field("from", &self.from)
+            .finish_non_exhaustive()
+    }
+}
+
+unsafe impl HandlerParam for WorldMut<'_> {
+    type State = ();
+
+    type Item<'a> = WorldMut<'a>;
+
+    fn init(_world: &mut World, config: &mut Config) -> Result<Self::State, InitError> {
+        if config.world_access != Access::None || config.event_queue_access != Access::None {
+            return Err(InitError(
+                "`WorldMut` conflicts with a previous handler parameter which accesses the world \
+                 or sends events"
+                    .into(),
+            ));
+        }
+
+        config.world_access = Access::ReadWrite;
+        config.event_queue_access = Access::ReadWrite;
+
+        Ok(())
+    }
+
+    unsafe fn get<'a>(
+        _state: &'a mut Self::State,
+        info: &'a HandlerInfo,
+        _event_ptr: EventPtr<'a>,
+        _target_location: EntityLocation,
+        world: UnsafeWorldCell<'a>,
+    ) -> Self::Item<'a> {
+        WorldMut {
+            world,
+            info,
+            from: world.handler_events_from(),
+        }
+    }
+
+    fn refresh_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+
+    fn remove_archetype(_state: &mut Self::State, _arch: &Archetype) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+    use core::alloc::Layout;
+    use core::panic::{RefUnwindSafe, UnwindSafe};
+    use core::ptr::NonNull;
+    use std::panic;
+
+    use crate::component::{ComponentDescriptor, StaleComponentId};
+    use crate::determinism::Manifest;
+    use crate::drop::{drop_fn_of, DropFn};
+    use crate::event::{ArchetypeMoved, EventCursor, TakeReceiver};
+    use crate::handler::ReplaceHandlerError;
+    use crate::prelude::*;
+    use crate::query::Dynamic;
+    use crate::world::WorldMut;
+
+    #[test]
+    fn drain_events_since_cursor() {
+        #[derive(Event, Clone, PartialEq, Debug)]
+        struct A(u32);
+
+        #[derive(Event)]
+        struct B;
+
+        let mut world = World::new();
+
+        world.record_events::<A>();
+
+        world.send(A(1));
+        world.send(B);
+        world.send(A(2));
+
+        let (records, cursor) = world.drain_events_since(EventCursor::default());
+
+        assert_eq!(
+            records
+                .iter()
+                .map(|r| r.downcast_ref::<A>().unwrap())
+                .collect::<Vec<_>>(),
+            [&A(1), &A(2)]
+        );
+
+        world.send_many(|mut s| {
+            s.send(A(3));
+            s.send(A(4));
+        });
+
+        let (records, _) = world.drain_events_since(cursor);
+
+        assert_eq!(
+            records
+                .into_iter()
+                .map(|r| r.downcast::<A>().unwrap())
+                .collect::<Vec<_>>(),
+            [A(3), A(4)]
+        );
+    }
+
+    #[test]
+    fn dynamic_component_bytes() {
+        let mut world = World::new();
+
+        let component = unsafe {
+            world.add_component_with_descriptor(ComponentDescriptor {
+                name: "dynamic".into(),
+                type_id: None,
+                layout: Layout::new::<[u8; 3]>(),
+                drop: None,
+                is_immutable: false,
+                is_double_buffered: false,
+                skip_identical_writes: None,
+                fields: vec![],
+            })
+        };
+
+        let e1 = world.spawn();
+        let e2 = world.spawn();
+        let e3 = world.spawn();
+
+        for (e, bytes) in [(e1, [1_u8, 2, 3]), (e2, [4, 5, 6]), (e2, [7, 8, 9])] {
+            unsafe { world.insert_dynamic(e, component, NonNull::from(&bytes).cast()) }.unwrap();
+        }
+
+        let mut items = world
+            .iter_dynamic(component)
+            .unwrap()
+            .map(|(id, ptr)| (id, unsafe { ptr.as_ref().to_vec() }))
+            .collect::<Vec<_>>();
+
+        items.sort_by_key(|(id, _)| *id);
+
+        assert_eq!(items, [(e1, vec![1, 2, 3]), (e2, vec![7, 8, 9])]);
+        assert!(world
+            .iter_dynamic(component)
+            .unwrap()
+            .all(|(id, _)| id != e3));
+    }
+
+    #[test]
+    fn dynamic_query() {
+        use std::sync::Mutex;
+
+        #[derive(Event)]
+        struct E;
+
+        let mut world = World::new();
+
+        let add = |world: &mut World, name: &'static str| unsafe {
+            world.add_component_with_descriptor(ComponentDescriptor {
+                name: name.into(),
+                type_id: None,
+                layout: Layout::new::<[u8; 3]>(),
+                drop: None,
+                is_immutable: false,
+                is_double_buffered: false,
+                skip_identical_writes: None,
+                fields: vec![],
+            })
+        };
+
+        let a = add(&mut world, "a");
+        let b = add(&mut world, "b");
+
+        let e1 = world.spawn();
+        let e2 = world.spawn();
+        world.spawn();
+
+        for (e, c, bytes) in [
+            (e1, a, [1_u8, 2, 3]),
+            (e2, a, [4, 5, 6]),
+            (e2, b, [7, 8, 9]),
+        ] {
+            unsafe { world.insert_dynamic(e, c, NonNull::from(&bytes).cast()) }.unwrap();
+        }
+
+        let seen = Arc::new(Mutex::new(vec![]));
+        let s = seen.clone();
+
+        world.add_handler(
+            (move |_: Receiver<E>, f: Fetcher<(EntityId, Dynamic, Option<Dynamic>)>| {
+                for (id, a, b) in f {
+                    let read = |ptr: NonNull<[u8]>| unsafe { ptr.as_ref().to_vec() };
+                    s.lock().unwrap().push((id, read(a), b.map(read)));
+                }
+            })
+            .with_dynamic(a)
+            .with_dynamic(b),
+        );
+
+        world.send(E);
+
+        let mut seen = seen.lock().unwrap().clone();
+        seen.sort_by_key(|(id, _, _)| *id);
+
+        assert_eq!(
+            seen,
+            [
+                (e1, vec![1, 2, 3], None),
+                (e2, vec![4, 5, 6], Some(vec![7, 8, 9]))
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "has no component")]
+    fn dynamic_query_without_component() {
+        #[derive(Event)]
+        struct E;
+
+        let mut world = World::new();
+        world.add_handler(|_: Receiver<E>, _: Fetcher<Dynamic>| {});
+    }
+
+    #[test]
+    #[should_panic(expected = "more dynamic components than it has `Dynamic` queries")]
+    fn dynamic_query_with_extra_component() {
+        #[derive(Event)]
+        struct E;
+
+        #[derive(Component)]
+        struct C;
+
+        let mut world = World::new();
+        let c = world.add_component::<C>();
+        world.add_handler(
+            (|_: Receiver<E>, _: Fetcher<Dynamic>| {})
+                .with_dynamic(c)
+                .with_dynamic(c),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "incompatible component access")]
+    fn dynamic_query_conflicts_with_writes() {
+        #[derive(Event)]
+        struct E;
+
+        #[derive(Component)]
+        struct C;
+
+        let mut world = World::new();
+        let c = world.add_component::<C>();
+        world.add_handler(
+            (|_: Receiver<E>, _: Fetcher<Dynamic>, _: Fetcher<&mut C>| {}).with_dynamic(c),
+        );
+    }
+
+    #[test]
+    fn runtime_components() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        unsafe fn drop_value(_: NonNull<u8>) {
+            DROPS.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let mut world = World::new();
+
+        let add = |world: &mut World| unsafe {
+            world.add_component_with_descriptor(ComponentDescriptor {
+                name: "script::Health".into(),
+                type_id: None,
+                layout: Layout::new::<u64>(),
+                drop: Some(drop_value),
+                is_immutable: false,
+                is_double_buffered: false,
+                skip_identical_writes: None,
+                fields: vec![],
+            })
+        };
+
+        // Components without a type ID are never deduplicated.
+        let a = add(&mut world);
+        let b = add(&mut world);
+        assert_ne!(a, b);
+        assert_eq!(world.components()[a].name(), world.components()[b].name());
+
+        let e = world.spawn();
+
+        for (component, value) in [(a, 1_u64), (b, 2)] {
+            unsafe { world.insert_dynamic(e, component, NonNull::from(&value).cast()) }.unwrap();
+        }
+
+        let read = |world: &World, component| {
+            let ptr = world.get_dynamic(e, component).unwrap().unwrap();
+            unsafe { *ptr.cast::<u64>().as_ptr() }
+        };
+
+        assert_eq!(read(&world, a), 1);
+        assert_eq!(read(&world, b), 2);
+
+        // Replacing a value drops the old one.
+        let value = 3_u64;
+        unsafe { world.insert_dynamic(e, a, NonNull::from(&value).cast()) }.unwrap();
+        assert_eq!(read(&world, a), 3);
+        assert_eq!(DROPS.load(Ordering::Relaxed), 1);
+
+        let other = world.spawn();
+        assert_eq!(world.get_dynamic(other, a), Ok(None));
+
+        world.despawn(e);
+        assert_eq!(DROPS.load(Ordering::Relaxed), 3);
+        assert_eq!(world.get_dynamic(e, a), Ok(None));
+    }
+
+    #[test]
+    fn stale_component_id() {
+        let mut world = World::new();
+
+        let add = |world: &mut World| unsafe {
+            world.add_component_with_descriptor(ComponentDescriptor {
+                name: "dynamic".into(),
+                type_id: None,
+                layout: Layout::new::<u32>(),
+                drop: None,
+                is_immutable: false,
+                is_double_buffered: false,
+                skip_identical_writes: None,
+                fields: vec![],
+            })
+        };
+
+        let old = add(&mut world);
+        world.remove_component(old);
+        let new = add(&mut world);
+
+        // The slot is reused with a new generation.
+        assert_eq!(old.index(), new.index());
+        assert_ne!(old, new);
+
+        let e = world.spawn();
+        let value = 123_u32;
+
+        assert_eq!(
+            unsafe { world.insert_dynamic(e, old, NonNull::from(&value).cast()) },
+            Err(StaleComponentId(old))
+        );
+        assert!(world.iter_dynamic(old).is_err());
+        assert!(world.components().get(old).is_none());
+
+        unsafe { world.insert_dynamic(e, new, NonNull::from(&value).cast()) }.unwrap();
+        assert_eq!(world.iter_dynamic(new).unwrap().count(), 1);
+
+        let owned = world.take_entity(e).unwrap();
+        assert!(owned.get_ptr(old).is_none());
+        assert!(owned.get_ptr(new).is_some());
+    }
+
+    #[test]
+    fn dynamic_component_size_not_multiple_of_align() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+        use std::alloc;
+
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        unsafe fn drop_value(ptr: NonNull<u8>) {
+            assert!((ptr.as_ptr() as usize).is_multiple_of(8));
+            DROPS.fetch_add(1, Ordering::Relaxed);
+        }
+
+        #[derive(Component)]
+        struct Marker;
+
+        let mut world = World::new();
+
+        let layout = Layout::from_size_align(12, 8).unwrap();
+
+        let component = unsafe {
+            world.add_component_with_descriptor(ComponentDescriptor {
+                name: "unpadded".into(),
+                type_id: None,
+                layout,
+                drop: Some(drop_value),
+                is_immutable: false,
+                is_double_buffered: false,
+                skip_identical_writes: None,
+                fields: vec![],
+            })
+        };
+
+        let info = world.components().get(component).unwrap();
+        assert_eq!(info.layout().size(), 16);
+        assert_eq!(info.size(), 12);
+
+        let entities = (0..3_u8)
+            .map(|i| {
+                let e = world.spawn();
+
+                // Allocate exactly 12 bytes so that reading any padding is detected by Miri.
+                unsafe {
+                    let src = alloc::alloc(layout);
+                    src.write_bytes(i, layout.size());
+                    world
+                        .insert_dynamic(e, component, NonNull::new(src).unwrap())
+                        .unwrap();
+                    alloc::dealloc(src, layout);
+                }
+
+                e
+            })
+            .collect::<Vec<_>>();
+
+        // Move the first entity to another archetype.
+        world.insert(entities[0], Marker);
+
+        for (id, ptr) in world.iter_dynamic(component).unwrap() {
+            let i = entities.iter().position(|&e| e == id).unwrap() as u8;
+
+            assert_eq!(ptr.len(), 12);
+            assert!((ptr.cast::<u8>().as_ptr() as usize).is_multiple_of(8));
+            assert_eq!(unsafe { *ptr.cast::<[u8; 12]>().as_ptr() }, [i; 12]);
+        }
+
+        world.despawn(entities[1]);
+        assert_eq!(DROPS.load(Ordering::Relaxed), 1);
+
+        drop(world);
+        assert_eq!(DROPS.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn deterministic_worlds_agree() {
+        #[derive(Component)]
+        struct A;
+
+        #[derive(Component)]
+        struct B;
+
+        #[derive(Event)]
+        struct E;
+
+        let manifest = Manifest::new()
+            .component::<A>()
+            .component::<B>()
+            .event::<E>();
+
+        let run = |swap: bool| {
+            let mut world = World::new_deterministic(&manifest);
+
+            // Touch types in a different order.
+            if swap {
+                world.add_handler(|_: Receiver<E>, _: Fetcher<(&B, &A)>| {});
+            } else {
+                world.add_handler(|_: Receiver<E>, _: Fetcher<(&A, &B)>| {});
+            }
+
+            let e1 = world.spawn();
+            let e2 = world.spawn();
+            let e3 = world.spawn();
+
+            if swap {
+                world.insert(e1, B);
+                world.insert(e1, A);
+                world.insert(e2, B);
+            } else {
+                world.insert(e1, A);
+                world.insert(e1, B);
+                world.insert(e2, B);
+            }
+
+            world.despawn(e3);
+            world.despawn(e2);
+            world.spawn();
+            world.send(E);
+
+            (world.add_component::<B>(), world.determinism_hash())
+        };
+
+        assert_eq!(run(false), run(true));
+    }
+
+    #[test]
+    fn deterministic_ids_with_different_insert_order() {
+        #[derive(Component)]
+        struct A;
+
+        #[derive(Component)]
+        struct B;
+
+        #[derive(Component)]
+        struct C;
+
+        #[derive(Event)]
+        struct E;
+
+        let manifest = Manifest::new()
+            .component::<A>()
+            .component::<B>()
+            .component::<C>()
+            .event::<E>();
+
+        let run = |reverse: bool| {
+            let mut world = World::new_deterministic(&manifest);
+
+            let entities = (0..4).map(|_| world.spawn()).collect::<Vec<_>>();
+
+            for &e in &entities {
+                if reverse {
+                    world.insert(e, C);
+                    world.insert(e, B);
+                    world.insert(e, A);
+                } else {
+                    world.insert(e, A);
+                    world.insert(e, B);
+                    world.insert(e, C);
+                }
+            }
+
+            world.remove::<B>(entities[1]);
+            world.despawn(entities[2]);
+
+            let respawned = world.spawn();
+            world.insert(respawned, B);
+
+            // Archetypes are compared by their components rather than their
+            // indices, since the intermediate archetypes differ.
+            let mut components = world
+                .archetypes()
+                .iter()
+                .flat_map(|arch| {
+                    arch.entity_ids()
+                        .iter()
+                        .map(|&id| (id, arch.component_indices().to_vec()))
+                })
+                .collect::<Vec<_>>();
+
+            components.sort_by_key(|&(id, _)| id);
+
+            (
+                entities,
+                respawned,
+                components,
+                world.add_component::<A>(),
+                world.add_component::<B>(),
+                world.add_component::<C>(),
+                world.add_event::<E>(),
+                world.determinism_hash(),
+            )
+        };
+
+        assert_eq!(run(false), run(true));
+    }
+
+    #[test]
+    #[should_panic]
+    fn deterministic_world_rejects_unlisted_event() {
+        #[derive(Event)]
+        struct E;
+
+        World::new_deterministic(&Manifest::new()).send(E);
+    }
+
+    #[test]
+    fn retain_entities() {
+        #[derive(Component)]
+        struct Keep;
+
+        #[derive(Component)]
+        struct Tracked(#[allow(dead_code)] Arc<()>);
+
+        let mut world = World::new();
+        let arc = Arc::new(());
+
+        let kept = (0..5)
+            .map(|i| {
+                let e = world.spawn();
+                world.insert(e, Tracked(arc.clone()));
+
+                if i % 2 == 0 {
+                    world.insert(e, Keep);
+                }
+
+                (e, i % 2 == 0)
+            })
+            .collect::<Vec<_>>();
+
+        world.retain_entities(|world, id| world.get::<Keep>(id).is_some());
+
+        for (e, keep) in kept {
+            assert_eq!(world.entities().contains(e), keep);
+        }
+
+        // Three remaining entities plus our own reference.
+        assert_eq!(Arc::strong_count(&arc), 4);
+    }
+
+    #[test]
+    fn archetype_moved() {
+        #[derive(Component)]
+        struct A;
+
+        #[derive(Component, Default)]
+        struct Moves(Vec<ArchetypeMoved>);
+
+        let mut world = World::new();
+
+        world.add_handler(
+            |r: Receiver<ArchetypeMoved, ()>, Single(moves): Single<&mut Moves>| {
+                moves.0.push(*r.event);
+            },
+        );
+
+        // Inserting `Moves` moves the log entity itself.
+        let log = world.spawn();
+        world.insert(log, Moves::default());
+
+        let e = world.spawn();
+        let empty = world.entities().get(e).unwrap().archetype;
+
+        world.insert(e, A);
+        let with_a = world.entities().get(e).unwrap().archetype;
+
+        // Overwriting doesn't change the archetype.
+        world.insert(e, A);
+        world.remove::<A>(e);
+
+        assert_ne!(empty, with_a);
+        assert_eq!(
+            world.get::<Moves>(log).unwrap().0[1..],
+            [
+                ArchetypeMoved {
+                    entity: e,
+                    from: empty,
+                    to: with_a
+                },
+                ArchetypeMoved {
+                    entity: e,
+                    from: with_a,
+                    to: empty
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn despawn_batch() {
+        #[derive(Component)]
+        struct A(#[allow(dead_code)] Arc<()>);
+
+        #[derive(Component)]
+        struct B;
+
+        let mut world = World::new();
+        let arc = Arc::new(());
+
+        let ids = (0..10)
+            .map(|i| {
+                let e = world.spawn();
+                world.insert(e, A(arc.clone()));
+
+                if i % 3 == 0 {
+                    world.insert(e, B);
+                }
+
+                e
+            })
+            .collect::<Vec<_>>();
+
+        let stale = world.spawn();
+        world.despawn(stale);
+
+        let plain = world.spawn();
+
+        let batch = ids
+            .iter()
+            .copied()
+            .step_by(2)
+            .chain([stale, plain, ids[0]])
+            .collect::<Vec<_>>();
+
+        assert_eq!(world.despawn_batch(batch), 6);
+
+        for (i, &e) in ids.iter().enumerate() {
+            assert_eq!(world.entities().contains(e), i % 2 == 1);
+            assert_eq!(world.get::<B>(e).is_some(), i % 2 == 1 && i % 3 == 0);
+        }
+
+        assert!(!world.entities().contains(plain));
+        assert_eq!(Arc::strong_count(&arc), 6);
+        assert_eq!(world.despawn_batch([ids[0]]), 0);
+    }
+
+    #[test]
+    fn world_drops_events() {
+        #[derive(Event)]
+        struct A(Arc<()>);
+
+        impl Drop for A {
+            fn drop(&mut self) {
+                eprintln!("calling A destructor");
+            }
+        }
+
+        #[derive(Event)]
+        struct B(Arc<()>);
+
+        impl Drop for B {
+            fn drop(&mut self) {
+                eprintln!("calling B destructor");
+            }
+        }
+
+        #[derive(Event)]
+        struct C(Arc<()>);
+
+        impl Drop for C {
+            fn drop(&mut self) {
+                eprintln!("calling C destructor");
+            }
+        }
+
+        let mut world = World::new();
+
+        world.add_handler(|r: Receiver<A>, mut s: Sender<B>| {
+            s.send(B(r.event.0.clone()));
+            s.send(B(r.event.0.clone()));
+        });
+
+        world.add_handler(|r: Receiver<B>, mut s: Sender<C>| {
+            s.send(C(r.event.0.clone()));
+            s.send(C(r.event.0.clone()));
+        });
+
+        world.add_handler(|r: Receiver<C>| println!("got C {:?}", Arc::as_ptr(&r.event.0)));
+
+        let arc = Arc::new(());
+
+        world.send(A(arc.clone()));
+
+        drop(world);
+
+        assert_eq!(Arc::strong_count(&arc), 1);
+    }
+
+    #[test]
+    fn world_drops_events_on_panic() {
+        #[derive(Event)]
+        struct A(Arc<()>);
+
+        impl Drop for A {
+            fn drop(&mut self) {
+                eprintln!("calling A destructor");
+            }
+        }
+
+        #[derive(Event)]
+        struct B(Arc<()>);
+
+        impl Drop for B {
+            fn drop(&mut self) {
+                eprintln!("calling B destructor");
+            }
+        }
+
+        #[allow(dead_code)]
+        #[derive(Event)]
+        struct C(Arc<()>);
+
+        impl Drop for C {
+            fn drop(&mut self) {
+                eprintln!("calling C destructor");
+            }
+        }
+
+        let mut world = World::new();
+
+        world.add_handler(|r: Receiver<A>, mut s: Sender<B>| {
+            s.send(B(r.event.0.clone()));
+            s.send(B(r.event.0.clone()));
+        });
+
+        world.add_handler(|r: Receiver<B>, mut sender: Sender<C>| {
+            sender.send(C(r.event.0.clone()));
+            sender.send(C(r.event.0.clone()));
+        });
+
+        world.add_handler(|_: Receiver<C>| panic!("oops!"));
+
+        let arc = Arc::new(());
+        let arc_cloned = arc.clone();
+
+        let res = panic::catch_unwind(move || world.send(A(arc_cloned)));
+
+        assert_eq!(*res.unwrap_err().downcast::<&str>().unwrap(), "oops!");
+
+        assert_eq!(Arc::strong_count(&arc), 1);
+    }
+
+    /// Asserts that `World` has the expected auto trait implementations.
+    fn _assert_auto_trait_impls()
+    where
+        World: Send + Sync + UnwindSafe + RefUnwindSafe,
+    {
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn send_parallel() {
+        use std::sync::Mutex;
+
+        #[derive(Event)]
+        struct Tick;
+
+        #[derive(Event)]
+        struct Followup;
+
+        #[derive(Component)]
+        struct A(u32);
+
+        #[derive(Component)]
+        struct B(u32);
+
+        let mut world = World::new();
+
+        let e = world.spawn();
+        world.insert(e, A(0));
+        world.insert(e, B(0));
+
+        let order = Arc::new(Mutex::new(vec![]));
+
+        let h1 = world.add_handler(
+            |_: Receiver<Tick>, f: Fetcher<&mut A>, mut s: Sender<Followup>| {
+                for a in f {
+                    a.0 += 1;
+                }
+                s.send(Followup);
+            },
+        );
+        let h2 = world.add_handler(|_: Receiver<Tick>, f: Fetcher<&mut B>| {
+            for b in f {
+                b.0 += 1;
+            }
+        });
+
+        let o = order.clone();
+        let h3 = world.add_handler(move |_: Receiver<Tick>, f: Fetcher<&A>| {
+            assert!(f.iter().all(|a| a.0 == 1));
+            o.lock().unwrap().push("read");
+        });
+
+        let o = order.clone();
+        world.add_handler(move |_: Receiver<Followup>, f: Fetcher<&B>| {
+            // Runs after every handler of `Tick`.
+            assert!(f.iter().all(|b| b.0 == 1));
+            o.lock().unwrap().push("followup");
+        });
+
+        let tick = world.add_event::<Tick>();
+        assert_eq!(world.dispatch_groups(tick), [vec![h1, h2], vec![h3]]);
+
+        world.send_parallel(Tick);
+
+        assert_eq!(*order.lock().unwrap(), ["read", "followup"]);
+        assert_eq!(world.get::<A>(e).unwrap().0, 1);
+        assert_eq!(world.get::<B>(e).unwrap().0, 1);
+        assert!(!world.parallel_dispatch);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn send_parallel_respects_run_order() {
+        use core::time::Duration;
+        use std::sync::Mutex;
+        use std::thread;
+
+        #[derive(Event)]
+        struct Tick;
+
+        #[derive(Component)]
+        struct A;
+
+        #[derive(Component)]
+        struct B;
+
+        let mut world = World::new();
+
+        let order = Arc::new(Mutex::new(vec![]));
+
+        let o = order.clone();
+        let h1 = world.add_handler(move |_: Receiver<Tick>, _: Fetcher<&mut A>| {
+            thread::sleep(Duration::from_millis(20));
+            o.lock().unwrap().push("first");
+        });
+
+        // Accesses are disjoint, but the constraint keeps the handlers apart.
+        let o = order.clone();
+        let h2 = world.add_handler(
+            (move |_: Receiver<Tick>, _: Fetcher<&mut B>| {
+                o.lock().unwrap().push("second");
+            })
+            .after(h1),
+        );
+
+        let h3 = world.add_handler(|_: Receiver<Tick>, _: Fetcher<&A>| {});
+
+        let tick = world.add_event::<Tick>();
+        assert_eq!(world.dispatch_groups(tick), [vec![h1], vec![h2, h3]]);
+
+        for _ in 0..5 {
+            world.send_parallel(Tick);
+        }
+
+        assert_eq!(*order.lock().unwrap(), ["first", "second"].repeat(5));
+    }
+
+    #[test]
+    fn world_mut() {
+        use std::sync::Mutex;
+
+        #[derive(Event)]
+        struct A;
+
+        #[derive(Event)]
+        struct B;
+
+        #[derive(Event)]
+        struct Nested;
+
+        #[derive(Component, PartialEq, Debug)]
+        struct C(u32);
+
+        let mut world = World::new();
+
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let l = log.clone();
+        world.add_handler(move |_: Receiver<A>, mut world: WorldMut| {
+            let e = world.spawn();
+            world.insert(e, C(1));
+            assert_eq!(world.get::<C>(e), Some(&C(1)));
+
+            world.get_mut::<C>(e).unwrap().0 += 1;
+            world.send(Nested);
+            l.lock().unwrap().push("a");
+        });
+
+        let l = log.clone();
+        world.add_handler(move |_: Receiver<Nested>, f: Fetcher<&C>| {
+            assert_eq!(f.iter().collect::<Vec<_>>(), [&C(2)]);
+            l.lock().unwrap().push("nested");
+        });
+
+        let l = log.clone();
+        world.add_handler(move |_: Receiver<B>| l.lock().unwrap().push("b"));
+
+        world.send_many(|mut s| {
+            s.send(A);
+            s.send(B);
+        });
+
+        // Events sent through the `WorldMut` are handled before it returns,
+        // but after the events queued before it.
+        assert_eq!(*log.lock().unwrap(), ["nested", "a", "b"]);
+        assert_eq!(world.entities().len(), 1);
+    }
+
+    #[test]
+    fn world_mut_conflicts() {
+        #[derive(Event)]
+        struct E;
+
+        #[derive(Component)]
+        struct C;
+
+        fn init_fails<H: IntoHandler<M>, M>(handler: H) -> bool {
+            panic::catch_unwind(panic::AssertUnwindSafe(|| {
+                World::new().add_handler(handler);
+            }))
+            .is_err()
+        }
+
+        assert!(init_fails(|_: Receiver<E>, _: Fetcher<&C>, _: WorldMut| {}));
+        assert!(init_fails(|_: Receiver<E>, _: WorldMut, _: Fetcher<&C>| {}));
+        assert!(init_fails(|_: Receiver<E>, _: Sender<E>, _: WorldMut| {}));
+        assert!(init_fails(|_: Receiver<E>, _: WorldMut, _: WorldMut| {}));
+        assert!(init_fails(|_: Receiver<Spawn, ()>, _: WorldMut| {}));
+        assert!(!init_fails(|_: Receiver<E>, _: WorldMut| {}));
+
+        let mut world = World::new();
+
+        let a = world.add_handler(|_: Receiver<E>, _: WorldMut| {});
+        let b = world.add_handler(|_: Receiver<E>, _: Fetcher<&C>| {});
+        let e = world.add_event::<E>();
+
+        assert_eq!(world.dispatch_groups(e), [vec![a], vec![b]]);
+    }
+
+    #[test]
+    #[should_panic(expected = "while flushing its own events")]
+    fn world_mut_receive_own_event() {
+        #[derive(Event)]
+        struct Ping(u32);
+
+        let mut world = World::new();
+
+        world.add_handler(|r: Receiver<Ping>, mut world: WorldMut| {
+            if r.event.0 == 0 {
+                world.send(Ping(1));
+            }
+        });
+
+        world.send(Ping(0));
+    }
+
+    #[test]
+    fn get_and_get_mut() {
+        #[derive(Component, PartialEq, Debug)]
+        struct C(u32);
+
+        #[derive(Component, PartialEq, Debug)]
+        struct Tag;
+
+        let mut world = World::new();
+
+        let e = world.spawn();
+
+        // Not registered yet.
+        assert_eq!(world.get::<C>(e), None);
+
+        let other = world.spawn();
+        world.insert(other, C(1));
+
+        world.insert(e, C(2));
+        assert_eq!(world.get::<Tag>(e), None);
+
+        world.get_mut::<C>(e).unwrap().0 += 1;
+
+        // Moving `e` to another archetype keeps the value.
+        world.insert(e, Tag);
+        assert_eq!(world.get::<C>(e), Some(&C(3)));
+        assert_eq!(world.get::<Tag>(e), Some(&Tag));
+        assert_eq!(world.get::<C>(other), Some(&C(1)));
+
+        world.despawn(e);
+        assert_eq!(world.get::<C>(e), None);
+        assert_eq!(world.get_mut::<C>(e), None);
+        assert_eq!(world.get::<C>(EntityId::NULL), None);
+    }
+
+    #[test]
+    fn pairs_mut() {
+        #[derive(Component)]
+        struct C(u32);
+
+        #[derive(Component)]
+        struct Tag;
+
+        let mut world = World::new();
+
+        let a = world.spawn();
+        world.insert(a, C(1));
+        let b = world.spawn();
+        world.insert(b, C(2));
+        // Different archetype.
+        let c = world.spawn();
+        world.insert(c, C(3));
+        world.insert(c, Tag);
+        let without = world.spawn();
+        let dead = world.spawn();
+        world.insert(dead, C(4));
+        world.despawn(dead);
+
+        let mut calls = 0;
+
+        world.pairs_mut::<C, _>(
+            &[(a, b), (b, c), (a, a), (a, without), (dead, b)],
+            |x, y| {
+                assert!(!core::ptr::eq(x, y));
+                core::mem::swap(&mut x.0, &mut y.0);
+                calls += 1;
+            },
+        );
+
+        assert_eq!(calls, 2);
+        assert_eq!(world.get::<C>(a).unwrap().0, 2);
+        assert_eq!(world.get::<C>(b).unwrap().0, 3);
+        assert_eq!(world.get::<C>(c).unwrap().0, 1);
+    }
+
+    #[test]
+    fn component_drop_hook() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static DROPS: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Component)]
+        struct C(u32);
+
+        impl Drop for C {
+            fn drop(&mut self) {
+                DROPS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        #[derive(Event)]
+        struct Dropped(EntityId, u32, usize);
+
+        #[derive(Component)]
+        struct Log(Vec<(EntityId, u32, usize)>);
+
+        let mut world = World::new();
+
+        world.set_component_drop_hook::<C>(|world, entity, c| {
+            world.send(Dropped(entity, c.0, DROPS.load(Ordering::Relaxed)));
+        });
+
+        world.add_handler(|r: Receiver<Dropped>, Single(log): Single<&mut Log>| {
+            log.0.push((r.event.0, r.event.1, r.event.2));
+        });
+
+        let log = world.spawn();
+        world.insert(log, Log(vec![]));
+
+        let ids: Vec<_> = (1..=4)
+            .map(|n| {
+                let e = world.spawn();
+                world.insert(e, C(n));
+                e
+            })
+            .collect();
+
+        // Replacing a component doesn't call the hook.
+        world.insert(ids[0], C(10));
+        assert_eq!(DROPS.load(Ordering::Relaxed), 1);
+
+        world.remove::<C>(ids[1]);
+        world.despawn(ids[0]);
+        world.despawn(ids[2]);
+        world.despawn(ids[3]);
+
+        // Each hook runs before the value is dropped.
+        assert_eq!(
+            world.get::<Log>(log).unwrap().0,
+            [
+                (ids[1], 2, 1),
+                (ids[0], 10, 2),
+                (ids[2], 3, 3),
+                (ids[3], 4, 4)
+            ]
+        );
+        assert_eq!(DROPS.load(Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn send_traced() {
+        #[derive(Event)]
+        struct Hit(#[event(target)] EntityId);
+
+        #[derive(Event)]
+        struct Cascaded;
+
+        #[derive(Component)]
+        struct Health;
+
+        #[derive(Component)]
+        struct Shield;
+
+        let mut world = World::new();
+
+        let h1 = world.add_handler(|_: Receiver<Hit, &Health>, mut s: Sender<Cascaded>| {
+            s.send(Cascaded);
+        });
+        world.add_handler(|_: Receiver<Hit, &Shield>| {});
+        let h3 = world.add_handler(|_: Receiver<Hit, ()>| {});
+        world.add_handler(|_: Receiver<Cascaded>| {});
+
+        let e = world.spawn();
+        world.insert(e, Health);
+
+        assert_eq!(world.send_traced(Hit(e)), [h1, h3]);
+    }
+
+    #[test]
+    fn send_checked() {
+        #[derive(Event)]
+        struct Hit(#[event(target)] EntityId);
+
+        #[derive(Event)]
+        struct Taken(u32);
+
+        #[derive(Component)]
+        struct Health;
+
+        let mut world = World::new();
+
+        world.add_handler(|_: Receiver<Hit, &Health>| {});
+        world.add_handler(|r: TakeReceiver<Taken>| assert_eq!(r.event.0, 1));
+
+        let e = world.spawn();
+
+        assert!(!world.send_checked(Hit(e)));
+
+        world.insert(e, Health);
+
+        assert!(world.send_checked(Hit(e)));
+        assert!(world.send_checked(Taken(1)));
+
+        world.despawn(e);
+
+        assert!(!world.send_checked(Hit(e)));
+    }
+
+    #[test]
+    fn entity_ref_and_mut() {
+        #[derive(Component, PartialEq, Debug)]
+        struct Health(u32);
+
+        #[derive(Component)]
+        struct Doomed;
+
+        #[derive(Component)]
+        struct Log(Vec<&'static str>);
+
+        let mut world = World::new();
+
+        let log = world.spawn();
+        world.insert(log, Log(vec![]));
+
+        world.add_handler(|_: Receiver<Insert<Health>, ()>, log: Single<&mut Log>| {
+            log.0 .0.push("insert");
+        });
+        world.add_handler(|_: Receiver<Remove<Health>, ()>, log: Single<&mut Log>| {
+            log.0 .0.push("remove");
+        });
+        world.add_handler(|_: Receiver<Despawn, ()>, log: Single<&mut Log>| {
+            log.0 .0.push("despawn");
+        });
+        world.add_handler(
+            |r: Receiver<Insert<Doomed>, EntityId>, mut s: Sender<Despawn>| {
+                s.despawn(r.query);
+            },
+        );
+
+        assert!(world.entity(EntityId::NULL).is_none());
+        assert!(world.entity_mut(EntityId::NULL).is_none());
+
+        let e = world.spawn();
+        let empty = world.entity(e).unwrap().archetype_id();
+
+        let mut entity = world.entity_mut(e).unwrap();
+        entity.insert(Health(3));
+        entity.get_mut::<Health>().unwrap().0 += 1;
+
+        assert!(entity.contains::<Health>());
+        assert_ne!(entity.archetype_id(), Some(empty));
+
+        entity.remove::<Health>();
+        assert!(!entity.contains::<Health>());
+        assert_eq!(entity.archetype_id(), Some(empty));
+
+        entity.insert(Health(5)).insert(Doomed);
+
+        // Despawned by the handler of `Insert<Doomed>`.
+        assert!(!entity.exists());
+        assert_eq!(entity.get::<Health>(), None);
+        assert_eq!(entity.archetype_id(), None);
+
+        let e = world.spawn();
+        world.insert(e, Health(1));
+
+        let entity = world.entity(e).unwrap();
+        assert_eq!(entity.id(), e);
+        assert_eq!(entity.get::<Health>(), Some(&Health(1)));
+
+        world.entity_mut(e).unwrap().despawn();
+        assert!(world.entity(e).is_none());
+
+        assert_eq!(
+            world.get::<Log>(log).unwrap().0,
+            ["insert", "remove", "insert", "despawn", "insert", "despawn"]
+        );
+    }
+
+    #[test]
+    fn inspect() {
+        #[derive(Component, PartialEq, Debug)]
+        struct Name(&'static str);
+
+        let mut world = World::new();
+
+        let e = world.spawn();
+        world.insert(e, Name("a"));
+        world.spawn();
+
+        let (count, name) = world.inspect(|view| {
+            (
+                view.entities().len(),
+                view.get::<Name>(e).map(|name| name.0),
+            )
+        });
+
+        assert_eq!(count, 2);
+        assert_eq!(name, Some("a"));
+    }
+
+    #[test]
+    fn sort_archetype_rows() {
+        #[derive(Component, PartialEq, Eq, PartialOrd, Ord, Debug)]
+        struct Key(u32);
+
+        #[derive(Component)]
+        struct Payload(String);
+
+        #[derive(Component)]
+        struct Tag;
+
+        #[derive(Event)]
+        struct Collect;
+
+        #[derive(Component)]
+        struct Keys(Vec<u32>);
+
+        let mut world = World::new();
+
+        world.add_handler(
+            |_: Receiver<Collect>,
+             f: Fetcher<(&Key, Not<&Tag>)>,
+             Single(keys): Single<&mut Keys>| {
+                keys.0 = f.iter().map(|(k, _)| k.0).collect();
+            },
+        );
+
+        let keys = world.spawn();
+        world.insert(keys, Keys(vec![]));
+
+        let mut ids = vec![];
+
+        for (i, key) in [7, 3, 9, 1, 3, 8, 0, 5].into_iter().enumerate() {
+            let e = world.spawn();
+            world.insert(e, Key(key));
+            world.insert(e, Payload(format!("{key}-{i}")));
+
+            if i % 3 == 0 {
+                world.insert(e, Tag);
+            }
+
+            ids.push((e, key, i));
+        }
+
+        // Shuffle the rows a bit.
+        world.despawn(ids[1].0);
+        world.despawn(ids[5].0);
+        ids.retain(|&(e, _, _)| world.entities().contains(e));
+
+        world.sort_archetype_rows::<Key>();
+
+        for &(e, key, i) in &ids {
+            assert_eq!(world.get::<Key>(e), Some(&Key(key)));
+            assert_eq!(world.get::<Payload>(e).unwrap().0, format!("{key}-{i}"));
+
+            let loc = world.entities().get(e).unwrap();
+            let arch = world.archetypes().get(loc.archetype).unwrap();
+            assert_eq!(arch.entity_ids()[loc.row.0 as usize], e);
+        }
+
+        // Fetchers observe the new order.
+        world.send(Collect);
+        assert_eq!(world.get::<Keys>(keys).unwrap().0, [3, 5, 9]);
+    }
+
+    #[test]
+    fn assert_settled() {
+        #[derive(Event, Clone)]
+        struct E;
+
+        let mut world = World::new();
 
-        let (id, is_new) = self.events.add(desc);
+        world.add_handler((|_: Receiver<E>| {}).cooldown_ticks(1).coalesce::<E>());
 
-        if is_new {
-            self.handlers.register_event(id.index());
+        world.send(E);
+        world.send(E);
+        world.advance_handler_cooldowns();
 
-            match kind {
-                EventKind::Normal => {}
-                EventKind::Insert { component_idx, .. } => {
-                    if let Some(info) = self.components.get_by_index_mut(component_idx) {
-                        info.insert_events.insert(id);
-                    }
-                }
-                EventKind::Remove { component_idx } => {
-                    if let Some(info) = self.components.get_by_index_mut(component_idx) {
-                        info.remove_events.insert(id);
-                    }
+        world.assert_settled();
+    }
+
+    #[test]
+    #[should_panic(expected = "has a pending coalesced event")]
+    fn assert_settled_pending() {
+        #[derive(Event, Clone)]
+        struct E;
+
+        let mut world = World::new();
+
+        world.add_handler((|_: Receiver<E>| {}).cooldown_ticks(1).coalesce::<E>());
+
+        world.send(E);
+        // Deferred until the cooldown elapses.
+        world.send(E);
+
+        world.assert_settled();
+    }
+
+    #[derive(Event)]
+    struct Tick;
+
+    #[derive(Event)]
+    struct Damage(#[event(target)] EntityId, u32);
+
+    #[derive(Component)]
+    struct Health(u32);
+
+    /// A world with a handler that sends one targeted event per entity.
+    fn frame_world(entities: u32) -> World {
+        let mut world = World::new();
+
+        world.add_handler(
+            |_: Receiver<Tick>, f: Fetcher<(EntityId, &Health)>, mut s: Sender<Damage>| {
+                for (id, _) in f {
+                    s.send(Damage(id, 1));
                 }
-                EventKind::SpawnQueued => {}
-                EventKind::Despawn => {}
-            }
+            },
+        );
 
-            self.send(AddEvent(id));
+        world.add_handler(|r: Receiver<Damage, &mut Health>| {
+            r.query.0 = r.query.0.saturating_sub(r.event.1);
+        });
+
+        for _ in 0..entities {
+            let e = world.spawn();
+            world.insert(e, Health(100));
         }
 
-        id
+        world
     }
 
-    /// Removes an event from the world and returns its [`EventInfo`]. If
-    /// the `event` ID is invalid, then `None` is returned and the function
-    /// has no effect.
-    ///
-    /// Removing an event has the following effects in the order listed:
-    /// 1. The [`RemoveEvent`] event is sent.
-    /// 2. All handlers that send or receive the event are removed.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use evenio::prelude::*;
-    ///
-    /// #[derive(Event)]
-    /// struct MyEvent;
-    ///
-    /// let mut world = World::new();
-    ///
-    /// let id = world.add_event::<MyEvent>();
-    /// world.remove_event(id);
-    ///
-    /// assert!(!world.events().contains(id));
-    /// ```
-    pub fn remove_event(&mut self, event: EventId) -> Option<EventInfo> {
-        assert!(self.event_queue.is_empty());
+    #[test]
+    fn prewarmed_frame_does_not_realloc() {
+        let mut world = frame_world(100);
 
-        if !self.events.contains(event) || event == EventId::SPAWN_QUEUED {
-            return None;
+        let report = world.prewarm(101);
+        assert!(report.event_queue_capacity >= 101);
+        assert!(report.event_bytes >= 101 * core::mem::size_of::<Damage>());
+
+        world.assert_no_realloc(|world| {
+            for _ in 0..10 {
+                world.send(Tick);
+            }
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "EventQueue buffer was reallocated")]
+    fn cold_frame_reallocs() {
+        let mut world = frame_world(100);
+
+        world.assert_no_realloc(|world| world.send(Tick));
+    }
+
+    #[test]
+    fn take_and_put_entity_between_worlds() {
+        use core::sync::atomic::{AtomicBool, Ordering};
+
+        #[derive(Component, PartialEq, Debug)]
+        struct Name(String);
+
+        #[derive(Component, PartialEq, Debug)]
+        struct Health(u32);
+
+        #[derive(Component)]
+        struct Marker;
+
+        #[derive(Component)]
+        struct Counted(#[allow(dead_code)] Arc<()>);
+
+        fn world() -> World {
+            let mut world = World::new();
+            world.add_component::<Name>();
+            world.add_component::<Health>();
+            world.add_component::<Marker>();
+            world.add_component::<Counted>();
+            world
         }
 
-        // Send event before removing anything.
-        self.send(RemoveEvent(event));
+        let mut a = world();
+        let mut b = world();
 
-        // Remove all handlers that send or receive this event.
-        let mut to_remove = vec![];
+        let count = Arc::new(());
 
-        for handler in self.handlers.iter() {
-            if handler.received_event() == event
-                || match event.index() {
-                    EventIdx::Targeted(idx) => handler.sent_targeted_events().contains(idx),
-                    EventIdx::Untargeted(idx) => handler.sent_untargeted_events().contains(idx),
-                }
-            {
-                to_remove.push(handler.id());
+        let e = a.spawn();
+        a.insert(e, Name("bob".into()));
+        a.insert(e, Health(42));
+        a.insert(e, Marker);
+        a.insert(e, Counted(count.clone()));
+
+        let other = a.spawn();
+        a.insert(other, Health(1));
+
+        let owned = a.take_entity(e).unwrap();
+
+        assert!(!a.entities().contains(e));
+        assert_eq!(a.get::<Health>(other), Some(&Health(1)));
+        assert_eq!(owned.len(), 4);
+        assert_eq!(owned.get::<Health>(), Some(&Health(42)));
+        assert_eq!(Arc::strong_count(&count), 2);
+
+        assert!(a.take_entity(e).is_none());
+
+        // The entity has its components by the time `Spawn` is received.
+        let spawned = Arc::new(AtomicBool::new(false));
+        let flag = spawned.clone();
+        b.add_handler(move |_: Receiver<Spawn, (&Name, &Health, With<&Marker>)>| {
+            flag.store(true, Ordering::Relaxed);
+        });
+
+        let e2 = b.put_entity(owned);
+
+        assert!(spawned.load(Ordering::Relaxed));
+
+        assert_eq!(b.get::<Name>(e2), Some(&Name("bob".into())));
+        assert_eq!(b.get::<Health>(e2), Some(&Health(42)));
+        assert!(b.get::<Marker>(e2).is_some());
+        assert_eq!(Arc::strong_count(&count), 2);
+
+        b.despawn(e2);
+        assert_eq!(Arc::strong_count(&count), 1);
+
+        // Dropping an owned entity drops its components.
+        let e3 = b.spawn();
+        b.insert(e3, Counted(count.clone()));
+        drop(b.take_entity(e3));
+        assert_eq!(Arc::strong_count(&count), 1);
+    }
+
+    /// Moves an entity with a dynamic `u64` component named `"a"` into a world
+    /// where the same component has the given name and drop function.
+    fn put_dynamic_entity(name: &'static str, drop: DropFn) {
+        fn add(world: &mut World, name: &'static str, drop: DropFn) -> ComponentId {
+            unsafe {
+                world.add_component_with_descriptor(ComponentDescriptor {
+                    name: name.into(),
+                    type_id: None,
+                    layout: Layout::new::<u64>(),
+                    drop,
+                    is_immutable: false,
+                    is_double_buffered: false,
+                    skip_identical_writes: None,
+                    fields: vec![],
+                })
             }
         }
 
-        for id in to_remove {
-            self.remove_handler(id);
+        let mut a = World::new();
+        let mut b = World::new();
+
+        let component = add(&mut a, "a", None);
+        add(&mut b, name, drop);
+
+        let e = a.spawn();
+        let value = 123_u64;
+        unsafe { a.insert_dynamic(e, component, NonNull::from(&value).cast()) }.unwrap();
+
+        let owned = a.take_entity(e).unwrap();
+        let e2 = b.put_entity(owned);
+
+        assert!(b.entities().contains(e2));
+    }
+
+    #[test]
+    fn put_entity_with_matching_dynamic_component() {
+        put_dynamic_entity("a", None);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not match a component in this world")]
+    fn put_entity_rejects_dynamic_component_with_other_name() {
+        put_dynamic_entity("b", None);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not match a component in this world")]
+    fn put_entity_rejects_dynamic_component_with_other_drop() {
+        put_dynamic_entity("a", drop_fn_of::<Box<u64>>());
+    }
+
+    #[test]
+    #[cfg(feature = "tracing")]
+    fn tracing_span_hierarchy() {
+        use std::sync::Mutex;
+
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event as TracingEvent, Metadata, Subscriber};
+
+        #[derive(Debug)]
+        struct Span {
+            kind: &'static str,
+            name: String,
+            target: Option<String>,
+            parent: Option<usize>,
+            events_sent: Option<u64>,
         }
 
-        let info = self.events.remove(event).unwrap();
+        #[derive(Default)]
+        struct Capture {
+            spans: Arc<Mutex<Vec<Span>>>,
+            stack: Mutex<Vec<usize>>,
+        }
 
-        match info.kind() {
-            EventKind::Normal => {}
-            EventKind::Insert { component_idx, .. } => {
-                if let Some(info) = self.components.get_by_index_mut(component_idx) {
-                    info.insert_events.remove(&event);
+        impl Visit for Span {
+            fn record_str(&mut self, field: &Field, value: &str) {
+                if field.name() == "name" {
+                    self.name = value.into();
                 }
             }
-            EventKind::Remove { component_idx } => {
-                if let Some(info) = self.components.get_by_index_mut(component_idx) {
-                    info.remove_events.remove(&event);
+
+            fn record_u64(&mut self, field: &Field, value: u64) {
+                if field.name() == "events_sent" {
+                    self.events_sent = Some(value);
+                }
+            }
+
+            fn record_debug(&mut self, field: &Field, value: &dyn core::fmt::Debug) {
+                if field.name() == "target" {
+                    self.target = Some(format!("{value:?}"));
                 }
             }
-            EventKind::SpawnQueued => {}
-            EventKind::Despawn => {}
         }
 
-        Some(info)
-    }
+        impl Subscriber for Capture {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
 
-    /// Returns the [`Entities`] for this world.
-    pub fn entities(&self) -> &Entities {
-        &self.entities
-    }
+            fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+                let parent = match attrs.parent() {
+                    Some(id) => Some(id.into_u64() as usize - 1),
+                    None if attrs.is_contextual() => self.stack.lock().unwrap().last().copied(),
+                    None => None,
+                };
+
+                let mut span = Span {
+                    kind: attrs.metadata().name(),
+                    name: String::new(),
+                    target: None,
+                    parent,
+                    events_sent: None,
+                };
+                attrs.record(&mut span);
+
+                let mut spans = self.spans.lock().unwrap();
+                spans.push(span);
+                Id::from_u64(spans.len() as u64)
+            }
 
-    /// Returns the [`Components`] for this world.  
-    pub fn components(&self) -> &Components {
-        &self.components
+            fn record(&self, span: &Id, values: &Record<'_>) {
+                values.record(&mut self.spans.lock().unwrap()[span.into_u64() as usize - 1]);
+            }
+
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+            fn event(&self, _: &TracingEvent<'_>) {}
+
+            fn enter(&self, span: &Id) {
+                self.stack
+                    .lock()
+                    .unwrap()
+                    .push(span.into_u64() as usize - 1);
+            }
+
+            fn exit(&self, _: &Id) {
+                self.stack.lock().unwrap().pop();
+            }
+        }
+
+        #[derive(Event)]
+        struct Hello;
+
+        #[derive(Event)]
+        struct Greet(#[event(target)] EntityId);
+
+        fn greet(_: Receiver<Greet, ()>) {}
+
+        fn idle(_: Receiver<Hello>) {}
+
+        let mut world = World::new();
+
+        let e = world.spawn();
+        let hello = world.add_event::<Hello>();
+        let greet_event = world.add_event::<Greet>();
+
+        let say_hello = world.add_handler(move |_: Receiver<Hello>, mut s: Sender<Greet>| {
+            s.send(Greet(e));
+        });
+        let idle = world.add_handler(idle);
+        let greet = world.add_handler(greet);
+
+        let spans = Arc::new(Mutex::new(vec![]));
+        let capture = Capture {
+            spans: spans.clone(),
+            ..Default::default()
+        };
+
+        tracing::subscriber::with_default(capture, || world.send(Hello));
+
+        let handler_name = |id| world.handlers().get(id).unwrap().name().to_owned();
+        let event_name = |id| world.events().get(id).unwrap().name().to_owned();
+
+        let spans = spans.lock().unwrap();
+        let summary: Vec<_> = spans
+            .iter()
+            .map(|s| (s.kind, s.name.clone(), s.parent, s.events_sent))
+            .collect();
+
+        assert_eq!(
+            summary,
+            [
+                ("event", event_name(hello), None, None),
+                ("handler", handler_name(say_hello), Some(0), Some(1)),
+                ("handler", handler_name(idle), Some(0), Some(0)),
+                ("event", event_name(greet_event), Some(1), None),
+                ("handler", handler_name(greet), Some(3), Some(0)),
+            ]
+        );
+
+        assert_eq!(spans[0].target, None);
+        assert_eq!(spans[3].target, Some(format!("{e:?}")));
     }
 
-    /// Returns the [`Handlers`] for this world.
-    pub fn handlers(&self) -> &Handlers {
-        &self.handlers
+    #[test]
+    fn replace_handler_keeps_locals() {
+        use crate::handler::Local;
+
+        #[derive(Event)]
+        struct Tick;
+
+        #[derive(Component)]
+        struct Log(Vec<(u32, u32)>);
+
+        let mut world = World::new();
+
+        let e = world.spawn();
+        world.insert(e, Log(vec![]));
+
+        let id = world.add_handler(
+            |_: Receiver<Tick>, mut n: Local<u32>, Single(log): Single<&mut Log>| {
+                *n += 1;
+                log.0.push((1, *n));
+            },
+        );
+
+        world.send(Tick);
+        world.send(Tick);
+
+        world
+            .replace_handler(
+                id,
+                |_: Receiver<Tick>,
+                 mut s: Local<String>,
+                 mut n: Local<u32>,
+                 Single(log): Single<&mut Log>| {
+                    s.push('x');
+                    *n += 1;
+                    log.0.push((s.len() as u32, *n));
+                },
+            )
+            .unwrap();
+
+        assert!(world.handlers().contains(id));
+
+        world.send(Tick);
+        world.send(Tick);
+
+        assert_eq!(
+            world.get::<Log>(e).unwrap().0,
+            [(1, 1), (1, 2), (1, 3), (2, 4)]
+        );
     }
 
-    /// Returns the [`Archetypes`] for this world.
-    pub fn archetypes(&self) -> &Archetypes {
-        &self.archetypes
+    #[test]
+    fn replace_handler_rejects_incompatible() {
+        #[derive(Event)]
+        struct A;
+
+        #[derive(Event)]
+        struct B;
+
+        #[derive(Component)]
+        struct C(u32);
+
+        let mut world = World::new();
+
+        let e = world.spawn();
+        world.insert(e, C(0));
+
+        let id = world.add_handler(|_: Receiver<A>, Single(c): Single<&mut C>| c.0 += 1);
+
+        assert!(matches!(
+            world.replace_handler(id, |_: Receiver<B>| {}),
+            Err(ReplaceHandlerError::ReceivedEventMismatch { .. })
+        ));
+
+        assert!(matches!(
+            world.replace_handler(id, (|_: Receiver<A>| {}).high()),
+            Err(ReplaceHandlerError::PriorityMismatch { .. })
+        ));
+
+        assert!(matches!(
+            world.replace_handler(id, |_: Receiver<A>, _: Fetcher<&mut C>, _: Fetcher<&C>| {}),
+            Err(ReplaceHandlerError::Init(_))
+        ));
+
+        world.send(A);
+
+        assert_eq!(world.get::<C>(e).unwrap().0, 1);
+
+        world.remove_handler(id);
+
+        assert!(matches!(
+            world.replace_handler(id, |_: Receiver<A>| {}),
+            Err(ReplaceHandlerError::NoSuchHandler)
+        ));
     }
 
-    /// Returns the [`Events`] for this world.
-    pub fn events(&self) -> &Events {
-        &self.events
+    #[test]
+    #[cfg(debug_assertions)]
+    fn component_invariant_violation() {
+        #[derive(Component)]
+        struct Health(i32);
+
+        let mut world = World::new();
+
+        world.add_invariant::<Health>(|h| h.0 >= 0);
+
+        let a = world.spawn();
+        world.insert(a, Health(10));
+        world.remove::<Health>(a);
+        world.insert(a, Health(5));
+
+        let b = world.spawn();
+
+        let res = panic::catch_unwind(panic::AssertUnwindSafe(|| world.insert(b, Health(-3))));
+        let msg = res.unwrap_err().downcast::<String>().unwrap();
+
+        assert!(msg.contains("invariant of component"), "{msg}");
+        assert!(msg.contains(&format!("{b:?}")), "{msg}");
     }
 
-    /// Send all queued events to handlers. The event queue will be empty after
-    /// this call.
-    fn flush_event_queue(&mut self) {
-        'next_event: while let Some(item) = self.event_queue.pop_front() {
-            let event_meta = item.meta;
-            let event_info = unsafe {
-                self.events
-                    .get_by_index(event_meta.event_idx())
-                    .unwrap_debug_checked()
-            };
-            let event_kind = event_info.kind();
-
-            // In case `Handler::run` unwinds, we need to drop the event we're holding on
-            // the stack. The other events in the event queue will be handled by
-            // `World`'s destructor.
-            struct EventDropper {
-                event: NonNull<u8>,
-                drop: DropFn,
-                ownership_flag: bool,
-            }
-
-            impl EventDropper {
-                /// Extracts the event pointer and drop fn without running
-                /// the destructor.
-                #[inline]
-                fn unpack(self) -> (NonNull<u8>, DropFn) {
-                    let event = self.event;
-                    let drop = self.drop;
-                    mem::forget(self);
-
-                    (event, drop)
-                }
+    #[test]
+    fn arena_world() {
+        #[derive(Component)]
+        struct Counted(#[allow(dead_code)] Arc<()>);
+
+        #[derive(Component, PartialEq, Debug)]
+        struct Big([u64; 16]);
+
+        let count = Arc::new(());
+
+        {
+            let mut world = World::new_in_arena(4096);
+
+            let mut entities = vec![];
+
+            for i in 0..100 {
+                let e = world.spawn();
+                world.insert(e, Counted(count.clone()));
+                world.insert(e, Big([i; 16]));
+                entities.push(e);
             }
 
-            impl Drop for EventDropper {
-                fn drop(&mut self) {
-                    if !self.ownership_flag {
-                        if let Some(drop) = self.drop {
-                            unsafe { drop(self.event) };
-                        }
-                    }
-                }
+            for (i, &e) in entities.iter().enumerate() {
+                assert_eq!(world.get::<Big>(e), Some(&Big([i as u64; 16])));
             }
 
-            let mut event = EventDropper {
-                event: item.event,
-                drop: event_info.drop(),
-                ownership_flag: false,
-            };
+            world.despawn(entities[0]);
 
-            let (handler_list, target_location) = match event_meta {
-                EventMeta::Untargeted { idx } => (
-                    unsafe {
-                        self.handlers
-                            .get_untargeted_list(idx)
-                            .unwrap_debug_checked()
-                    },
-                    EntityLocation::NULL,
-                ),
-                EventMeta::Targeted { idx, target } => {
-                    let Some(location) = self.entities.get(target) else {
-                        // Entity doesn't exist. Skip the event.
-                        continue;
-                    };
-
-                    let arch = unsafe {
-                        self.archetypes
-                            .get(location.archetype)
-                            .unwrap_debug_checked()
-                    };
-
-                    static EMPTY: HandlerList = HandlerList::new();
-
-                    // Return an empty handler list instead of continuing in case this event is
-                    // special.
-                    (arch.handler_list_for(idx).unwrap_or(&EMPTY), location)
-                }
-            };
+            let stats = world.arena_stats().unwrap();
+
+            assert_eq!(stats.capacity, 4096);
+            assert!(stats.used <= stats.capacity);
+            assert!(stats.abandoned > 0);
+            assert!(stats.fallback_count > 0);
+            assert!(stats.fallback_bytes >= 99 * 16 * 8);
+            assert_eq!(Arc::strong_count(&count), 100);
+
+            assert!(World::new().arena_stats().is_none());
+        }
 
-            let handlers: *const [_] = handler_list.handlers();
+        assert_eq!(Arc::strong_count(&count), 1);
+    }
 
-            let events_before = self.event_queue.len();
+    #[test]
+    fn drop_overflowed_arena_world() {
+        #[derive(Component)]
+        struct Counted(#[allow(dead_code)] Arc<()>);
 
-            for mut info_ptr in unsafe { (*handlers).iter().copied() } {
-                let info = unsafe { info_ptr.as_info_mut() };
+        #[derive(Component)]
+        struct Plain(#[allow(dead_code)] u64);
 
-                let handler: *mut dyn Handler = info.handler_mut();
+        #[derive(Component)]
+        struct Empty;
 
-                let event_ptr =
-                    EventPtr::new(event.event, NonNull::from(&mut event.ownership_flag));
+        let count = Arc::new(());
 
-                let world_cell = self.unsafe_cell_mut();
+        let mut world = World::new_in_arena(256);
 
-                unsafe { (*handler).run(info, event_ptr, target_location, world_cell) };
+        for i in 0..200 {
+            let e = world.spawn();
+            world.insert(e, Plain(i));
+            world.insert(e, Empty);
 
-                // Did the handler take ownership of the event?
-                if event.ownership_flag {
-                    // Don't drop event since we don't own it anymore.
-                    event.unpack();
+            if i % 2 == 0 {
+                world.insert(e, Counted(count.clone()));
+            }
+        }
+
+        let stats = world.arena_stats().unwrap();
+
+        assert!(stats.used <= stats.capacity);
+        assert!(stats.fallback_count > 0);
+        assert_eq!(Arc::strong_count(&count), 101);
+
+        drop(world);
+
+        assert_eq!(Arc::strong_count(&count), 1);
+    }
+
+    #[test]
+    fn spawn_batch() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        #[derive(Component)]
+        struct C;
+
+        #[derive(Event)]
+        struct Count;
 
-                    // Reverse pushed events so they're handled in FIFO order.
-                    unsafe { self.event_queue.reverse_from(events_before) };
+        static COUNTED: AtomicUsize = AtomicUsize::new(0);
 
-                    continue 'next_event;
-                }
-            }
+        let mut world = World::new();
 
-            // Reverse pushed events so they're handled in FIFO order.
-            unsafe { self.event_queue.reverse_from(events_before) };
+        world.add_handler(|_: Receiver<Count>, f: Fetcher<EntityId>| {
+            COUNTED.store(f.iter().count(), Ordering::Relaxed)
+        });
 
-            match event_kind {
-                EventKind::Normal => {
-                    // Ordinary event. Run drop fn.
-                    if let (ptr, Some(drop)) = event.unpack() {
-                        unsafe { drop(ptr) };
-                    }
-                }
-                EventKind::Insert {
-                    component_idx,
-                    component_offset,
-                } => {
-                    let entity_id = unsafe { *event.event.as_ptr().cast::<EntityId>() };
-
-                    if let Some(loc) = self.entities.get(entity_id) {
-                        let dst = unsafe {
-                            self.archetypes.traverse_insert(
-                                loc.archetype,
-                                component_idx,
-                                &mut self.components,
-                                &mut self.handlers,
-                            )
-                        };
-
-                        let component_ptr =
-                            unsafe { event.event.as_ptr().add(component_offset as usize) }
-                                .cast_const();
-
-                        unsafe {
-                            self.archetypes.move_entity(
-                                loc,
-                                dst,
-                                [(component_idx, component_ptr)],
-                                &mut self.entities,
-                            )
-                        };
-
-                        // Inserted component is owned by the archetype now. We wait to unpack
-                        // in case one of the above functions panics.
-                        event.unpack();
-                    }
-                }
-                EventKind::Remove { component_idx } => {
-                    // `Remove` doesn't need drop.
-                    let (event, _) = event.unpack();
-
-                    // SAFETY: `Remove` is `repr(transparent)` with the first field being the
-                    // `EntityId`, so we can safely reinterpret this pointer.
-                    let entity_id = unsafe { *event.as_ptr().cast::<EntityId>() };
-
-                    if let Some(loc) = self.entities.get(entity_id) {
-                        let dst = unsafe {
-                            self.archetypes.traverse_remove(
-                                loc.archetype,
-                                component_idx,
-                                &mut self.components,
-                                &mut self.handlers,
-                            )
-                        };
-
-                        unsafe {
-                            self.archetypes
-                                .move_entity(loc, dst, [], &mut self.entities)
-                        };
-                    }
-                }
-                EventKind::SpawnQueued => {
-                    // `SpawnQueued` doesn't need drop.
-                    let _ = event.unpack();
+        let batch = world.spawn_batch(5);
+        assert_eq!(batch.len(), 5);
+        let first: Vec<_> = batch.take(2).collect();
 
-                    // Spawn one entity from the reserved entity queue.
-                    self.reserved_entities
-                        .spawn_one(&mut self.entities, |id| self.archetypes.spawn(id));
-                }
-                EventKind::Despawn => {
-                    // `Despawn` doesn't need drop.
-                    let (event, _) = event.unpack();
+        assert_eq!(world.entities().len(), 2);
+        assert!(first.iter().all(|&e| world.entities().contains(e)));
 
-                    let entity_id = unsafe { *event.as_ptr().cast::<Despawn>() }.0;
+        world.send(Count);
+        assert_eq!(COUNTED.load(Ordering::Relaxed), 2);
 
-                    self.archetypes.remove_entity(entity_id, &mut self.entities);
+        // `Spawn` is sent once a handler can receive it.
+        world.add_handler(|r: Receiver<Spawn, EntityId>, mut s: Sender<Insert<C>>| {
+            s.insert(r.query, C)
+        });
 
-                    // Reset next key iter.
-                    self.reserved_entities.refresh(&self.entities);
-                }
-            }
-        }
+        let rest: Vec<_> = world.spawn_batch(3).collect();
 
-        self.event_queue.clear();
+        assert_eq!(world.entities().len(), 5);
+        assert_eq!(world.component_count::<C>(), 3);
+        assert!(rest.iter().all(|&e| world.get::<C>(e).is_some()));
     }
 
-    /// Returns a new [`UnsafeWorldCell`] with permission to _read_ all data in
-    /// this world.
-    pub fn unsafe_cell(&self) -> UnsafeWorldCell {
-        UnsafeWorldCell {
-            world: NonNull::from(self),
-            _marker: PhantomData,
-        }
-    }
+    #[test]
+    fn spawn_bundles() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
 
-    /// Returns a new [`UnsafeWorldCell`] with permission to _read and write_
-    /// all data in this world.
-    pub fn unsafe_cell_mut(&mut self) -> UnsafeWorldCell {
-        UnsafeWorldCell {
-            world: NonNull::from(self),
-            _marker: PhantomData,
-        }
-    }
-}
+        #[derive(Component, PartialEq, Debug)]
+        struct A(u32);
 
-impl Default for World {
-    fn default() -> Self {
-        Self::new()
-    }
-}
+        #[derive(Component, PartialEq, Debug)]
+        struct B(String);
 
-impl Drop for World {
-    fn drop(&mut self) {
-        // Drop in-flight events still in the event queue. This can happen if a panic
-        // occurs.
-        for item in self.event_queue.iter() {
-            let info = unsafe {
-                self.events
-                    .get_by_index(item.meta.event_idx())
-                    .unwrap_debug_checked()
-            };
+        static SPAWNED: AtomicUsize = AtomicUsize::new(0);
 
-            if let Some(drop) = info.drop() {
-                unsafe { drop(item.event) };
-            }
-        }
-    }
-}
+        let mut world = World::new();
 
-/// Used for queueing events. Passed to the closure given in [`send_many`].
-///
-/// [`send_many`]: World::send_many
-#[derive(Debug)]
-pub struct Sender<'a> {
-    world: &'a mut World,
-}
+        assert!(world
+            .spawn_bundles(core::iter::empty::<(A, B)>())
+            .is_empty());
+        assert_eq!(world.entities().len(), 0);
 
-impl Sender<'_> {
-    /// Enqueue an event.
-    pub fn send<E: Event>(&mut self, event: E) {
-        let idx = self.world.add_event::<E>().index().as_u32();
-        unsafe { self.world.event_queue.push_front(event, idx) };
-    }
+        let ids = world.spawn_bundles((0..4).map(|i| (B(format!("{i}")), A(i))));
 
-    /// Enqueue the spawning of an entity and [`Spawn`] event. Returns the
-    /// [`EntityId`] of the entity that will be spawned.
-    pub fn spawn(&mut self) -> EntityId {
-        let id = self.world.reserved_entities.reserve(&self.world.entities);
-        unsafe {
-            self.world
-                .event_queue
-                .push_front(SpawnQueued, EventId::SPAWN_QUEUED.index().as_u32())
+        assert_eq!(ids.len(), 4);
+
+        for (i, &e) in ids.iter().enumerate() {
+            assert_eq!(world.get::<A>(e), Some(&A(i as u32)));
+            assert_eq!(world.get::<B>(e), Some(&B(format!("{i}"))));
         }
-        self.send(Spawn(id));
-        id
-    }
 
-    /// Enqueue an [`Insert`] event.
-    pub fn insert<C: Component>(&mut self, entity: EntityId, component: C) {
-        self.send(Insert::new(entity, component))
-    }
+        // The entity has its components by the time `Spawn` is received.
+        world.add_handler(|_: Receiver<Spawn, (&A, &B)>| {
+            SPAWNED.fetch_add(1, Ordering::Relaxed);
+        });
 
-    /// Enqueue a [`Remove`] event.
-    pub fn remove<C: Component>(&mut self, entity: EntityId) {
-        self.send(Remove::<C>::new(entity))
-    }
+        world.spawn_bundles([(A(10), B("10".into())), (A(11), B("11".into()))]);
 
-    /// Enqueue a [`Despawn`] event.
-    pub fn despawn(&mut self, entity: EntityId) {
-        self.send(Despawn(entity))
+        assert_eq!(SPAWNED.load(Ordering::Relaxed), 2);
+        assert_eq!(world.component_count::<A>(), 6);
     }
-}
 
-/// Reference to a [`World`] where all methods take `&self` and aliasing rules
-/// are not checked. It is the caller's responsibility to ensure that
-/// Rust's aliasing rules are not violated.
-#[derive(Clone, Copy, Debug)]
-pub struct UnsafeWorldCell<'a> {
-    world: NonNull<World>,
-    _marker: PhantomData<(&'a World, &'a UnsafeCell<World>)>,
-}
+    #[test]
+    #[should_panic(expected = "more than once")]
+    fn spawn_bundles_duplicate_component() {
+        #[derive(Component)]
+        struct C(#[allow(dead_code)] u32);
 
-impl<'a> UnsafeWorldCell<'a> {
-    /// # Safety
-    ///
-    /// - Must be called from within a handler.
-    /// - Must have permission to access the event queue mutably.
-    /// - Event index must be correct for the given event.
-    pub unsafe fn send_with_index<E: Event>(self, event: E, idx: u32) {
-        unsafe { (*self.world.as_ptr()).event_queue.push_front(event, idx) }
-    }
+        let mut world = World::new();
 
-    /// # Safety
-    ///
-    /// - Must be called from within a handler.
-    /// - Must have permission to access the event queue mutably.
-    pub unsafe fn queue_spawn(self) -> EntityId {
-        let entity_id = (*self.world.as_ptr())
-            .reserved_entities
-            .reserve(self.entities());
-        self.send_with_index(SpawnQueued, EventId::SPAWN_QUEUED.index().as_u32());
-        entity_id
+        world.spawn_bundles([(C(1), C(2))]);
     }
 
-    /// Returns the [`Entities`] for this world.
-    pub fn entities(self) -> &'a Entities {
-        unsafe { &(*self.world.as_ptr()).entities }
-    }
+    #[derive(Event)]
+    struct Late;
 
-    /// Returns the [`Components`] for this world.
-    pub fn components(self) -> &'a Components {
-        unsafe { &(*self.world.as_ptr()).components }
-    }
+    #[derive(Component)]
+    struct LateComponent;
 
-    /// Returns the [`Handlers`] for this world.
-    pub fn handlers(self) -> &'a Handlers {
-        unsafe { &(*self.world.as_ptr()).handlers }
+    fn frozen_world() -> World {
+        let mut world = World::new();
+        world.freeze_registries();
+        world
     }
 
-    /// Returns the [`Archetypes`] for this world.
-    pub fn archetypes(self) -> &'a Archetypes {
-        unsafe { &(*self.world.as_ptr()).archetypes }
+    #[test]
+    #[should_panic(
+        expected = "cannot add event `evenio::world::tests::Late` while registries are frozen"
+    )]
+    fn frozen_send() {
+        frozen_world().send(Late);
     }
 
-    /// Returns the [`Events`] for this world.
-    pub fn events(self) -> &'a Events {
-        unsafe { &(*self.world.as_ptr()).events }
-    }
+    #[test]
+    #[should_panic(
+        expected = "cannot add component `evenio::world::tests::LateComponent` while registries \
+                    are frozen"
+    )]
+    fn frozen_insert() {
+        let mut world = World::new();
+        let e = world.spawn();
 
-    /// Returns an immutable reference to the underlying world.
-    ///
-    /// # Safety
-    ///
-    /// Must have permission to access the entire world immutably.
-    pub fn world(self) -> &'a World {
-        unsafe { &*self.world.as_ptr() }
+        world.freeze_registries();
+        world.insert(e, LateComponent);
     }
 
-    /// Returns a mutable reference to the underlying world.
-    ///
-    /// # Safety
-    ///
-    /// Must have permission the access the entire world immutably.
-    pub unsafe fn world_mut(self) -> &'a mut World {
-        &mut *self.world.as_ptr()
+    #[test]
+    #[should_panic(expected = "cannot add handler \
+                               `evenio::world::tests::frozen_add_handler::{{closure}}` while \
+                               registries are frozen")]
+    fn frozen_add_handler() {
+        frozen_world().add_handler(|_: Receiver<Late>| {});
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use alloc::sync::Arc;
-    use core::panic::{RefUnwindSafe, UnwindSafe};
-    use std::panic;
-
-    use crate::prelude::*;
 
     #[test]
-    fn world_drops_events() {
-        #[derive(Event)]
-        struct A(Arc<()>);
+    fn refreeze_registries() {
+        let mut world = World::new();
 
-        impl Drop for A {
-            fn drop(&mut self) {
-                eprintln!("calling A destructor");
-            }
-        }
+        world.add_handler(|_: Receiver<Late>| {});
+        world.freeze_registries();
+        assert!(world.registries_frozen());
 
-        #[derive(Event)]
-        struct B(Arc<()>);
+        // Existing types keep working.
+        let e = world.spawn();
+        world.send(Late);
+        world.add_event::<Late>();
 
-        impl Drop for B {
-            fn drop(&mut self) {
-                eprintln!("calling B destructor");
-            }
-        }
+        world.unfreeze_registries();
+        assert!(!world.registries_frozen());
 
-        #[derive(Event)]
-        struct C(Arc<()>);
+        world.add_handler(|_: Receiver<Insert<LateComponent>, ()>| {});
+        world.freeze_registries();
 
-        impl Drop for C {
-            fn drop(&mut self) {
-                eprintln!("calling C destructor");
-            }
-        }
+        world.insert(e, LateComponent);
+        world.despawn(e);
 
-        let mut world = World::new();
+        assert_eq!(world.component_count::<LateComponent>(), 0);
+    }
 
-        world.add_handler(|r: Receiver<A>, mut s: Sender<B>| {
-            s.send(B(r.event.0.clone()));
-            s.send(B(r.event.0.clone()));
-        });
+    #[test]
+    fn remove_on_despawn() {
+        #[derive(Component)]
+        struct A(u32, #[allow(dead_code)] Arc<()>);
 
-        world.add_handler(|r: Receiver<B>, mut s: Sender<C>| {
-            s.send(C(r.event.0.clone()));
-            s.send(C(r.event.0.clone()));
-        });
+        #[derive(Component)]
+        struct B(#[allow(dead_code)] Arc<()>);
 
-        world.add_handler(|r: Receiver<C>| println!("got C {:?}", Arc::as_ptr(&r.event.0)));
+        #[derive(Component)]
+        struct Log(Vec<String>);
+
+        let mut world = World::new();
+        world.set_remove_on_despawn(true);
+
+        let log = world.spawn();
+        world.insert(log, Log(vec![]));
+
+        world.add_handler(
+            |r: Receiver<Remove<A>, &A>, Single(log): Single<&mut Log>| {
+                log.0.push(format!("remove A({})", r.query.0));
+            },
+        );
+
+        // Despawning the entity again while it's being despawned has no effect.
+        world.add_handler(
+            |r: Receiver<Remove<B>, EntityId>,
+             Single(log): Single<&mut Log>,
+             mut s: Sender<Despawn>| {
+                log.0.push("remove B".into());
+                s.despawn(r.query);
+            },
+        );
+
+        world.add_handler(
+            |r: Receiver<Despawn, (Has<&A>, Has<&B>)>, Single(log): Single<&mut Log>| {
+                let (a, b) = r.query;
+                log.0.push(format!("despawn {a} {b}"));
+            },
+        );
 
         let arc = Arc::new(());
+        let e = world.spawn();
+        world.insert(e, A(1, arc.clone()));
+        world.insert(e, B(arc.clone()));
 
-        world.send(A(arc.clone()));
+        world.despawn(e);
 
-        drop(world);
+        assert!(!world.entities().contains(e));
+        assert_eq!(Arc::strong_count(&arc), 1);
+        assert_eq!(
+            world.get::<Log>(log).unwrap().0,
+            ["remove A(1)", "remove B", "despawn false false"]
+        );
+
+        // Disabled, no `Remove` events are sent.
+        world.set_remove_on_despawn(false);
+        world.get_mut::<Log>(log).unwrap().0.clear();
+
+        let e = world.spawn();
+        world.insert(e, A(2, arc.clone()));
+        world.despawn(e);
 
         assert_eq!(Arc::strong_count(&arc), 1);
+        assert_eq!(world.get::<Log>(log).unwrap().0, ["despawn true false"]);
     }
 
     #[test]
-    fn world_drops_events_on_panic() {
-        #[derive(Event)]
-        struct A(Arc<()>);
+    fn copy_query() {
+        #[derive(Component, Clone, Copy, PartialEq, Debug)]
+        struct A(u32);
 
-        impl Drop for A {
-            fn drop(&mut self) {
-                eprintln!("calling A destructor");
-            }
-        }
+        #[derive(Component, Clone, Copy, PartialEq, Debug)]
+        struct B(u64);
 
-        #[derive(Event)]
-        struct B(Arc<()>);
+        #[derive(Component, Clone, Copy, PartialEq, Debug)]
+        struct Z;
 
-        impl Drop for B {
-            fn drop(&mut self) {
-                eprintln!("calling B destructor");
-            }
-        }
+        let mut world = World::new();
 
-        #[allow(dead_code)]
-        #[derive(Event)]
-        struct C(Arc<()>);
+        let mut expected = vec![];
 
-        impl Drop for C {
-            fn drop(&mut self) {
-                eprintln!("calling C destructor");
+        for i in 0..10 {
+            let e = world.spawn();
+            world.insert(e, A(i));
+
+            if i % 2 == 0 {
+                world.insert(e, B(u64::from(i) * 10));
             }
-        }
 
-        let mut world = World::new();
+            if i % 3 == 0 {
+                world.insert(e, Z);
+            }
 
-        world.add_handler(|r: Receiver<A>, mut s: Sender<B>| {
-            s.send(B(r.event.0.clone()));
-            s.send(B(r.event.0.clone()));
-        });
+            expected.push((
+                e,
+                A(i),
+                (i % 2 == 0).then_some(B(u64::from(i) * 10)),
+                i % 3 == 0,
+            ));
+        }
 
-        world.add_handler(|r: Receiver<B>, mut sender: Sender<C>| {
-            sender.send(C(r.event.0.clone()));
-            sender.send(C(r.event.0.clone()));
-        });
+        // An empty archetype.
+        let e = world.spawn();
+        world.insert(e, B(0));
+        world.despawn(e);
 
-        world.add_handler(|_: Receiver<C>| panic!("oops!"));
+        let mut ids = world.copy_query::<EntityId>();
+        ids.sort();
+        let mut expected_ids: Vec<_> = expected.iter().map(|x| x.0).collect();
+        expected_ids.sort();
+        assert_eq!(ids, expected_ids);
 
-        let arc = Arc::new(());
-        let arc_cloned = arc.clone();
+        let mut a = world.copy_query::<&A>();
+        a.sort_by_key(|a| a.0);
+        assert_eq!(a, (0..10).map(A).collect::<Vec<_>>());
 
-        let res = panic::catch_unwind(move || world.send(A(arc_cloned)));
+        assert_eq!(world.copy_query::<&Z>().len(), 4);
+        assert_eq!(world.copy_query::<(&A, Not<&B>)>().len(), 5);
 
-        assert_eq!(*res.unwrap_err().downcast::<&str>().unwrap(), "oops!");
+        let mut rows = world.copy_query::<(EntityId, &A, Option<&B>, Has<&Z>)>();
+        rows.sort_by_key(|r| r.1 .0);
+        assert_eq!(rows, expected);
 
-        assert_eq!(Arc::strong_count(&arc), 1);
-    }
+        // The copy is not affected by later changes.
+        world.insert(expected[0].0, A(100));
+        assert_eq!(rows[0].1, A(0));
 
-    /// Asserts that `World` has the expected auto trait implementations.
-    fn _assert_auto_trait_impls()
-    where
-        World: Send + Sync + UnwindSafe + RefUnwindSafe,
-    {
+        // Unknown components match nothing and aren't registered.
+        #[derive(Component, Clone, Copy)]
+        struct Unknown;
+
+        let components = world.components().len();
+        assert!(world.copy_query::<&Unknown>().is_empty());
+        assert_eq!(world.copy_query::<Not<&Unknown>>().len(), 10);
+        assert_eq!(world.components().len(), components);
     }
 }
@@ -0,0 +1,287 @@
+//! Exercises each subsystem documented in `evenio::safety` through the public
+//! API. Sizes are kept small so this runs quickly under Miri:
+//!
+//! ```text
+//! MIRIFLAGS='-Zmiri-tree-borrows' cargo +nightly miri test --test invariants
+//! ```
+
+#![allow(clippy::tests_outside_test_module)]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use evenio::dedup::Window;
+use evenio::dyn_component::{AsDynMut, DynComponent};
+use evenio::prelude::*;
+
+#[derive(Event)]
+struct Tick;
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+#[component(double_buffer)]
+struct Pos(u32);
+
+#[derive(Component, PartialEq, Debug)]
+struct Name(String);
+
+#[derive(Component)]
+struct Tracked(#[allow(dead_code)] Arc<()>);
+
+#[derive(Component)]
+struct Marker;
+
+#[derive(Component, PartialEq, Eq, PartialOrd, Ord)]
+struct Key(u8);
+
+/// Moves entities with heap-owning, zero-sized and double-buffered components
+/// between archetypes, forcing swap removes from the middle of columns.
+#[test]
+fn archetype_moves() {
+    let mut world = World::new();
+    let arc = Arc::new(());
+
+    let ids: Vec<_> = (0..6).map(|_| world.spawn()).collect();
+
+    for (i, &e) in ids.iter().enumerate() {
+        world.insert(e, Pos(i as u32));
+        world.insert(e, Name(format!("e{i}")));
+        world.insert(e, Tracked(arc.clone()));
+    }
+
+    world.swap_buffers::<Pos>();
+
+    for &e in ids.iter().step_by(2) {
+        world.insert(e, Marker);
+    }
+
+    world.remove::<Name>(ids[1]);
+    world.despawn(ids[2]);
+    world.remove::<Marker>(ids[4]);
+    world.insert(ids[3], Pos(30));
+
+    assert_eq!(world.get::<Pos>(ids[3]), Some(&Pos(30)));
+    assert_eq!(world.get::<Name>(ids[5]), Some(&Name("e5".into())));
+    assert_eq!(world.get::<Name>(ids[1]), None);
+    assert_eq!(Arc::strong_count(&arc), 6);
+
+    world.add_handler(|_: Receiver<Tick>, f: Fetcher<(&Pos, Previous<Pos>)>| {
+        for (pos, prev) in f {
+            assert!(pos.0 == 30 || pos == prev);
+        }
+    });
+
+    world.send(Tick);
+
+    drop(world);
+
+    assert_eq!(Arc::strong_count(&arc), 1);
+}
+
+#[test]
+fn sorted_rows() {
+    let mut world = World::new();
+
+    let ids: Vec<_> = (0..5).map(|_| world.spawn()).collect();
+
+    for (i, &e) in ids.iter().enumerate() {
+        world.insert(e, Key(5 - i as u8));
+        world.insert(e, Name(format!("e{i}")));
+    }
+
+    world.sort_archetype_rows::<Key>();
+
+    for (i, &e) in ids.iter().enumerate() {
+        assert_eq!(world.get::<Name>(e), Some(&Name(format!("e{i}"))));
+    }
+
+    world.despawn(ids[0]);
+    assert_eq!(world.get::<Name>(ids[4]), Some(&Name("e4".into())));
+}
+
+#[test]
+fn drop_hook_payloads() {
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+    let mut world = World::new();
+
+    world.set_component_drop_hook::<Name>(|_, _, name| {
+        assert!(name.0.starts_with('e'));
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+    });
+
+    let a = world.spawn();
+    let b = world.spawn();
+    world.insert(a, Name("ea".into()));
+    world.insert(b, Name("eb".into()));
+
+    world.remove::<Name>(a);
+    world.despawn(b);
+
+    assert_eq!(DROPPED.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn event_payloads() {
+    #[derive(Event)]
+    struct Outer(Arc<()>);
+
+    #[derive(Event)]
+    struct Inner(Vec<u8>, #[allow(dead_code)] Arc<()>);
+
+    #[derive(Event)]
+    struct Taken(String);
+
+    let mut world = World::new();
+
+    world.add_handler(|r: Receiver<Outer>, mut s: Sender<(Inner, Taken)>| {
+        for i in 0..3 {
+            s.send(Inner(vec![i; 3], r.event.0.clone()));
+        }
+        s.send(Taken("taken".into()));
+    });
+
+    world.add_handler(|r: Receiver<Inner>| assert_eq!(r.event.0.len(), 3));
+
+    world.add_handler(|r: ReceiverMut<Taken>| {
+        let taken = EventMut::take(r.event);
+        assert_eq!(taken.0, "taken");
+    });
+
+    let arc = Arc::new(());
+
+    for _ in 0..2 {
+        world.send(Outer(arc.clone()));
+    }
+
+    assert_eq!(Arc::strong_count(&arc), 1);
+}
+
+#[test]
+fn event_payloads_on_panic() {
+    #[derive(Event)]
+    struct Boom(#[allow(dead_code)] Arc<()>);
+
+    let mut world = World::new();
+
+    world.add_handler(|_: Receiver<Boom>| panic!("boom"));
+
+    let arc = Arc::new(());
+
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        world.send(Boom(arc.clone()));
+    }));
+
+    assert!(res.is_err());
+    assert_eq!(Arc::strong_count(&arc), 1);
+}
+
+#[test]
+fn deduplicated_payloads() {
+    #[derive(Event, PartialEq, Eq, Hash, Clone)]
+    struct Msg(String);
+
+    let mut world = World::new();
+
+    world.dedup_window::<Msg>(Window::UntilExplicitReset);
+    world.add_handler(|_: Receiver<Msg>| {});
+
+    for s in ["a", "b", "a"] {
+        world.send(Msg(s.into()));
+    }
+
+    world.reset_dedup::<Msg>();
+    world.send(Msg("a".into()));
+
+    let stats = world.dedup_stats::<Msg>().unwrap();
+    assert_eq!((stats.delivered, stats.suppressed), (3, 1));
+}
+
+#[test]
+fn query_aliasing() {
+    #[derive(Event)]
+    struct Swap(EntityId, EntityId);
+
+    let mut world = World::new();
+
+    let a = world.spawn();
+    let b = world.spawn();
+    world.insert(a, Pos(1));
+    world.insert(b, Pos(2));
+
+    world.add_handler(|r: Receiver<Swap>, mut f: Fetcher<&mut Pos>| {
+        assert!(matches!(
+            f.get_many_mut([r.event.0, r.event.0]),
+            Err(GetError::AliasedMutability)
+        ));
+
+        let [x, y] = f.get_many_mut([r.event.0, r.event.1]).unwrap();
+        std::mem::swap(x, y);
+    });
+
+    world.send(Swap(a, b));
+
+    assert_eq!(world.get::<Pos>(a), Some(&Pos(2)));
+    assert_eq!(world.get::<Pos>(b), Some(&Pos(1)));
+}
+
+#[test]
+fn dyn_components() {
+    trait Counter: Send + Sync {
+        fn bump(&mut self);
+        fn get(&self) -> u32;
+    }
+
+    struct Small(u8);
+    struct Big([u64; 4]);
+
+    impl Counter for Small {
+        fn bump(&mut self) {
+            self.0 += 1;
+        }
+
+        fn get(&self) -> u32 {
+            self.0.into()
+        }
+    }
+
+    impl Counter for Big {
+        fn bump(&mut self) {
+            self.0[3] += 1;
+        }
+
+        fn get(&self) -> u32 {
+            self.0[3] as u32
+        }
+    }
+
+    let mut world = World::new();
+
+    world.init_dyn_component::<dyn Counter>();
+
+    let a = world.spawn();
+    let b = world.spawn();
+    world.insert(
+        a,
+        DynComponent::<dyn Counter>::new(Box::new(Small(1)), |c| c),
+    );
+    world.insert(
+        b,
+        DynComponent::<dyn Counter>::new(Box::new(Big([0; 4])), |c| c),
+    );
+    world.insert(b, Marker);
+
+    world.add_handler(|_: Receiver<Tick>, f: Fetcher<AsDynMut<dyn Counter>>| {
+        for c in f {
+            c.bump();
+        }
+    });
+
+    world.send(Tick);
+
+    let get = |world: &World, e| world.get::<DynComponent<dyn Counter>>(e).unwrap().get();
+    assert_eq!(get(&world, a), 2);
+    assert_eq!(get(&world, b), 1);
+
+    world.despawn(a);
+    assert_eq!(get(&world, b), 1);
+}
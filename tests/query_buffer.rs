@@ -0,0 +1,132 @@
+//! Checks that handlers using a `QueryBuffer` stop allocating once the buffer
+//! is large enough.
+
+#![allow(clippy::tests_outside_test_module)]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use evenio::entity::EntityLocation;
+use evenio::fetch::QueryBuffer;
+use evenio::prelude::*;
+
+/// Counts the allocations made by each thread.
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+#[derive(Event)]
+struct Frame;
+
+#[derive(Component)]
+struct Depth(u32);
+
+#[derive(Component)]
+struct Visible;
+
+#[derive(Component)]
+struct Drawn(u32);
+
+/// Spawns the entities drawn by the handler, sends `Frame` a few times and
+/// returns the number of allocations the handler made on the last frame.
+fn steady_state_allocations(world: &mut World) -> usize {
+    for i in 0..500 {
+        let e = world.spawn();
+        world.insert(e, Depth((i * 7919) % 500));
+        world.insert(e, Drawn(0));
+
+        if i % 3 == 0 {
+            world.insert(e, Visible);
+        }
+    }
+
+    let e = world.spawn();
+    world.insert(e, Drawn(u32::MAX));
+
+    for _ in 0..3 {
+        world.send(Frame);
+    }
+
+    world.get::<Drawn>(e).unwrap().0 as usize
+}
+
+#[test]
+fn sorting_every_frame_does_not_allocate() {
+    let mut world = World::new();
+
+    world.add_handler(
+        |_: Receiver<Frame>,
+         f: Fetcher<(&Depth, &Drawn)>,
+         mut buf: QueryBuffer<(u32, EntityLocation)>,
+         mut ids: QueryBuffer<EntityId>,
+         out: Fetcher<(EntityId, &mut Drawn, Not<&Depth>)>| {
+            let before = allocations();
+
+            let mut last = 0;
+            for (depth, _) in f.iter_sorted_by_key_cached(|(depth, _)| depth.0, &mut buf) {
+                assert!(depth.0 >= last);
+                last = depth.0;
+            }
+
+            f.collect_ids_into(&mut ids);
+            assert_eq!(ids.len(), 500);
+
+            let allocated = allocations() - before;
+
+            for (_, drawn, _) in out {
+                drawn.0 = allocated as u32;
+            }
+        },
+    );
+
+    assert_eq!(steady_state_allocations(&mut world), 0);
+}
+
+#[test]
+fn naive_sorting_allocates() {
+    let mut world = World::new();
+
+    world.add_handler(
+        |_: Receiver<Frame>,
+         f: Fetcher<(&Depth, &Drawn)>,
+         out: Fetcher<(EntityId, &mut Drawn, Not<&Depth>)>| {
+            let before = allocations();
+
+            let mut items: Vec<_> = f.iter().collect();
+            items.sort_unstable_by_key(|(depth, _)| depth.0);
+
+            let allocated = allocations() - before;
+
+            for (_, drawn, _) in out {
+                drawn.0 = allocated as u32;
+            }
+        },
+    );
+
+    assert!(steady_state_allocations(&mut world) > 0);
+}